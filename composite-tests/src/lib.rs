@@ -0,0 +1,44 @@
+//! A composite derive macro built from the expansions exported by
+//! `near-sdk-contract-tools-macros-core`, as a framework crate would.
+
+use near_sdk_contract_tools_macros_core::{
+    expand_external_derive, pause, standard::fungible_token,
+};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// A fungible token that can be paused: derives `FungibleToken` (options in
+/// `#[fungible_token(...)]`) and `Pause` (options in `#[pause(...)]`), and
+/// rejects transfers while the contract is paused.
+#[proc_macro_derive(GameToken, attributes(fungible_token, pause))]
+pub fn derive_game_token(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let mut e = darling::Error::accumulator();
+    let token = e.handle(expand_external_derive(
+        &input,
+        "fungible_token",
+        fungible_token::expand,
+    ));
+    let pause = e.handle(expand_external_derive(&input, "pause", pause::expand));
+
+    let ident = &input.ident;
+    let (imp, ty, wher) = input.generics.split_for_impl();
+
+    e.finish_with(quote! {
+        #token
+        #pause
+
+        impl #imp ::near_sdk_contract_tools::standard::nep141::Nep141Hook for #ident #ty #wher {
+            fn before_transfer(
+                &mut self,
+                _transfer: &::near_sdk_contract_tools::standard::nep141::Nep141Transfer,
+            ) {
+                <Self as ::near_sdk_contract_tools::pause::Pause>::require_unpaused();
+            }
+        }
+    })
+    .unwrap_or_else(|e| e.write_errors())
+    .into()
+}
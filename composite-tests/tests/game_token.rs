@@ -0,0 +1,59 @@
+use composite_tests::GameToken;
+use near_sdk::{near_bindgen, test_utils::VMContextBuilder, testing_env, AccountId};
+use near_sdk_contract_tools::{
+    pause::{Pause, PauseExternal},
+    standard::{
+        nep141::{Nep141, Nep141Controller},
+        nep148::Nep148,
+    },
+};
+
+#[derive(GameToken)]
+#[fungible_token(name = "Game Token", symbol = "GAME", decimals = 18)]
+#[pause(storage_key = b"game_pause")]
+#[near_bindgen]
+struct Contract {}
+
+fn setup() -> (Contract, AccountId, AccountId) {
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob_acct".parse().unwrap();
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .attached_deposit(1)
+        .build());
+
+    let mut contract = Contract {};
+    contract.deposit_unchecked(&alice, 100);
+
+    (contract, alice, bob)
+}
+
+#[test]
+fn components() {
+    let (contract, alice, _) = setup();
+
+    assert_eq!(contract.ft_metadata().symbol, "GAME");
+    assert_eq!(contract.ft_balance_of(alice).0, 100);
+    assert!(!contract.paus_is_paused());
+    assert_eq!(Contract::slot_paused().key, b"game_pause");
+}
+
+#[test]
+fn transfer_unpaused() {
+    let (mut contract, alice, bob) = setup();
+
+    contract.ft_transfer(bob.clone(), 40.into(), None);
+
+    assert_eq!(contract.ft_balance_of(alice).0, 60);
+    assert_eq!(contract.ft_balance_of(bob).0, 40);
+}
+
+#[test]
+#[should_panic(expected = "Disallowed while contract is paused")]
+fn transfer_paused() {
+    let (mut contract, _, bob) = setup();
+
+    contract.pause();
+    contract.ft_transfer(bob, 40.into(), None);
+}
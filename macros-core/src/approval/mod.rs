@@ -0,0 +1,4 @@
+//! Approval components
+
+/// `SimpleMultisig` derive
+pub mod simple_multisig;
@@ -0,0 +1,167 @@
+use darling::FromDeriveInput;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Expr;
+
+/// Options of the `SimpleMultisig` derive, read from `#[simple_multisig(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(simple_multisig), supports(struct_named))]
+pub struct SimpleMultisigMeta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) action: Expr,
+    pub(crate) role: Expr,
+    #[darling(default)]
+    pub(crate) external: bool,
+    pub(crate) external_prefix: Option<String>,
+
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `ApprovalManager` and `AccountAuthorizer` implementations, and
+/// the external methods, if any
+pub fn expand(meta: SimpleMultisigMeta) -> Result<TokenStream, darling::Error> {
+    let SimpleMultisigMeta {
+        storage_key,
+        action,
+        role,
+        external,
+        external_prefix,
+        generics,
+        cfg,
+        method_docs,
+        method_prefix,
+        ident,
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    if !external && method_prefix.is_some() {
+        return Err(darling::Error::custom(
+            "`method_prefix` requires `external`",
+        ));
+    }
+
+    let (imp, ty, wher) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                #me::slot::Slot::root(#storage_key)
+            }
+        }
+    });
+
+    let role_assertion = crate::rbac::assert_role(&role, &ident, &generics, &me);
+
+    let manager = quote! {
+        #me::approval::ApprovalManager<
+            #action,
+            #me::approval::simple_multisig::ApprovalState,
+            #me::approval::simple_multisig::Configuration<Self>,
+        >
+    };
+
+    let external = external.then(|| {
+        let prefix = external_prefix.as_deref().unwrap_or("multisig");
+        let request = format_ident!("{}_request", prefix);
+        let approve = format_ident!("{}_approve", prefix);
+        let execute = format_ident!("{}_execute", prefix);
+        let is_approved = format_ident!("{}_is_approved", prefix);
+        let request_summary = format_ident!("{}_request_summary", prefix);
+
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Creates a request for `action`, approved by no one yet. Returns the
+                /// ID of the request.
+                pub fn #request(&mut self, action: #action) -> u32 {
+                    <Self as #manager>::create_request(
+                        self,
+                        action,
+                        #me::approval::simple_multisig::ApprovalState::new(),
+                    )
+                    .unwrap_or_else(|e| #near_sdk::env::panic_str(&e.to_string()))
+                }
+
+                /// Approves the request with ID `request_id` on behalf of the caller.
+                pub fn #approve(&mut self, request_id: u32) {
+                    <Self as #manager>::approve_request(self, request_id)
+                    .unwrap_or_else(|e| #near_sdk::env::panic_str(&e.to_string()))
+                }
+
+                /// Executes the request with ID `request_id`, if it has been approved.
+                pub fn #execute(
+                    &mut self,
+                    request_id: u32,
+                ) -> <#action as #me::approval::Action<#ident #ty>>::Output {
+                    <Self as #manager>::execute_request(self, request_id)
+                    .unwrap_or_else(|e| #near_sdk::env::panic_str(&e.to_string()))
+                }
+
+                /// Returns `true` if the request with ID `request_id` may be executed.
+                pub fn #is_approved(&self, request_id: u32) -> bool {
+                    <Self as #manager>::is_approved_for_execution(request_id).is_ok()
+                }
+
+                /// Returns the approvers of the request with ID `request_id` and the
+                /// number of approvals that remain, if the request exists.
+                pub fn #request_summary(
+                    &self,
+                    request_id: u32,
+                ) -> Option<#me::approval::RequestSummary> {
+                    <Self as #manager>::request_summary(request_id)
+                }
+            }
+        }
+    });
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            impl #imp #manager for #ident #ty #wher {
+                #root
+            }
+
+            impl #imp #me::approval::simple_multisig::AccountAuthorizer for #ident #ty #wher {
+                type AuthorizationError =
+                    #me::approval::simple_multisig::macro_types::MissingRole<
+                        <Self as #me::rbac::Rbac>::Role
+                    >;
+
+                fn is_account_authorized(account_id: &#near_sdk::AccountId) -> Result<(), Self::AuthorizationError> {
+                    if <Self as #me::rbac::Rbac>::has_role(account_id, &#role) {
+                        Ok(())
+                    } else {
+                        Err(#me::approval::simple_multisig::macro_types::MissingRole(#role))
+                    }
+                }
+            }
+
+            #external
+
+            #role_assertion
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
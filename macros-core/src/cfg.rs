@@ -0,0 +1,325 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, ImplItem, Item, ItemImpl, Lit, Meta, NestedMeta};
+
+/// A `#[cfg(...)]` predicate for generated external methods, specified as
+/// `cfg = "<predicate>"` for all of them, or as
+/// `cfg(method = "<method>", predicate = "<predicate>")` for one of them.
+/// May be given multiple times.
+#[derive(Debug)]
+pub struct Cfg {
+    method: Option<syn::LitStr>,
+    predicate: NestedMeta,
+}
+
+fn parse_predicate(value: &syn::LitStr) -> darling::Result<NestedMeta> {
+    value
+        .parse::<NestedMeta>()
+        .ok()
+        .filter(is_valid_predicate)
+        .ok_or_else(|| {
+            darling::Error::custom(format!(
+                r#"Invalid cfg predicate `{}`, expected e.g. `feature = "name"`, `not(...)`, `all(...)`, or `any(...)`"#,
+                value.value(),
+            ))
+            .with_span(value)
+        })
+}
+
+/// Checks the grammar of a configuration predicate: an option (`name` or
+/// `name = "value"`), or `all`, `any`, or `not` of predicates.
+fn is_valid_predicate(predicate: &NestedMeta) -> bool {
+    let meta = match predicate {
+        NestedMeta::Meta(meta) => meta,
+        NestedMeta::Lit(_) => return false,
+    };
+
+    if meta.path().get_ident().is_none() {
+        return false;
+    }
+
+    match meta {
+        Meta::Path(_) => true,
+        Meta::NameValue(nv) => matches!(nv.lit, Lit::Str(_)),
+        Meta::List(list) => {
+            let nested_ok = list.nested.iter().all(is_valid_predicate);
+
+            if list.path.is_ident("not") {
+                nested_ok && list.nested.len() == 1
+            } else {
+                nested_ok && (list.path.is_ident("all") || list.path.is_ident("any"))
+            }
+        }
+    }
+}
+
+impl FromMeta for Cfg {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        match value {
+            Lit::Str(s) => Ok(Self {
+                method: None,
+                predicate: parse_predicate(s)?,
+            }),
+            _ => Err(darling::Error::unexpected_lit_type(value)),
+        }
+    }
+
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        #[derive(FromMeta)]
+        struct MethodCfg {
+            method: syn::LitStr,
+            predicate: syn::LitStr,
+        }
+
+        let MethodCfg { method, predicate } = MethodCfg::from_list(items)?;
+
+        Ok(Self {
+            method: Some(method),
+            predicate: parse_predicate(&predicate)?,
+        })
+    }
+}
+
+pub fn is_near_bindgen(item: &ItemImpl) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "near_bindgen")
+    })
+}
+
+fn method_name(item: &ImplItem) -> Option<String> {
+    match item {
+        ImplItem::Method(method) => Some(method.sig.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Applies the `cfg` options to the `#[near_bindgen]` impls in `expanded`.
+///
+/// `#[near_bindgen]` ignores `#[cfg(...)]` on methods (the exported function
+/// would call a method that does not exist), so each method with its own
+/// predicate is moved to an impl of its own, to which the predicate is
+/// applied. A trait cannot be partially implemented, so the rest of the
+/// methods of a trait impl become an inherent impl, as with `skip`.
+pub fn apply(
+    cfg: &[Cfg],
+    generics: &syn::Generics,
+    expanded: TokenStream,
+) -> darling::Result<TokenStream> {
+    if cfg.is_empty() {
+        return Ok(expanded);
+    }
+
+    if !generics.params.is_empty() {
+        return Err(darling::Error::custom(
+            "`cfg` is not supported on generic contracts, whose external methods are not exported",
+        ));
+    }
+
+    let mut e = darling::Error::accumulator();
+
+    let all = cfg
+        .iter()
+        .filter(|c| c.method.is_none())
+        .map(|c| &c.predicate)
+        .collect::<Vec<_>>();
+    let all = match all.as_slice() {
+        [] => None,
+        [predicate] => Some(quote! { #[cfg(#predicate)] }),
+        predicates => Some(quote! { #[cfg(all(#(#predicates),*))] }),
+    };
+
+    let mut by_method: Vec<(String, &syn::LitStr, &NestedMeta)> = vec![];
+    for c in cfg {
+        if let Some(method) = &c.method {
+            if by_method.iter().any(|(name, ..)| *name == method.value()) {
+                e.push(
+                    darling::Error::custom(format!("Duplicate cfg for `{}`", method.value()))
+                        .with_span(method),
+                );
+            } else {
+                by_method.push((method.value(), method, &c.predicate));
+            }
+        }
+    }
+
+    let file: syn::File = syn::parse2(expanded)?;
+    let mut items = vec![];
+    let mut methods: Vec<String> = vec![];
+
+    for item in file.items {
+        let mut item_impl = match item {
+            Item::Impl(item_impl) if is_near_bindgen(&item_impl) => item_impl,
+            item => {
+                items.push(quote! { #item });
+                continue;
+            }
+        };
+
+        methods.extend(item_impl.items.iter().filter_map(method_name));
+
+        let (split, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut item_impl.items)
+            .into_iter()
+            .partition(|item| {
+                method_name(item).map_or(false, |name| by_method.iter().any(|(m, ..)| *m == name))
+            });
+
+        if split.is_empty() {
+            item_impl.items = rest;
+            items.push(quote! {
+                #all
+                #item_impl
+            });
+            continue;
+        }
+
+        item_impl.trait_ = None;
+
+        for mut item in split.into_iter().chain(rest) {
+            if let ImplItem::Method(method) = &mut item {
+                method.vis = parse_quote! { pub };
+            }
+
+            let predicate = method_name(&item)
+                .and_then(|name| by_method.iter().find(|(m, ..)| *m == name))
+                .map(|(.., predicate)| quote! { #[cfg(#predicate)] });
+
+            match predicate {
+                Some(predicate) => {
+                    let mut method_impl = item_impl.clone();
+                    method_impl.items = vec![item];
+                    items.push(quote! {
+                        #all
+                        #predicate
+                        #method_impl
+                    });
+                }
+                None => item_impl.items.push(item),
+            }
+        }
+
+        if !item_impl.items.is_empty() {
+            items.push(quote! {
+                #all
+                #item_impl
+            });
+        }
+    }
+
+    for (name, method, _) in &by_method {
+        if !methods.contains(name) {
+            e.push(
+                darling::Error::custom(format!(
+                    "Unknown method `{name}`, expected one of: {}",
+                    methods.join(", "),
+                ))
+                .with_span(*method),
+            );
+        }
+    }
+
+    e.finish_with(quote! { #(#items)* })
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use crate::{pause::PauseMeta, standard::nep141::Nep141Meta, upgrade::UpgradeMeta};
+
+    fn expand_nep141(attr: &str) -> darling::Result<String> {
+        let ast = syn::parse_str(&format!("#[nep141({attr})] struct Contract {{}}")).unwrap();
+        Nep141Meta::from_derive_input(&ast)
+            .and_then(crate::standard::nep141::expand)
+            .map(|expanded| expanded.to_string())
+    }
+
+    #[test]
+    fn all_methods() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Pause)]
+            #[pause(cfg = "feature = \"testnet\"", cfg = "not(test)")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = PauseMeta::from_derive_input(&ast).unwrap();
+        let expanded = crate::pause::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains(
+            "# [cfg (all (feature = \"testnet\" , not (test)))] # [:: near_sdk :: near_bindgen] impl :: near_sdk_contract_tools :: pause :: PauseExternal for Contract"
+        ));
+        // Internal implementation is unconditional
+        assert!(
+            expanded.starts_with("impl :: near_sdk_contract_tools :: pause :: Pause for Contract")
+        );
+    }
+
+    #[test]
+    fn one_method() {
+        let expanded =
+            expand_nep141(r#"cfg(method = "ft_transfer_call", predicate = "feature = \"x\"")"#)
+                .unwrap();
+
+        assert!(expanded.contains(
+            "# [cfg (feature = \"x\")] # [:: near_sdk :: near_bindgen] impl Contract { # [doc"
+        ));
+        assert!(expanded.contains("# [payable] pub fn ft_transfer_call ("));
+        assert!(expanded.contains("# [payable] pub fn ft_transfer ("));
+        assert!(!expanded.contains("Nep141 for Contract"));
+        // Other trait impls are unaffected
+        assert!(expanded.contains("Nep141Resolver for Contract"));
+    }
+
+    #[test]
+    fn inherent_method() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Upgrade)]
+            #[upgrade(hook = "owner", staged, cfg(method = "deploy_staged", predicate = "test"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = UpgradeMeta::from_derive_input(&ast).unwrap();
+        let expanded = crate::upgrade::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains(
+            "# [cfg (test)] # [:: near_sdk :: near_bindgen] impl Contract { # [doc = r\" Deploys the staged code"
+        ));
+        assert!(expanded.contains("# [payable] pub fn stage_upgrade ("));
+    }
+
+    #[test]
+    #[should_panic = "Invalid cfg predicate `feature = x`"]
+    fn invalid_predicate() {
+        expand_nep141(r#"cfg = "feature = x""#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Invalid cfg predicate `nand(a, b)`"]
+    fn invalid_operator() {
+        expand_nep141(r#"cfg(method = "ft_transfer", predicate = "nand(a, b)")"#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Unknown method `ft_mint`, expected one of: ft_transfer, ft_transfer_call"]
+    fn unknown_method() {
+        expand_nep141(r#"cfg(method = "ft_mint", predicate = "test")"#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Duplicate cfg for `ft_transfer`"]
+    fn duplicate_method() {
+        expand_nep141(
+            r#"cfg(method = "ft_transfer", predicate = "test"), cfg(method = "ft_transfer", predicate = "test")"#,
+        )
+        .unwrap();
+    }
+}
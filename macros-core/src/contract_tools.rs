@@ -0,0 +1,310 @@
+use darling::FromMeta;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{spanned::Spanned, DeriveInput, Lit, Meta, NestedMeta};
+
+/// Components accepted by `#[contract_tools(...)]`, as `(attribute, derive)`,
+/// in the order in which they are derived.
+const COMPONENTS: &[(&str, &str)] = &[
+    ("owner", "Owner"),
+    ("pause", "Pause"),
+    ("rbac", "Rbac"),
+    ("nep141", "Nep141"),
+    ("nep148", "Nep148"),
+    ("fungible_token", "FungibleToken"),
+    ("nep245", "Nep245"),
+    ("contract_metadata", "ContractMetadata"),
+    ("simple_multisig", "SimpleMultisig"),
+    ("upgrade", "Upgrade"),
+    ("migrate", "Migrate"),
+    ("migrate_chain", "MigrateChain"),
+];
+
+struct Component {
+    name: &'static str,
+    derive: &'static str,
+    span: Span,
+    args: Vec<NestedMeta>,
+}
+
+/// Components given to the `#[contract_tools(...)]` attribute
+pub struct ContractToolsMeta {
+    me: syn::Path,
+    me_str: Option<syn::LitStr>,
+    components: Vec<Component>,
+}
+
+impl ContractToolsMeta {
+    fn get(&self, name: &str) -> Option<&Component> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+impl FromMeta for ContractToolsMeta {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut e = darling::Error::accumulator();
+        let mut me_str = None;
+        let mut components: Vec<Component> = vec![];
+
+        for item in items {
+            let (path, args) = match item {
+                NestedMeta::Meta(Meta::Path(path)) => (path, vec![]),
+                NestedMeta::Meta(Meta::List(list)) => {
+                    (&list.path, list.nested.iter().cloned().collect())
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    match &nv.lit {
+                        Lit::Str(s) => me_str = Some(s.clone()),
+                        lit => e.push(darling::Error::unexpected_lit_type(lit)),
+                    }
+                    continue;
+                }
+                _ => {
+                    e.push(
+                        darling::Error::custom("Expected a component, e.g. `owner` or `rbac(...)`")
+                            .with_span(item),
+                    );
+                    continue;
+                }
+            };
+
+            let found = path
+                .get_ident()
+                .and_then(|ident| COMPONENTS.iter().find(|(name, _)| ident == name));
+
+            let (name, derive) = match found {
+                Some(found) => *found,
+                None => {
+                    e.push(
+                        darling::Error::custom(format!(
+                            "Unknown component `{}`, expected one of: {}",
+                            quote! { #path }.to_string().replace(' ', ""),
+                            COMPONENTS
+                                .iter()
+                                .map(|(name, _)| *name)
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        ))
+                        .with_span(path),
+                    );
+                    continue;
+                }
+            };
+
+            if components.iter().any(|c| c.name == name) {
+                e.push(
+                    darling::Error::custom(format!("Duplicate component `{name}`")).with_span(path),
+                );
+                continue;
+            }
+
+            components.push(Component {
+                name,
+                derive,
+                span: path.span(),
+                args,
+            });
+        }
+
+        let me = match &me_str {
+            Some(s) => e.handle(s.parse().map_err(darling::Error::from)),
+            None => Some(crate::default_crate_name()),
+        };
+
+        e.finish()?;
+
+        components.sort_by_key(|c| COMPONENTS.iter().position(|(name, _)| *name == c.name));
+
+        Ok(Self {
+            me: me.unwrap_or_else(crate::default_crate_name),
+            me_str,
+            components,
+        })
+    }
+}
+
+/// The value of a string option of a component, e.g. `hook` of `upgrade`
+fn string_arg(component: &Component, option: &str) -> Option<String> {
+    component.args.iter().find_map(|arg| match arg {
+        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(option) => match &nv.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Checks that the components that use other components are combined with
+/// them, and that no two components generate the same implementation.
+fn check(meta: &ContractToolsMeta) -> darling::Result<()> {
+    let mut e = darling::Error::accumulator();
+
+    let mut require = |component: &Component, required: &str, reason: &str| {
+        if meta.get(required).is_none() {
+            e.push(
+                darling::Error::custom(format!(
+                    "`{}` {reason}, which requires `{required}`",
+                    component.name,
+                ))
+                .with_span(&component.span),
+            );
+        }
+    };
+
+    if let Some(multisig) = meta.get("simple_multisig") {
+        require(multisig, "rbac", "authorizes accounts by role");
+    }
+
+    if let Some(upgrade) = meta.get("upgrade") {
+        let hook = string_arg(upgrade, "hook").unwrap_or_default();
+
+        if hook == "owner" || hook.starts_with("owner_or_role(") {
+            require(upgrade, "owner", "uses an owner hook");
+        }
+        if hook.starts_with("role(") || hook.starts_with("owner_or_role(") {
+            require(upgrade, "rbac", "uses a role hook");
+        }
+        if hook == "multisig" {
+            require(upgrade, "simple_multisig", "uses a multisig hook");
+        }
+    }
+
+    if let Some(fungible_token) = meta.get("fungible_token") {
+        for standard in ["nep141", "nep148"] {
+            if meta.get(standard).is_some() {
+                e.push(
+                    darling::Error::custom(format!(
+                        "`fungible_token` already includes `{standard}`",
+                    ))
+                    .with_span(&fungible_token.span),
+                );
+            }
+        }
+    }
+
+    e.finish()
+}
+
+/// Expands `item` to the equivalent `#[derive(...)]` and component attributes
+pub fn expand(meta: ContractToolsMeta, item: DeriveInput) -> darling::Result<TokenStream> {
+    check(&meta)?;
+
+    let ContractToolsMeta {
+        me,
+        me_str,
+        components,
+    } = meta;
+
+    let derives = components.iter().map(|c| {
+        let derive = syn::Ident::new(c.derive, c.span);
+        quote! { #me::#derive }
+    });
+
+    let attrs = components.iter().filter_map(|c| {
+        let name = syn::Ident::new(c.name, c.span);
+        let mut args = c.args.iter().map(|arg| quote! { #arg }).collect::<Vec<_>>();
+
+        let has_crate = c.args.iter().any(
+            |arg| matches!(arg, NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate")),
+        );
+        if let (Some(me_str), false) = (&me_str, has_crate) {
+            args.push(quote! { crate = #me_str });
+        }
+
+        (!args.is_empty()).then(|| quote! { #[#name(#(#args),*)] })
+    });
+
+    Ok(quote! {
+        #[derive(#(#derives),*)]
+        #(#attrs)*
+        #item
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromMeta;
+
+    use super::{expand, ContractToolsMeta};
+
+    fn expand_str(attr: &str, item: &str) -> darling::Result<String> {
+        let attr: syn::Meta = syn::parse_str(attr).unwrap();
+        let item = syn::parse_str(item).unwrap();
+
+        ContractToolsMeta::from_meta(&attr)
+            .and_then(|meta| expand(meta, item))
+            .map(|expanded| expanded.to_string())
+    }
+
+    #[test]
+    fn fixed_order() {
+        let expanded = expand_str(
+            r#"contract_tools(
+                upgrade(hook = "role(Role::Upgrader)"),
+                fungible_token(name = "Token", symbol = "TKN", decimals = 18),
+                rbac(roles = "Role"),
+                owner,
+            )"#,
+            "#[near_bindgen] struct Contract {}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expanded,
+            "# [derive (:: near_sdk_contract_tools :: Owner , :: near_sdk_contract_tools :: Rbac , :: near_sdk_contract_tools :: FungibleToken , :: near_sdk_contract_tools :: Upgrade)] \
+             # [rbac (roles = \"Role\")] \
+             # [fungible_token (name = \"Token\" , symbol = \"TKN\" , decimals = 18)] \
+             # [upgrade (hook = \"role(Role::Upgrader)\")] \
+             # [near_bindgen] struct Contract { }",
+        );
+    }
+
+    #[test]
+    fn custom_crate() {
+        let expanded = expand_str(
+            r#"contract_tools(crate = "tools", owner, pause(crate = "other"))"#,
+            "struct Contract {}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expanded,
+            "# [derive (tools :: Owner , tools :: Pause)] \
+             # [owner (crate = \"tools\")] \
+             # [pause (crate = \"other\")] \
+             struct Contract { }",
+        );
+    }
+
+    #[test]
+    #[should_panic = "Unknown component `nep171`, expected one of: owner, pause, rbac"]
+    fn unknown_component() {
+        expand_str("contract_tools(owner, nep171)", "struct Contract {}").unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Duplicate component `owner`"]
+    fn duplicate_component() {
+        expand_str("contract_tools(owner, owner)", "struct Contract {}").unwrap();
+    }
+
+    #[test]
+    #[should_panic = "`upgrade` uses a role hook, which requires `rbac`"]
+    fn missing_rbac() {
+        expand_str(
+            r#"contract_tools(owner, upgrade(hook = "owner_or_role(Role::Upgrader)"))"#,
+            "struct Contract {}",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[should_panic = "`fungible_token` already includes `nep141`"]
+    fn fungible_token_conflict() {
+        expand_str(
+            r#"contract_tools(nep141, fungible_token(name = "T", symbol = "T", decimals = 0))"#,
+            "struct Contract {}",
+        )
+        .unwrap();
+    }
+}
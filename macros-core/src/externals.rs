@@ -0,0 +1,175 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    DeriveInput, Ident, ImplItemMethod, Token,
+};
+
+use crate::skip::Skip;
+
+/// `#[near_bindgen]` on a struct generates `<Struct>::ext` (and the
+/// `<Struct>Ext` type that the `#[near_bindgen]` impls generated by the derive
+/// macros add their methods to). If the attribute is not visible to the derive
+/// macro, require `ext` to exist, so that a missing `#[near_bindgen]` is
+/// reported on the struct itself.
+///
+/// The attribute is only visible if it is placed after the `#[derive(...)]`;
+/// if it is placed before it, it has already been expanded, and the check
+/// passes.
+///
+/// Generic contracts cannot be `#[near_bindgen]`, so they are not checked
+/// (see: `bindgen_attr`).
+pub fn near_bindgen_check(input: &DeriveInput) -> TokenStream {
+    let ident = &input.ident;
+
+    let is_near_bindgen = input.attrs.iter().any(|attr| {
+        attr.path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "near_bindgen")
+    });
+
+    if is_near_bindgen || !input.generics.params.is_empty() {
+        return quote! {};
+    }
+
+    let ext = Ident::new("ext", ident.span());
+
+    quote_spanned! { ident.span() =>
+        const _: () = {
+            let _ = #ident::#ext;
+        };
+    }
+}
+
+/// The `#[near_bindgen]` impl of a component's external trait, given as
+/// `(name, method)` pairs. If some of the methods are skipped, the trait
+/// cannot be implemented, so the remaining methods are generated as an
+/// inherent impl instead, and the contract implements the skipped ones itself.
+pub(crate) fn external_impl(
+    near_bindgen: Option<TokenStream>,
+    generics: &syn::Generics,
+    ident: &Ident,
+    external_trait: TokenStream,
+    methods: Vec<(&str, TokenStream)>,
+    skip: &Skip,
+) -> syn::Result<TokenStream> {
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    if !methods.iter().any(|(name, _)| skip.contains(name)) {
+        let methods = methods.into_iter().map(|(_, method)| method);
+
+        return Ok(quote! {
+            #near_bindgen
+            impl #imp #external_trait for #ident #ty #wher {
+                #(#methods)*
+            }
+        });
+    }
+
+    let methods = methods
+        .into_iter()
+        .filter(|(name, _)| !skip.contains(name))
+        .map(|(_, method)| {
+            let mut method: ImplItemMethod = syn::parse2(method)?;
+            method.vis = parse_quote! { pub };
+            Ok(method)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    if methods.is_empty() {
+        return Ok(quote! {});
+    }
+
+    Ok(quote! {
+        #near_bindgen
+        impl #imp #ident #ty #wher {
+            #(#methods)*
+        }
+    })
+}
+
+/// `Contract: method_a, method_b`
+pub struct ContractExternals {
+    contract: Ident,
+    methods: Punctuated<Ident, Token![,]>,
+}
+
+impl Parse for ContractExternals {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let contract = input.parse()?;
+
+        let methods = if input.is_empty() {
+            Punctuated::new()
+        } else {
+            input.parse::<Token![:]>()?;
+            Punctuated::parse_terminated(input)?
+        };
+
+        Ok(Self { contract, methods })
+    }
+}
+
+/// Expands to the checks of `assert_contract_externals!`
+pub fn expand_assert(input: ContractExternals) -> TokenStream {
+    let ContractExternals { contract, methods } = input;
+
+    let ext = Ident::new("ext", contract.span());
+    let contract_ext = format_ident!("{}Ext", contract);
+    let methods = methods.into_iter();
+
+    quote! {
+        {
+            let _ = #contract::#ext;
+            #(let _ = #contract_ext::#methods;)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_assert, near_bindgen_check};
+
+    #[test]
+    fn check_visible_near_bindgen() {
+        let input = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[near_sdk::near_bindgen]
+            struct Contract {}
+            "#,
+        )
+        .unwrap();
+
+        assert!(near_bindgen_check(&input).is_empty());
+    }
+
+    #[test]
+    fn check_generic() {
+        let input = syn::parse_str("struct Contract<T> { t: T }").unwrap();
+
+        assert!(near_bindgen_check(&input).is_empty());
+    }
+
+    #[test]
+    fn check_not_visible() {
+        let input = syn::parse_str("struct Contract {}").unwrap();
+
+        assert_eq!(
+            near_bindgen_check(&input).to_string(),
+            "const _ : () = { let _ = Contract :: ext ; } ;",
+        );
+    }
+
+    #[test]
+    fn assert_externals() {
+        let input = syn::parse_str("Contract: ft_transfer, ft_metadata,").unwrap();
+
+        assert_eq!(
+            expand_assert(input).to_string(),
+            "{ let _ = Contract :: ext ; let _ = ContractExt :: ft_transfer ; let _ = ContractExt :: ft_metadata ; }",
+        );
+    }
+}
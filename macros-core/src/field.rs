@@ -0,0 +1,38 @@
+use darling::ast::Data;
+
+/// Checks a `field = "<field>"` option, which keeps the state of a component
+/// in the named field of the contract instead of in storage slots: the
+/// contract must be a struct with that field, and the storage options
+/// (`(name, given)` pairs) do not apply.
+pub fn check(
+    field: &syn::Ident,
+    data: &Data<(), syn::Field>,
+    storage_options: &[(&str, bool)],
+) -> darling::Result<()> {
+    let mut e = darling::Error::accumulator();
+
+    match data {
+        Data::Enum(_) => {
+            e.push(darling::Error::custom("`field` is only supported on structs").with_span(field))
+        }
+        Data::Struct(fields) => {
+            if !fields.iter().any(|f| f.ident.as_ref() == Some(field)) {
+                e.push(
+                    darling::Error::custom(format!("The contract has no field `{field}`"))
+                        .with_span(field),
+                );
+            }
+        }
+    }
+
+    for (name, _) in storage_options.iter().filter(|(_, given)| *given) {
+        e.push(
+            darling::Error::custom(format!(
+                "`{name}` cannot be combined with `field`, which does not use storage slots",
+            ))
+            .with_span(field),
+        );
+    }
+
+    e.finish()
+}
@@ -0,0 +1,54 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+/// Checks generated for a `max_key_len = <N>` attribute
+#[derive(Default)]
+pub struct KeyLenCheck {
+    /// Item that fails to compile if the component uses its default root key
+    /// and its longest key exceeds the budget
+    pub(crate) constant: TokenStream,
+    /// Statements to insert in a generated `root()` function, after `root`
+    /// is bound to the root slot, for custom root keys (which are only known
+    /// at runtime)
+    pub(crate) runtime: TokenStream,
+}
+
+/// Builds the key length checks for a component. `suffix_len` is a constant
+/// expression for the length of the longest key suffix the component appends
+/// to its root.
+pub fn check(
+    max_key_len: Option<usize>,
+    storage_key: Option<&Expr>,
+    default_key: TokenStream,
+    suffix_len: TokenStream,
+    component: &str,
+    ident: &syn::Ident,
+) -> KeyLenCheck {
+    let max_key_len = match max_key_len {
+        Some(max_key_len) => max_key_len,
+        None => return KeyLenCheck::default(),
+    };
+
+    let message = format!(
+        "The longest storage key of the {component} component of `{ident}` exceeds max_key_len = {max_key_len}",
+    );
+
+    match storage_key {
+        None => KeyLenCheck {
+            constant: quote! {
+                const _: () = assert!(
+                    #default_key.as_bytes().len() + #suffix_len <= #max_key_len,
+                    #message,
+                );
+            },
+            runtime: quote! {},
+        },
+        Some(_) => KeyLenCheck {
+            constant: quote! {},
+            runtime: quote! {
+                debug_assert!(root.key.len() + #suffix_len <= #max_key_len, #message);
+            },
+        },
+    }
+}
@@ -0,0 +1,202 @@
+//! Expansions of the macros of near-sdk-contract-tools
+//!
+//! The derive macros of `near-sdk-contract-tools-macros` are thin wrappers
+//! around the functions of this crate, which a framework crate can reuse to
+//! build its own composite derive macros, e.g. a `GameToken` derive that
+//! combines `FungibleToken` and `Pause` with code of its own:
+//!
+//! ```ignore
+//! use near_sdk_contract_tools_macros_core::{
+//!     expand_derive, expand_external_derive, pause, standard::fungible_token,
+//! };
+//!
+//! #[proc_macro_derive(GameToken, attributes(fungible_token, pause))]
+//! pub fn derive_game_token(input: TokenStream) -> TokenStream {
+//!     let input = parse_macro_input!(input as DeriveInput);
+//!
+//!     let mut e = darling::Error::accumulator();
+//!     let token = e.handle(expand_external_derive(
+//!         &input,
+//!         "fungible_token",
+//!         fungible_token::expand,
+//!     ));
+//!     let pause = e.handle(expand_external_derive(&input, "pause", pause::expand));
+//!
+//!     e.finish_with(quote! { #token #pause })
+//!         .unwrap_or_else(|e| e.write_errors())
+//!         .into()
+//! }
+//! ```
+//!
+//! Each component module exposes its options (e.g. [`owner::OwnerMeta`]),
+//! which are read from the component's attribute (e.g. `#[owner(...)]`) with
+//! [`darling::FromDeriveInput`], and an `expand` function. The options are
+//! documented on the derive macros. Their fields are private, so that new
+//! options are not breaking changes.
+
+use darling::FromDeriveInput;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+pub mod approval;
+mod cfg;
+/// `#[contract_tools]` attribute
+pub mod contract_tools;
+/// Checks of `#[near_bindgen]` externals, and `assert_contract_externals!`
+pub mod externals;
+mod field;
+mod key_len;
+mod method_attrs;
+mod method_docs;
+/// `Migrate` derive
+pub mod migrate;
+/// `MigrateChain` derive
+pub mod migrate_chain;
+/// `Owner` derive
+pub mod owner;
+/// `Pause` derive
+pub mod pause;
+mod prefix;
+/// `#[rate_limited]` attribute
+pub mod rate_limit;
+/// `Rbac` derive
+pub mod rbac;
+/// `#[non_reentrant]` attribute
+pub mod reentrancy;
+mod rename;
+mod skip;
+#[cfg(test)]
+mod snapshot;
+pub mod standard;
+mod storage_key;
+/// `Upgrade` derive
+pub mod upgrade;
+
+// Every derive accepts the `crate`, `macros`, and `near_sdk` path overrides,
+// even if its expansion does not need all of them, so that a framework crate
+// re-exporting this one can pass the same options to every derive.
+
+fn default_crate_name() -> syn::Path {
+    syn::parse_str("::near_sdk_contract_tools").unwrap()
+}
+
+fn default_macros() -> syn::Path {
+    syn::parse_str("::near_sdk_contract_tools").unwrap()
+}
+
+fn default_near_sdk() -> syn::Path {
+    syn::parse_str("::near_sdk").unwrap()
+}
+
+fn default_serde() -> syn::Path {
+    syn::parse_str("::serde").unwrap()
+}
+
+/// `#[near_bindgen]` does not support impls with generic parameters, so the
+/// external interfaces of generic contracts are generated as plain impls:
+/// they can be called from Rust (e.g. by a concrete wrapper contract), but
+/// they are not exported. Returns `attr` for non-generic contracts, and
+/// nothing for generic ones. Use for `#[near_bindgen]` itself, and for the
+/// attributes that only it understands (`#[payable]`, `#[init]`, etc.).
+fn bindgen_attr(generics: &syn::Generics, attr: TokenStream) -> Option<TokenStream> {
+    generics.params.is_empty().then_some(attr)
+}
+
+/// Reads the options of a component from its `attribute` on `input` and
+/// expands them, e.g. `expand_derive(&input, "nep148", nep148::expand)`.
+///
+/// Errors that are not tied to a specific meta item (e.g. a missing field)
+/// point at `attribute`, or at the name of the item if it has no such
+/// attribute.
+pub fn expand_derive<T>(
+    input: &DeriveInput,
+    attribute: &str,
+    expand: fn(T) -> darling::Result<TokenStream>,
+) -> darling::Result<TokenStream>
+where
+    T: FromDeriveInput,
+{
+    T::from_derive_input(input)
+        .and_then(expand)
+        .map_err(|e| with_default_span(e, input, attribute))
+}
+
+/// Like [`expand_derive`], for components that generate `#[near_bindgen]`
+/// external methods, and therefore require the struct to be `#[near_bindgen]`
+/// (see: [`externals::near_bindgen_check`]).
+pub fn expand_external_derive<T>(
+    input: &DeriveInput,
+    attribute: &str,
+    expand: fn(T) -> darling::Result<TokenStream>,
+) -> darling::Result<TokenStream>
+where
+    T: FromDeriveInput,
+{
+    let expanded = expand_derive(input, attribute, expand)?;
+    let check = externals::near_bindgen_check(input);
+
+    Ok(quote! { #expanded #check })
+}
+
+/// Errors that are not tied to a specific meta item (e.g. a missing field,
+/// or conflicting options) point at the derive's attribute, or at the name
+/// of the struct if it has no such attribute, instead of at the derive.
+///
+/// Missing fields are reported together, in one error with an example of the
+/// attribute (see: [`example`]), after the other errors.
+fn with_default_span(e: darling::Error, input: &DeriveInput, attribute: &str) -> darling::Error {
+    let attr = input.attrs.iter().find(|a| a.path.is_ident(attribute));
+    let default_span = |e: darling::Error| match attr {
+        Some(attr) => e.with_span(&attr.path),
+        None => e.with_span(&input.ident),
+    };
+
+    let mut missing = vec![];
+    let mut errors = vec![];
+
+    for e in e.flatten() {
+        // Nested missing fields are reported with their location, e.g.
+        // "Missing field `predicate` at cfg", so they are left alone.
+        match e
+            .to_string()
+            .strip_prefix("Missing field `")
+            .and_then(|field| field.strip_suffix('`'))
+        {
+            Some(field) => missing.push(format!("`{field}`")),
+            None => errors.push(default_span(e)),
+        }
+    }
+
+    if !missing.is_empty() {
+        let fields = if missing.len() == 1 {
+            "field"
+        } else {
+            "fields"
+        };
+        let mut message = format!("Missing {fields} {}", missing.join(", "));
+        if let Some(example) = example(attribute) {
+            message.push_str(&format!("; expected e.g. `{example}`"));
+        }
+        errors.push(default_span(darling::Error::custom(message)));
+    }
+
+    darling::Error::multiple(errors)
+}
+
+/// An example of the attribute of each derive with required fields, with
+/// all of them.
+fn example(attribute: &str) -> Option<&'static str> {
+    Some(match attribute {
+        "fungible_token" => {
+            r#"#[fungible_token(name = "My Token", symbol = "MYT", decimals = 24)]"#
+        }
+        "migrate" => r#"#[migrate(from = "ContractV1")]"#,
+        "migrate_chain" => "#[migrate_chain(versions(ContractV1, ContractV2))]",
+        "nep148" => r#"#[nep148(name = "My Token", symbol = "MYT", decimals = 24)]"#,
+        "nep297" => r#"#[nep297(standard = "x-my-standard", version = "1.0.0")]"#,
+        "rbac" => r#"#[rbac(roles = "Role")]"#,
+        "simple_multisig" => r#"#[simple_multisig(action = "Action", role = "Role::Multisig")]"#,
+        _ => return None,
+    })
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use darling::FromMeta;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Lit, Meta, NestedMeta};
+
+/// `#[near_bindgen]` attributes that may be added to generated methods
+const ALLOWED: &[&str] = &["payable", "private"];
+
+/// Additional `#[near_bindgen]` attributes for generated external methods,
+/// specified as `method_attrs(<method> = "<attr>, <attr>", ...)`
+#[derive(Debug, Default)]
+pub struct MethodAttrs(BTreeMap<String, (Span, Vec<syn::Ident>)>);
+
+impl FromMeta for MethodAttrs {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut e = darling::Error::accumulator();
+        let mut map = BTreeMap::new();
+
+        for item in items {
+            let (path, lit) = match item {
+                NestedMeta::Meta(Meta::NameValue(nv)) => (&nv.path, &nv.lit),
+                _ => {
+                    e.push(
+                        darling::Error::custom(r#"Expected `<method> = "<attr>, ..."`"#)
+                            .with_span(item),
+                    );
+                    continue;
+                }
+            };
+
+            let method = match path.get_ident() {
+                Some(ident) => ident,
+                None => {
+                    e.push(darling::Error::custom("Expected a method name").with_span(path));
+                    continue;
+                }
+            };
+
+            let value = match lit {
+                Lit::Str(s) => s,
+                _ => {
+                    e.push(darling::Error::unexpected_lit_type(lit));
+                    continue;
+                }
+            };
+
+            let mut attrs: Vec<syn::Ident> = vec![];
+
+            for name in value.value().split(',').map(str::trim) {
+                if !ALLOWED.contains(&name) {
+                    e.push(
+                        darling::Error::custom(format!(
+                            r#"Invalid attribute "{name}", expected one of: {}"#,
+                            ALLOWED.join(", "),
+                        ))
+                        .with_span(value),
+                    );
+                } else if attrs.iter().any(|a| a == name) {
+                    e.push(
+                        darling::Error::custom(format!("Duplicate attribute `{name}`"))
+                            .with_span(value),
+                    );
+                } else {
+                    attrs.push(syn::Ident::new(name, value.span()));
+                }
+            }
+
+            if map
+                .insert(method.to_string(), (method.span(), attrs))
+                .is_some()
+            {
+                e.push(
+                    darling::Error::custom(format!("Duplicate method `{method}`")).with_span(path),
+                );
+            }
+        }
+
+        e.finish_with(Self(map))
+    }
+}
+
+impl MethodAttrs {
+    /// Moves the entries for the given methods into a new set, e.g. to hand
+    /// them to a constituent expansion.
+    pub fn take(&mut self, methods: &[&str]) -> Self {
+        Self(
+            methods
+                .iter()
+                .filter_map(|m| self.0.remove_entry(*m))
+                .collect(),
+        )
+    }
+
+    /// Checks that every entry names one of the generated `methods`, given
+    /// with the attributes they already have, and does not repeat one of
+    /// those attributes. Generic contracts cannot have entries, since their
+    /// methods are not exported (see: [`crate::bindgen_attr`]).
+    pub fn check(
+        &self,
+        generics: &syn::Generics,
+        methods: &[(&str, &[&str])],
+    ) -> darling::Result<()> {
+        let mut e = darling::Error::accumulator();
+
+        for (method, (span, attrs)) in &self.0 {
+            if crate::bindgen_attr(generics, quote! {}).is_none() {
+                e.push(
+                    darling::Error::custom(format!(
+                        "`{method}` cannot have `method_attrs`: the methods of generic contracts are not exported by `#[near_bindgen]`",
+                    ))
+                    .with_span(span),
+                );
+                continue;
+            }
+
+            match methods.iter().find(|(m, _)| m == method) {
+                None => e.push(
+                    darling::Error::custom(format!(
+                        "Unknown method `{method}`, expected one of: {}",
+                        methods
+                            .iter()
+                            .map(|(m, _)| *m)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ))
+                    .with_span(span),
+                ),
+                Some((_, existing)) => {
+                    for attr in attrs.iter().filter(|a| existing.iter().any(|x| a == x)) {
+                        e.push(
+                            darling::Error::custom(format!("`{method}` is already `#[{attr}]`",))
+                                .with_span(attr),
+                        );
+                    }
+                }
+            }
+        }
+
+        e.finish()
+    }
+
+    /// The methods that have entries, with the spans of their names
+    pub fn methods(&self) -> impl Iterator<Item = (&str, Span)> {
+        self.0
+            .iter()
+            .map(|(method, (span, _))| (method.as_str(), *span))
+    }
+
+    /// Attributes to add to the generated method
+    pub fn get(&self, method: &str) -> TokenStream {
+        self.0
+            .get(method)
+            .map(|(_, attrs)| quote! { #(#[#attrs])* })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromMeta;
+
+    use super::MethodAttrs;
+
+    fn parse(s: &str) -> darling::Result<MethodAttrs> {
+        let meta: syn::Meta = syn::parse_str(s).unwrap();
+        MethodAttrs::from_meta(&meta)
+    }
+
+    #[test]
+    fn parse_and_get() {
+        let attrs = parse(r#"method_attrs(a = "payable, private", b = "private")"#).unwrap();
+
+        attrs
+            .check(&Default::default(), &[("a", &[]), ("b", &[]), ("c", &[])])
+            .unwrap();
+
+        assert_eq!(attrs.get("a").to_string(), "# [payable] # [private]");
+        assert_eq!(attrs.get("b").to_string(), "# [private]");
+        assert!(attrs.get("c").is_empty());
+    }
+
+    #[test]
+    #[should_panic = "`a` cannot have `method_attrs`: the methods of generic contracts are not exported by `#[near_bindgen]`"]
+    fn generic_contract() {
+        let attrs = parse(r#"method_attrs(a = "payable")"#).unwrap();
+        let generics: syn::Generics = syn::parse_str("<T>").unwrap();
+        attrs.check(&generics, &[("a", &[])]).unwrap();
+    }
+
+    #[test]
+    fn take() {
+        let mut attrs = parse(r#"method_attrs(a = "payable", b = "private")"#).unwrap();
+        let b = attrs.take(&["b"]);
+
+        attrs.check(&Default::default(), &[("a", &[])]).unwrap();
+        b.check(&Default::default(), &[("b", &[])]).unwrap();
+    }
+
+    #[test]
+    #[should_panic = r#"Invalid attribute \"init\", expected one of: payable, private"#]
+    fn invalid_attribute() {
+        parse(r#"method_attrs(a = "init")"#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Unknown method `d`, expected one of: a, b"]
+    fn unknown_method() {
+        let attrs = parse(r#"method_attrs(d = "payable")"#).unwrap();
+        attrs
+            .check(&Default::default(), &[("a", &[]), ("b", &[])])
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic = "`a` is already `#[payable]`"]
+    fn existing_attribute() {
+        let attrs = parse(r#"method_attrs(a = "payable")"#).unwrap();
+        attrs
+            .check(&Default::default(), &[("a", &["payable"])])
+            .unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, ImplItem, Item, Lit, Meta, NestedMeta};
+
+/// Documentation for generated external methods, replacing the default,
+/// specified as `method_docs(<method> = "<doc>", ...)`
+#[derive(Debug, Default)]
+pub struct MethodDocs(Vec<(syn::Ident, syn::LitStr)>);
+
+impl FromMeta for MethodDocs {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut e = darling::Error::accumulator();
+        let mut docs: Vec<(syn::Ident, syn::LitStr)> = vec![];
+
+        for item in items {
+            let (method, lit) = match item {
+                NestedMeta::Meta(Meta::NameValue(nv)) => match (nv.path.get_ident(), &nv.lit) {
+                    (Some(method), Lit::Str(doc)) => (method, doc),
+                    (None, _) => {
+                        e.push(
+                            darling::Error::custom("Expected a method name").with_span(&nv.path),
+                        );
+                        continue;
+                    }
+                    (_, lit) => {
+                        e.push(darling::Error::unexpected_lit_type(lit));
+                        continue;
+                    }
+                },
+                _ => {
+                    e.push(
+                        darling::Error::custom(r#"Expected `<method> = "<doc>"`"#).with_span(item),
+                    );
+                    continue;
+                }
+            };
+
+            if docs.iter().any(|(m, _)| m == method) {
+                e.push(
+                    darling::Error::custom(format!("Duplicate method `{method}`"))
+                        .with_span(method),
+                );
+            } else {
+                docs.push((method.clone(), lit.clone()));
+            }
+        }
+
+        e.finish_with(Self(docs))
+    }
+}
+
+/// Replaces the documentation of the methods of the `#[near_bindgen]` impls
+/// in `expanded` that have `method_docs` entries. Each line of an entry
+/// becomes a line of the documentation.
+pub fn apply(
+    method_docs: &MethodDocs,
+    generics: &syn::Generics,
+    expanded: TokenStream,
+) -> darling::Result<TokenStream> {
+    if method_docs.0.is_empty() {
+        return Ok(expanded);
+    }
+
+    if !generics.params.is_empty() {
+        return Err(darling::Error::custom(
+            "`method_docs` is not supported on generic contracts, whose external methods are not exported",
+        ));
+    }
+
+    let mut file: syn::File = syn::parse2(expanded)?;
+    let mut methods: Vec<String> = vec![];
+
+    for item in &mut file.items {
+        let item_impl = match item {
+            Item::Impl(item_impl) if crate::cfg::is_near_bindgen(item_impl) => item_impl,
+            _ => continue,
+        };
+
+        for item in &mut item_impl.items {
+            let method = match item {
+                ImplItem::Method(method) => method,
+                _ => continue,
+            };
+
+            methods.push(method.sig.ident.to_string());
+
+            if let Some((_, doc)) = method_docs.0.iter().find(|(m, _)| *m == method.sig.ident) {
+                let lines = doc
+                    .value()
+                    .lines()
+                    .map(|line| format!(" {}", line.trim()).trim_end().to_string())
+                    .collect::<Vec<_>>();
+
+                method.attrs.retain(|attr| !attr.path.is_ident("doc"));
+                method.attrs.splice(
+                    0..0,
+                    lines.iter().map(|line| parse_quote! { #[doc = #line] }),
+                );
+            }
+        }
+    }
+
+    let mut e = darling::Error::accumulator();
+
+    for (method, _) in &method_docs.0 {
+        if !methods.iter().any(|m| method == m) {
+            e.push(
+                darling::Error::custom(format!(
+                    "Unknown method `{method}`, expected one of: {}",
+                    methods.join(", "),
+                ))
+                .with_span(method),
+            );
+        }
+    }
+
+    let items = file.items;
+    e.finish_with(quote! { #(#items)* })
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+    use syn::{ImplItem, ImplItemMethod, Item};
+
+    use crate::{owner::OwnerMeta, standard::nep141::Nep141Meta};
+
+    /// The documentation of `method` in the expansion, one entry per line
+    fn docs(expanded: proc_macro2::TokenStream, method: &str) -> Vec<String> {
+        let file: syn::File = syn::parse2(expanded).unwrap();
+
+        let method: ImplItemMethod = file
+            .items
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Impl(item_impl) => Some(item_impl.items),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|item| match item {
+                ImplItem::Method(m) if m.sig.ident == method => Some(m),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("`{method}` is not generated"));
+
+        method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .map(|attr| match attr.parse_meta().unwrap() {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => s.value(),
+                _ => panic!("Unexpected doc attribute"),
+            })
+            .collect()
+    }
+
+    fn expand_owner(attr: &str) -> darling::Result<proc_macro2::TokenStream> {
+        let ast = syn::parse_str(&format!("#[owner({attr})] struct Contract {{}}")).unwrap();
+        OwnerMeta::from_derive_input(&ast).and_then(crate::owner::expand)
+    }
+
+    #[test]
+    fn default_docs() {
+        let ast = syn::parse_str("#[derive(Nep141)] struct Contract {}").unwrap();
+        let expanded = Nep141Meta::from_derive_input(&ast)
+            .and_then(crate::standard::nep141::expand)
+            .unwrap();
+
+        let ft_transfer = docs(expanded, "ft_transfer").join("\n");
+        assert!(ft_transfer.contains("NEP-141"));
+        assert!(ft_transfer.contains("exactly 1 yoctoNEAR"));
+        assert!(ft_transfer.contains("Panics"));
+
+        let own_propose_owner = docs(expand_owner("").unwrap(), "own_propose_owner").join("\n");
+        assert!(own_propose_owner.contains("exactly 1 yoctoNEAR"));
+        assert!(own_propose_owner.contains("Panics"));
+    }
+
+    #[test]
+    fn custom_docs() {
+        let expanded = expand_owner(
+            r#"method_docs(own_propose_owner = "Hands the keys to `account_id`.
+            Only the council may call this.")"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            docs(expanded.clone(), "own_propose_owner"),
+            [
+                " Hands the keys to `account_id`.",
+                " Only the council may call this."
+            ],
+        );
+        // Other methods keep their default documentation
+        assert!(!docs(expanded, "own_accept_owner").is_empty());
+    }
+
+    #[test]
+    #[should_panic = "Unknown method `own_transfer`, expected one of: own_get_owner"]
+    fn unknown_method() {
+        expand_owner(r#"method_docs(own_transfer = "Transfers ownership.")"#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Duplicate method `own_get_owner`"]
+    fn duplicate_method() {
+        expand_owner(r#"method_docs(own_get_owner = "a", own_get_owner = "b")"#).unwrap();
+    }
+}
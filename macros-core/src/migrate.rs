@@ -0,0 +1,376 @@
+use darling::{FromDeriveInput, FromMeta};
+use once_cell::sync::OnceCell;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use regex::Regex;
+use syn::Expr;
+
+#[derive(Debug, Clone)]
+pub(crate) enum Allow {
+    Owner,
+    CurrentAccount,
+    Creator,
+    Role(Box<syn::Expr>),
+    Custom(Box<syn::Expr>),
+}
+
+impl FromMeta for Allow {
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        match value {
+            syn::Lit::Str(s) => Self::parse(&s.value(), s.span()),
+            _ => Err(darling::Error::unexpected_lit_type(value)),
+        }
+        .map_err(|e| e.with_span(value))
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Self::parse(value, Span::call_site())
+    }
+}
+
+impl Allow {
+    fn parse(value: &str, span: Span) -> darling::Result<Self> {
+        static REGEX: OnceCell<Regex> = OnceCell::new();
+
+        match value {
+            "owner" => Ok(Allow::Owner),
+            "self" => Ok(Allow::CurrentAccount),
+            "creator" => Ok(Allow::Creator),
+            _ => {
+                let r = REGEX.get_or_init(|| Regex::new(r"^role\((.+)\)$").unwrap());
+                let (expr, wrap): (_, fn(Box<Expr>) -> Allow) =
+                    match r.captures(value).and_then(|c| c.get(1)) {
+                        Some(role) => (role.as_str(), Allow::Role),
+                        None => (value, Allow::Custom),
+                    };
+
+                crate::rbac::parse_role(expr, span)
+                    .map(|e| wrap(Box::new(e)))
+                    .map_err(|e| {
+                        darling::Error::custom(format!(
+                            r#"Invalid value "{value}", expected "owner", "self", "creator", "role(...)", or an expression: {e}"#,
+                        ))
+                    })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Format {
+    Borsh,
+    Json,
+}
+
+impl FromMeta for Format {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "borsh" => Ok(Self::Borsh),
+            "json" => Ok(Self::Json),
+            _ => Err(darling::Error::custom(format!(
+                r#"Invalid value "{value}", expected "borsh" or "json""#
+            ))),
+        }
+    }
+}
+
+/// Options of the `Migrate` derive, read from `#[migrate(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(migrate), supports(struct_named))]
+pub struct MigrateMeta {
+    pub(crate) from: syn::Type,
+    pub(crate) from_format: Option<Format>,
+    pub(crate) to: Option<syn::Type>,
+    pub(crate) convert: Option<syn::Path>,
+    pub(crate) convert_with_args: Option<syn::Path>,
+    pub(crate) args: Option<syn::Type>,
+    pub(crate) allow: Option<Allow>,
+    pub(crate) on_deserialize_failure: Option<syn::Path>,
+    #[darling(default)]
+    pub(crate) progressive: bool,
+    pub(crate) from_version: Option<String>,
+    pub(crate) to_version: Option<String>,
+    #[darling(default)]
+    pub(crate) no_event: bool,
+    #[darling(default)]
+    pub(crate) allow_rerun: bool,
+    #[darling(default)]
+    pub(crate) dry_run: bool,
+
+    pub(crate) ident: syn::Ident,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `MigrateHook` implementation and the `migrate` external methods
+pub fn expand(meta: MigrateMeta) -> Result<TokenStream, darling::Error> {
+    let MigrateMeta {
+        from,
+        from_format,
+        to,
+        convert,
+        convert_with_args,
+        args,
+        allow,
+        on_deserialize_failure,
+        progressive,
+        from_version,
+        to_version,
+        no_event,
+        allow_rerun,
+        dry_run,
+
+        ident,
+        generics,
+        cfg,
+        method_docs,
+        method_prefix,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let mut e = darling::Error::accumulator();
+
+    if convert.is_some() && convert_with_args.is_some() {
+        e.push(
+            darling::Error::custom("`convert` and `convert_with_args` are mutually exclusive")
+                .with_span(&convert_with_args),
+        );
+    }
+
+    if args.is_some() && convert_with_args.is_none() {
+        e.push(
+            darling::Error::custom("`args` may only be specified with `convert_with_args`")
+                .with_span(&args),
+        );
+    }
+
+    e.finish()?;
+
+    let (imp, ty, wh) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+    let init = crate::bindgen_attr(&generics, quote! { #[init(ignore_state)] });
+
+    let from_version =
+        from_version.unwrap_or_else(|| from.to_token_stream().to_string().replace(' ', ""));
+    let to_version = to_version.unwrap_or_else(|| {
+        to.as_ref()
+            .map(|t| t.to_token_stream().to_string().replace(' ', ""))
+            .unwrap_or_else(|| ident.to_string())
+    });
+
+    let to = to
+        .map(|t| t.to_token_stream())
+        .unwrap_or_else(|| quote! { Self }.to_token_stream());
+
+    let event = (!no_event).then(|| {
+        quote! {
+            #me::standard::nep297::Event::emit(
+                &#me::migrate::MigrateEvent::new(#from_version, #to_version),
+            );
+        }
+    });
+
+    let finish = quote! {
+        <Self as #me::migrate::MigrateController>::record_migration(#from_version, #to_version);
+        #event
+    };
+
+    let role_assertion = match &allow {
+        Some(Allow::Role(role)) => Some(crate::rbac::assert_role(role, &ident, &generics, &me)),
+        _ => None,
+    };
+
+    let allow = allow.map(|allow| match allow {
+        Allow::Owner => quote! {
+            <Self as #me::owner::Owner>::require_owner();
+        },
+        Allow::CurrentAccount => quote! {
+            #near_sdk::assert_self();
+        },
+        Allow::Creator => quote! {
+            #near_sdk::require!(
+                #near_sdk::env::signer_account_id() == #near_sdk::env::current_account_id(),
+                "Migration must be signed by the contract account",
+            );
+        },
+        Allow::Role(role) => quote! {
+            <Self as #me::rbac::Rbac>::require_role(&#role);
+        },
+        Allow::Custom(expr) => quote! {
+            #near_sdk::require!(#expr, "Migration not allowed");
+        },
+    });
+
+    let step_allow = allow.clone().unwrap_or_else(|| {
+        quote! {
+            #near_sdk::assert_self();
+        }
+    });
+
+    let progressive = progressive.then(|| {
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wh {
+                /// Migrates up to `limit` more items. Can only be called by the accounts
+                /// allowed to migrate, or by the contract itself by default.
+                pub fn migrate_step(&mut self, limit: u32) -> #me::migrate::MigrationProgress {
+                    #step_allow
+                    <Self as #me::migrate::ProgressiveMigration>::migrate_step(self, limit)
+                }
+
+                /// Returns `true` if the progressive migration has completed.
+                pub fn migration_complete(&self) -> bool {
+                    <Self as #me::migrate::ProgressiveMigration>::is_migration_complete()
+                }
+            }
+        }
+    });
+
+    let rerun_guard = (!allow_rerun).then(|| {
+        quote! {
+            <Self as #me::migrate::MigrateController>::require_not_migrated(#from_version, #to_version);
+        }
+    });
+
+    let prelude = quote! {
+        #allow
+        #rerun_guard
+        let old_state = <Self as #me::migrate::MigrateController>::deserialize_old_schema();
+    };
+
+    let (args_param, convert) = if let Some(convert_with_args) = convert_with_args {
+        let args = args
+            .map(|a| a.to_token_stream())
+            .unwrap_or_else(|| quote! { String });
+
+        (
+            Some(quote! { args: #args }),
+            quote! { #convert_with_args(old_state, args) },
+        )
+    } else {
+        let convert = convert
+            .map(|c| quote! { #c(old_state) })
+            .unwrap_or_else(|| {
+                quote! {
+                    <Self as #me::migrate::TryMigrateHook>::try_on_migrate(old_state)
+                        .unwrap_or_else(|e| #near_sdk::FunctionError::panic(&e))
+                }
+            });
+
+        (None, convert)
+    };
+
+    let migrate = if let Some(args_param) = &args_param {
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wh {
+                /// Migrates the contract state from the old schema to the new schema.
+                #init
+                pub fn migrate(#args_param) -> Self {
+                    #prelude
+                    let new_state = #convert;
+                    #finish
+                    new_state
+                }
+            }
+        }
+    } else {
+        quote! {
+            #near_bindgen
+            impl #imp #me::migrate::MigrateExternal for #ident #ty #wh {
+                /// Migrates the contract state from the old schema to the new schema.
+                #init
+                fn migrate() -> Self {
+                    #prelude
+                    let new_state = #convert;
+                    #finish
+                    new_state
+                }
+            }
+        }
+    };
+
+    let dry_run = dry_run.then(|| {
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wh {
+                /// Returns a summary of the result of migrating the current state, without
+                /// modifying it.
+                pub fn migrate_dry_run(#args_param) -> <Self as #me::migrate::MigrateDryRun>::Summary {
+                    let old_state = <Self as #me::migrate::MigrateController>::deserialize_old_schema();
+                    let new_state = #convert;
+                    <Self as #me::migrate::MigrateDryRun>::summarize(new_state)
+                }
+            }
+        }
+    });
+
+    let deserialize_json = matches!(from_format, Some(Format::Json)).then(|| {
+        let failure = if on_deserialize_failure.is_some() {
+            quote! { Err(_) => <Self as #me::migrate::MigrateController>::on_deserialize_failure(bytes), }
+        } else {
+            quote! { Err(e) => #near_sdk::env::panic_str(&e.to_string()), }
+        };
+
+        quote! {
+            fn deserialize_old_schema() -> Self::OldSchema {
+                let bytes = <Self as #me::migrate::MigrateController>::read_old_state_bytes();
+
+                match #me::migrate::deserialize_json_state::<Self::OldSchema>(&bytes) {
+                    Ok(old_schema) => old_schema,
+                    #failure
+                }
+            }
+        }
+    });
+
+    let on_deserialize_failure = on_deserialize_failure.map(|f| {
+        quote! {
+            fn on_deserialize_failure(bytes: Vec<u8>) -> Self::OldSchema {
+                #f(bytes)
+            }
+        }
+    });
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            impl #imp #me::migrate::MigrateController for #ident #ty #wh {
+                type OldSchema = #from;
+                type NewSchema = #to;
+
+                #deserialize_json
+
+                #on_deserialize_failure
+            }
+
+            #migrate
+
+            #dry_run
+
+            #progressive
+
+            #role_assertion
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
@@ -0,0 +1,155 @@
+use darling::{util::PathList, FromDeriveInput};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Options of the `MigrateChain` derive, read from `#[migrate_chain(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(migrate_chain), supports(struct_named))]
+pub struct MigrateChainMeta {
+    pub(crate) versions: PathList,
+    pub(crate) versions_ident: Option<syn::Ident>,
+
+    pub(crate) ident: syn::Ident,
+    pub(crate) vis: syn::Visibility,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the chained `migrate` external method
+pub fn expand(meta: MigrateChainMeta) -> Result<TokenStream, darling::Error> {
+    let MigrateChainMeta {
+        versions,
+        versions_ident,
+
+        ident,
+        vis,
+        generics,
+        cfg,
+        method_docs,
+        method_prefix,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let mut e = darling::Error::accumulator();
+
+    if !generics.params.is_empty() {
+        e.push(
+            darling::Error::custom("MigrateChain does not support generic contracts")
+                .with_span(&generics),
+        );
+    }
+
+    if versions.is_empty() {
+        e.push(darling::Error::custom(
+            "At least one previous version must be specified",
+        ));
+    }
+
+    e.finish()?;
+
+    let versions_ident = versions_ident.unwrap_or_else(|| format_ident!("{}Versions", ident));
+
+    let types = versions
+        .iter()
+        .cloned()
+        .chain(std::iter::once(ident.clone().into()))
+        .collect::<Vec<syn::Path>>();
+
+    let variants = types
+        .iter()
+        .map(|t| {
+            t.segments
+                .last()
+                .map(|s| s.ident.clone())
+                .ok_or_else(|| darling::Error::custom("Invalid version type").with_span(t))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let count = types.len() as u32;
+    let indices = (0..count).collect::<Vec<_>>();
+
+    let conversions = types.iter().enumerate().map(|(i, _)| {
+        let steps = types[i + 1..].iter().map(|t| {
+            quote! { let state: #t = ::core::convert::From::from(state); }
+        });
+
+        quote! { #(#steps)* state }
+    });
+
+    let doc = format!("Schema versions of [`{ident}`], from oldest to latest");
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #[doc = #doc]
+            #vis enum #versions_ident {
+                #(
+                    #[allow(missing_docs)]
+                    #variants(#types),
+                )*
+            }
+
+            impl #me::migrate::VersionedState for #versions_ident {
+                type Latest = #ident;
+
+                const VERSIONS: u32 = #count;
+
+                fn version(&self) -> u32 {
+                    match self {
+                        #(Self::#variants(_) => #indices,)*
+                    }
+                }
+
+                fn deserialize_version(version: u32, bytes: &[u8]) -> Option<Self> {
+                    match version {
+                        #(
+                            #indices => <#types as #near_sdk::borsh::BorshDeserialize>::try_from_slice(bytes)
+                                .ok()
+                                .map(Self::#variants),
+                        )*
+                        _ => None,
+                    }
+                }
+
+                fn into_latest(self) -> #ident {
+                    match self {
+                        #(Self::#variants(state) => { #conversions },)*
+                    }
+                }
+            }
+
+            impl #me::migrate::MigrateChain for #ident {
+                type Versions = #versions_ident;
+            }
+
+            #[#near_sdk::near_bindgen]
+            impl #me::migrate::MigrateExternal for #ident {
+                /// Migrates the contract state from its current version to the latest.
+                #[init(ignore_state)]
+                fn migrate() -> Self {
+                    <#ident as #me::migrate::MigrateChain>::migrate_chain()
+                }
+            }
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
@@ -0,0 +1,355 @@
+use darling::FromDeriveInput;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `Owner` derive, read from `#[owner(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(owner), supports(struct_named, enum_any))]
+pub struct OwnerMeta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) max_key_len: Option<usize>,
+    pub(crate) field: Option<syn::Ident>,
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+    pub(crate) data: darling::ast::Data<(), syn::Field>,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Owner` and `OwnerExternal` implementations
+pub fn expand(meta: OwnerMeta) -> Result<TokenStream, darling::Error> {
+    let OwnerMeta {
+        storage_key,
+        max_key_len,
+        field,
+        method_attrs,
+        skip,
+        ident,
+        generics,
+        data,
+        cfg,
+        method_docs,
+        method_prefix,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    let key_len_check = crate::key_len::check(
+        max_key_len,
+        storage_key.as_ref(),
+        quote! { #me::DefaultStorageKey::Owner },
+        quote! { #me::owner::MAX_KEY_SUFFIX_LEN },
+        "Owner",
+        &ident,
+    );
+    let key_len_constant = key_len_check.constant;
+    let key_len_runtime = key_len_check.runtime;
+
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+    let payable = crate::bindgen_attr(&generics, quote! { #[payable] });
+
+    skip.check(
+        &[
+            "own_get_owner",
+            "own_get_proposed_owner",
+            "own_renounce_owner",
+            "own_propose_owner",
+            "own_accept_owner",
+        ],
+        &method_attrs,
+    )?;
+    method_attrs.check(
+        &generics,
+        &[
+            ("own_get_owner", &[]),
+            ("own_get_proposed_owner", &[]),
+            ("own_renounce_owner", &["payable"]),
+            ("own_propose_owner", &["payable"]),
+            ("own_accept_owner", &["payable"]),
+        ],
+    )?;
+    let attrs = |method| method_attrs.get(method);
+    let own_get_owner = attrs("own_get_owner");
+    let own_get_proposed_owner = attrs("own_get_proposed_owner");
+    let own_renounce_owner = attrs("own_renounce_owner");
+    let own_propose_owner = attrs("own_propose_owner");
+    let own_accept_owner = attrs("own_accept_owner");
+
+    if let Some(field) = &field {
+        crate::field::check(
+            field,
+            &data,
+            &[
+                ("storage_key", storage_key.is_some()),
+                ("max_key_len", max_key_len.is_some()),
+            ],
+        )?;
+    }
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                let root = #me::slot::Slot::root(#storage_key);
+                #key_len_runtime
+                root
+            }
+        }
+    });
+
+    let field_state = field.map(|field| {
+        quote! {
+            fn read_owner() -> Option<#near_sdk::AccountId> {
+                #near_sdk::env::state_read::<Self>().and_then(|contract| contract.#field.owner)
+            }
+
+            fn current_owner(&self) -> Option<#near_sdk::AccountId> {
+                self.#field.owner.clone()
+            }
+
+            fn proposed_owner(&self) -> Option<#near_sdk::AccountId> {
+                self.#field.proposed_owner.clone()
+            }
+
+            fn is_initialized(&self) -> bool {
+                self.#field.is_initialized
+            }
+
+            fn set_initialized(&mut self) {
+                self.#field.is_initialized = true;
+            }
+
+            fn update_owner_unchecked(&mut self, new: Option<#near_sdk::AccountId>) {
+                self.#field.owner = new;
+            }
+
+            fn update_proposed_unchecked(&mut self, new: Option<#near_sdk::AccountId>) {
+                self.#field.proposed_owner = new;
+            }
+        }
+    });
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::owner::OwnerExternal },
+        vec![
+            (
+                "own_get_owner",
+                quote! {
+                    /// Returns the account ID of the current owner, if any.
+                    #own_get_owner
+                    fn own_get_owner(&self) -> Option<#near_sdk::AccountId> {
+                        <Self as #me::owner::Owner>::current_owner(self)
+                    }
+                },
+            ),
+            (
+                "own_get_proposed_owner",
+                quote! {
+                    /// Returns the account ID that the current owner has proposed to take over
+                    /// ownership, if any.
+                    #own_get_proposed_owner
+                    fn own_get_proposed_owner(&self) -> Option<#near_sdk::AccountId> {
+                        <Self as #me::owner::Owner>::proposed_owner(self)
+                    }
+                },
+            ),
+            (
+                "own_renounce_owner",
+                quote! {
+                    /// Renounces ownership, leaving the contract without an owner. Requires a
+                    /// deposit of exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if the caller is not the current owner.
+                    #payable
+                    #own_renounce_owner
+                    fn own_renounce_owner(&mut self) {
+                        #near_sdk::assert_one_yocto();
+                        <Self as #me::owner::Owner>::renounce_owner(self);
+                    }
+                },
+            ),
+            (
+                "own_propose_owner",
+                quote! {
+                    /// Proposes `account_id` as the new owner, or withdraws the proposal if
+                    /// `account_id` is `null`. The proposed owner takes over with
+                    /// `own_accept_owner`. Requires a deposit of exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if the caller is not the current owner.
+                    #payable
+                    #own_propose_owner
+                    fn own_propose_owner(&mut self, account_id: Option<#near_sdk::AccountId>) {
+                        #near_sdk::assert_one_yocto();
+                        <Self as #me::owner::Owner>::propose_owner(self, account_id);
+                    }
+                },
+            ),
+            (
+                "own_accept_owner",
+                quote! {
+                    /// Accepts ownership proposed with `own_propose_owner`. Requires a deposit
+                    /// of exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if the caller is not the proposed owner.
+                    #payable
+                    #own_accept_owner
+                    fn own_accept_owner(&mut self) {
+                        #near_sdk::assert_one_yocto();
+                        <Self as #me::owner::Owner>::accept_owner(self);
+                    }
+                },
+            ),
+        ],
+        &skip,
+    )?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #key_len_constant
+
+            impl #imp #me::owner::Owner for #ident #ty #wher {
+                #root
+                #field_state
+            }
+
+            #external_impl
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::OwnerMeta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(method_attrs(own_get_owner = "private", own_accept_owner = "private"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [private] fn own_get_owner ("));
+        assert!(expanded.contains("# [payable] # [private] fn own_accept_owner ("));
+        assert!(expanded.contains("# [payable] fn own_propose_owner ("));
+    }
+
+    #[test]
+    fn method_attrs_generic() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(method_attrs(own_get_owner = "private"))]
+            struct Contract<T> {
+                t: T,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let message = super::expand(meta).unwrap_err().to_string();
+
+        assert!(message.contains("`own_get_owner` cannot have `method_attrs`"));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(skip = "own_get_owner, own_renounce_owner")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("OwnerExternal"));
+        assert!(!expanded.contains("fn own_get_owner ("));
+        assert!(!expanded.contains("fn own_renounce_owner ("));
+        assert!(expanded.contains("pub fn own_get_proposed_owner ("));
+        assert!(expanded.contains("# [payable] pub fn own_accept_owner ("));
+    }
+
+    #[test]
+    fn field() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(field = "owner_state")]
+            struct Contract { owner_state: OwnerState }
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("self . owner_state . owner = new ;"));
+        assert!(expanded.contains(
+            "< Self as :: near_sdk_contract_tools :: owner :: Owner > :: current_owner (self)"
+        ));
+    }
+
+    #[test]
+    fn field_errors() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(field = "owner_state", storage_key = "x")]
+            struct Contract { state: OwnerState }
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let message = super::expand(meta).unwrap_err().to_string();
+
+        assert!(message.contains("The contract has no field `owner_state`"));
+        assert!(message.contains(
+            "`storage_key` cannot be combined with `field`, which does not use storage slots"
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use darling::FromDeriveInput;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `Pause` derive, read from `#[pause(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(pause), supports(struct_named, enum_any))]
+pub struct PauseMeta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) max_key_len: Option<usize>,
+    pub(crate) field: Option<syn::Ident>,
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+    pub(crate) data: darling::ast::Data<(), syn::Field>,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Pause` and `PauseExternal` implementations
+pub fn expand(meta: PauseMeta) -> Result<TokenStream, darling::Error> {
+    let PauseMeta {
+        storage_key,
+        max_key_len,
+        field,
+        method_attrs,
+        skip,
+        ident,
+        generics,
+        data,
+        cfg,
+        method_docs,
+        method_prefix,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    let key_len_check = crate::key_len::check(
+        max_key_len,
+        storage_key.as_ref(),
+        quote! { #me::DefaultStorageKey::Pause },
+        quote! { #me::pause::MAX_KEY_SUFFIX_LEN },
+        "Pause",
+        &ident,
+    );
+    let key_len_constant = key_len_check.constant;
+    let key_len_runtime = key_len_check.runtime;
+
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+
+    skip.check(&["paus_is_paused"], &method_attrs)?;
+    method_attrs.check(&generics, &[("paus_is_paused", &[])])?;
+    let paus_is_paused = method_attrs.get("paus_is_paused");
+
+    if let Some(field) = &field {
+        crate::field::check(
+            field,
+            &data,
+            &[
+                ("storage_key", storage_key.is_some()),
+                ("max_key_len", max_key_len.is_some()),
+            ],
+        )?;
+    }
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                let root = #me::slot::Slot::root(#storage_key);
+                #key_len_runtime
+                root
+            }
+        }
+    });
+
+    let field_state = field.map(|field| {
+        quote! {
+            fn is_paused() -> bool {
+                #near_sdk::env::state_read::<Self>()
+                    .map_or(false, |contract| contract.#field.is_paused)
+            }
+
+            fn paused(&self) -> bool {
+                self.#field.is_paused
+            }
+
+            fn set_is_paused(&mut self, is_paused: bool) {
+                self.#field.is_paused = is_paused;
+            }
+        }
+    });
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::pause::PauseExternal },
+        vec![(
+            "paus_is_paused",
+            quote! {
+                /// Returns `true` if the contract is paused, `false` otherwise.
+                #paus_is_paused
+                fn paus_is_paused(&self) -> bool {
+                    <Self as #me::pause::Pause>::paused(self)
+                }
+            },
+        )],
+        &skip,
+    )?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #key_len_constant
+
+            impl #imp #me::pause::Pause for #ident #ty #wher {
+                #root
+                #field_state
+            }
+
+            #external_impl
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::PauseMeta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Pause)]
+            #[pause(method_attrs(paus_is_paused = "private"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = PauseMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [private] fn paus_is_paused ("));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Pause)]
+            #[pause(skip = "paus_is_paused")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = PauseMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn paus_is_paused ("));
+        assert!(expanded.contains("impl :: near_sdk_contract_tools :: pause :: Pause for Contract"));
+    }
+}
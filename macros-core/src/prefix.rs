@@ -0,0 +1,161 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, ImplItem, Item, Lit};
+
+/// A prefix for the names of generated external methods, specified as
+/// `method_prefix = "<prefix>"`, e.g. `method_prefix = "admin_"` generates
+/// `admin_own_get_owner` instead of `own_get_owner`.
+#[derive(Debug)]
+pub struct MethodPrefix(syn::LitStr);
+
+impl FromMeta for MethodPrefix {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        let value = match value {
+            Lit::Str(s) => s,
+            _ => return Err(darling::Error::unexpected_lit_type(value)),
+        };
+
+        let prefix = value.value();
+
+        if prefix.is_empty() || syn::parse_str::<syn::Ident>(&format!("{prefix}method")).is_err() {
+            return Err(darling::Error::custom(format!(
+                "Invalid method prefix `{prefix}`, expected the start of an identifier, e.g. `admin_`",
+            ))
+            .with_span(value));
+        }
+
+        Ok(Self(value.clone()))
+    }
+}
+
+/// Applies `method_prefix` to the methods of the `#[near_bindgen]` impls in
+/// `expanded`. Renamed methods no longer match their external trait, so the
+/// methods of a trait impl are generated as an inherent impl instead, as
+/// with `skip`. Internal implementations are not renamed.
+pub fn apply(
+    prefix: Option<&MethodPrefix>,
+    generics: &syn::Generics,
+    expanded: TokenStream,
+) -> darling::Result<TokenStream> {
+    let prefix = match prefix {
+        Some(MethodPrefix(prefix)) => prefix,
+        None => return Ok(expanded),
+    };
+
+    if !generics.params.is_empty() {
+        return Err(darling::Error::custom(
+            "`method_prefix` is not supported on generic contracts, whose external methods are not exported",
+        )
+        .with_span(prefix));
+    }
+
+    let file: syn::File = syn::parse2(expanded)?;
+
+    let items = file.items.into_iter().map(|item| match item {
+        Item::Impl(mut item_impl) if crate::cfg::is_near_bindgen(&item_impl) => {
+            item_impl.trait_ = None;
+
+            for item in &mut item_impl.items {
+                if let ImplItem::Method(method) = item {
+                    method.vis = parse_quote! { pub };
+                    method.sig.ident = format_ident!(
+                        "{}{}",
+                        prefix.value(),
+                        method.sig.ident,
+                        span = method.sig.ident.span(),
+                    );
+                }
+            }
+
+            quote! { #item_impl }
+        }
+        item => quote! { #item },
+    });
+
+    Ok(quote! { #(#items)* })
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use crate::{
+        approval::simple_multisig::SimpleMultisigMeta, migrate::MigrateMeta, owner::OwnerMeta,
+    };
+
+    #[test]
+    fn trait_methods() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Owner)]
+            #[owner(method_prefix = "admin_", skip = "own_renounce_owner")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = OwnerMeta::from_derive_input(&ast).unwrap();
+        let expanded = crate::owner::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("OwnerExternal"));
+        assert!(!expanded.contains("own_renounce_owner"));
+        assert!(expanded.contains("pub fn admin_own_get_owner ("));
+        assert!(expanded.contains("# [payable] pub fn admin_own_accept_owner ("));
+        // Internal implementation is unaffected
+        assert!(expanded.contains("impl :: near_sdk_contract_tools :: owner :: Owner for Contract"));
+    }
+
+    #[test]
+    fn with_cfg() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Migrate)]
+            #[migrate(from = "ContractV1", method_prefix = "state_", cfg(method = "migrate", predicate = "test"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = MigrateMeta::from_derive_input(&ast).unwrap();
+        let expanded = crate::migrate::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [cfg (test)] # [:: near_sdk :: near_bindgen] impl Contract {"));
+        assert!(expanded.contains("# [init (ignore_state)] pub fn state_migrate () -> Self {"));
+    }
+
+    #[test]
+    fn multisig() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(SimpleMultisig)]
+            #[simple_multisig(action = "Action", role = "Role::Multisig", external, method_prefix = "dao_")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = SimpleMultisigMeta::from_derive_input(&ast).unwrap();
+        let expanded = crate::approval::simple_multisig::expand(meta)
+            .unwrap()
+            .to_string();
+
+        assert!(expanded.contains("pub fn dao_multisig_request ("));
+        assert!(expanded.contains("pub fn dao_multisig_request_summary ("));
+    }
+
+    #[test]
+    #[should_panic = "Invalid method prefix `1_`"]
+    fn invalid_prefix() {
+        let ast = syn::parse_str(r#"#[owner(method_prefix = "1_")] struct Contract {}"#).unwrap();
+        OwnerMeta::from_derive_input(&ast).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "`method_prefix` is not supported on generic contracts"]
+    fn generic() {
+        let ast = syn::parse_str(r#"#[owner(method_prefix = "a_")] struct Contract<T> { t: T }"#)
+            .unwrap();
+        crate::owner::expand(OwnerMeta::from_derive_input(&ast).unwrap()).unwrap();
+    }
+}
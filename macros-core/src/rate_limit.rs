@@ -0,0 +1,54 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, ItemFn};
+
+/// Options of the `#[rate_limited(...)]` attribute
+#[derive(Debug, FromMeta)]
+pub struct RateLimitedMeta {
+    pub(crate) policy: Expr,
+    pub(crate) key: Option<String>,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to `item` with a call to `RateLimit::require_rate_limit` before
+/// its body
+pub fn expand(meta: RateLimitedMeta, item: ItemFn) -> Result<TokenStream, darling::Error> {
+    let RateLimitedMeta {
+        policy,
+        key,
+        me,
+        macros: _,
+        near_sdk: _,
+    } = meta;
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if sig.receiver().is_none() {
+        return Err(darling::Error::custom("Rate limits apply to methods only").with_span(&sig));
+    }
+
+    let key = key.unwrap_or_else(|| sig.ident.to_string());
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            <Self as #me::rate_limit::RateLimit>::require_rate_limit(#key, &(#policy));
+            #block
+        }
+    })
+}
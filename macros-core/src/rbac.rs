@@ -0,0 +1,205 @@
+use darling::{util::Flag, FromDeriveInput, FromVariant};
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::Expr;
+
+/// Options of the `Rbac` derive, read from `#[rbac(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(rbac), supports(struct_named, enum_any))]
+pub struct RbacMeta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) roles: Expr,
+
+    // darling
+    pub(crate) ident: syn::Ident,
+    pub(crate) generics: syn::Generics,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Rbac` implementation
+pub fn expand(meta: RbacMeta) -> Result<TokenStream, darling::Error> {
+    let RbacMeta {
+        storage_key,
+        roles,
+
+        ident,
+        generics,
+
+        me,
+        macros: _,
+        near_sdk: _,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                #me::slot::Slot::root(#storage_key)
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #imp #me::rbac::Rbac for #ident #ty #wher {
+            type Role = #roles;
+
+            #root
+        }
+    })
+}
+
+/// Parses a role expression given inside an attribute string, e.g.
+/// `Role::Admin` in `hook = "role(Role::Admin)"`, with the span of the
+/// string, so that errors in the expression point at the attribute.
+pub(crate) fn parse_role(role: &str, span: Span) -> syn::Result<Expr> {
+    fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|mut tt| {
+                if let TokenTree::Group(g) = &tt {
+                    let mut group = Group::new(g.delimiter(), respan(g.stream(), span));
+                    group.set_span(span);
+                    tt = group.into();
+                } else {
+                    tt.set_span(span);
+                }
+                tt
+            })
+            .collect()
+    }
+
+    let tokens = role
+        .parse::<TokenStream>()
+        .map_err(|e| syn::Error::new(span, e))?;
+
+    syn::parse2(respan(tokens, span))
+}
+
+/// Asserts that `role` is a role of the contract, i.e. an expression of type
+/// `<Contract as Rbac>::Role`, so that a misspelled variant fails to compile
+/// even if the guard that uses the role is excluded by `cfg`.
+pub(crate) fn assert_role(
+    role: &Expr,
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+    me: &syn::Path,
+) -> TokenStream {
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            fn assert_role #imp () #wher {
+                let _: &<#ident #ty as #me::rbac::Rbac>::Role = &#role;
+            }
+        };
+    }
+}
+
+/// Options of the `Role` derive, read from `#[role(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(role), supports(enum_any))]
+pub struct RoleMeta {
+    pub(crate) no_display: Flag,
+
+    // darling
+    pub(crate) ident: syn::Ident,
+    pub(crate) generics: syn::Generics,
+    pub(crate) data: darling::ast::Data<RoleVariant, ()>,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+#[derive(Debug, FromVariant)]
+pub(crate) struct RoleVariant {
+    pub(crate) ident: syn::Ident,
+    pub(crate) fields: darling::ast::Fields<()>,
+}
+
+/// Expands to the `RbacRole` implementation, which identifies a role by the
+/// index of its variant (as `BorshSerialize` would), and a `Display`
+/// implementation with the names of the variants
+pub fn expand_role(meta: RoleMeta) -> Result<TokenStream, darling::Error> {
+    let RoleMeta {
+        no_display,
+
+        ident,
+        generics,
+        data,
+
+        me,
+        macros: _,
+        near_sdk: _,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+
+    let variants = data.take_enum().unwrap_or_default();
+
+    let mut e = darling::Error::accumulator();
+
+    for variant in &variants {
+        if !variant.fields.is_unit() {
+            e.push(
+                darling::Error::custom(format!(
+                    "Roles cannot have fields, but `{}` does; implement `BorshSerialize` for the roles instead",
+                    variant.ident,
+                ))
+                .with_span(&variant.ident),
+            );
+        }
+    }
+
+    if variants.len() > usize::from(u8::MAX) + 1 {
+        e.push(darling::Error::custom("Roles are limited to 256 variants").with_span(&ident));
+    }
+
+    e.finish()?;
+
+    let idents = variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let indices = (0..=u8::MAX).take(variants.len());
+    let names = idents.iter().map(|i| i.to_string());
+
+    let display = (!no_display.is_present()).then(|| {
+        quote! {
+            impl #imp ::std::fmt::Display for #ident #ty #wher {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(match self {
+                        #(Self::#idents => #names,)*
+                    })
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #imp #me::rbac::RbacRole for #ident #ty #wher {
+            fn role_key(&self) -> ::std::vec::Vec<u8> {
+                ::std::vec![match self {
+                    #(Self::#idents => #indices,)*
+                }]
+            }
+        }
+
+        #display
+    })
+}
@@ -0,0 +1,64 @@
+use darling::{util::Flag, FromMeta};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ItemFn;
+
+/// Options of the `#[non_reentrant(...)]` attribute
+#[derive(Debug, FromMeta)]
+pub struct NonReentrantMeta {
+    pub(crate) scope: Option<String>,
+    pub(crate) hold: Flag,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to `item` with a call to `Reentrancy::enter` before its body,
+/// whose guard is released at the end of the body, or held with `hold`
+pub fn expand(meta: NonReentrantMeta, item: ItemFn) -> Result<TokenStream, darling::Error> {
+    let NonReentrantMeta {
+        scope,
+        hold,
+        me,
+        macros: _,
+        near_sdk: _,
+    } = meta;
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if sig.receiver().is_none() {
+        return Err(
+            darling::Error::custom("Reentrancy guards apply to methods only").with_span(&sig),
+        );
+    }
+
+    let scope = scope.unwrap_or_else(|| sig.ident.to_string());
+
+    let enter = quote! { <Self as #me::reentrancy::Reentrancy>::enter(#scope) };
+
+    let guard = if hold.is_present() {
+        quote! { #enter.hold(); }
+    } else {
+        quote! { let __reentrancy_guard = #enter; }
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            #guard
+            #block
+        }
+    })
+}
@@ -6,6 +6,9 @@ use heck::{
     ToUpperCamelCase,
 };
 
+/// Mirrors `near_sdk_contract_tools::rename::Strategy` (which cannot be
+/// shared, since the runtime crate depends on this one). Keep the two in
+/// sync; `tests/macros/event.rs` checks that they agree.
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 pub enum RenameStrategy {
@@ -35,8 +38,11 @@ impl RenameStrategy {
 
 impl FromMeta for RenameStrategy {
     fn from_string(value: &str) -> darling::Result<Self> {
-        RenameStrategy::try_from(value)
-            .map_err(|_| darling::Error::custom("Invalid rename strategy"))
+        RenameStrategy::try_from(value).map_err(|_| {
+            darling::Error::custom(format!(
+                r#"Invalid rename strategy "{value}", expected one of "UpperCamelCase", "lowerCamelCase", "snake_case", "kebab-case", "SHOUTY_SNAKE_CASE", "Title Case", or "SHOUTY-KEBAB-CASE""#,
+            ))
+        })
     }
 }
 
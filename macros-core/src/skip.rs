@@ -0,0 +1,138 @@
+use darling::FromMeta;
+use syn::Lit;
+
+use crate::method_attrs::MethodAttrs;
+
+/// Generated external methods to omit, specified as
+/// `skip = "<method>, <method>"`, so that the contract can implement them
+/// itself.
+#[derive(Debug, Default)]
+pub struct Skip(Vec<syn::Ident>);
+
+impl FromMeta for Skip {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        let value = match value {
+            Lit::Str(s) => s,
+            _ => return Err(darling::Error::unexpected_lit_type(value)),
+        };
+
+        let mut e = darling::Error::accumulator();
+        let mut methods: Vec<syn::Ident> = vec![];
+
+        for name in value.value().split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+
+            match syn::parse_str::<syn::Ident>(name) {
+                Err(_) => e.push(
+                    darling::Error::custom(format!("Invalid method name `{name}`"))
+                        .with_span(value),
+                ),
+                Ok(_) if methods.iter().any(|m| m == name) => e.push(
+                    darling::Error::custom(format!("Duplicate method `{name}`")).with_span(value),
+                ),
+                Ok(_) => methods.push(syn::Ident::new(name, value.span())),
+            }
+        }
+
+        e.finish_with(Self(methods))
+    }
+}
+
+impl Skip {
+    /// Moves the given methods into a new set, e.g. to hand them to a
+    /// constituent expansion.
+    pub fn take(&mut self, methods: &[&str]) -> Self {
+        let (taken, rest) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|m| methods.iter().any(|n| m == n));
+        self.0 = rest;
+        Self(taken)
+    }
+
+    /// Checks that every skipped method is one of the generated `methods`,
+    /// and that skipped methods are not also given `method_attrs`.
+    pub fn check(&self, methods: &[&str], method_attrs: &MethodAttrs) -> darling::Result<()> {
+        let mut e = darling::Error::accumulator();
+
+        for method in &self.0 {
+            if !methods.iter().any(|m| method == m) {
+                e.push(
+                    darling::Error::custom(format!(
+                        "Unknown method `{method}`, expected one of: {}",
+                        methods.join(", "),
+                    ))
+                    .with_span(method),
+                );
+            }
+        }
+
+        for (method, span) in method_attrs.methods() {
+            if self.contains(method) {
+                e.push(
+                    darling::Error::custom(format!(
+                        "`{method}` is skipped, so it cannot have `method_attrs`",
+                    ))
+                    .with_span(&span),
+                );
+            }
+        }
+
+        e.finish()
+    }
+
+    pub fn contains(&self, method: &str) -> bool {
+        self.0.iter().any(|m| m == method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromMeta;
+
+    use super::Skip;
+    use crate::method_attrs::MethodAttrs;
+
+    fn parse(s: &str) -> darling::Result<Skip> {
+        let meta: syn::Meta = syn::parse_str(s).unwrap();
+        Skip::from_meta(&meta)
+    }
+
+    #[test]
+    fn parse_and_take() {
+        let mut skip = parse(r#"skip = "a, b,""#).unwrap();
+        assert!(skip.contains("a"));
+        assert!(skip.contains("b"));
+        assert!(!skip.contains("c"));
+
+        let b = skip.take(&["b"]);
+        assert!(!skip.contains("b"));
+        assert!(b.contains("b"));
+
+        skip.check(&["a"], &MethodAttrs::default()).unwrap();
+        b.check(&["b"], &MethodAttrs::default()).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Unknown method `d`, expected one of: a, b"]
+    fn unknown_method() {
+        let skip = parse(r#"skip = "a, d""#).unwrap();
+        skip.check(&["a", "b"], &MethodAttrs::default()).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "Duplicate method `a`"]
+    fn duplicate_method() {
+        parse(r#"skip = "a, a""#).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "`a` is skipped, so it cannot have `method_attrs`"]
+    fn skipped_method_attrs() {
+        let skip = parse(r#"skip = "a""#).unwrap();
+        let meta: syn::Meta = syn::parse_str(r#"method_attrs(a = "private")"#).unwrap();
+        let method_attrs = MethodAttrs::from_meta(&meta).unwrap();
+        skip.check(&["a"], &method_attrs).unwrap();
+    }
+}
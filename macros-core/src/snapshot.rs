@@ -0,0 +1,459 @@
+//! Snapshots of the expansion of every macro, so that changes to the
+//! generated code show up in review. Run the tests with `UPDATE_SNAPSHOTS=1`
+//! to write the snapshots after an intended change.
+
+use darling::FromDeriveInput;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+const SNAPSHOTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/snapshots");
+
+/// Formats a token stream with one statement, item, or attribute per line,
+/// indented by brace depth. Only meant to make snapshot diffs readable.
+fn pretty(tokens: TokenStream) -> String {
+    struct Printer {
+        out: String,
+        indent: usize,
+        line_start: bool,
+        /// The previous token is a joint punctuation, e.g. the first `:` of `::`
+        joint: bool,
+    }
+
+    impl Printer {
+        fn token(&mut self, s: &str) {
+            if self.line_start {
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.line_start = false;
+            } else if !self.joint {
+                self.out.push(' ');
+            }
+            self.out.push_str(s);
+            self.joint = false;
+        }
+
+        fn newline(&mut self) {
+            if !self.line_start {
+                self.out.push('\n');
+                self.line_start = true;
+            }
+        }
+
+        fn stream(&mut self, tokens: TokenStream) {
+            let mut after_pound = false;
+
+            for tt in tokens {
+                match &tt {
+                    TokenTree::Group(g)
+                        if g.delimiter() == Delimiter::Brace && g.stream().is_empty() =>
+                    {
+                        self.token("{ }");
+                        self.newline();
+                    }
+                    TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                        self.token("{");
+                        self.newline();
+                        self.indent += 1;
+                        self.stream(g.stream());
+                        self.indent -= 1;
+                        self.newline();
+                        self.token("}");
+                        self.newline();
+                    }
+                    TokenTree::Group(g) if after_pound && g.delimiter() == Delimiter::Bracket => {
+                        self.token(&tt.to_string());
+                        self.newline();
+                    }
+                    TokenTree::Punct(p) if p.as_char() == ';' => {
+                        self.token(";");
+                        self.newline();
+                    }
+                    _ => self.token(&tt.to_string()),
+                }
+
+                after_pound = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '#');
+                self.joint = matches!(&tt, TokenTree::Punct(p) if p.spacing() == Spacing::Joint);
+            }
+        }
+    }
+
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+        line_start: true,
+        joint: false,
+    };
+    printer.stream(tokens);
+    printer.newline();
+    printer.out
+}
+
+fn assert_snapshot(name: &str, tokens: TokenStream) {
+    let path = format!("{SNAPSHOTS_DIR}/{name}.snap");
+    let actual = pretty(tokens);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(SNAPSHOTS_DIR).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!("Missing snapshot {path}, run the tests with UPDATE_SNAPSHOTS=1 to write it")
+    });
+
+    assert!(
+        expected == actual,
+        "Expansion of `{name}` does not match {path}, run the tests with UPDATE_SNAPSHOTS=1 to update it if the change is intended\n\n{}",
+        diff(&expected, &actual),
+    );
+}
+
+/// Lines that differ between the snapshot and the expansion
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    (0..expected.len().max(actual.len()))
+        .filter_map(|i| {
+            let (e, a) = (expected.get(i), actual.get(i));
+            (e != a).then(|| {
+                format!(
+                    "{:>5} - {}\n{:>5} + {}",
+                    i + 1,
+                    e.unwrap_or(&""),
+                    "",
+                    a.unwrap_or(&""),
+                )
+            })
+        })
+        .take(20)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn derive<T: FromDeriveInput>(
+    input: &str,
+    attribute: &str,
+    expand: fn(T) -> Result<TokenStream, darling::Error>,
+) -> TokenStream {
+    let input = syn::parse_str(input).unwrap();
+    crate::expand_derive(&input, attribute, expand).unwrap()
+}
+
+#[test]
+fn owner() {
+    assert_snapshot(
+        "owner",
+        derive(
+            "#[derive(Owner)] #[near_bindgen] struct Contract {}",
+            "owner",
+            crate::owner::expand,
+        ),
+    );
+}
+
+#[test]
+fn pause() {
+    assert_snapshot(
+        "pause",
+        derive(
+            r#"#[derive(Pause)] #[pause(storage_key = "p")] #[near_bindgen] struct Contract {}"#,
+            "pause",
+            crate::pause::expand,
+        ),
+    );
+}
+
+#[test]
+fn owner_method_attrs() {
+    assert_snapshot(
+        "owner_method_attrs",
+        derive(
+            r#"#[derive(Owner)] #[owner(method_attrs(own_get_owner = "private", own_accept_owner = "private"))] #[near_bindgen] struct Contract {}"#,
+            "owner",
+            crate::owner::expand,
+        ),
+    );
+}
+
+#[test]
+fn pause_method_attrs() {
+    assert_snapshot(
+        "pause_method_attrs",
+        derive(
+            r#"#[derive(Pause)] #[pause(method_attrs(paus_is_paused = "private, payable"))] #[near_bindgen] struct Contract {}"#,
+            "pause",
+            crate::pause::expand,
+        ),
+    );
+}
+
+#[test]
+fn rbac() {
+    assert_snapshot(
+        "rbac",
+        derive(
+            r#"#[derive(Rbac)] #[rbac(roles = "Role")] #[near_bindgen] struct Contract {}"#,
+            "rbac",
+            crate::rbac::expand,
+        ),
+    );
+}
+
+#[test]
+fn role() {
+    assert_snapshot(
+        "role",
+        derive(
+            "#[derive(Role)] enum Role { Admin, Minter }",
+            "role",
+            crate::rbac::expand_role,
+        ),
+    );
+}
+
+#[test]
+fn nep141() {
+    assert_snapshot(
+        "nep141",
+        derive(
+            "#[derive(Nep141)] #[near_bindgen] struct Contract {}",
+            "nep141",
+            crate::standard::nep141::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep148() {
+    assert_snapshot(
+        "nep148",
+        derive(
+            r#"#[derive(Nep148)] #[nep148(name = "Token", symbol = "TKN", decimals = 18)] #[near_bindgen] struct Contract {}"#,
+            "nep148",
+            crate::standard::nep148::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep245() {
+    assert_snapshot(
+        "nep245",
+        derive(
+            "#[derive(Nep245)] #[near_bindgen] struct Contract {}",
+            "nep245",
+            crate::standard::nep245::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep330() {
+    assert_snapshot(
+        "nep330",
+        derive(
+            r#"#[derive(ContractMetadata)] #[contract_metadata(link = "https://example.com/contract")] #[near_bindgen] struct Contract {}"#,
+            "contract_metadata",
+            crate::standard::nep330::expand,
+        ),
+    );
+}
+
+#[test]
+fn fungible_token() {
+    assert_snapshot(
+        "fungible_token",
+        derive(
+            r#"#[derive(FungibleToken)] #[fungible_token(name = "Token", symbol = "TKN", decimals = 18, no_hooks)] #[near_bindgen] struct Contract {}"#,
+            "fungible_token",
+            crate::standard::fungible_token::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep141_method_attrs() {
+    assert_snapshot(
+        "nep141_method_attrs",
+        derive(
+            r#"#[derive(Nep141)] #[nep141(method_attrs(ft_total_supply = "private", ft_transfer = "private"))] #[near_bindgen] struct Contract {}"#,
+            "nep141",
+            crate::standard::nep141::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep148_method_attrs() {
+    assert_snapshot(
+        "nep148_method_attrs",
+        derive(
+            r#"#[derive(Nep148)] #[nep148(name = "Token", symbol = "TKN", decimals = 18, method_attrs(ft_metadata = "private"))] #[near_bindgen] struct Contract {}"#,
+            "nep148",
+            crate::standard::nep148::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep245_method_attrs() {
+    assert_snapshot(
+        "nep245_method_attrs",
+        derive(
+            r#"#[derive(Nep245)] #[nep245(method_attrs(mt_balance_of = "private", mt_transfer = "private"))] #[near_bindgen] struct Contract {}"#,
+            "nep245",
+            crate::standard::nep245::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep330_method_attrs() {
+    assert_snapshot(
+        "nep330_method_attrs",
+        derive(
+            r#"#[derive(ContractMetadata)] #[contract_metadata(link = "https://example.com/contract", method_attrs(contract_source_metadata = "private"))] #[near_bindgen] struct Contract {}"#,
+            "contract_metadata",
+            crate::standard::nep330::expand,
+        ),
+    );
+}
+
+#[test]
+fn fungible_token_method_attrs() {
+    assert_snapshot(
+        "fungible_token_method_attrs",
+        derive(
+            r#"#[derive(FungibleToken)] #[fungible_token(name = "Token", symbol = "TKN", decimals = 18, no_hooks, method_attrs(ft_transfer = "private", ft_metadata = "private"))] #[near_bindgen] struct Contract {}"#,
+            "fungible_token",
+            crate::standard::fungible_token::expand,
+        ),
+    );
+}
+
+#[test]
+fn nep297() {
+    assert_snapshot(
+        "nep297",
+        derive(
+            r#"#[derive(Nep297)] #[nep297(standard = "x-test", version = "1.0.0", rename_all = "snake_case")] enum Event { ValueChanged { value: u32 }, Reset }"#,
+            "nep297",
+            crate::standard::nep297::expand,
+        ),
+    );
+}
+
+#[test]
+fn event() {
+    use darling::FromMeta;
+
+    let attr: syn::Meta =
+        syn::parse_str(r#"event(standard = "x-test", version = "1.0.0")"#).unwrap();
+    let item = syn::parse_str("struct ValueChanged { value: u32 }").unwrap();
+
+    assert_snapshot(
+        "event",
+        crate::standard::event::EventAttributeMeta::from_meta(&attr)
+            .and_then(|meta| crate::standard::event::event_attribute(meta, item))
+            .unwrap(),
+    );
+}
+
+#[test]
+fn rate_limited() {
+    use darling::FromMeta;
+
+    let attr: syn::Meta =
+        syn::parse_str(r#"rate_limited(policy = "RateLimitPolicy::cooldown(100)")"#).unwrap();
+    let item =
+        syn::parse_str("#[payable] pub fn claim(&mut self, amount: U128) -> U128 { amount }")
+            .unwrap();
+
+    assert_snapshot(
+        "rate_limited",
+        crate::rate_limit::RateLimitedMeta::from_meta(&attr)
+            .and_then(|meta| crate::rate_limit::expand(meta, item))
+            .unwrap(),
+    );
+}
+
+#[test]
+fn non_reentrant() {
+    use darling::FromMeta;
+
+    let attr: syn::Meta = syn::parse_str(r#"non_reentrant(scope = "withdraw")"#).unwrap();
+    let item =
+        syn::parse_str("pub fn withdraw(&mut self, amount: U128) -> Promise { transfer(amount) }")
+            .unwrap();
+
+    assert_snapshot(
+        "non_reentrant",
+        crate::reentrancy::NonReentrantMeta::from_meta(&attr)
+            .and_then(|meta| crate::reentrancy::expand(meta, item))
+            .unwrap(),
+    );
+}
+
+#[test]
+fn migrate() {
+    assert_snapshot(
+        "migrate",
+        derive(
+            r#"#[derive(Migrate)] #[migrate(from = "ContractV1")] #[near_bindgen] struct Contract {}"#,
+            "migrate",
+            crate::migrate::expand,
+        ),
+    );
+}
+
+#[test]
+fn migrate_chain() {
+    assert_snapshot(
+        "migrate_chain",
+        derive(
+            "#[derive(MigrateChain)] #[migrate_chain(versions(ContractV1, ContractV2))] #[near_bindgen] struct Contract {}",
+            "migrate_chain",
+            crate::migrate_chain::expand,
+        ),
+    );
+}
+
+#[test]
+fn simple_multisig() {
+    assert_snapshot(
+        "simple_multisig",
+        derive(
+            r#"#[derive(SimpleMultisig)] #[simple_multisig(action = "Action", role = "Role::Multisig", external)] #[near_bindgen] struct Contract {}"#,
+            "simple_multisig",
+            crate::approval::simple_multisig::expand,
+        ),
+    );
+}
+
+#[test]
+fn upgrade() {
+    assert_snapshot(
+        "upgrade",
+        derive(
+            r#"#[derive(Upgrade)] #[upgrade(hook = "owner", staged, rollback)] #[near_bindgen] struct Contract {}"#,
+            "upgrade",
+            crate::upgrade::expand,
+        ),
+    );
+}
+
+#[test]
+fn contract_tools() {
+    use darling::FromMeta;
+
+    let attr: syn::Meta =
+        syn::parse_str(r#"contract_tools(owner(skip = "own_get_owner"), pause)"#).unwrap();
+    let item = syn::parse_str("#[near_bindgen] struct Contract {}").unwrap();
+
+    assert_snapshot(
+        "contract_tools",
+        crate::contract_tools::ContractToolsMeta::from_meta(&attr)
+            .and_then(|meta| crate::contract_tools::expand(meta, item))
+            .unwrap(),
+    );
+}
@@ -5,22 +5,26 @@ use syn::Item;
 
 use crate::rename::RenameStrategy;
 
+/// Options of the `#[event(...)]` attribute
 #[derive(Debug, FromMeta)]
 pub struct EventAttributeMeta {
-    pub standard: String,
-    pub version: String,
-    pub rename: Option<RenameStrategy>,
-    pub rename_all: Option<RenameStrategy>,
-    pub name: Option<String>,
+    pub(crate) standard: String,
+    pub(crate) version: String,
+    pub(crate) rename: Option<RenameStrategy>,
+    pub(crate) rename_all: Option<RenameStrategy>,
+    pub(crate) name: Option<String>,
 
     #[darling(rename = "crate", default = "crate::default_crate_name")]
-    pub me: syn::Path,
+    pub(crate) me: syn::Path,
     #[darling(default = "crate::default_macros")]
-    pub macros: syn::Path,
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
     #[darling(default = "crate::default_serde")]
-    pub serde: syn::Path,
+    pub(crate) serde: syn::Path,
 }
 
+/// Expands to `item` with the `Nep297` derive and its serialization derives
 pub fn event_attribute(
     attr: EventAttributeMeta,
     item: Item,
@@ -34,6 +38,7 @@ pub fn event_attribute(
         serde,
         me,
         macros,
+        near_sdk,
     } = attr;
 
     let serde_untagged = matches!(item, Item::Enum(_)).then_some(quote! { #[serde(untagged)] });
@@ -61,11 +66,15 @@ pub fn event_attribute(
 
     let serde_str = quote! { #serde }.to_string();
     let me_str = quote! { #me }.to_string();
+    let macros_str = quote! { #macros }.to_string();
+    let near_sdk_str = quote! { #near_sdk }.to_string();
 
     Ok(quote::quote! {
         #[derive(#macros::Nep297, #serde::Serialize)]
         #[nep297(
             crate = #me_str,
+            macros = #macros_str,
+            near_sdk = #near_sdk_str,
             standard = #standard,
             version = #version,
             #rename #rename_all #default_rename #name
@@ -0,0 +1,225 @@
+use darling::{
+    util::{Flag, Override},
+    FromDeriveInput,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use super::{nep141, nep148};
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `FungibleToken` derive, read from `#[fungible_token(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(fungible_token), supports(struct_named, enum_any))]
+pub struct FungibleTokenMeta {
+    // Storage keys
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) core_storage_key: Option<Expr>,
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) metadata_storage_key: Option<Expr>,
+
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+
+    // NEP-141 fields
+    pub(crate) no_hooks: Flag,
+    pub(crate) hashed_keys: Option<Override<usize>>,
+    pub(crate) max_key_len: Option<usize>,
+
+    // NEP-148 fields
+    pub(crate) spec: Option<String>,
+    pub(crate) name: String,
+    pub(crate) symbol: String,
+    pub(crate) icon: Option<String>,
+    pub(crate) reference: Option<String>,
+    pub(crate) reference_hash: Option<String>,
+    pub(crate) decimals: u8,
+
+    // darling
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the NEP-141 and NEP-148 implementations
+pub fn expand(meta: FungibleTokenMeta) -> Result<TokenStream, darling::Error> {
+    let FungibleTokenMeta {
+        storage_key,
+        core_storage_key,
+        metadata_storage_key,
+
+        mut method_attrs,
+        mut skip,
+
+        no_hooks,
+        hashed_keys,
+        max_key_len,
+
+        spec,
+        name,
+        symbol,
+        icon,
+        reference,
+        reference_hash,
+        decimals,
+
+        generics,
+        cfg,
+        method_docs,
+        ident,
+
+        me,
+        macros,
+        near_sdk,
+    } = meta;
+
+    let mut e = darling::Error::accumulator();
+
+    for (key, name) in [
+        (&core_storage_key, "core_storage_key"),
+        (&metadata_storage_key, "metadata_storage_key"),
+    ] {
+        if storage_key.is_some() && key.is_some() {
+            e.push(
+                darling::Error::custom(format!(
+                    "`storage_key` and `{name}` are mutually exclusive",
+                ))
+                .with_span(key),
+            );
+        }
+    }
+
+    // The shared key keeps its meaning from before the parts could be keyed
+    // separately: it is the NEP-141 root, and the NEP-148 root is nested
+    // under it.
+    let metadata_method_attrs = method_attrs.take(&["ft_metadata"]);
+    let metadata_skip = skip.take(&["ft_metadata"]);
+
+    let expand_nep141 = nep141::expand(nep141::Nep141Meta {
+        storage_key: core_storage_key.or_else(|| storage_key.clone()),
+        no_hooks,
+        hashed_keys,
+        max_key_len,
+        method_attrs,
+        skip,
+        cfg: vec![],
+        method_docs: Default::default(),
+
+        generics: generics.clone(),
+        ident: ident.clone(),
+
+        me: me.clone(),
+        macros: macros.clone(),
+        near_sdk: near_sdk.clone(),
+    });
+
+    let expand_nep148 = nep148::expand(nep148::Nep148Meta {
+        storage_key: metadata_storage_key,
+        shared_storage_key: storage_key,
+        method_attrs: metadata_method_attrs,
+        skip: metadata_skip,
+        cfg: vec![],
+        method_docs: Default::default(),
+
+        spec,
+        name,
+        symbol,
+        icon,
+        reference,
+        reference_hash,
+        decimals,
+
+        generics: generics.clone(),
+        ident,
+
+        me,
+        macros,
+        near_sdk,
+    });
+
+    let nep141 = e.handle(expand_nep141);
+    let nep148 = e.handle(expand_nep148);
+
+    e.finish()?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #nep141
+            #nep148
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::FungibleTokenMeta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(FungibleToken)]
+            #[fungible_token(
+                name = "Token",
+                symbol = "TKN",
+                decimals = 18,
+                method_attrs(ft_resolve_transfer = "payable", ft_metadata = "private"),
+            )]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = FungibleTokenMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [private] # [payable] fn ft_resolve_transfer ("));
+        assert!(expanded.contains("# [private] fn ft_metadata ("));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(FungibleToken)]
+            #[fungible_token(
+                name = "Token",
+                symbol = "TKN",
+                decimals = 18,
+                skip = "ft_transfer_call, ft_metadata",
+            )]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = FungibleTokenMeta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn ft_transfer_call ("));
+        assert!(!expanded.contains("fn ft_metadata ("));
+        assert!(expanded.contains("pub fn ft_transfer ("));
+        assert!(expanded.contains("Nep148Controller for Contract"));
+    }
+}
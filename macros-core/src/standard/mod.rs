@@ -0,0 +1,17 @@
+//! Implementations of NEAR standards
+
+/// `#[event]` attribute
+pub mod event;
+/// `FungibleToken` derive
+pub mod fungible_token;
+
+/// `Nep141` derive
+pub mod nep141;
+/// `Nep148` derive
+pub mod nep148;
+/// `Nep245` derive
+pub mod nep245;
+/// `Nep297` derive
+pub mod nep297;
+/// `ContractMetadata` derive (NEP-330)
+pub mod nep330;
@@ -0,0 +1,368 @@
+use std::ops::Not;
+
+use darling::{
+    util::{Flag, Override},
+    FromDeriveInput,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `Nep141` derive, read from `#[nep141(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(nep141), supports(struct_named, enum_any))]
+pub struct Nep141Meta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) no_hooks: Flag,
+    pub(crate) hashed_keys: Option<Override<usize>>,
+    pub(crate) max_key_len: Option<usize>,
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Nep141Controller`, `Nep141`, and `Nep141Resolver`
+/// implementations
+pub fn expand(meta: Nep141Meta) -> Result<TokenStream, darling::Error> {
+    let Nep141Meta {
+        storage_key,
+        no_hooks,
+        hashed_keys,
+        max_key_len,
+        method_attrs,
+        skip,
+        generics,
+        cfg,
+        method_docs,
+        ident,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+    let payable = crate::bindgen_attr(&generics, quote! { #[payable] });
+    let private = crate::bindgen_attr(&generics, quote! { #[private] });
+
+    skip.check(
+        &[
+            "ft_transfer",
+            "ft_transfer_call",
+            "ft_total_supply",
+            "ft_balance_of",
+            "ft_resolve_transfer",
+        ],
+        &method_attrs,
+    )?;
+    method_attrs.check(
+        &generics,
+        &[
+            ("ft_transfer", &["payable"]),
+            ("ft_transfer_call", &["payable"]),
+            ("ft_total_supply", &[]),
+            ("ft_balance_of", &[]),
+            ("ft_resolve_transfer", &["private"]),
+        ],
+    )?;
+    let attrs = |method| method_attrs.get(method);
+    let ft_transfer = attrs("ft_transfer");
+    let ft_transfer_call = attrs("ft_transfer_call");
+    let ft_total_supply = attrs("ft_total_supply");
+    let ft_balance_of = attrs("ft_balance_of");
+    let ft_resolve_transfer = attrs("ft_resolve_transfer");
+
+    let hashed_key_len = hashed_keys
+        .map(|len| match len {
+            Override::Inherit => Ok(quote! { #me::slot::HASHED_KEY_LEN }),
+            Override::Explicit(len) if (1..=32).contains(&len) => Ok(quote! { #len }),
+            Override::Explicit(_) => Err(darling::Error::custom(
+                "Hashed key length must be between 1 and 32",
+            )),
+        })
+        .transpose()?;
+
+    let key_len_check = crate::key_len::check(
+        max_key_len,
+        storage_key.as_ref(),
+        quote! { #me::DefaultStorageKey::Nep141 },
+        match &hashed_key_len {
+            Some(len) => quote! { #me::standard::nep141::max_key_suffix_len(Some(#len)) },
+            None => quote! { #me::standard::nep141::max_key_suffix_len(None) },
+        },
+        "Nep141",
+        &ident,
+    );
+    let key_len_constant = key_len_check.constant;
+    let key_len_runtime = key_len_check.runtime;
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                let root = #me::slot::Slot::root(#storage_key);
+                #key_len_runtime
+                root
+            }
+        }
+    });
+
+    let hashed_key_len = hashed_key_len.map(|len| {
+        quote! {
+            fn hashed_key_len() -> Option<usize> {
+                Some(#len)
+            }
+        }
+    });
+
+    let before_transfer = no_hooks.is_present().not().then(|| {
+        quote! {
+            let hook_state = <Self as #me::standard::nep141::Nep141Hook::<_>>::before_transfer(self, &transfer);
+        }
+    });
+
+    let after_transfer = no_hooks.is_present().not().then(|| {
+        quote! {
+            <Self as #me::standard::nep141::Nep141Hook::<_>>::after_transfer(self, &transfer, hook_state);
+        }
+    });
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen.clone(),
+        &generics,
+        &ident,
+        quote! { #me::standard::nep141::Nep141 },
+        vec![
+            (
+                "ft_transfer",
+                quote! {
+                    /// Transfers `amount` tokens from the caller to `receiver_id`, with an
+                    /// optional `memo` (NEP-141). Requires a deposit of exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if the balance of the caller is less than `amount`.
+                    #payable
+                    #ft_transfer
+                    fn ft_transfer(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        amount: #near_sdk::json_types::U128,
+                        memo: Option<String>,
+                    ) {
+                        let transfer =
+                            #me::standard::nep141::Nep141Transfer::from_predecessor(receiver_id, amount, memo);
+
+                        #before_transfer
+
+                        #me::standard::nep141::Nep141Controller::transfer(
+                            self,
+                            transfer.sender_id.clone(),
+                            transfer.receiver_id.clone(),
+                            transfer.amount,
+                            transfer.memo.clone(),
+                        );
+
+                        #after_transfer
+                    }
+                },
+            ),
+            (
+                "ft_transfer_call",
+                quote! {
+                    /// Transfers `amount` tokens from the caller to `receiver_id`, with an
+                    /// optional `memo`, then calls `ft_on_transfer` on `receiver_id` with
+                    /// `msg` (NEP-141). Tokens that the receiver does not use are refunded.
+                    /// Requires a deposit of exactly 1 yoctoNEAR. Returns the amount of tokens
+                    /// used.
+                    ///
+                    /// Panics if the balance of the caller is less than `amount`, or if not
+                    /// enough gas is attached for the call and its callback.
+                    #payable
+                    #ft_transfer_call
+                    fn ft_transfer_call(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        amount: #near_sdk::json_types::U128,
+                        memo: Option<String>,
+                        msg: String,
+                    ) -> #near_sdk::Promise {
+                        let transfer =
+                            #me::standard::nep141::Nep141Transfer::from_predecessor(receiver_id, amount, memo);
+
+                        #before_transfer
+
+                        let r = #me::standard::nep141::Nep141Controller::transfer_call(
+                            self,
+                            transfer.sender_id.clone(),
+                            transfer.receiver_id.clone(),
+                            transfer.amount,
+                            transfer.memo.clone(),
+                            msg,
+                            #near_sdk::env::prepaid_gas(),
+                        );
+
+                        #after_transfer
+
+                        r
+                    }
+                },
+            ),
+            (
+                "ft_total_supply",
+                quote! {
+                    /// Returns the total supply of the token (NEP-141).
+                    #ft_total_supply
+                    fn ft_total_supply(&self) -> #near_sdk::json_types::U128 {
+                        <Self as #me::standard::nep141::Nep141Controller>::total_supply().into()
+                    }
+                },
+            ),
+            (
+                "ft_balance_of",
+                quote! {
+                    /// Returns the balance of `account_id` (NEP-141).
+                    #ft_balance_of
+                    fn ft_balance_of(&self, account_id: #near_sdk::AccountId) -> #near_sdk::json_types::U128 {
+                        <Self as #me::standard::nep141::Nep141Controller>::balance_of(&account_id).into()
+                    }
+                },
+            ),
+        ],
+        &skip,
+    )?;
+
+    let resolver_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::standard::nep141::Nep141Resolver },
+        vec![(
+            "ft_resolve_transfer",
+            quote! {
+                /// Callback of `ft_transfer_call`, which refunds the tokens that the
+                /// receiver did not use to `sender_id`. Returns the amount of tokens used.
+                /// Can only be called by the contract itself.
+                #private
+                #ft_resolve_transfer
+                fn ft_resolve_transfer(
+                    &mut self,
+                    sender_id: #near_sdk::AccountId,
+                    receiver_id: #near_sdk::AccountId,
+                    amount: #near_sdk::json_types::U128,
+                ) -> #near_sdk::json_types::U128 {
+                    #me::standard::nep141::Nep141Controller::resolve_transfer(
+                        self,
+                        sender_id,
+                        receiver_id,
+                        amount.into(),
+                    ).into()
+                }
+            },
+        )],
+        &skip,
+    )?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #key_len_constant
+
+            impl #imp #me::standard::nep330::ImplementsStandard<#me::standard::nep330::standards::Nep141> for #ident #ty #wher {}
+
+            impl #imp #me::standard::nep141::Nep141Controller for #ident #ty #wher {
+                #root
+                #hashed_key_len
+            }
+
+            #external_impl
+            #resolver_impl
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::Nep141Meta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep141)]
+            #[nep141(method_attrs(ft_transfer = "private", ft_balance_of = "private"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep141Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [payable] # [private] fn ft_transfer ("));
+        assert!(expanded.contains("# [private] fn ft_balance_of ("));
+        assert!(expanded.contains("# [payable] fn ft_transfer_call ("));
+        assert!(expanded.contains("# [private] fn ft_resolve_transfer ("));
+    }
+
+    #[test]
+    fn docs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep141)]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep141Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains(
+            r#"# [doc = r" Returns the total supply of the token (NEP-141)."] fn ft_total_supply ("#
+        ));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep141)]
+            #[nep141(skip = "ft_transfer_call")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep141Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn ft_transfer_call ("));
+        assert!(expanded.contains("# [payable] pub fn ft_transfer ("));
+        assert!(expanded.contains("pub fn ft_balance_of ("));
+        // Not skipped, so still implemented as a trait
+        assert!(expanded.contains("Nep141Resolver for Contract"));
+        assert!(expanded.contains("# [private] fn ft_resolve_transfer ("));
+    }
+}
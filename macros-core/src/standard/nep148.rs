@@ -0,0 +1,204 @@
+use darling::{FromDeriveInput, ToTokens};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `Nep148` derive, read from `#[nep148(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(nep148), supports(struct_named, enum_any))]
+pub struct Nep148Meta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    /// Root shared with NEP-141 (`#[fungible_token(storage_key = ...)]`),
+    /// under which the NEP-148 root is nested
+    #[darling(skip)]
+    pub(crate) shared_storage_key: Option<Expr>,
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+
+    pub(crate) spec: Option<String>,
+    pub(crate) name: String,
+    pub(crate) symbol: String,
+    pub(crate) icon: Option<String>,
+    pub(crate) reference: Option<String>,
+    pub(crate) reference_hash: Option<String>,
+    pub(crate) decimals: u8,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+fn optionize<T>(t: Option<T>) -> TokenStream
+where
+    T: ToTokens,
+{
+    t.map_or_else(|| quote! { None }, |v| quote! { Some(#v) })
+}
+
+/// Expands to the `Nep148` implementation
+pub fn expand(meta: Nep148Meta) -> Result<TokenStream, darling::Error> {
+    let Nep148Meta {
+        generics,
+        cfg,
+        method_docs,
+        ident,
+        // fields
+        storage_key,
+        shared_storage_key,
+        method_attrs,
+        skip,
+        spec,
+        name,
+        symbol,
+        icon,
+        reference,
+        reference_hash,
+        decimals,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let spec = spec.map(|s| s.to_token_stream()).unwrap_or_else(|| {
+        quote! {
+            #me::standard::nep148::FT_METADATA_SPEC
+        }
+    });
+
+    let icon = optionize(icon);
+    let reference = optionize(reference);
+
+    // TODO: Download reference field at compile time and calculate reference_hash automatically
+    let reference_hash = optionize(reference_hash.map(|s| {
+        let v = format!("{:?}", base64::decode(s).unwrap())
+            .parse::<quote::__private::TokenStream>()
+            .unwrap();
+
+        quote! { #near_sdk::json_types::Base64VecU8::from(#v.to_vec()) }
+    }));
+
+    let (imp, ty, wher) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+
+    skip.check(&["ft_metadata"], &method_attrs)?;
+    method_attrs.check(&generics, &[("ft_metadata", &[])])?;
+    let ft_metadata = method_attrs.get("ft_metadata");
+
+    let root = match (storage_key, shared_storage_key) {
+        (Some(storage_key), _) => Some(quote! {
+            #me::slot::Slot::root(#storage_key)
+        }),
+        (None, Some(shared_storage_key)) => Some(quote! {
+            #me::slot::Slot::<()>::new(#shared_storage_key).ns(#me::DefaultStorageKey::Nep148)
+        }),
+        (None, None) => None,
+    }
+    .map(|root| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                #root
+            }
+        }
+    });
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::standard::nep148::Nep148 },
+        vec![(
+            "ft_metadata",
+            quote! {
+                /// Returns the NEP-148 metadata of the token.
+                #ft_metadata
+                fn ft_metadata(&self) -> #me::standard::nep148::FungibleTokenMetadata {
+                    #me::standard::nep148::FungibleTokenMetadata {
+                        spec: #spec.into(),
+                        name: #name.into(),
+                        symbol: #symbol.into(),
+                        icon: #icon.map(|s: &str| s.into()),
+                        reference: #reference.map(|s: &str| s.into()),
+                        reference_hash: #reference_hash,
+                        decimals: #decimals,
+                    }
+                }
+            },
+        )],
+        &skip,
+    )?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            impl #imp #me::standard::nep330::ImplementsStandard<#me::standard::nep330::standards::Nep148> for #ident #ty #wher {}
+
+            impl #imp #me::standard::nep148::Nep148Controller for #ident #ty #wher {
+                #root
+            }
+
+            #external_impl
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::Nep148Meta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep148)]
+            #[nep148(name = "Token", symbol = "TKN", decimals = 18, method_attrs(ft_metadata = "private"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep148Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [private] fn ft_metadata ("));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep148)]
+            #[nep148(name = "Token", symbol = "TKN", decimals = 18, skip = "ft_metadata")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep148Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn ft_metadata ("));
+        assert!(expanded.contains("Nep148Controller for Contract"));
+    }
+}
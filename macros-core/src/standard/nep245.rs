@@ -0,0 +1,458 @@
+use std::ops::Not;
+
+use darling::{util::Flag, FromDeriveInput};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Options of the `Nep245` derive, read from `#[nep245(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(nep245), supports(struct_named, enum_any))]
+pub struct Nep245Meta {
+    #[darling(default, with = "crate::storage_key::parse")]
+    pub(crate) storage_key: Option<Expr>,
+    pub(crate) no_hooks: Flag,
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Nep245Controller`, `Nep245`, and `Nep245Resolver`
+/// implementations
+pub fn expand(meta: Nep245Meta) -> Result<TokenStream, darling::Error> {
+    let Nep245Meta {
+        storage_key,
+        no_hooks,
+        method_attrs,
+        skip,
+        generics,
+        cfg,
+        method_docs,
+        ident,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+    let payable = crate::bindgen_attr(&generics, quote! { #[payable] });
+    let private = crate::bindgen_attr(&generics, quote! { #[private] });
+
+    skip.check(
+        &[
+            "mt_transfer",
+            "mt_batch_transfer",
+            "mt_transfer_call",
+            "mt_batch_transfer_call",
+            "mt_balance_of",
+            "mt_batch_balance_of",
+            "mt_total_supply",
+            "mt_resolve_transfer",
+        ],
+        &method_attrs,
+    )?;
+    method_attrs.check(
+        &generics,
+        &[
+            ("mt_transfer", &["payable"]),
+            ("mt_batch_transfer", &["payable"]),
+            ("mt_transfer_call", &["payable"]),
+            ("mt_batch_transfer_call", &["payable"]),
+            ("mt_balance_of", &[]),
+            ("mt_batch_balance_of", &[]),
+            ("mt_total_supply", &[]),
+            ("mt_resolve_transfer", &["private"]),
+        ],
+    )?;
+    let attrs = |method| method_attrs.get(method);
+    let mt_transfer = attrs("mt_transfer");
+    let mt_batch_transfer = attrs("mt_batch_transfer");
+    let mt_transfer_call = attrs("mt_transfer_call");
+    let mt_batch_transfer_call = attrs("mt_batch_transfer_call");
+    let mt_balance_of = attrs("mt_balance_of");
+    let mt_batch_balance_of = attrs("mt_batch_balance_of");
+    let mt_total_supply = attrs("mt_total_supply");
+    let mt_resolve_transfer = attrs("mt_resolve_transfer");
+
+    let root = storage_key.map(|storage_key| {
+        quote! {
+            fn root() -> #me::slot::Slot<()> {
+                #me::slot::Slot::root(#storage_key)
+            }
+        }
+    });
+
+    let before_transfer = no_hooks.is_present().not().then(|| {
+        quote! {
+            let hook_state = <Self as #me::standard::nep245::Nep245Hook::<_>>::before_transfer(self, &transfer);
+        }
+    });
+
+    let after_transfer = no_hooks.is_present().not().then(|| {
+        quote! {
+            <Self as #me::standard::nep245::Nep245Hook::<_>>::after_transfer(self, &transfer, hook_state);
+        }
+    });
+
+    let do_transfer = quote! {
+        #before_transfer
+
+        #me::standard::nep245::Nep245Controller::transfer(
+            self,
+            transfer.sender_id.clone(),
+            transfer.receiver_id.clone(),
+            transfer.tokens.clone(),
+            transfer.memo.clone(),
+        );
+
+        #after_transfer
+    };
+
+    let do_transfer_call = quote! {
+        #before_transfer
+
+        let r = #me::standard::nep245::Nep245Controller::transfer_call(
+            self,
+            transfer.sender_id.clone(),
+            transfer.receiver_id.clone(),
+            transfer.tokens.clone(),
+            transfer.memo.clone(),
+            msg,
+            #near_sdk::env::prepaid_gas(),
+        );
+
+        #after_transfer
+
+        r
+    };
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen.clone(),
+        &generics,
+        &ident,
+        quote! { #me::standard::nep245::Nep245 },
+        vec![
+            (
+                "mt_transfer",
+                quote! {
+                    /// Transfers `amount` of the token `token_id` from the caller to
+                    /// `receiver_id`, with an optional `memo` (NEP-245). Requires a deposit of
+                    /// exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if `approval` is set (approvals are not supported), or if the
+                    /// balance of the caller is less than `amount`.
+                    #payable
+                    #mt_transfer
+                    fn mt_transfer(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        token_id: #me::standard::nep245::TokenId,
+                        amount: #near_sdk::json_types::U128,
+                        approval: Option<(#near_sdk::AccountId, u64)>,
+                        memo: Option<String>,
+                    ) {
+                        #near_sdk::require!(
+                            approval.is_none(),
+                            #me::standard::nep245::APPROVALS_UNSUPPORTED_FAIL_MESSAGE,
+                        );
+
+                        let transfer = #me::standard::nep245::Nep245Transfer::from_predecessor(
+                            receiver_id,
+                            vec![token_id],
+                            vec![amount],
+                            memo,
+                        );
+
+                        #do_transfer
+                    }
+                },
+            ),
+            (
+                "mt_batch_transfer",
+                quote! {
+                    /// Transfers `amounts` of the tokens `token_ids` from the caller to
+                    /// `receiver_id`, with an optional `memo` (NEP-245). Requires a deposit of
+                    /// exactly 1 yoctoNEAR.
+                    ///
+                    /// Panics if `approvals` is set (approvals are not supported), if
+                    /// `token_ids` and `amounts` differ in length, or if the balance of the
+                    /// caller of any of the tokens is less than its amount.
+                    #payable
+                    #mt_batch_transfer
+                    fn mt_batch_transfer(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        token_ids: Vec<#me::standard::nep245::TokenId>,
+                        amounts: Vec<#near_sdk::json_types::U128>,
+                        approvals: Option<Vec<Option<(#near_sdk::AccountId, u64)>>>,
+                        memo: Option<String>,
+                    ) {
+                        #near_sdk::require!(
+                            approvals.is_none(),
+                            #me::standard::nep245::APPROVALS_UNSUPPORTED_FAIL_MESSAGE,
+                        );
+
+                        let transfer = #me::standard::nep245::Nep245Transfer::from_predecessor(
+                            receiver_id,
+                            token_ids,
+                            amounts,
+                            memo,
+                        );
+
+                        #do_transfer
+                    }
+                },
+            ),
+            (
+                "mt_transfer_call",
+                quote! {
+                    /// Transfers `amount` of the token `token_id` from the caller to
+                    /// `receiver_id`, with an optional `memo`, then calls `mt_on_transfer` on
+                    /// `receiver_id` with `msg` (NEP-245). Tokens that the receiver does not
+                    /// use are refunded. Requires a deposit of exactly 1 yoctoNEAR. Returns the
+                    /// amount of the token used, as a list of one amount.
+                    ///
+                    /// Panics if `approval` is set (approvals are not supported), if the
+                    /// balance of the caller is less than `amount`, or if not enough gas is
+                    /// attached for the call and its callback.
+                    #payable
+                    #mt_transfer_call
+                    fn mt_transfer_call(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        token_id: #me::standard::nep245::TokenId,
+                        amount: #near_sdk::json_types::U128,
+                        approval: Option<(#near_sdk::AccountId, u64)>,
+                        memo: Option<String>,
+                        msg: String,
+                    ) -> #near_sdk::Promise {
+                        #near_sdk::require!(
+                            approval.is_none(),
+                            #me::standard::nep245::APPROVALS_UNSUPPORTED_FAIL_MESSAGE,
+                        );
+
+                        let mut transfer = #me::standard::nep245::Nep245Transfer::from_predecessor(
+                            receiver_id,
+                            vec![token_id],
+                            vec![amount],
+                            memo,
+                        );
+                        transfer.msg = Some(msg.clone());
+
+                        #do_transfer_call
+                    }
+                },
+            ),
+            (
+                "mt_batch_transfer_call",
+                quote! {
+                    /// Transfers `amounts` of the tokens `token_ids` from the caller to
+                    /// `receiver_id`, with an optional `memo`, then calls `mt_on_transfer` on
+                    /// `receiver_id` with `msg` (NEP-245). Tokens that the receiver does not
+                    /// use are refunded. Requires a deposit of exactly 1 yoctoNEAR. Returns the
+                    /// amount of each token used.
+                    ///
+                    /// Panics if `approvals` is set (approvals are not supported), if
+                    /// `token_ids` and `amounts` differ in length, if the balance of the caller
+                    /// of any of the tokens is less than its amount, or if not enough gas is
+                    /// attached for the call and its callback.
+                    #payable
+                    #mt_batch_transfer_call
+                    fn mt_batch_transfer_call(
+                        &mut self,
+                        receiver_id: #near_sdk::AccountId,
+                        token_ids: Vec<#me::standard::nep245::TokenId>,
+                        amounts: Vec<#near_sdk::json_types::U128>,
+                        approvals: Option<Vec<Option<(#near_sdk::AccountId, u64)>>>,
+                        memo: Option<String>,
+                        msg: String,
+                    ) -> #near_sdk::Promise {
+                        #near_sdk::require!(
+                            approvals.is_none(),
+                            #me::standard::nep245::APPROVALS_UNSUPPORTED_FAIL_MESSAGE,
+                        );
+
+                        let mut transfer = #me::standard::nep245::Nep245Transfer::from_predecessor(
+                            receiver_id,
+                            token_ids,
+                            amounts,
+                            memo,
+                        );
+                        transfer.msg = Some(msg.clone());
+
+                        #do_transfer_call
+                    }
+                },
+            ),
+            (
+                "mt_balance_of",
+                quote! {
+                    /// Returns the balance of the token `token_id` of `account_id` (NEP-245).
+                    #mt_balance_of
+                    fn mt_balance_of(
+                        &self,
+                        account_id: #near_sdk::AccountId,
+                        token_id: #me::standard::nep245::TokenId,
+                    ) -> #near_sdk::json_types::U128 {
+                        <Self as #me::standard::nep245::Nep245Controller>::balance_of(&token_id, &account_id).into()
+                    }
+                },
+            ),
+            (
+                "mt_batch_balance_of",
+                quote! {
+                    /// Returns the balance of each of the tokens `token_ids` of `account_id`
+                    /// (NEP-245).
+                    #mt_batch_balance_of
+                    fn mt_batch_balance_of(
+                        &self,
+                        account_id: #near_sdk::AccountId,
+                        token_ids: Vec<#me::standard::nep245::TokenId>,
+                    ) -> Vec<#near_sdk::json_types::U128> {
+                        token_ids
+                            .iter()
+                            .map(|token_id| {
+                                <Self as #me::standard::nep245::Nep245Controller>::balance_of(token_id, &account_id).into()
+                            })
+                            .collect()
+                    }
+                },
+            ),
+            (
+                "mt_total_supply",
+                quote! {
+                    /// Returns the total supply of the token `token_id` (NEP-245).
+                    #mt_total_supply
+                    fn mt_total_supply(
+                        &self,
+                        token_id: #me::standard::nep245::TokenId,
+                    ) -> #near_sdk::json_types::U128 {
+                        <Self as #me::standard::nep245::Nep245Controller>::total_supply(&token_id).into()
+                    }
+                },
+            ),
+        ],
+        &skip,
+    )?;
+
+    let resolver_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::standard::nep245::Nep245Resolver },
+        vec![(
+            "mt_resolve_transfer",
+            quote! {
+                /// Callback of `mt_transfer_call` and `mt_batch_transfer_call`, which
+                /// refunds the tokens that the receiver did not use to their previous
+                /// owners. Returns the amount of each token used. Can only be called by
+                /// the contract itself.
+                #private
+                #mt_resolve_transfer
+                fn mt_resolve_transfer(
+                    &mut self,
+                    previous_owner_ids: Vec<#near_sdk::AccountId>,
+                    receiver_id: #near_sdk::AccountId,
+                    token_ids: Vec<#me::standard::nep245::TokenId>,
+                    amounts: Vec<#near_sdk::json_types::U128>,
+                ) -> Vec<#near_sdk::json_types::U128> {
+                    #me::standard::nep245::Nep245Controller::resolve_transfer(
+                        self,
+                        previous_owner_ids,
+                        receiver_id,
+                        token_ids
+                            .into_iter()
+                            .zip(amounts.into_iter().map(u128::from))
+                            .collect(),
+                    )
+                    .into_iter()
+                    .map(Into::into)
+                    .collect()
+                }
+            },
+        )],
+        &skip,
+    )?;
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            impl #imp #me::standard::nep330::ImplementsStandard<#me::standard::nep330::standards::Nep245> for #ident #ty #wher {}
+
+            impl #imp #me::standard::nep245::Nep245Controller for #ident #ty #wher {
+                #root
+            }
+
+            #external_impl
+            #resolver_impl
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::Nep245Meta;
+
+    #[test]
+    fn method_attrs() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep245)]
+            #[nep245(method_attrs(mt_transfer = "private", mt_balance_of = "private"))]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep245Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains("# [payable] # [private] fn mt_transfer ("));
+        assert!(expanded.contains("# [private] fn mt_balance_of ("));
+        assert!(expanded.contains("# [payable] fn mt_batch_transfer_call ("));
+        assert!(expanded.contains("# [private] fn mt_resolve_transfer ("));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(Nep245)]
+            #[nep245(skip = "mt_batch_transfer_call")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep245Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn mt_batch_transfer_call ("));
+        assert!(expanded.contains("# [payable] pub fn mt_transfer_call ("));
+        assert!(expanded.contains("Nep245Resolver for Contract"));
+    }
+}
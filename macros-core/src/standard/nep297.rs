@@ -6,6 +6,7 @@ use quote::quote;
 
 use crate::rename::RenameStrategy;
 
+/// Options of the `Nep297` derive, read from `#[nep297(...)]`
 #[derive(Debug, FromDeriveInput)]
 #[darling(
     attributes(nep297),
@@ -13,18 +14,24 @@ use crate::rename::RenameStrategy;
     and_then = "Self::check"
 )]
 pub struct Nep297Meta {
-    pub standard: String,
-    pub version: String,
-    pub name: Option<String>,
-    pub rename: Option<RenameStrategy>,
-    pub rename_all: Option<RenameStrategy>,
-    pub ident: syn::Ident,
-    pub generics: syn::Generics,
-    pub data: darling::ast::Data<EventVariantReceiver, ()>,
+    pub(crate) standard: String,
+    pub(crate) version: String,
+    pub(crate) name: Option<String>,
+    pub(crate) rename: Option<RenameStrategy>,
+    pub(crate) rename_all: Option<RenameStrategy>,
+    pub(crate) ident: syn::Ident,
+    pub(crate) generics: syn::Generics,
+    pub(crate) data: darling::ast::Data<EventVariantReceiver, ()>,
 
     // crates
     #[darling(rename = "crate", default = "crate::default_crate_name")]
-    pub me: syn::Path,
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
 }
 
 macro_rules! disallow_field {
@@ -40,7 +47,7 @@ macro_rules! disallow_field {
 }
 
 impl Nep297Meta {
-    pub fn check(self) -> darling::Result<Self> {
+    pub(crate) fn check(self) -> darling::Result<Self> {
         let mut e = darling::Error::accumulator();
 
         match &self.data {
@@ -59,13 +66,14 @@ impl Nep297Meta {
 
 #[derive(Debug, FromVariant)]
 #[darling(attributes(nep297))]
-pub struct EventVariantReceiver {
-    pub ident: syn::Ident,
-    pub fields: darling::ast::Fields<()>,
-    pub rename: Option<RenameStrategy>,
-    pub name: Option<String>,
+pub(crate) struct EventVariantReceiver {
+    pub(crate) ident: syn::Ident,
+    pub(crate) fields: darling::ast::Fields<()>,
+    pub(crate) rename: Option<RenameStrategy>,
+    pub(crate) name: Option<String>,
 }
 
+/// Expands to the `Event` implementation
 pub fn expand(meta: Nep297Meta) -> Result<TokenStream, darling::Error> {
     let Nep297Meta {
         standard,
@@ -77,6 +85,8 @@ pub fn expand(meta: Nep297Meta) -> Result<TokenStream, darling::Error> {
         generics,
         data,
         me,
+        macros: _,
+        near_sdk: _,
     } = meta;
 
     let (imp, ty, wher) = generics.split_for_impl();
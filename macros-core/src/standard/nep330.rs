@@ -0,0 +1,188 @@
+use darling::{FromDeriveInput, FromMeta};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{method_attrs::MethodAttrs, skip::Skip};
+
+/// Standards that component derives register with `ImplementsStandard`,
+/// which `contract_source_metadata` reports if the contract implements them.
+const COMPONENT_STANDARDS: &[&str] = &["Nep141", "Nep148", "Nep245"];
+
+/// A standard listed with `standard(standard = "...", version = "...")`
+#[derive(Debug, FromMeta)]
+pub(crate) struct StandardMeta {
+    standard: String,
+    version: String,
+}
+
+/// Options of the `ContractMetadata` derive, read from
+/// `#[contract_metadata(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(contract_metadata), supports(struct_named, enum_any))]
+pub struct Nep330Meta {
+    pub(crate) version: Option<String>,
+    pub(crate) link: Option<String>,
+    #[darling(multiple, rename = "standard")]
+    pub(crate) standards: Vec<StandardMeta>,
+
+    #[darling(default)]
+    pub(crate) method_attrs: MethodAttrs,
+    #[darling(default)]
+    pub(crate) skip: Skip,
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `Nep330` implementation
+pub fn expand(meta: Nep330Meta) -> Result<TokenStream, darling::Error> {
+    let Nep330Meta {
+        version,
+        link,
+        standards,
+        method_attrs,
+        skip,
+        cfg,
+        method_docs,
+        generics,
+        ident,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+
+    skip.check(&["contract_source_metadata"], &method_attrs)?;
+    method_attrs.check(&generics, &[("contract_source_metadata", &[])])?;
+    let contract_source_metadata = method_attrs.get("contract_source_metadata");
+
+    // Defaults are read from the manifest of the crate deriving the contract
+    let version = version.map_or_else(
+        || quote! { Some(::core::env!("CARGO_PKG_VERSION").into()) },
+        |version| quote! { Some(#version.into()) },
+    );
+    let link = link.map_or_else(
+        || {
+            quote! {
+                Some(::core::env!("CARGO_PKG_REPOSITORY"))
+                    .filter(|link: &&str| !link.is_empty())
+                    .map(Into::into)
+            }
+        },
+        |link| quote! { Some(#link.into()) },
+    );
+
+    let component_standards = COMPONENT_STANDARDS.iter().map(|name| {
+        let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+        quote! {
+            (&#me::standard::nep330::Probe::<Self, #me::standard::nep330::standards::#name>::new()).standard()
+        }
+    });
+
+    let listed_standards = (!standards.is_empty()).then(|| {
+        let standards = standards.iter().map(|StandardMeta { standard, version }| {
+            quote! { #me::standard::nep330::Standard::new(#standard, #version) }
+        });
+
+        quote! { standards.extend([#(#standards),*]); }
+    });
+
+    let external_impl = crate::externals::external_impl(
+        near_bindgen,
+        &generics,
+        &ident,
+        quote! { #me::standard::nep330::Nep330 },
+        vec![(
+            "contract_source_metadata",
+            quote! {
+                /// Returns the NEP-330 source metadata of the contract: the
+                /// version and location of its source code, and the standards
+                /// it implements.
+                #contract_source_metadata
+                fn contract_source_metadata(&self) -> #me::standard::nep330::ContractSourceMetadata {
+                    use #me::standard::nep330::{Registered as _, Unregistered as _};
+
+                    let mut standards = vec![#me::standard::nep330::Standard::new(
+                        "nep330",
+                        #me::standard::nep330::NEP330_VERSION,
+                    )];
+                    standards.extend([#(#component_standards),*].into_iter().flatten());
+                    #listed_standards
+
+                    #me::standard::nep330::ContractSourceMetadata {
+                        version: #version,
+                        link: #link,
+                        standards,
+                    }
+                }
+            },
+        )],
+        &skip,
+    )?;
+
+    crate::cfg::apply(&cfg, &generics, external_impl)
+        .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+
+    use super::Nep330Meta;
+
+    #[test]
+    fn listed_standards() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(ContractMetadata)]
+            #[contract_metadata(
+                version = "1.2.3",
+                standard(standard = "nep171", version = "1.1.0"),
+                standard(standard = "nep177", version = "2.0.0"),
+            )]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep330Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(expanded.contains(r#"Some ("1.2.3" . into ())"#));
+        assert!(expanded.contains(r#"Standard :: new ("nep171" , "1.1.0")"#));
+        assert!(expanded.contains(r#"Standard :: new ("nep177" , "2.0.0")"#));
+        assert!(expanded.contains(r#"env ! ("CARGO_PKG_REPOSITORY")"#));
+    }
+
+    #[test]
+    fn skip() {
+        let ast = syn::parse_str(
+            r#"
+            #[derive(ContractMetadata)]
+            #[contract_metadata(skip = "contract_source_metadata")]
+            struct Contract {}
+        "#,
+        )
+        .unwrap();
+
+        let meta = Nep330Meta::from_derive_input(&ast).unwrap();
+        let expanded = super::expand(meta).unwrap().to_string();
+
+        assert!(!expanded.contains("fn contract_source_metadata ("));
+    }
+}
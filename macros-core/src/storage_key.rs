@@ -0,0 +1,61 @@
+use darling::FromMeta;
+use syn::{parse_quote, Expr, Lit, Meta, MetaNameValue};
+
+/// Parses a `storage_key` option. The value is either a string containing an
+/// expression that implements `IntoStorageKey` (e.g. `"StorageKey::Owner"`, a
+/// path to a constant, or `"b\"k\".to_vec()"`), or a byte string literal
+/// (e.g. `b"k"`), which is used as a `&[u8]`.
+pub fn parse(meta: &Meta) -> darling::Result<Option<Expr>> {
+    match meta {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::ByteStr(bytes),
+            ..
+        }) => Ok(Some(parse_quote! { &#bytes[..] })),
+        Meta::NameValue(MetaNameValue {
+            lit: lit @ Lit::Str(_),
+            ..
+        }) => Expr::from_value(lit).map(Some),
+        _ => Err(darling::Error::custom(
+            "Expected a string containing an expression, or a byte string literal",
+        )
+        .with_span(meta)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::parse;
+
+    fn parse_str(s: &str) -> darling::Result<String> {
+        let meta: syn::Meta = syn::parse_str(s).unwrap();
+        parse(&meta).map(|e| e.unwrap().into_token_stream().to_string())
+    }
+
+    #[test]
+    fn expression() {
+        assert_eq!(
+            parse_str(r#"storage_key = "StorageKey::Owner""#).unwrap(),
+            "StorageKey :: Owner",
+        );
+        assert_eq!(
+            parse_str(r#"storage_key = "crate::keys::OWNER""#).unwrap(),
+            "crate :: keys :: OWNER",
+        );
+    }
+
+    #[test]
+    fn byte_string() {
+        assert_eq!(
+            parse_str(r#"storage_key = b"owner""#).unwrap(),
+            "& b\"owner\" [..]",
+        );
+    }
+
+    #[test]
+    #[should_panic = "Expected a string containing an expression, or a byte string literal"]
+    fn other_literal() {
+        parse_str("storage_key = 1").unwrap();
+    }
+}
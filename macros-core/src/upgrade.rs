@@ -0,0 +1,810 @@
+use darling::{FromDeriveInput, FromMeta};
+use once_cell::sync::OnceCell;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use regex::Regex;
+use syn::Expr;
+
+#[derive(Debug, Clone)]
+pub(crate) enum HookBody {
+    Empty,
+    Custom,
+    Owner,
+    Role(Box<syn::Expr>),
+    OwnerOrRole(Box<syn::Expr>),
+    Expr(syn::Path),
+    Multisig,
+}
+
+impl FromMeta for HookBody {
+    fn from_none() -> Option<Self> {
+        Some(Self::Custom)
+    }
+
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        match value {
+            syn::Lit::Str(s) => Self::parse(&s.value(), s.span()),
+            _ => Err(darling::Error::unexpected_lit_type(value)),
+        }
+        .map_err(|e| e.with_span(value))
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Self::parse(value, Span::call_site())
+    }
+}
+
+impl HookBody {
+    fn parse(value: &str, span: Span) -> darling::Result<Self> {
+        static REGEX: OnceCell<Regex> = OnceCell::new();
+
+        if value == "empty" {
+            return Ok(HookBody::Empty);
+        } else if value == "owner" {
+            return Ok(HookBody::Owner);
+        } else if value == "multisig" {
+            return Ok(HookBody::Multisig);
+        }
+
+        let r = REGEX.get_or_init(|| Regex::new(r"^(role|owner_or_role|expr)\((.*)\)$").unwrap());
+
+        let (kind, inner) = r
+            .captures(value)
+            .and_then(|c| Some((c.get(1)?.as_str(), c.get(2)?.as_str())))
+            .ok_or_else(|| {
+                darling::Error::custom(&format!(
+                    r#"Invalid value "{value}", expected "empty", "owner", "multisig", "role(...)", "owner_or_role(...)", or "expr(...)""#,
+                ))
+            })?;
+
+        match kind {
+            "expr" => syn::parse_str::<syn::Path>(inner)
+                .map(HookBody::Expr)
+                .map_err(|_| {
+                    darling::Error::custom(&format!(
+                        r#"Invalid value "{value}", expected the path of a function `fn(&self)` in "expr(...)""#,
+                    ))
+                }),
+            _ => {
+                let role = crate::rbac::parse_role(inner, span).map_err(|_| {
+                    darling::Error::custom(&format!(
+                        r#"Invalid value "{value}", expected a role expression in "{kind}(...)""#,
+                    ))
+                })?;
+
+                Ok(if kind == "role" {
+                    HookBody::Role(Box::new(role))
+                } else {
+                    HookBody::OwnerOrRole(Box::new(role))
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Serializer {
+    Borsh,
+    JsonBase64,
+}
+
+impl FromMeta for Serializer {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "borsh" => Ok(Self::Borsh),
+            "jsonbase64" => Ok(Self::JsonBase64),
+            _ => Err(darling::Error::custom(&format!(
+                r#"Invalid value "{value}", expected "borsh" or "jsonbase64""#
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum OnComplete {
+    Default,
+    Custom,
+}
+
+impl FromMeta for OnComplete {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "default" => Ok(Self::Default),
+            "custom" => Ok(Self::Custom),
+            _ => Err(darling::Error::custom(&format!(
+                r#"Invalid value "{value}", expected "default" or "custom""#
+            ))),
+        }
+    }
+}
+
+/// Options of the `Upgrade` derive, read from `#[upgrade(...)]`
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(upgrade), supports(struct_named))]
+pub struct UpgradeMeta {
+    pub(crate) hook: HookBody,
+    pub(crate) serializer: Option<Serializer>,
+    pub(crate) function_name: Option<syn::Ident>,
+    #[darling(default)]
+    pub(crate) private: bool,
+    #[darling(default)]
+    pub(crate) raw_input: bool,
+    pub(crate) migrate_method_name: Option<String>,
+    pub(crate) migrate_method_args: Option<Expr>,
+    pub(crate) migrate_minimum_gas: Option<Expr>,
+    pub(crate) migrate_static_gas: Option<Expr>,
+    #[darling(default)]
+    pub(crate) no_migrate: bool,
+    #[darling(default)]
+    pub(crate) allow_skip_migration: bool,
+    #[darling(default)]
+    pub(crate) staged: bool,
+    pub(crate) stage_delay_ns: Option<Expr>,
+    #[darling(default)]
+    pub(crate) rollback: bool,
+    pub(crate) max_retained_code_len: Option<Expr>,
+    pub(crate) on_complete: Option<OnComplete>,
+    pub(crate) event_standard: Option<String>,
+
+    #[darling(multiple)]
+    pub(crate) cfg: Vec<crate::cfg::Cfg>,
+    #[darling(default)]
+    pub(crate) method_docs: crate::method_docs::MethodDocs,
+    pub(crate) method_prefix: Option<crate::prefix::MethodPrefix>,
+
+    pub(crate) generics: syn::Generics,
+    pub(crate) ident: syn::Ident,
+
+    // crates
+    #[darling(rename = "crate", default = "crate::default_crate_name")]
+    pub(crate) me: syn::Path,
+    #[allow(dead_code)]
+    #[darling(default = "crate::default_macros")]
+    pub(crate) macros: syn::Path,
+    #[darling(default = "crate::default_near_sdk")]
+    pub(crate) near_sdk: syn::Path,
+}
+
+/// Expands to the `upgrade` external methods and the upgrade hook, if any
+pub fn expand(meta: UpgradeMeta) -> Result<TokenStream, darling::Error> {
+    let UpgradeMeta {
+        hook,
+        serializer,
+        function_name,
+        private,
+        raw_input,
+        migrate_method_name,
+        migrate_method_args,
+        migrate_minimum_gas,
+        migrate_static_gas,
+        no_migrate,
+        allow_skip_migration,
+        staged,
+        stage_delay_ns,
+        rollback,
+        max_retained_code_len,
+        on_complete,
+        event_standard,
+
+        ident,
+        generics,
+        cfg,
+        method_docs,
+        method_prefix,
+
+        me,
+        macros: _,
+        near_sdk,
+    } = meta;
+
+    let (imp, ty, wher) = generics.split_for_impl();
+    let near_bindgen = crate::bindgen_attr(&generics, quote! { #[#near_sdk::near_bindgen] });
+    let payable = crate::bindgen_attr(&generics, quote! { #[payable] });
+
+    let multisig = matches!(hook, HookBody::Multisig);
+    let delayed = stage_delay_ns.is_some();
+
+    let mut e = darling::Error::accumulator();
+
+    if no_migrate
+        && (migrate_method_name.is_some()
+            || migrate_method_args.is_some()
+            || migrate_minimum_gas.is_some()
+            || migrate_static_gas.is_some()
+            || allow_skip_migration)
+    {
+        e.push(darling::Error::custom(
+            "`no_migrate` cannot be combined with `migrate_*` or `allow_skip_migration`",
+        ));
+    }
+
+    if delayed && !staged {
+        e.push(
+            darling::Error::custom("`stage_delay_ns` may only be specified with `staged`")
+                .with_span(&stage_delay_ns),
+        );
+    }
+
+    if multisig && !staged {
+        e.push(darling::Error::custom(
+            "`hook = \"multisig\"` may only be specified with `staged`",
+        ));
+    }
+
+    if multisig && (function_name.is_some() || private || raw_input) {
+        e.push(darling::Error::custom(
+            "`function_name`, `private`, and `raw_input` cannot be specified with `hook = \"multisig\"`, which does not expose an upgrade function",
+        ).with_span(&function_name));
+    }
+
+    if delayed && !multisig && (function_name.is_some() || private || raw_input) {
+        e.push(darling::Error::custom(
+            "`function_name`, `private`, and `raw_input` cannot be specified with `stage_delay_ns`, which does not expose an upgrade function",
+        ).with_span(&function_name));
+    }
+
+    if raw_input && !matches!(serializer, Some(Serializer::Borsh)) {
+        e.push(darling::Error::custom(
+            "`raw_input` may only be specified with `serializer = \"borsh\"`",
+        ));
+    }
+
+    if raw_input && allow_skip_migration {
+        e.push(darling::Error::custom(
+            "`raw_input` cannot be combined with `allow_skip_migration`",
+        ));
+    }
+
+    if on_complete.is_some() && method_prefix.is_some() {
+        e.push(darling::Error::custom(
+            "`method_prefix` cannot be combined with `on_complete`, whose callback is called by its default name",
+        ));
+    }
+
+    if rollback && !staged {
+        e.push(darling::Error::custom(
+            "`rollback` may only be specified with `staged`",
+        ));
+    }
+
+    if max_retained_code_len.is_some() && !rollback {
+        e.push(
+            darling::Error::custom("`max_retained_code_len` may only be specified with `rollback`")
+                .with_span(&max_retained_code_len),
+        );
+    }
+
+    e.finish()?;
+
+    // Defaults are defined in main crate.
+    // I don't think these defaults can be easily defined using
+    // #[darling(default = "...")] because they are different types.
+    let migrate_method_name = migrate_method_name
+        .map(|e| quote! { #e })
+        .unwrap_or_else(|| quote! { #me::upgrade::DEFAULT_POST_UPGRADE_METHOD_NAME });
+    let migrate_method_args = migrate_method_args
+        .map(|e| quote! { #e })
+        .unwrap_or_else(|| quote! { #me::upgrade::DEFAULT_POST_UPGRADE_METHOD_ARGS });
+    let migrate_minimum_gas = migrate_minimum_gas
+        .map(|e| quote! { #e })
+        .unwrap_or_else(|| quote! { #me::upgrade::DEFAULT_POST_UPGRADE_MINIMUM_GAS });
+    // The completion callback reserves its gas up front
+    let migrate_minimum_gas = if on_complete.is_some() {
+        quote! { #migrate_minimum_gas + #me::upgrade::DEFAULT_UPGRADE_COMPLETE_GAS }
+    } else {
+        migrate_minimum_gas
+    };
+    let migrate_static_gas = migrate_static_gas
+        .map(|e| quote! { Some(#e) })
+        .unwrap_or_else(|| quote! { None });
+
+    let stage_delay_ns = stage_delay_ns.map(|delay| {
+        quote! {
+            fn stage_delay_ns() -> u64 {
+                #delay
+            }
+        }
+    });
+
+    let event_standard = event_standard
+        .map(|e| quote! { #e })
+        .unwrap_or_else(|| quote! { #me::upgrade::DEFAULT_UPGRADE_EVENT_STANDARD });
+
+    let role_assertion = match &hook {
+        HookBody::Role(role) | HookBody::OwnerOrRole(role) => {
+            Some(crate::rbac::assert_role(role, &ident, &generics, &me))
+        }
+        _ => None,
+    };
+
+    let hook_implementation = match &hook {
+        // Should we generate an UpgradeHook implementation with body?
+        HookBody::Empty => Some(quote! {}), // empty implementation
+        HookBody::Custom => None,           // user-provided implementation
+        HookBody::Owner => Some(quote! {
+            <Self as #me::owner::Owner>::require_owner();
+        }),
+        HookBody::Role(role) => Some(quote! {
+            <Self as #me::rbac::Rbac>::require_role(&#role);
+        }),
+        HookBody::OwnerOrRole(role) => Some(quote! {
+            if let (Err(owner_error), Err(role_error)) = (
+                <Self as #me::owner::Owner>::check_owner(),
+                <Self as #me::rbac::Rbac>::check_role(&#role),
+            ) {
+                #near_sdk::env::panic_str(&format!(
+                    "Unauthorized upgrade: {owner_error}; {role_error}",
+                ));
+            }
+        }),
+        HookBody::Expr(path) => Some(quote! {
+            #path(self);
+        }),
+        HookBody::Multisig => Some(quote! {
+            <Self as #me::approval::simple_multisig::AccountAuthorizer>::is_account_authorized(
+                &#near_sdk::env::predecessor_account_id(),
+            )
+            .unwrap_or_else(|e| #near_sdk::env::panic_str(&e.to_string()));
+        }),
+    }
+    .map(|body| {
+        // Interpolate body if implementation is to be generated.
+        quote! {
+            impl #imp #me::upgrade::serialized::UpgradeHook for #ident #ty #wher {
+                fn on_upgrade(&self) {
+                    #body
+                }
+            }
+        }
+    });
+
+    let (serializer_attribute, code_type, code_conversion) =
+        match serializer.unwrap_or(Serializer::JsonBase64) {
+            Serializer::Borsh => (
+                crate::bindgen_attr(&generics, quote! { #[serializer(borsh)] }).unwrap_or_default(),
+                quote! { Vec<u8> },
+                quote! {},
+            ),
+            Serializer::JsonBase64 => (
+                quote! {},
+                quote! { #near_sdk::json_types::Base64VecU8 },
+                quote! { let code: Vec<u8> = code.into(); },
+            ),
+        };
+
+    let post_upgrade = quote! {
+        #me::upgrade::PostUpgrade {
+            method: #migrate_method_name.to_string(),
+            args: #migrate_method_args,
+            minimum_gas: #migrate_minimum_gas,
+            static_gas: #migrate_static_gas,
+        }
+    };
+
+    // Arguments provided at call time override the configured default
+    let post_upgrade_with_args = if no_migrate {
+        quote! {
+            let post_upgrade: Option<#me::upgrade::PostUpgrade> = None;
+        }
+    } else {
+        let skip_migration = if allow_skip_migration {
+            quote! { (!skip_migration).then_some(post_upgrade) }
+        } else {
+            quote! { Some(post_upgrade) }
+        };
+
+        quote! {
+            let mut post_upgrade = #post_upgrade;
+            if let Some(migrate_args) = migrate_args {
+                post_upgrade.args = migrate_args.into();
+            }
+            let post_upgrade = #skip_migration;
+        }
+    };
+
+    let migrate_params = (!no_migrate).then(|| {
+        let skip_migration = allow_skip_migration.then(|| {
+            quote! { #serializer_attribute skip_migration: bool, }
+        });
+
+        quote! {
+            #serializer_attribute migrate_args: Option<#near_sdk::json_types::Base64VecU8>,
+            #skip_migration
+        }
+    });
+
+    let emit = |event: TokenStream| {
+        quote! {
+            #me::upgrade::emit_upgrade_event(&#event, #event_standard);
+        }
+    };
+
+    let emit_deployed = emit(quote! {
+        #me::upgrade::UpgradeDeployed {
+            code_hash: code_hash.into(),
+            by: #near_sdk::env::predecessor_account_id(),
+            migrate: post_upgrade.as_ref().map(|post_upgrade| post_upgrade.method.clone()),
+        }
+    });
+
+    let emit_unstaged = emit(quote! {
+        #me::upgrade::UpgradeUnstaged {
+            code_hash: code_hash.into(),
+            by: #near_sdk::env::predecessor_account_id(),
+        }
+    });
+
+    let emit_staged = emit(quote! {
+        #me::upgrade::UpgradeStaged {
+            code_hash: code_hash.into(),
+            by: #near_sdk::env::predecessor_account_id(),
+        }
+    });
+
+    let complete_callback = on_complete.is_some();
+
+    // Chains the completion callback (if any) onto a deploy promise
+    let then_complete = |deploy: TokenStream| {
+        if complete_callback {
+            quote! {
+                #me::upgrade::serialized::on_complete(#deploy, code_hash);
+            }
+        } else {
+            quote! {
+                #deploy;
+            }
+        }
+    };
+
+    let on_complete = on_complete.map(|on_complete| {
+        let hook_implementation = matches!(on_complete, OnComplete::Default).then(|| {
+            quote! {
+                impl #imp #me::upgrade::serialized::UpgradeCompleteHook for #ident #ty #wher {}
+            }
+        });
+
+        let emit_completed = emit(quote! {
+            #me::upgrade::UpgradeCompleted {
+                code_hash: code_hash.into(),
+            }
+        });
+
+        let emit_failed = emit(quote! {
+            #me::upgrade::UpgradeFailed {
+                code_hash: code_hash.into(),
+            }
+        });
+
+        let private_callback = crate::bindgen_attr(&generics, quote! { #[private] });
+
+        quote! {
+            #hook_implementation
+
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Callback of a deployment, which reports whether the upgrade succeeded.
+                #private_callback
+                pub fn on_upgrade_complete(&mut self, code_hash: #near_sdk::json_types::Base58CryptoHash) {
+                    let code_hash: #near_sdk::CryptoHash = code_hash.into();
+                    if #near_sdk::is_promise_success() {
+                        #emit_completed
+                        #me::upgrade::serialized::UpgradeCompleteHook::on_upgrade_complete(self, code_hash);
+                    } else {
+                        #emit_failed
+                        #me::upgrade::serialized::UpgradeCompleteHook::on_upgrade_failed(self, code_hash);
+                    }
+                }
+            }
+        }
+    });
+
+    let record_deploy = rollback.then(|| {
+        quote! {
+            <#ident #ty as #me::upgrade::rollback::UpgradeRollback>::record_deploy(&code);
+        }
+    });
+
+    let rollback = rollback.then(|| {
+        let max_retained_code_len = max_retained_code_len.map(|len| {
+            quote! {
+                fn max_retained_code_len() -> usize {
+                    #len
+                }
+            }
+        });
+
+        quote! {
+            impl #imp #me::upgrade::rollback::UpgradeRollback for #ident #ty #wher {
+                #max_retained_code_len
+            }
+
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Stages the previously deployed code for deployment. Returns its hash.
+                #payable
+                pub fn stage_rollback(&mut self) -> #near_sdk::json_types::Base58CryptoHash {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    if let Some(code_hash) = <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash() {
+                        #emit_unstaged
+                    }
+                    let code_hash = <Self as #me::upgrade::rollback::UpgradeRollback>::stage_rollback();
+                    #emit_staged
+                    code_hash.into()
+                }
+
+                /// Returns the hash of the previously deployed code, if it is retained.
+                pub fn previous_code_hash(&self) -> Option<#near_sdk::json_types::Base58CryptoHash> {
+                    <Self as #me::upgrade::rollback::UpgradeRollback>::previous_code_hash().map(Into::into)
+                }
+            }
+        }
+    });
+
+    let record_staged_deploy = record_deploy.as_ref().map(|record_deploy| {
+        quote! {
+            if let Some(code) = <#ident #ty as #me::upgrade::staged::StagedUpgrade>::slot_staged_code().read_raw() {
+                #record_deploy
+            }
+        }
+    });
+
+    let action_migrate_args = if no_migrate {
+        quote! {
+            #near_sdk::require!(self.migrate_args.is_none(), "Migration is disabled");
+        }
+    } else if allow_skip_migration {
+        quote! {
+            let migrate_args = self.migrate_args;
+            let skip_migration = false;
+        }
+    } else {
+        quote! {
+            let migrate_args = self.migrate_args;
+        }
+    };
+
+    // Staged code (and its hash) is removed by the deployment
+    let deploy_staged_with_complete = |deploy: TokenStream| {
+        if complete_callback {
+            quote! {
+                let promise = #deploy;
+                if let Some(code_hash) = staged_code_hash {
+                    #me::upgrade::serialized::on_complete(promise, code_hash);
+                }
+            }
+        } else {
+            quote! {
+                #deploy;
+            }
+        }
+    };
+
+    let deploy_staged = if multisig {
+        let deploy = deploy_staged_with_complete(quote! {
+            <#ident #ty as #me::upgrade::staged::StagedUpgrade>::deploy_staged(
+                post_upgrade,
+                Some(self.code_hash.into()),
+            )
+        });
+
+        // Deployment is only possible by executing an approved request
+        quote! {
+            impl #imp #me::approval::Action<#ident #ty> for #me::upgrade::staged::UpgradeAction #wher {
+                type Output = ();
+
+                fn execute(self, _contract: &mut #ident #ty) -> Self::Output {
+                    #action_migrate_args
+                    #post_upgrade_with_args
+                    let staged_code_hash = <#ident #ty as #me::upgrade::staged::StagedUpgrade>::staged_code_hash();
+                    if let Some(code_hash) = staged_code_hash {
+                        #emit_deployed
+                    }
+                    #record_staged_deploy
+                    #deploy
+                }
+            }
+        }
+    } else {
+        let deploy = deploy_staged_with_complete(quote! {
+            <Self as #me::upgrade::staged::StagedUpgrade>::deploy_staged(
+                post_upgrade,
+                expected_hash.map(Into::into),
+            )
+        });
+
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Deploys the staged code, if its hash matches `expected_hash` (if
+                /// given), and its stage delay has elapsed.
+                pub fn deploy_staged(
+                    &mut self,
+                    #serializer_attribute expected_hash: Option<#near_sdk::json_types::Base58CryptoHash>,
+                    #migrate_params
+                ) {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    #post_upgrade_with_args
+                    let staged_code_hash = <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash();
+                    if let Some(code_hash) = staged_code_hash {
+                        #emit_deployed
+                    }
+                    #record_staged_deploy
+                    #deploy
+                }
+            }
+        }
+    };
+
+    let staged = staged.then(|| {
+        quote! {
+            impl #imp #me::upgrade::staged::StagedUpgrade for #ident #ty #wher {
+                #stage_delay_ns
+            }
+
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Stages `code` for deployment, replacing any staged code. Returns its
+                /// hash.
+                #payable
+                pub fn stage_upgrade(
+                    &mut self,
+                    #serializer_attribute code: #code_type,
+                ) -> #near_sdk::json_types::Base58CryptoHash {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    #code_conversion
+                    if let Some(code_hash) = <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash() {
+                        #emit_unstaged
+                    }
+                    let code_hash = <Self as #me::upgrade::staged::StagedUpgrade>::stage_upgrade(code);
+                    #emit_staged
+                    code_hash.into()
+                }
+
+                /// Returns the hash of the staged code, if any.
+                pub fn staged_code_hash(&self) -> Option<#near_sdk::json_types::Base58CryptoHash> {
+                    <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash().map(Into::into)
+                }
+
+                /// Returns information about the staged code, if any.
+                pub fn staged_upgrade(&self) -> Option<#me::upgrade::staged::StagedUpgradeInfo> {
+                    <Self as #me::upgrade::staged::StagedUpgrade>::staged_upgrade_info()
+                }
+
+                /// Removes the staged code.
+                pub fn unstage_upgrade(&mut self) {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    if let Some(code_hash) = <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash() {
+                        #emit_unstaged
+                    }
+                    <Self as #me::upgrade::staged::StagedUpgrade>::unstage_upgrade();
+                }
+
+                /// Uploads chunk number `index` of code to stage.
+                #payable
+                pub fn stage_upgrade_chunk(
+                    &mut self,
+                    #serializer_attribute index: u32,
+                    #serializer_attribute bytes: #code_type,
+                ) {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    let code = bytes;
+                    #code_conversion
+                    <Self as #me::upgrade::staged::StagedUpgrade>::stage_upgrade_chunk(index, code);
+                }
+
+                /// Stages the `total_chunks` uploaded chunks as one piece of code, if its
+                /// hash matches `expected_hash`. Returns the hash.
+                #payable
+                pub fn finalize_staged(
+                    &mut self,
+                    #serializer_attribute total_chunks: u32,
+                    #serializer_attribute expected_hash: #near_sdk::json_types::Base58CryptoHash,
+                ) -> #near_sdk::json_types::Base58CryptoHash {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    if let Some(code_hash) = <Self as #me::upgrade::staged::StagedUpgrade>::staged_code_hash() {
+                        #emit_unstaged
+                    }
+                    let code_hash = <Self as #me::upgrade::staged::StagedUpgrade>::finalize_staged(
+                        total_chunks,
+                        expected_hash.into(),
+                    );
+                    #emit_staged
+                    code_hash.into()
+                }
+
+                /// Discards the uploaded chunks.
+                pub fn abort_staged_upload(&mut self) {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    <Self as #me::upgrade::staged::StagedUpgrade>::abort_staged_upload();
+                }
+            }
+
+            #deploy_staged
+        }
+    });
+
+    // Direct upgrades would bypass the multisig or the staging delay
+    let upgrade = (!multisig && !delayed).then(|| {
+        let function_name = function_name.unwrap_or_else(|| syn::Ident::new("upgrade", ident.span()));
+        let private = private
+            .then(|| crate::bindgen_attr(&generics, quote! { #[private] }))
+            .flatten();
+
+        // With raw input, the code is the entire function input
+        let (params, read_input) = if raw_input {
+            let migrate_args = (!no_migrate).then(|| {
+                quote! {
+                    let migrate_args: Option<#near_sdk::json_types::Base64VecU8> = None;
+                }
+            });
+
+            (
+                quote! {},
+                quote! {
+                    let code = #near_sdk::env::input()
+                        .unwrap_or_else(|| #near_sdk::env::panic_str("No code provided"));
+                    let expected_hash: Option<#near_sdk::json_types::Base58CryptoHash> = None;
+                    #migrate_args
+                },
+            )
+        } else {
+            (
+                quote! {
+                    #serializer_attribute code: #code_type,
+                    #serializer_attribute expected_hash: Option<#near_sdk::json_types::Base58CryptoHash>,
+                    #migrate_params
+                },
+                quote! {},
+            )
+        };
+
+        let deploy = then_complete(quote! {
+            match post_upgrade {
+                Some(post_upgrade) => #me::upgrade::serialized::upgrade(code, post_upgrade),
+                None => #me::upgrade::serialized::deploy(code),
+            }
+        });
+
+        quote! {
+            #near_bindgen
+            impl #imp #ident #ty #wher {
+                /// Deploys new code and, unless migration is disabled or skipped, calls the
+                /// migrate function of the new code.
+                #private
+                pub fn #function_name(&mut self, #params) {
+                    #me::upgrade::serialized::UpgradeHook::on_upgrade(self);
+                    #read_input
+                    #code_conversion
+                    let code_hash = #near_sdk::env::sha256_array(&code);
+                    if let Some(expected_hash) = expected_hash {
+                        #me::upgrade::serialized::require_hash_match(code_hash, expected_hash.into());
+                    }
+                    #post_upgrade_with_args
+                    #emit_deployed
+                    #record_deploy
+                    #deploy
+                }
+            }
+        }
+    });
+
+    crate::cfg::apply(
+        &cfg,
+        &generics,
+        quote! {
+            #upgrade
+
+            #staged
+
+            #rollback
+
+            #on_complete
+
+            #hook_implementation
+
+            #role_assertion
+        },
+    )
+    .and_then(|expanded| crate::method_docs::apply(&method_docs, &generics, expanded))
+    .and_then(|expanded| crate::prefix::apply(method_prefix.as_ref(), &generics, expanded))
+}
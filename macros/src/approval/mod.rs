@@ -1 +0,0 @@
-pub mod simple_multisig;
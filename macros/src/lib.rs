@@ -1,36 +1,119 @@
 //! Macros for near-sdk-contract-tools
+//!
+//! # Storage keys
+//!
+//! The `storage_key` options accept either a string containing an expression,
+//! e.g. `storage_key = "StorageKey::Owner"` or
+//! `storage_key = "crate::keys::OWNER"`, or a byte string literal, e.g.
+//! `storage_key = b"owner"`. The expression must implement
+//! `near_sdk::IntoStorageKey`. (Note that `&[u8; N]` does not, except for
+//! `N = 1`; a byte string literal is converted to a `&[u8]`.)
+//!
+//! # Versioned contracts
+//!
+//! `Owner`, `Pause`, `Rbac`, `Nep141`, `Nep148`, `FungibleToken`, `Nep245`,
+//! and `ContractMetadata` may be derived on an enum as well as on a struct,
+//! e.g. a contract defined as
+//! `enum Contract { V1(ContractV1), V2(ContractV2) }`. The components keep
+//! their state in their own storage slots, not in fields of the contract, so
+//! the implementations are the same for every variant.
+//!
+//! # Skipping external methods
+//!
+//! `Owner`, `Pause`, `Nep141`, `Nep148`, `FungibleToken`, `Nep245`, and
+//! `ContractMetadata` accept `skip = "<method>, ..."` to omit generated
+//! external methods, so that the contract can implement them itself, e.g.
+//! `#[nep141(skip = "ft_transfer_call")]`. The internal implementation (e.g.
+//! `Nep141Controller`) is still generated. An external trait (e.g. `Nep141`)
+//! cannot be implemented partially, so if any of its methods are skipped, the
+//! rest are generated as inherent `pub fn`s of the contract instead, and the
+//! contract does not implement the trait. Skipped methods cannot also be
+//! given `method_attrs`.
+//!
+//! # Conditional external methods
+//!
+//! `Owner`, `Pause`, `Nep141`, `Nep148`, `FungibleToken`, `Nep245`,
+//! `ContractMetadata`, `SimpleMultisig`, `Upgrade`, `Migrate`, and
+//! `MigrateChain` accept `cfg = "<predicate>"` to
+//! generate their external methods only if the `#[cfg(...)]` predicate holds,
+//! e.g. `#[upgrade(hook = "owner", cfg = "not(feature = \"immutable\")")]`,
+//! and `cfg(method = "<method>", predicate = "<predicate>")` to do so for a
+//! single method. Both may be given multiple times. As with `skip`, if a
+//! method of an external trait has its own predicate, the methods of the
+//! trait are generated as inherent `pub fn`s of the contract instead.
+//! Internal implementations are always generated. Not supported on generic
+//! contracts.
+//!
+//! # External method names
+//!
+//! | Derive | Generated external methods | Renamed with |
+//! |---|---|---|
+//! | `Owner` | `own_get_owner`, `own_get_proposed_owner`, `own_renounce_owner`, `own_propose_owner`, `own_accept_owner` | `method_prefix` |
+//! | `Pause` | `paus_is_paused` | `method_prefix` |
+//! | `Rbac` | none | |
+//! | `Nep141` | `ft_transfer`, `ft_transfer_call`, `ft_total_supply`, `ft_balance_of`, `ft_resolve_transfer` | fixed by the standard |
+//! | `Nep148` | `ft_metadata` | fixed by the standard |
+//! | `Nep245` | `mt_transfer`, `mt_batch_transfer`, `mt_transfer_call`, `mt_batch_transfer_call`, `mt_balance_of`, `mt_batch_balance_of`, `mt_total_supply`, `mt_resolve_transfer` | fixed by the standard |
+//! | `ContractMetadata` | `contract_source_metadata` | fixed by the standard |
+//! | `SimpleMultisig` (`external`) | `multisig_request`, `multisig_approve`, `multisig_execute`, `multisig_is_approved`, `multisig_request_summary` | `external_prefix` (replaces `multisig`), `method_prefix` |
+//! | `Migrate` | `migrate`; with `dry_run`: `migrate_dry_run`; with `progressive`: `migrate_step`, `migration_complete` | `method_prefix` |
+//! | `MigrateChain` | `migrate` | `method_prefix` |
+//! | `Upgrade` | `upgrade`; with `staged`: `stage_upgrade`, `stage_upgrade_chunk`, `finalize_staged`, `abort_staged_upload`, `staged_code_hash`, `staged_upgrade`, `unstage_upgrade`, `deploy_staged`; with `rollback`: `stage_rollback`, `previous_code_hash`; with `on_complete`: `on_upgrade_complete` | `function_name` (replaces `upgrade`), `method_prefix` (except with `on_complete`) |
+//!
+//! `method_prefix = "<prefix>"` prepends `<prefix>` to the name of every
+//! generated external method, e.g. `#[pause(method_prefix = "admin_")]`
+//! generates `admin_paus_is_paused`, so that two components (or a component
+//! and the contract itself) do not generate methods with the same name. As
+//! with `skip`, the methods of an external trait are generated as inherent
+//! `pub fn`s of the contract instead. Internal implementations are not
+//! renamed, and the other options (`skip`, `cfg`, `method_attrs`) still refer
+//! to methods by their default names. A prefixed `migrate` method must be
+//! passed to `Upgrade` as `migrate_method_name`. Not supported on generic
+//! contracts.
+//!
+//! # Documentation of external methods
+//!
+//! Generated external methods are documented (in `cargo doc` and in the ABI)
+//! with what they do, the deposit they require, and when they panic. The
+//! derives that accept `cfg` also accept
+//! `method_docs(<method> = "<doc>", ...)` to replace the documentation of
+//! some methods, e.g.
+//! `#[owner(method_docs(own_propose_owner = "Hands the contract over."))]`.
+//! Each line of `<doc>` becomes a line of the documentation. Methods are
+//! referred to by their default names. Not supported on generic contracts.
 
 use darling::{FromDeriveInput, FromMeta};
+use near_sdk_contract_tools_macros_core::{
+    approval, contract_tools, externals, migrate, migrate_chain, owner, pause, rate_limit, rbac,
+    reentrancy, standard, upgrade,
+};
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, AttributeArgs, DeriveInput, Item};
+use syn::{parse_macro_input, AttributeArgs, DeriveInput, Item, ItemFn};
 
-mod approval;
-mod migrate;
-mod owner;
-mod pause;
-mod rbac;
-mod rename;
-mod standard;
-mod upgrade;
+// The expansions are implemented in `near-sdk-contract-tools-macros-core`,
+// so that framework crates can reuse them in their own derive macros.
 
-fn default_crate_name() -> syn::Path {
-    syn::parse_str("::near_sdk_contract_tools").unwrap()
-}
-
-fn default_macros() -> syn::Path {
-    syn::parse_str("::near_sdk_contract_tools").unwrap()
-}
-
-fn default_near_sdk() -> syn::Path {
-    syn::parse_str("::near_sdk").unwrap()
-}
+fn make_derive<T>(
+    input: TokenStream,
+    attribute: &str,
+    expand: fn(T) -> Result<proc_macro2::TokenStream, darling::Error>,
+) -> TokenStream
+where
+    T: FromDeriveInput,
+{
+    let input = parse_macro_input!(input as DeriveInput);
 
-fn default_serde() -> syn::Path {
-    syn::parse_str("::serde").unwrap()
+    near_sdk_contract_tools_macros_core::expand_derive(&input, attribute, expand)
+        .unwrap_or_else(|e| e.write_errors())
+        .into()
 }
 
-fn make_derive<T>(
+/// Like [`make_derive`], for derive macros that generate `#[near_bindgen]`
+/// external methods, and therefore require the struct to be `#[near_bindgen]`
+/// (see: [`externals::near_bindgen_check`]).
+fn make_external_derive<T>(
     input: TokenStream,
+    attribute: &str,
     expand: fn(T) -> Result<proc_macro2::TokenStream, darling::Error>,
 ) -> TokenStream
 where
@@ -38,10 +121,9 @@ where
 {
     let input = parse_macro_input!(input as DeriveInput);
 
-    FromDeriveInput::from_derive_input(&input)
-        .and_then(expand)
-        .map(Into::into)
-        .unwrap_or_else(|e| e.write_errors().into())
+    near_sdk_contract_tools_macros_core::expand_external_derive(&input, attribute, expand)
+        .unwrap_or_else(|e| e.write_errors())
+        .into()
 }
 
 /// Use on a struct to emit NEP-297 event strings.
@@ -59,41 +141,102 @@ where
 /// - `SHOUTY_SNAKE_CASE`
 /// - `SHOUTY-KEBAB-CASE`
 /// - `Title Case`
+///
+/// The same conversions are available at runtime in
+/// `near_sdk_contract_tools::rename`, with the `rename` feature.
 #[proc_macro_derive(Nep297, attributes(nep297))]
 pub fn derive_nep297(input: TokenStream) -> TokenStream {
-    make_derive(input, standard::nep297::expand)
+    make_derive(input, "nep297", standard::nep297::expand)
 }
 
 /// Creates a managed, lazily-loaded `Owner` implementation for the targeted
 /// `#[near_bindgen]` struct.
 ///
 /// The storage key prefix for the fields can be optionally specified (default:
-/// `"~o"`) using `#[owner(storage_key = "<expression>")]`.
+/// `"~o"`) using `#[owner(storage_key = "<expression>")]` or
+/// `#[owner(storage_key = b"<bytes>")]`.
+///
+/// A budget for the length of the longest storage key of the component can be
+/// specified using `#[owner(max_key_len = <length>)]`. With the default storage
+/// key, exceeding the budget is a compile error. With a custom storage key, it
+/// is checked by a debug assertion when the key is created.
+///
+/// Alternatively, the state can be kept in a field of type
+/// `near_sdk_contract_tools::owner::OwnerState` of the contract using
+/// `#[owner(field = "<field>")]` (see the tradeoffs in the docs of the `owner`
+/// module).
+///
+/// Extra `#[near_bindgen]` attributes (`payable` or `private`) can be added to
+/// the generated `own_*` methods, e.g.
+/// `#[owner(method_attrs(own_get_owner = "private"))]`. Not supported on
+/// generic contracts, whose methods are not exported.
+///
+/// Generated `own_*` methods can be omitted using
+/// `#[owner(skip = "<method>, ...")]`, or generated only for some builds
+/// using `#[owner(cfg = "<predicate>")]`, or renamed using
+/// `#[owner(method_prefix = "<prefix>")]` (see: [crate-level docs](crate)).
 #[proc_macro_derive(Owner, attributes(owner))]
 pub fn derive_owner(input: TokenStream) -> TokenStream {
-    make_derive(input, owner::expand)
+    make_external_derive(input, "owner", owner::expand)
 }
 
 /// Makes a contract pausable. Provides an implementation of the `Pause` trait.
 ///
 /// The storage key prefix for the fields can be optionally specified (default:
-/// `"~p"`) using `#[pause(storage_key = "<expression>")]`.
+/// `"~p"`) using `#[pause(storage_key = "<expression>")]` or
+/// `#[pause(storage_key = b"<bytes>")]`.
+///
+/// A budget for the length of the longest storage key of the component can be
+/// specified using `#[pause(max_key_len = <length>)]`. With the default storage
+/// key, exceeding the budget is a compile error. With a custom storage key, it
+/// is checked by a debug assertion when the key is created.
+///
+/// Alternatively, the state can be kept in a field of type
+/// `near_sdk_contract_tools::pause::PauseState` of the contract using
+/// `#[pause(field = "<field>")]` (see the tradeoffs in the docs of the `pause`
+/// module).
+///
+/// `paus_is_paused` can be made `#[private]` using
+/// `#[pause(method_attrs(paus_is_paused = "private"))]`, or omitted using
+/// `#[pause(skip = "paus_is_paused")]`, or generated only for some builds
+/// using `#[pause(cfg = "<predicate>")]`, or renamed using
+/// `#[pause(method_prefix = "<prefix>")]`.
 #[proc_macro_derive(Pause, attributes(pause))]
 pub fn derive_pause(input: TokenStream) -> TokenStream {
-    make_derive(input, pause::expand)
+    make_external_derive(input, "pause", pause::expand)
 }
 
 /// Adds role-based access control. No external methods are exposed.
 ///
 /// The roles prefix must be specify a type using #[rbac(roles = "MyRoles")].
 /// Typically "MyRoles" is an enum and it's variants are the different role
-/// names.
+/// names. The roles type must either derive [`Role`] or implement
+/// `BorshSerialize`.
+///
+/// Roles named in the attributes of other derives, e.g.
+/// `#[upgrade(hook = "role(MyRoles::Upgrader)")]`, are checked against the
+/// roles type at compile time, even if the code that uses them is excluded
+/// with `cfg`. A misspelled role is reported at the attribute.
 ///
 /// The storage key prefix for the fields can be optionally specified (default:
-/// `"~r"`) using `#[rbac(storage_key = "<expression>")]`.
+/// `"~r"`) using `#[rbac(storage_key = "<expression>")]` or
+/// `#[rbac(storage_key = b"<bytes>")]`.
 #[proc_macro_derive(Rbac, attributes(rbac))]
 pub fn derive_rbac(input: TokenStream) -> TokenStream {
-    make_derive(input, rbac::expand)
+    make_derive(input, "rbac", rbac::expand)
+}
+
+/// Makes a plain enum usable as the roles of `Rbac`, without deriving
+/// `BorshSerialize` or `BorshStorageKey`: implements `RbacRole`, which
+/// identifies each role in storage by the index of its variant, and `Display`
+/// with the names of the variants (e.g. for the `MissingRole` error of
+/// `SimpleMultisig`).
+///
+/// The variants cannot have fields. `Display` can be omitted using
+/// `#[role(no_display)]`.
+#[proc_macro_derive(Role, attributes(role))]
+pub fn derive_role(input: TokenStream) -> TokenStream {
+    make_derive(input, "role", rbac::expand_role)
 }
 
 /// Adds NEP-141 fungible token core functionality to a contract. Exposes
@@ -101,10 +244,34 @@ pub fn derive_rbac(input: TokenStream) -> TokenStream {
 /// and receiver functionality (see: `near_sdk_contract_tools::standard::nep141`).
 ///
 /// The storage key prefix for the fields can be optionally specified (default:
-/// `"~$141"`) using `#[nep141(storage_key = "<expression>")]`.
+/// `"~$141"`) using `#[nep141(storage_key = "<expression>")]` or
+/// `#[nep141(storage_key = b"<bytes>")]`.
+///
+/// Account keys can be replaced by a truncated SHA-256 hash of the account ID
+/// using `#[nep141(hashed_keys)]` (default length:
+/// `near_sdk_contract_tools::slot::HASHED_KEY_LEN`) or
+/// `#[nep141(hashed_keys = <length>)]`. This changes the storage layout, so
+/// it should not be switched on a deployed contract.
+///
+/// A budget for the length of the longest storage key of the component can be
+/// specified using `#[nep141(max_key_len = <length>)]`. With the default storage
+/// key, exceeding the budget is a compile error. With a custom storage key, it
+/// is checked by a debug assertion when the key is created. Account IDs are
+/// counted at their maximum length of 64 bytes.
+///
+/// Extra `#[near_bindgen]` attributes for the generated `ft_*` methods can be
+/// specified using `#[nep141(method_attrs(<method> = "<attr>, ..."))]`. Only
+/// `payable` and `private` are accepted, only where the method does not
+/// already have them, and not on generic contracts.
+///
+/// Generated `ft_*` methods can be omitted using
+/// `#[nep141(skip = "<method>, ...")]`, and generated only for some builds
+/// using `#[nep141(cfg = "<predicate>")]` or
+/// `#[nep141(cfg(method = "<method>", predicate = "<predicate>"))]` (see:
+/// [crate-level docs](crate)).
 #[proc_macro_derive(Nep141, attributes(nep141))]
 pub fn derive_nep141(input: TokenStream) -> TokenStream {
-    make_derive(input, standard::nep141::expand)
+    make_external_derive(input, "nep141", standard::nep141::expand)
 }
 
 /// Adds NEP-148 fungible token metadata functionality to a contract. Metadata
@@ -112,6 +279,10 @@ pub fn derive_nep141(input: TokenStream) -> TokenStream {
 ///
 /// Specify metadata using the `#[nep148(...)]` attribute.
 ///
+/// The storage key prefix reserved for storage-backed metadata can be
+/// optionally specified (default: `"~$148"`) using
+/// `#[nep148(storage_key = "<expression>")]`.
+///
 /// Fields:
 ///  - `name`
 ///  - `symbol`
@@ -120,9 +291,14 @@ pub fn derive_nep141(input: TokenStream) -> TokenStream {
 ///  - `icon` (optional)
 ///  - `reference` (optional)
 ///  - `reference_hash` (optional)
+///  - `method_attrs` (optional) Extra `#[near_bindgen]` attributes for
+///     `ft_metadata`, e.g. `method_attrs(ft_metadata = "private")`
+///  - `skip` (optional) `skip = "ft_metadata"` omits `ft_metadata`
+///  - `cfg` (optional) `cfg = "<predicate>"` generates `ft_metadata` only if
+///     the `#[cfg(...)]` predicate holds
 #[proc_macro_derive(Nep148, attributes(nep148))]
 pub fn derive_nep148(input: TokenStream) -> TokenStream {
-    make_derive(input, standard::nep148::expand)
+    make_external_derive(input, "nep148", standard::nep148::expand)
 }
 
 /// Implements NEP-141 and NEP-148 functionality, like
@@ -130,9 +306,69 @@ pub fn derive_nep148(input: TokenStream) -> TokenStream {
 ///
 /// Attributes are the union of those for the constituent derive macros.
 /// Specify attributes with `#[fungible_token(...)]`.
+///
+/// Storage keys:
+///  - `storage_key` The NEP-141 root. The NEP-148 root is nested under it (the
+///     key followed by `"~$148"`).
+///  - `core_storage_key` The NEP-141 root.
+///  - `metadata_storage_key` The NEP-148 root.
+///
+/// `storage_key` cannot be combined with the other two.
 #[proc_macro_derive(FungibleToken, attributes(fungible_token))]
 pub fn derive_fungible_token(input: TokenStream) -> TokenStream {
-    make_derive(input, standard::fungible_token::expand)
+    make_external_derive(input, "fungible_token", standard::fungible_token::expand)
+}
+
+/// Adds NEP-245 multi token core functionality to a contract. Exposes `mt_*`
+/// functions to the public blockchain, implements internal controller and
+/// receiver functionality (see: `near_sdk_contract_tools::standard::nep245`).
+///
+/// The storage key prefix for the fields can be optionally specified (default:
+/// `"~$245"`) using `#[nep245(storage_key = "<expression>")]` or
+/// `#[nep245(storage_key = b"<bytes>")]`.
+///
+/// Transfer hooks (`Nep245Hook`) can be disabled using `#[nep245(no_hooks)]`.
+///
+/// Extra `#[near_bindgen]` attributes for the generated `mt_*` methods can be
+/// specified using `#[nep245(method_attrs(<method> = "<attr>, ..."))]`. Only
+/// `payable` and `private` are accepted, only where the method does not
+/// already have them, and not on generic contracts.
+///
+/// Generated `mt_*` methods can be omitted using
+/// `#[nep245(skip = "<method>, ...")]`, and generated only for some builds
+/// using `#[nep245(cfg = "<predicate>")]` or
+/// `#[nep245(cfg(method = "<method>", predicate = "<predicate>"))]` (see:
+/// [crate-level docs](crate)).
+#[proc_macro_derive(Nep245, attributes(nep245))]
+pub fn derive_nep245(input: TokenStream) -> TokenStream {
+    make_external_derive(input, "nep245", standard::nep245::expand)
+}
+
+/// Adds NEP-330 contract source metadata to a contract. Exposes
+/// `contract_source_metadata`, which returns the version and location of the
+/// contract's source code, and the standards it implements.
+///
+/// The standards of the components that the contract derives (`Nep141`,
+/// `Nep148`, `FungibleToken`, and `Nep245`) are listed automatically, along
+/// with NEP-330 itself.
+///
+/// Fields:
+///  - `version` (optional) Version of the source code (default: the version
+///     of the crate, `CARGO_PKG_VERSION`)
+///  - `link` (optional) Location of the source code (default: the repository
+///     of the crate, `CARGO_PKG_REPOSITORY`, or none if it is not set)
+///  - `standard` (optional, multiple) Other standards implemented by the
+///     contract, e.g. `standard(standard = "nep171", version = "1.0.0")`
+///  - `method_attrs` (optional) Extra `#[near_bindgen]` attributes for
+///     `contract_source_metadata`, e.g.
+///     `method_attrs(contract_source_metadata = "private")`
+///  - `skip` (optional) `skip = "contract_source_metadata"` omits
+///     `contract_source_metadata`
+///  - `cfg` (optional) `cfg = "<predicate>"` generates
+///     `contract_source_metadata` only if the `#[cfg(...)]` predicate holds
+#[proc_macro_derive(ContractMetadata, attributes(contract_metadata))]
+pub fn derive_contract_metadata(input: TokenStream) -> TokenStream {
+    make_external_derive(input, "contract_metadata", standard::nep330::expand)
 }
 
 /// Migrate a contract's default struct from one schema to another.
@@ -141,22 +377,91 @@ pub fn derive_fungible_token(input: TokenStream) -> TokenStream {
 ///
 /// Fields include:
 ///  - `from` Old default struct type to convert from. (required)
+///  - `from_format` Format of the stored old state, either `"borsh"` or
+///     `"json"`. JSON state is deserialized with `serde_json`, so the `from`
+///     type must implement `Deserialize` (as well as `BorshDeserialize`,
+///     like any `from` type). (optional, default: `"borsh"`)
 ///  - `to` New default struct type to convert into. (optional, default: `Self`)
 ///  - `convert` Identifier of a function that converts from the old schema to
 ///     the new schema. Mutually exclusive with `convert_with_args`. (optional,
-///     default: `<Self::NewSchema as From<Self::OldSchema>>::from`)
+///     default: `TryMigrateHook::try_on_migrate`)
 ///  - `convert_with_args` Identifier of a function that converts from the old
-///     schema to the new schema and accepts a single `String` argument.
-///     Mutually exclusive with `convert`. (optional)
-///  - `allow` Expression to evaluate before allowing
+///     schema to the new schema and accepts a second argument of the type
+///     specified by `args`. The generated `migrate(args)` function takes the
+///     argument as JSON, e.g. `{"args": ...}`, and is an inherent method
+///     instead of an implementation of `MigrateExternal`. Mutually exclusive
+///     with `convert`. (optional)
+///  - `args` Type of the argument accepted by `convert_with_args`. Must
+///     implement `Serialize` and `Deserialize`. (optional, default: `String`)
+///  - `allow` Who may call `migrate`. (optional, default: anyone) One of:
+///     - `"owner"` The owner of the contract. Requires `Owner`.
+///     - `"self"` The contract account itself, e.g. during an upgrade.
+///     - `"creator"` Transactions signed by the contract account.
+///     - `"role(...)"` Accounts with the given role. Requires `Rbac`.
+///     - Any other value is an expression that must evaluate to `true`.
+///  - `on_deserialize_failure` Identifier of a function that receives the
+///     raw state bytes (`Vec<u8>`) if they cannot be deserialized as the old
+///     schema, and returns the old schema. (optional, default: panic)
+///  - `from_version` Version string of the old schema, used in the emitted
+///     `MigrateEvent`. (optional, default: name of the `from` type)
+///  - `to_version` Version string of the new schema, used in the emitted
+///     `MigrateEvent`. (optional, default: name of the `to` type)
+///  - `allow_rerun` Flag. By default, the generated `migrate` function
+///     records `from_version` and `to_version` in storage and refuses to run
+///     again for the same pair of versions. Set this flag to allow running the migration repeatedly.
+///     (optional)
+///  - `dry_run` Flag. Exposes a `migrate_dry_run()` view (taking the same
+///     arguments as `migrate`) that performs the conversion and returns a
+///     summary of the new state without writing anything. The contract must
+///     implement `MigrateDryRun`, which asserts that the conversion has no
+///     side effects. (optional)
+///  - `no_event` Flag. Do not emit a `MigrateEvent` after migrating.
+///     (optional)
+///  - `progressive` Flag. Exposes `migrate_step(limit)` and
+///     `migration_complete()` to the blockchain. The contract must implement
+///     `ProgressiveMigration`. `migrate_step` is guarded by `allow` like
+///     `migrate`, and may only be called by the contract itself if `allow` is
+///     not specified. (optional)
+///  - `cfg` `#[cfg(...)]` predicates for the exposed functions (see:
+///     [crate-level docs](crate)). (optional)
+///  - `method_prefix` Prefix for the names of the exposed functions, e.g.
+///     `method_prefix = "v2_"` exposes `v2_migrate` (see:
+///     [crate-level docs](crate)). (optional)
 #[proc_macro_derive(Migrate, attributes(migrate))]
 pub fn derive_migrate(input: TokenStream) -> TokenStream {
-    make_derive(input, migrate::expand)
+    make_external_derive(input, "migrate", migrate::expand)
+}
+
+/// Migrate a contract's default struct through a chain of schema versions.
+///
+/// Generates an enum of every schema version (implementing
+/// `VersionedState`), and implementations of `MigrateChain` and
+/// `MigrateExternal` for the target struct. The user must implement `From`
+/// between each pair of consecutive versions. The generated `migrate`
+/// function reads the stored version from the version tag slot (falling
+/// back to trying to deserialize each version), converts it to the latest
+/// version, and records the new version tag.
+///
+/// Fields may be specified in the `#[migrate_chain(...)]` attribute.
+///
+/// Fields include:
+///  - `versions` Previous schema versions, oldest first, e.g.
+///     `versions(ContractV1, ContractV2)`. The target struct is the latest
+///     version. (required)
+///  - `versions_ident` Name of the generated enum. (optional, default:
+///     `<Ident>Versions`)
+///  - `cfg` `#[cfg(...)]` predicates for the exposed functions (see:
+///     [crate-level docs](crate)). (optional)
+///  - `method_prefix` Prefix for the name of the exposed `migrate` function
+///     (see: [crate-level docs](crate)). (optional)
+#[proc_macro_derive(MigrateChain, attributes(migrate_chain))]
+pub fn derive_migrate_chain(input: TokenStream) -> TokenStream {
+    make_external_derive(input, "migrate_chain", migrate_chain::expand)
 }
 
 /// Create a simple multisig component. Does not expose any functions to the
-/// blockchain. Creates implementations for `ApprovalManager` and
-/// `AccountApprover` for the target contract struct.
+/// blockchain unless `external` is specified. Creates implementations for
+/// `ApprovalManager` and `AccountApprover` for the target contract struct.
 ///
 /// Fields may be specified in the `#[simple_multisig(...)]` attribute.
 ///
@@ -165,9 +470,20 @@ pub fn derive_migrate(input: TokenStream) -> TokenStream {
 ///  - `action` What sort of approval `Action` can be approved by the multisig
 ///     component?
 ///  - `role` Approving accounts are required to have this `Rbac` role.
+///  - `external` Expose `<prefix>_request(action)`, `<prefix>_approve(request_id)`,
+///     `<prefix>_execute(request_id)`, `<prefix>_is_approved(request_id)`,
+///     and `<prefix>_request_summary(request_id)` to the blockchain. The action type must implement `Deserialize`.
+///     (optional)
+///  - `external_prefix` Prefix for the names of the exposed functions
+///     (optional, default: `"multisig"`)
+///  - `cfg` `#[cfg(...)]` predicates for the exposed functions (see:
+///     [crate-level docs](crate)). (optional)
+///  - `method_prefix` Prefix prepended to the names of the exposed
+///     functions, e.g. `method_prefix = "dao_"` exposes `dao_multisig_request`.
+///     Requires `external`. (optional)
 #[proc_macro_derive(SimpleMultisig, attributes(simple_multisig))]
 pub fn derive_simple_multisig(input: TokenStream) -> TokenStream {
-    make_derive(input, approval::simple_multisig::expand)
+    make_derive(input, "simple_multisig", approval::simple_multisig::expand)
 }
 
 /// Smart `#[event]` macro
@@ -182,6 +498,127 @@ pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
         .unwrap_or_else(|e| e.write_errors().into())
 }
 
+/// Limits how often each account may call a method, with a
+/// `RateLimitPolicy`. Injects a call to `RateLimit::require_rate_limit` for
+/// the predecessor before the body of the method, so the contract must
+/// implement `RateLimit`.
+///
+/// Fields may be specified in the `#[rate_limited(...)]` attribute.
+///
+/// Fields include:
+///  - `policy` Expression evaluating to the `RateLimitPolicy`, e.g.
+///     `policy = "RateLimitPolicy::cooldown(60_000_000_000)"` or the name of
+///     a constant. (required)
+///  - `key` Key identifying the limited action in storage. Methods with the
+///     same key share their limit. (optional, default: the name of the
+///     method)
+#[proc_macro_attribute]
+pub fn rate_limited(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+
+    rate_limit::RateLimitedMeta::from_list(&attr)
+        .and_then(|meta| rate_limit::expand(meta, item))
+        .map(Into::into)
+        .unwrap_or_else(|e| e.write_errors().into())
+}
+
+/// Rejects reentrant calls to a method, with a reentrancy guard from
+/// `Reentrancy::enter`. Injects the guard before the body of the method, so
+/// the contract must implement `Reentrancy`.
+///
+/// Fields may be specified in the `#[non_reentrant(...)]` attribute.
+///
+/// Fields include:
+///  - `scope` Scope of the guard. Methods with the same scope exclude each
+///     other. (optional, default: the name of the method)
+///  - `hold` Keep the scope locked after the method returns, across the
+///     promises it schedules, until `Reentrancy::release` is called, e.g. by
+///     the callback. (optional, default: released when the method returns)
+#[proc_macro_attribute]
+pub fn non_reentrant(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+
+    reentrancy::NonReentrantMeta::from_list(&attr)
+        .and_then(|meta| reentrancy::expand(meta, item))
+        .map(Into::into)
+        .unwrap_or_else(|e| e.write_errors().into())
+}
+
+/// Composes multiple components on a contract, e.g.:
+///
+/// ```ignore
+/// #[contract_tools(
+///     owner,
+///     pause,
+///     rbac(roles = "Role"),
+///     fungible_token(name = "My Token", symbol = "TKN", decimals = 18),
+/// )]
+/// #[near_bindgen]
+/// struct Contract {}
+/// ```
+///
+/// Each component is given as the name of its attribute, optionally followed
+/// by the options it accepts in that attribute. The item is expanded to the
+/// equivalent `#[derive(...)]` and component attributes, in this order,
+/// regardless of the order in which the components are listed: `owner`,
+/// `pause`, `rbac`, `nep141`, `nep148`, `fungible_token`, `nep245`,
+/// `contract_metadata`, `simple_multisig`, `upgrade`, `migrate`,
+/// `migrate_chain`.
+///
+/// Components that use other components must be combined with them:
+///  - `simple_multisig` requires `rbac`.
+///  - `upgrade` with `hook = "owner"` requires `owner`, with
+///     `hook = "role(...)"` requires `rbac`, with
+///     `hook = "owner_or_role(...)"` requires both, and with
+///     `hook = "multisig"` requires `simple_multisig`.
+///
+/// `fungible_token` cannot be combined with `nep141` or `nep148`, which it
+/// includes.
+///
+/// `crate = "..."` sets the path of `near_sdk_contract_tools` for the derives,
+/// and for every component that does not specify its own.
+///
+/// Place it before `#[near_bindgen]`, so that the derives can see it.
+#[proc_macro_attribute]
+pub fn contract_tools(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as AttributeArgs);
+    let item = parse_macro_input!(item as DeriveInput);
+
+    contract_tools::ContractToolsMeta::from_list(&attr)
+        .and_then(|meta| contract_tools::expand(meta, item))
+        .map(Into::into)
+        .unwrap_or_else(|e| e.write_errors().into())
+}
+
+/// Asserts that a contract struct is `#[near_bindgen]`, and that the given
+/// methods were processed by `#[near_bindgen]`, i.e. they will be exported
+/// when the contract is compiled to WebAssembly. Meant to be dropped into a
+/// unit test:
+///
+/// ```ignore
+/// #[test]
+/// fn externals() {
+///     assert_contract_externals!(Contract: ft_transfer, ft_metadata, own_get_owner);
+/// }
+/// ```
+///
+/// The check relies on the `ContractExt` type generated by `#[near_bindgen]`,
+/// so a missing struct attribute or method fails to compile, naming the
+/// missing item. Generic contracts cannot be `#[near_bindgen]`, so their
+/// methods are not exported, and they always fail this check.
+///
+/// The derive macros that generate external methods perform the first half
+/// of this check themselves: if `#[near_bindgen]` is not found after the
+/// `#[derive(...)]` attribute, they require `Contract::ext` to exist.
+#[proc_macro]
+pub fn assert_contract_externals(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as externals::ContractExternals);
+
+    externals::expand_assert(input).into()
+}
+
 /// Create an upgrade component. Does not expose any functions to the
 /// blockchain.
 ///
@@ -192,11 +629,28 @@ pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///     - `"none"` - Empty upgrade hook.
 ///     - `"owner"` - The upgrade function may only be called by the owner of the contract as specified by an `Owner` implementation.
 ///     - `"role(r)"` - The upgrade function may only be called by an account that has been assigned the role `r` as determined by an `Rbac` implementation.
+///     - `"owner_or_role(r)"` - The upgrade function may only be called by the owner, or by an account that has been assigned the role `r`. If neither is the case, the panic message names both failed checks.
+///     - `"expr(path::to::function)"` - Calls the function `fn(&self)` at the given path, which should panic if the upgrade is not allowed.
+///     - `"multisig"` - Requires `staged`. Staging and unstaging code may only be done by accounts authorized by the contract's `AccountAuthorizer` (e.g. from `SimpleMultisig`). `upgrade` and `deploy_staged` are not exposed: instead, `Action` is implemented for `UpgradeAction`, so the staged code is deployed by executing an approved request.
 ///  - `serializer` - `"borsh"` or `"jsonbase64"` (default). Indicates the serialization format of code the `upgrade` function will accept.
+///  - `function_name` - The name of the generated upgrade function. Default `"upgrade"`.
+///  - `private` - Flag. Marks the upgrade function `#[private]`, so it may only be called by the contract account itself (the hook is still called).
+///  - `raw_input` - Flag. Requires `serializer = "borsh"`. The upgrade function takes no parameters: the entire function call input is the code to deploy, byte-for-byte, with no length prefix or other envelope. `expected_hash` and `migrate_args` are not available in this mode. Cannot be combined with `allow_skip_migration`.
+///  - `staged` - Flag. Also exposes `stage_upgrade(code)`, `unstage_upgrade()`, and `deploy_staged()` for two-phase upgrades, which call the upgrade hook, and the views `staged_code_hash()` and `staged_upgrade()` (hash and earliest deploy timestamp). `stage_upgrade` is payable: the attached deposit must cover the storage cost of the code, and is refunded on deploy or unstage. Code too large for a single transaction can be uploaded with `stage_upgrade_chunk(index, bytes)` (payable, in any order) and staged with `finalize_staged(total_chunks, expected_hash)`; `abort_staged_upload()` discards the chunks and refunds their deposit.
+///  - `stage_delay_ns` - Minimum time in nanoseconds between staging code and deploying it. Requires `staged`. Default 0. `upgrade` is not exposed, so that all code is deployed with `deploy_staged` after the delay; `function_name`, `private`, and `raw_input` cannot be specified.
+///  - `rollback` - Flag. Requires `staged`. Records the hash of the code replaced by each upgrade, exposed as the view `previous_code_hash()`, and exposes `stage_rollback()`, which stages the previous code for redeployment (if it was retained). `stage_rollback` is payable, like `stage_upgrade`.
+///  - `max_retained_code_len` - Requires `rollback`. Code up to this many bytes is retained in storage so that it can be rolled back to. The contract pays for this storage. Default 0 (only hashes are retained).
+///  - `on_complete` - `"default"` or `"custom"`. Schedules a callback to `on_upgrade_complete(code_hash)` after each deployment, and exposes that function (`#[private]`). The callback emits `UpgradeCompleted` or `UpgradeFailed` and calls the matching method of `UpgradeCompleteHook`, which is implemented with no-ops (`"default"`) or must be explicitly provided (`"custom"`). If the upgrade succeeds, the callback runs in the new code, which must expose `on_upgrade_complete` as well. Gas for the callback is reserved in addition to `migrate_minimum_gas`.
+///  - `event_standard` - The NEP-297 standard string of the emitted `UpgradeStaged`, `UpgradeDeployed`, `UpgradeUnstaged`, `UpgradeCompleted`, and `UpgradeFailed` events. Default `"x-upgr"`.
+///  - `no_migrate` - Flag. Deploy the code without calling a migrate function afterwards, for upgrades that do not change the state schema. Cannot be combined with `migrate_*` options or `allow_skip_migration`. `upgrade` and `deploy_staged` do not accept `migrate_args`.
+///  - `allow_skip_migration` - Flag. `upgrade` and `deploy_staged` accept an additional `skip_migration: bool` parameter which, if `true`, deploys the code without calling the migrate function.
 ///  - `migrate_method_name` - The name of the method to call after the upgrade. Default `"migrate"`.
-///  - `migrate_method_args` - The input to send to the migrate function. Default empty vector.
-///  - `migrate_minimum_gas` - How much gas to guarantee the migrate function, otherwise reject. Default 15T.
+///  - `migrate_method_args` - The input to send to the migrate function. Default empty vector. Can be overridden at call time: `upgrade` and `deploy_staged` accept an optional `migrate_args` parameter (`UpgradeAction` has a `migrate_args` field). When the new code uses `#[derive(Migrate)]` with `args = "T"`, the migrate function reads its input as JSON in the form `{"args": T}`, so that is what `migrate_args` should contain. This is not checked.
+///  - `migrate_minimum_gas` - Reject the upgrade if less than this much gas remains for the migrate function. Default 15T.
+///  - `migrate_static_gas` - Attach exactly this much gas to the migrate function. By default, the migrate function receives all remaining gas.
+///  - `cfg` - `#[cfg(...)]` predicates for the exposed functions, e.g. `cfg = "not(feature = \"immutable\")"` to build an immutable contract (see: [crate-level docs](crate)).
+///  - `method_prefix` - Prefix for the names of the exposed functions, e.g. `method_prefix = "code_"` exposes `code_upgrade` (see: [crate-level docs](crate)). Cannot be combined with `on_complete`.
 #[proc_macro_derive(Upgrade, attributes(upgrade))]
 pub fn derive_upgrade(input: TokenStream) -> TokenStream {
-    make_derive(input, upgrade::expand)
+    make_external_derive(input, "upgrade", upgrade::expand)
 }
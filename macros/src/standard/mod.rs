@@ -1,6 +0,0 @@
-pub mod event;
-pub mod fungible_token;
-
-pub mod nep141;
-pub mod nep148;
-pub mod nep297;
@@ -0,0 +1,282 @@
+//! Compile tests for the code generated by the macros under strict lints:
+//! every derive and attribute is expanded here with warnings denied and
+//! `clippy::pedantic` enabled, so that users who enable them are not
+//! warned about code that they did not write.
+//!
+//! `missing_docs` (enabled for the workspace) is allowed, because
+//! `#[near_bindgen]` generates undocumented items, e.g. `ContractExt`.
+//!
+//! Only meant to be type-checked: the contracts export functions with the
+//! same names, so the crate does not build for `wasm32`.
+#![deny(warnings, clippy::pedantic)]
+#![allow(missing_docs)]
+
+/// All of the components, derived separately
+pub mod contract {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        serde::{Deserialize, Serialize},
+        BorshStorageKey, PanicOnDefault,
+    };
+    use near_sdk_contract_tools::{
+        approval::{simple_multisig::Configuration, Action, ApprovalManager},
+        event,
+        migrate::MigrateHook,
+        owner::Owner,
+        pause::Pause,
+        rbac::Rbac,
+        standard::{
+            nep141::{Nep141Hook, Nep141Transfer},
+            nep297::Event,
+        },
+        ContractMetadata, FungibleToken, Migrate, Nep245, Owner, Pause, Rbac, SimpleMultisig,
+        Upgrade,
+    };
+
+    /// Emitted when the value is reset
+    #[event(standard = "x-pedantic", version = "1.0.0", serde = "near_sdk::serde")]
+    pub struct Reset {
+        /// New value
+        pub value: u32,
+    }
+
+    /// Roles of the contract
+    #[derive(BorshSerialize, BorshStorageKey, Debug)]
+    pub enum Role {
+        /// May approve multisig requests
+        Admin,
+    }
+
+    impl std::fmt::Display for Role {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            std::fmt::Debug::fmt(self, f)
+        }
+    }
+
+    /// Actions of the multisig
+    #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+    #[serde(crate = "near_sdk::serde")]
+    pub enum ResetAction {
+        /// Resets the value to zero
+        Reset,
+    }
+
+    impl Action<Contract> for ResetAction {
+        type Output = ();
+
+        fn execute(self, contract: &mut Contract) -> Self::Output {
+            contract.value = 0;
+            Reset { value: 0 }.emit();
+        }
+    }
+
+    /// Previous version of the contract
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct ContractV1 {
+        /// Stored value
+        pub value: u32,
+    }
+
+    /// Contract with every component
+    #[derive(
+        BorshSerialize,
+        BorshDeserialize,
+        PanicOnDefault,
+        Owner,
+        Pause,
+        Rbac,
+        FungibleToken,
+        Nep245,
+        ContractMetadata,
+        SimpleMultisig,
+        Migrate,
+        Upgrade,
+    )]
+    #[rbac(roles = "Role")]
+    #[fungible_token(name = "Pedantic", symbol = "PED", decimals = 24)]
+    #[nep245(no_hooks)]
+    #[simple_multisig(action = "ResetAction", role = "Role::Admin", external)]
+    #[migrate(from = "ContractV1")]
+    #[upgrade(hook = "owner", staged, rollback)]
+    #[near_bindgen]
+    pub struct Contract {
+        /// Stored value
+        pub value: u32,
+    }
+
+    impl MigrateHook for Contract {
+        fn on_migrate(old: ContractV1) -> Self {
+            Self { value: old.value }
+        }
+    }
+
+    impl Nep141Hook for Contract {
+        fn before_transfer(&mut self, _transfer: &Nep141Transfer) {
+            Self::require_unpaused();
+        }
+    }
+
+    #[near_bindgen]
+    impl Contract {
+        /// Initializes the contract, with the predecessor as the owner and
+        /// the only admin
+        #[init]
+        #[must_use]
+        pub fn new() -> Self {
+            let mut contract = Self { value: 0 };
+
+            Owner::init(&mut contract, &env::predecessor_account_id());
+            contract.add_role(env::predecessor_account_id(), &Role::Admin);
+            <Self as ApprovalManager<_, _, _>>::init(Configuration::new(1, 0));
+
+            contract
+        }
+
+        /// Sets the value. Only the owner may call this method, and only
+        /// while the contract is unpaused.
+        pub fn set_value(&mut self, value: u32) {
+            Self::require_owner();
+            Self::require_unpaused();
+            self.value = value;
+        }
+    }
+}
+
+/// Standards with a migration chain
+pub mod chain {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        near_bindgen,
+        serde::Serialize,
+    };
+    use near_sdk_contract_tools::{standard::nep297::Event, MigrateChain, Nep141, Nep148, Nep297};
+
+    /// Emitted when the token is migrated
+    #[derive(Serialize, Nep297)]
+    #[serde(crate = "near_sdk::serde")]
+    #[nep297(standard = "x-pedantic", version = "1.0.0")]
+    pub struct Migrated;
+
+    /// Previous version of the token
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct TokenV1 {}
+
+    impl From<TokenV1> for Token {
+        fn from(_: TokenV1) -> Self {
+            Migrated.emit();
+            Self {}
+        }
+    }
+
+    /// Token with a migration chain
+    #[derive(BorshSerialize, BorshDeserialize, Nep141, Nep148, MigrateChain)]
+    #[nep141(no_hooks, hashed_keys)]
+    #[nep148(name = "Chained", symbol = "CHN", decimals = 24)]
+    #[migrate_chain(versions(TokenV1))]
+    #[near_bindgen]
+    pub struct Token {}
+}
+
+/// Components composed with `#[contract_tools]`, some of whose methods are
+/// skipped
+pub mod composed {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        near_bindgen, AccountId, BorshStorageKey, PanicOnDefault,
+    };
+    use near_sdk_contract_tools::{contract_tools, owner::Owner, upgrade::staged::UpgradeAction};
+
+    /// Roles of the contract
+    #[derive(BorshSerialize, BorshStorageKey, Debug)]
+    pub enum Role {
+        /// May approve upgrades
+        Upgrader,
+    }
+
+    impl std::fmt::Display for Role {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            std::fmt::Debug::fmt(self, f)
+        }
+    }
+
+    /// Contract upgraded by multisig
+    #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+    #[contract_tools(
+        owner(skip = "own_get_owner"),
+        pause,
+        rbac(roles = "Role"),
+        simple_multisig(action = "UpgradeAction", role = "Role::Upgrader"),
+        upgrade(hook = "multisig", staged, serializer = "borsh")
+    )]
+    #[near_bindgen]
+    pub struct Composed {}
+
+    #[near_bindgen]
+    impl Composed {
+        /// Replaces the generated `own_get_owner`
+        #[must_use]
+        pub fn own_get_owner(&self) -> Option<AccountId> {
+            Self::slot_owner().read()
+        }
+    }
+}
+
+/// Event enum with renamed variants
+pub mod events {
+    use near_sdk_contract_tools::event;
+
+    /// Events of the contract
+    #[event(
+        standard = "x-pedantic",
+        version = "1.0.0",
+        rename_all = "snake_case",
+        serde = "near_sdk::serde"
+    )]
+    pub enum ContractEvent {
+        /// The value changed
+        ValueChanged {
+            /// New value
+            value: u32,
+        },
+        /// The value was reset
+        Reset,
+    }
+}
+
+/// Upgrade by owner or role, with JSON input
+pub mod upgrade_role {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        near_bindgen, PanicOnDefault,
+    };
+    use near_sdk_contract_tools::{Owner, Rbac, Role, Upgrade};
+
+    /// Roles of the contract
+    #[derive(Role)]
+    pub enum Role {
+        /// May upgrade the contract
+        Upgrader,
+    }
+
+    /// Contract upgraded by the owner or an upgrader
+    #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Owner, Rbac, Upgrade)]
+    #[rbac(roles = "Role")]
+    #[upgrade(hook = "owner_or_role(Role::Upgrader)", serializer = "jsonbase64")]
+    #[near_bindgen]
+    pub struct Contract {}
+}
+
+/// Generic contract, whose external methods are not exported
+pub mod generic {
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk_contract_tools::{FungibleToken, Owner, Pause};
+
+    /// Generic contract
+    #[derive(BorshSerialize, BorshDeserialize, Owner, Pause, FungibleToken)]
+    #[fungible_token(name = "Generic", symbol = "GEN", decimals = 24, no_hooks)]
+    pub struct Contract<T: BorshSerialize + BorshDeserialize> {
+        /// Stored value
+        pub value: T,
+    }
+}
@@ -0,0 +1,207 @@
+//! Compile tests for the macros when `near-sdk`, `near-sdk-contract-tools`,
+//! and `near-sdk-contract-tools-macros` are renamed (to `sdk`, `tools`, and
+//! `tools_macros`), so none of the default paths resolve.
+//!
+//! `#[near_bindgen]` itself refers to `near_sdk`, so each module imports
+//! `sdk` under that name. This does not make `::near_sdk` resolve.
+//!
+//! Only meant to be type-checked: the contracts export functions with the
+//! same names, so the crate does not build for `wasm32`.
+#![allow(missing_docs)]
+
+pub mod contract {
+    use sdk as near_sdk;
+
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen, PanicOnDefault,
+    };
+    use tools::{
+        approval::{simple_multisig::Configuration, Action, ApprovalManager},
+        migrate::MigrateHook,
+        owner::Owner,
+        pause::Pause,
+        rbac::Rbac,
+        standard::nep297::Event,
+        FungibleToken, Migrate, Owner, Pause, Rbac, SimpleMultisig, Upgrade,
+    };
+
+    #[tools_macros::event(
+        standard = "x-renamed",
+        version = "1.0.0",
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk",
+        serde = "sdk::serde"
+    )]
+    pub struct Reset {
+        pub value: u32,
+    }
+
+    // `BorshStorageKey` refers to `::near_sdk`
+    #[derive(BorshSerialize)]
+    pub enum Role {
+        Admin,
+    }
+
+    impl near_sdk::IntoStorageKey for Role {
+        fn into_storage_key(self) -> Vec<u8> {
+            self.try_to_vec().unwrap()
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub enum ResetAction {
+        Reset,
+    }
+
+    impl Action<Contract> for ResetAction {
+        type Output = ();
+
+        fn execute(self, contract: &mut Contract) -> Self::Output {
+            contract.value = 0;
+            Reset { value: 0 }.emit();
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct ContractV1 {
+        pub value: u32,
+    }
+
+    #[derive(
+        BorshSerialize,
+        BorshDeserialize,
+        PanicOnDefault,
+        Owner,
+        Pause,
+        Rbac,
+        FungibleToken,
+        SimpleMultisig,
+        Migrate,
+        Upgrade,
+    )]
+    #[owner(crate = "tools", macros = "tools_macros", near_sdk = "sdk")]
+    #[pause(crate = "tools", macros = "tools_macros", near_sdk = "sdk")]
+    #[rbac(
+        roles = "Role",
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[fungible_token(
+        name = "Renamed",
+        symbol = "REN",
+        decimals = 24,
+        no_hooks,
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[simple_multisig(
+        action = "ResetAction",
+        role = "Role::Admin",
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[migrate(
+        from = "ContractV1",
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[upgrade(
+        hook = "owner",
+        staged,
+        rollback,
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[near_bindgen]
+    pub struct Contract {
+        pub value: u32,
+    }
+
+    impl MigrateHook for Contract {
+        fn on_migrate(old: ContractV1) -> Self {
+            Self { value: old.value }
+        }
+    }
+
+    #[near_bindgen]
+    impl Contract {
+        #[init]
+        pub fn new() -> Self {
+            let mut contract = Self { value: 0 };
+
+            Owner::init(&mut contract, &env::predecessor_account_id());
+            contract.add_role(env::predecessor_account_id(), &Role::Admin);
+            <Self as ApprovalManager<_, _, _>>::init(Configuration::new(1, 0));
+
+            contract
+        }
+
+        pub fn set_value(&mut self, value: u32) {
+            Self::require_owner();
+            Self::require_unpaused();
+            self.value = value;
+        }
+    }
+}
+
+pub mod chain {
+    use sdk as near_sdk;
+
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        near_bindgen,
+    };
+    use tools::{standard::nep297::Event, MigrateChain, Nep141, Nep148, Nep297};
+
+    #[derive(Nep297)]
+    #[nep297(
+        standard = "x-renamed",
+        version = "1.0.0",
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    pub struct Migrated;
+
+    impl sdk::serde::Serialize for Migrated {
+        fn serialize<S: sdk::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_unit()
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct TokenV1 {}
+
+    impl From<TokenV1> for Token {
+        fn from(_: TokenV1) -> Self {
+            Migrated.emit();
+            Self {}
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Nep141, Nep148, MigrateChain)]
+    #[nep141(no_hooks, crate = "tools", macros = "tools_macros", near_sdk = "sdk")]
+    #[nep148(
+        name = "Chained",
+        symbol = "CHN",
+        decimals = 24,
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[migrate_chain(
+        versions(TokenV1),
+        crate = "tools",
+        macros = "tools_macros",
+        near_sdk = "sdk"
+    )]
+    #[near_bindgen]
+    pub struct Token {}
+}
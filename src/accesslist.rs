@@ -0,0 +1,381 @@
+//! Allowlist or blocklist of accounts.
+//!
+//! An [`AccessList`] is a set of accounts, and a mode, chosen when the list
+//! is initialized: in [`AccessListMode::Allow`] mode only the listed
+//! accounts are permitted, and in [`AccessListMode::Deny`] mode all accounts
+//! but the listed ones are. Methods are gated with the guard methods
+//! [`AccessList::require_permitted`] and
+//! [`AccessList::require_predecessor_permitted`], as with
+//! [`Rbac::require_role`](crate::rbac::Rbac::require_role).
+//!
+//! Implementing [`Nep141AccessList`] provides an implementation of
+//! [`Nep141Hook`] that requires the sender and the receiver of NEP-141
+//! transfers to be permitted.
+//!
+//! Changes to the list are not restricted, so the contract should guard the
+//! methods that call [`AccessList::add`] and [`AccessList::remove`] (e.g.
+//! with [`Owner::require_owner`](crate::owner::Owner::require_owner)).
+//!
+//! ```
+//! use near_sdk::{near_bindgen, AccountId};
+//! use near_sdk_contract_tools::{
+//!     accesslist::{AccessList, Nep141AccessList},
+//!     owner::Owner,
+//!     FungibleToken, Owner,
+//! };
+//!
+//! #[derive(Owner, FungibleToken)]
+//! #[fungible_token(name = "Token", symbol = "TKN", decimals = 18)]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl AccessList for Contract {}
+//!
+//! // Blocked accounts can neither send nor receive tokens
+//! impl Nep141AccessList for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     pub fn block(&mut self, account_ids: Vec<AccountId>) {
+//!         Self::require_owner();
+//!         self.add_batch(account_ids);
+//!     }
+//!
+//!     pub fn unblock(&mut self, account_ids: Vec<AccountId>) {
+//!         Self::require_owner();
+//!         self.remove_batch(account_ids);
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The access list root storage slot is not used or modified. The default key is `~al`.
+//! * (ERR) [`AccessList::init`] is called once, before the list is used.
+//! * (ERR) [`AccessList::require_permitted`] may only be called with a
+//!   permitted account.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, require, AccountId, BorshStorageKey,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    slot::Slot,
+    standard::{
+        nep141::{Nep141Hook, Nep141Transfer},
+        nep297::Event,
+    },
+    DefaultStorageKey,
+};
+
+const NOT_INITIALIZED_FAIL_MESSAGE: &str = message!("ACL1", "init must be called before use");
+const ALREADY_INITIALIZED_FAIL_MESSAGE: &str = message!("ACL2", "init can only be called once");
+const NOT_ALLOWED_FAIL_MESSAGE: &str = message!("ACL3", "Account is not allowlisted");
+const DENIED_FAIL_MESSAGE: &str = message!("ACL4", "Account is blocklisted");
+
+/// Whether the listed accounts are the only permitted accounts, or the only
+/// prohibited ones
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AccessListMode {
+    /// Only the listed accounts are permitted
+    Allow,
+    /// All accounts but the listed ones are permitted
+    Deny,
+}
+
+/// Reasons an account fails an access list check
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum AccessListError {
+    /// The list is an allowlist, and the account is not listed
+    #[error("{}", NOT_ALLOWED_FAIL_MESSAGE)]
+    NotAllowlisted(AccountId),
+    /// The list is a blocklist, and the account is listed
+    #[error("{}", DENIED_FAIL_MESSAGE)]
+    Blocklisted(AccountId),
+}
+
+/// Events emitted when accounts are added to or removed from an access list
+#[event(
+    standard = "x-accesslist",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum AccessListEvent {
+    /// Emitted when accounts are added to the list
+    Add {
+        /// The added accounts
+        account_ids: Vec<AccountId>,
+    },
+    /// Emitted when accounts are removed from the list
+    Remove {
+        /// The removed accounts
+        account_ids: Vec<AccountId>,
+    },
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Mode,
+    Account(&'a AccountId),
+}
+
+/// Allowlist or blocklist of accounts
+pub trait AccessList {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::AccessList)
+    }
+
+    /// Storage slot for the mode of the list
+    fn slot_mode() -> Slot<AccessListMode> {
+        <Self as AccessList>::root().field(StorageKey::Mode)
+    }
+
+    /// Storage slot for the membership of an account. Exists if, and only
+    /// if, the account is listed.
+    fn slot_listed(account_id: &AccountId) -> Slot<bool> {
+        <Self as AccessList>::root().field(StorageKey::Account(account_id))
+    }
+
+    /// Sets the mode of the list. Can only be called once.
+    fn init(&mut self, mode: AccessListMode) {
+        require!(
+            Self::slot_mode().swap(&mode).is_none(),
+            ALREADY_INITIALIZED_FAIL_MESSAGE,
+        );
+    }
+
+    /// The mode of the list. Panics if the list has not been initialized.
+    fn mode() -> AccessListMode {
+        Self::slot_mode()
+            .read()
+            .unwrap_or_else(|| env::panic_str(NOT_INITIALIZED_FAIL_MESSAGE))
+    }
+
+    /// Returns whether the account is listed
+    fn contains(account_id: &AccountId) -> bool {
+        Self::slot_listed(account_id).exists()
+    }
+
+    /// Adds an account to the list. Emits an [`AccessListEvent::Add`] event
+    /// if it was not listed. Returns whether it was not listed.
+    fn add(&mut self, account_id: AccountId) -> bool {
+        self.add_batch(vec![account_id]) == 1
+    }
+
+    /// Removes an account from the list. Emits an [`AccessListEvent::Remove`]
+    /// event if it was listed. Returns whether it was listed.
+    fn remove(&mut self, account_id: AccountId) -> bool {
+        self.remove_batch(vec![account_id]) == 1
+    }
+
+    /// Adds accounts to the list, with a single [`AccessListEvent::Add`]
+    /// event for the accounts that were not listed. Returns the number of
+    /// accounts added.
+    fn add_batch(&mut self, account_ids: Vec<AccountId>) -> usize {
+        let account_ids = account_ids
+            .into_iter()
+            .filter(|account_id| Self::slot_listed(account_id).swap(&true).is_none())
+            .collect::<Vec<_>>();
+        let added = account_ids.len();
+
+        if added > 0 {
+            AccessListEvent::Add { account_ids }.emit();
+        }
+
+        added
+    }
+
+    /// Removes accounts from the list, with a single
+    /// [`AccessListEvent::Remove`] event for the accounts that were listed.
+    /// Returns the number of accounts removed.
+    fn remove_batch(&mut self, account_ids: Vec<AccountId>) -> usize {
+        let account_ids = account_ids
+            .into_iter()
+            .filter(|account_id| Self::slot_listed(account_id).remove())
+            .collect::<Vec<_>>();
+        let removed = account_ids.len();
+
+        if removed > 0 {
+            AccessListEvent::Remove { account_ids }.emit();
+        }
+
+        removed
+    }
+
+    /// Returns whether the account is permitted by the list
+    fn is_permitted(account_id: &AccountId) -> bool {
+        Self::check_permitted(account_id).is_ok()
+    }
+
+    /// Fallible version of [`AccessList::require_permitted`], useful for
+    /// composing with other checks
+    fn check_permitted(account_id: &AccountId) -> Result<(), AccessListError> {
+        match (Self::mode(), Self::contains(account_id)) {
+            (AccessListMode::Allow, false) => {
+                Err(AccessListError::NotAllowlisted(account_id.clone()))
+            }
+            (AccessListMode::Deny, true) => Err(AccessListError::Blocklisted(account_id.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Requires the account to be permitted by the list
+    fn require_permitted(account_id: &AccountId) {
+        Self::check_permitted(account_id).unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Requires the predecessor to be permitted by the list
+    fn require_predecessor_permitted() {
+        Self::require_permitted(&env::predecessor_account_id());
+    }
+}
+
+/// Requires the senders and the receivers of NEP-141 transfers to be
+/// permitted by the [`AccessList`]. Implementing this trait provides an
+/// implementation of [`Nep141Hook`].
+pub trait Nep141AccessList: AccessList {}
+
+/// State of the [`Nep141Hook`] provided by [`Nep141AccessList`]
+#[derive(Default, Clone, Copy, Debug)]
+pub struct AccessListChecked;
+
+impl<C: Nep141AccessList> Nep141Hook<AccessListChecked> for C {
+    fn before_transfer(&mut self, transfer: &Nep141Transfer) -> AccessListChecked {
+        Self::require_permitted(&transfer.sender_id);
+        Self::require_permitted(&transfer.receiver_id);
+        AccessListChecked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{json_types::U128, near_bindgen};
+
+    use super::{AccessList, AccessListError, AccessListMode, Nep141AccessList};
+    use crate::{
+        standard::nep141::{Nep141, Nep141Controller},
+        testing::{account, TestEnv},
+        Nep141,
+    };
+
+    #[derive(Nep141)]
+    #[nep141(crate = "crate")]
+    #[near_bindgen]
+    struct Contract {}
+
+    impl AccessList for Contract {}
+
+    impl Nep141AccessList for Contract {}
+
+    fn setup(mode: AccessListMode) -> (TestEnv, Contract) {
+        let env = TestEnv::new();
+        let mut contract = Contract {};
+
+        AccessList::init(&mut contract, mode);
+        contract.add(account("alice"));
+        contract.deposit_unchecked(&account("alice"), 100);
+        contract.deposit_unchecked(&account("bob"), 100);
+
+        (env, contract)
+    }
+
+    #[test]
+    fn allow_mode() {
+        let (_env, mut contract) = setup(AccessListMode::Allow);
+
+        assert!(Contract::is_permitted(&account("alice")));
+        assert_eq!(
+            Contract::check_permitted(&account("bob")),
+            Err(AccessListError::NotAllowlisted(account("bob"))),
+        );
+
+        assert!(contract.remove(account("alice")));
+        assert!(!contract.remove(account("alice")));
+        assert!(!Contract::is_permitted(&account("alice")));
+    }
+
+    #[test]
+    fn deny_mode() {
+        let (_env, mut contract) = setup(AccessListMode::Deny);
+
+        assert!(Contract::is_permitted(&account("bob")));
+        assert_eq!(
+            Contract::check_permitted(&account("alice")),
+            Err(AccessListError::Blocklisted(account("alice"))),
+        );
+
+        assert!(!contract.add(account("alice")));
+        assert!(contract.remove(account("alice")));
+        assert!(Contract::is_permitted(&account("alice")));
+    }
+
+    #[test]
+    fn batch() {
+        let (_env, mut contract) = setup(AccessListMode::Allow);
+
+        assert_eq!(
+            contract.add_batch(vec![account("alice"), account("bob"), account("carol")]),
+            2,
+        );
+        assert!(Contract::contains(&account("bob")));
+        assert!(Contract::contains(&account("carol")));
+
+        assert_eq!(
+            contract.remove_batch(vec![account("alice"), account("dave")]),
+            1,
+        );
+        assert!(!Contract::contains(&account("alice")));
+        assert!(!Contract::contains(&account("dave")));
+    }
+
+    #[test]
+    #[should_panic(expected = "init can only be called once")]
+    fn init_twice() {
+        let (_env, mut contract) = setup(AccessListMode::Allow);
+
+        AccessList::init(&mut contract, AccessListMode::Deny);
+    }
+
+    #[test]
+    fn transfer_permitted() {
+        let (mut env, mut contract) = setup(AccessListMode::Deny);
+
+        env.set_predecessor(&account("bob")).set_deposit(1);
+        contract.ft_transfer(account("carol"), U128(10), None);
+
+        assert_eq!(Contract::balance_of(&account("carol")), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blocklisted")]
+    fn transfer_from_blocked_sender() {
+        let (mut env, mut contract) = setup(AccessListMode::Deny);
+
+        env.set_predecessor(&account("alice")).set_deposit(1);
+        contract.ft_transfer(account("bob"), U128(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not allowlisted")]
+    fn transfer_to_unlisted_receiver() {
+        let (mut env, mut contract) = setup(AccessListMode::Allow);
+
+        env.set_predecessor(&account("alice")).set_deposit(1);
+        contract.ft_transfer(account("bob"), U128(10), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! Set of the accounts registered with the contract.
+//!
+//! Storage management, holder enumeration, and airdrops all need to know
+//! which accounts are registered. [`AccountRegistry`] keeps that set in one
+//! place, in a [`SlotMap`], so it can be listed page by page.
+//!
+//! Other components subscribe to registrations through
+//! [`AccountRegistryHook`], which the contract implements, e.g. to set up or
+//! clear the per-account state of a component.
+//!
+//! Implementing [`Nep141AccountRegistry`] provides an implementation of
+//! [`Nep141Hook`] that requires the receivers of NEP-141 transfers to be
+//! registered.
+//!
+//! Registration is not restricted, so the contract should guard the methods
+//! that call [`AccountRegistry::register`] and
+//! [`AccountRegistry::unregister`] (e.g. by requiring a storage deposit, or
+//! by only registering the predecessor).
+//!
+//! ```
+//! use near_sdk::{env, near_bindgen, AccountId};
+//! use near_sdk_contract_tools::{
+//!     account_registry::{AccountRegistry, AccountRegistryHook, Nep141AccountRegistry},
+//!     FungibleToken,
+//! };
+//!
+//! #[derive(FungibleToken)]
+//! #[fungible_token(name = "Token", symbol = "TKN", decimals = 18)]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl AccountRegistryHook for Contract {}
+//!
+//! impl AccountRegistry for Contract {}
+//!
+//! // Tokens can only be transferred to registered accounts
+//! impl Nep141AccountRegistry for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     pub fn register(&mut self) -> bool {
+//!         AccountRegistry::register(self, env::predecessor_account_id())
+//!     }
+//!
+//!     pub fn accounts(&self, offset: u32, limit: u32) -> Vec<AccountId> {
+//!         Self::registered_accounts(offset, limit)
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The account registry root storage slot is not used or modified. The default key is `~ar`.
+//! * (ERR) [`AccountRegistry::require_registered`] may only be called with a
+//!   registered account.
+//! * (ERR) [`AccountRegistryHook::on_register`] and
+//!   [`AccountRegistryHook::on_unregister`] are called on every change to
+//!   the set.
+
+use near_sdk::{env, AccountId};
+use near_sdk_contract_tools_macros::event;
+
+use crate::{
+    slot::{Slot, SlotMap},
+    standard::{
+        nep141::{Nep141Hook, Nep141Transfer},
+        nep297::Event,
+    },
+    DefaultStorageKey,
+};
+
+const NOT_REGISTERED_FAIL_MESSAGE: &str = message!("REG1", "Account is not registered");
+
+/// Events emitted when accounts are registered or unregistered
+#[event(
+    standard = "x-account-registry",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum AccountRegistryEvent {
+    /// Emitted when an account is registered
+    Register {
+        /// The registered account
+        account_id: AccountId,
+    },
+    /// Emitted when an account is unregistered
+    Unregister {
+        /// The unregistered account
+        account_id: AccountId,
+    },
+}
+
+/// Subscribes other components to changes to the [`AccountRegistry`]. Does
+/// nothing by default.
+pub trait AccountRegistryHook {
+    /// Called after an account is registered
+    fn on_register(&mut self, _account_id: &AccountId) {}
+
+    /// Called after an account is unregistered
+    fn on_unregister(&mut self, _account_id: &AccountId) {}
+}
+
+/// Set of the accounts registered with the contract
+pub trait AccountRegistry: AccountRegistryHook {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::AccountRegistry)
+    }
+
+    /// The registered accounts, with the block timestamps at which they were
+    /// registered
+    fn registry() -> SlotMap<AccountId, u64> {
+        SlotMap::new(<Self as AccountRegistry>::root())
+    }
+
+    /// Returns whether the account is registered
+    fn is_registered(account_id: &AccountId) -> bool {
+        Self::registry().contains(account_id)
+    }
+
+    /// Block timestamp at which the account was registered, if it is
+    fn registered_at(account_id: &AccountId) -> Option<u64> {
+        Self::registry().get(account_id)
+    }
+
+    /// Number of registered accounts
+    fn registered_count() -> u32 {
+        Self::registry().len()
+    }
+
+    /// At most `limit` registered accounts, starting at index `offset`.
+    /// Unregistering an account moves the last account into its index.
+    fn registered_accounts(offset: u32, limit: u32) -> Vec<AccountId> {
+        Self::registry().keys().iter(offset, limit).collect()
+    }
+
+    /// Registers an account. Emits an [`AccountRegistryEvent::Register`]
+    /// event and calls [`AccountRegistryHook::on_register`] if it was not
+    /// registered. Returns whether it was not registered.
+    fn register(&mut self, account_id: AccountId) -> bool {
+        let mut registry = Self::registry();
+        if registry.contains(&account_id) {
+            return false;
+        }
+
+        registry.insert(&account_id, &env::block_timestamp());
+        self.on_register(&account_id);
+        AccountRegistryEvent::Register { account_id }.emit();
+
+        true
+    }
+
+    /// Unregisters an account. Emits an [`AccountRegistryEvent::Unregister`]
+    /// event and calls [`AccountRegistryHook::on_unregister`] if it was
+    /// registered. Returns whether it was registered.
+    fn unregister(&mut self, account_id: AccountId) -> bool {
+        if Self::registry().remove(&account_id).is_none() {
+            return false;
+        }
+
+        self.on_unregister(&account_id);
+        AccountRegistryEvent::Unregister { account_id }.emit();
+
+        true
+    }
+
+    /// Requires the account to be registered
+    fn require_registered(account_id: &AccountId) {
+        if !Self::is_registered(account_id) {
+            env::panic_str(NOT_REGISTERED_FAIL_MESSAGE);
+        }
+    }
+}
+
+/// Requires the receivers of NEP-141 transfers to be registered with the
+/// [`AccountRegistry`]. Implementing this trait provides an implementation
+/// of [`Nep141Hook`].
+pub trait Nep141AccountRegistry: AccountRegistry {}
+
+/// State of the [`Nep141Hook`] provided by [`Nep141AccountRegistry`]
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RegistrationChecked;
+
+impl<C: Nep141AccountRegistry> Nep141Hook<RegistrationChecked> for C {
+    fn before_transfer(&mut self, transfer: &Nep141Transfer) -> RegistrationChecked {
+        Self::require_registered(&transfer.receiver_id);
+        RegistrationChecked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{json_types::U128, near_bindgen, AccountId};
+
+    use super::{AccountRegistry, AccountRegistryHook, Nep141AccountRegistry};
+    use crate::{
+        standard::nep141::{Nep141, Nep141Controller},
+        testing::{account, TestEnv},
+        Nep141,
+    };
+
+    #[derive(Nep141)]
+    #[nep141(crate = "crate")]
+    #[near_bindgen]
+    struct Contract {
+        hook_calls: Vec<(bool, AccountId)>,
+    }
+
+    impl AccountRegistryHook for Contract {
+        fn on_register(&mut self, account_id: &AccountId) {
+            self.hook_calls.push((true, account_id.clone()));
+        }
+
+        fn on_unregister(&mut self, account_id: &AccountId) {
+            self.hook_calls.push((false, account_id.clone()));
+        }
+    }
+
+    impl AccountRegistry for Contract {}
+
+    impl Nep141AccountRegistry for Contract {}
+
+    fn setup() -> (TestEnv, Contract) {
+        let env = TestEnv::new();
+        let mut contract = Contract { hook_calls: vec![] };
+
+        contract.register(account("alice"));
+        contract.deposit_unchecked(&account("alice"), 100);
+
+        (env, contract)
+    }
+
+    #[test]
+    fn register_unregister() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(1_000);
+        assert!(contract.register(account("bob")));
+        assert!(!contract.register(account("bob")));
+        assert!(Contract::is_registered(&account("bob")));
+        assert_eq!(Contract::registered_at(&account("bob")), Some(1_000));
+        assert_eq!(Contract::registered_count(), 2);
+
+        assert!(contract.unregister(account("alice")));
+        assert!(!contract.unregister(account("alice")));
+        assert!(!Contract::is_registered(&account("alice")));
+        assert_eq!(Contract::registered_at(&account("alice")), None);
+        assert_eq!(Contract::registered_count(), 1);
+
+        assert_eq!(
+            contract.hook_calls,
+            vec![
+                (true, account("alice")),
+                (true, account("bob")),
+                (false, account("alice")),
+            ],
+        );
+    }
+
+    #[test]
+    fn pagination() {
+        let (_env, mut contract) = setup();
+
+        for name in ["bob", "carol", "dave", "erin"] {
+            contract.register(account(name));
+        }
+
+        assert_eq!(
+            Contract::registered_accounts(0, 3),
+            vec![account("alice"), account("bob"), account("carol")],
+        );
+        assert_eq!(
+            Contract::registered_accounts(3, 3),
+            vec![account("dave"), account("erin")],
+        );
+        assert!(Contract::registered_accounts(5, 3).is_empty());
+
+        // The last account takes the place of the unregistered one
+        contract.unregister(account("bob"));
+        assert_eq!(
+            Contract::registered_accounts(0, 10),
+            vec![
+                account("alice"),
+                account("erin"),
+                account("carol"),
+                account("dave")
+            ],
+        );
+    }
+
+    #[test]
+    fn transfer_to_registered() {
+        let (mut env, mut contract) = setup();
+        contract.register(account("bob"));
+
+        env.set_predecessor(&account("alice")).set_deposit(1);
+        contract.ft_transfer(account("bob"), U128(10), None);
+
+        assert_eq!(Contract::balance_of(&account("bob")), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not registered")]
+    fn transfer_to_unregistered() {
+        let (mut env, mut contract) = setup();
+
+        env.set_predecessor(&account("alice")).set_deposit(1);
+        contract.ft_transfer(account("bob"), U128(10), None);
+    }
+}
@@ -4,19 +4,83 @@ use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     env, require, AccountId, BorshStorageKey,
 };
+use near_sdk_contract_tools_macros::event;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{slot::Slot, DefaultStorageKey};
+use crate::{
+    slot::{Slot, TrackedNamespace},
+    standard::nep297::Event,
+    DefaultStorageKey,
+};
 
 /// Error message emitted when the component is used before it is initialized
-pub const NOT_INITIALIZED: &str = "init must be called before use";
+pub const NOT_INITIALIZED: &str = message!("APP1", "init must be called before use");
 /// Error message emitted when the init function is called multiple times
-pub const ALREADY_INITIALIZED: &str = "init can only be called once";
+pub const ALREADY_INITIALIZED: &str = message!("APP2", "init can only be called once");
+/// Error message emitted when too many requests are approved in one call
+pub const BATCH_TOO_LARGE: &str = message!("APP3", "Too many requests in batch");
+
+/// Maximum number of requests that may be approved in a single call to
+/// [`ApprovalManager::approve_requests`]
+pub const MAX_BATCH_APPROVALS: usize = 32;
+
+/// Maximum number of children of the namespace of a request (see:
+/// [`ApprovalManager::request_namespace`]), so that removing a request takes
+/// bounded gas
+pub const MAX_REQUEST_NAMESPACE_CHILDREN: u32 = 16;
 
 pub mod native_transaction_action;
 pub mod simple_multisig;
 
+/// Events emitted by an [`ApprovalManager`]
+#[event(
+    standard = "x-appr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum ApprovalEvent {
+    /// Emitted when an account successfully approves a request
+    Approve {
+        /// ID of the approved request
+        request_id: u32,
+        /// Account to which the approval is attributed
+        account_id: AccountId,
+        /// Account that submitted the approval on behalf of `account_id`, if
+        /// the approval was made through a delegation
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delegate_id: Option<AccountId>,
+    },
+    /// Emitted when an account delegates its approval power to another
+    Delegate {
+        /// Account delegating its approval power
+        delegator_id: AccountId,
+        /// Account receiving the approval power
+        delegate_id: AccountId,
+        /// Network timestamp after which the delegation is no longer valid
+        expires_at_nanoseconds: u64,
+    },
+    /// Emitted when a delegation is revoked by the delegator
+    RevokeDelegation {
+        /// Account that had delegated its approval power
+        delegator_id: AccountId,
+        /// Account that is no longer able to approve on behalf of the delegator
+        delegate_id: AccountId,
+    },
+    /// Emitted when a fallible action fails to execute. The request is
+    /// retained and may be executed again or removed.
+    ExecutionFailure {
+        /// ID of the request that failed to execute
+        request_id: u32,
+        /// Number of failed execution attempts for this request so far
+        failure_count: u32,
+        /// Error message produced by the failed attempt
+        error: String,
+    },
+}
+
 /// Actions can be executed after they are approved
 pub trait Action<Cont: ?Sized> {
     /// Return type of the action. Useful if the action creates a `Promise`, for example.
@@ -25,6 +89,48 @@ pub trait Action<Cont: ?Sized> {
     fn execute(self, contract: &mut Cont) -> Self::Output;
 }
 
+/// Actions that do not need access to the contract when they are executed.
+/// Wrap in [`Stateless`] to use as an [`Action`] for any contract.
+pub trait StatelessAction {
+    /// Return type of the action
+    type Output;
+    /// Perform the action. One time only.
+    fn execute(self) -> Self::Output;
+}
+
+/// Adapts a [`StatelessAction`] into an [`Action`] for any contract. Has the
+/// same serialized representation as the wrapped action.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Stateless<T>(pub T);
+
+impl<T: BorshSerialize> BorshSerialize for Stateless<T> {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for Stateless<T> {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        T::deserialize(buf).map(Self)
+    }
+}
+
+impl<T> From<T> for Stateless<T> {
+    fn from(action: T) -> Self {
+        Self(action)
+    }
+}
+
+impl<C: ?Sized, T: StatelessAction> Action<C> for Stateless<T> {
+    type Output = T::Output;
+
+    fn execute(self, _contract: &mut C) -> Self::Output {
+        self.0.execute()
+    }
+}
+
 /// Defines the operating parameters for an ApprovalManager and performs
 /// approvals
 pub trait ApprovalConfiguration<A, S> {
@@ -53,17 +159,55 @@ pub trait ApprovalConfiguration<A, S> {
         action_request: &ActionRequest<A, S>,
     ) -> Result<(), Self::AuthorizationError>;
 
+    /// Is the account allowed to delegate its approval power? Approvals by
+    /// the delegate are still authorized against the delegator, per request.
+    fn is_account_authorized_to_delegate(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<(), Self::AuthorizationError>;
+
     /// Modify action_request.approval_state in-place to increase approval
     fn try_approve_with_authorized_account(
         &self,
         account_id: AccountId,
         action_request: &mut ActionRequest<A, S>,
     ) -> Result<(), Self::ApprovalError>;
+
+    /// Called once when a request is created, before it is stored. Allows the
+    /// configuration to record information in the approval state, e.g. a
+    /// snapshot of the configuration itself.
+    fn prepare_request(&self, _action_request: &mut ActionRequest<A, S>) {}
+}
+
+/// Approval states that keep track of which accounts have approved a request
+pub trait ApproverList {
+    /// Accounts that have approved the request so far, in order of approval
+    fn approvers(&self) -> &[AccountId];
+}
+
+/// Configurations that can report how far a request is from being approved
+pub trait ApprovalProgress<A, S>: ApprovalConfiguration<A, S> {
+    /// Number of additional approvals the request requires before it may be
+    /// executed
+    fn approvals_remaining(&self, action_request: &ActionRequest<A, S>) -> u8;
+}
+
+/// Read-only overview of the approval progress of a request
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct RequestSummary {
+    /// Accounts that have approved the request so far
+    pub approvers: Vec<AccountId>,
+    /// Number of additional approvals required
+    pub approvals_remaining: u8,
+    /// Whether the request may currently be executed
+    pub is_approved_for_execution: bool,
 }
 
 /// An action request is composed of an action that will be executed when the
 /// associated approval state is satisfied
 #[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
 pub struct ActionRequest<A, S> {
     /// The action that will be executed when the approval state is
     /// fulfilled
@@ -72,11 +216,61 @@ pub struct ActionRequest<A, S> {
     pub approval_state: S,
 }
 
+/// Temporary transfer of approval power from one account (the delegator) to
+/// another (the delegate)
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Delegation {
+    /// Account that may approve requests on behalf of the delegator
+    pub delegate_id: AccountId,
+    /// Network timestamp after which the delegation is no longer valid
+    pub expires_at_nanoseconds: u64,
+}
+
+/// Tracks attempts to execute a request using
+/// [`ApprovalManager::try_execute_request`] or
+/// [`ApprovalManager::try_execute_request_deferred`]
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq,
+)]
+pub struct ExecutionStatus {
+    /// Number of failed execution attempts
+    pub failure_count: u32,
+    /// Error message of the most recent failed attempt
+    pub last_error: Option<String>,
+    /// A deferred execution is in progress and has not yet been resolved
+    pub pending: bool,
+}
+
+impl ExecutionStatus {
+    fn record_failure(&mut self, request_id: u32, error: String) {
+        self.failure_count += 1;
+        self.last_error = Some(error.clone());
+
+        ApprovalEvent::ExecutionFailure {
+            request_id,
+            failure_count: self.failure_count,
+            error,
+        }
+        .emit();
+    }
+}
+
+impl Delegation {
+    /// Returns `true` if the delegation has not yet expired
+    pub fn is_active(&self) -> bool {
+        env::block_timestamp() < self.expires_at_nanoseconds
+    }
+}
+
 #[derive(BorshSerialize, BorshStorageKey)]
-enum ApprovalStorageKey {
+enum ApprovalStorageKey<'a> {
     NextRequestId,
     Config,
     Request(u32),
+    Delegation(&'a AccountId),
+    DelegatorOf(&'a AccountId),
+    ExecutionStatus(u32),
+    RequestNamespace(u32),
 }
 
 /// The account is ineligile to perform an action for some reason
@@ -87,6 +281,9 @@ pub struct UnauthorizedAccountError<AuthErr>(AccountId, AuthErr);
 /// Top-level errors that may occur when attempting to approve a request
 #[derive(Error, Clone, Debug)]
 pub enum ApprovalError<AuthErr, AppErr> {
+    /// No request exists with the given ID
+    #[error("Request not found: {0}")]
+    RequestNotFound(u32),
     /// The account is not allowed to act on requests
     #[error(transparent)]
     UnauthorizedAccount(#[from] UnauthorizedAccountError<AuthErr>),
@@ -95,6 +292,34 @@ pub enum ApprovalError<AuthErr, AppErr> {
     ApprovalError(AppErr),
 }
 
+/// Outcome of approving a single request in a batch
+pub type BatchApprovalResult<AuthErr, AppErr> = Result<(), ApprovalError<AuthErr, AppErr>>;
+
+/// Errors that may occur when trying to delegate approval power
+#[derive(Error, Clone, Debug)]
+pub enum DelegationError {
+    /// Only accounts that may approve requests may delegate
+    #[error("Account '{0}' is not authorized to delegate approvals")]
+    UnauthorizedDelegator(AccountId),
+    /// An account cannot delegate to itself
+    #[error("Cannot delegate to self")]
+    SelfDelegation,
+    /// The delegation would already be expired
+    #[error("Delegation expiry must be in the future")]
+    AlreadyExpired,
+    /// The delegate is already acting on behalf of another account
+    #[error("Account '{0}' is already a delegate for another account")]
+    DelegateUnavailable(AccountId),
+}
+
+/// Errors that may occur when trying to revoke a delegation
+#[derive(Error, Clone, Debug)]
+pub enum RevocationError {
+    /// The account has no active delegation
+    #[error("No active delegation")]
+    NoActiveDelegation,
+}
+
 /// Errors that may occur when trying to execute a request
 #[derive(Error, Clone, Debug)]
 pub enum ExecutionError<AuthErr, ExecErr> {
@@ -104,6 +329,22 @@ pub enum ExecutionError<AuthErr, ExecErr> {
     /// Unapproved requests cannot be executed
     #[error("Request not approved: {0}")]
     ExecutionEligibility(ExecErr),
+    /// A deferred execution of the request has not yet been resolved
+    #[error("Execution pending: {0}")]
+    ExecutionPending(u32),
+}
+
+/// Errors that may occur when trying to execute a request with a fallible
+/// action
+#[derive(Error, Clone, Debug)]
+pub enum TryExecutionError<AuthErr, ExecErr, ActErr> {
+    /// The request could not be executed
+    #[error(transparent)]
+    Execution(#[from] ExecutionError<AuthErr, ExecErr>),
+    /// The action was executed and returned an error. The request has been
+    /// retained.
+    #[error("Action failed: {0}")]
+    ActionFailed(ActErr),
 }
 
 /// Errors that may occur when trying to create a request
@@ -123,6 +364,9 @@ pub enum RemovalError<AuthErr, RemErr> {
     /// This request is not (yet?) allowed to be removed
     #[error("Removal not allowed: {0}")]
     RemovalNotAllowed(RemErr),
+    /// A deferred execution of the request has not yet been resolved
+    #[error("Execution pending: {0}")]
+    ExecutionPending(u32),
 }
 
 /// Collection of action requests that manages their approval state and
@@ -135,7 +379,7 @@ where
 {
     /// Storage root
     fn root() -> Slot<()> {
-        Slot::new(DefaultStorageKey::ApprovalManager)
+        Slot::root(DefaultStorageKey::ApprovalManager)
     }
 
     /// Because requests will be deleted from the requests collection,
@@ -167,6 +411,146 @@ where
         Self::slot_request(request_id).read()
     }
 
+    /// Storage slot for the execution status of a request
+    fn slot_execution_status(request_id: u32) -> Slot<ExecutionStatus> {
+        Self::root().field(ApprovalStorageKey::ExecutionStatus(request_id))
+    }
+
+    /// Get the execution status of a request, if a fallible execution of the
+    /// request has been attempted
+    fn get_execution_status(request_id: u32) -> Option<ExecutionStatus> {
+        Self::slot_execution_status(request_id).read()
+    }
+
+    /// Namespace for any further data kept per request, e.g. by an action or
+    /// a configuration. Its children are removed along with the request, so
+    /// there may be at most [`MAX_REQUEST_NAMESPACE_CHILDREN`] of them.
+    fn request_namespace(request_id: u32) -> TrackedNamespace {
+        TrackedNamespace::with_capacity(
+            Self::root().field(ApprovalStorageKey::RequestNamespace(request_id)),
+            MAX_REQUEST_NAMESPACE_CHILDREN,
+        )
+    }
+
+    /// Removes a request, its execution status, and its namespace (see:
+    /// [`ApprovalManager::request_namespace`]) from storage
+    fn remove_request_unchecked(request_id: u32) {
+        Self::slot_request(request_id).remove();
+        Self::slot_execution_status(request_id).remove();
+
+        Self::request_namespace(request_id).remove_all(MAX_REQUEST_NAMESPACE_CHILDREN);
+    }
+
+    /// Storage slot for the delegation made by an account
+    fn slot_delegation(delegator_id: &AccountId) -> Slot<Delegation> {
+        Self::root().field_ref(&ApprovalStorageKey::Delegation(delegator_id))
+    }
+
+    /// Storage slot for the reverse mapping from a delegate to its delegator
+    fn slot_delegator_of(delegate_id: &AccountId) -> Slot<AccountId> {
+        Self::root().field_ref(&ApprovalStorageKey::DelegatorOf(delegate_id))
+    }
+
+    /// Returns the delegation made by the account, if it exists and has not
+    /// expired
+    fn get_delegation(delegator_id: &AccountId) -> Option<Delegation> {
+        Self::slot_delegation(delegator_id)
+            .read()
+            .filter(Delegation::is_active)
+    }
+
+    /// Returns the account on whose behalf the given delegate is currently
+    /// allowed to approve requests, if any
+    fn delegator_of(delegate_id: &AccountId) -> Option<AccountId> {
+        Self::slot_delegator_of(delegate_id)
+            .read()
+            .filter(|delegator_id| {
+                Self::get_delegation(delegator_id)
+                    .map_or(false, |delegation| &delegation.delegate_id == delegate_id)
+            })
+    }
+
+    /// Delegates the approval power of the predecessor to `delegate_id` until
+    /// the network timestamp reaches `expires_at_nanoseconds`. Replaces any
+    /// existing delegation made by the predecessor.
+    ///
+    /// While the delegation is active, approvals submitted by the delegate are
+    /// attributed to the delegator, and the delegator (not the delegate) must
+    /// pass the configured authorization check, unless the delegate is
+    /// authorized itself, in which case they are its own.
+    ///
+    /// The predecessor must pass
+    /// [`ApprovalConfiguration::is_account_authorized_to_delegate`].
+    ///
+    /// Emits an `ApprovalEvent::Delegate` event.
+    fn delegate_approval(
+        &mut self,
+        delegate_id: AccountId,
+        expires_at_nanoseconds: u64,
+    ) -> Result<(), DelegationError> {
+        let predecessor = env::predecessor_account_id();
+
+        if Self::get_config()
+            .is_account_authorized_to_delegate(&predecessor)
+            .is_err()
+        {
+            return Err(DelegationError::UnauthorizedDelegator(predecessor));
+        }
+
+        if predecessor == delegate_id {
+            return Err(DelegationError::SelfDelegation);
+        }
+
+        if expires_at_nanoseconds <= env::block_timestamp() {
+            return Err(DelegationError::AlreadyExpired);
+        }
+
+        if Self::delegator_of(&delegate_id).map_or(false, |d| d != predecessor) {
+            return Err(DelegationError::DelegateUnavailable(delegate_id));
+        }
+
+        if let Some(previous) = Self::slot_delegation(&predecessor).read() {
+            Self::slot_delegator_of(&previous.delegate_id).remove();
+        }
+
+        Self::slot_delegation(&predecessor).write(&Delegation {
+            delegate_id: delegate_id.clone(),
+            expires_at_nanoseconds,
+        });
+        Self::slot_delegator_of(&delegate_id).write(&predecessor);
+
+        ApprovalEvent::Delegate {
+            delegator_id: predecessor,
+            delegate_id,
+            expires_at_nanoseconds,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    /// Revokes the active delegation made by the predecessor.
+    ///
+    /// Emits an `ApprovalEvent::RevokeDelegation` event.
+    fn revoke_delegation(&mut self) -> Result<(), RevocationError> {
+        let predecessor = env::predecessor_account_id();
+
+        let delegation = Self::slot_delegation(&predecessor)
+            .take()
+            .filter(Delegation::is_active)
+            .ok_or(RevocationError::NoActiveDelegation)?;
+
+        Self::slot_delegator_of(&delegation.delegate_id).remove();
+
+        ApprovalEvent::RevokeDelegation {
+            delegator_id: predecessor,
+            delegate_id: delegation.delegate_id,
+        }
+        .emit();
+
+        Ok(())
+    }
+
     /// Must be called before using the Approval construct. Can only be called
     /// once.
     fn init(config: C) {
@@ -184,7 +568,7 @@ where
     ) -> Result<u32, CreationError<C::AuthorizationError>> {
         let request_id = Self::slot_next_request_id().read().unwrap_or(0);
 
-        let request = ActionRequest {
+        let mut request = ActionRequest {
             action,
             approval_state,
         };
@@ -196,6 +580,8 @@ where
             .is_account_authorized(&predecessor, &request)
             .map_err(|e| UnauthorizedAccountError(predecessor, e))?;
 
+        config.prepare_request(&mut request);
+
         Self::slot_next_request_id().write(&(request_id + 1));
         Self::slot_request(request_id).write(&request);
 
@@ -212,22 +598,145 @@ where
         Self::is_approved_for_execution(request_id)
             .map_err(ExecutionError::ExecutionEligibility)?;
 
+        if Self::slot_execution_status(request_id)
+            .read()
+            .map_or(false, |status| status.pending)
+        {
+            return Err(ExecutionError::ExecutionPending(request_id));
+        }
+
         let predecessor = env::predecessor_account_id();
         let config = Self::get_config();
 
-        let mut request_slot = Self::slot_request(request_id);
-        let request = request_slot.read().unwrap();
+        let request = Self::slot_request(request_id).read().unwrap();
 
         config
             .is_account_authorized(&predecessor, &request)
             .map_err(|e| UnauthorizedAccountError(predecessor, e))?;
 
         let result = request.action.execute(self);
-        request_slot.remove();
+        Self::remove_request_unchecked(request_id);
 
         Ok(result)
     }
 
+    /// Executes a request whose action may fail. If the action returns
+    /// `Err`, the request and its approvals are retained so that execution
+    /// can be retried or the request removed, the failure is recorded in the
+    /// request's [`ExecutionStatus`], and an
+    /// [`ApprovalEvent::ExecutionFailure`] event is emitted. Note that the
+    /// failed attempt is not rolled back, so actions should return `Err`
+    /// before making any changes to contract state.
+    ///
+    /// If the action creates a `Promise`, a successful return only means that
+    /// the promise was created; use
+    /// [`ApprovalManager::try_execute_request_deferred`] to retain the request
+    /// until the promise resolves.
+    fn try_execute_request<T, E>(
+        &mut self,
+        request_id: u32,
+    ) -> Result<T, TryExecutionError<C::AuthorizationError, C::ExecutionEligibilityError, E>>
+    where
+        A: Action<Self, Output = Result<T, E>>,
+        E: ToString,
+    {
+        Self::is_approved_for_execution(request_id)
+            .map_err(ExecutionError::ExecutionEligibility)?;
+
+        let mut status_slot = Self::slot_execution_status(request_id);
+        let mut status = status_slot.read().unwrap_or_default();
+        if status.pending {
+            return Err(ExecutionError::ExecutionPending(request_id).into());
+        }
+
+        let predecessor = env::predecessor_account_id();
+        let config = Self::get_config();
+
+        let request = Self::slot_request(request_id).read().unwrap();
+
+        config
+            .is_account_authorized(&predecessor, &request)
+            .map_err(|e| ExecutionError::from(UnauthorizedAccountError(predecessor, e)))?;
+
+        match request.action.execute(self) {
+            Ok(output) => {
+                Self::remove_request_unchecked(request_id);
+                Ok(output)
+            }
+            Err(e) => {
+                status.record_failure(request_id, e.to_string());
+                status_slot.write(&status);
+                Err(TryExecutionError::ActionFailed(e))
+            }
+        }
+    }
+
+    /// Executes a request whose action completes asynchronously, e.g. by
+    /// creating a `Promise`. Synchronous failures are handled as in
+    /// [`ApprovalManager::try_execute_request`]. On success, the request is
+    /// retained and marked as pending, which prevents it from being executed
+    /// again or removed until the contract calls
+    /// [`ApprovalManager::resolve_request_execution`], usually from the
+    /// callback of the returned promise.
+    fn try_execute_request_deferred<T, E>(
+        &mut self,
+        request_id: u32,
+    ) -> Result<T, TryExecutionError<C::AuthorizationError, C::ExecutionEligibilityError, E>>
+    where
+        A: Action<Self, Output = Result<T, E>>,
+        E: ToString,
+    {
+        Self::is_approved_for_execution(request_id)
+            .map_err(ExecutionError::ExecutionEligibility)?;
+
+        let mut status_slot = Self::slot_execution_status(request_id);
+        let mut status = status_slot.read().unwrap_or_default();
+        if status.pending {
+            return Err(ExecutionError::ExecutionPending(request_id).into());
+        }
+
+        let predecessor = env::predecessor_account_id();
+        let config = Self::get_config();
+
+        let request = Self::slot_request(request_id).read().unwrap();
+
+        config
+            .is_account_authorized(&predecessor, &request)
+            .map_err(|e| ExecutionError::from(UnauthorizedAccountError(predecessor, e)))?;
+
+        let result = request.action.execute(self);
+
+        match result {
+            Ok(_) => status.pending = true,
+            Err(ref e) => status.record_failure(request_id, e.to_string()),
+        }
+        status_slot.write(&status);
+
+        result.map_err(TryExecutionError::ActionFailed)
+    }
+
+    /// Resolves a pending execution started by
+    /// [`ApprovalManager::try_execute_request_deferred`]. On success, the
+    /// request is removed. On failure, the request is retained and the
+    /// failure is recorded as in [`ApprovalManager::try_execute_request`].
+    /// Panics if the request does not have a pending execution.
+    fn resolve_request_execution(&mut self, request_id: u32, result: Result<(), String>) {
+        let mut status_slot = Self::slot_execution_status(request_id);
+        let mut status = status_slot
+            .read()
+            .filter(|status| status.pending)
+            .unwrap_or_else(|| env::panic_str(&format!("No pending execution: {request_id}")));
+
+        match result {
+            Ok(()) => Self::remove_request_unchecked(request_id),
+            Err(error) => {
+                status.pending = false;
+                status.record_failure(request_id, error);
+                status_slot.write(&status);
+            }
+        }
+    }
+
     /// Is the given request ID able to be executed if such a request were to
     /// be initiated by an authorized account?
     fn is_approved_for_execution(request_id: u32) -> Result<(), C::ExecutionEligibilityError> {
@@ -237,38 +746,118 @@ where
         config.is_approved_for_execution(&request)
     }
 
+    /// Accounts that have approved the request so far. Returns `None` if the
+    /// request does not exist.
+    fn approvers_of(request_id: u32) -> Option<Vec<AccountId>>
+    where
+        S: ApproverList,
+    {
+        Self::get_request(request_id).map(|request| request.approval_state.approvers().to_vec())
+    }
+
+    /// Number of additional approvals the request requires before it may be
+    /// executed. Returns `None` if the request does not exist.
+    fn approvals_remaining(request_id: u32) -> Option<u8>
+    where
+        C: ApprovalProgress<A, S>,
+    {
+        Self::get_request(request_id)
+            .map(|request| Self::get_config().approvals_remaining(&request))
+    }
+
+    /// Summarizes the approval progress of a request. Returns `None` if the
+    /// request does not exist.
+    fn request_summary(request_id: u32) -> Option<RequestSummary>
+    where
+        S: ApproverList,
+        C: ApprovalProgress<A, S>,
+    {
+        let request = Self::get_request(request_id)?;
+        let config = Self::get_config();
+
+        Some(RequestSummary {
+            approvers: request.approval_state.approvers().to_vec(),
+            approvals_remaining: config.approvals_remaining(&request),
+            is_approved_for_execution: config.is_approved_for_execution(&request).is_ok(),
+        })
+    }
+
     /// Tries to approve the action request designated by the given request ID
-    /// with the given arguments. Panics if the request ID does not exist.
+    /// with the given arguments. If the predecessor is not authorized itself,
+    /// but is the delegate of an active delegation, the approval is
+    /// attributed to the delegator. Emits an `ApprovalEvent::Approve` event
+    /// on success.
     fn approve_request(
         &mut self,
         request_id: u32,
     ) -> Result<(), ApprovalError<C::AuthorizationError, C::ApprovalError>> {
         let mut request_slot = Self::slot_request(request_id);
-        let mut request = request_slot.read().unwrap();
+        let mut request = request_slot
+            .read()
+            .ok_or(ApprovalError::RequestNotFound(request_id))?;
 
         let predecessor = env::predecessor_account_id();
         let config = Self::get_config();
 
+        let approver_id = match Self::delegator_of(&predecessor) {
+            Some(delegator_id)
+                if config
+                    .is_account_authorized(&predecessor, &request)
+                    .is_err() =>
+            {
+                delegator_id
+            }
+            _ => predecessor.clone(),
+        };
+
         config
-            .is_account_authorized(&predecessor, &request)
-            .map_err(|e| UnauthorizedAccountError(predecessor.clone(), e))?;
+            .is_account_authorized(&approver_id, &request)
+            .map_err(|e| UnauthorizedAccountError(approver_id.clone(), e))?;
 
         config
-            .try_approve_with_authorized_account(predecessor, &mut request)
+            .try_approve_with_authorized_account(approver_id.clone(), &mut request)
             .map_err(ApprovalError::ApprovalError)?;
 
         request_slot.write(&request);
 
+        let delegate_id = (approver_id != predecessor).then_some(predecessor);
+
+        ApprovalEvent::Approve {
+            request_id,
+            account_id: approver_id,
+            delegate_id,
+        }
+        .emit();
+
         Ok(())
     }
 
+    /// Approves each of the given requests independently. A failure to
+    /// approve one request does not prevent the approval of the others.
+    /// Returns the outcome of each approval, in the same order as the
+    /// provided IDs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than [`MAX_BATCH_APPROVALS`] request IDs are provided.
+    fn approve_requests(
+        &mut self,
+        request_ids: Vec<u32>,
+    ) -> Vec<BatchApprovalResult<C::AuthorizationError, C::ApprovalError>> {
+        require!(request_ids.len() <= MAX_BATCH_APPROVALS, BATCH_TOO_LARGE);
+
+        request_ids
+            .into_iter()
+            .map(|request_id| self.approve_request(request_id))
+            .collect()
+    }
+
     /// Tries to remove the action request indicated by request_id.
     fn remove_request(
         &mut self,
         request_id: u32,
     ) -> Result<(), RemovalError<C::AuthorizationError, C::RemovalError>> {
-        let mut request_slot = Self::slot_request(request_id);
-        let request = request_slot.read().unwrap();
+        let request = Self::slot_request(request_id).read().unwrap();
         let predecessor = env::predecessor_account_id();
 
         let config = Self::get_config();
@@ -281,7 +870,14 @@ where
             .is_account_authorized(&predecessor, &request)
             .map_err(|e| UnauthorizedAccountError(predecessor, e))?;
 
-        request_slot.remove();
+        if Self::slot_execution_status(request_id)
+            .read()
+            .map_or(false, |status| status.pending)
+        {
+            return Err(RemovalError::ExecutionPending(request_id));
+        }
+
+        Self::remove_request_unchecked(request_id);
 
         Ok(())
     }
@@ -300,7 +896,10 @@ mod tests {
 
     use crate::{rbac::Rbac, slot::Slot};
 
-    use super::{Action, ActionRequest, ApprovalConfiguration, ApprovalManager};
+    use super::{
+        Action, ActionRequest, ApprovalConfiguration, ApprovalError, ApprovalManager,
+        ExecutionError, ExecutionStatus, TryExecutionError,
+    };
 
     #[derive(BorshSerialize, BorshStorageKey)]
     enum Role {
@@ -363,7 +962,7 @@ mod tests {
         pub approved_by: Vec<AccountId>,
     }
 
-    impl ApprovalConfiguration<MyAction, MultisigApprovalState> for MultisigConfig {
+    impl<A> ApprovalConfiguration<A, MultisigApprovalState> for MultisigConfig {
         type ApprovalError = String;
         type RemovalError = ();
         type AuthorizationError = String;
@@ -371,7 +970,7 @@ mod tests {
 
         fn is_approved_for_execution(
             &self,
-            action_request: &super::ActionRequest<MyAction, MultisigApprovalState>,
+            action_request: &super::ActionRequest<A, MultisigApprovalState>,
         ) -> Result<(), Self::ExecutionEligibilityError> {
             let valid_signatures = action_request
                 .approval_state
@@ -391,7 +990,7 @@ mod tests {
 
         fn is_removable(
             &self,
-            _action_request: &super::ActionRequest<MyAction, MultisigApprovalState>,
+            _action_request: &super::ActionRequest<A, MultisigApprovalState>,
         ) -> Result<(), Self::RemovalError> {
             Ok(())
         }
@@ -399,7 +998,18 @@ mod tests {
         fn is_account_authorized(
             &self,
             account_id: &AccountId,
-            _action_request: &ActionRequest<MyAction, MultisigApprovalState>,
+            _action_request: &ActionRequest<A, MultisigApprovalState>,
+        ) -> Result<(), Self::AuthorizationError> {
+            if Contract::has_role(account_id, &Role::Multisig) {
+                Ok(())
+            } else {
+                Err("Account is missing Multisig role".to_string())
+            }
+        }
+
+        fn is_account_authorized_to_delegate(
+            &self,
+            account_id: &AccountId,
         ) -> Result<(), Self::AuthorizationError> {
             if Contract::has_role(account_id, &Role::Multisig) {
                 Ok(())
@@ -411,7 +1021,7 @@ mod tests {
         fn try_approve_with_authorized_account(
             &self,
             account_id: AccountId,
-            action_request: &mut ActionRequest<MyAction, MultisigApprovalState>,
+            action_request: &mut ActionRequest<A, MultisigApprovalState>,
         ) -> Result<(), Self::ApprovalError> {
             if action_request
                 .approval_state
@@ -433,6 +1043,129 @@ mod tests {
         testing_env!(context.build());
     }
 
+    struct SayHowdy;
+
+    impl super::StatelessAction for SayHowdy {
+        type Output = &'static str;
+
+        fn execute(self) -> Self::Output {
+            "howdy"
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    struct SetValue(u32);
+
+    impl Action<FallibleContract> for SetValue {
+        type Output = Result<u32, String>;
+
+        fn execute(self, contract: &mut FallibleContract) -> Self::Output {
+            if contract.locked {
+                return Err("Contract is locked".to_string());
+            }
+
+            contract.value = self.0;
+            Ok(self.0)
+        }
+    }
+
+    struct FallibleContract {
+        locked: bool,
+        value: u32,
+    }
+
+    impl ApprovalManager<SetValue, MultisigApprovalState, MultisigConfig> for FallibleContract {
+        fn root() -> Slot<()> {
+            Slot::new(b"f")
+        }
+    }
+
+    fn fallible_contract_with_approved_request() -> (FallibleContract, u32) {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        Contract {}.add_role(alice.clone(), &Role::Multisig);
+        <FallibleContract as ApprovalManager<_, _, _>>::init(MultisigConfig { threshold: 1 });
+
+        let mut contract = FallibleContract {
+            locked: true,
+            value: 0,
+        };
+
+        predecessor(&alice);
+        let request_id = contract
+            .create_request(SetValue(5), Default::default())
+            .unwrap();
+        contract.approve_request(request_id).unwrap();
+
+        (contract, request_id)
+    }
+
+    #[test]
+    fn failed_execution_is_retried() {
+        let (mut contract, request_id) = fallible_contract_with_approved_request();
+
+        assert!(matches!(
+            contract.try_execute_request(request_id),
+            Err(TryExecutionError::ActionFailed(e)) if e == "Contract is locked",
+        ));
+        assert!(FallibleContract::get_request(request_id).is_some());
+        assert_eq!(
+            FallibleContract::get_execution_status(request_id),
+            Some(ExecutionStatus {
+                failure_count: 1,
+                last_error: Some("Contract is locked".to_string()),
+                pending: false,
+            }),
+        );
+
+        contract.locked = false;
+
+        assert_eq!(contract.try_execute_request(request_id).unwrap(), 5);
+        assert_eq!(contract.value, 5);
+        assert!(FallibleContract::get_request(request_id).is_none());
+        assert!(FallibleContract::get_execution_status(request_id).is_none());
+    }
+
+    #[test]
+    fn deferred_execution() {
+        let (mut contract, request_id) = fallible_contract_with_approved_request();
+        contract.locked = false;
+
+        contract.try_execute_request_deferred(request_id).unwrap();
+        assert!(
+            FallibleContract::get_execution_status(request_id)
+                .unwrap()
+                .pending
+        );
+        assert!(matches!(
+            contract.try_execute_request(request_id),
+            Err(TryExecutionError::Execution(
+                ExecutionError::ExecutionPending(_)
+            )),
+        ));
+
+        contract.resolve_request_execution(request_id, Err("Promise failed".to_string()));
+        let status = FallibleContract::get_execution_status(request_id).unwrap();
+        assert!(!status.pending);
+        assert_eq!(status.failure_count, 1);
+        assert!(FallibleContract::get_request(request_id).is_some());
+
+        contract.try_execute_request_deferred(request_id).unwrap();
+        contract.resolve_request_execution(request_id, Ok(()));
+        assert!(FallibleContract::get_request(request_id).is_none());
+        assert!(FallibleContract::get_execution_status(request_id).is_none());
+    }
+
+    #[test]
+    fn stateless_adapter() {
+        let mut contract = Contract {};
+
+        assert_eq!(
+            Action::execute(super::Stateless(SayHowdy), &mut contract),
+            "howdy",
+        );
+    }
+
     #[test]
     fn successful_approval() {
         let alice: AccountId = "alice".parse().unwrap();
@@ -527,6 +1260,283 @@ mod tests {
         contract.remove_request(request_id).unwrap();
     }
 
+    #[test]
+    fn removal_wipes_request_namespace() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+
+        let removed = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        let executed = contract
+            .create_request(MyAction::SayGoodbye, Default::default())
+            .unwrap();
+
+        let slots = |request_id| {
+            let mut namespace = Contract::request_namespace(request_id);
+            (0..3u8)
+                .map(|i| namespace.field::<u32>(vec![i]))
+                .collect::<Vec<_>>()
+        };
+
+        for request_id in [removed, executed] {
+            for mut slot in slots(request_id) {
+                slot.write(&request_id);
+            }
+            assert_eq!(Contract::request_namespace(request_id).len(), 3);
+        }
+
+        contract.remove_request(removed).unwrap();
+
+        contract.approve_request(executed).unwrap();
+        contract.execute_request(executed).unwrap();
+
+        for request_id in [removed, executed] {
+            assert!(Contract::request_namespace(request_id).is_empty());
+            assert!(slots(request_id).iter().all(|slot| !slot.exists()));
+        }
+    }
+
+    #[test]
+    #[should_panic = "TrackedNamespace capacity exceeded"]
+    fn request_namespace_capacity() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+
+        let request_id = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+
+        let mut namespace = Contract::request_namespace(request_id);
+        for i in 0..=super::MAX_REQUEST_NAMESPACE_CHILDREN {
+            namespace.field_ref::<u32, _>(&i);
+        }
+    }
+
+    #[test]
+    fn batch_approval() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+
+        let first = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        let second = contract
+            .create_request(MyAction::SayGoodbye, Default::default())
+            .unwrap();
+
+        contract.approve_request(second).unwrap();
+
+        let results = contract.approve_requests(vec![first, second, 99]);
+
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(ApprovalError::ApprovalError(_))));
+        assert!(matches!(
+            results[2],
+            Err(ApprovalError::RequestNotFound(99))
+        ));
+
+        assert!(Contract::is_approved_for_execution(first).is_ok());
+        assert!(Contract::is_approved_for_execution(second).is_ok());
+    }
+
+    #[test]
+    #[should_panic = "Too many requests in batch"]
+    fn batch_approval_too_large() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+
+        contract.approve_requests(vec![0; super::MAX_BATCH_APPROVALS + 1]);
+    }
+
+    #[test]
+    fn delegated_approval() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+        let dave: AccountId = "dave".parse().unwrap();
+
+        let mut contract = Contract::new(2);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+        contract.add_role(bob.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+        let request_id = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        contract.delegate_approval(dave.clone(), 1000).unwrap();
+
+        assert_eq!(Contract::delegator_of(&dave), Some(alice.clone()));
+
+        predecessor(&dave);
+        contract.approve_request(request_id).unwrap();
+
+        assert_eq!(
+            Contract::get_request(request_id)
+                .unwrap()
+                .approval_state
+                .approved_by,
+            vec![alice.clone()],
+        );
+
+        // Delegator cannot approve a second time
+        predecessor(&alice);
+        assert!(matches!(
+            contract.approve_request(request_id),
+            Err(ApprovalError::ApprovalError(_)),
+        ));
+
+        assert!(Contract::is_approved_for_execution(request_id).is_err());
+
+        predecessor(&bob);
+        contract.approve_request(request_id).unwrap();
+
+        assert!(Contract::is_approved_for_execution(request_id).is_ok());
+    }
+
+    #[test]
+    fn delegation_expiry() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let dave: AccountId = "dave".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+        let request_id = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        contract.delegate_approval(dave.clone(), 1000).unwrap();
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(dave.clone())
+            .block_timestamp(1000)
+            .build());
+
+        assert_eq!(Contract::delegator_of(&dave), None);
+        assert!(matches!(
+            contract.approve_request(request_id),
+            Err(ApprovalError::UnauthorizedAccount(_)),
+        ));
+    }
+
+    #[test]
+    fn delegation_revocation() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let dave: AccountId = "dave".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+        let request_id = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        contract.delegate_approval(dave.clone(), 1000).unwrap();
+        contract.revoke_delegation().unwrap();
+
+        assert!(contract.revoke_delegation().is_err());
+        assert_eq!(Contract::get_delegation(&alice), None);
+
+        predecessor(&dave);
+        assert!(matches!(
+            contract.approve_request(request_id),
+            Err(ApprovalError::UnauthorizedAccount(_)),
+        ));
+    }
+
+    #[test]
+    fn delegate_unavailable() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+        let dave: AccountId = "dave".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+        contract.add_role(bob.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+        contract.delegate_approval(dave.clone(), 1000).unwrap();
+
+        predecessor(&bob);
+        assert!(matches!(
+            contract.delegate_approval(dave.clone(), 1000),
+            Err(super::DelegationError::DelegateUnavailable(_)),
+        ));
+    }
+
+    #[test]
+    fn unauthorized_delegator() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let dave: AccountId = "dave".parse().unwrap();
+
+        let mut contract = Contract::new(1);
+
+        predecessor(&alice);
+        assert!(matches!(
+            contract.delegate_approval(dave.clone(), 1000),
+            Err(super::DelegationError::UnauthorizedDelegator(_)),
+        ));
+        assert_eq!(Contract::delegator_of(&dave), None);
+    }
+
+    #[test]
+    fn authorized_delegate_approves_for_itself() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+
+        let mut contract = Contract::new(2);
+
+        contract.add_role(alice.clone(), &Role::Multisig);
+        contract.add_role(bob.clone(), &Role::Multisig);
+
+        predecessor(&alice);
+        let request_id = contract
+            .create_request(MyAction::SayHello, Default::default())
+            .unwrap();
+        contract.delegate_approval(bob.clone(), 1000).unwrap();
+
+        predecessor(&bob);
+        contract.approve_request(request_id).unwrap();
+
+        assert_eq!(
+            Contract::get_request(request_id)
+                .unwrap()
+                .approval_state
+                .approved_by,
+            vec![bob.clone()],
+        );
+
+        // The delegator keeps its own vote
+        predecessor(&alice);
+        contract.approve_request(request_id).unwrap();
+
+        assert!(Contract::is_approved_for_execution(request_id).is_ok());
+    }
+
     #[test]
     fn dynamic_eligibility() {
         let alice: AccountId = "alice".parse().unwrap();
@@ -4,14 +4,30 @@
 
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
     json_types::{U128, U64},
-    AccountId, Gas, Promise,
+    AccountId, CurveType, Gas, GasWeight, Promise, PublicKey,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+/// Maximum number of receivers in a [`NativeTransactionBatch`]
+pub const MAX_BATCH_RECEIVERS: usize = 16;
+/// Maximum number of stages in a [`NativeTransactionChain`]
+pub const MAX_CHAIN_STAGES: usize = 8;
+/// Gas reserved for the remainder of the current function call by
+/// [`default_function_call_gas`]
+pub const RESERVED_GAS: Gas = Gas(10_000_000_000_000);
 
 /// Every native NEAR action can be mapped to a Promise action.
 /// NOTE: The native ADD_KEY action is split into two: one for adding a
 /// full-access key, one for a function call access key.
+///
+/// The JSON representation follows NEAR conventions: amounts are strings
+/// (`U128`), code and function call arguments are base64-encoded strings,
+/// gas is a string (or, when deserializing, a number), and public keys are
+/// base58-encoded strings with a curve prefix. The borsh representation is
+/// unaffected.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum PromiseAction {
     /// Native CREATE_ACCOUNT action
@@ -19,6 +35,7 @@ pub enum PromiseAction {
     /// Native DEPLOY_CONTRACT action
     DeployContract {
         /// WASM binary blob
+        #[serde(with = "base64_bytes")]
         code: Vec<u8>,
     },
     /// Native FUNCTION_CALL action
@@ -26,10 +43,12 @@ pub enum PromiseAction {
         /// Name of function to call on receiver
         function_name: String,
         /// Function input (optional)
+        #[serde(with = "base64_bytes")]
         arguments: Vec<u8>,
         /// Attached deposit
         amount: U128,
         /// Attached gas
+        #[serde(deserialize_with = "gas_string_or_number")]
         gas: U64,
     },
     /// Native TRANSFER action
@@ -41,7 +60,7 @@ pub enum PromiseAction {
     Stake {
         /// Amount of tokens to stake
         amount: U128,
-        /// Public key of validator node
+        /// Public key of validator node. Must be an ed25519 key.
         public_key: String,
     },
     /// Native ADD_KEY action for full-access keys
@@ -69,11 +88,168 @@ pub enum PromiseAction {
         /// Public key to remove
         public_key: String,
     },
-    /// Native DELETE_ACCOUNT action
+    /// Native DELETE_ACCOUNT action. Irreversible, and transfers the entire
+    /// balance of the receiver to the beneficiary, so it may only be executed
+    /// if the `dangerous-actions` feature is enabled.
     DeleteAccount {
         /// Remaining account balance transferred to beneficiary
         beneficiary_id: AccountId,
     },
+    /// Native FUNCTION_CALL action that is attached a weighted share of the
+    /// unused gas, distributed at the end of execution among all function
+    /// calls with a weight
+    FunctionCallWeight {
+        /// Name of function to call on receiver
+        function_name: String,
+        /// Function input (optional)
+        #[serde(with = "base64_bytes")]
+        arguments: Vec<u8>,
+        /// Attached deposit
+        amount: U128,
+        /// Minimum amount of gas attached (default: 0)
+        #[serde(default = "zero_gas", deserialize_with = "gas_string_or_number")]
+        gas: U64,
+        /// Weight of the share of unused gas to attach
+        gas_weight: U64,
+    },
+}
+
+fn zero_gas() -> U64 {
+    U64(0)
+}
+
+fn gas_string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Number(u64),
+        String(U64),
+    }
+
+    Ok(match Repr::deserialize(deserializer)? {
+        Repr::Number(n) => U64(n),
+        Repr::String(s) => s,
+    })
+}
+
+mod base64_bytes {
+    use near_sdk::base64;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        base64::decode(String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl PromiseAction {
+    /// Irreversible actions that may only be executed if the
+    /// `dangerous-actions` feature is enabled
+    pub fn is_dangerous(&self) -> bool {
+        matches!(self, Self::DeleteAccount { .. })
+    }
+
+    /// The public key used by the action, if any
+    pub fn public_key(&self) -> Option<&str> {
+        match self {
+            Self::Stake { public_key, .. }
+            | Self::AddFullAccessKey { public_key, .. }
+            | Self::AddAccessKey { public_key, .. }
+            | Self::DeleteKey { public_key } => Some(public_key),
+            _ => None,
+        }
+    }
+}
+
+/// Computes a static gas amount for each of `function_calls` function calls
+/// by splitting the gas remaining in the current function call evenly, after
+/// setting aside [`RESERVED_GAS`]
+pub fn default_function_call_gas(function_calls: usize) -> Gas {
+    let remaining = env::prepaid_gas()
+        .0
+        .saturating_sub(env::used_gas().0)
+        .saturating_sub(RESERVED_GAS.0);
+
+    Gas(remaining / function_calls.max(1) as u64)
+}
+
+/// Parses a public key from its string representation: a base58-encoded key
+/// with an `ed25519:` or `secp256k1:` prefix. Panics with a descriptive
+/// message if the key is malformed.
+pub fn parse_public_key(public_key: &str) -> PublicKey {
+    try_parse_public_key(public_key).unwrap_or_else(|e| env::panic_str(&e.to_string()))
+}
+
+/// Parses a public key from its string representation: a base58-encoded key
+/// with an `ed25519:` or `secp256k1:` prefix
+pub fn try_parse_public_key(public_key: &str) -> Result<PublicKey, NativeTransactionActionError> {
+    public_key
+        .parse::<PublicKey>()
+        .map_err(|e| NativeTransactionActionError::InvalidPublicKey {
+            public_key: public_key.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// Errors that make a native transaction invalid
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum NativeTransactionActionError {
+    /// Accounts may only be created as direct sub-accounts of the current
+    /// account
+    #[error(
+        "Cannot create account '{receiver_id}': not a direct sub-account of '{current_account_id}'"
+    )]
+    NotDirectSubAccount {
+        /// Receiver of the transaction
+        receiver_id: AccountId,
+        /// Account executing the transaction
+        current_account_id: AccountId,
+    },
+    /// CreateAccount must precede all other actions in the transaction
+    #[error("CreateAccount must be the first action")]
+    CreateAccountNotFirst,
+    /// Dangerous actions require the `dangerous-actions` feature
+    #[error("Dangerous actions are not allowed without the `dangerous-actions` feature")]
+    DangerousActionNotAllowed,
+    /// The public key could not be parsed
+    #[error("Invalid public key '{public_key}': {reason}")]
+    InvalidPublicKey {
+        /// The malformed key
+        public_key: String,
+        /// Why the key could not be parsed
+        reason: String,
+    },
+    /// Validators may only stake with ed25519 keys
+    #[error("Staking key must be an ed25519 key: '{0}'")]
+    UnsuitableStakingKey(String),
+    /// A batch must contain at least one transaction
+    #[error("Batch is empty")]
+    EmptyBatch,
+    /// A batch may not contain more than [`MAX_BATCH_RECEIVERS`] transactions
+    #[error("Batch has {0} receivers, but at most {MAX_BATCH_RECEIVERS} are allowed")]
+    TooManyReceivers(usize),
+    /// A chain must contain at least one stage
+    #[error("Chain is empty")]
+    EmptyChain,
+    /// A chain may not contain more than [`MAX_CHAIN_STAGES`] stages
+    #[error("Chain has {0} stages, but at most {MAX_CHAIN_STAGES} are allowed")]
+    TooManyStages(usize),
+    /// A function call must specify either gas or a gas weight
+    #[error("Function call '{0}' must specify gas or a gas weight")]
+    NoFunctionCallGas(String),
+}
+
+/// Returns `true` if `account_id` is of the form `<name>.<parent_id>`, where
+/// `<name>` contains no dots
+pub fn is_direct_sub_account(parent_id: &AccountId, account_id: &AccountId) -> bool {
+    account_id
+        .as_str()
+        .strip_suffix(parent_id.as_str())
+        .and_then(|prefix| prefix.strip_suffix('.'))
+        .map_or(false, |name| !name.is_empty() && !name.contains('.'))
 }
 
 /// A native protocol-level transaction that (de)serializes into many different
@@ -86,10 +262,82 @@ pub struct NativeTransactionAction {
     pub actions: Vec<PromiseAction>,
 }
 
+impl NativeTransactionAction {
+    /// Checks that the transaction can be executed by the current account.
+    /// A transaction that creates an account must target a direct
+    /// sub-account of the current account (e.g. `sub.dao.near` from
+    /// `dao.near`), and `CreateAccount` must be its first action, so that it
+    /// may be followed by e.g. `Transfer` and `DeployContract` to provision
+    /// the new account in a single batch.
+    ///
+    /// Dangerous actions (see [`PromiseAction::is_dangerous`]) are rejected
+    /// unless the `dangerous-actions` feature is enabled.
+    ///
+    /// All public keys must be well-formed, and staking keys must be ed25519
+    /// keys.
+    pub fn validate(&self) -> Result<(), NativeTransactionActionError> {
+        if !cfg!(feature = "dangerous-actions")
+            && self.actions.iter().any(PromiseAction::is_dangerous)
+        {
+            return Err(NativeTransactionActionError::DangerousActionNotAllowed);
+        }
+
+        for action in &self.actions {
+            if let PromiseAction::FunctionCall {
+                function_name, gas, ..
+            } = action
+            {
+                if gas.0 == 0 {
+                    return Err(NativeTransactionActionError::NoFunctionCallGas(
+                        function_name.clone(),
+                    ));
+                }
+            }
+
+            if let Some(public_key) = action.public_key() {
+                let parsed = try_parse_public_key(public_key)?;
+
+                if matches!(action, PromiseAction::Stake { .. })
+                    && parsed.curve_type() != CurveType::ED25519
+                {
+                    return Err(NativeTransactionActionError::UnsuitableStakingKey(
+                        public_key.to_string(),
+                    ));
+                }
+            }
+        }
+
+        let create_account_index = self
+            .actions
+            .iter()
+            .position(|action| matches!(action, PromiseAction::CreateAccount));
+
+        match create_account_index {
+            None => Ok(()),
+            Some(0) => {
+                let current_account_id = env::current_account_id();
+                if is_direct_sub_account(&current_account_id, &self.receiver_id) {
+                    Ok(())
+                } else {
+                    Err(NativeTransactionActionError::NotDirectSubAccount {
+                        receiver_id: self.receiver_id.clone(),
+                        current_account_id,
+                    })
+                }
+            }
+            Some(_) => Err(NativeTransactionActionError::CreateAccountNotFirst),
+        }
+    }
+}
+
 impl<C> super::Action<C> for NativeTransactionAction {
     type Output = Promise;
 
     fn execute(self, _contract: &mut C) -> Self::Output {
+        if let Err(e) = self.validate() {
+            env::panic_str(&e.to_string());
+        }
+
         let mut promise = Promise::new(self.receiver_id);
 
         // Construct promise
@@ -102,7 +350,7 @@ impl<C> super::Action<C> for NativeTransactionAction {
                     function_names,
                     nonce,
                 } => promise.add_access_key_with_nonce(
-                    public_key.parse().unwrap(),
+                    parse_public_key(&public_key),
                     allowance.into(),
                     receiver_id,
                     function_names.join(","),
@@ -110,7 +358,7 @@ impl<C> super::Action<C> for NativeTransactionAction {
                 ),
                 PromiseAction::AddFullAccessKey { public_key, nonce } => promise
                     .add_full_access_key_with_nonce(
-                        public_key.parse().unwrap(),
+                        parse_public_key(&public_key),
                         nonce.map(Into::into).unwrap_or(0),
                     ),
                 PromiseAction::CreateAccount => promise.create_account(),
@@ -120,15 +368,26 @@ impl<C> super::Action<C> for NativeTransactionAction {
                     arguments,
                     amount,
                     gas,
-                } => {
-                    promise.function_call(function_name, arguments, amount.into(), Gas(gas.into()))
-                }
+                } => promise.function_call(function_name, arguments, amount.into(), gas.0.into()),
+                PromiseAction::FunctionCallWeight {
+                    function_name,
+                    arguments,
+                    amount,
+                    gas,
+                    gas_weight,
+                } => promise.function_call_weight(
+                    function_name,
+                    arguments,
+                    amount.into(),
+                    gas.0.into(),
+                    GasWeight(gas_weight.into()),
+                ),
                 PromiseAction::Transfer { amount } => promise.transfer(amount.into()),
                 PromiseAction::Stake { amount, public_key } => {
-                    promise.stake(amount.into(), public_key.parse().unwrap())
+                    promise.stake(amount.into(), parse_public_key(&public_key))
                 }
                 PromiseAction::DeleteKey { public_key } => {
-                    promise.delete_key(public_key.parse().unwrap())
+                    promise.delete_key(parse_public_key(&public_key))
                 }
                 PromiseAction::DeleteAccount { beneficiary_id } => {
                     promise.delete_account(beneficiary_id)
@@ -139,3 +398,563 @@ impl<C> super::Action<C> for NativeTransactionAction {
         promise
     }
 }
+
+/// Native transactions to multiple receivers that are approved and executed
+/// as a single unit, e.g. a payout to several accounts
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct NativeTransactionBatch {
+    /// Transactions to execute, one per receiver
+    pub transactions: Vec<NativeTransactionAction>,
+}
+
+impl NativeTransactionBatch {
+    /// Checks that the batch contains between 1 and [`MAX_BATCH_RECEIVERS`]
+    /// transactions, and that each of them is valid (see
+    /// [`NativeTransactionAction::validate`])
+    pub fn validate(&self) -> Result<(), NativeTransactionActionError> {
+        match self.transactions.len() {
+            0 => return Err(NativeTransactionActionError::EmptyBatch),
+            n if n > MAX_BATCH_RECEIVERS => {
+                return Err(NativeTransactionActionError::TooManyReceivers(n))
+            }
+            _ => {}
+        }
+
+        self.transactions
+            .iter()
+            .try_for_each(NativeTransactionAction::validate)
+    }
+}
+
+/// Executes every transaction in the batch and joins the resulting promises
+/// with [`Promise::and`]. Note that the protocol does not allow a joint
+/// promise to be returned from a contract method, so the output should be
+/// dropped or chained with [`Promise::then`].
+impl<C> super::Action<C> for NativeTransactionBatch {
+    type Output = Promise;
+
+    fn execute(self, contract: &mut C) -> Self::Output {
+        if let Err(e) = self.validate() {
+            env::panic_str(&e.to_string());
+        }
+
+        self.transactions
+            .into_iter()
+            .map(|transaction| super::Action::execute(transaction, contract))
+            .reduce(Promise::and)
+            .unwrap() // validated to be non-empty
+    }
+}
+
+/// Native transactions that are approved as a single unit and executed in
+/// sequence: each stage is scheduled with [`Promise::then`], so it only
+/// executes after the previous stage has completed.
+///
+/// Note that a stage is executed even if an earlier stage fails, and that the
+/// failure of a stage does not roll back earlier ones. If the outcome of a
+/// stage matters, make the following stage a call to a resolver function that
+/// checks the promise result.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct NativeTransactionChain {
+    /// Transactions to execute, in order
+    pub stages: Vec<NativeTransactionAction>,
+}
+
+impl NativeTransactionChain {
+    /// Checks that the chain contains between 1 and [`MAX_CHAIN_STAGES`]
+    /// stages, and that each of them is valid (see
+    /// [`NativeTransactionAction::validate`])
+    pub fn validate(&self) -> Result<(), NativeTransactionActionError> {
+        match self.stages.len() {
+            0 => return Err(NativeTransactionActionError::EmptyChain),
+            n if n > MAX_CHAIN_STAGES => {
+                return Err(NativeTransactionActionError::TooManyStages(n))
+            }
+            _ => {}
+        }
+
+        self.stages
+            .iter()
+            .try_for_each(NativeTransactionAction::validate)
+    }
+}
+
+impl<C> super::Action<C> for NativeTransactionChain {
+    type Output = Promise;
+
+    fn execute(self, contract: &mut C) -> Self::Output {
+        if let Err(e) = self.validate() {
+            env::panic_str(&e.to_string());
+        }
+
+        self.stages
+            .into_iter()
+            .map(|stage| super::Action::execute(stage, contract))
+            .reduce(Promise::then)
+            .unwrap() // validated to be non-empty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{test_utils::VMContextBuilder, testing_env, AccountId, CurveType, Gas};
+
+    use super::{
+        default_function_call_gas, is_direct_sub_account, parse_public_key, try_parse_public_key,
+        NativeTransactionAction, NativeTransactionActionError, NativeTransactionBatch,
+        NativeTransactionChain, PromiseAction, MAX_BATCH_RECEIVERS, MAX_CHAIN_STAGES, RESERVED_GAS,
+    };
+    use near_sdk::{
+        borsh::{BorshDeserialize, BorshSerialize},
+        serde_json::{self, json},
+    };
+
+    use crate::approval::Action;
+
+    fn current_account(account_id: &AccountId) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(account_id.clone());
+        testing_env!(context.build());
+    }
+
+    fn provision(receiver_id: &str) -> NativeTransactionAction {
+        NativeTransactionAction {
+            receiver_id: receiver_id.parse().unwrap(),
+            actions: vec![
+                PromiseAction::CreateAccount,
+                PromiseAction::Transfer {
+                    amount: near_sdk::ONE_NEAR.into(),
+                },
+                PromiseAction::DeployContract { code: vec![] },
+            ],
+        }
+    }
+
+    #[test]
+    fn direct_sub_account() {
+        let dao: AccountId = "dao.near".parse().unwrap();
+
+        assert!(is_direct_sub_account(
+            &dao,
+            &"sub.dao.near".parse().unwrap()
+        ));
+        assert!(!is_direct_sub_account(
+            &dao,
+            &"a.sub.dao.near".parse().unwrap()
+        ));
+        assert!(!is_direct_sub_account(
+            &dao,
+            &"subdao.near".parse().unwrap()
+        ));
+        assert!(!is_direct_sub_account(&dao, &"dao.near".parse().unwrap()));
+        assert!(!is_direct_sub_account(
+            &dao,
+            &"sub.other.near".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn validate_create_account() {
+        let dao: AccountId = "dao.near".parse().unwrap();
+        current_account(&dao);
+
+        assert_eq!(provision("sub.dao.near").validate(), Ok(()));
+        assert_eq!(
+            provision("sub.other.near").validate(),
+            Err(NativeTransactionActionError::NotDirectSubAccount {
+                receiver_id: "sub.other.near".parse().unwrap(),
+                current_account_id: dao,
+            }),
+        );
+
+        let mut out_of_order = provision("sub.dao.near");
+        out_of_order.actions.reverse();
+        assert_eq!(
+            out_of_order.validate(),
+            Err(NativeTransactionActionError::CreateAccountNotFirst),
+        );
+    }
+
+    #[test]
+    fn dangerous_action_gate() {
+        current_account(&"dao.near".parse().unwrap());
+
+        let decommission = NativeTransactionAction {
+            receiver_id: "sub.dao.near".parse().unwrap(),
+            actions: vec![PromiseAction::DeleteAccount {
+                beneficiary_id: "dao.near".parse().unwrap(),
+            }],
+        };
+
+        if cfg!(feature = "dangerous-actions") {
+            assert_eq!(decommission.validate(), Ok(()));
+        } else {
+            assert_eq!(
+                decommission.validate(),
+                Err(NativeTransactionActionError::DangerousActionNotAllowed),
+            );
+        }
+    }
+
+    const ED25519_KEY: &str = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp";
+    const SECP256K1_KEY: &str = "secp256k1:qMoRgcoXai4mBPsdbHi1wfyxF9TdbPCF4qSDQTRP3TfescSRoUdSx6nmeQoN3aiwGzwMyGXAb1gUjBTv5AY8DXj";
+
+    #[test]
+    fn parse_valid_keys() {
+        assert_eq!(
+            parse_public_key(ED25519_KEY).curve_type(),
+            CurveType::ED25519
+        );
+        assert_eq!(
+            parse_public_key(SECP256K1_KEY).curve_type(),
+            CurveType::SECP256K1,
+        );
+    }
+
+    #[test]
+    fn parse_invalid_keys() {
+        for key in [
+            "ed25519:",
+            "ed25519:0OIl",
+            "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKE",
+            "rsa:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+        ] {
+            assert!(matches!(
+                try_parse_public_key(key),
+                Err(NativeTransactionActionError::InvalidPublicKey { public_key, .. }) if public_key == key,
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic = "Invalid public key 'ed25519:0OIl'"]
+    fn parse_invalid_key_panics() {
+        parse_public_key("ed25519:0OIl");
+    }
+
+    fn stake_and_call(public_key: &str) -> NativeTransactionAction {
+        NativeTransactionAction {
+            receiver_id: "dao.near".parse().unwrap(),
+            actions: vec![
+                PromiseAction::Stake {
+                    amount: (near_sdk::ONE_NEAR * 100).into(),
+                    public_key: public_key.to_string(),
+                },
+                PromiseAction::FunctionCall {
+                    function_name: "on_stake".to_string(),
+                    arguments: vec![],
+                    amount: 0.into(),
+                    gas: (Gas::ONE_TERA.0 * 10).into(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn stake_with_function_call() {
+        let mut contract = ();
+        current_account(&"dao.near".parse().unwrap());
+
+        let action = stake_and_call(ED25519_KEY);
+        assert_eq!(action.validate(), Ok(()));
+
+        Action::execute(action, &mut contract);
+    }
+
+    fn payout(receivers: usize) -> NativeTransactionBatch {
+        NativeTransactionBatch {
+            transactions: (0..receivers)
+                .map(|i| NativeTransactionAction {
+                    receiver_id: format!("account{i}.near").parse().unwrap(),
+                    actions: vec![PromiseAction::Transfer {
+                        amount: near_sdk::ONE_NEAR.into(),
+                    }],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn batch_bounds() {
+        current_account(&"dao.near".parse().unwrap());
+
+        assert_eq!(
+            payout(0).validate(),
+            Err(NativeTransactionActionError::EmptyBatch),
+        );
+        assert_eq!(payout(1).validate(), Ok(()));
+        assert_eq!(payout(MAX_BATCH_RECEIVERS).validate(), Ok(()));
+        assert_eq!(
+            payout(MAX_BATCH_RECEIVERS + 1).validate(),
+            Err(NativeTransactionActionError::TooManyReceivers(
+                MAX_BATCH_RECEIVERS + 1
+            )),
+        );
+    }
+
+    #[test]
+    fn chain_bounds() {
+        current_account(&"dao.near".parse().unwrap());
+
+        let chain = |stages: usize| NativeTransactionChain {
+            stages: payout(stages).transactions,
+        };
+
+        assert_eq!(
+            chain(0).validate(),
+            Err(NativeTransactionActionError::EmptyChain),
+        );
+        assert_eq!(chain(MAX_CHAIN_STAGES).validate(), Ok(()));
+        assert_eq!(
+            chain(MAX_CHAIN_STAGES + 1).validate(),
+            Err(NativeTransactionActionError::TooManyStages(
+                MAX_CHAIN_STAGES + 1
+            )),
+        );
+    }
+
+    #[test]
+    fn function_call_requires_gas() {
+        current_account(&"dao.near".parse().unwrap());
+
+        let call = |action: PromiseAction| NativeTransactionAction {
+            receiver_id: "dao.near".parse().unwrap(),
+            actions: vec![action],
+        };
+        let function_call = |gas: u64| {
+            call(PromiseAction::FunctionCall {
+                function_name: "f".to_string(),
+                arguments: vec![],
+                amount: 0.into(),
+                gas: gas.into(),
+            })
+        };
+        let weighted = call(PromiseAction::FunctionCallWeight {
+            function_name: "f".to_string(),
+            arguments: vec![],
+            amount: 0.into(),
+            gas: 0.into(),
+            gas_weight: 1.into(),
+        });
+
+        assert_eq!(
+            function_call(0).validate(),
+            Err(NativeTransactionActionError::NoFunctionCallGas(
+                "f".to_string()
+            )),
+        );
+        assert_eq!(function_call(Gas::ONE_TERA.0).validate(), Ok(()));
+        assert_eq!(weighted.validate(), Ok(()));
+
+        Action::execute(weighted, &mut ());
+    }
+
+    #[test]
+    fn default_gas() {
+        let mut context = VMContextBuilder::new();
+        context.prepaid_gas(Gas::ONE_TERA * 100);
+        testing_env!(context.build());
+
+        let gas = default_function_call_gas(3);
+        assert!(gas.0 > 0);
+        assert!(gas.0 * 3 <= (Gas::ONE_TERA * 100 - RESERVED_GAS).0);
+    }
+
+    fn golden(action: PromiseAction, expected: serde_json::Value) {
+        assert_eq!(serde_json::to_value(&action).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<PromiseAction>(expected).unwrap(),
+            action,
+        );
+    }
+
+    #[test]
+    fn json_create_account() {
+        golden(PromiseAction::CreateAccount, json!("CreateAccount"));
+    }
+
+    #[test]
+    fn json_deploy_contract() {
+        golden(
+            PromiseAction::DeployContract {
+                code: b"\0asm\x01\0\0\0".to_vec(),
+            },
+            json!({ "DeployContract": { "code": "AGFzbQEAAAA=" } }),
+        );
+    }
+
+    #[test]
+    fn json_function_call() {
+        golden(
+            PromiseAction::FunctionCall {
+                function_name: "set_value".to_string(),
+                arguments: br#"{"value":1}"#.to_vec(),
+                amount: 1.into(),
+                gas: 5_000_000_000_000.into(),
+            },
+            json!({
+                "FunctionCall": {
+                    "function_name": "set_value",
+                    "arguments": "eyJ2YWx1ZSI6MX0=",
+                    "amount": "1",
+                    "gas": "5000000000000",
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn json_function_call_weight() {
+        golden(
+            PromiseAction::FunctionCallWeight {
+                function_name: "set_value".to_string(),
+                arguments: vec![],
+                amount: 0.into(),
+                gas: 0.into(),
+                gas_weight: 1.into(),
+            },
+            json!({
+                "FunctionCallWeight": {
+                    "function_name": "set_value",
+                    "arguments": "",
+                    "amount": "0",
+                    "gas": "0",
+                    "gas_weight": "1",
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn json_function_call_gas_number() {
+        let action: PromiseAction = serde_json::from_value(json!({
+            "FunctionCall": {
+                "function_name": "set_value",
+                "arguments": "",
+                "amount": "0",
+                "gas": 5_000_000_000_000u64,
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(
+            action,
+            PromiseAction::FunctionCall {
+                function_name: "set_value".to_string(),
+                arguments: vec![],
+                amount: 0.into(),
+                gas: 5_000_000_000_000.into(),
+            },
+        );
+    }
+
+    #[test]
+    fn json_transfer() {
+        golden(
+            PromiseAction::Transfer {
+                amount: near_sdk::ONE_NEAR.into(),
+            },
+            json!({ "Transfer": { "amount": "1000000000000000000000000" } }),
+        );
+    }
+
+    #[test]
+    fn json_stake() {
+        golden(
+            PromiseAction::Stake {
+                amount: 100.into(),
+                public_key: ED25519_KEY.to_string(),
+            },
+            json!({ "Stake": { "amount": "100", "public_key": ED25519_KEY } }),
+        );
+    }
+
+    #[test]
+    fn json_add_full_access_key() {
+        golden(
+            PromiseAction::AddFullAccessKey {
+                public_key: ED25519_KEY.to_string(),
+                nonce: Some(7.into()),
+            },
+            json!({ "AddFullAccessKey": { "public_key": ED25519_KEY, "nonce": "7" } }),
+        );
+    }
+
+    #[test]
+    fn json_add_access_key() {
+        golden(
+            PromiseAction::AddAccessKey {
+                public_key: ED25519_KEY.to_string(),
+                allowance: 250.into(),
+                receiver_id: "dao.near".parse().unwrap(),
+                function_names: vec!["approve".to_string(), "execute".to_string()],
+                nonce: None,
+            },
+            json!({
+                "AddAccessKey": {
+                    "public_key": ED25519_KEY,
+                    "allowance": "250",
+                    "receiver_id": "dao.near",
+                    "function_names": ["approve", "execute"],
+                    "nonce": null,
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn json_delete_key() {
+        golden(
+            PromiseAction::DeleteKey {
+                public_key: ED25519_KEY.to_string(),
+            },
+            json!({ "DeleteKey": { "public_key": ED25519_KEY } }),
+        );
+    }
+
+    #[test]
+    fn json_delete_account() {
+        golden(
+            PromiseAction::DeleteAccount {
+                beneficiary_id: "dao.near".parse().unwrap(),
+            },
+            json!({ "DeleteAccount": { "beneficiary_id": "dao.near" } }),
+        );
+    }
+
+    #[test]
+    fn borsh_unchanged() {
+        let action = PromiseAction::FunctionCall {
+            function_name: "f".to_string(),
+            arguments: vec![1, 2],
+            amount: 3.into(),
+            gas: 4.into(),
+        };
+
+        // As serialized before JSON-friendly representations and gas weights
+        // were added
+        #[rustfmt::skip]
+        let expected = [
+            2, // variant index
+            1, 0, 0, 0, b'f', // function_name
+            2, 0, 0, 0, 1, 2, // arguments
+            3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // amount
+            4, 0, 0, 0, 0, 0, 0, 0, // gas
+        ];
+
+        assert_eq!(action.try_to_vec().unwrap(), expected);
+        assert_eq!(PromiseAction::try_from_slice(&expected).unwrap(), action);
+    }
+
+    #[test]
+    fn stake_requires_ed25519_key() {
+        current_account(&"dao.near".parse().unwrap());
+
+        assert_eq!(
+            stake_and_call(SECP256K1_KEY).validate(),
+            Err(NativeTransactionActionError::UnsuitableStakingKey(
+                SECP256K1_KEY.to_string()
+            )),
+        );
+    }
+}
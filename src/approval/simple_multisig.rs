@@ -10,7 +10,7 @@ use near_sdk::{
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::{ActionRequest, ApprovalConfiguration};
+use super::{ActionRequest, ApprovalConfiguration, ApprovalProgress, ApproverList};
 
 /// Check which accounts are eligible to submit approvals to an
 /// [ApprovalManager](super::ApprovalManager)
@@ -23,7 +23,20 @@ pub trait AccountAuthorizer {
 }
 
 /// M (threshold) of N approval scheme
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+///
+/// By default, the threshold and validity period in effect when a request is
+/// created are recorded in the request's [`ApprovalState`], and the request
+/// is evaluated against that snapshot for its entire lifetime. Otherwise,
+/// lowering the threshold (or extending the validity period) while a
+/// sensitive request is pending would make that request easier to execute
+/// than its approvers agreed to. Contracts that want configuration changes to
+/// apply to pending requests can opt out with
+/// [`Configuration::with_live_configuration`].
+///
+/// Configurations stored before the opt-out existed deserialize with the
+/// default (snapshots are used), so it must be the last value in its storage
+/// slot.
+#[derive(BorshSerialize, Serialize, Deserialize, Clone, Debug)]
 pub struct Configuration<Au: AccountAuthorizer> {
     /// How many approvals are required?
     pub threshold: u8,
@@ -31,41 +44,133 @@ pub struct Configuration<Au: AccountAuthorizer> {
     /// approval-eligible member after this period has elapsed.
     /// 0 = perpetual validity, no deletion
     pub validity_period_nanoseconds: u64,
+    /// Evaluate pending requests against the current configuration instead
+    /// of the configuration in effect when they were created
+    #[serde(default)]
+    pub use_live_configuration: bool,
     #[borsh_skip]
     #[serde(skip)]
     _authorizer: PhantomData<Au>,
 }
 
+impl<Au: AccountAuthorizer> BorshDeserialize for Configuration<Au> {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let threshold: u8 = BorshDeserialize::deserialize(buf)?;
+        let validity_period_nanoseconds: u64 = BorshDeserialize::deserialize(buf)?;
+        // Stored before `use_live_configuration` was added
+        let use_live_configuration = if buf.is_empty() {
+            false
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+
+        Ok(Self {
+            threshold,
+            validity_period_nanoseconds,
+            use_live_configuration,
+            _authorizer: PhantomData,
+        })
+    }
+}
+
 impl<Au: AccountAuthorizer> Configuration<Au> {
     /// Create an approval scheme with the given threshold
     pub fn new(threshold: u8, validity_period_nanoseconds: u64) -> Self {
         Self {
             threshold,
             validity_period_nanoseconds,
+            use_live_configuration: false,
             _authorizer: PhantomData,
         }
     }
 
+    /// Evaluate pending requests against the current configuration instead
+    /// of the configuration in effect when they were created
+    pub fn with_live_configuration(mut self) -> Self {
+        self.use_live_configuration = true;
+        self
+    }
+
+    /// The threshold and validity period that apply to a request with the
+    /// given approval state
+    pub fn effective_for(&self, approval_state: &ApprovalState) -> ConfigurationSnapshot {
+        match approval_state.configuration_snapshot {
+            Some(ref snapshot) if !self.use_live_configuration => snapshot.clone(),
+            _ => self.snapshot(),
+        }
+    }
+
+    /// Current threshold and validity period
+    pub fn snapshot(&self) -> ConfigurationSnapshot {
+        ConfigurationSnapshot {
+            threshold: self.threshold,
+            validity_period_nanoseconds: self.validity_period_nanoseconds,
+        }
+    }
+
     /// Is the given approval state still considered valid?
     pub fn is_within_validity_period(&self, approval_state: &ApprovalState) -> bool {
-        if self.validity_period_nanoseconds == 0 {
+        let validity_period_nanoseconds = self
+            .effective_for(approval_state)
+            .validity_period_nanoseconds;
+
+        if validity_period_nanoseconds == 0 {
             true
         } else {
             env::block_timestamp()
                 .checked_sub(approval_state.created_at_nanoseconds)
                 .unwrap() // inconsistent state if a request timestamp is in the future
-                < self.validity_period_nanoseconds
+                < validity_period_nanoseconds
         }
     }
 }
 
+/// Configuration parameters recorded when a request is created
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct ConfigurationSnapshot {
+    /// How many approvals are required?
+    pub threshold: u8,
+    /// Validity period of the request. 0 = perpetual validity
+    pub validity_period_nanoseconds: u64,
+}
+
 /// Approval state for simple multisig
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug)]
+///
+/// Requests created before configuration snapshots were recorded
+/// deserialize without a snapshot, and are evaluated against the current
+/// configuration. The approval state must therefore be the last value in its
+/// storage slot, as it is in [`ActionRequest`].
+#[derive(BorshSerialize, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
 pub struct ApprovalState {
     /// List of accounts that have approved an action thus far
     pub approved_by: Vec<AccountId>,
     /// Network timestamp when the request was created
     pub created_at_nanoseconds: u64,
+    /// Configuration in effect when the request was created. Set by the
+    /// configuration when the request is created.
+    #[serde(default)]
+    pub configuration_snapshot: Option<ConfigurationSnapshot>,
+}
+
+impl BorshDeserialize for ApprovalState {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let approved_by: Vec<AccountId> = BorshDeserialize::deserialize(buf)?;
+        let created_at_nanoseconds: u64 = BorshDeserialize::deserialize(buf)?;
+        // Stored before `configuration_snapshot` was added
+        let configuration_snapshot = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+
+        Ok(Self {
+            approved_by,
+            created_at_nanoseconds,
+            configuration_snapshot,
+        })
+    }
 }
 
 impl Default for ApprovalState {
@@ -80,10 +185,17 @@ impl ApprovalState {
         Self {
             approved_by: Vec::new(),
             created_at_nanoseconds: env::block_timestamp(),
+            configuration_snapshot: None,
         }
     }
 }
 
+impl ApproverList for ApprovalState {
+    fn approvers(&self) -> &[AccountId] {
+        &self.approved_by
+    }
+}
+
 /// If a request has expired, some actions may not be performed
 #[derive(Error, Clone, Debug)]
 #[error("Validity period exceeded")]
@@ -142,7 +254,7 @@ where
         }
 
         let current = action_request.approval_state.approved_by.len();
-        let required = self.threshold as usize;
+        let required = self.effective_for(&action_request.approval_state).threshold as usize;
 
         if current < required {
             return Err(ExecutionEligibilityError::InsufficientApprovals { current, required });
@@ -170,6 +282,13 @@ where
         Au::is_account_authorized(account_id)
     }
 
+    fn is_account_authorized_to_delegate(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<(), Self::AuthorizationError> {
+        Au::is_account_authorized(account_id)
+    }
+
     fn try_approve_with_authorized_account(
         &self,
         account_id: AccountId,
@@ -191,6 +310,22 @@ where
 
         Ok(())
     }
+
+    fn prepare_request(&self, action_request: &mut ActionRequest<Ac, ApprovalState>) {
+        action_request.approval_state.configuration_snapshot = Some(self.snapshot());
+    }
+}
+
+impl<Au, Ac> ApprovalProgress<Ac, ApprovalState> for Configuration<Au>
+where
+    Au: AccountAuthorizer,
+{
+    fn approvals_remaining(&self, action_request: &ActionRequest<Ac, ApprovalState>) -> u8 {
+        let approvals = action_request.approval_state.approved_by.len();
+
+        (self.effective_for(&action_request.approval_state).threshold as usize)
+            .saturating_sub(approvals) as u8
+    }
 }
 
 /// Types used by near-sdk-contract-tools-macros
@@ -213,13 +348,19 @@ mod tests {
     };
     use thiserror::Error;
 
+    use std::time::Duration;
+
     use crate::{
         approval::{
-            simple_multisig::{AccountAuthorizer, ApprovalState, Configuration},
-            ApprovalManager,
+            simple_multisig::{
+                AccountAuthorizer, ApprovalError, ApprovalState, Configuration,
+                ExecutionEligibilityError, RequestExpiredError,
+            },
+            ApprovalManager, RequestSummary,
         },
         rbac::Rbac,
         slot::Slot,
+        testing::TestEnv,
         Rbac,
     };
 
@@ -351,6 +492,156 @@ mod tests {
         assert_eq!(contract.execute(request_id), "hello");
     }
 
+    fn lower_threshold_after_first_approval(configuration: Configuration<Contract>) -> u32 {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new();
+        Contract::slot_config().write(&configuration);
+
+        predecessor(&alice);
+        contract.obtain_multisig_permission();
+
+        let request_id = contract.create(true);
+        contract.approve(request_id);
+
+        Contract::slot_config().write(&Configuration {
+            threshold: 1,
+            ..configuration
+        });
+
+        request_id
+    }
+
+    #[test]
+    fn configuration_snapshot() {
+        let request_id = lower_threshold_after_first_approval(Configuration::new(2, 10000));
+
+        assert!(Contract::is_approved_for_execution(request_id).is_err());
+    }
+
+    #[test]
+    fn live_configuration() {
+        let request_id = lower_threshold_after_first_approval(
+            Configuration::new(2, 10000).with_live_configuration(),
+        );
+
+        assert!(Contract::is_approved_for_execution(request_id).is_ok());
+    }
+
+    #[test]
+    fn legacy_storage() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+
+        let mut contract = Contract::new();
+
+        predecessor(&alice);
+        contract.obtain_multisig_permission();
+        predecessor(&bob);
+        contract.obtain_multisig_permission();
+
+        // As stored before configuration snapshots were added: threshold 2,
+        // validity period 10000
+        #[rustfmt::skip]
+        let config = [
+            2, // threshold
+            16, 39, 0, 0, 0, 0, 0, 0, // validity_period_nanoseconds
+        ];
+        Contract::slot_config().write_raw(&config);
+
+        // Request 0: say hello, approved by alice, created at 0
+        #[rustfmt::skip]
+        let request = [
+            0, // action
+            1, 0, 0, 0, // approved_by
+            5, 0, 0, 0, b'a', b'l', b'i', b'c', b'e',
+            0, 0, 0, 0, 0, 0, 0, 0, // created_at_nanoseconds
+        ];
+        Contract::slot_request(0).write_raw(&request);
+        Contract::slot_next_request_id().write(&1);
+
+        let configuration = Contract::get_config();
+        assert_eq!(configuration.threshold, 2);
+        assert_eq!(configuration.validity_period_nanoseconds, 10000);
+        assert!(!configuration.use_live_configuration);
+
+        let approval_state = Contract::get_request(0).unwrap().approval_state;
+        assert_eq!(approval_state.approved_by, vec![alice]);
+        assert_eq!(approval_state.configuration_snapshot, None);
+
+        assert!(Contract::is_approved_for_execution(0).is_err());
+        contract.approve(0);
+        assert_eq!(contract.execute(0), "hello");
+    }
+
+    #[test]
+    fn configuration_snapshot_validity_period() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        let mut contract = Contract::new();
+
+        predecessor(&alice);
+        contract.obtain_multisig_permission();
+
+        let request_id = contract.create(true);
+        contract.approve(request_id);
+
+        Contract::slot_config().write(&Configuration::new(2, 20000));
+
+        let created_at = Contract::get_request(request_id)
+            .unwrap()
+            .approval_state
+            .created_at_nanoseconds;
+
+        let mut context = VMContextBuilder::new();
+        context
+            .predecessor_account_id(alice)
+            .block_timestamp(created_at + 10000);
+        testing_env!(context.build());
+
+        contract.remove(request_id);
+    }
+
+    #[test]
+    fn approval_progress() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+
+        let mut contract = Contract::new();
+
+        predecessor(&bob);
+        contract.obtain_multisig_permission();
+        predecessor(&alice);
+        contract.obtain_multisig_permission();
+
+        let request_id = contract.create(true);
+
+        assert_eq!(Contract::approvers_of(request_id), Some(vec![]));
+        assert_eq!(Contract::approvals_remaining(request_id), Some(2));
+
+        contract.approve(request_id);
+
+        assert_eq!(
+            Contract::approvers_of(request_id),
+            Some(vec![alice.clone()])
+        );
+        assert_eq!(Contract::approvals_remaining(request_id), Some(1));
+
+        predecessor(&bob);
+        contract.approve(request_id);
+
+        assert_eq!(
+            Contract::request_summary(request_id),
+            Some(RequestSummary {
+                approvers: vec![alice, bob],
+                approvals_remaining: 0,
+                is_approved_for_execution: true,
+            }),
+        );
+
+        assert_eq!(Contract::approvers_of(request_id + 1), None);
+    }
+
     #[test]
     fn successful_removal() {
         let alice: AccountId = "alice".parse().unwrap();
@@ -434,4 +725,77 @@ mod tests {
 
         contract.remove(request_id);
     }
+
+    /// Two multisig members, alice and bob, and a request created and
+    /// approved by alice, with a validity period of 10000 ns.
+    fn approved_once() -> (TestEnv, Contract, AccountId, AccountId, u32) {
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob_acct".parse().unwrap();
+
+        let mut env = TestEnv::new();
+        let mut contract = Contract::new();
+
+        env.set_predecessor(&bob);
+        contract.obtain_multisig_permission();
+        env.set_predecessor(&alice);
+        contract.obtain_multisig_permission();
+
+        let request_id = contract.create(true);
+        contract.approve(request_id);
+
+        (env, contract, alice, bob, request_id)
+    }
+
+    #[test]
+    fn approval_within_validity_period() {
+        let (mut env, mut contract, _, bob, request_id) = approved_once();
+
+        env.clock().advance(Duration::from_nanos(9999));
+        env.set_predecessor(&bob);
+        contract.approve(request_id);
+
+        assert_eq!(contract.execute(request_id), "hello");
+    }
+
+    #[test]
+    fn expired_request_cannot_be_approved() {
+        let (mut env, mut contract, _, bob, request_id) = approved_once();
+
+        env.clock().advance(Duration::from_nanos(10000));
+        env.set_predecessor(&bob);
+        assert!(matches!(
+            contract.approve_request(request_id),
+            Err(crate::approval::ApprovalError::ApprovalError(
+                ApprovalError::RequestExpired(RequestExpiredError)
+            )),
+        ));
+    }
+
+    #[test]
+    fn approved_request_expires() {
+        let (mut env, mut contract, _, bob, request_id) = approved_once();
+
+        env.set_predecessor(&bob);
+        contract.approve(request_id);
+        assert!(Contract::is_approved_for_execution(request_id).is_ok());
+
+        env.clock().advance(Duration::from_nanos(10000));
+        assert!(matches!(
+            Contract::is_approved_for_execution(request_id),
+            Err(ExecutionEligibilityError::RequestExpired(
+                RequestExpiredError
+            )),
+        ));
+    }
+
+    #[test]
+    fn expired_request_can_be_removed() {
+        let (mut env, mut contract, ..) = approved_once();
+        let request_id = contract.create(false);
+
+        env.clock().advance(Duration::from_nanos(10000));
+        contract.remove(request_id);
+
+        assert!(Contract::get_request(request_id).is_none());
+    }
 }
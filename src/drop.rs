@@ -0,0 +1,1030 @@
+//! Linkdrop-style drops: a funder locks NEAR or NEP-141 tokens that can be
+//! claimed by whoever holds a secret key, e.g. shared as a link with a new
+//! user, rather than by an account.
+//!
+//! [`Linkdrop::create_drop`] records the drop and adds the public key of the
+//! drop to the contract account, as a function call access key that may
+//! only call the claim method of the contract (see
+//! [`Linkdrop::claim_method_name`]). The key is only confirmed once the
+//! callback [`Linkdrop::resolve_add_key`] sees that it was added: if the
+//! contract account already had the key, adding it fails, and the drop can
+//! never be claimed, only refunded. The holder of the secret key signs a
+//! call to the claim method with it, which identifies the drop, and
+//! [`Linkdrop::claim`] sends the assets to the account of their choice.
+//!
+//! A drop is kept until its assets are sent: the callback
+//! [`Linkdrop::resolve_transfer`] removes it and deletes its key once the
+//! transfer succeeds, and otherwise lets it be claimed or refunded again,
+//! e.g. after the claimer registers with the token contract.
+//!
+//! NEAR drops are funded by the deposit attached to
+//! [`Linkdrop::create_drop`]. Token drops are created first, and then
+//! funded by the funder with `ft_transfer_call`, where the `msg` of the
+//! transfer is a [`DropMsg`] (see [`Linkdrop::fund_ft_drop`]). Either way,
+//! the funder also attaches [`Linkdrop::claim_allowance`], which pays for
+//! the gas of the claim.
+//!
+//! A drop may expire. Once it has, it can no longer be claimed, and anyone
+//! may refund it to its funder, with [`Linkdrop::refund_drop`]. The funder
+//! may also refund a drop that is not funded yet, or that never expires. A
+//! drop whose key could not be added may be refunded at any time.
+//!
+//! ```
+//! use near_sdk::{
+//!     json_types::{U128, U64},
+//!     near_bindgen, AccountId, Promise, PromiseOrValue, PublicKey,
+//! };
+//! use near_sdk_contract_tools::{
+//!     drop::{DropAsset, Linkdrop},
+//!     standard::nep141::Nep141Receiver,
+//! };
+//!
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl Linkdrop for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     #[payable]
+//!     pub fn create_drop(
+//!         &mut self,
+//!         public_key: PublicKey,
+//!         asset: DropAsset,
+//!         amount: U128,
+//!         expires_at: Option<U64>,
+//!     ) -> Promise {
+//!         Linkdrop::create_drop(self, public_key, asset, amount.0, expires_at.map(|t| t.0))
+//!     }
+//!
+//!     #[private]
+//!     pub fn drop_resolve_add_key(&mut self, public_key: PublicKey) {
+//!         self.resolve_add_key(public_key)
+//!     }
+//!
+//!     pub fn claim(&mut self, account_id: AccountId) -> Promise {
+//!         Linkdrop::claim(self, account_id)
+//!     }
+//!
+//!     #[private]
+//!     pub fn drop_resolve_transfer(&mut self, public_key: PublicKey) {
+//!         self.resolve_transfer(public_key)
+//!     }
+//! }
+//!
+//! #[near_bindgen]
+//! impl Nep141Receiver for Contract {
+//!     fn ft_on_transfer(
+//!         &mut self,
+//!         sender_id: AccountId,
+//!         amount: U128,
+//!         msg: String,
+//!     ) -> PromiseOrValue<U128> {
+//!         self.fund_ft_drop(sender_id, amount, &msg)
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The drop root storage slot is not used or modified. The default key is `~dr`.
+//! * (UB) The contract holds the assets of the drops until they are claimed
+//!   or refunded.
+//! * (UB) The keys of drops are not otherwise added to or deleted from the
+//!   contract account.
+//! * (UB) The method named [`Linkdrop::resolve_add_key_method_name`] calls
+//!   [`Linkdrop::resolve_add_key`].
+//! * (UB) The method named [`Linkdrop::resolve_transfer_method_name`] calls
+//!   [`Linkdrop::resolve_transfer`].
+//! * (ERR) A public key may be used by one drop at a time.
+//! * (ERR) Only keys added by [`Linkdrop::create_drop`] are deleted.
+//! * (ERR) A drop is claimed only with a call signed by its key, after the
+//!   key is added, before it expires, and after it is funded.
+//! * (ERR) A drop is claimed or refunded once, unless the transfer of its
+//!   assets fails.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::{U128, U64},
+    require,
+    serde_json::json,
+    AccountId, Balance, BorshStorageKey, Gas, Promise, PromiseOrValue, PromiseResult, PublicKey,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slot::Slot,
+    standard::{nep141::ext_nep141, nep297::Event},
+    DefaultStorageKey,
+};
+
+const DROP_EXISTS_FAIL_MESSAGE: &str = message!("DRP1", "Drop already exists");
+const INCORRECT_DEPOSIT_FAIL_MESSAGE: &str = message!("DRP2", "Incorrect deposit for drop");
+const NO_DROP_FAIL_MESSAGE: &str = message!("DRP3", "No such drop");
+const NOT_FUNDED_FAIL_MESSAGE: &str = message!("DRP4", "Drop is not funded");
+const EXPIRED_FAIL_MESSAGE: &str = message!("DRP5", "Drop expired");
+const NOT_EXPIRED_FAIL_MESSAGE: &str = message!("DRP6", "Drop has not expired");
+const INVALID_FUNDING_FAIL_MESSAGE: &str = message!("DRP7", "Invalid drop funding");
+const NOT_SIGNED_BY_CONTRACT_FAIL_MESSAGE: &str =
+    message!("DRP8", "Claims must be signed with the key of the drop");
+const KEY_NOT_ADDED_FAIL_MESSAGE: &str = message!("DRP9", "Drop key is not added");
+const KEY_NOT_PENDING_FAIL_MESSAGE: &str = message!("DRP10", "Drop key is not pending");
+const CALLBACK_ONLY_FAIL_MESSAGE: &str = message!("DRP11", "Callback only");
+const TRANSFERRING_FAIL_MESSAGE: &str = message!("DRP12", "Drop is being transferred");
+const NOT_TRANSFERRING_FAIL_MESSAGE: &str = message!("DRP13", "Drop is not being transferred");
+
+/// Default of [`Linkdrop::claim_allowance`]: 0.01 NEAR
+pub const DEFAULT_CLAIM_ALLOWANCE: Balance = 10_000_000_000_000_000_000_000;
+
+/// Gas attached to the `ft_transfer` that claims or refunds a token drop
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+/// Default of [`Linkdrop::resolve_add_key_method_name`]
+pub const DEFAULT_RESOLVE_ADD_KEY_METHOD_NAME: &str = "drop_resolve_add_key";
+/// Gas attached to the callback that confirms the key of a drop
+pub const GAS_FOR_RESOLVE_ADD_KEY: Gas = Gas(10_000_000_000_000);
+
+/// Default of [`Linkdrop::resolve_transfer_method_name`]
+pub const DEFAULT_RESOLVE_TRANSFER_METHOD_NAME: &str = "drop_resolve_transfer";
+/// Gas attached to the callback that removes a drop once its assets are sent
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+/// The asset of a drop
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DropAsset {
+    /// NEAR, attached to the call that created the drop
+    Near,
+    /// NEP-141 tokens, received with `ft_transfer_call`
+    FungibleToken {
+        /// The token contract
+        contract_id: AccountId,
+    },
+}
+
+/// Whether the key of a drop was added to the contract account
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DropKeyStatus {
+    /// The key is being added
+    Pending,
+    /// The key was added by the drop, and is deleted with it
+    Added,
+    /// The key could not be added, e.g. because the contract account
+    /// already had it. The drop can only be refunded, and the key is not
+    /// deleted.
+    Failed,
+}
+
+/// A transfer of the assets of a drop, awaiting
+/// [`Linkdrop::resolve_transfer`]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DropTransfer {
+    /// To the account chosen by [`Linkdrop::claim`]
+    Claim,
+    /// Back to the funder, by [`Linkdrop::refund_drop`]
+    Refund,
+}
+
+/// A drop, as stored by [`Linkdrop`]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct DropRecord {
+    /// Account that created the drop, and receives it on refund
+    pub funder_id: AccountId,
+    /// The asset of the drop
+    pub asset: DropAsset,
+    /// Amount of the asset
+    pub amount: U128,
+    /// NEAR attached for the gas of the claim, refunded with the drop
+    pub allowance: U128,
+    /// Block timestamp from which the drop can no longer be claimed, if any
+    pub expires_at: Option<U64>,
+    /// Whether the contract received the asset
+    pub funded: bool,
+    /// Whether the key of the drop was added to the contract account
+    pub key_status: DropKeyStatus,
+    /// The transfer of the assets in progress, if any
+    pub transfer: Option<DropTransfer>,
+}
+
+impl DropRecord {
+    /// Whether the drop has expired at the current block timestamp
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| env::block_timestamp() >= expires_at.0)
+    }
+}
+
+/// `msg` of an `ft_transfer_call` to the contract that funds a token drop,
+/// as JSON, e.g. `{"public_key":"ed25519:..."}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct DropMsg {
+    /// Public key of the drop
+    #[cfg_attr(feature = "abi", schemars(with = "String"))]
+    pub public_key: PublicKey,
+}
+
+/// Events emitted when drops are created, funded, claimed, and refunded
+#[event(
+    standard = "x-drop",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum DropEvent {
+    /// Emitted when a drop is created
+    Create {
+        /// Public key of the drop
+        public_key: PublicKey,
+        /// Account that created the drop
+        funder_id: AccountId,
+        /// The asset of the drop
+        asset: DropAsset,
+        /// Amount of the asset
+        amount: U128,
+    },
+    /// Emitted when the tokens of a token drop are received
+    Fund {
+        /// Public key of the drop
+        public_key: PublicKey,
+    },
+    /// Emitted when a drop is claimed
+    Claim {
+        /// Public key of the drop
+        public_key: PublicKey,
+        /// Account that receives the asset
+        account_id: AccountId,
+    },
+    /// Emitted when an expired drop is refunded
+    Refund {
+        /// Public key of the drop
+        public_key: PublicKey,
+        /// Account that receives the asset
+        funder_id: AccountId,
+    },
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Drop(&'a PublicKey),
+}
+
+/// Drops of NEAR and NEP-141 tokens, claimable with a key
+pub trait Linkdrop {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Drop)
+    }
+
+    /// Storage slot of a drop
+    fn slot_drop(public_key: &PublicKey) -> Slot<DropRecord> {
+        <Self as Linkdrop>::root().field(StorageKey::Drop(public_key))
+    }
+
+    /// Name of the method of the contract that calls [`Linkdrop::claim`].
+    /// The keys of drops may only call this method. `"claim"` by default.
+    fn claim_method_name() -> &'static str {
+        "claim"
+    }
+
+    /// Gas allowance of the key of a drop, in yoctoNEAR, attached by the
+    /// funder. [`DEFAULT_CLAIM_ALLOWANCE`] by default.
+    fn claim_allowance() -> Balance {
+        DEFAULT_CLAIM_ALLOWANCE
+    }
+
+    /// Name of the private method of the contract that calls
+    /// [`Linkdrop::resolve_add_key`]. [`DEFAULT_RESOLVE_ADD_KEY_METHOD_NAME`]
+    /// by default.
+    fn resolve_add_key_method_name() -> &'static str {
+        DEFAULT_RESOLVE_ADD_KEY_METHOD_NAME
+    }
+
+    /// Name of the private method of the contract that calls
+    /// [`Linkdrop::resolve_transfer`].
+    /// [`DEFAULT_RESOLVE_TRANSFER_METHOD_NAME`] by default.
+    fn resolve_transfer_method_name() -> &'static str {
+        DEFAULT_RESOLVE_TRANSFER_METHOD_NAME
+    }
+
+    /// Returns the drop with key `public_key`, if any
+    fn drop_record(public_key: &PublicKey) -> Option<DropRecord> {
+        Self::slot_drop(public_key).read()
+    }
+
+    /// Creates a drop of `amount` of `asset` from the predecessor, claimable
+    /// with `public_key` until `expires_at`, if set. For payable methods: the
+    /// attached deposit must be [`Linkdrop::claim_allowance`], plus `amount`
+    /// for NEAR drops. Token drops must then be funded with
+    /// [`Linkdrop::fund_ft_drop`], once the key is added. Emits a
+    /// [`DropEvent::Create`] event. Returns the promise adding the key to the
+    /// contract account, followed by [`Linkdrop::resolve_add_key`].
+    ///
+    /// # Panics
+    ///
+    /// If a drop with key `public_key` already exists, or if the attached
+    /// deposit is incorrect.
+    fn create_drop(
+        &mut self,
+        public_key: PublicKey,
+        asset: DropAsset,
+        amount: u128,
+        expires_at: Option<u64>,
+    ) -> Promise {
+        let allowance = Self::claim_allowance();
+        let expected_deposit = match asset {
+            DropAsset::Near => amount.checked_add(allowance),
+            DropAsset::FungibleToken { .. } => Some(allowance),
+        };
+        require!(
+            expected_deposit == Some(env::attached_deposit()),
+            INCORRECT_DEPOSIT_FAIL_MESSAGE,
+        );
+
+        let mut slot = Self::slot_drop(&public_key);
+        require!(!slot.exists(), DROP_EXISTS_FAIL_MESSAGE);
+
+        let funder_id = env::predecessor_account_id();
+        slot.write(&DropRecord {
+            funder_id: funder_id.clone(),
+            asset: asset.clone(),
+            amount: amount.into(),
+            allowance: allowance.into(),
+            expires_at: expires_at.map(Into::into),
+            funded: asset == DropAsset::Near,
+            key_status: DropKeyStatus::Pending,
+            transfer: None,
+        });
+
+        DropEvent::Create {
+            public_key: public_key.clone(),
+            funder_id,
+            asset,
+            amount: amount.into(),
+        }
+        .emit();
+
+        let current_account_id = env::current_account_id();
+        Promise::new(current_account_id.clone())
+            .add_access_key(
+                public_key.clone(),
+                allowance,
+                current_account_id.clone(),
+                Self::claim_method_name().to_string(),
+            )
+            .then(Promise::new(current_account_id).function_call(
+                Self::resolve_add_key_method_name().to_string(),
+                json!({ "public_key": public_key }).to_string().into_bytes(),
+                0,
+                GAS_FOR_RESOLVE_ADD_KEY,
+            ))
+    }
+
+    /// Records whether the key of the drop `public_key` was added, from the
+    /// result of the promise returned by [`Linkdrop::create_drop`]. For the
+    /// private callback method named
+    /// [`Linkdrop::resolve_add_key_method_name`].
+    ///
+    /// # Panics
+    ///
+    /// If not called by the contract itself, or if the key of the drop is not
+    /// pending.
+    fn resolve_add_key(&mut self, public_key: PublicKey) {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            CALLBACK_ONLY_FAIL_MESSAGE,
+        );
+
+        let mut slot = Self::slot_drop(&public_key);
+        let mut drop = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_DROP_FAIL_MESSAGE));
+        require!(
+            drop.key_status == DropKeyStatus::Pending,
+            KEY_NOT_PENDING_FAIL_MESSAGE,
+        );
+
+        drop.key_status = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => DropKeyStatus::Added,
+            PromiseResult::Failed => DropKeyStatus::Failed,
+        };
+        slot.write(&drop);
+    }
+
+    /// Funds a token drop with the tokens of an `ft_transfer_call` from
+    /// `sender_id`, for `ft_on_transfer`. The predecessor is the token
+    /// contract, and `msg` is a [`DropMsg`]. Keeps all of the tokens. Emits
+    /// a [`DropEvent::Fund`] event.
+    ///
+    /// # Panics
+    ///
+    /// If `msg` is not a [`DropMsg`], or if the drop does not exist, is
+    /// already funded, has no added key, or was created by another account,
+    /// for another token, or for another amount, in which case the token
+    /// contract refunds the tokens.
+    fn fund_ft_drop(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: &str,
+    ) -> PromiseOrValue<U128> {
+        let DropMsg { public_key } = serde_json::from_str(msg)
+            .unwrap_or_else(|_| env::panic_str(INVALID_FUNDING_FAIL_MESSAGE));
+
+        let mut slot = Self::slot_drop(&public_key);
+        let mut drop = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_DROP_FAIL_MESSAGE));
+
+        let asset = DropAsset::FungibleToken {
+            contract_id: env::predecessor_account_id(),
+        };
+        require!(
+            !drop.funded
+                && drop.key_status == DropKeyStatus::Added
+                && drop.transfer.is_none()
+                && drop.funder_id == sender_id
+                && drop.asset == asset
+                && drop.amount == amount,
+            INVALID_FUNDING_FAIL_MESSAGE,
+        );
+
+        drop.funded = true;
+        slot.write(&drop);
+
+        DropEvent::Fund { public_key }.emit();
+
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Sends the asset of the drop whose key signed the current call to
+    /// `account_id`. The call must be signed by the contract account with
+    /// the key of the drop. Emits a [`DropEvent::Claim`] event. Returns the
+    /// promise transferring the asset, followed by
+    /// [`Linkdrop::resolve_transfer`].
+    ///
+    /// # Panics
+    ///
+    /// If the call was not signed with the key of a drop, or if the key was
+    /// not added by the drop, or if the drop is not funded, has expired, or
+    /// is being transferred.
+    fn claim(&mut self, account_id: AccountId) -> Promise {
+        let current_account_id = env::current_account_id();
+        require!(
+            env::predecessor_account_id() == current_account_id,
+            NOT_SIGNED_BY_CONTRACT_FAIL_MESSAGE,
+        );
+
+        let public_key = env::signer_account_pk();
+        let mut slot = Self::slot_drop(&public_key);
+        let mut drop = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_DROP_FAIL_MESSAGE));
+        require!(
+            drop.key_status == DropKeyStatus::Added,
+            KEY_NOT_ADDED_FAIL_MESSAGE,
+        );
+        require!(drop.funded, NOT_FUNDED_FAIL_MESSAGE);
+        require!(!drop.is_expired(), EXPIRED_FAIL_MESSAGE);
+        require!(drop.transfer.is_none(), TRANSFERRING_FAIL_MESSAGE);
+
+        drop.transfer = Some(DropTransfer::Claim);
+        slot.write(&drop);
+
+        DropEvent::Claim {
+            public_key: public_key.clone(),
+            account_id: account_id.clone(),
+        }
+        .emit();
+
+        transfer(&drop.asset, account_id, drop.amount, "drop claim")
+            .then(resolve_transfer::<Self>(public_key))
+    }
+
+    /// Sends the asset of a drop back to its funder, with the allowance
+    /// (for a funded token drop, once the tokens are sent). Anyone may
+    /// refund an expired drop, or a drop whose key could not be added, and
+    /// the funder may also refund a drop that is not funded, or that never
+    /// expires. Emits a [`DropEvent::Refund`] event. Returns the promise
+    /// transferring the asset, followed by [`Linkdrop::resolve_transfer`].
+    ///
+    /// # Panics
+    ///
+    /// If there is no drop with key `public_key`, if its key is pending, if
+    /// it is being transferred, or if its key was added and it may not be
+    /// refunded yet.
+    fn refund_drop(&mut self, public_key: PublicKey) -> Promise {
+        let mut slot = Self::slot_drop(&public_key);
+        let mut drop = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_DROP_FAIL_MESSAGE));
+        require!(drop.transfer.is_none(), TRANSFERRING_FAIL_MESSAGE);
+
+        match drop.key_status {
+            DropKeyStatus::Pending => env::panic_str(KEY_NOT_ADDED_FAIL_MESSAGE),
+            DropKeyStatus::Added => {
+                let by_funder = env::predecessor_account_id() == drop.funder_id
+                    && (!drop.funded || drop.expires_at.is_none());
+                require!(by_funder || drop.is_expired(), NOT_EXPIRED_FAIL_MESSAGE);
+            }
+            DropKeyStatus::Failed => {}
+        }
+
+        drop.transfer = Some(DropTransfer::Refund);
+        slot.write(&drop);
+
+        let DropRecord {
+            funder_id,
+            asset,
+            amount,
+            allowance,
+            funded,
+            ..
+        } = drop;
+
+        // The allowance of a funded token drop is sent once its tokens are
+        let promise = match asset {
+            DropAsset::Near => Promise::new(funder_id.clone()).transfer(amount.0 + allowance.0),
+            DropAsset::FungibleToken { .. } if funded => {
+                transfer(&asset, funder_id.clone(), amount, "drop refund")
+            }
+            DropAsset::FungibleToken { .. } => {
+                Promise::new(funder_id.clone()).transfer(allowance.0)
+            }
+        };
+
+        DropEvent::Refund {
+            public_key: public_key.clone(),
+            funder_id,
+        }
+        .emit();
+
+        promise.then(resolve_transfer::<Self>(public_key))
+    }
+
+    /// Completes the transfer of the assets of the drop `public_key`, from
+    /// the result of the promise returned by [`Linkdrop::claim`] or
+    /// [`Linkdrop::refund_drop`]. If the transfer succeeded, removes the
+    /// drop, deletes its key if the drop added it, and sends the allowance
+    /// of a refunded token drop to the funder. If it failed, the drop may be
+    /// claimed or refunded again. For the private callback method named
+    /// [`Linkdrop::resolve_transfer_method_name`].
+    ///
+    /// # Panics
+    ///
+    /// If not called by the contract itself, or if the drop is not being
+    /// transferred.
+    fn resolve_transfer(&mut self, public_key: PublicKey) {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            CALLBACK_ONLY_FAIL_MESSAGE,
+        );
+
+        let mut slot = Self::slot_drop(&public_key);
+        let mut drop = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_DROP_FAIL_MESSAGE));
+        let transfer = drop
+            .transfer
+            .take()
+            .unwrap_or_else(|| env::panic_str(NOT_TRANSFERRING_FAIL_MESSAGE));
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {
+                slot.remove();
+
+                if drop.key_status == DropKeyStatus::Added {
+                    Promise::new(env::current_account_id()).delete_key(public_key);
+                }
+
+                if transfer == DropTransfer::Refund && drop.funded && drop.asset != DropAsset::Near
+                {
+                    Promise::new(drop.funder_id).transfer(drop.allowance.0);
+                }
+            }
+            PromiseResult::Failed => {
+                slot.write(&drop);
+            }
+        }
+    }
+}
+
+/// The callback [`Linkdrop::resolve_transfer`] of the drop `public_key`
+fn resolve_transfer<C: Linkdrop + ?Sized>(public_key: PublicKey) -> Promise {
+    Promise::new(env::current_account_id()).function_call(
+        C::resolve_transfer_method_name().to_string(),
+        json!({ "public_key": public_key }).to_string().into_bytes(),
+        0,
+        GAS_FOR_RESOLVE_TRANSFER,
+    )
+}
+
+/// Sends `amount` of `asset` from the contract to `receiver_id`
+fn transfer(asset: &DropAsset, receiver_id: AccountId, amount: U128, memo: &str) -> Promise {
+    match asset {
+        DropAsset::Near => Promise::new(receiver_id).transfer(amount.0),
+        DropAsset::FungibleToken { contract_id } => ext_nep141::ext(contract_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(receiver_id, amount, Some(memo.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        env, json_types::U128, mock::VmAction, test_utils::get_created_receipts, PromiseResult,
+        PublicKey,
+    };
+
+    use super::{DropAsset, DropKeyStatus, DropTransfer, Linkdrop, DEFAULT_CLAIM_ALLOWANCE};
+    use crate::testing::{account, TestEnv};
+
+    struct Contract;
+
+    impl Linkdrop for Contract {}
+
+    fn public_key() -> PublicKey {
+        "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap()
+    }
+
+    fn token() -> DropAsset {
+        DropAsset::FungibleToken {
+            contract_id: account("token"),
+        }
+    }
+
+    /// Creates a drop from alice, whose key is not yet added, and returns the
+    /// environment
+    fn create_pending(asset: DropAsset, amount: u128, expires_at: Option<u64>) -> TestEnv {
+        let mut env = TestEnv::new();
+        let deposit = match asset {
+            DropAsset::Near => amount + DEFAULT_CLAIM_ALLOWANCE,
+            DropAsset::FungibleToken { .. } => DEFAULT_CLAIM_ALLOWANCE,
+        };
+
+        env.set_predecessor(&account("alice")).set_deposit(deposit);
+        Contract.create_drop(public_key(), asset, amount, expires_at);
+        env.set_deposit(0);
+
+        env
+    }
+
+    /// Resolves the promise adding the key of the drop with `result`, then
+    /// returns to calls from alice
+    fn resolve_add_key(env: &mut TestEnv, result: PromiseResult) {
+        env.set_predecessor(&env::current_account_id())
+            .set_promise_results(vec![result]);
+        Contract.resolve_add_key(public_key());
+        env.set_promise_results(vec![])
+            .set_predecessor(&account("alice"));
+    }
+
+    /// Creates a drop from alice, whose key is added, and returns the
+    /// environment
+    fn create(asset: DropAsset, amount: u128, expires_at: Option<u64>) -> TestEnv {
+        let mut env = create_pending(asset, amount, expires_at);
+        resolve_add_key(&mut env, PromiseResult::Successful(vec![]));
+        env
+    }
+
+    /// Resolves the transfer of the assets of the drop with `result`
+    fn resolve_transfer(env: &mut TestEnv, result: PromiseResult) {
+        env.set_predecessor(&env::current_account_id())
+            .set_promise_results(vec![result]);
+        Contract.resolve_transfer(public_key());
+    }
+
+    /// Whether a receipt of the current call sends `deposit` to `receiver`
+    fn transfers(receiver: &str, deposit: u128) -> bool {
+        get_created_receipts().iter().any(|receipt| {
+            receipt.receiver_id == account(receiver)
+                && receipt.actions == vec![VmAction::Transfer { deposit }]
+        })
+    }
+
+    /// Whether a receipt of the current call deletes a key
+    fn deletes_key() -> bool {
+        get_created_receipts().iter().any(|receipt| {
+            receipt
+                .actions
+                .iter()
+                .any(|action| matches!(action, VmAction::DeleteKey { .. }))
+        })
+    }
+
+    /// Signs the following calls with the key of the drop
+    fn sign_with_drop_key(env: &mut TestEnv) {
+        env.set_predecessor(&env::current_account_id())
+            .set_signer_pk(&public_key());
+    }
+
+    #[test]
+    fn claim_near() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        let drop = Contract::drop_record(&public_key()).unwrap();
+        assert_eq!(drop.funder_id, account("alice"));
+        assert_eq!(drop.amount, U128(100));
+        assert!(drop.funded);
+        assert_eq!(drop.key_status, DropKeyStatus::Added);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        assert!(transfers("bob", 100));
+        assert!(!deletes_key());
+        assert_eq!(
+            Contract::drop_record(&public_key()).unwrap().transfer,
+            Some(DropTransfer::Claim),
+        );
+
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+        assert!(deletes_key());
+    }
+
+    #[test]
+    fn failed_claim_claimed_again() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        resolve_transfer(&mut env, PromiseResult::Failed);
+        assert!(!deletes_key());
+
+        let drop = Contract::drop_record(&public_key()).unwrap();
+        assert_eq!(drop.transfer, None);
+        assert_eq!(drop.key_status, DropKeyStatus::Added);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("carol"));
+        assert!(transfers("carol", 100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop is not being transferred")]
+    fn resolve_transfer_twice() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        resolve_transfer(&mut env, PromiseResult::Failed);
+        resolve_transfer(&mut env, PromiseResult::Failed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback only")]
+    fn resolve_transfer_external() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        env.set_predecessor(&account("alice"))
+            .set_promise_results(vec![PromiseResult::Failed]);
+        Contract.resolve_transfer(public_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "Incorrect deposit for drop")]
+    fn create_incorrect_deposit() {
+        let mut env = TestEnv::new();
+
+        env.set_predecessor(&account("alice")).set_deposit(100);
+        Contract.create_drop(public_key(), DropAsset::Near, 100, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop already exists")]
+    fn create_twice() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        env.set_deposit(100 + DEFAULT_CLAIM_ALLOWANCE);
+        Contract.create_drop(public_key(), DropAsset::Near, 100, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims must be signed with the key of the drop")]
+    fn claim_from_other_account() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        env.set_signer_pk(&public_key());
+        Contract.claim(account("alice"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop is being transferred")]
+    fn claim_twice() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        Contract.claim(account("bob"));
+    }
+
+    #[test]
+    fn fund_and_claim_ft() {
+        let mut env = create(token(), 50, None);
+        assert!(!Contract::drop_record(&public_key()).unwrap().funded);
+
+        let msg = format!(r#"{{"public_key":"{}"}}"#, String::from(&public_key()));
+        env.set_predecessor(&account("token"));
+        Contract.fund_ft_drop(account("alice"), U128(50), &msg);
+        assert!(Contract::drop_record(&public_key()).unwrap().funded);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid drop funding")]
+    fn fund_ft_while_refunding() {
+        let mut env = create(token(), 50, None);
+        Contract.refund_drop(public_key());
+
+        let msg = format!(r#"{{"public_key":"{}"}}"#, String::from(&public_key()));
+        env.set_predecessor(&account("token"));
+        Contract.fund_ft_drop(account("alice"), U128(50), &msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid drop funding")]
+    fn fund_ft_wrong_amount() {
+        let mut env = create(token(), 50, None);
+
+        let msg = format!(r#"{{"public_key":"{}"}}"#, String::from(&public_key()));
+        env.set_predecessor(&account("token"));
+        Contract.fund_ft_drop(account("alice"), U128(49), &msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop is not funded")]
+    fn claim_unfunded() {
+        let mut env = create(token(), 50, None);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop expired")]
+    fn claim_expired() {
+        let mut env = create(DropAsset::Near, 100, Some(1_000));
+
+        sign_with_drop_key(&mut env);
+        env.set_block_timestamp(1_000);
+        Contract.claim(account("bob"));
+    }
+
+    #[test]
+    fn refund_expired() {
+        let mut env = create(DropAsset::Near, 100, Some(1_000));
+
+        env.set_predecessor(&account("bob"))
+            .set_block_timestamp(1_000);
+        Contract.refund_drop(public_key());
+        assert!(transfers("alice", 100 + DEFAULT_CLAIM_ALLOWANCE));
+
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+        assert!(deletes_key());
+    }
+
+    #[test]
+    fn refund_unfunded_by_funder() {
+        let mut env = create(token(), 50, Some(1_000));
+
+        Contract.refund_drop(public_key());
+        assert!(transfers("alice", DEFAULT_CLAIM_ALLOWANCE));
+
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+        assert!(deletes_key());
+    }
+
+    #[test]
+    fn refund_funded_ft_allowance_after_tokens() {
+        let mut env = create(token(), 50, None);
+        let msg = format!(r#"{{"public_key":"{}"}}"#, String::from(&public_key()));
+        env.set_predecessor(&account("token"));
+        Contract.fund_ft_drop(account("alice"), U128(50), &msg);
+
+        env.set_predecessor(&account("alice"));
+        Contract.refund_drop(public_key());
+        assert!(!transfers("alice", DEFAULT_CLAIM_ALLOWANCE));
+
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert!(transfers("alice", DEFAULT_CLAIM_ALLOWANCE));
+    }
+
+    #[test]
+    fn refund_without_expiry_by_funder() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        Contract.refund_drop(public_key());
+        resolve_transfer(&mut env, PromiseResult::Failed);
+        assert_eq!(Contract::drop_record(&public_key()).unwrap().transfer, None);
+
+        env.set_predecessor(&account("alice"));
+        Contract.refund_drop(public_key());
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop has not expired")]
+    fn refund_without_expiry_by_other() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        env.set_predecessor(&account("bob"));
+        Contract.refund_drop(public_key());
+    }
+
+    #[test]
+    fn refund_failed_key() {
+        // e.g. the contract account already has the key
+        let mut env = create_pending(DropAsset::Near, 100, Some(1_000));
+        resolve_add_key(&mut env, PromiseResult::Failed);
+        assert_eq!(
+            Contract::drop_record(&public_key()).unwrap().key_status,
+            DropKeyStatus::Failed,
+        );
+
+        // Refundable before it expires
+        env.set_predecessor(&account("bob"));
+        Contract.refund_drop(public_key());
+        assert!(transfers("alice", 100 + DEFAULT_CLAIM_ALLOWANCE));
+
+        resolve_transfer(&mut env, PromiseResult::Successful(vec![]));
+        assert_eq!(Contract::drop_record(&public_key()), None);
+        assert!(!deletes_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop key is not added")]
+    fn claim_failed_key() {
+        let mut env = create_pending(DropAsset::Near, 100, None);
+        resolve_add_key(&mut env, PromiseResult::Failed);
+
+        sign_with_drop_key(&mut env);
+        Contract.claim(account("bob"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop key is not added")]
+    fn refund_pending_key() {
+        let mut env = create_pending(DropAsset::Near, 100, Some(1_000));
+
+        env.set_block_timestamp(1_000);
+        Contract.refund_drop(public_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback only")]
+    fn resolve_add_key_external() {
+        let mut env = create_pending(DropAsset::Near, 100, None);
+
+        env.set_predecessor(&account("alice"))
+            .set_promise_results(vec![PromiseResult::Successful(vec![])]);
+        Contract.resolve_add_key(public_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop key is not pending")]
+    fn resolve_add_key_twice() {
+        let mut env = create(DropAsset::Near, 100, None);
+
+        resolve_add_key(&mut env, PromiseResult::Failed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Drop has not expired")]
+    fn refund_before_expiry() {
+        let mut env = create(DropAsset::Near, 100, Some(1_000));
+
+        env.set_block_timestamp(999);
+        Contract.refund_drop(public_key());
+    }
+}
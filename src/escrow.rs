@@ -0,0 +1,603 @@
+//! Escrow pattern: a depositor locks NEAR or NEP-141 tokens under an
+//! identifier, and an arbiter later releases them to a beneficiary, or
+//! refunds them to the depositor.
+//!
+//! [`Escrow`] keeps the escrow records and moves the assets. Who may settle
+//! an escrow is decided by [`EscrowHook::on_settle`], which the contract
+//! implements, e.g. with [`Owner::require_owner`](crate::owner::Owner::require_owner),
+//! [`Rbac::require_role`](crate::rbac::Rbac::require_role), or by requiring a
+//! call from the contract itself for a multisig.
+//!
+//! Assets are locked either from a payable method, with
+//! [`Escrow::lock_attached_deposit`], or from `ft_on_transfer`, with
+//! [`Escrow::lock_ft_transfer`], where the `msg` of the transfer is an
+//! [`EscrowMsg`].
+//!
+//! An escrow is marked as settled when its assets are sent. If the transfer
+//! fails (e.g. the beneficiary is not registered with the token contract),
+//! the callback [`Escrow::resolve_settle`] locks it again, so that it may be
+//! settled again.
+//!
+//! ```
+//! use near_sdk::{
+//!     env, json_types::U128, near_bindgen, AccountId, Promise, PromiseOrValue,
+//! };
+//! use near_sdk_contract_tools::{
+//!     escrow::{Escrow, EscrowHook, EscrowRecord, EscrowState},
+//!     owner::Owner,
+//!     standard::nep141::Nep141Receiver,
+//!     Owner,
+//! };
+//!
+//! #[derive(Owner)]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl EscrowHook for Contract {
+//!     fn on_settle(&self, _escrow: &EscrowRecord, _state: &EscrowState) {
+//!         Self::require_owner();
+//!     }
+//! }
+//!
+//! impl Escrow for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     #[payable]
+//!     pub fn lock(&mut self, id: String, beneficiary_id: AccountId) {
+//!         self.lock_attached_deposit(id, beneficiary_id);
+//!     }
+//!
+//!     pub fn release(&mut self, id: String) -> Promise {
+//!         Escrow::release(self, &id)
+//!     }
+//!
+//!     #[private]
+//!     pub fn escrow_resolve_settle(&mut self, id: String) {
+//!         self.resolve_settle(&id)
+//!     }
+//! }
+//!
+//! #[near_bindgen]
+//! impl Nep141Receiver for Contract {
+//!     fn ft_on_transfer(
+//!         &mut self,
+//!         sender_id: AccountId,
+//!         amount: U128,
+//!         msg: String,
+//!     ) -> PromiseOrValue<U128> {
+//!         self.lock_ft_transfer(sender_id, amount, &msg)
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The escrow root storage slot is not used or modified. The default key is `~es`.
+//! * (UB) The contract holds the locked assets until they are settled.
+//! * (UB) The method named [`Escrow::resolve_settle_method_name`] calls
+//!   [`Escrow::resolve_settle`].
+//! * (ERR) An identifier may be used by one escrow only, even after it is settled.
+//! * (ERR) An escrow may be settled (released or refunded) once, unless the
+//!   transfer of its assets fails.
+//! * (ERR) [`EscrowHook::on_settle`] is called before every settlement.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    require,
+    serde_json::json,
+    AccountId, BorshStorageKey, Gas, Promise, PromiseOrValue, PromiseResult,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slot::Slot,
+    standard::{nep141::ext_nep141, nep297::Event},
+    DefaultStorageKey,
+};
+
+const ESCROW_EXISTS_FAIL_MESSAGE: &str = message!("ESC1", "Escrow already exists");
+const ZERO_AMOUNT_FAIL_MESSAGE: &str = message!("ESC2", "Cannot lock zero");
+const NO_ESCROW_FAIL_MESSAGE: &str = message!("ESC3", "No such escrow");
+const SETTLED_FAIL_MESSAGE: &str = message!("ESC4", "Escrow already settled");
+const INVALID_MSG_FAIL_MESSAGE: &str = message!("ESC5", "Invalid escrow message");
+const CALLBACK_ONLY_FAIL_MESSAGE: &str = message!("ESC6", "Callback only");
+
+/// Gas attached to the `ft_transfer` that settles a token escrow
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+/// Default of [`Escrow::resolve_settle_method_name`]
+pub const DEFAULT_RESOLVE_SETTLE_METHOD_NAME: &str = "escrow_resolve_settle";
+/// Gas attached to the callback [`Escrow::resolve_settle`]
+pub const GAS_FOR_RESOLVE_SETTLE: Gas = Gas(10_000_000_000_000);
+
+/// Identifier of an escrow, chosen by the depositor
+pub type EscrowId = String;
+
+/// The asset held in escrow
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EscrowAsset {
+    /// NEAR, attached to the call that locked it
+    Near,
+    /// NEP-141 tokens, received with `ft_transfer_call`
+    FungibleToken {
+        /// The token contract
+        contract_id: AccountId,
+    },
+}
+
+/// Whether an escrow is still locked, or how it was settled
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowState {
+    /// The assets are held by the contract
+    Locked,
+    /// The assets were sent to the beneficiary
+    Released,
+    /// The assets were sent back to the depositor
+    Refunded,
+}
+
+/// An escrow, as stored by [`Escrow`]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct EscrowRecord {
+    /// Identifier of the escrow
+    pub id: EscrowId,
+    /// Account that locked the assets, and receives them on refund
+    pub depositor_id: AccountId,
+    /// Account that receives the assets on release
+    pub beneficiary_id: AccountId,
+    /// The locked asset
+    pub asset: EscrowAsset,
+    /// Amount of the asset
+    pub amount: U128,
+    /// Current state
+    pub state: EscrowState,
+}
+
+/// `msg` of an `ft_transfer_call` to the contract that locks the tokens, as
+/// JSON, e.g. `{"id":"order-1","beneficiary_id":"bob.near"}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct EscrowMsg {
+    /// Identifier of the new escrow
+    pub id: EscrowId,
+    /// Account that receives the tokens on release
+    pub beneficiary_id: AccountId,
+}
+
+/// Events emitted when escrows are locked and settled
+#[event(
+    standard = "x-escrow",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum EscrowEvent {
+    /// Emitted when assets are locked
+    Lock {
+        /// Identifier of the escrow
+        id: EscrowId,
+        /// Account that locked the assets
+        depositor_id: AccountId,
+        /// Account that receives the assets on release
+        beneficiary_id: AccountId,
+        /// The locked asset
+        asset: EscrowAsset,
+        /// Amount of the asset
+        amount: U128,
+    },
+    /// Emitted when assets are sent to the beneficiary
+    Release {
+        /// Identifier of the escrow
+        id: EscrowId,
+        /// Account that receives the assets
+        beneficiary_id: AccountId,
+    },
+    /// Emitted when assets are sent back to the depositor
+    Refund {
+        /// Identifier of the escrow
+        id: EscrowId,
+        /// Account that receives the assets
+        depositor_id: AccountId,
+    },
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Escrow(&'a str),
+}
+
+/// Authorization of escrow settlements
+pub trait EscrowHook {
+    /// Called before an escrow is released or refunded, with the state it
+    /// is about to enter. Panics if the predecessor may not settle it.
+    fn on_settle(&self, escrow: &EscrowRecord, state: &EscrowState);
+}
+
+/// Escrows of NEAR and NEP-141 tokens, settled by an arbiter
+pub trait Escrow: EscrowHook {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Escrow)
+    }
+
+    /// Storage slot of an escrow record
+    fn slot_escrow(id: &str) -> Slot<EscrowRecord> {
+        Self::root().field(StorageKey::Escrow(id))
+    }
+
+    /// Name of the private method of the contract that calls
+    /// [`Escrow::resolve_settle`]. [`DEFAULT_RESOLVE_SETTLE_METHOD_NAME`] by
+    /// default.
+    fn resolve_settle_method_name() -> &'static str {
+        DEFAULT_RESOLVE_SETTLE_METHOD_NAME
+    }
+
+    /// Returns the escrow with identifier `id`, if any
+    fn escrow(&self, id: &str) -> Option<EscrowRecord> {
+        Self::slot_escrow(id).read()
+    }
+
+    /// Records that `depositor_id` locked `amount` of `asset`, which the
+    /// contract must have received. Emits an [`EscrowEvent::Lock`] event.
+    ///
+    /// # Panics
+    ///
+    /// If an escrow with identifier `id` already exists, or if `amount` is
+    /// zero.
+    fn lock(
+        &mut self,
+        id: EscrowId,
+        depositor_id: AccountId,
+        beneficiary_id: AccountId,
+        asset: EscrowAsset,
+        amount: u128,
+    ) {
+        require!(amount > 0, ZERO_AMOUNT_FAIL_MESSAGE);
+
+        let mut slot = Self::slot_escrow(&id);
+        require!(!slot.exists(), ESCROW_EXISTS_FAIL_MESSAGE);
+
+        slot.write(&EscrowRecord {
+            id: id.clone(),
+            depositor_id: depositor_id.clone(),
+            beneficiary_id: beneficiary_id.clone(),
+            asset: asset.clone(),
+            amount: amount.into(),
+            state: EscrowState::Locked,
+        });
+
+        EscrowEvent::Lock {
+            id,
+            depositor_id,
+            beneficiary_id,
+            asset,
+            amount: amount.into(),
+        }
+        .emit();
+    }
+
+    /// Locks the NEAR attached to the current call, from the predecessor.
+    /// For payable methods.
+    fn lock_attached_deposit(&mut self, id: EscrowId, beneficiary_id: AccountId) {
+        self.lock(
+            id,
+            env::predecessor_account_id(),
+            beneficiary_id,
+            EscrowAsset::Near,
+            env::attached_deposit(),
+        );
+    }
+
+    /// Locks the tokens of an `ft_transfer_call` from `sender_id`, for
+    /// `ft_on_transfer`. The predecessor is the token contract, and `msg` is
+    /// an [`EscrowMsg`]. Keeps all of the tokens.
+    ///
+    /// # Panics
+    ///
+    /// If `msg` is not an [`EscrowMsg`], or if the tokens cannot be locked,
+    /// in which case the token contract refunds them.
+    fn lock_ft_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: &str,
+    ) -> PromiseOrValue<U128> {
+        let EscrowMsg { id, beneficiary_id } =
+            serde_json::from_str(msg).unwrap_or_else(|_| env::panic_str(INVALID_MSG_FAIL_MESSAGE));
+
+        self.lock(
+            id,
+            sender_id,
+            beneficiary_id,
+            EscrowAsset::FungibleToken {
+                contract_id: env::predecessor_account_id(),
+            },
+            amount.into(),
+        );
+
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Sends the assets of an escrow to its beneficiary. Emits an
+    /// [`EscrowEvent::Release`] event. Returns the transfer, followed by
+    /// [`Escrow::resolve_settle`].
+    ///
+    /// # Panics
+    ///
+    /// If there is no escrow with identifier `id`, if it is already settled,
+    /// or if [`EscrowHook::on_settle`] panics.
+    fn release(&mut self, id: &str) -> Promise {
+        settle(self, id, EscrowState::Released)
+    }
+
+    /// Sends the assets of an escrow back to its depositor. Emits an
+    /// [`EscrowEvent::Refund`] event. Returns the transfer, followed by
+    /// [`Escrow::resolve_settle`].
+    ///
+    /// # Panics
+    ///
+    /// If there is no escrow with identifier `id`, if it is already settled,
+    /// or if [`EscrowHook::on_settle`] panics.
+    fn refund(&mut self, id: &str) -> Promise {
+        settle(self, id, EscrowState::Refunded)
+    }
+
+    /// Locks the escrow `id` again if the transfer of its assets failed,
+    /// from the result of the promise returned by [`Escrow::release`] or
+    /// [`Escrow::refund`]. For the private callback method named
+    /// [`Escrow::resolve_settle_method_name`].
+    ///
+    /// # Panics
+    ///
+    /// If not called by the contract itself, or if there is no escrow with
+    /// identifier `id`.
+    fn resolve_settle(&mut self, id: &str) {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            CALLBACK_ONLY_FAIL_MESSAGE,
+        );
+
+        let mut slot = Self::slot_escrow(id);
+        let mut escrow = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_ESCROW_FAIL_MESSAGE));
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                escrow.state = EscrowState::Locked;
+                slot.write(&escrow);
+            }
+        }
+    }
+}
+
+/// Marks an escrow as settled, after [`EscrowHook::on_settle`], and sends
+/// its assets to the beneficiary (`Released`) or to the depositor
+/// (`Refunded`), followed by [`Escrow::resolve_settle`].
+fn settle<C: Escrow + ?Sized>(contract: &mut C, id: &str, state: EscrowState) -> Promise {
+    let mut slot = C::slot_escrow(id);
+    let mut escrow = slot
+        .read()
+        .unwrap_or_else(|| env::panic_str(NO_ESCROW_FAIL_MESSAGE));
+    require!(escrow.state == EscrowState::Locked, SETTLED_FAIL_MESSAGE);
+
+    contract.on_settle(&escrow, &state);
+
+    let receiver_id = if state == EscrowState::Released {
+        escrow.beneficiary_id.clone()
+    } else {
+        escrow.depositor_id.clone()
+    };
+
+    escrow.state = state;
+    slot.write(&escrow);
+
+    let EscrowRecord {
+        id,
+        depositor_id,
+        beneficiary_id,
+        asset,
+        amount,
+        state,
+    } = escrow;
+
+    let transfer = match asset {
+        EscrowAsset::Near => Promise::new(receiver_id).transfer(amount.0),
+        EscrowAsset::FungibleToken { contract_id } => ext_nep141::ext(contract_id)
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(receiver_id, amount, Some(format!("escrow {id}"))),
+    };
+
+    let resolve = Promise::new(env::current_account_id()).function_call(
+        C::resolve_settle_method_name().to_string(),
+        json!({ "id": id }).to_string().into_bytes(),
+        0,
+        GAS_FOR_RESOLVE_SETTLE,
+    );
+
+    if state == EscrowState::Released {
+        EscrowEvent::Release { id, beneficiary_id }.emit();
+    } else {
+        EscrowEvent::Refund { id, depositor_id }.emit();
+    }
+
+    transfer.then(resolve)
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{env, json_types::U128, near_bindgen, PromiseResult};
+
+    use super::{Escrow, EscrowAsset, EscrowHook, EscrowRecord, EscrowState};
+    use crate::{
+        owner::Owner,
+        testing::{account, TestEnv},
+        Owner,
+    };
+
+    #[derive(Owner)]
+    #[owner(crate = "crate")]
+    #[near_bindgen]
+    struct Contract {}
+
+    impl EscrowHook for Contract {
+        fn on_settle(&self, _escrow: &EscrowRecord, _state: &EscrowState) {
+            Self::require_owner();
+        }
+    }
+
+    impl Escrow for Contract {}
+
+    fn setup(env: &mut TestEnv) -> Contract {
+        let mut contract = Contract {};
+        Owner::init(&mut contract, &account("arbiter"));
+
+        env.set_predecessor(&account("alice")).set_deposit(100);
+        contract.lock_attached_deposit("a".to_string(), account("bob"));
+
+        contract
+    }
+
+    #[test]
+    fn lock_and_release() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        assert_eq!(
+            contract.escrow("a"),
+            Some(EscrowRecord {
+                id: "a".to_string(),
+                depositor_id: account("alice"),
+                beneficiary_id: account("bob"),
+                asset: EscrowAsset::Near,
+                amount: U128(100),
+                state: EscrowState::Locked,
+            }),
+        );
+
+        env.set_predecessor(&account("arbiter")).set_deposit(0);
+        contract.release("a");
+
+        assert_eq!(contract.escrow("a").unwrap().state, EscrowState::Released);
+    }
+
+    /// Resolves the settlement of escrow "a" with `result`
+    fn resolve_settle(env: &mut TestEnv, contract: &mut Contract, result: PromiseResult) {
+        env.set_predecessor(&env::current_account_id())
+            .set_promise_results(vec![result]);
+        contract.resolve_settle("a");
+        env.set_promise_results(vec![]);
+    }
+
+    #[test]
+    fn failed_settlement_locked_again() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        env.set_predecessor(&account("arbiter")).set_deposit(0);
+        contract.release("a");
+        resolve_settle(&mut env, &mut contract, PromiseResult::Failed);
+        assert_eq!(contract.escrow("a").unwrap().state, EscrowState::Locked);
+
+        env.set_predecessor(&account("arbiter"));
+        contract.refund("a");
+        resolve_settle(&mut env, &mut contract, PromiseResult::Successful(vec![]));
+        assert_eq!(contract.escrow("a").unwrap().state, EscrowState::Refunded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback only")]
+    fn resolve_settle_external() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        env.set_predecessor(&account("arbiter")).set_deposit(0);
+        contract.release("a");
+        env.set_promise_results(vec![PromiseResult::Failed]);
+        contract.resolve_settle("a");
+    }
+
+    #[test]
+    #[should_panic(expected = "Escrow already settled")]
+    fn double_settlement() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        env.set_predecessor(&account("arbiter")).set_deposit(0);
+        contract.refund("a");
+        contract.release("a");
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner only")]
+    fn settle_unauthorized() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        contract.release("a");
+    }
+
+    #[test]
+    #[should_panic(expected = "Escrow already exists")]
+    fn reuse_id() {
+        let mut env = TestEnv::new();
+        let mut contract = setup(&mut env);
+
+        env.set_predecessor(&account("arbiter")).set_deposit(0);
+        contract.refund("a");
+
+        env.set_predecessor(&account("alice")).set_deposit(100);
+        contract.lock_attached_deposit("a".to_string(), account("bob"));
+    }
+
+    #[test]
+    fn lock_ft_transfer() {
+        let mut env = TestEnv::new();
+        let mut contract = Contract {};
+
+        env.set_predecessor(&account("token"));
+        contract.lock_ft_transfer(
+            account("alice"),
+            U128(50),
+            r#"{"id":"b","beneficiary_id":"bob"}"#,
+        );
+
+        let escrow = contract.escrow("b").unwrap();
+        assert_eq!(escrow.depositor_id, account("alice"));
+        assert_eq!(escrow.beneficiary_id, account("bob"));
+        assert_eq!(
+            escrow.asset,
+            EscrowAsset::FungibleToken {
+                contract_id: account("token"),
+            },
+        );
+        assert_eq!(escrow.amount, U128(50));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid escrow message")]
+    fn lock_ft_transfer_invalid_msg() {
+        let mut env = TestEnv::new();
+        let mut contract = Contract {};
+
+        env.set_predecessor(&account("token"));
+        contract.lock_ft_transfer(account("alice"), U128(50), "b");
+    }
+}
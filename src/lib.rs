@@ -3,40 +3,199 @@
 /// Default storage keys used by various traits' `root()` functions.
 #[derive(Clone, Debug)]
 pub enum DefaultStorageKey {
+    /// Default storage key for [`account_registry::AccountRegistry::root`]
+    AccountRegistry,
+    /// Default storage key for [`accesslist::AccessList::root`]
+    AccessList,
     /// Default storage key for [`approval::ApprovalManager::root`]
     ApprovalManager,
+    /// Default storage key for [`drop::Linkdrop::root`]
+    Drop,
+    /// Default storage key for [`escrow::Escrow::root`]
+    Escrow,
     /// Default storage key for [`standard::nep141::Nep141Controller::root`]
     Nep141,
+    /// Default storage key for [`standard::nep148::Nep148Controller::root`]
+    Nep148,
+    /// Default storage key for [`standard::nep245::Nep245Controller::root`]
+    Nep245,
+    /// Default storage key for [`standard::nep413::Nep413Controller::root`]
+    Nep413,
+    /// Default storage key for [`migrate::MigrateController::slot_migrated_version`]
+    MigratedVersion,
+    /// Default storage key for [`nonce::Nonce::root`]
+    Nonce,
     /// Default storage key for [`owner::Owner::root`]
     Owner,
     /// Default storage key for [`pause::Pause::root`]
     Pause,
+    /// Default storage key for [`migrate::ProgressiveMigration::root`]
+    ProgressiveMigration,
+    /// Default storage key for [`rate_limit::RateLimit::root`]
+    RateLimit,
     /// Default storage key for [`rbac::Rbac::root`]
     Rbac,
+    /// Default storage key for [`reentrancy::Reentrancy::root`]
+    Reentrancy,
+    /// Default storage key for [`splitter::Splitter::root`]
+    Splitter,
+    /// Default storage key for [`upgrade::staged::StagedUpgrade::root`]
+    StagedUpgrade,
+    /// Default storage key for [`migrate::MigrateChain::slot_state_version`]
+    StateVersion,
+    /// Default storage key for [`timelock::Timelock::root`]
+    Timelock,
+    /// Default storage key for [`upgrade::rollback::UpgradeRollback::root`]
+    UpgradeRollback,
+    /// Default storage key for [`vesting::Vesting::root`]
+    Vesting,
+    /// Default storage key for [`xcall::XCall::root`]
+    XCall,
 }
 
-impl IntoStorageKey for DefaultStorageKey {
-    fn into_storage_key(self) -> Vec<u8> {
+impl DefaultStorageKey {
+    /// All default storage keys used by the built-in components
+    pub const ALL: [DefaultStorageKey; 24] = [
+        DefaultStorageKey::AccountRegistry,
+        DefaultStorageKey::AccessList,
+        DefaultStorageKey::ApprovalManager,
+        DefaultStorageKey::Drop,
+        DefaultStorageKey::Escrow,
+        DefaultStorageKey::Nep141,
+        DefaultStorageKey::Nep148,
+        DefaultStorageKey::Nep245,
+        DefaultStorageKey::Nep413,
+        DefaultStorageKey::MigratedVersion,
+        DefaultStorageKey::Nonce,
+        DefaultStorageKey::Owner,
+        DefaultStorageKey::Pause,
+        DefaultStorageKey::ProgressiveMigration,
+        DefaultStorageKey::RateLimit,
+        DefaultStorageKey::Rbac,
+        DefaultStorageKey::Reentrancy,
+        DefaultStorageKey::Splitter,
+        DefaultStorageKey::StagedUpgrade,
+        DefaultStorageKey::StateVersion,
+        DefaultStorageKey::Timelock,
+        DefaultStorageKey::UpgradeRollback,
+        DefaultStorageKey::Vesting,
+        DefaultStorageKey::XCall,
+    ];
+
+    /// Returns `true` if the key is used as a prefix for other keys, `false`
+    /// if the key is a single slot.
+    pub fn is_namespace(&self) -> bool {
+        !matches!(
+            self,
+            DefaultStorageKey::MigratedVersion
+                | DefaultStorageKey::Pause
+                | DefaultStorageKey::StateVersion,
+        )
+    }
+
+    /// The storage key as bytes. Usable in constant expressions, e.g. to
+    /// compute key lengths at compile time.
+    pub const fn as_bytes(&self) -> &'static [u8] {
         match self {
-            DefaultStorageKey::ApprovalManager => b"~am".to_vec(),
-            DefaultStorageKey::Nep141 => b"~$141".to_vec(),
-            DefaultStorageKey::Owner => b"~o".to_vec(),
-            DefaultStorageKey::Pause => b"~p".to_vec(),
-            DefaultStorageKey::Rbac => b"~r".to_vec(),
+            DefaultStorageKey::AccessList => b"~al",
+            DefaultStorageKey::AccountRegistry => b"~ar",
+            DefaultStorageKey::ApprovalManager => b"~am",
+            DefaultStorageKey::Drop => b"~dr",
+            DefaultStorageKey::Escrow => b"~es",
+            DefaultStorageKey::Nep141 => b"~$141",
+            DefaultStorageKey::Nep148 => b"~$148",
+            DefaultStorageKey::Nep245 => b"~$245",
+            DefaultStorageKey::Nep413 => b"~$413",
+            DefaultStorageKey::MigratedVersion => b"~mv",
+            DefaultStorageKey::Nonce => b"~n",
+            DefaultStorageKey::Owner => b"~o",
+            DefaultStorageKey::Pause => b"~p",
+            DefaultStorageKey::ProgressiveMigration => b"~pm",
+            DefaultStorageKey::RateLimit => b"~lm",
+            DefaultStorageKey::Rbac => b"~r",
+            DefaultStorageKey::Reentrancy => b"~gd",
+            DefaultStorageKey::Splitter => b"~sp",
+            DefaultStorageKey::StagedUpgrade => b"~su",
+            DefaultStorageKey::StateVersion => b"~sv",
+            DefaultStorageKey::Timelock => b"~tl",
+            DefaultStorageKey::UpgradeRollback => b"~ur",
+            DefaultStorageKey::Vesting => b"~vs",
+            DefaultStorageKey::XCall => b"~xc",
         }
     }
 }
 
+impl IntoStorageKey for DefaultStorageKey {
+    fn into_storage_key(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+// Failure messages are selected with these macros, so that the `min-size`
+// feature can replace them with short codes (listed in the README). Codes
+// replacing formatted messages are not formatted.
+
+/// A failure message, or its code with the `min-size` feature enabled
+#[cfg(not(feature = "min-size"))]
+macro_rules! message {
+    ($code:literal, $message:literal $(,)?) => {
+        $message
+    };
+}
+
+#[cfg(feature = "min-size")]
+macro_rules! message {
+    ($code:literal, $message:literal $(,)?) => {
+        $code
+    };
+}
+
+/// A formatted failure message, or its code with the `min-size` feature
+/// enabled. Either way, a `&str`.
+#[cfg(not(feature = "min-size"))]
+macro_rules! format_message {
+    ($code:literal, $($arg:tt)*) => {
+        &format!($($arg)*)
+    };
+}
+
+#[cfg(feature = "min-size")]
+macro_rules! format_message {
+    ($code:literal, $($arg:tt)*) => {{
+        // Keeps the arguments used, without evaluating them
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+        $code
+    }};
+}
+
 pub mod standard;
 
+pub mod accesslist;
+pub mod account_registry;
 pub mod approval;
+pub mod drop;
+pub mod escrow;
 pub mod migrate;
+pub mod nonce;
 pub mod owner;
 pub mod pause;
+pub mod rate_limit;
 pub mod rbac;
+pub mod reentrancy;
+#[cfg(feature = "rename")]
+pub mod rename;
 pub mod slot;
+pub mod splitter;
+pub mod storage_accounting;
+#[cfg(feature = "unit-testing")]
+pub mod testing;
+pub mod timelock;
 pub mod upgrade;
 pub mod utils;
+pub mod vesting;
+pub mod xcall;
 
 use near_sdk::IntoStorageKey;
 pub use near_sdk_contract_tools_macros::*;
@@ -3,16 +3,154 @@
 //! The migration controller takes the old and new schema and deserializes
 //! the contract state from the old schema. The [`on_migrate`][`MigrateHook::on_migrate`]
 //! method takes this state and replaces it with the new schema.
-//! [`MigrateExternal`] exposes this functionality publicly.
+//! [`MigrateExternal`] exposes this functionality publicly. The derived
+//! `migrate` function emits a [`MigrateEvent`] when the migration completes.
 //!
 //! The crate exports a [derive macro](near_sdk_contract_tools_macros::Migrate)
 //! that derives a default implementation for migration.
 //!
 //! Note: [`MigrateHook`] must be implemented by the user and is not derived
 //! by default. It must convert data in the old schema to the new schema without
-//! failing. For a complete example checkout [upgrade_new.rs](https://github.com/NEARFoundation/near-sdk-contract-tools/blob/develop/workspaces-tests/src/bin/upgrade_new.rs)
+//! failing. If the conversion may fail, implement [`TryMigrateHook`] instead.
+//! Contracts that have been upgraded several times may instead derive
+//! [`MigrateChain`](near_sdk_contract_tools_macros::MigrateChain), listing
+//! every previous schema version in order. The stored version is detected
+//! (see [`MigrateChain::read_versioned_state`]) and converted to the latest
+//! version by applying each `From` conversion in turn.
+//!
+//! Migrations that are too large to complete in a single transaction (e.g.
+//! re-keying a large collection) may implement [`ProgressiveMigration`],
+//! which processes the migration in steps, keeping a cursor in storage
+//! between them.
+//!
+//! For a complete example checkout [upgrade_new.rs](https://github.com/NEARFoundation/near-sdk-contract-tools/blob/develop/workspaces-tests/src/bin/upgrade_new.rs)
 //! in workspace-tests.
 //!
+//! # Access control
+//!
+//! The `allow` option of the derive macro restricts who may call `migrate`.
+//! The `"owner"` and `"role(...)"` shorthands require the [`Owner`](crate::owner::Owner)
+//! and [`Rbac`](crate::rbac::Rbac) components, respectively:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{migrate::MigrateHook, owner::Owner, Migrate, Owner};
+//!
+//! #[derive(BorshSerialize, BorshDeserialize)]
+//! struct Old {
+//!     foo: u64,
+//! }
+//!
+//! #[derive(Owner, Migrate, BorshSerialize, BorshDeserialize)]
+//! #[migrate(from = "Old", allow = "owner")]
+//! #[near_bindgen]
+//! struct Contract {
+//!     bar: u64,
+//! }
+//!
+//! impl MigrateHook for Contract {
+//!     fn on_migrate(old: Old) -> Self {
+//!         Self { bar: old.foo }
+//!     }
+//! }
+//! ```
+//!
+//! Without the component, the contract does not compile:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{migrate::MigrateHook, Migrate};
+//!
+//! #[derive(BorshSerialize, BorshDeserialize)]
+//! struct Old {
+//!     foo: u64,
+//! }
+//!
+//! #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+//! #[migrate(from = "Old", allow = "owner")]
+//! #[near_bindgen]
+//! struct Contract {
+//!     bar: u64,
+//! }
+//!
+//! impl MigrateHook for Contract {
+//!     fn on_migrate(old: Old) -> Self {
+//!         Self { bar: old.foo }
+//!     }
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen, BorshStorageKey,
+//! };
+//! use near_sdk_contract_tools::{migrate::MigrateHook, Migrate};
+//!
+//! #[derive(BorshSerialize, BorshStorageKey)]
+//! enum Role {
+//!     Admin,
+//! }
+//!
+//! #[derive(BorshSerialize, BorshDeserialize)]
+//! struct Old {
+//!     foo: u64,
+//! }
+//!
+//! #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+//! #[migrate(from = "Old", allow = "role(Role::Admin)")]
+//! #[near_bindgen]
+//! struct Contract {
+//!     bar: u64,
+//! }
+//!
+//! impl MigrateHook for Contract {
+//!     fn on_migrate(old: Old) -> Self {
+//!         Self { bar: old.foo }
+//!     }
+//! }
+//! ```
+//!
+//! # Dry runs
+//!
+//! The `dry_run` option of the derive macro exposes a `migrate_dry_run` view
+//! that performs the conversion without writing anything. The contract must
+//! opt in by implementing [`MigrateDryRun`], which asserts that the
+//! conversion has no side effects:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{migrate::MigrateHook, Migrate};
+//!
+//! #[derive(BorshSerialize, BorshDeserialize)]
+//! struct Old {
+//!     foo: u64,
+//! }
+//!
+//! #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+//! #[migrate(from = "Old", dry_run)]
+//! #[near_bindgen]
+//! struct Contract {
+//!     bar: u64,
+//! }
+//!
+//! // Missing `impl MigrateDryRun for Contract`
+//! impl MigrateHook for Contract {
+//!     fn on_migrate(old: Old) -> Self {
+//!         Self { bar: old.foo }
+//!     }
+//! }
+//! ```
+//!
 //! # Safety
 //! The contract state must conform to the old schema otherwise deserializing it
 //! will fail and throw an error.
@@ -20,12 +158,45 @@
 
 use near_sdk::{
     borsh::{BorshDeserialize, BorshSerialize},
-    env, ext_contract,
+    env, ext_contract, require,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    serde_json, AccountId,
 };
+use near_sdk_contract_tools_macros::event;
+use thiserror::Error;
+
+use crate::{slot::Slot, DefaultStorageKey};
 
-// TODO: Migration events?
-// *Possibly* unnecessary, since the salient occurence will probably be the instigating event (e.g. a code upgrade)
-// Alternative solution: post-migration hook/callback so that the author can implement their own events if desired
+const STATE_KEY: &[u8] = b"STATE";
+
+/// Event emitted when the contract state is migrated
+#[event(
+    standard = "x-migr",
+    version = "1.0.0",
+    name = "migrate",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct MigrateEvent {
+    /// Schema version the state was migrated from
+    pub from_version: String,
+    /// Schema version the state was migrated to
+    pub to_version: String,
+    /// Account that invoked the migration
+    pub by: AccountId,
+}
+
+impl MigrateEvent {
+    /// Creates an event for a migration invoked by the predecessor account
+    pub fn new(from_version: impl Into<String>, to_version: impl Into<String>) -> Self {
+        Self {
+            from_version: from_version.into(),
+            to_version: to_version.into(),
+            by: env::predecessor_account_id(),
+        }
+    }
+}
 
 /// Conversion between two storage schemas
 pub trait MigrateController {
@@ -34,12 +205,63 @@ pub trait MigrateController {
     /// Schema that will be used henceforth, to convert into
     type NewSchema: BorshSerialize;
 
-    /// Deserializes the old schema from storage.
+    /// Deserializes the old schema from storage. If the stored state does
+    /// not conform to the old schema, the raw bytes are passed to
+    /// [`MigrateController::on_deserialize_failure`].
     ///
     /// It is probably not necessary to override this function.
     fn deserialize_old_schema() -> Self::OldSchema {
-        env::state_read::<Self::OldSchema>()
-            .unwrap_or_else(|| env::panic_str("Failed to deserialize old state"))
+        let bytes = Self::read_old_state_bytes();
+
+        match Self::OldSchema::try_from_slice(&bytes) {
+            Ok(old_schema) => old_schema,
+            Err(_) => Self::on_deserialize_failure(bytes),
+        }
+    }
+
+    /// Reads the raw bytes of the old state from storage
+    fn read_old_state_bytes() -> Vec<u8> {
+        env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str(message!("MIG2", "Failed to read old state")))
+    }
+
+    /// Storage slot for the most recent migration, recorded as
+    /// `"{from_version}->{to_version}"`
+    fn slot_migrated_version() -> Slot<String> {
+        Slot::new(DefaultStorageKey::MigratedVersion)
+    }
+
+    /// Rejects if the most recent migration was from `from_version` to
+    /// `to_version`. Keying on both versions allows a contract to keep its
+    /// type name (the default `to_version`) across several migrations.
+    fn require_not_migrated(from_version: &str, to_version: &str) {
+        if Self::slot_migrated_version().read().as_deref()
+            == Some(&*format!("{from_version}->{to_version}"))
+        {
+            env::panic_str(format_message!(
+                "MIG4",
+                "Already migrated from {from_version} to {to_version}",
+            ));
+        }
+    }
+
+    /// Records a migration from `from_version` to `to_version`
+    fn record_migration(from_version: &str, to_version: &str) {
+        Self::slot_migrated_version().write(&format!("{from_version}->{to_version}"));
+    }
+
+    /// Called with the raw state bytes when they cannot be deserialized as
+    /// the old schema. Override this function to attempt alternative
+    /// decodings (e.g. older versions, JSON), or to store the bytes aside and
+    /// produce a placeholder state.
+    ///
+    /// Panics by default.
+    fn on_deserialize_failure(_bytes: Vec<u8>) -> Self::OldSchema {
+        env::panic_str(format_message!(
+            "MIG5",
+            "Failed to deserialize old state as `{}`",
+            std::any::type_name::<Self::OldSchema>(),
+        ))
     }
 }
 
@@ -53,6 +275,266 @@ pub trait MigrateHook: MigrateController {
     ) -> <Self as MigrateController>::NewSchema;
 }
 
+/// Error returned by a fallible migration hook. When the generated `migrate`
+/// function receives this error, it panics with the error message, so no new
+/// state is written.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("Migration failed: {message}")]
+pub struct MigrateError {
+    /// Why the migration failed
+    pub message: String,
+}
+
+impl MigrateError {
+    /// Creates an error with the given message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl AsRef<str> for MigrateError {
+    fn as_ref(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Error deserializing old state that was stored as JSON
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum JsonStateError {
+    /// The stored state is not valid JSON
+    #[error("Old state is not valid JSON: {0}")]
+    NotJson(String),
+    /// The stored state is valid JSON, but does not match the old schema
+    #[error("Old state does not match the schema of `{expected}`: {reason}")]
+    WrongShape {
+        /// Name of the old schema type
+        expected: String,
+        /// Why the state does not match the schema
+        reason: String,
+    },
+}
+
+/// Deserializes old state that was stored as JSON rather than borsh. Used
+/// by the derive macro when `from_format = "json"` is specified.
+pub fn deserialize_json_state<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, JsonStateError> {
+    let value: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(|e| JsonStateError::NotJson(e.to_string()))?;
+
+    serde_json::from_value(value).map_err(|e| JsonStateError::WrongShape {
+        expected: std::any::type_name::<T>().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Fallible variant of [`MigrateHook`], for migrations that may encounter
+/// recoverable problems (e.g. the old state violates an invariant that
+/// requires operator intervention). Implemented for every [`MigrateHook`].
+/// Implement this trait instead of [`MigrateHook`] if the migration may fail.
+pub trait TryMigrateHook: MigrateController {
+    /// Receives the old schema deserialized from storage, and tries to
+    /// convert it into the new schema.
+    fn try_on_migrate(
+        old_schema: <Self as MigrateController>::OldSchema,
+    ) -> Result<<Self as MigrateController>::NewSchema, MigrateError>;
+}
+
+impl<T: MigrateHook> TryMigrateHook for T {
+    fn try_on_migrate(
+        old_schema: <Self as MigrateController>::OldSchema,
+    ) -> Result<<Self as MigrateController>::NewSchema, MigrateError> {
+        Ok(<T as MigrateHook>::on_migrate(old_schema))
+    }
+}
+
+/// Opts a migration in to dry runs, which perform the conversion in a view
+/// call and return a summary of the result without writing anything.
+///
+/// Implementing this trait asserts that the conversion is free of side
+/// effects: it must not write to storage, emit events, or schedule promises.
+/// (Storage writes would cause the view call to fail.)
+pub trait MigrateDryRun: MigrateController {
+    /// Serializable summary of the new state
+    type Summary: Serialize;
+
+    /// Summarizes the result of the conversion. Return the new schema itself
+    /// to view the entire state, or select only the relevant fields.
+    fn summarize(new_schema: <Self as MigrateController>::NewSchema) -> Self::Summary;
+}
+
+/// All of the schema versions a contract's state has used, in order from
+/// oldest to latest. Usually implemented by the enum that the
+/// [`MigrateChain`](near_sdk_contract_tools_macros::MigrateChain) derive
+/// macro generates.
+pub trait VersionedState: Sized {
+    /// Latest schema version
+    type Latest;
+
+    /// Total number of schema versions, including the latest
+    const VERSIONS: u32;
+
+    /// Index of the contained schema version (0 is the oldest)
+    fn version(&self) -> u32;
+
+    /// Deserializes `bytes` as the schema version with the given index.
+    /// Returns `None` if the index is out of range or the bytes do not
+    /// conform to that schema.
+    fn deserialize_version(version: u32, bytes: &[u8]) -> Option<Self>;
+
+    /// Converts the contained state to the latest schema version, applying
+    /// the conversions between consecutive versions in order
+    fn into_latest(self) -> Self::Latest;
+
+    /// Tries to deserialize `bytes` as each schema version, starting with the
+    /// latest. Schemas that share a serialized layout cannot be told apart,
+    /// so the version tag should be preferred when it is available.
+    fn detect(bytes: &[u8]) -> Option<Self> {
+        (0..Self::VERSIONS)
+            .rev()
+            .find_map(|version| Self::deserialize_version(version, bytes))
+    }
+}
+
+/// Migration through a chain of schema versions. The index of the stored
+/// schema version is recorded in a storage slot after each migration.
+pub trait MigrateChain: Sized {
+    /// All of the contract's schema versions, with `Self` as the latest
+    type Versions: VersionedState<Latest = Self>;
+
+    /// Storage slot for the index of the currently stored schema version
+    fn slot_state_version() -> Slot<u32> {
+        Slot::new(DefaultStorageKey::StateVersion)
+    }
+
+    /// Index of the currently stored schema version, if it has been recorded
+    fn stored_state_version() -> Option<u32> {
+        Self::slot_state_version().read()
+    }
+
+    /// Records the latest schema version as the stored version. Call this
+    /// from the contract's initializer so that future migrations do not need
+    /// to fall back to detection.
+    fn write_state_version() {
+        Self::slot_state_version().write(&(<Self::Versions as VersionedState>::VERSIONS - 1));
+    }
+
+    /// Reads the contract state from storage. Uses the recorded version tag
+    /// if present, otherwise falls back to [`VersionedState::detect`].
+    fn read_versioned_state() -> Self::Versions {
+        let bytes = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str(message!("MIG2", "Failed to read old state")));
+
+        match Self::stored_state_version() {
+            Some(version) => {
+                Self::Versions::deserialize_version(version, &bytes).unwrap_or_else(|| {
+                    env::panic_str(format_message!(
+                        "MIG6",
+                        "Failed to deserialize old state as version {version}"
+                    ))
+                })
+            }
+            None => Self::Versions::detect(&bytes).unwrap_or_else(|| {
+                env::panic_str(message!("MIG3", "Failed to detect old state version"))
+            }),
+        }
+    }
+
+    /// Converts the stored state to the latest schema version and records
+    /// the new version tag.
+    fn migrate_chain() -> Self {
+        let state = Self::read_versioned_state().into_latest();
+        Self::write_state_version();
+        state
+    }
+}
+
+const MIGRATION_IN_PROGRESS_MESSAGE: &str = message!("MIG1", "Migration in progress");
+
+/// Result of a single step of a [`ProgressiveMigration`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrationProgress {
+    /// Whether the migration has completed
+    pub done: bool,
+    /// Number of items processed by this step
+    pub items_processed: u32,
+}
+
+/// Migration that is performed over multiple transactions. The position of
+/// the migration is kept in a cursor slot between steps.
+///
+/// The migration is started by calling
+/// [`ProgressiveMigration::start_progressive_migration`], usually from the
+/// [`MigrateHook`]. Until the migration completes, contract methods that
+/// depend on the migrated data should call
+/// [`ProgressiveMigration::require_migration_complete`].
+pub trait ProgressiveMigration {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::ProgressiveMigration)
+    }
+
+    /// Storage slot for the migration cursor
+    fn slot_migration_cursor() -> Slot<u64> {
+        Self::root().field(b"c")
+    }
+
+    /// Storage slot for the flag indicating that a migration is in progress
+    fn slot_migration_in_progress() -> Slot<bool> {
+        Self::root().field(b"p")
+    }
+
+    /// Position of the next item to migrate
+    fn migration_cursor() -> u64 {
+        Self::slot_migration_cursor().read().unwrap_or(0)
+    }
+
+    /// Returns `true` if no migration is in progress
+    fn is_migration_complete() -> bool {
+        !Self::slot_migration_in_progress().read().unwrap_or(false)
+    }
+
+    /// Rejects if a migration is in progress
+    fn require_migration_complete() {
+        require!(Self::is_migration_complete(), MIGRATION_IN_PROGRESS_MESSAGE);
+    }
+
+    /// Marks a migration as in progress and resets the cursor
+    fn start_progressive_migration() {
+        Self::slot_migration_cursor().write(&0);
+        Self::slot_migration_in_progress().write(&true);
+    }
+
+    /// Migrates up to `limit` items, starting at `cursor`. Must be
+    /// implemented by the user.
+    fn migrate_items(&mut self, cursor: u64, limit: u32) -> MigrationProgress;
+
+    /// Performs the next step of the migration, advancing the cursor. Does
+    /// nothing if no migration is in progress.
+    fn migrate_step(&mut self, limit: u32) -> MigrationProgress {
+        if Self::is_migration_complete() {
+            return MigrationProgress {
+                done: true,
+                items_processed: 0,
+            };
+        }
+
+        let cursor = Self::migration_cursor();
+        let progress = self.migrate_items(cursor, limit);
+
+        if progress.done {
+            Self::slot_migration_cursor().remove();
+            Self::slot_migration_in_progress().remove();
+        } else {
+            Self::slot_migration_cursor().write(&(cursor + progress.items_processed as u64));
+        }
+
+        progress
+    }
+}
+
 /// Migrate-able contracts expose this trait publicly
 #[ext_contract(ext_migrate)]
 pub trait MigrateExternal {
@@ -0,0 +1,329 @@
+//! Per-account nonces, for replay protection of signed messages and
+//! meta-transactions.
+//!
+//! Each account has its own sequence of `u64` nonces, and each nonce may be
+//! used once. The [`NonceStrategy`] decides what happens to skipped nonces:
+//!
+//! * [`NonceStrategy::Sequential`]: nonces must strictly increase. Using a
+//!   nonce invalidates all lower nonces, including the skipped ones. The
+//!   state of an account is a single counter.
+//! * [`NonceStrategy::Window`]: nonces may be used out of order, as long as
+//!   they are within [`NONCE_WINDOW`] of the highest nonce used so far. A
+//!   bitmap keeps track of the nonces used in the window.
+//!
+//! Either way, the state of an account is one storage slot, so using a nonce
+//! costs one read and one write.
+//!
+//! The nonce [`u64::MAX`] is never accepted, so that [`Nonce::next_nonce`]
+//! can be represented: an account that uses the nonce `u64::MAX - 1` has no
+//! nonces left.
+//!
+//! ```
+//! use near_sdk::{env, near_bindgen};
+//! use near_sdk_contract_tools::nonce::{Nonce, NonceStrategy};
+//!
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl Nonce for Contract {
+//!     fn nonce_strategy() -> NonceStrategy {
+//!         NonceStrategy::Window
+//!     }
+//! }
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     pub fn relay(&mut self, nonce: u64) {
+//!         self.require_nonce(&env::predecessor_account_id(), nonce);
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The nonce root storage slot is not used or modified. The default key is `~n`.
+//! * (UB) The strategy does not change from [`NonceStrategy::Window`] to
+//!   [`NonceStrategy::Sequential`] while nonces are in use.
+//! * (ERR) A nonce is used at most once per account.
+//! * (ERR) A nonce is not used after a higher nonce, unless it is within the
+//!   window of [`NonceStrategy::Window`].
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, AccountId, BorshStorageKey,
+};
+use thiserror::Error;
+
+use crate::{slot::Slot, DefaultStorageKey};
+
+const USED_FAIL_MESSAGE: &str = message!("NON1", "Nonce already used");
+const TOO_LOW_FAIL_MESSAGE: &str = message!("NON2", "Nonce is too low");
+const OVERFLOW_FAIL_MESSAGE: &str = message!("NON3", "Nonce overflow");
+
+/// Number of nonces below the highest used nonce that may still be used with
+/// [`NonceStrategy::Window`], including the highest used nonce itself
+pub const NONCE_WINDOW: u64 = u64::BITS as u64;
+
+/// What happens to skipped nonces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonceStrategy {
+    /// Nonces must strictly increase. Skipped nonces can no longer be used.
+    Sequential,
+    /// Nonces may be used out of order within [`NONCE_WINDOW`] of the highest
+    /// used nonce
+    Window,
+}
+
+/// Nonces used by an account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NonceState {
+    /// One more than the highest used nonce
+    pub next: u64,
+    /// Bit `i` is set if the nonce `next - 1 - i` was used. Only maintained
+    /// with [`NonceStrategy::Window`].
+    pub window: u64,
+}
+
+impl NonceState {
+    /// Records `nonce` as used, according to `strategy`
+    pub fn use_nonce(&mut self, nonce: u64, strategy: NonceStrategy) -> Result<(), NonceError> {
+        if nonce == u64::MAX {
+            return Err(NonceError::Overflow);
+        }
+
+        if nonce >= self.next {
+            // Slide the window up, so that bit 0 is `nonce`
+            let shift = nonce - self.next + 1;
+            self.window = match strategy {
+                NonceStrategy::Window if shift < NONCE_WINDOW => (self.window << shift) | 1,
+                NonceStrategy::Window => 1,
+                NonceStrategy::Sequential => 0,
+            };
+            self.next = nonce + 1;
+            return Ok(());
+        }
+
+        let offset = self.next - 1 - nonce;
+        match strategy {
+            NonceStrategy::Window if offset < NONCE_WINDOW => {
+                let bit = 1 << offset;
+                if self.window & bit != 0 {
+                    return Err(NonceError::Used(nonce));
+                }
+                self.window |= bit;
+                Ok(())
+            }
+            _ => Err(NonceError::TooLow(nonce)),
+        }
+    }
+}
+
+/// Reasons a nonce is rejected
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum NonceError {
+    /// The nonce was already used
+    #[error("{}", USED_FAIL_MESSAGE)]
+    Used(u64),
+    /// The nonce is lower than the nonces that may still be used. It may or
+    /// may not have been used.
+    #[error("{}", TOO_LOW_FAIL_MESSAGE)]
+    TooLow(u64),
+    /// The nonce is [`u64::MAX`], which is never accepted
+    #[error("{}", OVERFLOW_FAIL_MESSAGE)]
+    Overflow,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Account(&'a AccountId),
+}
+
+/// Per-account nonces
+pub trait Nonce {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Nonce)
+    }
+
+    /// Storage slot of the nonces used by an account
+    fn slot_nonce_state(account_id: &AccountId) -> Slot<NonceState> {
+        <Self as Nonce>::root().field(StorageKey::Account(account_id))
+    }
+
+    /// What happens to skipped nonces. Sequential by default.
+    fn nonce_strategy() -> NonceStrategy {
+        NonceStrategy::Sequential
+    }
+
+    /// The lowest nonce that `account_id` may use and that is higher than
+    /// all of its used nonces. 0 for accounts that have not used any nonces.
+    fn next_nonce(account_id: &AccountId) -> u64 {
+        Self::slot_nonce_state(account_id)
+            .read()
+            .map_or(0, |state| state.next)
+    }
+
+    /// Records `nonce` as used by `account_id`, if it may be used
+    fn use_nonce(&mut self, account_id: &AccountId, nonce: u64) -> Result<(), NonceError> {
+        let mut slot = Self::slot_nonce_state(account_id);
+        let mut state = slot.read().unwrap_or_default();
+        state.use_nonce(nonce, Self::nonce_strategy())?;
+        slot.write(&state);
+        Ok(())
+    }
+
+    /// Like [`Nonce::use_nonce`], but panics if the nonce is rejected
+    fn require_nonce(&mut self, account_id: &AccountId, nonce: u64) {
+        self.use_nonce(account_id, nonce)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::AccountId;
+
+    use super::{Nonce, NonceError, NonceState, NonceStrategy, NONCE_WINDOW};
+    use crate::testing::TestEnv;
+
+    struct Sequential;
+
+    impl Nonce for Sequential {}
+
+    struct Window;
+
+    impl Nonce for Window {
+        fn nonce_strategy() -> NonceStrategy {
+            NonceStrategy::Window
+        }
+    }
+
+    fn alice() -> AccountId {
+        "alice".parse().unwrap()
+    }
+
+    #[test]
+    fn sequential_reuse() {
+        let _env = TestEnv::new();
+
+        assert_eq!(Sequential::next_nonce(&alice()), 0);
+        assert_eq!(Sequential.use_nonce(&alice(), 0), Ok(()));
+        assert_eq!(Sequential::next_nonce(&alice()), 1);
+        assert_eq!(
+            Sequential.use_nonce(&alice(), 0),
+            Err(NonceError::TooLow(0)),
+        );
+    }
+
+    #[test]
+    fn sequential_gap() {
+        let _env = TestEnv::new();
+
+        assert_eq!(Sequential.use_nonce(&alice(), 5), Ok(()));
+        assert_eq!(Sequential::next_nonce(&alice()), 6);
+        // Skipped nonces can no longer be used
+        assert_eq!(
+            Sequential.use_nonce(&alice(), 3),
+            Err(NonceError::TooLow(3)),
+        );
+        assert_eq!(Sequential.use_nonce(&alice(), 6), Ok(()));
+    }
+
+    #[test]
+    fn window_reuse() {
+        let _env = TestEnv::new();
+
+        assert_eq!(Window.use_nonce(&alice(), 0), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 0), Err(NonceError::Used(0)));
+        assert_eq!(Window.use_nonce(&alice(), 1), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 1), Err(NonceError::Used(1)));
+        assert_eq!(Window.use_nonce(&alice(), 0), Err(NonceError::Used(0)));
+    }
+
+    #[test]
+    fn window_gap() {
+        let _env = TestEnv::new();
+
+        assert_eq!(Window.use_nonce(&alice(), 10), Ok(()));
+        assert_eq!(Window::next_nonce(&alice()), 11);
+
+        // Skipped nonces can be used out of order, once each
+        assert_eq!(Window.use_nonce(&alice(), 3), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 7), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 3), Err(NonceError::Used(3)));
+        assert_eq!(Window::next_nonce(&alice()), 11);
+
+        // Until they fall out of the window
+        let top = 10 + NONCE_WINDOW;
+        assert_eq!(Window.use_nonce(&alice(), top), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 11), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), 10), Err(NonceError::TooLow(10)));
+        assert_eq!(Window.use_nonce(&alice(), 7), Err(NonceError::TooLow(7)));
+        assert_eq!(Window.use_nonce(&alice(), top), Err(NonceError::Used(top)));
+
+        // A jump past the window forgets it
+        let far = top + NONCE_WINDOW * 3;
+        assert_eq!(Window.use_nonce(&alice(), far), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), far - 1), Ok(()));
+        assert_eq!(
+            Window.use_nonce(&alice(), top),
+            Err(NonceError::TooLow(top)),
+        );
+    }
+
+    #[test]
+    fn distinct_accounts() {
+        let _env = TestEnv::new();
+        let bob: AccountId = "bob".parse().unwrap();
+
+        assert_eq!(Sequential.use_nonce(&alice(), 4), Ok(()));
+        assert_eq!(Sequential::next_nonce(&bob), 0);
+        assert_eq!(Sequential.use_nonce(&bob, 0), Ok(()));
+    }
+
+    #[test]
+    fn overflow() {
+        let _env = TestEnv::new();
+
+        for strategy in [NonceStrategy::Sequential, NonceStrategy::Window] {
+            let mut state = NonceState::default();
+
+            assert_eq!(
+                state.use_nonce(u64::MAX, strategy),
+                Err(NonceError::Overflow),
+            );
+            assert_eq!(state.use_nonce(u64::MAX - 1, strategy), Ok(()));
+            assert_eq!(state.next, u64::MAX);
+            assert_eq!(
+                state.use_nonce(u64::MAX, strategy),
+                Err(NonceError::Overflow),
+            );
+        }
+
+        assert_eq!(Window.use_nonce(&alice(), u64::MAX - 2), Ok(()));
+        assert_eq!(Window.use_nonce(&alice(), u64::MAX - 1), Ok(()));
+        assert_eq!(Window::next_nonce(&alice()), u64::MAX);
+        assert_eq!(
+            Window.use_nonce(&alice(), u64::MAX - 1),
+            Err(NonceError::Used(u64::MAX - 1)),
+        );
+        assert_eq!(
+            Window.use_nonce(&alice(), u64::MAX),
+            Err(NonceError::Overflow),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce is too low")]
+    fn require_nonce() {
+        let _env = TestEnv::new();
+
+        Sequential.require_nonce(&alice(), 1);
+        Sequential.require_nonce(&alice(), 1);
+    }
+}
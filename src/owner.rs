@@ -30,21 +30,41 @@
 //! * (ERR) Only the proposed owner can call [`Owner::accept_owner`].
 //! * (ERR) The external functions exposed in [`OwnerExternal`] call their
 //!   respective [`Owner`] methods and expect the same invariants.
+//!
+//! # Field-embedded state
+//! With `#[owner(field = "<field>")]`, the derive macro keeps the state in a
+//! field of type [`OwnerState`] of the contract struct instead of in storage
+//! slots, and the storage slot functions are unused. The state is then part
+//! of the root contract state: it is covered by the contract's Borsh schema
+//! and read and written with it, which saves a storage read per value for
+//! contracts that use it in most calls.
+//!
+//! The static functions ([`Owner::require_owner`], [`Owner::check_owner`])
+//! have no `self`, so they deserialize the whole root state to read the
+//! owner, which is costly for a large contract struct. They also do not see
+//! changes made through `self` earlier in the same call, since the root
+//! state is only written at the end of it. Prefer [`Owner::assert_owner`] in
+//! methods of the contract.
 #![allow(missing_docs)] // #[ext_contract(...)] does not play nicely with clippy
 
 use near_sdk::{
-    borsh::{self, BorshSerialize},
+    borsh::{self, BorshDeserialize, BorshSerialize},
     env, ext_contract, require, AccountId, BorshStorageKey,
 };
 use near_sdk_contract_tools_macros::event;
+use thiserror::Error;
 
 use crate::{slot::Slot, standard::nep297::Event, DefaultStorageKey};
 
-const ONLY_OWNER_FAIL_MESSAGE: &str = "Owner only";
-const OWNER_INIT_FAIL_MESSAGE: &str = "Owner already initialized";
-const NO_OWNER_FAIL_MESSAGE: &str = "No owner";
-const ONLY_PROPOSED_OWNER_FAIL_MESSAGE: &str = "Proposed owner only";
-const NO_PROPOSED_OWNER_FAIL_MESSAGE: &str = "No proposed owner";
+const ONLY_OWNER_FAIL_MESSAGE: &str = message!("OWN1", "Owner only");
+const OWNER_INIT_FAIL_MESSAGE: &str = message!("OWN2", "Owner already initialized");
+const NO_OWNER_FAIL_MESSAGE: &str = message!("OWN3", "No owner");
+const ONLY_PROPOSED_OWNER_FAIL_MESSAGE: &str = message!("OWN4", "Proposed owner only");
+const NO_PROPOSED_OWNER_FAIL_MESSAGE: &str = message!("OWN5", "No proposed owner");
+
+/// Length of the longest storage key suffix that [`Owner`] appends to its
+/// root
+pub const MAX_KEY_SUFFIX_LEN: usize = 1;
 
 /// Events emitted by function calls on an ownable contract
 #[event(
@@ -71,6 +91,29 @@ pub enum OwnerEvent {
     },
 }
 
+/// Reasons the predecessor is not the owner
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum OwnerError {
+    /// The contract has no owner
+    #[error("{}", NO_OWNER_FAIL_MESSAGE)]
+    NoOwner,
+    /// The predecessor is not the owner
+    #[error("{}", ONLY_OWNER_FAIL_MESSAGE)]
+    NotOwner,
+}
+
+/// State of [`Owner`] kept in a field of the contract, with
+/// `#[owner(field = "<field>")]`, instead of in storage slots
+#[derive(BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct OwnerState {
+    /// Whether [`Owner::init`] has been called
+    pub is_initialized: bool,
+    /// The current owner, if any
+    pub owner: Option<AccountId>,
+    /// The proposed owner, if any
+    pub proposed_owner: Option<AccountId>,
+}
+
 #[derive(BorshSerialize, BorshStorageKey, Debug, Clone)]
 enum StorageKey {
     IsInitialized,
@@ -82,7 +125,12 @@ enum StorageKey {
 pub trait Owner {
     /// Storage root
     fn root() -> Slot<()> {
-        Slot::new(DefaultStorageKey::Owner)
+        Slot::root(DefaultStorageKey::Owner)
+    }
+
+    /// Length of the longest storage key used by this component
+    fn max_key_len() -> usize {
+        Self::root().key.len() + MAX_KEY_SUFFIX_LEN
     }
 
     /// Storage slot for initialization state
@@ -100,10 +148,35 @@ pub trait Owner {
         Self::root().field(StorageKey::ProposedOwner)
     }
 
+    /// Reads the current owner from storage. Used where there is no `self`,
+    /// e.g. by [`Owner::check_owner`].
+    fn read_owner() -> Option<AccountId> {
+        Self::slot_owner().read()
+    }
+
+    /// The current owner
+    fn current_owner(&self) -> Option<AccountId> {
+        Self::read_owner()
+    }
+
+    /// The proposed owner
+    fn proposed_owner(&self) -> Option<AccountId> {
+        Self::slot_proposed_owner().read()
+    }
+
+    /// Whether [`Owner::init`] has been called
+    fn is_initialized(&self) -> bool {
+        Self::slot_is_initialized().exists()
+    }
+
+    /// Records that [`Owner::init`] has been called
+    fn set_initialized(&mut self) {
+        Self::slot_is_initialized().write(&true);
+    }
+
     /// Updates the current owner and emits relevant event
     fn update_owner(&mut self, new: Option<AccountId>) {
-        let owner = Self::slot_owner();
-        let old = owner.read();
+        let old = self.current_owner();
         if old != new {
             OwnerEvent::Transfer {
                 old,
@@ -116,8 +189,7 @@ pub trait Owner {
 
     /// Updates proposed owner and emits relevant event
     fn update_proposed(&mut self, new: Option<AccountId>) {
-        let proposed_owner = Self::slot_proposed_owner();
-        let old = proposed_owner.read();
+        let old = self.proposed_owner();
         if old != new {
             OwnerEvent::Propose {
                 old,
@@ -144,8 +216,8 @@ pub trait Owner {
     fn assert_owner(&self) {
         require!(
             &env::predecessor_account_id()
-                == Self::slot_owner()
-                    .read()
+                == self
+                    .current_owner()
                     .as_ref()
                     .unwrap_or_else(|| env::panic_str(NO_OWNER_FAIL_MESSAGE)),
             ONLY_OWNER_FAIL_MESSAGE,
@@ -178,13 +250,10 @@ pub trait Owner {
     /// }
     /// ```
     fn init(&mut self, owner_id: &AccountId) {
-        require!(
-            !Self::slot_is_initialized().exists(),
-            OWNER_INIT_FAIL_MESSAGE,
-        );
+        require!(!self.is_initialized(), OWNER_INIT_FAIL_MESSAGE);
 
-        Self::slot_is_initialized().write(&true);
-        Self::slot_owner().write(owner_id);
+        self.set_initialized();
+        self.update_owner_unchecked(Some(owner_id.clone()));
 
         OwnerEvent::Transfer {
             old: None,
@@ -215,14 +284,19 @@ pub trait Owner {
     /// }
     /// ```
     fn require_owner() {
-        require!(
-            &env::predecessor_account_id()
-                == Self::slot_owner()
-                    .read()
-                    .as_ref()
-                    .unwrap_or_else(|| env::panic_str(NO_OWNER_FAIL_MESSAGE)),
-            ONLY_OWNER_FAIL_MESSAGE,
-        );
+        Self::check_owner().unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Fallible version of [`Owner::require_owner`], useful for composing
+    /// with other checks
+    fn check_owner() -> Result<(), OwnerError> {
+        let owner = Self::read_owner().ok_or(OwnerError::NoOwner)?;
+
+        if env::predecessor_account_id() == owner {
+            Ok(())
+        } else {
+            Err(OwnerError::NotOwner)
+        }
     }
 
     /// Removes the contract's owner. Can only be called by the current owner.
@@ -230,7 +304,7 @@ pub trait Owner {
     /// Emits an `OwnerEvent::Transfer` event, and an `OwnerEvent::Propose`
     /// event if there is a currently proposed owner.
     fn renounce_owner(&mut self) {
-        Self::require_owner();
+        self.assert_owner();
 
         self.update_proposed(None);
         self.update_owner(None);
@@ -244,7 +318,7 @@ pub trait Owner {
     /// The currently proposed owner may be reset by calling this function with
     /// the argument `None`.
     fn propose_owner(&mut self, account_id: Option<AccountId>) {
-        Self::require_owner();
+        self.assert_owner();
 
         self.update_proposed(account_id);
     }
@@ -255,8 +329,8 @@ pub trait Owner {
     /// Emits events corresponding to the transfer of ownership and reset of the
     /// proposed owner.
     fn accept_owner(&mut self) {
-        let proposed_owner = Self::slot_proposed_owner()
-            .take()
+        let proposed_owner = self
+            .proposed_owner()
             .unwrap_or_else(|| env::panic_str(NO_PROPOSED_OWNER_FAIL_MESSAGE));
 
         require!(
@@ -264,6 +338,8 @@ pub trait Owner {
             ONLY_PROPOSED_OWNER_FAIL_MESSAGE,
         );
 
+        self.update_proposed_unchecked(None);
+
         OwnerEvent::Propose {
             old: Some(proposed_owner.clone()),
             new: None,
@@ -303,7 +379,7 @@ mod tests {
     use near_sdk::{near_bindgen, test_utils::VMContextBuilder, testing_env, AccountId};
 
     use crate::{
-        owner::{Owner, OwnerExternal},
+        owner::{Owner, OwnerError, OwnerExternal},
         Owner,
     };
 
@@ -357,6 +433,29 @@ mod tests {
         contract.owner_only();
     }
 
+    #[test]
+    fn check_owner() {
+        let owner_id: AccountId = "owner".parse().unwrap();
+
+        let mut contract = Contract::new(owner_id.clone());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(owner_id.clone())
+            .build());
+
+        assert_eq!(Contract::check_owner(), Ok(()));
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("alice".parse().unwrap())
+            .build());
+
+        assert_eq!(Contract::check_owner(), Err(OwnerError::NotOwner));
+
+        contract.update_owner_unchecked(None);
+
+        assert_eq!(Contract::check_owner(), Err(OwnerError::NoOwner));
+    }
+
     #[test]
     fn renounce_owner() {
         let owner_id: AccountId = "owner".parse().unwrap();
@@ -21,14 +21,35 @@
 //! * (ERR) Only a "paused" contract can call `unpause`.
 //! * (ERR) [`Pause::require_paused`] may only be called when the contract is paused.
 //! * (ERR) [`Pause::require_unpaused`] may only be called when the contract is unpaused.
+//!
+//! # Field-embedded state
+//! With `#[pause(field = "<field>")]`, the derive macro keeps the state in a
+//! field of type [`PauseState`] of the contract struct instead of in a storage
+//! slot, and the storage slot functions are unused. The state is then part of
+//! the root contract state, which saves a storage read in every call that
+//! checks it.
+//!
+//! The static functions ([`Pause::is_paused`], [`Pause::require_paused`],
+//! [`Pause::require_unpaused`]) have no `self`, so they deserialize the whole
+//! root state to read the flag, which is costly for a large contract struct.
+//! They also do not see changes made through `self` earlier in the same call,
+//! since the root state is only written at the end of it. Prefer
+//! [`Pause::paused`] in methods of the contract.
 #![allow(missing_docs)] // #[ext_contract(...)] does not play nicely with clippy
 
 use crate::{slot::Slot, standard::nep297::Event, DefaultStorageKey};
-use near_sdk::{ext_contract, require};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    ext_contract, require,
+};
 use near_sdk_contract_tools_macros::event;
 
-const UNPAUSED_FAIL_MESSAGE: &str = "Disallowed while contract is unpaused";
-const PAUSED_FAIL_MESSAGE: &str = "Disallowed while contract is paused";
+const UNPAUSED_FAIL_MESSAGE: &str = message!("PAU1", "Disallowed while contract is unpaused");
+const PAUSED_FAIL_MESSAGE: &str = message!("PAU2", "Disallowed while contract is paused");
+
+/// Length of the longest storage key suffix that [`Pause`] appends to its
+/// root. The pause state is stored in the root itself.
+pub const MAX_KEY_SUFFIX_LEN: usize = 0;
 
 /// Events emitted when contract pause state is changed
 #[event(
@@ -45,6 +66,14 @@ pub enum PauseEvent {
     Unpause,
 }
 
+/// State of [`Pause`] kept in a field of the contract, with
+/// `#[pause(field = "<field>")]`, instead of in a storage slot
+#[derive(BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct PauseState {
+    /// Whether the contract is paused
+    pub is_paused: bool,
+}
+
 /// Internal-only interactions for a pausable contract
 ///
 /// # Examples
@@ -84,6 +113,11 @@ pub trait Pause {
         Slot::new(DefaultStorageKey::Pause)
     }
 
+    /// Length of the longest storage key used by this component
+    fn max_key_len() -> usize {
+        Self::root().key.len() + MAX_KEY_SUFFIX_LEN
+    }
+
     /// Storage slot for pause state
     fn slot_paused() -> Slot<bool> {
         Self::root().transmute()
@@ -100,10 +134,15 @@ pub trait Pause {
         Self::slot_paused().read().unwrap_or(false)
     }
 
+    /// Like [`Pause::is_paused`], but reads the state through `self`
+    fn paused(&self) -> bool {
+        Self::is_paused()
+    }
+
     /// Pauses the contract if it is currently unpaused, panics otherwise.
     /// Emits a `PauseEvent::Pause` event.
     fn pause(&mut self) {
-        Self::require_unpaused();
+        require!(!self.paused(), PAUSED_FAIL_MESSAGE);
         self.set_is_paused(true);
         PauseEvent::Pause.emit();
     }
@@ -111,7 +150,7 @@ pub trait Pause {
     /// Unpauses the contract if it is currently paused, panics otherwise.
     /// Emits a `PauseEvent::Unpause` event.
     fn unpause(&mut self) {
-        Self::require_paused();
+        require!(self.paused(), UNPAUSED_FAIL_MESSAGE);
         self.set_is_paused(false);
         PauseEvent::Unpause.emit();
     }
@@ -0,0 +1,257 @@
+//! Per-account rate limits on contract methods.
+//!
+//! A [`RateLimitPolicy`] limits how often each account may perform an
+//! action, identified by a key (e.g. the name of the method). [`RateLimit`]
+//! records the block timestamps of the recent calls of each account, for
+//! each key, and rejects the calls that exceed the policy.
+//!
+//! The [`rate_limited`](near_sdk_contract_tools_macros::rate_limited)
+//! attribute injects the check for the predecessor into a method:
+//!
+//! ```
+//! use near_sdk::near_bindgen;
+//! use near_sdk_contract_tools::{
+//!     rate_limit::{RateLimit, RateLimitPolicy},
+//!     rate_limited,
+//! };
+//!
+//! const ONCE_A_DAY: RateLimitPolicy = RateLimitPolicy::cooldown(86_400_000_000_000);
+//!
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl RateLimit for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     #[rate_limited(policy = "ONCE_A_DAY")]
+//!     pub fn faucet(&mut self) {
+//!         // ...
+//!     }
+//!
+//!     #[rate_limited(
+//!         policy = "RateLimitPolicy::sliding_window(3_600_000_000_000, 5)",
+//!         key = "vote",
+//!     )]
+//!     pub fn vote(&mut self, option: u32) {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The rate limit root storage slot is not used or modified. The default key is `~lm`.
+//! * (UB) The policy of a key does not change while calls are recorded for it.
+//! * (ERR) An account does not make more calls with the same key than the
+//!   policy allows.
+
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    env, AccountId, BorshStorageKey,
+};
+use thiserror::Error;
+
+use crate::{slot::Slot, DefaultStorageKey};
+
+const RATE_LIMITED_FAIL_MESSAGE: &str = message!("RTL1", "Rate limit exceeded");
+
+/// How often an account may perform an action. Durations are in nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    /// At most one call per `interval_ns`
+    Cooldown {
+        /// Minimum time between two calls
+        interval_ns: u64,
+    },
+    /// At most `max_calls` calls in any period of `window_ns`
+    SlidingWindow {
+        /// Length of the window
+        window_ns: u64,
+        /// Maximum number of calls in the window
+        max_calls: u32,
+    },
+}
+
+impl RateLimitPolicy {
+    /// At most one call per `interval_ns`
+    pub const fn cooldown(interval_ns: u64) -> Self {
+        Self::Cooldown { interval_ns }
+    }
+
+    /// At most `max_calls` calls in any period of `window_ns`
+    pub const fn sliding_window(window_ns: u64, max_calls: u32) -> Self {
+        Self::SlidingWindow {
+            window_ns,
+            max_calls,
+        }
+    }
+
+    /// Length of the window, and maximum number of calls in it. A cooldown
+    /// is a window of one call.
+    pub const fn window(&self) -> (u64, u32) {
+        match *self {
+            Self::Cooldown { interval_ns } => (interval_ns, 1),
+            Self::SlidingWindow {
+                window_ns,
+                max_calls,
+            } => (window_ns, max_calls),
+        }
+    }
+}
+
+/// A call was rejected by a [`RateLimitPolicy`]
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("{}", RATE_LIMITED_FAIL_MESSAGE)]
+pub struct RateLimited {
+    /// Earliest block timestamp at which the call would be allowed
+    pub retry_at: u64,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Calls(&'a str, &'a AccountId),
+}
+
+/// Per-account rate limits
+pub trait RateLimit {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::RateLimit)
+    }
+
+    /// Storage slot for the block timestamps of the recent calls of an
+    /// account with a key, oldest first
+    fn slot_calls(key: &str, account_id: &AccountId) -> Slot<Vec<u64>> {
+        <Self as RateLimit>::root().field(StorageKey::Calls(key, account_id))
+    }
+
+    /// Records a call by `account_id` with `key` at the current block
+    /// timestamp, if `policy` allows it
+    fn check_and_update(
+        key: &str,
+        account_id: &AccountId,
+        policy: &RateLimitPolicy,
+    ) -> Result<(), RateLimited> {
+        let (window_ns, max_calls) = policy.window();
+        let now = env::block_timestamp();
+
+        let mut slot = Self::slot_calls(key, account_id);
+        let mut calls = slot.read().unwrap_or_default();
+        // Calls older than the window no longer count
+        calls.retain(|&call| now.saturating_sub(call) < window_ns);
+
+        if calls.len() >= max_calls as usize {
+            // Allowed once all but `max_calls - 1` of the calls leave the
+            // window. Never, if no calls are allowed.
+            let retry_at = calls
+                .get(calls.len() - max_calls as usize)
+                .map_or(u64::MAX, |call| call.saturating_add(window_ns));
+            return Err(RateLimited { retry_at });
+        }
+
+        calls.push(now);
+        slot.write(&calls);
+
+        Ok(())
+    }
+
+    /// Records a call by the predecessor with `key`. Panics if `policy` does
+    /// not allow it. Called by methods with the
+    /// [`rate_limited`](near_sdk_contract_tools_macros::rate_limited)
+    /// attribute.
+    fn require_rate_limit(key: &str, policy: &RateLimitPolicy) {
+        Self::check_and_update(key, &env::predecessor_account_id(), policy)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{RateLimit, RateLimitPolicy, RateLimited};
+    use crate::testing::{account, TestEnv};
+
+    const COOLDOWN: RateLimitPolicy = RateLimitPolicy::cooldown(100);
+    const WINDOW: RateLimitPolicy = RateLimitPolicy::sliding_window(100, 3);
+
+    struct Contract;
+
+    impl RateLimit for Contract {}
+
+    fn call(policy: &RateLimitPolicy, name: &str) -> Result<(), RateLimited> {
+        Contract::check_and_update("key", &account(name), policy)
+    }
+
+    #[test]
+    fn cooldown() {
+        let mut env = TestEnv::new();
+
+        env.set_block_timestamp(1_000);
+        assert_eq!(call(&COOLDOWN, "alice"), Ok(()));
+        assert_eq!(
+            call(&COOLDOWN, "alice"),
+            Err(RateLimited { retry_at: 1_100 }),
+        );
+
+        env.set_block_timestamp(1_099);
+        assert!(call(&COOLDOWN, "alice").is_err());
+
+        env.set_block_timestamp(1_100);
+        assert_eq!(call(&COOLDOWN, "alice"), Ok(()));
+        assert!(call(&COOLDOWN, "alice").is_err());
+    }
+
+    #[test]
+    fn sliding_window() {
+        let mut env = TestEnv::new();
+
+        env.set_block_timestamp(1_000);
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        env.set_block_timestamp(1_040);
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        assert_eq!(call(&WINDOW, "alice"), Err(RateLimited { retry_at: 1_100 }));
+
+        // The first call leaves the window
+        env.set_block_timestamp(1_100);
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        assert_eq!(call(&WINDOW, "alice"), Err(RateLimited { retry_at: 1_140 }));
+
+        // The calls at 1_040 leave the window
+        env.set_block_timestamp(1_140);
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        assert_eq!(call(&WINDOW, "alice"), Ok(()));
+        assert!(call(&WINDOW, "alice").is_err());
+    }
+
+    #[test]
+    fn distinct_accounts() {
+        let mut env = TestEnv::new();
+
+        env.set_block_timestamp(1_000);
+        assert_eq!(call(&COOLDOWN, "alice"), Ok(()));
+        assert_eq!(call(&COOLDOWN, "bob"), Ok(()));
+        assert!(call(&COOLDOWN, "alice").is_err());
+        assert!(call(&COOLDOWN, "bob").is_err());
+    }
+
+    #[test]
+    fn distinct_keys() {
+        let mut env = TestEnv::new();
+
+        env.set_block_timestamp(1_000);
+        assert_eq!(
+            Contract::check_and_update("a", &account("alice"), &COOLDOWN),
+            Ok(()),
+        );
+        assert_eq!(
+            Contract::check_and_update("b", &account("alice"), &COOLDOWN),
+            Ok(()),
+        );
+    }
+}
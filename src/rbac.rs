@@ -2,7 +2,10 @@
 //! accounts and control their access.
 //!
 //! RBAC expects the user to provide a type for [`Rbac::Role`]. Typically,
-//! this is an enum and its variants are the distinct roles. An account can be
+//! this is an enum and its variants are the distinct roles. A plain enum can
+//! derive [`Role`](near_sdk_contract_tools_macros::Role), which identifies
+//! each role in storage by the index of its variant; any type that implements
+//! `BorshSerialize` may be used as well (see: [`RbacRole`]). An account can be
 //! associated with multiple roles. [`Rbac`] implements methods to add, remove,
 //! and check an account for a role. It also provides "guard" methods to require
 //! or prohibit a particular role. Typically, these are used to guard access to
@@ -29,35 +32,67 @@ use std::iter::FusedIterator;
 
 use near_sdk::{
     borsh::{self, BorshSerialize},
-    env, require,
+    env,
     store::UnorderedSet,
-    AccountId, BorshStorageKey, IntoStorageKey,
+    AccountId, BorshStorageKey,
 };
 
+use thiserror::Error;
+
 use crate::{slot::Slot, DefaultStorageKey};
 
-const REQUIRE_ROLE_FAIL_MESSAGE: &str = "Unauthorized role";
-const PROHIBIT_ROLE_FAIL_MESSAGE: &str = "Prohibited role";
+const REQUIRE_ROLE_FAIL_MESSAGE: &str = message!("RBAC1", "Unauthorized role");
+const PROHIBIT_ROLE_FAIL_MESSAGE: &str = message!("RBAC2", "Prohibited role");
+
+/// Reasons the predecessor fails a role check
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum RbacError {
+    /// The predecessor does not have a required role
+    #[error("{}", REQUIRE_ROLE_FAIL_MESSAGE)]
+    Unauthorized,
+    /// The predecessor has a prohibited role
+    #[error("{}", PROHIBIT_ROLE_FAIL_MESSAGE)]
+    Prohibited,
+}
 
 #[derive(BorshSerialize, BorshStorageKey)]
-enum StorageKey<R> {
-    Role(R),
+enum StorageKey {
+    Role,
+}
+
+/// A role of [`Rbac`], identified in storage by [`RbacRole::role_key`].
+///
+/// Implemented for every type that implements `BorshSerialize`, as its Borsh
+/// serialization. [`Role`](near_sdk_contract_tools_macros::Role) implements
+/// it for a plain enum (which should not also implement `BorshSerialize`) as
+/// the index of the variant, which is the Borsh serialization of the enum if
+/// it derived `BorshSerialize` instead, so switching between the two keeps
+/// the stored roles.
+pub trait RbacRole {
+    /// Bytes identifying the role in storage keys
+    fn role_key(&self) -> Vec<u8>;
+}
+
+impl<T: BorshSerialize> RbacRole for T {
+    fn role_key(&self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
 }
 
 /// Role-based access control
 pub trait Rbac {
     /// Roles type (probably an enum).
-    type Role: BorshSerialize + IntoStorageKey;
+    type Role: RbacRole;
 
     /// Storage slot namespace for items.
     fn root() -> Slot<()> {
-        Slot::new(DefaultStorageKey::Rbac)
+        Slot::root(DefaultStorageKey::Rbac)
     }
 
     /// Storage slot for the backing `UnorderedSet` of all accounts assigned
     /// to a role.
     fn slot_members_of(role: &Self::Role) -> Slot<UnorderedSet<AccountId>> {
-        Self::root().field::<UnorderedSet<AccountId>>(StorageKey::Role(role))
+        Self::root().ns(StorageKey::Role).field(role.role_key())
     }
 
     /// Deserializes the backing `UnorderedSet` structure, executes predicate
@@ -114,20 +149,31 @@ pub trait Rbac {
 
     /// Requires transaction predecessor to have a given role.
     fn require_role(role: &Self::Role) {
-        let predecessor = env::predecessor_account_id();
-        require!(
-            Self::has_role(&predecessor, role),
-            REQUIRE_ROLE_FAIL_MESSAGE,
-        );
+        Self::check_role(role).unwrap_or_else(|e| env::panic_str(&e.to_string()));
     }
 
     /// Requires transaction predecessor to not have a given role.
     fn prohibit_role(role: &Self::Role) {
-        let predecessor = env::predecessor_account_id();
-        require!(
-            !Self::has_role(&predecessor, role),
-            PROHIBIT_ROLE_FAIL_MESSAGE,
-        );
+        Self::check_prohibited_role(role).unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Fallible version of [`Rbac::require_role`], useful for composing with
+    /// other checks
+    fn check_role(role: &Self::Role) -> Result<(), RbacError> {
+        if Self::has_role(&env::predecessor_account_id(), role) {
+            Ok(())
+        } else {
+            Err(RbacError::Unauthorized)
+        }
+    }
+
+    /// Fallible version of [`Rbac::prohibit_role`]
+    fn check_prohibited_role(role: &Self::Role) -> Result<(), RbacError> {
+        if Self::has_role(&env::predecessor_account_id(), role) {
+            Err(RbacError::Prohibited)
+        } else {
+            Ok(())
+        }
     }
 }
 
@@ -189,9 +235,9 @@ mod tests {
         test_utils::VMContextBuilder,
         testing_env, AccountId, BorshStorageKey,
     };
-    use near_sdk_contract_tools_macros::Rbac;
+    use near_sdk_contract_tools_macros::{Rbac, Role};
 
-    use super::Rbac;
+    use super::{Rbac, RbacError, RbacRole};
 
     #[derive(BorshSerialize, BorshStorageKey)]
     enum Role {
@@ -204,6 +250,18 @@ mod tests {
     #[near_bindgen]
     struct Contract {}
 
+    #[derive(Role)]
+    #[role(crate = "crate")]
+    enum PlainRole {
+        A,
+        B,
+    }
+
+    #[derive(Rbac)]
+    #[rbac(roles = "PlainRole", crate = "crate")]
+    #[near_bindgen]
+    struct PlainContract {}
+
     #[test]
     pub fn empty() {
         let a: AccountId = "account".parse().unwrap();
@@ -300,6 +358,24 @@ mod tests {
         Contract::require_role(&Role::B);
     }
 
+    #[test]
+    pub fn check_role() {
+        let mut r = Contract {};
+        let a: AccountId = "account".parse().unwrap();
+
+        r.add_role(a.clone(), &Role::A);
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(a).build());
+
+        assert_eq!(Contract::check_role(&Role::A), Ok(()));
+        assert_eq!(Contract::check_role(&Role::B), Err(RbacError::Unauthorized));
+        assert_eq!(
+            Contract::check_prohibited_role(&Role::A),
+            Err(RbacError::Prohibited),
+        );
+        assert_eq!(Contract::check_prohibited_role(&Role::B), Ok(()));
+    }
+
     #[test]
     #[should_panic = "Prohibited role"]
     pub fn prohibit_role_fail() {
@@ -333,4 +409,27 @@ mod tests {
 
         Contract::prohibit_role(&Role::B);
     }
+
+    #[test]
+    pub fn plain_role() {
+        let mut r = PlainContract {};
+        let a: AccountId = "account".parse().unwrap();
+
+        r.add_role(a.clone(), &PlainRole::B);
+
+        assert!(!PlainContract::has_role(&a, &PlainRole::A));
+        assert!(PlainContract::has_role(&a, &PlainRole::B));
+        assert_eq!(PlainContract::iter_members_of(&PlainRole::B).count(), 1);
+        assert_eq!(PlainRole::B.to_string(), "B");
+    }
+
+    #[test]
+    pub fn plain_role_key_matches_borsh() {
+        assert_eq!(PlainRole::A.role_key(), Role::A.role_key());
+        assert_eq!(PlainRole::B.role_key(), Role::B.role_key());
+        assert_eq!(
+            PlainContract::slot_members_of(&PlainRole::B).key,
+            Contract::slot_members_of(&Role::B).key,
+        );
+    }
 }
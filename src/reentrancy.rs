@@ -0,0 +1,287 @@
+//! Reentrancy guard.
+//!
+//! A NEAR function call runs to completion before any other call to the
+//! same contract starts, so a contract cannot be reentered in the middle of
+//! a method. Reentrancy happens across promise boundaries instead: a method
+//! calls another contract (e.g. `ft_on_transfer`), and that contract calls
+//! back into the first one before the callback of the first method runs.
+//!
+//! [`Reentrancy::enter`] locks a scope, and returns a [`GuardToken`] that
+//! unlocks it when it is dropped, at the end of the method. Scopes are
+//! independent, so unrelated flows do not block each other. The
+//! [`non_reentrant`](near_sdk_contract_tools_macros::non_reentrant)
+//! attribute injects the guard into a method:
+//!
+//! * `#[non_reentrant]` locks the scope for the duration of the method. This
+//!   rejects reentrant calls made within the same function call, e.g. by a
+//!   hook that calls back into a guarded method.
+//! * `#[non_reentrant(hold)]` keeps the scope locked after the method
+//!   returns, across the promises it schedules, until the contract calls
+//!   [`Reentrancy::release`], typically in the callback. Calls into the
+//!   scope before then are rejected.
+//!
+//! The lock is a storage flag rather than an in-memory one, because a lock
+//! held across promises must outlive the function call. A function call
+//! that fails reverts its storage writes, so a lock is never left behind by
+//! a failed call that was not holding it already.
+//!
+//! ```
+//! use near_sdk::{env, near_bindgen, AccountId, Promise};
+//! use near_sdk_contract_tools::{non_reentrant, reentrancy::Reentrancy};
+//!
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl Reentrancy for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     #[non_reentrant(scope = "withdraw", hold)]
+//!     pub fn withdraw(&mut self, receiver_id: AccountId) -> Promise {
+//!         // ...
+//!         Promise::new(receiver_id).transfer(1).then(
+//!             Self::ext(env::current_account_id()).on_withdraw(),
+//!         )
+//!     }
+//!
+//!     #[private]
+//!     pub fn on_withdraw(&mut self) {
+//!         Self::release("withdraw");
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The reentrancy root storage slot is not used or modified. The default key is `~gd`.
+//! * (UB) A scope held across promises is released by a callback that
+//!   cannot fail before it releases the scope. Otherwise, the scope stays
+//!   locked until [`Reentrancy::release`] is called for it.
+//! * (ERR) A scope is entered at most once until it is released.
+
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    env, BorshStorageKey,
+};
+use thiserror::Error;
+
+use crate::{slot::Slot, DefaultStorageKey};
+
+const REENTRANT_FAIL_MESSAGE: &str = message!("RNT1", "Reentrant call");
+
+/// A scope was entered while it was locked
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("{}", REENTRANT_FAIL_MESSAGE)]
+pub struct Reentrant {
+    /// The locked scope
+    pub scope: String,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Lock(&'a str),
+}
+
+/// Lock of a scope, released when dropped unless it is held with
+/// [`GuardToken::hold`]
+#[must_use]
+#[derive(Debug)]
+pub struct GuardToken {
+    slot: Slot<bool>,
+    held: bool,
+}
+
+impl GuardToken {
+    /// Keeps the scope locked after this token is dropped, across promise
+    /// boundaries, until [`Reentrancy::release`] is called for it
+    pub fn hold(mut self) {
+        self.held = true;
+    }
+}
+
+impl Drop for GuardToken {
+    fn drop(&mut self) {
+        if !self.held {
+            self.slot.remove();
+        }
+    }
+}
+
+/// Contract-wide reentrancy guard, with independent scopes
+pub trait Reentrancy {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Reentrancy)
+    }
+
+    /// Storage slot of the lock of a scope. Exists if, and only if, the
+    /// scope is locked.
+    fn slot_lock(scope: &str) -> Slot<bool> {
+        <Self as Reentrancy>::root().field(StorageKey::Lock(scope))
+    }
+
+    /// Returns whether the scope is locked
+    fn is_locked(scope: &str) -> bool {
+        Self::slot_lock(scope).exists()
+    }
+
+    /// Fallible version of [`Reentrancy::enter`]
+    fn try_enter(scope: &str) -> Result<GuardToken, Reentrant> {
+        let mut slot = Self::slot_lock(scope);
+
+        if slot.swap(&true).is_some() {
+            return Err(Reentrant {
+                scope: scope.to_string(),
+            });
+        }
+
+        Ok(GuardToken { slot, held: false })
+    }
+
+    /// Locks the scope until the returned token is dropped. Panics if the
+    /// scope is already locked. Called by methods with the
+    /// [`non_reentrant`](near_sdk_contract_tools_macros::non_reentrant)
+    /// attribute.
+    fn enter(scope: &str) -> GuardToken {
+        Self::try_enter(scope).unwrap_or_else(|e| env::panic_str(&e.to_string()))
+    }
+
+    /// Unlocks a scope held with [`GuardToken::hold`]. Returns whether it
+    /// was locked.
+    fn release(scope: &str) -> bool {
+        Self::slot_lock(scope).remove()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::near_bindgen;
+
+    use super::{Reentrancy, Reentrant};
+    use crate::{non_reentrant, testing::TestEnv};
+
+    #[near_bindgen]
+    struct Contract {
+        calls: u32,
+    }
+
+    impl Reentrancy for Contract {}
+
+    #[near_bindgen]
+    impl Contract {
+        /// Calls itself `depth` more times, as a malicious hook would
+        #[non_reentrant(crate = "crate")]
+        pub fn guarded(&mut self, depth: u32) {
+            self.calls += 1;
+            if depth > 0 {
+                self.guarded(depth - 1);
+            }
+        }
+
+        #[non_reentrant(scope = "guarded", crate = "crate")]
+        pub fn same_scope(&mut self) {
+            self.calls += 1;
+        }
+
+        #[non_reentrant(scope = "other", crate = "crate")]
+        pub fn other_scope(&mut self) {
+            self.guarded(0);
+        }
+
+        #[non_reentrant(scope = "flow", hold, crate = "crate")]
+        pub fn start_flow(&mut self) {
+            self.calls += 1;
+        }
+
+        #[private]
+        pub fn finish_flow(&mut self) {
+            Self::release("flow");
+        }
+    }
+
+    #[test]
+    fn not_reentrant() {
+        let _env = TestEnv::new();
+        let mut contract = Contract { calls: 0 };
+
+        contract.guarded(0);
+        contract.guarded(0);
+        contract.same_scope();
+
+        assert_eq!(contract.calls, 3);
+        assert!(!Contract::is_locked("guarded"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Reentrant call")]
+    fn reentrant() {
+        let _env = TestEnv::new();
+        let mut contract = Contract { calls: 0 };
+
+        contract.guarded(1);
+    }
+
+    #[test]
+    fn independent_scopes() {
+        let _env = TestEnv::new();
+        let mut contract = Contract { calls: 0 };
+
+        contract.other_scope();
+
+        assert_eq!(contract.calls, 1);
+        assert!(!Contract::is_locked("guarded"));
+        assert!(!Contract::is_locked("other"));
+    }
+
+    #[test]
+    fn hold_across_promises() {
+        let _env = TestEnv::new();
+        let mut contract = Contract { calls: 0 };
+
+        contract.start_flow();
+        assert!(Contract::is_locked("flow"));
+        assert_eq!(
+            Contract::try_enter("flow").unwrap_err(),
+            Reentrant {
+                scope: "flow".to_string(),
+            },
+        );
+
+        contract.finish_flow();
+        assert!(!Contract::is_locked("flow"));
+
+        contract.start_flow();
+        assert_eq!(contract.calls, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reentrant call")]
+    fn reentrant_while_held() {
+        let _env = TestEnv::new();
+        let mut contract = Contract { calls: 0 };
+
+        contract.start_flow();
+        contract.start_flow();
+    }
+
+    #[test]
+    fn guard_token() {
+        let _env = TestEnv::new();
+
+        let token = Contract::enter("scope");
+        assert!(Contract::is_locked("scope"));
+        assert!(Contract::try_enter("scope").is_err());
+        drop(token);
+        assert!(!Contract::is_locked("scope"));
+
+        Contract::enter("scope").hold();
+        assert!(Contract::is_locked("scope"));
+        assert!(Contract::release("scope"));
+        assert!(!Contract::release("scope"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Case conversion strategies, as used by the `rename` and `rename_all`
+//! options of `#[derive(Nep297)]` and `#[event]`.
+//!
+//! Useful for converting names at runtime the same way the macros do at
+//! compile time, e.g. to map the variants of a role enum to external strings.
+//! Available with the `rename` feature, which keeps the case conversion
+//! dependency out of contracts that do not use it:
+//!
+//! ```toml
+//! [dependencies]
+//! near-sdk-contract-tools = { version = "*", features = ["rename"] }
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use near_sdk_contract_tools::rename::{apply, Strategy};
+//!
+//! assert_eq!(apply(Strategy::SnakeCase, "NftMint"), "nft_mint");
+//!
+//! let strategy: Strategy = "SHOUTY-KEBAB-CASE".parse().unwrap();
+//! assert_eq!(apply(strategy, "NftMint"), "NFT-MINT");
+//! ```
+use std::{fmt::Display, str::FromStr};
+
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase, ToTitleCase,
+    ToUpperCamelCase,
+};
+use thiserror::Error;
+
+/// A case conversion strategy
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+pub enum Strategy {
+    /// `"UpperCamelCase"`
+    UpperCamelCase,
+    /// `"lowerCamelCase"`
+    LowerCamelCase,
+    /// `"snake_case"`
+    SnakeCase,
+    /// `"kebab-case"`
+    KebabCase,
+    /// `"SHOUTY_SNAKE_CASE"` (also `"SCREAMING_SNAKE_CASE"` and
+    /// `"SHOUTING_SNAKE_CASE"`)
+    ShoutySnakeCase,
+    /// `"Title Case"`
+    TitleCase,
+    /// `"SHOUTY-KEBAB-CASE"` (also `"SCREAMING-KEBAB-CASE"` and
+    /// `"SHOUTING-KEBAB-CASE"`)
+    ShoutyKebabCase,
+}
+
+impl Strategy {
+    /// All strategies
+    pub const ALL: [Strategy; 7] = [
+        Strategy::UpperCamelCase,
+        Strategy::LowerCamelCase,
+        Strategy::SnakeCase,
+        Strategy::KebabCase,
+        Strategy::ShoutySnakeCase,
+        Strategy::TitleCase,
+        Strategy::ShoutyKebabCase,
+    ];
+
+    /// The name of the strategy, as accepted by the macros
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Strategy::UpperCamelCase => "UpperCamelCase",
+            Strategy::LowerCamelCase => "lowerCamelCase",
+            Strategy::SnakeCase => "snake_case",
+            Strategy::KebabCase => "kebab-case",
+            Strategy::ShoutySnakeCase => "SHOUTY_SNAKE_CASE",
+            Strategy::TitleCase => "Title Case",
+            Strategy::ShoutyKebabCase => "SHOUTY-KEBAB-CASE",
+        }
+    }
+
+    /// Converts a string according to the strategy. Word boundaries are
+    /// detected at case changes (keeping runs of capitals, like acronyms,
+    /// together), and at underscores, hyphens, and spaces, which are
+    /// otherwise dropped.
+    pub fn apply(&self, s: &str) -> String {
+        match self {
+            Strategy::UpperCamelCase => s.to_upper_camel_case(),
+            Strategy::LowerCamelCase => s.to_lower_camel_case(),
+            Strategy::SnakeCase => s.to_snake_case(),
+            Strategy::KebabCase => s.to_kebab_case(),
+            Strategy::ShoutySnakeCase => s.to_shouty_snake_case(),
+            Strategy::TitleCase => s.to_title_case(),
+            Strategy::ShoutyKebabCase => s.to_shouty_kebab_case(),
+        }
+    }
+}
+
+/// Converts a string according to a strategy (see: [`Strategy::apply`])
+pub fn apply(strategy: Strategy, s: &str) -> String {
+    strategy.apply(s)
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The string is not the name of a [`Strategy`]
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error(
+    r#"Invalid rename strategy "{0}", expected one of "UpperCamelCase", "lowerCamelCase", "snake_case", "kebab-case", "SHOUTY_SNAKE_CASE", "Title Case", or "SHOUTY-KEBAB-CASE""#
+)]
+pub struct InvalidStrategyError(pub String);
+
+impl FromStr for Strategy {
+    type Err = InvalidStrategyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "UpperCamelCase" => Ok(Self::UpperCamelCase),
+            "lowerCamelCase" => Ok(Self::LowerCamelCase),
+            "snake_case" => Ok(Self::SnakeCase),
+            "kebab-case" => Ok(Self::KebabCase),
+            "SHOUTY_SNAKE_CASE" | "SCREAMING_SNAKE_CASE" | "SHOUTING_SNAKE_CASE" => {
+                Ok(Self::ShoutySnakeCase)
+            }
+            "Title Case" => Ok(Self::TitleCase),
+            "SHOUTY-KEBAB-CASE" | "SCREAMING-KEBAB-CASE" | "SHOUTING-KEBAB-CASE" => {
+                Ok(Self::ShoutyKebabCase)
+            }
+            _ => Err(InvalidStrategyError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, InvalidStrategyError, Strategy};
+
+    #[rustfmt::skip]
+    const CASES: &[(&str, [&str; 7])] = &[
+        // input, then in the order of `Strategy::ALL`
+        ("NftMint", ["NftMint", "nftMint", "nft_mint", "nft-mint", "NFT_MINT", "Nft Mint", "NFT-MINT"]),
+        ("HTTPRequest", ["HttpRequest", "httpRequest", "http_request", "http-request", "HTTP_REQUEST", "Http Request", "HTTP-REQUEST"]),
+        ("parseHTTPResponse", ["ParseHttpResponse", "parseHttpResponse", "parse_http_response", "parse-http-response", "PARSE_HTTP_RESPONSE", "Parse Http Response", "PARSE-HTTP-RESPONSE"]),
+        ("ABC", ["Abc", "abc", "abc", "abc", "ABC", "Abc", "ABC"]),
+        ("Version2Update", ["Version2Update", "version2Update", "version2_update", "version2-update", "VERSION2_UPDATE", "Version2 Update", "VERSION2-UPDATE"]),
+        ("v2_update", ["V2Update", "v2Update", "v2_update", "v2-update", "V2_UPDATE", "V2 Update", "V2-UPDATE"]),
+        ("mint2ft", ["Mint2ft", "mint2ft", "mint2ft", "mint2ft", "MINT2FT", "Mint2ft", "MINT2FT"]),
+        ("A1B2", ["A1b2", "a1b2", "a1b2", "a1b2", "A1B2", "A1b2", "A1B2"]),
+        ("_private_field", ["PrivateField", "privateField", "private_field", "private-field", "PRIVATE_FIELD", "Private Field", "PRIVATE-FIELD"]),
+        ("__double", ["Double", "double", "double", "double", "DOUBLE", "Double", "DOUBLE"]),
+        ("kebab-case-input", ["KebabCaseInput", "kebabCaseInput", "kebab_case_input", "kebab-case-input", "KEBAB_CASE_INPUT", "Kebab Case Input", "KEBAB-CASE-INPUT"]),
+        ("Title Case Input", ["TitleCaseInput", "titleCaseInput", "title_case_input", "title-case-input", "TITLE_CASE_INPUT", "Title Case Input", "TITLE-CASE-INPUT"]),
+        ("SHOUTY_SNAKE", ["ShoutySnake", "shoutySnake", "shouty_snake", "shouty-snake", "SHOUTY_SNAKE", "Shouty Snake", "SHOUTY-SNAKE"]),
+        ("", ["", "", "", "", "", "", ""]),
+    ];
+
+    #[test]
+    fn strategies() {
+        for (input, expected) in CASES {
+            for (strategy, expected) in Strategy::ALL.iter().zip(expected) {
+                assert_eq!(
+                    &apply(*strategy, input),
+                    expected,
+                    "{strategy} of {input:?}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names() {
+        for strategy in Strategy::ALL {
+            assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        }
+
+        assert_eq!(
+            "SCREAMING_SNAKE_CASE".parse(),
+            Ok(Strategy::ShoutySnakeCase)
+        );
+        assert_eq!("SHOUTING-KEBAB-CASE".parse(), Ok(Strategy::ShoutyKebabCase));
+        assert_eq!(
+            "camelCase".parse::<Strategy>(),
+            Err(InvalidStrategyError("camelCase".to_string())),
+        );
+    }
+}
@@ -2,32 +2,167 @@
 //!
 //! Makes it easy to create and manage storage keys and avoid unnecessary
 //! writes to contract storage. This reduces transaction IO  and saves on gas.
-use std::marker::PhantomData;
+use std::{collections::BTreeMap, marker::PhantomData};
 
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    env, IntoStorageKey,
+    env, require, IntoStorageKey,
 };
 
-/// A storage slot, composed of a storage location (key) and a data type
+pub mod codec;
+
+use codec::{BorshCodec, Decode, Encode};
+
+use crate::migrate::MigrationProgress;
+
+/// Default number of bytes of the hash kept by [`Slot::field_hashed`]
+pub const HASHED_KEY_LEN: usize = 16;
+
+/// A storage slot, composed of a storage location (key), a data type, and a
+/// codec that (de)serializes the data (default: [`BorshCodec`], see:
+/// [`codec`]).
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
-pub struct Slot<T> {
+pub struct Slot<T, C = BorshCodec> {
     /// The storage key this slot controls
     pub key: Vec<u8>,
     #[borsh_skip]
-    _marker: PhantomData<T>,
+    _marker: PhantomData<(T, C)>,
 }
 
 impl Slot<()> {
     /// A placeholder slot. Useful for creating namespaced fields.
+    ///
+    /// With the `storage-key-check` feature enabled, panics if the key is a
+    /// prefix of, or is prefixed by, another root created by the contract
+    /// (see: [`assert_no_prefix_collisions`]).
     pub fn root<K: IntoStorageKey>(key: K) -> Self {
+        let key = key.into_storage_key();
+
+        #[cfg(feature = "storage-key-check")]
+        check::register_root(&key);
+
         Self {
-            key: key.into_storage_key(),
+            key,
             _marker: PhantomData,
         }
     }
 }
 
+/// Returns the first pair of keys where one is a prefix of the other (or
+/// they are equal), if any. Slots namespaced by one key of such a pair may
+/// overwrite slots namespaced by the other.
+pub fn find_prefix_collision<'a>(keys: &[&'a [u8]]) -> Option<(&'a [u8], &'a [u8])> {
+    keys.iter().enumerate().find_map(|(i, a)| {
+        keys[i + 1..]
+            .iter()
+            .find(|b| a.starts_with(b) || b.starts_with(a))
+            .map(|b| (*a, *b))
+    })
+}
+
+/// Asserts that none of the given storage key prefixes is a prefix of
+/// another. Useful in tests to make sure that the storage keys chosen for
+/// the components of a contract do not collide, e.g.:
+///
+/// ```
+/// use near_sdk::IntoStorageKey;
+/// use near_sdk_contract_tools::{slot::assert_no_prefix_collisions, DefaultStorageKey};
+///
+/// assert_no_prefix_collisions(&[
+///     &DefaultStorageKey::Owner.into_storage_key(),
+///     &DefaultStorageKey::Nep141.into_storage_key(),
+///     b"my_data",
+/// ]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if two keys collide.
+pub fn assert_no_prefix_collisions(keys: &[&[u8]]) {
+    if let Some((a, b)) = find_prefix_collision(keys) {
+        env::panic_str(&format!(
+            "Storage key prefix collision: {:?} and {:?}",
+            String::from_utf8_lossy(a),
+            String::from_utf8_lossy(b),
+        ));
+    }
+}
+
+/// Storage key checks for tests, enabled by the `storage-key-check` feature.
+///
+/// Besides checking roots for prefix collisions (see: [`Slot::root`]), the
+/// longest key accessed through a [`Slot`] is recorded, so that contract
+/// authors can audit the key lengths of their storage layouts, e.g.:
+///
+/// ```ignore
+/// check::reset_longest_key();
+/// contract.ft_transfer(receiver_id, amount, None);
+/// assert!(check::longest_key().unwrap().len() <= 48);
+/// ```
+#[cfg(feature = "storage-key-check")]
+pub mod check {
+    use std::cell::RefCell;
+
+    use near_sdk::env;
+
+    thread_local! {
+        static ROOTS: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+        static LONGEST_KEY: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    }
+
+    /// The longest key accessed through a [`Slot`](super::Slot) on this
+    /// thread since the last [`reset_longest_key`], if any
+    pub fn longest_key() -> Option<Vec<u8>> {
+        LONGEST_KEY.with(|longest| longest.borrow().clone())
+    }
+
+    /// Forgets the longest key accessed so far
+    pub fn reset_longest_key() {
+        LONGEST_KEY.with(|longest| *longest.borrow_mut() = None);
+    }
+
+    pub(crate) fn record_access(key: &[u8]) {
+        LONGEST_KEY.with(|longest| {
+            let mut longest = longest.borrow_mut();
+
+            let is_longer = match &*longest {
+                Some(longest) => key.len() > longest.len(),
+                None => true,
+            };
+
+            if is_longer {
+                *longest = Some(key.to_vec());
+            }
+        });
+    }
+
+    /// Remembers a root key, panicking if it collides with another. The same
+    /// root is created again every time a component accesses its storage, so
+    /// equal keys are not considered a collision here.
+    pub(crate) fn register_root(key: &[u8]) {
+        ROOTS.with(|roots| {
+            let mut roots = roots.borrow_mut();
+
+            if roots.iter().any(|root| root == key) {
+                return;
+            }
+
+            if let Some(root) = roots
+                .iter()
+                .find(|root| root.starts_with(key) || key.starts_with(root))
+            {
+                env::panic_str(&format!(
+                    "Storage key prefix collision: {:?} and {:?}",
+                    String::from_utf8_lossy(root),
+                    String::from_utf8_lossy(key),
+                ));
+            }
+
+            roots.push(key.to_vec());
+        });
+    }
+}
+
 impl<T> Slot<T> {
     /// Creates a new [`Slot`] that controls the given storage key
     pub fn new(key: impl IntoStorageKey) -> Self {
@@ -36,6 +171,22 @@ impl<T> Slot<T> {
             _marker: PhantomData,
         }
     }
+}
+
+impl<T, C> Slot<T, C> {
+    /// Creates a [`Slot`] that controls the same storage key, but
+    /// (de)serializes its value with a different codec.
+    ///
+    /// # Warning
+    ///
+    /// If the data in the slot was written with a different codec, methods
+    /// like [`Slot::read`] and [`Slot::take`] will panic.
+    pub fn with_codec<D>(self) -> Slot<T, D> {
+        Slot {
+            key: self.key,
+            _marker: PhantomData,
+        }
+    }
 
     /// Creates a new [`Slot`] that controls the given key namespaced (prefixed)
     /// by the parent key, to be used as a namespace for another subfield.
@@ -55,6 +206,57 @@ impl<T> Slot<T> {
         }
     }
 
+    /// Creates a new [`Slot`] that controls the given key namespaced (prefixed)
+    /// by the parent key. The key is serialized from a reference, so it does
+    /// not have to be owned (e.g. by cloning an `AccountId`). Produces the
+    /// same key as [`Slot::field`] would with a key implementing
+    /// [`near_sdk::BorshStorageKey`].
+    pub fn field_ref<U, K: BorshSerialize + ?Sized>(&self, key: &K) -> Slot<U> {
+        let mut full_key = self.key.clone();
+        key.serialize(&mut full_key).unwrap();
+
+        Slot {
+            key: full_key,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a new [`Slot`] that controls the given key namespaced (prefixed)
+    /// by the parent key, like [`Slot::field_ref`], but the key is replaced by
+    /// the first [`HASHED_KEY_LEN`] bytes of the SHA-256 hash of its
+    /// serialization. Useful for long dynamic key components like account IDs
+    /// or token IDs, since every byte of a storage key adds to the cost of
+    /// each read and write.
+    ///
+    /// # Warning
+    ///
+    /// Different keys can, in theory, hash to the same slot. With the default
+    /// length, this is astronomically unlikely (about one in 2^64 after 2^32
+    /// keys), but the risk grows quickly with shorter lengths (see
+    /// [`Slot::field_hashed_len`]).
+    ///
+    /// Switching an existing field between raw and hashed keys changes its
+    /// storage layout, so data written before the switch will not be found.
+    pub fn field_hashed<U, K: BorshSerialize + ?Sized>(&self, key: &K) -> Slot<U> {
+        self.field_hashed_len(key, HASHED_KEY_LEN)
+    }
+
+    /// Like [`Slot::field_hashed`], but keeps the first `len` bytes of the
+    /// hash. `len` must be between 1 and 32 (inclusive).
+    pub fn field_hashed_len<U, K: BorshSerialize + ?Sized>(&self, key: &K, len: usize) -> Slot<U> {
+        require!(
+            (1..=32).contains(&len),
+            message!("SLOT1", "Hashed key length must be between 1 and 32"),
+        );
+
+        let hash = env::sha256(&key.try_to_vec().unwrap());
+
+        Slot {
+            key: [&self.key[..], &hash[..len]].concat(),
+            _marker: PhantomData,
+        }
+    }
+
     /// Creates a [`Slot`] that tries to parse a different data type from the same
     /// storage slot.
     ///
@@ -62,7 +264,7 @@ impl<T> Slot<T> {
     ///
     /// If the data in the slot is not parsable into the new type, methods like
     /// [`Slot::read`] and [`Slot::take`] will panic.
-    pub fn transmute<U>(&self) -> Slot<U> {
+    pub fn transmute<U>(&self) -> Slot<U, C> {
         Slot {
             key: self.key.clone(),
             _marker: PhantomData,
@@ -71,30 +273,85 @@ impl<T> Slot<T> {
 
     /// Write raw bytes into the storage slot. No type checking.
     pub fn write_raw(&mut self, value: &[u8]) -> bool {
+        #[cfg(feature = "storage-key-check")]
+        check::record_access(&self.key);
+
         env::storage_write(&self.key, value)
     }
 
     /// Read raw bytes from the slot. No type checking or parsing.
     pub fn read_raw(&self) -> Option<Vec<u8>> {
+        #[cfg(feature = "storage-key-check")]
+        check::record_access(&self.key);
+
         env::storage_read(&self.key)
     }
 
     /// Returns `true` if this slot's key is currently present in the smart
     /// contract storage, `false` otherwise
     pub fn exists(&self) -> bool {
+        #[cfg(feature = "storage-key-check")]
+        check::record_access(&self.key);
+
         env::storage_has_key(&self.key)
     }
 
     /// Removes the managed key from storage
     pub fn remove(&mut self) -> bool {
+        #[cfg(feature = "storage-key-check")]
+        check::record_access(&self.key);
+
         env::storage_remove(&self.key)
     }
+
+    /// Removes the managed key from storage and returns the raw bytes it
+    /// held, if present. No parsing.
+    pub fn take_raw(&mut self) -> Option<Vec<u8>> {
+        if self.remove() {
+            // unwrap should be safe if remove returns true
+            Some(env::storage_get_evicted().unwrap())
+        } else {
+            None
+        }
+    }
+
+    /// Exchanges the contents of two slots. No parsing, so this is cheap
+    /// even for large values. If only one of the slots has a value, it is
+    /// moved to the other slot.
+    pub fn swap_with(&mut self, other: &mut Slot<T, C>) {
+        let this = self.take_raw();
+        let that = other.take_raw();
+
+        if let Some(that) = that {
+            self.write_raw(&that);
+        }
+
+        if let Some(this) = this {
+            other.write_raw(&this);
+        }
+    }
+
+    /// Moves the contents of this slot to `dest`, overwriting any value
+    /// already there, and removes this slot. If this slot is empty, `dest`
+    /// is removed as well. No parsing. Returns `true` if a value was moved.
+    pub fn move_to(mut self, mut dest: Slot<T, C>) -> bool {
+        match self.take_raw() {
+            Some(value) => {
+                dest.write_raw(&value);
+                true
+            }
+            None => {
+                dest.remove();
+                false
+            }
+        }
+    }
 }
 
-impl<T: BorshSerialize> Slot<T> {
+impl<T, C: Encode<T>> Slot<T, C> {
     /// Writes a value to the managed storage slot
     pub fn write(&mut self, value: &T) -> bool {
-        self.write_raw(&value.try_to_vec().unwrap())
+        self.write_raw(&C::encode(value))
     }
 
     /// If the given value is `Some(T)`, writes `T` to storage. Otherwise,
@@ -110,50 +367,1289 @@ impl<T: BorshSerialize> Slot<T> {
     }
 }
 
-impl<T: BorshDeserialize> Slot<T> {
+impl<T, C: Decode<T>> Slot<T, C> {
     /// Reads a value from storage, if present.
     pub fn read(&self) -> Option<T> {
-        self.read_raw().map(|v| T::try_from_slice(&v).unwrap())
+        self.read_raw().map(|v| C::decode(&v))
     }
 
     /// Removes a value from storage and returns it if present.
     pub fn take(&mut self) -> Option<T> {
-        if self.remove() {
-            // unwrap should be safe if remove returns true
-            Some(T::try_from_slice(&env::storage_get_evicted().unwrap()).unwrap())
-        } else {
-            None
-        }
+        self.take_raw().map(|v| C::decode(&v))
+    }
+
+    /// Reads a value from storage, or returns `default` if not present.
+    pub fn read_or(&self, default: T) -> T {
+        self.read().unwrap_or(default)
     }
 }
 
-impl<T: BorshSerialize + BorshDeserialize> Slot<T> {
+impl<T: Default, C: Decode<T>> Slot<T, C> {
+    /// Reads a value from storage, or returns `T::default()` if not present.
+    pub fn read_or_default(&self) -> T {
+        self.read().unwrap_or_default()
+    }
+}
+
+impl<T, C: Encode<T> + Decode<T>> Slot<T, C> {
     /// Writes a value to storage and returns the evicted value, if present.
     pub fn swap(&mut self, value: &T) -> Option<T> {
-        if self.write_raw(&value.try_to_vec().unwrap()) {
+        if self.write_raw(&C::encode(value)) {
             // unwrap should be safe because write_raw returned true
-            Some(T::try_from_slice(&env::storage_get_evicted().unwrap()).unwrap())
+            Some(C::decode(&env::storage_get_evicted().unwrap()))
         } else {
             None
         }
     }
 }
 
-impl<T> IntoStorageKey for Slot<T> {
+impl<T: Default, C: Encode<T> + Decode<T>> Slot<T, C> {
+    /// Reads the value (or `T::default()` if not present), applies `f` to it,
+    /// writes it back, and returns the new value.
+    ///
+    /// The write is skipped if `f` leaves the value unchanged, so a slot that
+    /// is not present stays that way if `f` does not change the default.
+    pub fn modify(&mut self, f: impl FnOnce(&mut T)) -> T {
+        let raw = self.read_raw();
+        let mut value = match &raw {
+            Some(raw) => C::decode(raw),
+            None => T::default(),
+        };
+
+        f(&mut value);
+
+        let new_raw = C::encode(&value);
+        let unchanged = match raw {
+            Some(raw) => raw == new_raw,
+            None => C::encode(&T::default()) == new_raw,
+        };
+
+        if !unchanged {
+            self.write_raw(&new_raw);
+        }
+
+        value
+    }
+}
+
+impl<T, C> IntoStorageKey for Slot<T, C> {
     fn into_storage_key(self) -> Vec<u8> {
         self.key
     }
 }
 
-impl<T, U> PartialEq<Slot<U>> for Slot<T> {
-    fn eq(&self, other: &Slot<U>) -> bool {
+impl<T, C, U, D> PartialEq<Slot<U, D>> for Slot<T, C> {
+    fn eq(&self, other: &Slot<U, D>) -> bool {
         self.key == other.key
     }
 }
 
+/// Moves the values of the children of `old_root` with the given keys to the
+/// children of `new_root` with the same keys, applying `transform` to the raw
+/// bytes of each value. Useful for migrating between storage layouts.
+///
+/// At most `limit` keys are processed per call, in order. Keys without a value
+/// under `old_root` are skipped, but still count as processed, so the next
+/// call should continue with `&keys[progress.items_processed as usize..]`
+/// (e.g. from [`ProgressiveMigration::migrate_items`](crate::migrate::ProgressiveMigration::migrate_items)).
+/// Existing values under `new_root` are overwritten.
+///
+/// NEAR storage cannot be scanned by prefix, so the keys must be known to
+/// the caller (e.g. from a [`TrackedNamespace`] or a list of accounts).
+pub fn migrate_prefix(
+    old_root: &Slot<()>,
+    new_root: &Slot<()>,
+    keys: &[Vec<u8>],
+    transform: impl Fn(Vec<u8>) -> Vec<u8>,
+    limit: u32,
+) -> MigrationProgress {
+    let batch = &keys[..keys.len().min(limit as usize)];
+
+    for key in batch {
+        if let Some(value) = old_root.field::<()>(key.clone()).take_raw() {
+            new_root
+                .field::<()>(key.clone())
+                .write_raw(&transform(value));
+        }
+    }
+
+    MigrationProgress {
+        done: batch.len() == keys.len(),
+        items_processed: batch.len() as u32,
+    }
+}
+
+/// Like [`migrate_prefix`], but deserializes each value as `T` and
+/// serializes the transformed value as `U`.
+pub fn migrate_prefix_typed<T: BorshDeserialize, U: BorshSerialize>(
+    old_root: &Slot<()>,
+    new_root: &Slot<()>,
+    keys: &[Vec<u8>],
+    transform: impl Fn(T) -> U,
+    limit: u32,
+) -> MigrationProgress {
+    migrate_prefix(
+        old_root,
+        new_root,
+        keys,
+        |value| BorshCodec::encode(&transform(BorshCodec::decode(&value))),
+        limit,
+    )
+}
+
+/// A [`Slot`] that reads its value from storage at most once, and writes it
+/// back at most once.
+///
+/// The value is read from storage on first access and kept in memory
+/// afterwards. Changes are only made in memory until [`CachedSlot::flush`]
+/// is called, or the `CachedSlot` is dropped. This is useful when the same
+/// value is read and updated many times in one function call, e.g. the total
+/// supply of a token during a batch of mints.
+///
+/// # Warning
+///
+/// The cache is not shared with anything else, so while a `CachedSlot` is
+/// alive, nothing else should read or write the same storage key. Otherwise,
+/// it will observe stale values, or have its writes overwritten by the flush.
+///
+/// The cache only lives as long as the current function call. Flush it
+/// before creating any promises whose callbacks depend on the value, and do
+/// not assume the value is still current in a callback: other transactions
+/// may have changed it in the meantime.
+#[derive(Debug)]
+pub struct CachedSlot<T: BorshSerialize + BorshDeserialize> {
+    slot: Slot<T>,
+    value: Option<Option<T>>,
+    dirty: bool,
+}
+
+impl<T: BorshSerialize + BorshDeserialize> CachedSlot<T> {
+    /// Creates a cache for the given slot. Does not read from storage.
+    pub fn new(slot: Slot<T>) -> Self {
+        Self {
+            slot,
+            value: None,
+            dirty: false,
+        }
+    }
+
+    /// The slot this cache reads from and writes to
+    pub fn slot(&self) -> &Slot<T> {
+        &self.slot
+    }
+
+    fn load(&mut self) -> &mut Option<T> {
+        if self.value.is_none() {
+            self.value = Some(self.slot.read());
+        }
+
+        // Just loaded
+        self.value.as_mut().unwrap()
+    }
+
+    /// Returns the value, reading it from storage if it has not been read
+    /// yet.
+    pub fn get(&mut self) -> Option<&T> {
+        self.load().as_ref()
+    }
+
+    /// Returns a mutable reference to the value, reading it from storage if
+    /// it has not been read yet. Marks the cache as modified.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.dirty = true;
+        self.load().as_mut()
+    }
+
+    /// Replaces the value in memory (`None` removes it from storage when
+    /// flushed). Does not read from storage.
+    pub fn set(&mut self, value: Option<T>) {
+        self.value = Some(value);
+        self.dirty = true;
+    }
+
+    /// Returns `true` if the value has been modified since it was last read
+    /// from or written to storage.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writes the value to storage if it has been modified. Returns `true`
+    /// if storage was written to.
+    pub fn flush(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+
+        self.dirty = false;
+
+        if let Some(value) = &self.value {
+            self.slot.set(value.as_ref());
+        }
+
+        true
+    }
+}
+
+impl<T: BorshSerialize + BorshDeserialize> Drop for CachedSlot<T> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Stages reads and writes to many slots in memory, and writes them to
+/// storage at once when committed.
+///
+/// Each storage key is read at most once, and written at most once, with the
+/// last staged value: writing the same balance ten times in a batch costs a
+/// single storage write. Nothing is written to storage until
+/// [`SlotTransaction::commit`] is called. A transaction that is dropped
+/// without being committed is discarded.
+///
+/// # Warning
+///
+/// Like [`CachedSlot`], the transaction does not observe writes made
+/// directly to storage after it read a key, and its commit overwrites them.
+/// Commit it before reading the same slots another way, and before creating
+/// any promises whose callbacks depend on the staged values.
+#[derive(Debug, Default)]
+#[must_use]
+pub struct SlotTransaction {
+    entries: BTreeMap<Vec<u8>, TransactionEntry>,
+}
+
+#[derive(Debug)]
+struct TransactionEntry {
+    value: Option<Vec<u8>>,
+    dirty: bool,
+}
+
+impl SlotTransaction {
+    /// Creates an empty transaction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the raw bytes of a slot, from the staged value if there is one.
+    /// Otherwise reads from storage, and remembers the value.
+    pub fn read_raw<T>(&mut self, slot: &Slot<T>) -> Option<&[u8]> {
+        self.entries
+            .entry(slot.key.clone())
+            .or_insert_with(|| TransactionEntry {
+                value: slot.read_raw(),
+                dirty: false,
+            })
+            .value
+            .as_deref()
+    }
+
+    /// Reads a slot, from the staged value if there is one.
+    pub fn read<T: BorshDeserialize>(&mut self, slot: &Slot<T>) -> Option<T> {
+        self.read_raw(slot)
+            .map(|value| T::try_from_slice(value).unwrap())
+    }
+
+    fn stage(&mut self, key: &[u8], value: Option<Vec<u8>>) {
+        self.entries
+            .insert(key.to_vec(), TransactionEntry { value, dirty: true });
+    }
+
+    /// Stages raw bytes to be written to a slot. No type checking.
+    pub fn write_raw<T>(&mut self, slot: &Slot<T>, value: &[u8]) {
+        self.stage(&slot.key, Some(value.to_vec()));
+    }
+
+    /// Stages a value to be written to a slot.
+    pub fn write<T: BorshSerialize>(&mut self, slot: &Slot<T>, value: &T) {
+        self.stage(&slot.key, Some(value.try_to_vec().unwrap()));
+    }
+
+    /// Stages the removal of a slot.
+    pub fn remove<T>(&mut self, slot: &Slot<T>) {
+        self.stage(&slot.key, None);
+    }
+
+    /// Number of slots with staged changes
+    pub fn len(&self) -> usize {
+        self.entries.values().filter(|entry| entry.dirty).count()
+    }
+
+    /// Returns `true` if no changes are staged
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes all staged changes to storage. Returns the number of storage
+    /// writes (and removals) performed.
+    pub fn commit(self) -> u32 {
+        let mut writes = 0;
+
+        for (key, entry) in self.entries {
+            if entry.dirty {
+                match entry.value {
+                    Some(value) => env::storage_write(&key, &value),
+                    None => env::storage_remove(&key),
+                };
+
+                writes += 1;
+            }
+        }
+
+        writes
+    }
+}
+
+/// A list of values stored under a [`Slot`] namespace.
+///
+/// The length is stored in the root slot itself, and the element at index `i`
+/// in the slot `root.field_ref(&i)`. Elements are only ever appended or
+/// swap-removed, so no element other than the removed and the last one is
+/// touched by an update.
+///
+/// # Warning
+///
+/// The root slot must not be used for anything else, including as a prefix
+/// for other slots.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct SlotVec<T> {
+    root: Slot<()>,
+    #[borsh_skip]
+    _marker: PhantomData<T>,
+}
+
+impl<T: BorshSerialize + BorshDeserialize> SlotVec<T> {
+    /// Creates a list stored under the given root slot
+    pub fn new(root: Slot<()>) -> Self {
+        Self {
+            root,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Storage slot for the length of the list
+    pub fn slot_len(&self) -> Slot<u32> {
+        self.root.transmute()
+    }
+
+    /// Storage slot for the element at `index`
+    pub fn slot_element(&self, index: u32) -> Slot<T> {
+        self.root.field_ref(&index)
+    }
+
+    /// Number of elements in the list
+    pub fn len(&self) -> u32 {
+        self.slot_len().read().unwrap_or(0)
+    }
+
+    /// Returns `true` if the list has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the element at `index`, if it exists
+    pub fn get(&self, index: u32) -> Option<T> {
+        if index < self.len() {
+            self.slot_element(index).read()
+        } else {
+            None
+        }
+    }
+
+    /// Appends an element to the end of the list, returning its index
+    pub fn push(&mut self, value: &T) -> u32 {
+        let index = self.len();
+        let len = index
+            .checked_add(1)
+            .unwrap_or_else(|| env::panic_str(message!("SLOT2", "SlotVec length overflow")));
+
+        self.slot_element(index).write(value);
+        self.slot_len().write(&len);
+
+        index
+    }
+
+    /// Removes the last element of the list and returns it
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.len().checked_sub(1)?;
+        let value = self.slot_element(index).take();
+        self.set_len(index);
+        value
+    }
+
+    /// Removes the element at `index` and returns it, replacing it with the
+    /// last element of the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: u32) -> T {
+        let len = self.len();
+
+        require!(
+            index < len,
+            message!("SLOT3", "SlotVec index out of bounds")
+        );
+
+        let last = len - 1;
+        let mut last_slot = self.slot_element(last);
+        let last_value = last_slot
+            .take()
+            .unwrap_or_else(|| env::panic_str(message!("SLOT4", "Inconsistent SlotVec state")));
+
+        let value = if index == last {
+            last_value
+        } else {
+            self.slot_element(index)
+                .swap(&last_value)
+                .unwrap_or_else(|| env::panic_str(message!("SLOT4", "Inconsistent SlotVec state")))
+        };
+
+        self.set_len(last);
+
+        value
+    }
+
+    /// Iterates over at most `limit` elements, starting at index `offset`
+    pub fn iter(&self, offset: u32, limit: u32) -> impl Iterator<Item = T> {
+        let end = offset.saturating_add(limit).min(self.len());
+        let root = self.root.clone();
+
+        (offset..end).map(move |index| {
+            root.field_ref::<T, _>(&index)
+                .read()
+                .unwrap_or_else(|| env::panic_str(message!("SLOT4", "Inconsistent SlotVec state")))
+        })
+    }
+
+    fn set_len(&mut self, len: u32) {
+        let mut slot_len = self.slot_len();
+
+        if len == 0 {
+            slot_len.remove();
+        } else {
+            slot_len.write(&len);
+        }
+    }
+}
+
+/// A map stored under a [`Slot`] namespace, whose entries can be iterated.
+///
+/// # Storage layout
+///
+/// With `k` the Borsh serialization of a key, and `i` of its index (`u32`,
+/// little-endian):
+///
+/// - `root + "k"`: number of entries (see [`SlotVec`]).
+/// - `root + "k" + i`: the key at index `i`.
+/// - `root + "v" + k`: the value of key `k`.
+/// - `root + "i" + k`: the index of key `k`.
+///
+/// Removing an entry moves the last key into its index (like
+/// [`SlotVec::swap_remove`]), so iteration order is not preserved across
+/// removals.
+///
+/// # Warning
+///
+/// The root slot must not be used for anything else, including as a prefix
+/// for other slots.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct SlotMap<K, V> {
+    root: Slot<()>,
+    #[borsh_skip]
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> SlotMap<K, V>
+where
+    K: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    /// Creates a map stored under the given root slot
+    pub fn new(root: Slot<()>) -> Self {
+        Self {
+            root,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The list of keys, in iteration order
+    pub fn keys(&self) -> SlotVec<K> {
+        SlotVec::new(self.root.ns(b"k"))
+    }
+
+    /// Storage slot for the value of `key`
+    pub fn slot_value(&self, key: &K) -> Slot<V> {
+        self.root.ns(b"v").field_ref(key)
+    }
+
+    /// Storage slot for the index of `key` in [`SlotMap::keys`]
+    pub fn slot_index(&self, key: &K) -> Slot<u32> {
+        self.root.ns(b"i").field_ref(key)
+    }
+
+    /// Number of entries in the map
+    pub fn len(&self) -> u32 {
+        self.keys().len()
+    }
+
+    /// Returns `true` if the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the value of `key`, if present
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.slot_value(key).read()
+    }
+
+    /// Returns `true` if the map has an entry for `key`
+    pub fn contains(&self, key: &K) -> bool {
+        self.slot_index(key).exists()
+    }
+
+    /// Sets the value of `key`, returning the previous value, if any
+    pub fn insert(&mut self, key: &K, value: &V) -> Option<V> {
+        let previous = self.slot_value(key).swap(value);
+
+        if previous.is_none() {
+            let index = self.keys().push(key);
+            self.slot_index(key).write(&index);
+        }
+
+        previous
+    }
+
+    /// Removes the entry for `key`, returning its value, if any
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.slot_value(key).take()?;
+        let index = self
+            .slot_index(key)
+            .take()
+            .unwrap_or_else(|| env::panic_str(message!("SLOT5", "Inconsistent SlotMap state")));
+
+        let mut keys = self.keys();
+        keys.swap_remove(index);
+
+        // The last key was moved into the removed index
+        if let Some(moved_key) = keys.get(index) {
+            self.slot_index(&moved_key).write(&index);
+        }
+
+        Some(value)
+    }
+
+    /// Iterates over at most `limit` entries, starting at index `offset`
+    pub fn iter(&self, offset: u32, limit: u32) -> impl Iterator<Item = (K, V)> {
+        let values = self.root.ns(b"v");
+
+        self.keys().iter(offset, limit).map(move |key| {
+            let value = values
+                .field_ref::<V, _>(&key)
+                .read()
+                .unwrap_or_else(|| env::panic_str(message!("SLOT5", "Inconsistent SlotMap state")));
+            (key, value)
+        })
+    }
+}
+
+/// A [`Slot`] namespace that keeps track of the child slots created in it,
+/// so that they can be listed, and all be removed later.
+///
+/// NEAR storage cannot be scanned by prefix, so child slots must be created
+/// through [`TrackedNamespace::field`] or [`TrackedNamespace::field_ref`] to
+/// be listed by [`TrackedNamespace::keys`] or removed by
+/// [`TrackedNamespace::remove_all`]. Tracking a child costs an additional
+/// few storage entries, so this is best suited to namespaces with a bounded
+/// number of children, which [`TrackedNamespace::with_capacity`] enforces.
+///
+/// # Ordering
+///
+/// Children are listed in insertion order. Each child keeps its position
+/// until it is removed: removing a child leaves a tombstone in its position
+/// instead of moving another child into it, so paging through the children
+/// with [`TrackedNamespace::keys`] does not skip or repeat any child, even
+/// if children are removed between pages. Pages may therefore contain fewer
+/// than `limit` children.
+///
+/// Tombstones are cleared when they reach the end of the list (e.g. after
+/// [`TrackedNamespace::remove_all`]), and otherwise take up a few bytes of
+/// storage each.
+///
+/// # Storage layout
+///
+/// With `c` the key of a child:
+///
+/// - `root + "c" + c`: the child slot.
+/// - `root + "k"`: the list of child keys, in insertion order (see
+///   [`SlotVec`]). Removed children are `None`.
+/// - `root + "p" + c`: the position of `c` in the list.
+/// - `root + "n"`: the number of tracked children.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TrackedNamespace {
+    root: Slot<()>,
+    capacity: Option<u32>,
+}
+
+impl TrackedNamespace {
+    /// Creates a tracked namespace under the given root slot
+    pub fn new(root: Slot<()>) -> Self {
+        Self {
+            root,
+            capacity: None,
+        }
+    }
+
+    /// Creates a tracked namespace under the given root slot that tracks at
+    /// most `capacity` children, so that a single call to
+    /// [`TrackedNamespace::remove_all`] with `capacity` as the limit removes
+    /// all of them. The capacity is not stored: every handle to the
+    /// namespace must be created with it.
+    pub fn with_capacity(root: Slot<()>, capacity: u32) -> Self {
+        Self {
+            root,
+            capacity: Some(capacity),
+        }
+    }
+
+    fn list(&self) -> SlotVec<Option<Vec<u8>>> {
+        SlotVec::new(self.root.ns(b"k"))
+    }
+
+    fn slot_child<U>(&self, key: Vec<u8>) -> Slot<U> {
+        self.root.ns(b"c").field(key)
+    }
+
+    fn slot_position(&self, key: Vec<u8>) -> Slot<u32> {
+        self.root.ns(b"p").field(key)
+    }
+
+    fn slot_len(&self) -> Slot<u32> {
+        self.root.field(b"n")
+    }
+
+    /// Number of tracked children
+    pub fn len(&self) -> u32 {
+        self.slot_len().read_or_default()
+    }
+
+    /// Returns `true` if there are no tracked children
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of positions in the list of children, including tombstones.
+    /// Page through [`TrackedNamespace::keys`] with offsets up to this.
+    pub fn positions(&self) -> u32 {
+        self.list().len()
+    }
+
+    /// Returns `true` if the child with the given key is tracked
+    pub fn contains(&self, key: impl IntoStorageKey) -> bool {
+        self.slot_position(key.into_storage_key()).exists()
+    }
+
+    /// Returns the child slot with the given key, tracking it for removal
+    ///
+    /// # Panics
+    ///
+    /// If the child is not tracked yet, and the namespace is at capacity.
+    pub fn field<U>(&mut self, key: impl IntoStorageKey) -> Slot<U> {
+        let key = key.into_storage_key();
+        let mut position = self.slot_position(key.clone());
+
+        if !position.exists() {
+            require!(
+                self.capacity.map_or(true, |capacity| self.len() < capacity),
+                message!("SLOT6", "TrackedNamespace capacity exceeded"),
+            );
+
+            position.write(&self.list().push(&Some(key.clone())));
+            self.slot_len().modify(|len| *len += 1);
+        }
+
+        self.slot_child(key)
+    }
+
+    /// Returns the child slot with the given key, serialized from a
+    /// reference, tracking it for removal
+    pub fn field_ref<U, K: BorshSerialize + ?Sized>(&mut self, key: &K) -> Slot<U> {
+        self.field(key.try_to_vec().unwrap())
+    }
+
+    /// Keys of the tracked children at positions `offset` to
+    /// `offset + limit`, in insertion order. Tombstones are skipped, so fewer
+    /// than `limit` keys may be returned even if there are more children.
+    pub fn keys(&self, offset: u32, limit: u32) -> Vec<Vec<u8>> {
+        self.list().iter(offset, limit).flatten().collect()
+    }
+
+    /// Keys and values of the tracked children at positions `offset` to
+    /// `offset + limit`, in insertion order. Children that have been tracked
+    /// but not written to are skipped.
+    pub fn entries<T: BorshDeserialize>(&self, offset: u32, limit: u32) -> Vec<(Vec<u8>, T)> {
+        self.keys(offset, limit)
+            .into_iter()
+            .filter_map(|key| {
+                let value = self.slot_child::<T>(key.clone()).read()?;
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// Pops tombstones off the end of the list
+    fn trim(&mut self) {
+        let mut list = self.list();
+
+        while let Some(None) = list.len().checked_sub(1).and_then(|i| list.get(i)) {
+            list.pop();
+        }
+    }
+
+    /// Removes the child with the given key from storage and stops tracking
+    /// it. Other children keep their positions. Returns `true` if the child
+    /// was tracked.
+    pub fn remove(&mut self, key: impl IntoStorageKey) -> bool {
+        let key = key.into_storage_key();
+        let position = match self.slot_position(key.clone()).take() {
+            Some(position) => position,
+            None => return false,
+        };
+
+        self.slot_child::<()>(key).remove();
+        self.list().slot_element(position).write(&None);
+        self.slot_len().modify(|len| *len -= 1);
+        self.trim();
+
+        true
+    }
+
+    /// Removes at most `limit` tracked children from storage, returning the
+    /// number of children removed. Call repeatedly until it returns 0 (or
+    /// [`TrackedNamespace::is_empty`]) to remove all children.
+    pub fn remove_all(&mut self, limit: u32) -> u32 {
+        let mut list = self.list();
+        let mut removed = 0;
+
+        // Remove from the end of the list, so no positions have to change
+        while removed < limit {
+            let key = match list.pop() {
+                Some(Some(key)) => key,
+                // The list never ends with a tombstone
+                _ => break,
+            };
+
+            self.slot_child::<()>(key.clone()).remove();
+            self.slot_position(key).remove();
+            removed += 1;
+
+            self.trim();
+        }
+
+        self.slot_len().modify(|len| *len -= removed);
+
+        removed
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Slot;
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        test_utils::VMContextBuilder,
+        testing_env, AccountId, BorshStorageKey, IntoStorageKey,
+    };
+
+    use super::{
+        assert_no_prefix_collisions, find_prefix_collision, migrate_prefix, migrate_prefix_typed,
+        CachedSlot, Slot, SlotMap, SlotTransaction, SlotVec, TrackedNamespace, HASHED_KEY_LEN,
+    };
+    use crate::{migrate::MigrationProgress, DefaultStorageKey};
+
+    #[derive(BorshSerialize, BorshStorageKey)]
+    enum OwnedKey {
+        Account(AccountId),
+    }
+
+    #[derive(BorshSerialize)]
+    enum BorrowedKey<'a> {
+        Account(&'a AccountId),
+    }
+
+    #[test]
+    fn field_ref() {
+        let root = Slot::root(b"r");
+        let account_id: AccountId = "alice.near".parse().unwrap();
+
+        let owned = root.field::<u128>(OwnedKey::Account(account_id.clone()));
+        let borrowed = root.field_ref::<u128, _>(&BorrowedKey::Account(&account_id));
+
+        assert_eq!(owned.key, borrowed.key);
+    }
+
+    #[test]
+    fn field_hashed() {
+        let root = Slot::root(b"r");
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let a = root.field_hashed::<u128, _>(&BorrowedKey::Account(&alice));
+        let b = root.field_hashed::<u128, _>(&BorrowedKey::Account(&bob));
+
+        assert_ne!(a.key, b.key);
+        assert_eq!(a.key.len(), 1 + HASHED_KEY_LEN);
+        assert!(a.key.starts_with(b"r"));
+        assert_eq!(
+            a.key,
+            root.field_hashed::<u128, _>(&BorrowedKey::Account(&alice))
+                .key,
+        );
+
+        let short = root.field_hashed_len::<u128, _>(&BorrowedKey::Account(&alice), 4);
+        assert_eq!(short.key, a.key[..5]);
+    }
+
+    #[test]
+    #[should_panic = "Hashed key length must be between 1 and 32"]
+    fn field_hashed_len_too_long() {
+        Slot::root(b"r").field_hashed_len::<u128, _>(&0u8, 33);
+    }
+
+    #[test]
+    fn transaction() {
+        let mut a = Slot::<u32>::new(b"a");
+        let b = Slot::<u32>::new(b"b");
+        let mut c = Slot::<u32>::new(b"c");
+        a.write(&1);
+        c.write(&3);
+
+        let mut transaction = SlotTransaction::new();
+        assert_eq!(transaction.read(&a), Some(1));
+        assert!(transaction.is_empty());
+
+        for i in 0..10 {
+            transaction.write(&a, &i);
+            transaction.write(&b, &(i * 2));
+        }
+        transaction.remove(&c);
+
+        // Staged values are visible to the transaction only
+        assert_eq!(transaction.read(&a), Some(9));
+        assert_eq!(transaction.read(&c), None);
+        assert_eq!(a.read(), Some(1));
+        assert_eq!(b.read(), None);
+        assert_eq!(c.read(), Some(3));
+
+        assert_eq!(transaction.len(), 3);
+        assert_eq!(transaction.commit(), 3);
+
+        assert_eq!(a.read(), Some(9));
+        assert_eq!(b.read(), Some(18));
+        assert!(!c.exists());
+    }
+
+    #[test]
+    fn transaction_discarded() {
+        let slot = Slot::<u32>::new(b"d");
+
+        {
+            let mut transaction = SlotTransaction::new();
+            transaction.write(&slot, &1);
+        }
+
+        assert!(!slot.exists());
+    }
+
+    #[test]
+    fn read_or_default() {
+        let mut slot = Slot::<u32>::new(b"o");
+        assert_eq!(slot.read_or_default(), 0);
+        assert_eq!(slot.read_or(7), 7);
+
+        slot.write(&3);
+        assert_eq!(slot.read_or_default(), 3);
+        assert_eq!(slot.read_or(7), 3);
+    }
+
+    #[test]
+    fn modify() {
+        let mut slot = Slot::<u32>::new(b"m");
+
+        assert_eq!(slot.modify(|v| *v += 2), 2);
+        assert_eq!(slot.read(), Some(2));
+
+        assert_eq!(slot.modify(|v| *v *= 5), 10);
+        assert_eq!(slot.read(), Some(10));
+    }
+
+    #[test]
+    fn modify_missing_unchanged() {
+        let mut slot = Slot::<u32>::new(b"m");
+
+        assert_eq!(slot.modify(|_| {}), 0);
+        assert!(!slot.exists());
+
+        // Setting the default is not a change either
+        assert_eq!(slot.modify(|v| *v = 0), 0);
+        assert!(!slot.exists());
+    }
+
+    #[test]
+    fn modify_unchanged_skips_write() {
+        let mut slot = Slot::<u32>::new(b"m");
+        slot.write(&1);
+
+        let gas = || near_sdk::env::used_gas().0;
+
+        let before = gas();
+        slot.modify(|_| {});
+        let unchanged = gas() - before;
+
+        let before = gas();
+        slot.modify(|v| *v += 1);
+        let changed = gas() - before;
+
+        assert!(unchanged < changed);
+        assert_eq!(slot.read(), Some(2));
+    }
+
+    #[test]
+    fn default_storage_keys_do_not_collide() {
+        let keys = DefaultStorageKey::ALL.map(|k| (k.is_namespace(), k.into_storage_key()));
+
+        for (i, (a_is_namespace, a)) in keys.iter().enumerate() {
+            for (b_is_namespace, b) in &keys[i + 1..] {
+                // A single slot only collides with a namespace that prefixes
+                // it, or with an equal key.
+                let collides = a == b
+                    || (*a_is_namespace && b.starts_with(a))
+                    || (*b_is_namespace && a.starts_with(b));
+
+                assert!(!collides, "{a:?} collides with {b:?}");
+            }
+        }
+
+        let namespaces = keys
+            .iter()
+            .filter(|(is_namespace, _)| *is_namespace)
+            .map(|(_, key)| &key[..])
+            .collect::<Vec<_>>();
+
+        assert_no_prefix_collisions(&namespaces);
+    }
+
+    #[test]
+    fn prefix_collision() {
+        assert_eq!(find_prefix_collision(&[b"~a", b"~b", b"~c"]), None);
+        assert_eq!(
+            find_prefix_collision(&[b"~a", b"~bc", b"~b"]),
+            Some((&b"~bc"[..], &b"~b"[..])),
+        );
+        assert_eq!(
+            find_prefix_collision(&[b"~a", b"~a"]),
+            Some((&b"~a"[..], &b"~a"[..])),
+        );
+    }
+
+    #[test]
+    #[should_panic = "Storage key prefix collision"]
+    fn assert_prefix_collision() {
+        let owner = DefaultStorageKey::Owner.into_storage_key();
+
+        // User-specified key that is prefixed by the owner component's key
+        assert_no_prefix_collisions(&[b"~my_data", &owner, b"~owner"]);
+    }
+
+    #[cfg(feature = "storage-key-check")]
+    #[test]
+    fn longest_key() {
+        use super::check;
+
+        check::reset_longest_key();
+        assert_eq!(check::longest_key(), None);
+
+        let root = Slot::root(b"r");
+        root.field::<u32>(b"long".to_vec()).write(&1);
+        root.field::<u32>(b"s".to_vec()).read();
+
+        assert_eq!(check::longest_key(), Some(b"rlong".to_vec()));
+
+        check::reset_longest_key();
+        assert_eq!(check::longest_key(), None);
+    }
+
+    #[cfg(feature = "storage-key-check")]
+    #[test]
+    #[should_panic = "Storage key prefix collision"]
+    fn root_collision() {
+        Slot::root(DefaultStorageKey::Owner);
+        Slot::root(DefaultStorageKey::Owner);
+        Slot::root(b"~owner");
+    }
+
+    #[test]
+    fn migrate_prefix_paginated() {
+        let old_root = Slot::root(&b"old"[..]);
+        let new_root = Slot::root(&b"new"[..]);
+        let keys = (0u32..30)
+            .map(|i| i.try_to_vec().unwrap())
+            .collect::<Vec<_>>();
+
+        for (i, key) in keys.iter().enumerate() {
+            old_root.field::<u32>(key.clone()).write(&(i as u32));
+        }
+
+        let progress = migrate_prefix_typed(&old_root, &new_root, &keys, |v: u32| v as u64 * 2, 20);
+        assert_eq!(
+            progress,
+            MigrationProgress {
+                done: false,
+                items_processed: 20,
+            },
+        );
+
+        let progress = migrate_prefix_typed(
+            &old_root,
+            &new_root,
+            &keys[progress.items_processed as usize..],
+            |v: u32| v as u64 * 2,
+            20,
+        );
+        assert_eq!(
+            progress,
+            MigrationProgress {
+                done: true,
+                items_processed: 10,
+            },
+        );
+
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!old_root.field::<u32>(key.clone()).exists());
+            assert_eq!(
+                new_root.field::<u64>(key.clone()).read(),
+                Some(i as u64 * 2),
+            );
+        }
+    }
+
+    #[test]
+    fn migrate_prefix_raw() {
+        let old_root = Slot::root(&b"old"[..]);
+        let new_root = Slot::root(&b"new"[..]);
+        let keys = vec![b"a".to_vec(), b"missing".to_vec(), b"b".to_vec()];
+
+        old_root.field::<()>(b"a".to_vec()).write_raw(b"1");
+        old_root.field::<()>(b"b".to_vec()).write_raw(b"2");
+
+        let progress = migrate_prefix(
+            &old_root,
+            &new_root,
+            &keys,
+            |mut v| {
+                v.push(b'!');
+                v
+            },
+            10,
+        );
+
+        assert!(progress.done);
+        assert_eq!(progress.items_processed, 3);
+        assert_eq!(
+            new_root.field::<()>(b"a".to_vec()).read_raw(),
+            Some(b"1!".to_vec())
+        );
+        assert_eq!(
+            new_root.field::<()>(b"b".to_vec()).read_raw(),
+            Some(b"2!".to_vec())
+        );
+        assert!(!new_root.field::<()>(b"missing".to_vec()).exists());
+        assert!(!old_root.field::<()>(b"a".to_vec()).exists());
+    }
+
+    #[test]
+    fn take_raw() {
+        let mut slot = Slot::<u32>::new(b"t");
+        assert_eq!(slot.take_raw(), None);
+
+        slot.write(&1);
+        assert_eq!(slot.take_raw(), Some(vec![1, 0, 0, 0]));
+        assert!(!slot.exists());
+    }
+
+    #[test]
+    fn swap_with() {
+        let mut a = Slot::<u32>::new(b"a");
+        let mut b = Slot::<u32>::new(b"b");
+
+        // Both absent
+        a.swap_with(&mut b);
+        assert_eq!((a.read(), b.read()), (None, None));
+
+        // Only one present
+        a.write(&1);
+        a.swap_with(&mut b);
+        assert_eq!((a.read(), b.read()), (None, Some(1)));
+        a.swap_with(&mut b);
+        assert_eq!((a.read(), b.read()), (Some(1), None));
+
+        // Both present
+        b.write(&2);
+        a.swap_with(&mut b);
+        assert_eq!((a.read(), b.read()), (Some(2), Some(1)));
+    }
+
+    #[test]
+    fn move_to() {
+        let mut a = Slot::<u32>::new(b"a");
+        let mut b = Slot::<u32>::new(b"b");
+
+        // Source absent, destination present
+        b.write(&2);
+        assert!(!a.clone().move_to(b.clone()));
+        assert_eq!((a.read(), b.read()), (None, None));
+
+        // Source present, destination absent
+        a.write(&1);
+        assert!(a.clone().move_to(b.clone()));
+        assert_eq!((a.read(), b.read()), (None, Some(1)));
+
+        // Both present
+        a.write(&3);
+        assert!(a.clone().move_to(b.clone()));
+        assert_eq!((a.read(), b.read()), (None, Some(3)));
+
+        // Both absent
+        b.remove();
+        assert!(!a.clone().move_to(b.clone()));
+        assert_eq!((a.read(), b.read()), (None, None));
+    }
+
+    #[test]
+    fn tracked_namespace_remove_all() {
+        let mut namespace = TrackedNamespace::new(Slot::root(b"n"));
+
+        let mut children = (0u32..10)
+            .map(|i| namespace.field_ref::<u32, _>(&i))
+            .collect::<Vec<_>>();
+
+        for (i, child) in children.iter_mut().enumerate() {
+            child.write(&(i as u32));
+        }
+
+        // Tracking the same child again has no effect
+        namespace.field_ref::<u32, _>(&0u32);
+        assert_eq!(namespace.len(), 10);
+
+        assert_eq!(namespace.remove_all(6), 6);
+        assert_eq!(namespace.len(), 4);
+        assert_eq!(children.iter().filter(|child| child.exists()).count(), 4);
+
+        assert_eq!(namespace.remove_all(6), 4);
+        assert!(namespace.is_empty());
+        assert!(children.iter().all(|child| !child.exists()));
+
+        assert_eq!(namespace.remove_all(6), 0);
+    }
+
+    #[test]
+    fn tracked_namespace_capacity() {
+        let mut namespace = TrackedNamespace::with_capacity(Slot::root(b"n"), 2);
+
+        namespace.field_ref::<u32, _>(&0u32).write(&0);
+        namespace.field_ref::<u32, _>(&1u32).write(&1);
+        // Children that are already tracked do not count again
+        namespace.field_ref::<u32, _>(&1u32).write(&2);
+
+        assert!(namespace.remove(0u32.try_to_vec().unwrap()));
+        namespace.field_ref::<u32, _>(&2u32).write(&2);
+
+        assert_eq!(namespace.remove_all(2), 2);
+        assert!(namespace.is_empty());
+    }
+
+    #[test]
+    #[should_panic = "TrackedNamespace capacity exceeded"]
+    fn tracked_namespace_over_capacity() {
+        let mut namespace = TrackedNamespace::with_capacity(Slot::root(b"n"), 2);
+
+        for i in 0u32..3 {
+            namespace.field_ref::<u32, _>(&i);
+        }
+    }
+
+    #[test]
+    fn tracked_namespace_paging() {
+        let mut namespace = TrackedNamespace::new(Slot::root(b"n"));
+
+        for i in 0u32..25 {
+            namespace.field_ref::<u32, _>(&i).write(&(i * 10));
+        }
+
+        // Tracked, but never written
+        namespace.field_ref::<u32, _>(&25u32);
+
+        assert_eq!(namespace.len(), 26);
+
+        let key = |i: u32| i.try_to_vec().unwrap();
+        let mut seen = vec![];
+        let mut offset = 0;
+
+        while offset < namespace.positions() {
+            for (k, value) in namespace.entries::<u32>(offset, 7) {
+                let i = u32::try_from_slice(&k).unwrap();
+                assert_eq!(value, i * 10);
+                seen.push(i);
+            }
+
+            // Remove a child that was already listed, and one that was not
+            let remove = offset / 7;
+            assert!(namespace.remove(key(remove)));
+            assert!(namespace.remove(key(24 - remove)));
+            assert!(!namespace.remove(key(24 - remove)));
+
+            offset += 7;
+        }
+
+        // Children removed after being listed were still listed once; those
+        // removed before were not listed. Nothing was skipped or repeated.
+        assert_eq!(seen, (0..22).collect::<Vec<_>>());
+        assert_eq!(namespace.len(), 26 - 8);
+
+        // Insertion order is kept after removals, and removed children leave
+        // no gaps at the end
+        let remaining = (4..=20).map(key).chain([key(25)]).collect::<Vec<_>>();
+        assert_eq!(namespace.keys(0, 100), remaining);
+        assert!(!namespace.contains(key(0)));
+        assert!(namespace.contains(key(4)));
+
+        assert_eq!(namespace.remove_all(100), 18);
+        assert!(namespace.is_empty());
+        assert_eq!(namespace.positions(), 0);
+    }
+
+    #[test]
+    fn cached_slot() {
+        let mut slot = Slot::<u32>::new(b"c");
+        slot.write(&1);
+
+        let mut cached = CachedSlot::new(slot.clone());
+        assert_eq!(cached.get(), Some(&1));
+        assert!(!cached.is_dirty());
+
+        // Changes stay in memory until flushed
+        slot.write(&5);
+        assert_eq!(cached.get(), Some(&1));
+        *cached.get_mut().unwrap() += 1;
+        assert_eq!(cached.get(), Some(&2));
+        assert_eq!(slot.read(), Some(5));
+
+        assert!(cached.flush());
+        assert!(!cached.flush());
+        assert_eq!(slot.read(), Some(2));
+
+        // Flushed on drop
+        cached.set(None);
+        drop(cached);
+        assert_eq!(slot.read(), None);
+    }
 
     #[test]
     fn partialeq() {
@@ -163,4 +1659,135 @@ mod tests {
         let b = Slot::<u32>::new(b"b");
         assert_ne!(a1, b);
     }
+
+    /// Deterministic xorshift generator, so failures are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u32) -> u32 {
+            (self.next() % n as u64) as u32
+        }
+    }
+
+    #[test]
+    fn slot_vec_matches_vec() {
+        for seed in 1..=20 {
+            // Each run gets a fresh gas budget
+            testing_env!(VMContextBuilder::new().build());
+            let mut rng = Rng(seed);
+            let mut slot_vec = SlotVec::<u64>::new(Slot::root(format!("v{seed}").into_bytes()));
+            let mut model = Vec::<u64>::new();
+
+            for _ in 0..200 {
+                match rng.below(4) {
+                    0 | 1 => {
+                        let value = rng.next();
+                        assert_eq!(slot_vec.push(&value), model.len() as u32);
+                        model.push(value);
+                    }
+                    2 => {
+                        assert_eq!(slot_vec.pop(), model.pop());
+                    }
+                    _ if !model.is_empty() => {
+                        let index = rng.below(model.len() as u32);
+                        assert_eq!(
+                            slot_vec.swap_remove(index),
+                            model.swap_remove(index as usize),
+                        );
+                    }
+                    _ => {}
+                }
+
+                assert_eq!(slot_vec.len(), model.len() as u32);
+
+                let index = rng.below(model.len() as u32 + 2);
+                assert_eq!(slot_vec.get(index), model.get(index as usize).copied());
+
+                let offset = rng.below(model.len() as u32 + 2);
+                let limit = rng.below(10);
+                assert_eq!(
+                    slot_vec.iter(offset, limit).collect::<Vec<_>>(),
+                    model
+                        .iter()
+                        .skip(offset as usize)
+                        .take(limit as usize)
+                        .copied()
+                        .collect::<Vec<_>>(),
+                );
+            }
+
+            // Nothing is left behind after removing every element
+            while slot_vec.pop().is_some() {}
+            assert!(!slot_vec.slot_len().exists());
+            assert!(!slot_vec.slot_element(0).exists());
+        }
+    }
+
+    #[test]
+    #[should_panic = "SlotVec index out of bounds"]
+    fn slot_vec_swap_remove_out_of_bounds() {
+        let mut slot_vec = SlotVec::<u32>::new(Slot::root(b"v"));
+        slot_vec.push(&1);
+        slot_vec.swap_remove(1);
+    }
+
+    #[test]
+    fn slot_map_matches_model() {
+        for seed in 1..=20 {
+            // Each run gets a fresh gas budget
+            testing_env!(VMContextBuilder::new().build());
+            let mut rng = Rng(seed);
+            let mut slot_map = SlotMap::<u8, u64>::new(Slot::root(format!("m{seed}").into_bytes()));
+            // Entries in iteration order, removed like `Vec::swap_remove`
+            let mut model = Vec::<(u8, u64)>::new();
+
+            for _ in 0..200 {
+                let key = rng.below(16) as u8;
+                let position = model.iter().position(|(k, _)| *k == key);
+
+                if rng.below(3) == 0 {
+                    assert_eq!(
+                        slot_map.remove(&key),
+                        position.map(|position| model.swap_remove(position).1),
+                    );
+                } else {
+                    let value = rng.next();
+                    let previous = match position {
+                        Some(position) => Some(std::mem::replace(&mut model[position].1, value)),
+                        None => {
+                            model.push((key, value));
+                            None
+                        }
+                    };
+                    assert_eq!(slot_map.insert(&key, &value), previous);
+                }
+
+                assert_eq!(slot_map.len(), model.len() as u32);
+
+                let key = rng.below(16) as u8;
+                let expected = model.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+                assert_eq!(slot_map.get(&key), expected);
+                assert_eq!(slot_map.contains(&key), expected.is_some());
+
+                let offset = rng.below(model.len() as u32 + 2);
+                let limit = rng.below(10);
+                assert_eq!(
+                    slot_map.iter(offset, limit).collect::<Vec<_>>(),
+                    model
+                        .iter()
+                        .skip(offset as usize)
+                        .take(limit as usize)
+                        .copied()
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,150 @@
+//! Serialization strategies for the values of a [`Slot`](super::Slot)
+//!
+//! The codec is a type parameter of the slot, so two slots that serialize
+//! the same key differently have different types:
+//!
+//! ```compile_fail
+//! use near_sdk_contract_tools::slot::{codec::JsonCodec, Slot};
+//!
+//! let mut borsh = Slot::<u32>::new(b"v");
+//! let mut json = Slot::<u32>::new(b"v").with_codec::<JsonCodec>();
+//!
+//! // Slots with different codecs cannot exchange values
+//! borsh.swap_with(&mut json);
+//! ```
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Serializes values to be written to a slot
+pub trait Encode<T: ?Sized> {
+    /// Serializes a value
+    fn encode(value: &T) -> Vec<u8>;
+}
+
+/// Deserializes values read from a slot
+pub trait Decode<T> {
+    /// Deserializes a value. Panics if the bytes are not a valid value.
+    fn decode(bytes: &[u8]) -> T;
+}
+
+/// Serializes values with Borsh. The default codec.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BorshCodec;
+
+impl<T: BorshSerialize + ?Sized> Encode<T> for BorshCodec {
+    fn encode(value: &T) -> Vec<u8> {
+        value.try_to_vec().unwrap()
+    }
+}
+
+impl<T: BorshDeserialize> Decode<T> for BorshCodec {
+    fn decode(bytes: &[u8]) -> T {
+        T::try_from_slice(bytes).unwrap()
+    }
+}
+
+/// Serializes values as JSON, so they can be read by generic off-chain
+/// tooling. Larger and more expensive to (de)serialize than Borsh.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonCodec;
+
+impl<T: Serialize + ?Sized> Encode<T> for JsonCodec {
+    fn encode(value: &T) -> Vec<u8> {
+        serde_json::to_vec(value).unwrap_or_else(|e| env::panic_str(&e.to_string()))
+    }
+}
+
+impl<T: DeserializeOwned> Decode<T> for JsonCodec {
+    fn decode(bytes: &[u8]) -> T {
+        serde_json::from_slice(bytes).unwrap_or_else(|e| env::panic_str(&e.to_string()))
+    }
+}
+
+/// Stores bytes as-is, without a length prefix. Useful for values that are
+/// already serialized, like contract code.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RawCodec;
+
+impl Encode<Vec<u8>> for RawCodec {
+    fn encode(value: &Vec<u8>) -> Vec<u8> {
+        value.clone()
+    }
+}
+
+impl Encode<[u8]> for RawCodec {
+    fn encode(value: &[u8]) -> Vec<u8> {
+        value.to_vec()
+    }
+}
+
+impl Decode<Vec<u8>> for RawCodec {
+    fn decode(bytes: &[u8]) -> Vec<u8> {
+        bytes.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{BorshCodec, JsonCodec, RawCodec};
+    use crate::slot::Slot;
+
+    #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+    struct Config {
+        name: String,
+        limit: u32,
+    }
+
+    #[test]
+    fn borsh_round_trip() {
+        let mut slot = Slot::<(u8, String)>::new(b"b");
+        slot.write(&(1, "one".to_string()));
+
+        assert_eq!(slot.read_raw(), Some(vec![1, 3, 0, 0, 0, b'o', b'n', b'e']));
+        assert_eq!(slot.read(), Some((1, "one".to_string())));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut slot = Slot::<Config>::new(b"j").with_codec::<JsonCodec>();
+        let config = Config {
+            name: "config".to_string(),
+            limit: 10,
+        };
+
+        slot.write(&config);
+
+        assert_eq!(
+            slot.read_raw(),
+            Some(br#"{"name":"config","limit":10}"#.to_vec()),
+        );
+        assert_eq!(slot.read(), Some(config));
+        assert_eq!(slot.modify(|c| c.limit += 1).limit, 11);
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let mut slot = Slot::<Vec<u8>>::new(b"r").with_codec::<RawCodec>();
+        slot.write(&vec![0, 1, 2]);
+
+        assert_eq!(slot.read_raw(), Some(vec![0, 1, 2]));
+        assert_eq!(slot.take(), Some(vec![0, 1, 2]));
+        assert!(!slot.exists());
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_codec() {
+        let mut slot = Slot::<Config>::new(b"m").with_codec::<JsonCodec>();
+        slot.write(&Config {
+            name: "config".to_string(),
+            limit: 10,
+        });
+
+        slot.with_codec::<BorshCodec>().transmute::<u64>().read();
+    }
+}
@@ -0,0 +1,670 @@
+//! Payment splitter: NEAR and NEP-141 tokens received by the contract are
+//! claimable by a fixed set of payees, pro rata to their shares.
+//!
+//! Shares are in basis points, and sum to [`TOTAL_SHARES`]. The payees and
+//! their shares are set once, with [`Splitter::init_payees`]. Each asset
+//! (NEAR, and each token contract) is accounted for separately: the amount
+//! owed to a payee is its share of everything the contract received of the
+//! asset, minus what was already released to it. Anyone may release the
+//! amount owed to a payee; it is always sent to the payee. A release is
+//! recorded when it is sent, and rolled back by the callback
+//! [`Splitter::resolve_release`] if the transfer fails (e.g. the payee is not
+//! registered with the token contract), so that it may be released again.
+//!
+//! Shares are rounded down, and the rounding dust of every payee is owed to
+//! the last payee, so the amounts owed always add up to the amount received.
+//! Since dust accumulates unevenly, the amount owed to the last payee may
+//! momentarily decrease after a deposit; releases are therefore also capped
+//! by the amount not yet released, so that the releases never exceed the
+//! deposits.
+//!
+//! NEAR is received from a payable method, with
+//! [`Splitter::deposit_attached`], and tokens from `ft_on_transfer`, with
+//! [`Splitter::deposit_ft_transfer`].
+//!
+//! ```
+//! use near_sdk::{
+//!     json_types::U128, near_bindgen, AccountId, Promise, PromiseOrValue,
+//! };
+//! use near_sdk_contract_tools::{
+//!     splitter::{Payee, SplitAsset, Splitter},
+//!     standard::nep141::Nep141Receiver,
+//! };
+//!
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl Splitter for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     #[init]
+//!     pub fn new(payees: Vec<Payee>) -> Self {
+//!         let mut contract = Self {};
+//!         contract.init_payees(payees);
+//!         contract
+//!     }
+//!
+//!     #[payable]
+//!     pub fn deposit(&mut self) {
+//!         self.deposit_attached();
+//!     }
+//!
+//!     pub fn release(&mut self, payee_id: AccountId) -> Promise {
+//!         Splitter::release(self, SplitAsset::Near, &payee_id)
+//!     }
+//!
+//!     #[private]
+//!     pub fn split_resolve_release(
+//!         &mut self,
+//!         asset: SplitAsset,
+//!         payee_id: AccountId,
+//!         amount: U128,
+//!     ) {
+//!         self.resolve_release(asset, payee_id, amount)
+//!     }
+//! }
+//!
+//! #[near_bindgen]
+//! impl Nep141Receiver for Contract {
+//!     fn ft_on_transfer(
+//!         &mut self,
+//!         sender_id: AccountId,
+//!         amount: U128,
+//!         msg: String,
+//!     ) -> PromiseOrValue<U128> {
+//!         self.deposit_ft_transfer(amount)
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The splitter root storage slot is not used or modified. The default key is `~sp`.
+//! * (UB) The contract holds the deposited assets until they are released.
+//! * (UB) The method named [`Splitter::resolve_release_method_name`] calls
+//!   [`Splitter::resolve_release`].
+//! * (ERR) The payees are set once, are distinct, and their shares are
+//!   positive and sum to [`TOTAL_SHARES`].
+//! * (ERR) The releases of an asset never exceed its deposits.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    require,
+    serde_json::json,
+    AccountId, BorshStorageKey, Gas, Promise, PromiseOrValue, PromiseResult,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slot::Slot,
+    standard::{nep141::ext_nep141, nep297::Event},
+    DefaultStorageKey,
+};
+
+const ALREADY_INITIALIZED_FAIL_MESSAGE: &str =
+    message!("SPL1", "Payees can only be initialized once");
+const INVALID_PAYEES_FAIL_MESSAGE: &str = message!("SPL2", "Invalid payees or shares");
+const NOT_A_PAYEE_FAIL_MESSAGE: &str = message!("SPL3", "Not a payee");
+const NOTHING_TO_RELEASE_FAIL_MESSAGE: &str = message!("SPL4", "Nothing to release");
+const CALLBACK_ONLY_FAIL_MESSAGE: &str = message!("SPL5", "Callback only");
+
+/// Sum of the shares of all payees, in basis points
+pub const TOTAL_SHARES: u16 = 10_000;
+
+/// Gas attached to the `ft_transfer` that releases tokens
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+/// Default of [`Splitter::resolve_release_method_name`]
+pub const DEFAULT_RESOLVE_RELEASE_METHOD_NAME: &str = "split_resolve_release";
+/// Gas attached to the callback [`Splitter::resolve_release`]
+pub const GAS_FOR_RESOLVE_RELEASE: Gas = Gas(10_000_000_000_000);
+
+/// An asset received by the splitter
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SplitAsset {
+    /// NEAR, attached to calls to the contract
+    Near,
+    /// NEP-141 tokens, received with `ft_transfer_call`
+    FungibleToken {
+        /// The token contract
+        contract_id: AccountId,
+    },
+}
+
+/// A payee and its share
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct Payee {
+    /// Account receiving the releases
+    pub account_id: AccountId,
+    /// Share of the deposits, in basis points
+    pub share: u16,
+}
+
+/// Events emitted when assets are deposited and released
+#[event(
+    standard = "x-splitter",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum SplitterEvent {
+    /// Emitted when the contract receives an asset
+    Deposit {
+        /// The asset
+        asset: SplitAsset,
+        /// Amount received
+        amount: U128,
+    },
+    /// Emitted when an asset is released to a payee
+    Release {
+        /// The asset
+        asset: SplitAsset,
+        /// Account receiving the asset
+        payee_id: AccountId,
+        /// Amount released
+        amount: U128,
+    },
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Payees,
+    TotalReceived(&'a SplitAsset),
+    TotalReleased(&'a SplitAsset),
+    Released(&'a SplitAsset, &'a AccountId),
+}
+
+/// `share` basis points of `total`, rounded down, without overflow: the
+/// remainder is less than [`TOTAL_SHARES`], so its product with `share` fits
+fn share_of(total: u128, share: u16) -> u128 {
+    let (share, shares) = (share as u128, TOTAL_SHARES as u128);
+    total / shares * share + total % shares * share / shares
+}
+
+/// Amount of `total_received` owed to the payee at `index`, including what
+/// was already released. The last payee is owed the rounding dust.
+fn entitlement(payees: &[Payee], index: usize, total_received: u128) -> u128 {
+    if index + 1 == payees.len() {
+        total_received
+            - payees[..index]
+                .iter()
+                .map(|payee| share_of(total_received, payee.share))
+                .sum::<u128>()
+    } else {
+        share_of(total_received, payees[index].share)
+    }
+}
+
+/// Splits received NEAR and NEP-141 tokens between payees
+pub trait Splitter {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Splitter)
+    }
+
+    /// Storage slot of the payees
+    fn slot_payees() -> Slot<Vec<Payee>> {
+        <Self as Splitter>::root().field(StorageKey::Payees)
+    }
+
+    /// Storage slot of the total amount received of an asset
+    fn slot_total_received(asset: &SplitAsset) -> Slot<u128> {
+        <Self as Splitter>::root().field(StorageKey::TotalReceived(asset))
+    }
+
+    /// Storage slot of the total amount released of an asset
+    fn slot_total_released(asset: &SplitAsset) -> Slot<u128> {
+        <Self as Splitter>::root().field(StorageKey::TotalReleased(asset))
+    }
+
+    /// Storage slot of the amount of an asset released to a payee
+    fn slot_released(asset: &SplitAsset, payee_id: &AccountId) -> Slot<u128> {
+        <Self as Splitter>::root().field(StorageKey::Released(asset, payee_id))
+    }
+
+    /// Name of the private method of the contract that calls
+    /// [`Splitter::resolve_release`]. [`DEFAULT_RESOLVE_RELEASE_METHOD_NAME`]
+    /// by default.
+    fn resolve_release_method_name() -> &'static str {
+        DEFAULT_RESOLVE_RELEASE_METHOD_NAME
+    }
+
+    /// Sets the payees and their shares. Must be called once, before any
+    /// release.
+    ///
+    /// # Panics
+    ///
+    /// If the payees are already set, if `payees` is empty or has
+    /// duplicates, or if the shares are not all positive, or do not sum to
+    /// [`TOTAL_SHARES`].
+    fn init_payees(&mut self, payees: Vec<Payee>) {
+        let mut slot = Self::slot_payees();
+        require!(!slot.exists(), ALREADY_INITIALIZED_FAIL_MESSAGE);
+
+        let valid = !payees.is_empty()
+            && payees.iter().all(|payee| payee.share > 0)
+            && payees.iter().map(|payee| payee.share as u32).sum::<u32>() == TOTAL_SHARES as u32
+            && payees.iter().enumerate().all(|(i, payee)| {
+                payees[..i]
+                    .iter()
+                    .all(|other| other.account_id != payee.account_id)
+            });
+        require!(valid, INVALID_PAYEES_FAIL_MESSAGE);
+
+        slot.write(&payees);
+    }
+
+    /// The payees and their shares. Empty before [`Splitter::init_payees`].
+    fn payees() -> Vec<Payee> {
+        Self::slot_payees().read().unwrap_or_default()
+    }
+
+    /// Total amount of `asset` received
+    fn total_received(asset: &SplitAsset) -> u128 {
+        Self::slot_total_received(asset).read().unwrap_or(0)
+    }
+
+    /// Total amount of `asset` released
+    fn total_released(asset: &SplitAsset) -> u128 {
+        Self::slot_total_released(asset).read().unwrap_or(0)
+    }
+
+    /// Amount of `asset` released to `payee_id`
+    fn released(asset: &SplitAsset, payee_id: &AccountId) -> u128 {
+        Self::slot_released(asset, payee_id).read().unwrap_or(0)
+    }
+
+    /// Amount of `asset` that may be released to `payee_id` now. 0 for
+    /// accounts that are not payees.
+    fn releasable(asset: &SplitAsset, payee_id: &AccountId) -> u128 {
+        let payees = Self::payees();
+        let index = match payees.iter().position(|p| &p.account_id == payee_id) {
+            Some(index) => index,
+            None => return 0,
+        };
+
+        let total_received = Self::total_received(asset);
+        let owed = entitlement(&payees, index, total_received)
+            .saturating_sub(Self::released(asset, payee_id));
+        let available = total_received - Self::total_released(asset);
+
+        owed.min(available)
+    }
+
+    /// Records that the contract received `amount` of `asset`. Emits a
+    /// [`SplitterEvent::Deposit`] event.
+    fn deposit(&mut self, asset: SplitAsset, amount: u128) {
+        let mut slot = Self::slot_total_received(&asset);
+        slot.write(&(slot.read().unwrap_or(0) + amount));
+
+        SplitterEvent::Deposit {
+            asset,
+            amount: amount.into(),
+        }
+        .emit();
+    }
+
+    /// Records the NEAR attached to the current call. For payable methods.
+    fn deposit_attached(&mut self) {
+        self.deposit(SplitAsset::Near, env::attached_deposit());
+    }
+
+    /// Records the tokens of an `ft_transfer_call`, for `ft_on_transfer`.
+    /// The predecessor is the token contract. Keeps all of the tokens.
+    fn deposit_ft_transfer(&mut self, amount: U128) -> PromiseOrValue<U128> {
+        self.deposit(
+            SplitAsset::FungibleToken {
+                contract_id: env::predecessor_account_id(),
+            },
+            amount.into(),
+        );
+
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Sends the amount of `asset` owed to `payee_id` (see
+    /// [`Splitter::releasable`]). Emits a [`SplitterEvent::Release`] event.
+    /// Returns the transfer, followed by [`Splitter::resolve_release`].
+    ///
+    /// # Panics
+    ///
+    /// If `payee_id` is not a payee, or if nothing may be released to it.
+    fn release(&mut self, asset: SplitAsset, payee_id: &AccountId) -> Promise {
+        require!(
+            Self::payees().iter().any(|p| &p.account_id == payee_id),
+            NOT_A_PAYEE_FAIL_MESSAGE,
+        );
+
+        let amount = Self::releasable(&asset, payee_id);
+        require!(amount > 0, NOTHING_TO_RELEASE_FAIL_MESSAGE);
+
+        let mut slot = Self::slot_released(&asset, payee_id);
+        slot.write(&(slot.read().unwrap_or(0) + amount));
+        let mut slot = Self::slot_total_released(&asset);
+        slot.write(&(slot.read().unwrap_or(0) + amount));
+
+        let transfer = match asset {
+            SplitAsset::Near => Promise::new(payee_id.clone()).transfer(amount),
+            SplitAsset::FungibleToken { ref contract_id } => ext_nep141::ext(contract_id.clone())
+                .with_attached_deposit(1)
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .ft_transfer(payee_id.clone(), amount.into(), Some("split".to_string())),
+        };
+
+        SplitterEvent::Release {
+            asset: asset.clone(),
+            payee_id: payee_id.clone(),
+            amount: amount.into(),
+        }
+        .emit();
+
+        transfer.then(
+            Promise::new(env::current_account_id()).function_call(
+                Self::resolve_release_method_name().to_string(),
+                json!({
+                    "asset": asset,
+                    "payee_id": payee_id,
+                    "amount": U128(amount),
+                })
+                .to_string()
+                .into_bytes(),
+                0,
+                GAS_FOR_RESOLVE_RELEASE,
+            ),
+        )
+    }
+
+    /// Rolls back the release of `amount` of `asset` to `payee_id` if its
+    /// transfer failed, from the result of the promise returned by
+    /// [`Splitter::release`]. For the private callback method named
+    /// [`Splitter::resolve_release_method_name`].
+    ///
+    /// # Panics
+    ///
+    /// If not called by the contract itself.
+    fn resolve_release(&mut self, asset: SplitAsset, payee_id: AccountId, amount: U128) {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            CALLBACK_ONLY_FAIL_MESSAGE,
+        );
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                let mut slot = Self::slot_released(&asset, &payee_id);
+                slot.write(&(slot.read().unwrap_or(0) - amount.0));
+                let mut slot = Self::slot_total_released(&asset);
+                slot.write(&(slot.read().unwrap_or(0) - amount.0));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{env, PromiseResult};
+    use proptest::prelude::*;
+
+    use super::{Payee, SplitAsset, Splitter};
+    use crate::testing::{account, TestEnv};
+
+    struct Contract;
+
+    impl Splitter for Contract {}
+
+    fn payees(shares: &[u16]) -> Vec<Payee> {
+        shares
+            .iter()
+            .enumerate()
+            .map(|(i, share)| Payee {
+                account_id: account(&format!("payee{i}")),
+                share: *share,
+            })
+            .collect()
+    }
+
+    fn setup(shares: &[u16]) -> (TestEnv, Contract) {
+        let env = TestEnv::new();
+        let mut contract = Contract;
+        contract.init_payees(payees(shares));
+        (env, contract)
+    }
+
+    #[test]
+    fn release_pro_rata() {
+        let (_env, mut contract) = setup(&[5_000, 3_000, 2_000]);
+
+        contract.deposit(SplitAsset::Near, 1_000);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        assert_eq!(
+            Contract::released(&SplitAsset::Near, &account("payee0")),
+            500
+        );
+
+        contract.deposit(SplitAsset::Near, 1_000);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        contract.release(SplitAsset::Near, &account("payee1"));
+        contract.release(SplitAsset::Near, &account("payee2"));
+
+        assert_eq!(
+            [0, 1, 2]
+                .map(|i| Contract::released(&SplitAsset::Near, &account(&format!("payee{i}")))),
+            [1_000, 600, 400],
+        );
+        assert_eq!(Contract::total_released(&SplitAsset::Near), 2_000);
+    }
+
+    #[test]
+    fn dust_to_last_payee() {
+        let (_env, mut contract) = setup(&[3_333, 3_333, 3_334]);
+
+        contract.deposit(SplitAsset::Near, 10);
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee0")),
+            3
+        );
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee1")),
+            3
+        );
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee2")),
+            4
+        );
+    }
+
+    #[test]
+    fn releases_capped_by_deposits() {
+        let (_env, mut contract) = setup(&[3_333, 3_333, 3_334]);
+
+        // The last payee is owed all of the first 3, as dust
+        contract.deposit(SplitAsset::Near, 3);
+        contract.release(SplitAsset::Near, &account("payee2"));
+        assert_eq!(Contract::released(&SplitAsset::Near, &account("payee2")), 3);
+
+        // Now owed 1 each, so the last payee was overpaid by 1
+        contract.deposit(SplitAsset::Near, 1);
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee2")),
+            0
+        );
+        contract.release(SplitAsset::Near, &account("payee0"));
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee1")),
+            0
+        );
+        assert_eq!(Contract::total_released(&SplitAsset::Near), 4);
+    }
+
+    #[test]
+    fn assets_are_separate() {
+        let (_env, mut contract) = setup(&[5_000, 5_000]);
+        let token = SplitAsset::FungibleToken {
+            contract_id: account("token"),
+        };
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.deposit(token.clone(), 10);
+
+        assert_eq!(Contract::releasable(&token, &account("payee0")), 5);
+        contract.release(token.clone(), &account("payee0"));
+        assert_eq!(Contract::releasable(&token, &account("payee0")), 0);
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee0")),
+            50
+        );
+    }
+
+    /// Resolves the release of `amount` of NEAR to payee0 with `result`
+    fn resolve_release(env: &mut TestEnv, result: PromiseResult, amount: u128) {
+        env.set_predecessor(&env::current_account_id())
+            .set_promise_results(vec![result]);
+        Contract.resolve_release(SplitAsset::Near, account("payee0"), amount.into());
+        env.set_promise_results(vec![]);
+    }
+
+    #[test]
+    fn failed_release_rolled_back() {
+        let (mut env, mut contract) = setup(&[5_000, 5_000]);
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        resolve_release(&mut env, PromiseResult::Failed, 50);
+
+        assert_eq!(Contract::released(&SplitAsset::Near, &account("payee0")), 0);
+        assert_eq!(Contract::total_released(&SplitAsset::Near), 0);
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee0")),
+            50
+        );
+    }
+
+    #[test]
+    fn successful_release_kept() {
+        let (mut env, mut contract) = setup(&[5_000, 5_000]);
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        resolve_release(&mut env, PromiseResult::Successful(vec![]), 50);
+
+        assert_eq!(
+            Contract::released(&SplitAsset::Near, &account("payee0")),
+            50
+        );
+        assert_eq!(
+            Contract::releasable(&SplitAsset::Near, &account("payee0")),
+            0
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback only")]
+    fn resolve_release_external() {
+        let (mut env, mut contract) = setup(&[5_000, 5_000]);
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        env.set_predecessor(&account("payee0"))
+            .set_promise_results(vec![PromiseResult::Failed]);
+        contract.resolve_release(SplitAsset::Near, account("payee0"), 50.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to release")]
+    fn nothing_to_release() {
+        let (_env, mut contract) = setup(&[5_000, 5_000]);
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.release(SplitAsset::Near, &account("payee0"));
+        contract.release(SplitAsset::Near, &account("payee0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not a payee")]
+    fn not_a_payee() {
+        let (_env, mut contract) = setup(&[5_000, 5_000]);
+
+        contract.deposit(SplitAsset::Near, 100);
+        contract.release(SplitAsset::Near, &account("alice"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payees or shares")]
+    fn shares_not_total() {
+        setup(&[5_000, 4_999]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payees or shares")]
+    fn duplicate_payees() {
+        let _env = TestEnv::new();
+        let mut payees = payees(&[5_000, 5_000]);
+        payees[1].account_id = payees[0].account_id.clone();
+
+        Contract.init_payees(payees);
+    }
+
+    #[test]
+    #[should_panic(expected = "Payees can only be initialized once")]
+    fn init_twice() {
+        let (_env, mut contract) = setup(&[10_000]);
+
+        contract.init_payees(payees(&[10_000]));
+    }
+
+    /// Amounts deposited, each followed by releases to some payees
+    fn deposits() -> impl Strategy<Value = Vec<(u128, Vec<usize>)>> {
+        prop::collection::vec(
+            (1..1_000u128, prop::collection::vec(0..3usize, 0..3)),
+            1..20,
+        )
+    }
+
+    proptest! {
+        /// Releases never exceed deposits, and releasing to every payee
+        /// releases everything.
+        #[test]
+        fn releases_never_exceed_deposits(deposits in deposits()) {
+            // Storage outlives test cases in the same thread
+            near_sdk::env::set_blockchain_interface(Default::default());
+
+            let mut env = TestEnv::new();
+            let near = SplitAsset::Near;
+            let payee = |i: usize| account(&format!("payee{i}"));
+            Contract.init_payees(payees(&[3_333, 3_333, 3_334]));
+
+            // Each release is a call of its own, with its own gas
+            let mut release = |i: usize| {
+                if Contract::releasable(&near, &payee(i)) > 0 {
+                    env.set_predecessor(&payee(i));
+                    Contract.release(near.clone(), &payee(i));
+                }
+            };
+
+            for (amount, releases) in deposits {
+                Contract.deposit(near.clone(), amount);
+                releases.into_iter().for_each(&mut release);
+
+                let released = (0..3).map(|i| Contract::released(&near, &payee(i))).sum::<u128>();
+                prop_assert_eq!(released, Contract::total_released(&near));
+                prop_assert!(released <= Contract::total_received(&near));
+            }
+
+            (0..3).for_each(release);
+            prop_assert_eq!(Contract::total_released(&near), Contract::total_received(&near));
+        }
+    }
+}
@@ -2,4 +2,7 @@
 
 pub mod nep141;
 pub mod nep148;
+pub mod nep245;
 pub mod nep297;
+pub mod nep330;
+pub mod nep413;
@@ -1,24 +1,51 @@
 //! NEP-141 fungible token core implementation
 //! <https://github.com/near/NEPs/blob/master/neps/nep-0141.md>
+//!
+//! The `#[ext_contract]` interfaces of the standard ([`ext_nep141`],
+//! [`ext_nep141_receiver`], and [`ext_nep141_resolver`]) are defined in this
+//! module, not generated by the derive macros, so they do not collide with
+//! items of the same names in a crate that defines several token contracts.
+//! Call them through this module, e.g.
+//! `near_sdk_contract_tools::standard::nep141::ext_nep141_receiver::ext(receiver_id)`.
 #![allow(missing_docs)] // ext_contract doesn't play nice with #![warn(missing_docs)]
 
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     env, ext_contract,
     json_types::U128,
-    require, AccountId, BorshStorageKey, Gas, Promise, PromiseOrValue, PromiseResult,
+    require, AccountId, Balance, BorshStorageKey, Gas, Promise, PromiseOrValue,
 };
 use near_sdk_contract_tools_macros::event;
 use serde::{Deserialize, Serialize};
 
-use crate::{slot::Slot, standard::nep297::*, DefaultStorageKey};
+use crate::{
+    slot::{Slot, SlotTransaction},
+    standard::nep297::*,
+    storage_accounting::StorageAccounting,
+    utils::{checked_add_u128, checked_sub_u128, read_u128_or_zero},
+    xcall::{self, Rollback},
+    DefaultStorageKey,
+};
 
 /// Gas value required for ft_resolve_transfer calls
 pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
 /// Gas value required for ft_transfer_call calls (includes gas for )
 pub const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
 
-const MORE_GAS_FAIL_MESSAGE: &str = "More gas is required";
+const MORE_GAS_FAIL_MESSAGE: &str = message!("FT1", "More gas is required");
+
+/// Length of the longest storage key suffix that [`Nep141Controller`]
+/// appends to its root, given its [`Nep141Controller::hashed_key_len`].
+/// Account IDs are counted at their maximum length of 64 bytes.
+pub const fn max_key_suffix_len(hashed_key_len: Option<usize>) -> usize {
+    match hashed_key_len {
+        // Hashed account keys, or the total supply key (1 byte)
+        Some(len) if len > 1 => len,
+        Some(_) => 1,
+        // Enum discriminant + string length prefix + account ID
+        None => 1 + 4 + 64,
+    }
+}
 
 /// NEP-141 standard events for minting, burning, and transferring tokens
 #[event(
@@ -28,7 +55,7 @@ const MORE_GAS_FAIL_MESSAGE: &str = "More gas is required";
     standard = "nep141",
     version = "1.0.0"
 )]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Nep141Event {
     /// Token mint event. Emitted when tokens are created and total_supply is
     /// increased.
@@ -43,12 +70,29 @@ pub enum Nep141Event {
     FtBurn(Vec<event::FtBurnData>),
 }
 
+#[cfg(feature = "unit-testing")]
+impl crate::testing::FromEventLog for Nep141Event {
+    fn from_event_log(log: crate::testing::RawEventLog) -> Option<Self> {
+        if log.standard != "nep141" {
+            return None;
+        }
+
+        Some(match log.event.as_str() {
+            "ft_mint" => Self::FtMint(serde_json::from_value(log.data).ok()?),
+            "ft_transfer" => Self::FtTransfer(serde_json::from_value(log.data).ok()?),
+            "ft_burn" => Self::FtBurn(serde_json::from_value(log.data).ok()?),
+            _ => return None,
+        })
+    }
+}
+
 pub mod event {
     use near_sdk::{json_types::U128, AccountId};
-    use serde::Serialize;
+    use serde::{Deserialize, Serialize};
 
     /// Individual mint metadata
-    #[derive(Serialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
     pub struct FtMintData {
         /// Address to which new tokens were minted
         pub owner_id: AccountId,
@@ -60,7 +104,8 @@ pub mod event {
     }
 
     /// Individual transfer metadata
-    #[derive(Serialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
     pub struct FtTransferData {
         /// Account ID of the sender
         pub old_owner_id: AccountId,
@@ -74,7 +119,8 @@ pub mod event {
     }
 
     /// Individual burn metadata
-    #[derive(Serialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
     pub struct FtBurnData {
         /// Account ID from which tokens were burned
         pub owner_id: AccountId,
@@ -142,9 +188,9 @@ pub mod event {
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
-enum StorageKey {
+enum StorageKey<'a> {
     TotalSupply,
-    Account(AccountId),
+    Account(&'a AccountId),
 }
 
 /// Contracts may implement this trait to inject code into NEP-141 functions.
@@ -167,9 +213,29 @@ pub trait Nep141Hook<T: Default = ()> {
     fn after_transfer(&mut self, _transfer: &Nep141Transfer, _state: T) {}
 }
 
+/// Charges the senders of NEP-141 transfers for the storage the transfers
+/// consume (e.g. the balance record of a new receiver) from their storage
+/// balance, and credits them for any storage freed. Implementing this trait
+/// provides an implementation of [`Nep141Hook`].
+pub trait Nep141StorageBalance {
+    /// Storage balance of an account, in yoctoNEAR
+    fn slot_storage_balance(account_id: &AccountId) -> Slot<Balance>;
+}
+
+impl<C: Nep141StorageBalance> Nep141Hook<StorageAccounting> for C {
+    fn before_transfer(&mut self, transfer: &Nep141Transfer) -> StorageAccounting {
+        StorageAccounting::debit(Self::slot_storage_balance(&transfer.sender_id))
+    }
+
+    fn after_transfer(&mut self, _transfer: &Nep141Transfer, state: StorageAccounting) {
+        state.settle();
+    }
+}
+
 /// Transfer metadata generic over both types of transfer (`ft_transfer` and
 /// `ft_transfer_call`).
 #[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
 pub struct Nep141Transfer {
     /// Sender's account ID
     pub sender_id: AccountId,
@@ -189,18 +255,50 @@ impl Nep141Transfer {
     pub fn is_transfer_call(&self) -> bool {
         self.msg.is_some()
     }
+
+    /// Requires a deposit of exactly one yoctoNEAR, and describes a transfer
+    /// from the predecessor. Shared by the `ft_transfer` and
+    /// `ft_transfer_call` implementations generated by `#[derive(Nep141)]`.
+    #[inline(never)]
+    pub fn from_predecessor(receiver_id: AccountId, amount: U128, memo: Option<String>) -> Self {
+        near_sdk::assert_one_yocto();
+
+        Self {
+            sender_id: env::predecessor_account_id(),
+            receiver_id,
+            amount: amount.into(),
+            memo,
+            msg: None,
+        }
+    }
 }
 
 /// Non-public implementations of functions for managing a fungible token.
 pub trait Nep141Controller {
     /// Root storage slot
     fn root() -> Slot<()> {
-        Slot::new(DefaultStorageKey::Nep141)
+        Slot::root(DefaultStorageKey::Nep141)
+    }
+
+    /// Number of bytes of the hash to use for account keys, or `None` to use
+    /// the account ID as-is. See [`Slot::field_hashed`] for the tradeoffs.
+    fn hashed_key_len() -> Option<usize> {
+        None
+    }
+
+    /// Length of the longest storage key used by this component
+    fn max_key_len() -> usize {
+        Self::root().key.len() + max_key_suffix_len(Self::hashed_key_len())
     }
 
     /// Slot for account data
     fn slot_account(account_id: &AccountId) -> Slot<u128> {
-        Self::root().field(StorageKey::Account(account_id.clone()))
+        let key = StorageKey::Account(account_id);
+
+        match Self::hashed_key_len() {
+            Some(len) => Self::root().field_hashed_len(&key, len),
+            None => Self::root().field_ref(&key),
+        }
     }
 
     /// Slot for storing total supply
@@ -210,12 +308,12 @@ pub trait Nep141Controller {
 
     /// Get the balance of an account. Returns 0 if the account does not exist.
     fn balance_of(account_id: &AccountId) -> u128 {
-        Self::slot_account(account_id).read().unwrap_or(0)
+        read_u128_or_zero(&Self::slot_account(account_id))
     }
 
     /// Get the total circulating supply of the token.
     fn total_supply() -> u128 {
-        Self::slot_total_supply().read().unwrap_or(0)
+        read_u128_or_zero(&Self::slot_total_supply())
     }
 
     /// Removes tokens from an account and decreases total supply. No event
@@ -227,47 +325,188 @@ pub trait Nep141Controller {
     /// if `total_supply` is less than `amount`.
     fn withdraw_unchecked(&mut self, account_id: &AccountId, amount: u128) {
         if amount != 0 {
-            let balance = Self::balance_of(account_id);
+            checked_sub_u128(
+                &mut Self::slot_account(account_id),
+                amount,
+                message!("FT2", "Balance underflow"),
+            );
+            checked_sub_u128(
+                &mut Self::slot_total_supply(),
+                amount,
+                message!("FT3", "Total supply underflow"),
+            );
+        }
+    }
+
+    /// Increases the token balance of an account. Updates total supply. No
+    /// event emission,
+    ///
+    /// # Panics
+    ///
+    /// Panics if the balance of `account_id` plus `amount` >= `u128::MAX`, or
+    /// if the total supply plus `amount` >= `u128::MAX`.
+    fn deposit_unchecked(&mut self, account_id: &AccountId, amount: u128) {
+        if amount != 0 {
+            checked_add_u128(
+                &mut Self::slot_account(account_id),
+                amount,
+                message!("FT4", "Balance overflow"),
+            );
+            checked_add_u128(
+                &mut Self::slot_total_supply(),
+                amount,
+                message!("FT5", "Total supply overflow"),
+            );
+        }
+    }
+
+    /// Like `Nep141Controller::withdraw_unchecked`, but only stages the
+    /// writes in `transaction`.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::withdraw_unchecked`
+    fn withdraw_unchecked_in(
+        &mut self,
+        transaction: &mut SlotTransaction,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        if amount != 0 {
+            let slot_account = Self::slot_account(account_id);
+            let balance = transaction.read(&slot_account).unwrap_or(0);
             if let Some(balance) = balance.checked_sub(amount) {
-                Self::slot_account(account_id).write(&balance);
+                transaction.write(&slot_account, &balance);
             } else {
-                env::panic_str("Balance underflow");
+                env::panic_str(message!("FT2", "Balance underflow"));
             }
 
-            let total_supply = Self::total_supply();
+            let slot_total_supply = Self::slot_total_supply();
+            let total_supply = transaction.read(&slot_total_supply).unwrap_or(0);
             if let Some(total_supply) = total_supply.checked_sub(amount) {
-                Self::slot_total_supply().write(&total_supply);
+                transaction.write(&slot_total_supply, &total_supply);
             } else {
-                env::panic_str("Total supply underflow");
+                env::panic_str(message!("FT3", "Total supply underflow"));
             }
         }
     }
 
-    /// Increases the token balance of an account. Updates total supply. No
-    /// event emission,
+    /// Like `Nep141Controller::deposit_unchecked`, but only stages the writes
+    /// in `transaction`.
     ///
     /// # Panics
     ///
-    /// Panics if the balance of `account_id` plus `amount` >= `u128::MAX`, or
-    /// if the total supply plus `amount` >= `u128::MAX`.
-    fn deposit_unchecked(&mut self, account_id: &AccountId, amount: u128) {
+    /// See: `Nep141Controller::deposit_unchecked`
+    fn deposit_unchecked_in(
+        &mut self,
+        transaction: &mut SlotTransaction,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
         if amount != 0 {
-            let balance = Self::balance_of(account_id);
+            let slot_account = Self::slot_account(account_id);
+            let balance = transaction.read(&slot_account).unwrap_or(0);
             if let Some(balance) = balance.checked_add(amount) {
-                Self::slot_account(account_id).write(&balance);
+                transaction.write(&slot_account, &balance);
             } else {
-                env::panic_str("Balance overflow");
+                env::panic_str(message!("FT4", "Balance overflow"));
             }
 
-            let total_supply = Self::total_supply();
+            let slot_total_supply = Self::slot_total_supply();
+            let total_supply = transaction.read(&slot_total_supply).unwrap_or(0);
             if let Some(total_supply) = total_supply.checked_add(amount) {
-                Self::slot_total_supply().write(&total_supply);
+                transaction.write(&slot_total_supply, &total_supply);
             } else {
-                env::panic_str("Total supply overflow");
+                env::panic_str(message!("FT5", "Total supply overflow"));
             }
         }
     }
 
+    /// Like `Nep141Controller::transfer_unchecked`, but only stages the
+    /// writes in `transaction`.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::transfer_unchecked`
+    fn transfer_unchecked_in(
+        &mut self,
+        transaction: &mut SlotTransaction,
+        sender_account_id: &AccountId,
+        receiver_account_id: &AccountId,
+        amount: u128,
+    ) {
+        let slot_sender = Self::slot_account(sender_account_id);
+        let sender_balance = transaction.read(&slot_sender).unwrap_or(0);
+
+        if let Some(sender_balance) = sender_balance.checked_sub(amount) {
+            transaction.write(&slot_sender, &sender_balance);
+
+            let slot_receiver = Self::slot_account(receiver_account_id);
+            let receiver_balance = transaction.read(&slot_receiver).unwrap_or(0);
+            if let Some(receiver_balance) = receiver_balance.checked_add(amount) {
+                transaction.write(&slot_receiver, &receiver_balance);
+            } else {
+                env::panic_str(message!("FT6", "Receiver balance overflow"));
+            }
+        } else {
+            env::panic_str(message!("FT7", "Sender balance underflow"));
+        }
+    }
+
+    /// Increases the token balances of many accounts. Updates total supply.
+    /// Each balance and the total supply are written only once. No event
+    /// emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::deposit_unchecked`
+    fn deposit_batch_unchecked(&mut self, deposits: &[(AccountId, u128)]) {
+        let mut transaction = SlotTransaction::new();
+
+        for (account_id, amount) in deposits {
+            self.deposit_unchecked_in(&mut transaction, account_id, *amount);
+        }
+
+        transaction.commit();
+    }
+
+    /// Removes tokens from many accounts. Updates total supply. Each balance
+    /// and the total supply are written only once. No event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::withdraw_unchecked`
+    fn withdraw_batch_unchecked(&mut self, withdrawals: &[(AccountId, u128)]) {
+        let mut transaction = SlotTransaction::new();
+
+        for (account_id, amount) in withdrawals {
+            self.withdraw_unchecked_in(&mut transaction, account_id, *amount);
+        }
+
+        transaction.commit();
+    }
+
+    /// Performs many transfers between accounts, in order. Each balance is
+    /// written only once. No change to total supply. No event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::transfer_unchecked`
+    fn transfer_batch_unchecked(&mut self, transfers: &[(AccountId, AccountId, u128)]) {
+        let mut transaction = SlotTransaction::new();
+
+        for (sender_account_id, receiver_account_id, amount) in transfers {
+            self.transfer_unchecked_in(
+                &mut transaction,
+                sender_account_id,
+                receiver_account_id,
+                *amount,
+            );
+        }
+
+        transaction.commit();
+    }
+
     /// Decreases the balance of `sender_account_id` by `amount` and increases
     /// the balance of `receiver_account_id` by the same. No change to total
     /// supply. No event emission.
@@ -285,15 +524,21 @@ pub trait Nep141Controller {
         let sender_balance = Self::balance_of(sender_account_id);
 
         if let Some(sender_balance) = sender_balance.checked_sub(amount) {
-            let receiver_balance = Self::balance_of(receiver_account_id);
+            // A self-transfer must not read the balance from before the
+            // withdrawal, or it would mint `amount`
+            let receiver_balance = if receiver_account_id == sender_account_id {
+                sender_balance
+            } else {
+                Self::balance_of(receiver_account_id)
+            };
             if let Some(receiver_balance) = receiver_balance.checked_add(amount) {
                 Self::slot_account(sender_account_id).write(&sender_balance);
                 Self::slot_account(receiver_account_id).write(&receiver_balance);
             } else {
-                env::panic_str("Receiver balance overflow");
+                env::panic_str(message!("FT6", "Receiver balance overflow"));
             }
         } else {
-            env::panic_str("Sender balance underflow");
+            env::panic_str(message!("FT7", "Sender balance underflow"));
         }
     }
 
@@ -320,6 +565,34 @@ pub trait Nep141Controller {
         .emit();
     }
 
+    /// Performs many NEP-141 token transfers, with a single event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::transfer_batch_unchecked`
+    fn transfer_batch(
+        &mut self,
+        transfers: Vec<(AccountId, AccountId, u128)>,
+        memo: Option<String>,
+    ) {
+        self.transfer_batch_unchecked(&transfers);
+
+        Nep141Event::FtTransfer(
+            transfers
+                .into_iter()
+                .map(
+                    |(old_owner_id, new_owner_id, amount)| event::FtTransferData {
+                        old_owner_id,
+                        new_owner_id,
+                        amount: amount.into(),
+                        memo: memo.clone(),
+                    },
+                )
+                .collect(),
+        )
+        .emit();
+    }
+
     /// Performs an NEP-141 token mint, with event emission.
     ///
     /// # Panics
@@ -352,6 +625,48 @@ pub trait Nep141Controller {
         .emit();
     }
 
+    /// Performs many NEP-141 token mints, with a single event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::deposit_batch_unchecked`
+    fn mint_batch(&mut self, mints: Vec<(AccountId, u128)>, memo: Option<String>) {
+        self.deposit_batch_unchecked(&mints);
+
+        Nep141Event::FtMint(
+            mints
+                .into_iter()
+                .map(|(owner_id, amount)| event::FtMintData {
+                    owner_id,
+                    amount: amount.into(),
+                    memo: memo.clone(),
+                })
+                .collect(),
+        )
+        .emit();
+    }
+
+    /// Performs many NEP-141 token burns, with a single event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep141Controller::withdraw_batch_unchecked`
+    fn burn_batch(&mut self, burns: Vec<(AccountId, u128)>, memo: Option<String>) {
+        self.withdraw_batch_unchecked(&burns);
+
+        Nep141Event::FtBurn(
+            burns
+                .into_iter()
+                .map(|(owner_id, amount)| event::FtBurnData {
+                    owner_id,
+                    amount: amount.into(),
+                    memo: memo.clone(),
+                })
+                .collect(),
+        )
+        .emit();
+    }
+
     /// Performs an NEP-141 token transfer call, with event emission.
     ///
     /// # Panics
@@ -397,31 +712,46 @@ pub trait Nep141Controller {
     }
 
     /// Resolves an NEP-141 `ft_transfer_call` promise chain.
+    ///
+    /// See also: [`FtTransferCallRollback`]
     fn resolve_transfer(
         &mut self,
         sender_id: AccountId,
         receiver_id: AccountId,
         amount: u128,
     ) -> u128 {
-        let ft_on_transfer_promise_result = env::promise_result(0);
-
-        let unused_amount = match ft_on_transfer_promise_result {
-            PromiseResult::NotReady => env::abort(),
-            PromiseResult::Successful(value) => {
-                if let Ok(U128(unused_amount)) = serde_json::from_slice::<U128>(&value) {
-                    std::cmp::min(amount, unused_amount)
-                } else {
-                    amount
-                }
-            }
-            PromiseResult::Failed => amount,
-        };
+        xcall::resolve(
+            self,
+            FtTransferCallRollback {
+                sender_id,
+                receiver_id,
+                amount,
+            },
+        )
+    }
+}
+
+/// Resolution of an `ft_transfer_call`, as a cross-contract call
+/// [`Rollback`]: refunds the tokens that the receiver did not use, or all of
+/// them if `ft_on_transfer` failed, as far as the receiver still holds them.
+/// Returns the used amount.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FtTransferCallRollback {
+    /// Sender of the transfer
+    pub sender_id: AccountId,
+    /// Receiver of the transfer
+    pub receiver_id: AccountId,
+    /// Amount transferred
+    pub amount: u128,
+}
 
+impl FtTransferCallRollback {
+    fn refund<C: Nep141Controller + ?Sized>(self, contract: &mut C, unused_amount: u128) -> u128 {
         let refunded_amount = if unused_amount > 0 {
-            let receiver_balance = Self::balance_of(&receiver_id);
+            let receiver_balance = C::balance_of(&self.receiver_id);
             if receiver_balance > 0 {
                 let refund_amount = std::cmp::min(receiver_balance, unused_amount);
-                self.transfer(receiver_id, sender_id, refund_amount, None);
+                contract.transfer(self.receiver_id, self.sender_id, refund_amount, None);
                 refund_amount
             } else {
                 0
@@ -431,7 +761,27 @@ pub trait Nep141Controller {
         };
 
         // Used amount
-        amount - refunded_amount
+        self.amount - refunded_amount
+    }
+}
+
+impl<C: Nep141Controller + ?Sized> Rollback<C> for FtTransferCallRollback {
+    type Output = u128;
+
+    fn commit(self, contract: &mut C, value: Vec<u8>) -> u128 {
+        let unused_amount = if let Ok(U128(unused_amount)) = serde_json::from_slice::<U128>(&value)
+        {
+            std::cmp::min(self.amount, unused_amount)
+        } else {
+            self.amount
+        };
+
+        self.refund(contract, unused_amount)
+    }
+
+    fn rollback(self, contract: &mut C) -> u128 {
+        let amount = self.amount;
+        self.refund(contract, amount)
     }
 }
 
@@ -487,3 +837,394 @@ pub trait Nep141 {
     /// Returns the amount of tokens controlled by `account_id`
     fn ft_balance_of(&self, account_id: AccountId) -> U128;
 }
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        borsh::{self, BorshSerialize},
+        AccountId, BorshStorageKey, PromiseResult,
+    };
+
+    use std::{
+        collections::HashMap,
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    use proptest::prelude::*;
+
+    use super::{event::FtTransferData, Nep141Controller, Nep141Event};
+    use crate::{
+        slot::{Slot, SlotTransaction},
+        testing::{events, ft, TestEnv},
+        DefaultStorageKey,
+    };
+
+    struct Contract;
+
+    impl Nep141Controller for Contract {}
+
+    struct HashedContract;
+
+    impl Nep141Controller for HashedContract {
+        fn hashed_key_len() -> Option<usize> {
+            Some(8)
+        }
+    }
+
+    /// Storage key layout before account IDs were borrowed
+    #[derive(BorshSerialize, BorshStorageKey)]
+    enum OwnedStorageKey {
+        TotalSupply,
+        Account(AccountId),
+    }
+
+    #[test]
+    fn batch_total_supply() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let mut contract = Contract;
+
+        contract.mint_batch(vec![(alice.clone(), 10), (bob.clone(), 5)], None);
+        assert_eq!(Contract::total_supply(), 15);
+
+        contract.burn_batch(vec![(alice.clone(), 3), (bob.clone(), 5)], None);
+        assert_eq!(Contract::total_supply(), 7);
+        assert_eq!(Contract::balance_of(&alice), 7);
+        assert_eq!(Contract::balance_of(&bob), 0);
+    }
+
+    #[test]
+    fn storage_keys_unchanged() {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let root = Slot::<()>::new(DefaultStorageKey::Nep141);
+
+        assert_eq!(
+            Contract::slot_account(&account_id).key,
+            root.field::<u128>(OwnedStorageKey::Account(account_id.clone()))
+                .key,
+        );
+        assert_eq!(
+            Contract::slot_total_supply().key,
+            root.field::<u128>(OwnedStorageKey::TotalSupply).key,
+        );
+    }
+
+    #[test]
+    fn hashed_keys() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let slot = HashedContract::slot_account(&alice);
+        assert_eq!(slot.key.len(), HashedContract::root().key.len() + 8);
+        assert_ne!(slot.key, HashedContract::slot_account(&bob).key);
+        assert_ne!(slot.key, Contract::slot_account(&alice).key);
+
+        let mut contract = HashedContract;
+        contract.mint(alice.clone(), 10, None);
+        contract.transfer(alice.clone(), bob.clone(), 4, None);
+
+        assert_eq!(HashedContract::balance_of(&alice), 6);
+        assert_eq!(HashedContract::balance_of(&bob), 4);
+    }
+
+    #[test]
+    fn transaction_writes_once() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let charlie: AccountId = "charlie.near".parse().unwrap();
+
+        let mut contract = Contract;
+        let mut transaction = SlotTransaction::new();
+
+        contract.deposit_unchecked_in(&mut transaction, &alice, 100);
+
+        let accounts = [&alice, &bob, &charlie];
+        for i in 0..10 {
+            contract.transfer_unchecked_in(
+                &mut transaction,
+                accounts[i % 3],
+                accounts[(i + 1) % 3],
+                10 - i as u128,
+            );
+        }
+
+        // Nothing written yet
+        assert_eq!(Contract::total_supply(), 0);
+        assert_eq!(Contract::balance_of(&alice), 0);
+
+        // 3 balances + total supply
+        assert_eq!(transaction.commit(), 4);
+
+        assert_eq!(Contract::total_supply(), 100);
+        assert_eq!(
+            Contract::balance_of(&alice)
+                + Contract::balance_of(&bob)
+                + Contract::balance_of(&charlie),
+            100,
+        );
+        assert_eq!(
+            Contract::balance_of(&alice),
+            100 - 10 - 7 - 4 - 1 + 8 + 5 + 2
+        );
+    }
+
+    #[test]
+    fn transfer_batch() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let mut contract = Contract;
+
+        contract.mint(alice.clone(), 10, None);
+        contract.transfer_batch(
+            vec![
+                (alice.clone(), bob.clone(), 7),
+                (bob.clone(), alice.clone(), 2),
+                (alice.clone(), bob.clone(), 5),
+            ],
+            None,
+        );
+
+        assert_eq!(Contract::balance_of(&alice), 0);
+        assert_eq!(Contract::balance_of(&bob), 10);
+        assert_eq!(Contract::total_supply(), 10);
+    }
+
+    #[test]
+    #[should_panic = "Sender balance underflow"]
+    fn transfer_batch_underflow() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let mut contract = Contract;
+
+        contract.mint(alice.clone(), 10, None);
+        contract.transfer_batch_unchecked(&[
+            (alice.clone(), bob.clone(), 7),
+            (alice.clone(), bob.clone(), 7),
+        ]);
+    }
+
+    /// Sets up the state of an `ft_transfer_call` from alice to bob of 100
+    /// tokens, as `ft_resolve_transfer` finds it, with the given result of
+    /// `ft_on_transfer`.
+    fn resolve_setup(result: PromiseResult) -> (TestEnv, Contract, AccountId, AccountId) {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let mut env = TestEnv::new();
+        let mut contract = Contract;
+        contract.deposit_unchecked(&alice, 150);
+        contract.transfer_unchecked(&alice, &bob, 100);
+
+        env.set_promise_results(vec![result]);
+
+        (env, contract, alice, bob)
+    }
+
+    fn refund_event(alice: &AccountId, bob: &AccountId, amount: u128) -> Nep141Event {
+        Nep141Event::FtTransfer(vec![FtTransferData {
+            old_owner_id: bob.clone(),
+            new_owner_id: alice.clone(),
+            amount: amount.into(),
+            memo: None,
+        }])
+    }
+
+    #[test]
+    fn resolve_transfer_partial_refund() {
+        let (env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(b"\"30\"".to_vec()));
+
+        // 30 unused, refunded
+        assert_eq!(
+            contract.resolve_transfer(alice.clone(), bob.clone(), 100),
+            70
+        );
+        assert_eq!(Contract::balance_of(&alice), 80);
+        assert_eq!(Contract::balance_of(&bob), 70);
+        assert_eq!(
+            env.events_of::<Nep141Event>(),
+            vec![refund_event(&alice, &bob, 30)],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_unused_capped_at_amount() {
+        let (_env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(b"\"1000\"".to_vec()));
+
+        assert_eq!(
+            contract.resolve_transfer(alice.clone(), bob.clone(), 100),
+            0
+        );
+        assert_eq!(Contract::balance_of(&alice), 150);
+        assert_eq!(Contract::balance_of(&bob), 0);
+    }
+
+    #[test]
+    fn resolve_transfer_invalid_value() {
+        let (env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(b"not a U128".to_vec()));
+
+        // Full refund
+        assert_eq!(
+            contract.resolve_transfer(alice.clone(), bob.clone(), 100),
+            0
+        );
+        assert_eq!(Contract::balance_of(&alice), 150);
+        assert_eq!(Contract::balance_of(&bob), 0);
+        assert_eq!(
+            env.events_of::<Nep141Event>(),
+            vec![refund_event(&alice, &bob, 100)],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_failed() {
+        let (env, mut contract, alice, bob) = resolve_setup(PromiseResult::Failed);
+
+        // Full refund
+        assert_eq!(
+            contract.resolve_transfer(alice.clone(), bob.clone(), 100),
+            0
+        );
+        assert_eq!(Contract::balance_of(&alice), 150);
+        assert_eq!(Contract::balance_of(&bob), 0);
+        assert_eq!(
+            env.events_of::<Nep141Event>(),
+            vec![refund_event(&alice, &bob, 100)],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_refund_capped_at_receiver_balance() {
+        let (_env, mut contract, alice, bob) = resolve_setup(PromiseResult::Failed);
+        let charlie: AccountId = "charlie.near".parse().unwrap();
+
+        // The receiver spent some of the tokens before the resolution
+        contract.transfer_unchecked(&bob, &charlie, 60);
+
+        assert_eq!(
+            contract.resolve_transfer(alice.clone(), bob.clone(), 100),
+            60
+        );
+        assert_eq!(Contract::balance_of(&alice), 90);
+        assert_eq!(Contract::balance_of(&bob), 0);
+        assert_eq!(Contract::balance_of(&charlie), 60);
+    }
+
+    #[test]
+    fn self_transfer() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), 10, None);
+        contract.transfer(alice.clone(), alice.clone(), 7, None);
+
+        ft::assert_balances::<Contract>(&[(&alice, 10)]);
+        ft::assert_total_supply::<Contract>(10);
+    }
+
+    #[test]
+    fn emitted_events_replay() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), 10, None);
+        contract.mint(bob.clone(), 10, Some("memo".to_string()));
+        contract.mint_batch(vec![(alice.clone(), 1), (bob.clone(), 2)], None);
+        contract.transfer(alice.clone(), bob.clone(), 3, None);
+        contract.transfer(bob.clone(), alice.clone(), 4, Some(String::new()));
+        contract.burn(alice.clone(), 5, None);
+        contract.burn_batch(vec![(alice, 1), (bob, 1)], Some("memo".to_string()));
+
+        let logs = near_sdk::test_utils::get_logs();
+        let events = events::replay::<Nep141Event>(&logs).unwrap();
+
+        assert_eq!(events.len(), logs.len());
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Mint(usize, u128),
+        Burn(usize, u128),
+        Transfer(usize, usize, u128),
+    }
+
+    const ACCOUNTS: [&str; 3] = ["alice.near", "bob.near", "charlie.near"];
+
+    fn op() -> impl Strategy<Value = Op> {
+        let account = 0..ACCOUNTS.len();
+        let amount = 0..1000u128;
+
+        prop_oneof![
+            (account.clone(), amount.clone()).prop_map(|(a, n)| Op::Mint(a, n)),
+            (account.clone(), amount.clone()).prop_map(|(a, n)| Op::Burn(a, n)),
+            (account.clone(), account, amount).prop_map(|(a, b, n)| Op::Transfer(a, b, n)),
+        ]
+    }
+
+    proptest! {
+        /// Random sequences of operations, some of which fail for lack of
+        /// balance, keep the storage in line with a model of the balances.
+        #[test]
+        fn matches_model(ops in prop::collection::vec(op(), 1..50)) {
+            // Storage outlives test cases in the same thread
+            near_sdk::env::set_blockchain_interface(Default::default());
+
+            let accounts = ACCOUNTS.map(|a| a.parse::<AccountId>().unwrap());
+            let mut model = HashMap::<usize, u128>::new();
+            let mut contract = Contract;
+
+            let balance =
+                |model: &HashMap<usize, u128>, a: usize| model.get(&a).copied().unwrap_or(0);
+
+            for op in ops {
+                let succeeded = catch_unwind(AssertUnwindSafe(|| match op {
+                    Op::Mint(a, n) => contract.mint(accounts[a].clone(), n, None),
+                    Op::Burn(a, n) => contract.burn(accounts[a].clone(), n, None),
+                    Op::Transfer(a, b, n) => {
+                        contract.transfer(accounts[a].clone(), accounts[b].clone(), n, None)
+                    }
+                }))
+                .is_ok();
+
+                let expected = match op {
+                    Op::Mint(a, n) => {
+                        *model.entry(a).or_default() += n;
+                        true
+                    }
+                    Op::Burn(a, n) => match balance(&model, a).checked_sub(n) {
+                        Some(rest) => {
+                            model.insert(a, rest);
+                            true
+                        }
+                        None => false,
+                    },
+                    Op::Transfer(a, b, n) => match balance(&model, a).checked_sub(n) {
+                        Some(rest) => {
+                            model.insert(a, rest);
+                            *model.entry(b).or_default() += n;
+                            true
+                        }
+                        None => false,
+                    },
+                };
+
+                prop_assert_eq!(succeeded, expected, "{:?}", op);
+
+                for (i, account_id) in accounts.iter().enumerate() {
+                    prop_assert_eq!(Contract::balance_of(account_id), balance(&model, i));
+                }
+                ft::assert_supply_equals_sum_of_balances::<Contract>(
+                    &accounts.iter().collect::<Vec<_>>(),
+                );
+            }
+        }
+    }
+}
@@ -9,11 +9,14 @@ use near_sdk::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::{slot::Slot, DefaultStorageKey};
+
 /// Version of the NEP-148 metadata spec
 pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
 
 /// NEP-148-compatible metadata struct
 #[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
 pub struct FungibleTokenMetadata {
     /// Version of the NEP-148 spec
     pub spec: String,
@@ -34,6 +37,16 @@ pub struct FungibleTokenMetadata {
     pub decimals: u8,
 }
 
+/// Internal functions for [`Nep148`]. The derive macro serves metadata
+/// hardcoded into the contract code, so nothing is stored under the root
+/// yet: it is reserved for storage-backed metadata.
+pub trait Nep148Controller {
+    /// Root storage slot
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Nep148)
+    }
+}
+
 /// Contract that supports the NEP-148 metadata standard
 #[ext_contract(ext_nep148)]
 pub trait Nep148 {
@@ -0,0 +1,951 @@
+//! NEP-245 multi token core implementation
+//! <https://github.com/near/NEPs/blob/master/neps/nep-0245.md>
+//!
+//! Balances are kept per token ID and account, so a token may be fungible
+//! (any supply) or non-fungible (a supply of 1) alike. Approvals are not
+//! implemented: transfers are always made by the owner of the tokens, and the
+//! transfer methods panic if their `approval` (or `approvals`) argument is
+//! set.
+//!
+//! As with NEP-141, the `#[ext_contract]` interfaces of the standard
+//! ([`ext_nep245`], [`ext_nep245_receiver`], and [`ext_nep245_resolver`]) are
+//! defined in this module, e.g.
+//! `near_sdk_contract_tools::standard::nep245::ext_nep245_receiver::ext(receiver_id)`.
+#![allow(missing_docs)] // ext_contract doesn't play nice with #![warn(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, ext_contract,
+    json_types::U128,
+    require, AccountId, BorshStorageKey, Gas, Promise, PromiseOrValue, PromiseResult,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slot::{Slot, SlotTransaction},
+    standard::nep297::*,
+    utils::read_u128_or_zero,
+    DefaultStorageKey,
+};
+
+/// Gas value required for mt_resolve_transfer calls
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+/// Gas value required for mt_transfer_call calls (includes gas for the
+/// mt_resolve_transfer callback)
+pub const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+
+const MORE_GAS_FAIL_MESSAGE: &str = message!("MT1", "More gas is required");
+/// Error message of the transfer methods when an approval is given, since
+/// approvals are not implemented
+pub const APPROVALS_UNSUPPORTED_FAIL_MESSAGE: &str = message!("MT9", "Approvals are not supported");
+
+/// Token IDs are arbitrary strings chosen by the contract
+pub type TokenId = String;
+
+/// NEP-245 standard events for minting, burning, and transferring tokens
+#[event(
+    crate = "crate",
+    macros = "crate",
+    serde = "serde",
+    standard = "nep245",
+    version = "1.0.0"
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nep245Event {
+    /// Token mint event. Emitted when tokens are created and the supply of
+    /// each token is increased.
+    MtMint(Vec<event::MtMintData>),
+
+    /// Token transfer event. Emitted when tokens are transferred between two
+    /// accounts. No change to supply.
+    MtTransfer(Vec<event::MtTransferData>),
+
+    /// Token burn event. Emitted when tokens are burned (removed from supply).
+    MtBurn(Vec<event::MtBurnData>),
+}
+
+#[cfg(feature = "unit-testing")]
+impl crate::testing::FromEventLog for Nep245Event {
+    fn from_event_log(log: crate::testing::RawEventLog) -> Option<Self> {
+        if log.standard != "nep245" {
+            return None;
+        }
+
+        Some(match log.event.as_str() {
+            "mt_mint" => Self::MtMint(serde_json::from_value(log.data).ok()?),
+            "mt_transfer" => Self::MtTransfer(serde_json::from_value(log.data).ok()?),
+            "mt_burn" => Self::MtBurn(serde_json::from_value(log.data).ok()?),
+            _ => return None,
+        })
+    }
+}
+
+pub mod event {
+    use near_sdk::{json_types::U128, AccountId};
+    use serde::{Deserialize, Serialize};
+
+    use super::TokenId;
+
+    /// Individual mint metadata
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+    pub struct MtMintData {
+        /// Address to which new tokens were minted
+        pub owner_id: AccountId,
+        /// IDs of the minted tokens
+        pub token_ids: Vec<TokenId>,
+        /// Amount minted of each token
+        pub amounts: Vec<U128>,
+        /// Optional note
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub memo: Option<String>,
+    }
+
+    /// Individual transfer metadata
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+    pub struct MtTransferData {
+        /// Approved account that made the transfer on behalf of the owner
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub authorized_id: Option<AccountId>,
+        /// Account ID of the sender
+        pub old_owner_id: AccountId,
+        /// Account ID of the receiver
+        pub new_owner_id: AccountId,
+        /// IDs of the transferred tokens
+        pub token_ids: Vec<TokenId>,
+        /// Amount transferred of each token
+        pub amounts: Vec<U128>,
+        /// Optional note
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub memo: Option<String>,
+    }
+
+    /// Individual burn metadata
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+    pub struct MtBurnData {
+        /// Account ID from which tokens were burned
+        pub owner_id: AccountId,
+        /// Approved account that burned the tokens on behalf of the owner
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub authorized_id: Option<AccountId>,
+        /// IDs of the burned tokens
+        pub token_ids: Vec<TokenId>,
+        /// Amount burned of each token
+        pub amounts: Vec<U128>,
+        /// Optional note
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub memo: Option<String>,
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{super::Nep245Event, *};
+        use crate::standard::nep297::Event;
+
+        #[test]
+        fn mint() {
+            assert_eq!(
+                Nep245Event::MtMint(vec![MtMintData {
+                    owner_id: "foundation.near".parse().unwrap(),
+                    token_ids: vec!["aurora".to_string(), "proximitylabs".to_string()],
+                    amounts: vec![1u128.into(), 100u128.into()],
+                    memo: None,
+                }])
+                .to_event_string(),
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"foundation.near","token_ids":["aurora","proximitylabs"],"amounts":["1","100"]}]}"#,
+            );
+        }
+
+        #[test]
+        fn transfer() {
+            assert_eq!(
+                Nep245Event::MtTransfer(vec![
+                    MtTransferData {
+                        authorized_id: None,
+                        old_owner_id: "user1.near".parse().unwrap(),
+                        new_owner_id: "user2.near".parse().unwrap(),
+                        token_ids: vec!["meme".to_string()],
+                        amounts: vec![1u128.into()],
+                        memo: Some("have fun!".to_string()),
+                    },
+                    MtTransferData {
+                        authorized_id: Some("market.near".parse().unwrap()),
+                        old_owner_id: "user2.near".parse().unwrap(),
+                        new_owner_id: "user3.near".parse().unwrap(),
+                        token_ids: vec!["gold".to_string()],
+                        amounts: vec![24u128.into()],
+                        memo: None,
+                    },
+                ])
+                .to_event_string(),
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"user1.near","new_owner_id":"user2.near","token_ids":["meme"],"amounts":["1"],"memo":"have fun!"},{"authorized_id":"market.near","old_owner_id":"user2.near","new_owner_id":"user3.near","token_ids":["gold"],"amounts":["24"]}]}"#,
+            );
+        }
+
+        #[test]
+        fn burn() {
+            assert_eq!(
+                Nep245Event::MtBurn(vec![MtBurnData {
+                    owner_id: "foundation.near".parse().unwrap(),
+                    authorized_id: None,
+                    token_ids: vec!["aurora".to_string()],
+                    amounts: vec![1u128.into()],
+                    memo: None,
+                }])
+                .to_event_string(),
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"foundation.near","token_ids":["aurora"],"amounts":["1"]}]}"#,
+            );
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    TotalSupply(&'a str),
+    Balance(&'a str, &'a AccountId),
+}
+
+/// Contracts may implement this trait to inject code into NEP-245 functions.
+///
+/// `T` is an optional value for passing state between different lifecycle
+/// hooks, as in [`crate::standard::nep141::Nep141Hook`].
+pub trait Nep245Hook<T: Default = ()> {
+    /// Executed before a token transfer is conducted
+    ///
+    /// May return an optional state value which will be passed along to the
+    /// following `after_transfer`.
+    fn before_transfer(&mut self, _transfer: &Nep245Transfer) -> T {
+        Default::default()
+    }
+
+    /// Executed after a token transfer is conducted
+    ///
+    /// Receives the state value returned by `before_transfer`.
+    fn after_transfer(&mut self, _transfer: &Nep245Transfer, _state: T) {}
+}
+
+/// Transfer metadata generic over all types of transfer (`mt_transfer`,
+/// `mt_batch_transfer`, and their `_call` variants).
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct Nep245Transfer {
+    /// Sender's account ID
+    pub sender_id: AccountId,
+    /// Receiver's account ID
+    pub receiver_id: AccountId,
+    /// Transferred tokens, as `(token_id, amount)`
+    pub tokens: Vec<(TokenId, u128)>,
+    /// Optional memo string
+    pub memo: Option<String>,
+    /// Message passed to contract located at `receiver_id`
+    pub msg: Option<String>,
+}
+
+impl Nep245Transfer {
+    /// Returns `true` if this transfer comes from a `mt_transfer_call` or
+    /// `mt_batch_transfer_call` call, `false` otherwise
+    pub fn is_transfer_call(&self) -> bool {
+        self.msg.is_some()
+    }
+
+    /// Requires a deposit of exactly one yoctoNEAR, and describes a transfer
+    /// from the predecessor. Shared by the transfer implementations
+    /// generated by `#[derive(Nep245)]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `token_ids` and `amounts` differ in length.
+    #[inline(never)]
+    pub fn from_predecessor(
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        memo: Option<String>,
+    ) -> Self {
+        near_sdk::assert_one_yocto();
+        require!(
+            token_ids.len() == amounts.len(),
+            message!("MT8", "Token IDs and amounts differ in length"),
+        );
+
+        Self {
+            sender_id: env::predecessor_account_id(),
+            receiver_id,
+            tokens: token_ids
+                .into_iter()
+                .zip(amounts.into_iter().map(u128::from))
+                .collect(),
+            memo,
+            msg: None,
+        }
+    }
+}
+
+fn checked_add_in(
+    transaction: &mut SlotTransaction,
+    slot: &Slot<u128>,
+    amount: u128,
+    message: &str,
+) {
+    let value = transaction.read(slot).unwrap_or(0);
+    match value.checked_add(amount) {
+        Some(value) => transaction.write(slot, &value),
+        None => env::panic_str(message),
+    }
+}
+
+fn checked_sub_in(
+    transaction: &mut SlotTransaction,
+    slot: &Slot<u128>,
+    amount: u128,
+    message: &str,
+) {
+    let value = transaction.read(slot).unwrap_or(0);
+    match value.checked_sub(amount) {
+        Some(value) => transaction.write(slot, &value),
+        None => env::panic_str(message),
+    }
+}
+
+fn split_tokens(tokens: &[(TokenId, u128)]) -> (Vec<TokenId>, Vec<U128>) {
+    tokens
+        .iter()
+        .map(|(token_id, amount)| (token_id.clone(), U128(*amount)))
+        .unzip()
+}
+
+/// Non-public implementations of functions for managing multi tokens.
+///
+/// Functions taking many tokens as `(token_id, amount)` write each balance
+/// and supply only once, and a token ID may appear more than once.
+pub trait Nep245Controller {
+    /// Root storage slot
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Nep245)
+    }
+
+    /// Slot for the balance of a token of an account
+    fn slot_balance(token_id: &str, account_id: &AccountId) -> Slot<u128> {
+        Self::root().field_ref(&StorageKey::Balance(token_id, account_id))
+    }
+
+    /// Slot for the total supply of a token
+    fn slot_total_supply(token_id: &str) -> Slot<u128> {
+        Self::root().field_ref(&StorageKey::TotalSupply(token_id))
+    }
+
+    /// Get the balance of a token of an account. Returns 0 if the account
+    /// does not hold the token.
+    fn balance_of(token_id: &str, account_id: &AccountId) -> u128 {
+        read_u128_or_zero(&Self::slot_balance(token_id, account_id))
+    }
+
+    /// Get the total circulating supply of a token.
+    fn total_supply(token_id: &str) -> u128 {
+        read_u128_or_zero(&Self::slot_total_supply(token_id))
+    }
+
+    /// Increases the balances of tokens of an account. Updates the total
+    /// supply of each token. No event emission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a balance or a total supply would overflow.
+    fn deposit_unchecked(&mut self, account_id: &AccountId, tokens: &[(TokenId, u128)]) {
+        let mut transaction = SlotTransaction::new();
+
+        for (token_id, amount) in tokens {
+            if *amount != 0 {
+                checked_add_in(
+                    &mut transaction,
+                    &Self::slot_balance(token_id, account_id),
+                    *amount,
+                    message!("MT4", "Balance overflow"),
+                );
+                checked_add_in(
+                    &mut transaction,
+                    &Self::slot_total_supply(token_id),
+                    *amount,
+                    message!("MT5", "Total supply overflow"),
+                );
+            }
+        }
+
+        transaction.commit();
+    }
+
+    /// Removes tokens from an account. Updates the total supply of each
+    /// token. No event emission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the balance of `account_id` or the total supply of a token
+    /// is less than the amount removed.
+    fn withdraw_unchecked(&mut self, account_id: &AccountId, tokens: &[(TokenId, u128)]) {
+        let mut transaction = SlotTransaction::new();
+
+        for (token_id, amount) in tokens {
+            if *amount != 0 {
+                checked_sub_in(
+                    &mut transaction,
+                    &Self::slot_balance(token_id, account_id),
+                    *amount,
+                    message!("MT2", "Balance underflow"),
+                );
+                checked_sub_in(
+                    &mut transaction,
+                    &Self::slot_total_supply(token_id),
+                    *amount,
+                    message!("MT3", "Total supply underflow"),
+                );
+            }
+        }
+
+        transaction.commit();
+    }
+
+    /// Moves tokens from `sender_account_id` to `receiver_account_id`. No
+    /// change to supply. No event emission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the balance of `sender_account_id` of a token is less than
+    /// the amount transferred, or if the balance of `receiver_account_id`
+    /// would overflow.
+    fn transfer_unchecked(
+        &mut self,
+        sender_account_id: &AccountId,
+        receiver_account_id: &AccountId,
+        tokens: &[(TokenId, u128)],
+    ) {
+        let mut transaction = SlotTransaction::new();
+
+        for (token_id, amount) in tokens {
+            checked_sub_in(
+                &mut transaction,
+                &Self::slot_balance(token_id, sender_account_id),
+                *amount,
+                message!("MT7", "Sender balance underflow"),
+            );
+            checked_add_in(
+                &mut transaction,
+                &Self::slot_balance(token_id, receiver_account_id),
+                *amount,
+                message!("MT6", "Receiver balance overflow"),
+            );
+        }
+
+        transaction.commit();
+    }
+
+    /// Performs an NEP-245 token transfer, with event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep245Controller::transfer_unchecked`
+    fn transfer(
+        &mut self,
+        sender_account_id: AccountId,
+        receiver_account_id: AccountId,
+        tokens: Vec<(TokenId, u128)>,
+        memo: Option<String>,
+    ) {
+        self.transfer_unchecked(&sender_account_id, &receiver_account_id, &tokens);
+
+        let (token_ids, amounts) = split_tokens(&tokens);
+
+        Nep245Event::MtTransfer(vec![event::MtTransferData {
+            authorized_id: None,
+            old_owner_id: sender_account_id,
+            new_owner_id: receiver_account_id,
+            token_ids,
+            amounts,
+            memo,
+        }])
+        .emit();
+    }
+
+    /// Performs an NEP-245 token mint, with event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep245Controller::deposit_unchecked`
+    fn mint(&mut self, account_id: AccountId, tokens: Vec<(TokenId, u128)>, memo: Option<String>) {
+        self.deposit_unchecked(&account_id, &tokens);
+
+        let (token_ids, amounts) = split_tokens(&tokens);
+
+        Nep245Event::MtMint(vec![event::MtMintData {
+            owner_id: account_id,
+            token_ids,
+            amounts,
+            memo,
+        }])
+        .emit();
+    }
+
+    /// Performs an NEP-245 token burn, with event emission.
+    ///
+    /// # Panics
+    ///
+    /// See: `Nep245Controller::withdraw_unchecked`
+    fn burn(&mut self, account_id: AccountId, tokens: Vec<(TokenId, u128)>, memo: Option<String>) {
+        self.withdraw_unchecked(&account_id, &tokens);
+
+        let (token_ids, amounts) = split_tokens(&tokens);
+
+        Nep245Event::MtBurn(vec![event::MtBurnData {
+            owner_id: account_id,
+            authorized_id: None,
+            token_ids,
+            amounts,
+            memo,
+        }])
+        .emit();
+    }
+
+    /// Performs an NEP-245 token transfer call, with event emission. The
+    /// promise resolves to the amount of each token used by the receiver.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gas_allowance` < `GAS_FOR_MT_TRANSFER_CALL`.
+    ///
+    /// See also: `Nep245Controller::transfer`
+    fn transfer_call(
+        &mut self,
+        sender_account_id: AccountId,
+        receiver_account_id: AccountId,
+        tokens: Vec<(TokenId, u128)>,
+        memo: Option<String>,
+        msg: String,
+        gas_allowance: Gas,
+    ) -> Promise {
+        require!(
+            gas_allowance >= GAS_FOR_MT_TRANSFER_CALL,
+            MORE_GAS_FAIL_MESSAGE,
+        );
+
+        let (token_ids, amounts) = split_tokens(&tokens);
+        let previous_owner_ids = vec![sender_account_id.clone(); tokens.len()];
+
+        self.transfer(
+            sender_account_id.clone(),
+            receiver_account_id.clone(),
+            tokens,
+            memo,
+        );
+
+        let receiver_gas = Gas(gas_allowance.0 - GAS_FOR_MT_TRANSFER_CALL.0);
+
+        // Initiating receiver's call and the callback
+        ext_nep245_receiver::ext(receiver_account_id.clone())
+            .with_static_gas(receiver_gas)
+            .mt_on_transfer(
+                sender_account_id,
+                previous_owner_ids.clone(),
+                token_ids.clone(),
+                amounts.clone(),
+                msg,
+            )
+            .then(
+                ext_nep245_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .mt_resolve_transfer(
+                        previous_owner_ids,
+                        receiver_account_id,
+                        token_ids,
+                        amounts,
+                    ),
+            )
+    }
+
+    /// Resolves an NEP-245 `mt_transfer_call` promise chain: refunds the
+    /// tokens that the receiver did not use to their previous owners, at most
+    /// the receiver's current balance of each token. Returns the amount of
+    /// each token used.
+    ///
+    /// If `mt_on_transfer` failed, or did not return one amount per token,
+    /// all of the tokens are refunded.
+    fn resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        tokens: Vec<(TokenId, u128)>,
+    ) -> Vec<u128> {
+        let unused_amounts = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => serde_json::from_slice::<Vec<U128>>(&value)
+                .ok()
+                .filter(|unused| unused.len() == tokens.len()),
+            PromiseResult::Failed => None,
+        };
+
+        let mut transaction = SlotTransaction::new();
+        let mut refunds: Vec<event::MtTransferData> = vec![];
+
+        let used_amounts = tokens
+            .into_iter()
+            .zip(previous_owner_ids)
+            .enumerate()
+            .map(|(i, ((token_id, amount), previous_owner_id))| {
+                let unused_amount = unused_amounts
+                    .as_ref()
+                    .map_or(amount, |unused| std::cmp::min(amount, unused[i].0));
+
+                let slot_receiver = Self::slot_balance(&token_id, &receiver_id);
+                let receiver_balance = transaction.read(&slot_receiver).unwrap_or(0);
+                let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+
+                if refund_amount > 0 {
+                    transaction.write(&slot_receiver, &(receiver_balance - refund_amount));
+                    checked_add_in(
+                        &mut transaction,
+                        &Self::slot_balance(&token_id, &previous_owner_id),
+                        refund_amount,
+                        message!("MT6", "Receiver balance overflow"),
+                    );
+
+                    // One event entry per previous owner
+                    match refunds
+                        .iter_mut()
+                        .find(|refund| refund.new_owner_id == previous_owner_id)
+                    {
+                        Some(refund) => {
+                            refund.token_ids.push(token_id);
+                            refund.amounts.push(refund_amount.into());
+                        }
+                        None => refunds.push(event::MtTransferData {
+                            authorized_id: None,
+                            old_owner_id: receiver_id.clone(),
+                            new_owner_id: previous_owner_id,
+                            token_ids: vec![token_id],
+                            amounts: vec![refund_amount.into()],
+                            memo: None,
+                        }),
+                    }
+                }
+
+                amount - refund_amount
+            })
+            .collect();
+
+        transaction.commit();
+
+        if !refunds.is_empty() {
+            Nep245Event::MtTransfer(refunds).emit();
+        }
+
+        used_amounts
+    }
+}
+
+/// A contract that may be the recipient of an `mt_transfer_call` function
+/// call.
+#[ext_contract(ext_nep245_receiver)]
+pub trait Nep245Receiver {
+    /// Function that is called in an `mt_transfer_call` promise chain.
+    /// Returns the number of tokens of each ID that are not "used", that is,
+    /// those that the token contract will attempt to refund to their previous
+    /// owners.
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+/// Multi token contract callback after `mt_transfer_call` execution.
+#[ext_contract(ext_nep245_resolver)]
+pub trait Nep245Resolver {
+    /// Callback, last in `mt_transfer_call` promise chain. Returns the amount
+    /// of each token used by the receiver.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+    ) -> Vec<U128>;
+}
+
+/// Externally-accessible NEP-245-compatible multi token interface.
+#[ext_contract(ext_nep245)]
+pub trait Nep245 {
+    /// Performs a transfer of a single token
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    );
+
+    /// Performs a transfer of many tokens to the same receiver
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    );
+
+    /// Performs a transfer of a single token, then initiates a promise chain
+    /// that calls `mt_on_transfer` on the receiving account, followed by
+    /// `mt_resolve_transfer` on the original token contract (this contract).
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> Promise;
+
+    /// Like `mt_transfer_call`, for many tokens
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> Promise;
+
+    /// Returns the amount of a token controlled by `account_id`
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+    /// Returns the amounts of many tokens controlled by `account_id`
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+    /// Returns the current total amount of a token tracked by the contract
+    fn mt_total_supply(&self, token_id: TokenId) -> U128;
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{AccountId, PromiseResult};
+
+    use super::{event::MtTransferData, Nep245Controller, Nep245Event, TokenId};
+    use crate::testing::{events, TestEnv};
+
+    struct Contract;
+
+    impl Nep245Controller for Contract {}
+
+    fn tokens(tokens: &[(&str, u128)]) -> Vec<(TokenId, u128)> {
+        tokens
+            .iter()
+            .map(|(token_id, amount)| (token_id.to_string(), *amount))
+            .collect()
+    }
+
+    #[test]
+    fn balances_per_token() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), tokens(&[("gold", 100), ("sword", 1)]), None);
+        contract.transfer(alice.clone(), bob.clone(), tokens(&[("gold", 30)]), None);
+        contract.burn(alice.clone(), tokens(&[("sword", 1)]), None);
+
+        assert_eq!(Contract::balance_of("gold", &alice), 70);
+        assert_eq!(Contract::balance_of("gold", &bob), 30);
+        assert_eq!(Contract::balance_of("sword", &alice), 0);
+        assert_eq!(Contract::total_supply("gold"), 100);
+        assert_eq!(Contract::total_supply("sword"), 0);
+        assert_eq!(Contract::total_supply("silver"), 0);
+    }
+
+    #[test]
+    fn duplicate_token_ids() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), tokens(&[("gold", 10), ("gold", 5)]), None);
+        contract.transfer(
+            alice.clone(),
+            bob.clone(),
+            tokens(&[("gold", 7), ("gold", 8)]),
+            None,
+        );
+
+        assert_eq!(Contract::balance_of("gold", &alice), 0);
+        assert_eq!(Contract::balance_of("gold", &bob), 15);
+        assert_eq!(Contract::total_supply("gold"), 15);
+    }
+
+    #[test]
+    #[should_panic = "Sender balance underflow"]
+    fn duplicate_token_ids_underflow() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), tokens(&[("gold", 10)]), None);
+        contract.transfer_unchecked(&alice, &bob, &tokens(&[("gold", 7), ("gold", 7)]));
+    }
+
+    #[test]
+    fn self_transfer() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), tokens(&[("gold", 10)]), None);
+        contract.transfer(alice.clone(), alice.clone(), tokens(&[("gold", 7)]), None);
+
+        assert_eq!(Contract::balance_of("gold", &alice), 10);
+        assert_eq!(Contract::total_supply("gold"), 10);
+    }
+
+    /// Sets up the state of an `mt_batch_transfer_call` from alice to bob of
+    /// 100 gold and 1 sword, as `mt_resolve_transfer` finds it, with the
+    /// given result of `mt_on_transfer`.
+    fn resolve_setup(result: PromiseResult) -> (TestEnv, Contract, AccountId, AccountId) {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        let mut env = TestEnv::new();
+        let mut contract = Contract;
+        contract.deposit_unchecked(&alice, &tokens(&[("gold", 150), ("sword", 1)]));
+        contract.transfer_unchecked(&alice, &bob, &tokens(&[("gold", 100), ("sword", 1)]));
+
+        env.set_promise_results(vec![result]);
+
+        (env, contract, alice, bob)
+    }
+
+    fn resolve(contract: &mut Contract, alice: &AccountId, bob: &AccountId) -> Vec<u128> {
+        contract.resolve_transfer(
+            vec![alice.clone(), alice.clone()],
+            bob.clone(),
+            tokens(&[("gold", 100), ("sword", 1)]),
+        )
+    }
+
+    fn refund_event(alice: &AccountId, bob: &AccountId, refunds: &[(&str, u128)]) -> Nep245Event {
+        Nep245Event::MtTransfer(vec![MtTransferData {
+            authorized_id: None,
+            old_owner_id: bob.clone(),
+            new_owner_id: alice.clone(),
+            token_ids: refunds.iter().map(|(id, _)| id.to_string()).collect(),
+            amounts: refunds.iter().map(|(_, amount)| (*amount).into()).collect(),
+            memo: None,
+        }])
+    }
+
+    #[test]
+    fn resolve_transfer_partial_refund() {
+        let (env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(br#"["30","0"]"#.to_vec()));
+
+        // 30 gold unused, refunded
+        assert_eq!(resolve(&mut contract, &alice, &bob), vec![70, 1]);
+        assert_eq!(Contract::balance_of("gold", &alice), 80);
+        assert_eq!(Contract::balance_of("gold", &bob), 70);
+        assert_eq!(Contract::balance_of("sword", &bob), 1);
+        assert_eq!(
+            env.events_of::<Nep245Event>(),
+            vec![refund_event(&alice, &bob, &[("gold", 30)])],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_unused_capped_at_amount() {
+        let (_env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(br#"["1000","1000"]"#.to_vec()));
+
+        assert_eq!(resolve(&mut contract, &alice, &bob), vec![0, 0]);
+        assert_eq!(Contract::balance_of("gold", &alice), 150);
+        assert_eq!(Contract::balance_of("sword", &alice), 1);
+        assert_eq!(Contract::balance_of("gold", &bob), 0);
+    }
+
+    #[test]
+    fn resolve_transfer_wrong_length() {
+        let (env, mut contract, alice, bob) =
+            resolve_setup(PromiseResult::Successful(br#"["0"]"#.to_vec()));
+
+        // Full refund
+        assert_eq!(resolve(&mut contract, &alice, &bob), vec![0, 0]);
+        assert_eq!(Contract::balance_of("gold", &alice), 150);
+        assert_eq!(Contract::balance_of("sword", &alice), 1);
+        assert_eq!(
+            env.events_of::<Nep245Event>(),
+            vec![refund_event(&alice, &bob, &[("gold", 100), ("sword", 1)])],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_failed() {
+        let (env, mut contract, alice, bob) = resolve_setup(PromiseResult::Failed);
+
+        // Full refund
+        assert_eq!(resolve(&mut contract, &alice, &bob), vec![0, 0]);
+        assert_eq!(Contract::balance_of("gold", &alice), 150);
+        assert_eq!(Contract::balance_of("gold", &bob), 0);
+        assert_eq!(Contract::balance_of("sword", &bob), 0);
+        assert_eq!(
+            env.events_of::<Nep245Event>(),
+            vec![refund_event(&alice, &bob, &[("gold", 100), ("sword", 1)])],
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_refund_capped_at_receiver_balance() {
+        let (_env, mut contract, alice, bob) = resolve_setup(PromiseResult::Failed);
+        let charlie: AccountId = "charlie.near".parse().unwrap();
+
+        // The receiver spent some of the tokens before the resolution
+        contract.transfer_unchecked(&bob, &charlie, &tokens(&[("gold", 60), ("sword", 1)]));
+
+        assert_eq!(resolve(&mut contract, &alice, &bob), vec![60, 1]);
+        assert_eq!(Contract::balance_of("gold", &alice), 90);
+        assert_eq!(Contract::balance_of("gold", &bob), 0);
+        assert_eq!(Contract::balance_of("gold", &charlie), 60);
+        assert_eq!(Contract::balance_of("sword", &charlie), 1);
+    }
+
+    #[test]
+    fn emitted_events_replay() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+
+        TestEnv::new();
+
+        let mut contract = Contract;
+        contract.mint(alice.clone(), tokens(&[("gold", 10), ("sword", 1)]), None);
+        contract.transfer(
+            alice.clone(),
+            bob.clone(),
+            tokens(&[("gold", 3), ("sword", 1)]),
+            Some("memo".to_string()),
+        );
+        contract.burn(bob, tokens(&[("gold", 3)]), None);
+
+        let logs = near_sdk::test_utils::get_logs();
+        let events = events::replay::<Nep245Event>(&logs).unwrap();
+
+        assert_eq!(events.len(), 3);
+    }
+}
@@ -62,6 +62,7 @@ pub trait ToEventLog {
 /// NEP-297 Event Log Data
 /// <https://github.com/near/NEPs/blob/master/neps/nep-0297.md#specification>
 #[derive(Serialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
 pub struct EventLog<T> {
     /// Name of the event standard, e.g. "nep171"
     pub standard: &'static str,
@@ -0,0 +1,184 @@
+//! NEP-330 contract source metadata implementation
+//! <https://github.com/near/NEPs/blob/master/neps/nep-0330.md>
+//!
+//! The `ContractMetadata` derive reports the standards of the components
+//! that the contract derives (e.g. `Nep141`, `FungibleToken`, `Nep245`).
+//! Each of those derives registers its standard by implementing
+//! [`ImplementsStandard`] for the contract, and the `ContractMetadata`
+//! derive checks, for every standard of [`standards`], whether the contract
+//! implements it.
+#![allow(missing_docs)] // ext_contract doesn't play nice with #![warn(missing_docs)]
+
+use std::marker::PhantomData;
+
+use near_sdk::ext_contract;
+use serde::{Deserialize, Serialize};
+
+/// Version of the NEP-330 spec
+pub const NEP330_VERSION: &str = "1.1.0";
+
+/// NEP-330-compatible contract source metadata
+#[derive(Deserialize, Serialize, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct ContractSourceMetadata {
+    /// Version of the source code, e.g. a crate version or a commit hash
+    pub version: Option<String>,
+    /// Where the source code can be found, e.g. a repository URL
+    pub link: Option<String>,
+    /// Standards implemented by the contract
+    pub standards: Vec<Standard>,
+}
+
+/// A standard implemented by a contract
+#[derive(Deserialize, Serialize, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct Standard {
+    /// Name of the standard, e.g. `"nep141"`
+    pub standard: String,
+    /// Version of the standard, e.g. `"1.0.0"`
+    pub version: String,
+}
+
+impl Standard {
+    /// Creates a standard entry
+    pub fn new(standard: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            standard: standard.into(),
+            version: version.into(),
+        }
+    }
+}
+
+/// A standard that component derives register on a contract
+pub trait ComponentStandard {
+    /// Name of the standard, e.g. `"nep141"`
+    const STANDARD: &'static str;
+    /// Version of the standard implemented by the component
+    const VERSION: &'static str;
+}
+
+/// Standards implemented by the components of this crate
+pub mod standards {
+    use super::ComponentStandard;
+
+    macro_rules! standards {
+        ($($(#[$m:meta])* $name:ident = ($standard:literal, $version:literal),)*) => {
+            $(
+                $(#[$m])*
+                #[derive(Clone, Copy, Debug)]
+                pub struct $name;
+
+                impl ComponentStandard for $name {
+                    const STANDARD: &'static str = $standard;
+                    const VERSION: &'static str = $version;
+                }
+            )*
+        };
+    }
+
+    standards! {
+        /// NEP-141 fungible token core
+        Nep141 = ("nep141", "1.0.0"),
+        /// NEP-148 fungible token metadata
+        Nep148 = ("nep148", "1.0.0"),
+        /// NEP-245 multi token core
+        Nep245 = ("nep245", "1.0.0"),
+    }
+}
+
+/// Registers standard `S` as implemented by a contract. Implemented by the
+/// derive of the component that implements `S`, e.g. `Nep141` implements
+/// `ImplementsStandard<standards::Nep141>`.
+pub trait ImplementsStandard<S: ComponentStandard> {}
+
+/// Checks whether a contract implements a standard, for the
+/// `ContractMetadata` derive. A derive macro cannot see the other derives of
+/// the contract, so the check is made where the contract type is known, by
+/// method resolution: [`Registered::standard`] applies if `T` implements
+/// [`ImplementsStandard<S>`], and [`Unregistered::standard`] (which takes one
+/// more reference) otherwise. This only works if `T` is a concrete type, so
+/// generic contracts must list their standards with
+/// `#[contract_metadata(standard(...))]`.
+///
+/// ```
+/// use near_sdk_contract_tools::standard::nep330::{
+///     standards, ImplementsStandard, Probe, Registered as _, Unregistered as _,
+/// };
+///
+/// struct Contract;
+///
+/// impl ImplementsStandard<standards::Nep141> for Contract {}
+///
+/// assert!((&Probe::<Contract, standards::Nep141>::new()).standard().is_some());
+/// assert!((&Probe::<Contract, standards::Nep245>::new()).standard().is_none());
+/// ```
+pub struct Probe<T: ?Sized, S>(PhantomData<S>, PhantomData<T>);
+
+impl<T: ?Sized, S> Probe<T, S> {
+    /// Creates a probe
+    pub fn new() -> Self {
+        Self(PhantomData, PhantomData)
+    }
+}
+
+impl<T: ?Sized, S> Default for Probe<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// See: [`Probe`]
+pub trait Registered {
+    /// The standard, since the contract implements it
+    fn standard(&self) -> Option<Standard>;
+}
+
+impl<T: ImplementsStandard<S> + ?Sized, S: ComponentStandard> Registered for Probe<T, S> {
+    fn standard(&self) -> Option<Standard> {
+        Some(Standard::new(S::STANDARD, S::VERSION))
+    }
+}
+
+/// See: [`Probe`]
+pub trait Unregistered {
+    /// Nothing, since the contract does not implement the standard
+    fn standard(&self) -> Option<Standard>;
+}
+
+impl<T: ?Sized, S> Unregistered for &Probe<T, S> {
+    fn standard(&self) -> Option<Standard> {
+        None
+    }
+}
+
+/// Contract that supports the NEP-330 contract source metadata standard
+#[ext_contract(ext_nep330)]
+pub trait Nep330 {
+    /// Returns the source metadata of this contract.
+    fn contract_source_metadata(&self) -> ContractSourceMetadata;
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::serde_json::{self, json};
+
+    use super::{ContractSourceMetadata, Standard};
+
+    #[test]
+    fn json_shape() {
+        let metadata = ContractSourceMetadata {
+            version: Some("1.0.0".into()),
+            link: None,
+            standards: vec![Standard::new("nep330", "1.1.0")],
+        };
+
+        assert_eq!(
+            serde_json::to_value(metadata).unwrap(),
+            json!({
+                "version": "1.0.0",
+                "link": null,
+                "standards": [{ "standard": "nep330", "version": "1.1.0" }],
+            }),
+        );
+    }
+}
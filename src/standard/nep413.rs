@@ -0,0 +1,558 @@
+//! NEP-413 signed message verification
+//! <https://github.com/near/NEPs/blob/master/neps/nep-0413.md>
+//!
+//! Wallets sign a [`Payload`] with `signMessage` by signing the SHA-256 hash
+//! of the Borsh serialization of [`TAG`] followed by the payload, so that the
+//! signature cannot be replayed as a transaction. [`verify`] checks such a
+//! signature, and [`Nep413Controller`] additionally checks that the message
+//! is meant for the contract, and that its nonce has not been used before.
+//!
+//! Nonces are random by default, and every used nonce is kept in storage.
+//! Contracts that also implement [`Nonce`] may instead accept messages whose
+//! nonce is the next `u64` nonce of the signer (see
+//! [`Nep413Controller::use_sequenced_signed_message`]), which only costs one
+//! storage slot per signer.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, AccountId, CurveType, PublicKey,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    nonce::{Nonce, NonceError},
+    slot::Slot,
+    DefaultStorageKey,
+};
+
+/// Prefix of signed payloads, `2^31 + 413`. Transactions cannot start with
+/// it, so a signed message is never a valid transaction.
+pub const TAG: u32 = (1 << 31) + 413;
+
+const WRONG_RECIPIENT_FAIL_MESSAGE: &str = message!("SIG1", "Message is for another recipient");
+const INVALID_SIGNATURE_FAIL_MESSAGE: &str = message!("SIG2", "Invalid signature");
+const NONCE_USED_FAIL_MESSAGE: &str = message!("SIG3", "Nonce already used");
+const NONCE_NOT_U64_FAIL_MESSAGE: &str = message!("SIG4", "Nonce is not a 64-bit integer");
+
+/// A message signed by a wallet with `signMessage`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Payload {
+    /// The message that was signed
+    pub message: String,
+    /// Unique value, to prevent replays. Base64-encoded in JSON.
+    #[serde(with = "base64_nonce")]
+    #[cfg_attr(feature = "abi", schemars(with = "String"))]
+    pub nonce: [u8; 32],
+    /// Account ID of the intended recipient, e.g. the contract
+    pub recipient: String,
+    /// URL the wallet calls back after signing, if any
+    pub callback_url: Option<String>,
+}
+
+mod base64_nonce {
+    use near_sdk::base64;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(nonce: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(nonce))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        base64::decode(String::deserialize(deserializer)?)
+            .map_err(D::Error::custom)?
+            .try_into()
+            .map_err(|_| D::Error::custom("Nonce must be 32 bytes"))
+    }
+}
+
+impl Payload {
+    /// The bytes that are hashed: [`TAG`] followed by the payload, both
+    /// Borsh-serialized
+    pub fn to_signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = TAG.to_le_bytes().to_vec();
+        // Serializing into a `Vec` cannot fail
+        BorshSerialize::serialize(self, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// The SHA-256 hash of [`Payload::to_signed_bytes`], which is what the
+    /// wallet signs
+    pub fn hash(&self) -> [u8; 32] {
+        env::sha256_array(&self.to_signed_bytes())
+    }
+
+    /// The nonce as a `u64`, if it is one: a 256-bit big-endian integer
+    /// lower than 2^64
+    pub fn nonce_u64(&self) -> Option<u64> {
+        let (high, low) = self.nonce.split_at(24);
+        if high.iter().any(|b| *b != 0) {
+            return None;
+        }
+        // `low` is 8 bytes long
+        Some(u64::from_be_bytes(low.try_into().unwrap()))
+    }
+
+    /// Sets the nonce to a `u64`, as read by [`Payload::nonce_u64`]
+    pub fn with_nonce_u64(self, nonce: u64) -> Self {
+        let mut bytes = [0; 32];
+        bytes[24..].copy_from_slice(&nonce.to_be_bytes());
+        Self {
+            nonce: bytes,
+            ..self
+        }
+    }
+}
+
+/// Verifies that `signature` is the signature of `payload` by
+/// `public_key`. Only ED25519 keys are supported: returns `false` for other
+/// keys, and for signatures that are not 64 bytes long.
+pub fn verify(payload: &Payload, signature: &[u8], public_key: &PublicKey) -> bool {
+    if public_key.curve_type() != CurveType::ED25519 {
+        return false;
+    }
+
+    match (
+        <&[u8; 64]>::try_from(signature),
+        public_key.as_bytes()[1..].try_into(),
+    ) {
+        (Ok(signature), Ok(public_key)) => ed25519_verify(signature, &payload.hash(), public_key),
+        _ => false,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    // Host function, not exposed by near-sdk 4
+    extern "C" {
+        fn ed25519_verify(
+            sig_len: u64,
+            sig_ptr: u64,
+            msg_len: u64,
+            msg_ptr: u64,
+            pub_key_len: u64,
+            pub_key_ptr: u64,
+        ) -> u64;
+    }
+
+    // SAFETY: the pointers and lengths are those of live slices
+    unsafe {
+        ed25519_verify(
+            signature.len() as _,
+            signature.as_ptr() as _,
+            message.len() as _,
+            message.as_ptr() as _,
+            public_key.len() as _,
+            public_key.as_ptr() as _,
+        ) == 1
+    }
+}
+
+// Only the NEAR runtime provides the host function, and the mocked blockchain
+// of near-sdk does not
+#[cfg(not(target_arch = "wasm32"))]
+fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    use ed25519_dalek::Verifier;
+
+    match (
+        ed25519_dalek::PublicKey::from_bytes(public_key),
+        ed25519_dalek::Signature::from_bytes(signature),
+    ) {
+        (Ok(public_key), Ok(signature)) => public_key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+/// Reasons a signed message is rejected
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum Nep413Error {
+    /// The recipient of the message is not the current account
+    #[error("{}", WRONG_RECIPIENT_FAIL_MESSAGE)]
+    WrongRecipient,
+    /// The signature does not match the payload and public key
+    #[error("{}", INVALID_SIGNATURE_FAIL_MESSAGE)]
+    InvalidSignature,
+    /// A message with the same nonce was already used
+    #[error("{}", NONCE_USED_FAIL_MESSAGE)]
+    NonceUsed,
+    /// The nonce of a sequenced message is not a `u64`
+    #[error("{}", NONCE_NOT_U64_FAIL_MESSAGE)]
+    NonceNotU64,
+    /// The nonce of a sequenced message was rejected by [`Nonce`]
+    #[error(transparent)]
+    Nonce(#[from] NonceError),
+}
+
+/// Verifies signed messages meant for the contract, and keeps track of their
+/// nonces, so that each message is accepted only once.
+///
+/// Used nonces are kept in storage forever, so the contract pays for the
+/// storage of every message it accepts.
+pub trait Nep413Controller {
+    /// Root storage slot
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Nep413)
+    }
+
+    /// Slot that exists if `nonce` was used
+    fn slot_nonce(nonce: &[u8; 32]) -> Slot<()> {
+        Self::root().field(&nonce[..])
+    }
+
+    /// Whether a message with `nonce` was already accepted
+    fn is_nonce_used(nonce: &[u8; 32]) -> bool {
+        Self::slot_nonce(nonce).exists()
+    }
+
+    /// Accepts a message signed by `public_key`, if it is meant for the
+    /// current account, its signature is valid, and its nonce was not used
+    /// before. Records the nonce as used.
+    ///
+    /// This does not check who `public_key` belongs to.
+    fn use_signed_message(
+        &mut self,
+        payload: &Payload,
+        signature: &[u8],
+        public_key: &PublicKey,
+    ) -> Result<(), Nep413Error> {
+        if payload.recipient != env::current_account_id().as_str() {
+            return Err(Nep413Error::WrongRecipient);
+        }
+
+        if !verify(payload, signature, public_key) {
+            return Err(Nep413Error::InvalidSignature);
+        }
+
+        let mut slot = Self::slot_nonce(&payload.nonce);
+        if slot.exists() {
+            return Err(Nep413Error::NonceUsed);
+        }
+        slot.write(&());
+
+        Ok(())
+    }
+
+    /// Like [`Nep413Controller::use_signed_message`], but panics if the
+    /// message is rejected.
+    fn require_signed_message(
+        &mut self,
+        payload: &Payload,
+        signature: &[u8],
+        public_key: &PublicKey,
+    ) {
+        self.use_signed_message(payload, signature, public_key)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Accepts a message signed by `signer_id` with `public_key`, if it is
+    /// meant for the current account, its signature is valid, and its nonce
+    /// (see [`Payload::nonce_u64`]) may be used by `signer_id` according to
+    /// [`Nonce`]. Records the nonce as used by `signer_id`.
+    ///
+    /// The nonces of sequenced messages are not recorded by
+    /// [`Nep413Controller::is_nonce_used`], so a contract should accept
+    /// either sequenced or unsequenced messages, not both.
+    ///
+    /// This does not check that `public_key` belongs to `signer_id`.
+    fn use_sequenced_signed_message(
+        &mut self,
+        signer_id: &AccountId,
+        payload: &Payload,
+        signature: &[u8],
+        public_key: &PublicKey,
+    ) -> Result<(), Nep413Error>
+    where
+        Self: Nonce,
+    {
+        if payload.recipient != env::current_account_id().as_str() {
+            return Err(Nep413Error::WrongRecipient);
+        }
+
+        let nonce = payload.nonce_u64().ok_or(Nep413Error::NonceNotU64)?;
+
+        if !verify(payload, signature, public_key) {
+            return Err(Nep413Error::InvalidSignature);
+        }
+
+        self.use_nonce(signer_id, nonce)?;
+
+        Ok(())
+    }
+
+    /// Like [`Nep413Controller::use_sequenced_signed_message`], but panics if
+    /// the message is rejected.
+    fn require_sequenced_signed_message(
+        &mut self,
+        signer_id: &AccountId,
+        payload: &Payload,
+        signature: &[u8],
+        public_key: &PublicKey,
+    ) where
+        Self: Nonce,
+    {
+        self.use_sequenced_signed_message(signer_id, payload, signature, public_key)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        base64, serde_json, test_utils::VMContextBuilder, testing_env, AccountId, PublicKey,
+    };
+
+    use super::{verify, Nep413Controller, Nep413Error, Payload};
+    use crate::nonce::{Nonce, NonceError};
+
+    // Vectors computed independently of this crate, with Python (Borsh
+    // serialization and SHA-256) and OpenSSL (ED25519 signatures), using the
+    // key with seed `sha256("near-sdk-contract-tools nep413")`.
+
+    const PUBLIC_KEY: &str = "ed25519:GZRZ1xs3tJN3wQPjLw1GLoT5Eojx9L8sQK4kNp9jsiKG";
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn public_key() -> PublicKey {
+        PUBLIC_KEY.parse().unwrap()
+    }
+
+    fn without_callback() -> (Payload, Vec<u8>) {
+        (
+            Payload {
+                message: "Hello NEAR!".to_string(),
+                nonce: [42; 32],
+                recipient: "alice.near".to_string(),
+                callback_url: None,
+            },
+            hex("5267c4be7362d89ae771656b18ab65163a146c3708202ae26d843806f7154140c7f0ec8911bef90f9cb35ba8e568fc481a2957d7512476623b4aacf9904cac04"),
+        )
+    }
+
+    fn with_callback() -> (Payload, Vec<u8>) {
+        let mut nonce = [0; 32];
+        for (i, b) in nonce.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        (
+            Payload {
+                message: "Approve request 7".to_string(),
+                nonce,
+                recipient: "contract.near".to_string(),
+                callback_url: Some("https://example.com/callback".to_string()),
+            },
+            hex("ef2a2a9952a83472d59e8ca8ade7d4fda8cdcc5c2f92af5136caed9e99a8478aca0811b910b7bd850399a0b17acdd13d5e3aa89d7d27ce51b71bb18501a8660b"),
+        )
+    }
+
+    #[test]
+    fn signed_bytes() {
+        assert_eq!(
+            without_callback().0.to_signed_bytes(),
+            hex("9d0100800b00000048656c6c6f204e454152212a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a0a000000616c6963652e6e65617200"),
+        );
+        assert_eq!(
+            with_callback().0.to_signed_bytes(),
+            hex("9d01008011000000417070726f766520726571756573742037000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f0d000000636f6e74726163742e6e656172011c00000068747470733a2f2f6578616d706c652e636f6d2f63616c6c6261636b"),
+        );
+    }
+
+    #[test]
+    fn hash() {
+        assert_eq!(
+            without_callback().0.hash().to_vec(),
+            hex("bdcca5cdabd8fea4e94ba9e1bf580b27cc6c2bf0db9c55dbeb463d8bc12ea1ee"),
+        );
+        assert_eq!(
+            with_callback().0.hash().to_vec(),
+            hex("d326d772a7a82a3735fe7f0da090d6797ff22ee864afc02818545edb1b3a90fd"),
+        );
+    }
+
+    #[test]
+    fn verify_signatures() {
+        for (payload, signature) in [without_callback(), with_callback()] {
+            assert!(verify(&payload, &signature, &public_key()));
+
+            let tampered = Payload {
+                message: format!("{}.", payload.message),
+                ..payload.clone()
+            };
+            assert!(!verify(&tampered, &signature, &public_key()));
+
+            assert!(!verify(&payload, &signature[..63], &public_key()));
+        }
+
+        let (payload, signature) = without_callback();
+        let other_key: PublicKey = "ed25519:GDMThB8VfQzCPxNBtivBXREsrUeKYoCqGCCjcqkVtNMf"
+            .parse()
+            .unwrap();
+        assert!(!verify(&payload, &signature, &other_key));
+
+        let secp256k1_key: PublicKey = "secp256k1:4HxKByB8vHF8pGdUzn6Bhf2959NQC5abfGSF13o8Ndv39kQMkqmcC1C6byRJVV5d8w2rLvwCZmdfj8Qr1tk3LvYq"
+            .parse()
+            .unwrap();
+        assert!(!verify(&payload, &signature, &secp256k1_key));
+    }
+
+    #[test]
+    fn json() {
+        let (payload, _) = with_callback();
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "message": "Approve request 7",
+                "nonce": base64::encode(payload.nonce),
+                "recipient": "contract.near",
+                "callbackUrl": "https://example.com/callback",
+            }),
+        );
+        assert_eq!(serde_json::from_value::<Payload>(json).unwrap(), payload);
+    }
+
+    struct Contract;
+
+    impl Nep413Controller for Contract {}
+
+    impl Nonce for Contract {}
+
+    /// Signs `payload` with the key of [`PUBLIC_KEY`]
+    fn sign(payload: &Payload) -> Vec<u8> {
+        use ed25519_dalek::{ExpandedSecretKey, SecretKey};
+
+        let seed = near_sdk::env::sha256(b"near-sdk-contract-tools nep413");
+        let secret = SecretKey::from_bytes(&seed).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        assert_eq!(&public_key().as_bytes()[1..], public.as_bytes());
+
+        ExpandedSecretKey::from(&secret)
+            .sign(&payload.hash(), &public)
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn as_recipient(payload: &Payload) {
+        let recipient: AccountId = payload.recipient.parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(recipient)
+            .build());
+    }
+
+    #[test]
+    fn use_signed_message() {
+        let (payload, signature) = without_callback();
+        as_recipient(&payload);
+
+        assert!(!Contract::is_nonce_used(&payload.nonce));
+        assert_eq!(
+            Contract.use_signed_message(&payload, &signature, &public_key()),
+            Ok(()),
+        );
+        assert!(Contract::is_nonce_used(&payload.nonce));
+        assert_eq!(
+            Contract.use_signed_message(&payload, &signature, &public_key()),
+            Err(Nep413Error::NonceUsed),
+        );
+    }
+
+    #[test]
+    fn use_signed_message_rejected() {
+        let (payload, signature) = without_callback();
+        let (other, _) = with_callback();
+        as_recipient(&other);
+
+        assert_eq!(
+            Contract.use_signed_message(&payload, &signature, &public_key()),
+            Err(Nep413Error::WrongRecipient),
+        );
+
+        as_recipient(&payload);
+        let mut invalid = signature.clone();
+        invalid[0] ^= 1;
+
+        assert_eq!(
+            Contract.use_signed_message(&payload, &invalid, &public_key()),
+            Err(Nep413Error::InvalidSignature),
+        );
+        // Rejected messages do not use up their nonce
+        assert!(!Contract::is_nonce_used(&payload.nonce));
+    }
+
+    #[test]
+    fn nonce_u64() {
+        let (payload, _) = without_callback();
+        assert_eq!(payload.nonce_u64(), None);
+
+        let payload = payload.with_nonce_u64(0x0102_0304_0506_0708);
+        assert_eq!(payload.nonce[..24], [0; 24]);
+        assert_eq!(payload.nonce[24..], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(payload.nonce_u64(), Some(0x0102_0304_0506_0708));
+    }
+
+    #[test]
+    fn use_sequenced_signed_message() {
+        let signer: AccountId = "signer.near".parse().unwrap();
+        let (payload, signature) = without_callback();
+        as_recipient(&payload);
+
+        assert_eq!(
+            Contract.use_sequenced_signed_message(&signer, &payload, &signature, &public_key()),
+            Err(Nep413Error::NonceNotU64),
+        );
+
+        for nonce in [0, 1, 5] {
+            let payload = payload.clone().with_nonce_u64(nonce);
+            let signature = sign(&payload);
+
+            assert_eq!(
+                Contract.use_sequenced_signed_message(&signer, &payload, &signature, &public_key()),
+                Ok(()),
+            );
+            assert_eq!(Contract::next_nonce(&signer), nonce + 1);
+            assert_eq!(
+                Contract.use_sequenced_signed_message(&signer, &payload, &signature, &public_key()),
+                Err(Nep413Error::Nonce(NonceError::TooLow(nonce))),
+            );
+        }
+
+        // The nonce is recorded for the signer only
+        let other: AccountId = "other.near".parse().unwrap();
+        let payload = payload.with_nonce_u64(0);
+        assert_eq!(
+            Contract.use_sequenced_signed_message(&other, &payload, &sign(&payload), &public_key()),
+            Ok(()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce is too low")]
+    fn require_sequenced_signed_message_replay() {
+        let signer: AccountId = "signer.near".parse().unwrap();
+        let (payload, _) = with_callback();
+        let payload = payload.with_nonce_u64(7);
+        let signature = sign(&payload);
+        as_recipient(&payload);
+
+        Contract.require_sequenced_signed_message(&signer, &payload, &signature, &public_key());
+        Contract.require_sequenced_signed_message(&signer, &payload, &signature, &public_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce already used")]
+    fn require_signed_message_replay() {
+        let (payload, signature) = with_callback();
+        as_recipient(&payload);
+
+        Contract.require_signed_message(&payload, &signature, &public_key());
+        Contract.require_signed_message(&payload, &signature, &public_key());
+    }
+}
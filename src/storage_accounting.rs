@@ -0,0 +1,260 @@
+//! Charging accounts for the storage their actions consume.
+//!
+//! A [`StorageAccounting`] guard records the contract's storage usage when
+//! it is created, and settles the difference when it is dropped (or when
+//! [`StorageAccounting::settle`] is called):
+//!
+//! - [`StorageAccounting::debit`] charges added storage to a balance slot, and
+//!   credits the slot for freed storage.
+//! - [`StorageAccounting::attached_deposit`] requires added storage to be
+//!   covered by the attached deposit, and refunds the predecessor the rest of
+//!   the deposit, plus the value of any freed storage.
+//! - [`StorageAccounting::start`] does nothing on its own: the caller reads the
+//!   [`StorageDelta`] and handles it.
+//!
+//! ```
+//! use near_sdk::{env, test_utils::VMContextBuilder, testing_env, ONE_NEAR};
+//! use near_sdk_contract_tools::storage_accounting::StorageAccounting;
+//!
+//! testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+//!
+//! let accounting = StorageAccounting::attached_deposit();
+//!
+//! env::storage_write(b"key", b"value");
+//!
+//! // Charges the storage to the attached deposit and refunds the rest
+//! let delta = accounting.settle();
+//! assert!(delta.bytes > 0);
+//! ```
+//!
+//! # Warning
+//!
+//! Every [`StorageAccounting::attached_deposit`] guard settles against the
+//! whole attached deposit, so use at most one per function call.
+//!
+//! Guards are not settled if the contract is panicking.
+use near_sdk::{env, require, Balance, Promise};
+
+use crate::slot::Slot;
+
+/// Net change in the contract's storage usage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageDelta {
+    /// Number of bytes added (positive) or freed (negative)
+    pub bytes: i64,
+}
+
+impl StorageDelta {
+    /// Cost of the added storage. 0 if storage was freed.
+    pub fn cost(&self) -> Balance {
+        Balance::from(self.bytes.max(0).unsigned_abs()) * env::storage_byte_cost()
+    }
+
+    /// Value of the freed storage. 0 if storage was added.
+    pub fn refund(&self) -> Balance {
+        Balance::from(self.bytes.min(0).unsigned_abs()) * env::storage_byte_cost()
+    }
+}
+
+/// How a [`StorageAccounting`] guard settles the storage delta
+#[derive(Clone, Debug)]
+pub enum Settlement {
+    /// Debit added storage from (or credit freed storage to) a balance
+    Balance(Slot<Balance>),
+    /// Charge added storage to the attached deposit, refunding the rest
+    AttachedDeposit,
+}
+
+impl Settlement {
+    fn apply(self, delta: StorageDelta) {
+        match self {
+            Settlement::Balance(mut slot) => {
+                let balance = slot.read().unwrap_or(0);
+                let cost = delta.cost();
+
+                require!(
+                    balance >= cost,
+                    format_message!("STO1", 
+                        "Insufficient storage balance: {cost} yoctoNEAR required, {balance} yoctoNEAR available",
+                    ),
+                );
+
+                slot.write(&(balance - cost + delta.refund()));
+            }
+            Settlement::AttachedDeposit => {
+                let attached_deposit = env::attached_deposit();
+                let cost = delta.cost();
+
+                require!(
+                    attached_deposit >= cost,
+                    format_message!(
+                        "STO2",
+                        "Insufficient deposit for storage: {cost} yoctoNEAR required"
+                    ),
+                );
+
+                let refund = attached_deposit - cost + delta.refund();
+
+                if refund > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(refund);
+                }
+            }
+        }
+    }
+}
+
+/// Guard that measures the change in storage usage from its creation, and
+/// settles it when dropped
+#[derive(Debug)]
+#[must_use]
+pub struct StorageAccounting {
+    initial_storage_usage: u64,
+    settlement: Option<Settlement>,
+}
+
+impl Default for StorageAccounting {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl StorageAccounting {
+    fn new(settlement: Option<Settlement>) -> Self {
+        Self {
+            initial_storage_usage: env::storage_usage(),
+            settlement,
+        }
+    }
+
+    /// Starts measuring without any settlement
+    pub fn start() -> Self {
+        Self::new(None)
+    }
+
+    /// Starts measuring, settling against the given balance
+    pub fn debit(balance: Slot<Balance>) -> Self {
+        Self::new(Some(Settlement::Balance(balance)))
+    }
+
+    /// Starts measuring, settling against the attached deposit
+    pub fn attached_deposit() -> Self {
+        Self::new(Some(Settlement::AttachedDeposit))
+    }
+
+    /// Change in storage usage since the guard was created
+    pub fn delta(&self) -> StorageDelta {
+        StorageDelta {
+            bytes: env::storage_usage() as i64 - self.initial_storage_usage as i64,
+        }
+    }
+
+    /// Settles the change in storage usage now, and returns it
+    pub fn settle(mut self) -> StorageDelta {
+        let delta = self.delta();
+
+        if let Some(settlement) = self.settlement.take() {
+            settlement.apply(delta);
+        }
+
+        delta
+    }
+}
+
+impl Drop for StorageAccounting {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+
+        if let Some(settlement) = self.settlement.take() {
+            settlement.apply(self.delta());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        env,
+        mock::VmAction,
+        test_utils::{get_created_receipts, VMContextBuilder},
+        testing_env, ONE_NEAR,
+    };
+
+    use super::StorageAccounting;
+    use crate::slot::Slot;
+
+    #[test]
+    fn delta() {
+        let accounting = StorageAccounting::start();
+
+        env::storage_write(b"key", b"value");
+        let added = accounting.delta();
+        assert!(added.bytes > 0);
+        assert_eq!(added.cost(), added.bytes as u128 * env::storage_byte_cost());
+        assert_eq!(added.refund(), 0);
+
+        let accounting = StorageAccounting::start();
+
+        env::storage_remove(b"key");
+        let freed = accounting.settle();
+        assert_eq!(freed.bytes, -added.bytes);
+        assert_eq!(freed.cost(), 0);
+        assert_eq!(freed.refund(), added.cost());
+    }
+
+    #[test]
+    fn debit_and_credit() {
+        let mut balance = Slot::new(b"b");
+        balance.write(&ONE_NEAR);
+
+        let delta = {
+            let accounting = StorageAccounting::debit(balance.clone());
+            env::storage_write(b"key", b"value");
+            accounting.delta()
+        };
+
+        assert_eq!(balance.read(), Some(ONE_NEAR - delta.cost()));
+
+        {
+            let _accounting = StorageAccounting::debit(balance.clone());
+            env::storage_remove(b"key");
+        }
+
+        assert_eq!(balance.read(), Some(ONE_NEAR));
+    }
+
+    #[test]
+    #[should_panic = "Insufficient storage balance"]
+    fn debit_insufficient() {
+        let accounting = StorageAccounting::debit(Slot::new(b"b"));
+        env::storage_write(b"key", b"value");
+        accounting.settle();
+    }
+
+    #[test]
+    fn attached_deposit_refund() {
+        testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+
+        let accounting = StorageAccounting::attached_deposit();
+        env::storage_write(b"key", b"value");
+        let delta = accounting.settle();
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+
+        match &receipts[0].actions[..] {
+            [VmAction::Transfer { deposit }] => assert_eq!(*deposit, ONE_NEAR - delta.cost()),
+            actions => panic!("Unexpected actions: {actions:?}"),
+        }
+    }
+
+    #[test]
+    #[should_panic = "Insufficient deposit for storage"]
+    fn attached_deposit_insufficient() {
+        testing_env!(VMContextBuilder::new().attached_deposit(1).build());
+
+        let _accounting = StorageAccounting::attached_deposit();
+        env::storage_write(b"key", b"value");
+    }
+}
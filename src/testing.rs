@@ -0,0 +1,275 @@
+//! Helpers for unit tests of contracts, available with the `unit-testing`
+//! feature, e.g. as a dev-dependency:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! near-sdk-contract-tools = { version = "*", features = ["unit-testing"] }
+//! ```
+//!
+//! [`TestEnv`] sets up the mocked blockchain of `near_sdk`, including the
+//! results of the promises received by callbacks, and captures the NEP-297
+//! events emitted by the contract, parsed back into their types.
+//! [`storage`] reads and compares the whole storage of the mocked blockchain,
+//! [`fixtures`] sets up the owner and roles of a contract, and [`events`]
+//! replays recorded event logs through the event types.
+//!
+//! # Examples
+//!
+//! ```
+//! use near_sdk::{near_bindgen, AccountId};
+//! use near_sdk_contract_tools::{
+//!     standard::nep141::{event::FtTransferData, Nep141, Nep141Controller, Nep141Event},
+//!     testing::TestEnv,
+//!     Nep141,
+//! };
+//!
+//! #[derive(Nep141)]
+//! #[nep141(no_hooks)]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! let alice: AccountId = "alice".parse().unwrap();
+//! let bob: AccountId = "bob".parse().unwrap();
+//!
+//! let mut env = TestEnv::new();
+//! let mut contract = Contract {};
+//! contract.deposit_unchecked(&alice, 100);
+//!
+//! // `ft_transfer` is called by alice, with 1 yoctoNEAR attached
+//! env.set_predecessor(&alice).set_deposit(1);
+//! contract.ft_transfer(bob.clone(), 40.into(), None);
+//!
+//! assert_eq!(
+//!     env.events_of::<Nep141Event>(),
+//!     vec![Nep141Event::FtTransfer(vec![FtTransferData {
+//!         old_owner_id: alice,
+//!         new_owner_id: bob,
+//!         amount: 40.into(),
+//!         memo: None,
+//!     }])],
+//! );
+//! ```
+
+use near_sdk::{
+    test_utils::{get_logs, VMContextBuilder},
+    testing_env, AccountId, Balance, PromiseResult, PublicKey, RuntimeFeesConfig, VMConfig,
+};
+use serde::Deserialize;
+use std::time::Duration;
+
+pub mod events;
+pub mod fixtures;
+pub mod ft;
+pub mod storage;
+
+/// Parses an account ID, e.g. `account("alice")`
+///
+/// # Panics
+///
+/// If `name` is not a valid account ID.
+#[track_caller]
+pub fn account(name: &str) -> AccountId {
+    name.parse()
+        .unwrap_or_else(|e| panic!("Invalid account ID {name:?}: {e}"))
+}
+
+/// The context of the mocked blockchain of `near_sdk`.
+///
+/// Every setter applies the new context immediately, as if a new function
+/// call started: storage is kept, but the logs (and therefore the captured
+/// events) are cleared.
+pub struct TestEnv {
+    context: VMContextBuilder,
+    promise_results: Vec<PromiseResult>,
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestEnv {
+    /// Applies the default context of `near_sdk`'s `VMContextBuilder`
+    pub fn new() -> Self {
+        let mut env = Self {
+            context: VMContextBuilder::new(),
+            promise_results: vec![],
+        };
+        env.apply();
+        env
+    }
+
+    fn apply(&mut self) -> &mut Self {
+        testing_env!(
+            self.context.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            // `PromiseResult` is not `Clone`
+            self.promise_results
+                .iter()
+                .map(|result| match result {
+                    PromiseResult::NotReady => PromiseResult::NotReady,
+                    PromiseResult::Successful(value) => PromiseResult::Successful(value.clone()),
+                    PromiseResult::Failed => PromiseResult::Failed,
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Sets the predecessor (caller) of the following calls
+    pub fn set_predecessor(&mut self, account_id: &AccountId) -> &mut Self {
+        self.context.predecessor_account_id(account_id.clone());
+        self.apply()
+    }
+
+    /// Sets the deposit attached to the following calls, in yoctoNEAR
+    pub fn set_deposit(&mut self, amount: Balance) -> &mut Self {
+        self.context.attached_deposit(amount);
+        self.apply()
+    }
+
+    /// Sets the public key of the access key that signed the transaction of
+    /// the following calls
+    pub fn set_signer_pk(&mut self, public_key: &PublicKey) -> &mut Self {
+        self.context.signer_account_pk(public_key.clone());
+        self.apply()
+    }
+
+    /// Sets the block timestamp, in nanoseconds
+    pub fn set_block_timestamp(&mut self, timestamp_ns: u64) -> &mut Self {
+        self.context.block_timestamp(timestamp_ns);
+        self.apply()
+    }
+
+    /// Moves the block timestamp forward by `duration_ns` nanoseconds
+    pub fn advance_time(&mut self, duration_ns: u64) -> &mut Self {
+        let timestamp_ns = self.context.context.block_timestamp + duration_ns;
+        self.set_block_timestamp(timestamp_ns)
+    }
+
+    /// The block timestamp, as a [`Clock`], e.g. to move past the validity
+    /// period of a request:
+    /// `env.clock().advance(Duration::from_secs(60))`
+    pub fn clock(&mut self) -> Clock<'_> {
+        Clock { env: self }
+    }
+
+    /// Sets the results of the promises that the following calls receive,
+    /// as a callback does (e.g. `env::promise_result(0)` in
+    /// `ft_resolve_transfer`)
+    pub fn set_promise_results(&mut self, promise_results: Vec<PromiseResult>) -> &mut Self {
+        self.promise_results = promise_results;
+        self.apply()
+    }
+
+    /// The events of type `E` emitted since the context was last applied,
+    /// in order. Logs that are not events of type `E` are ignored.
+    pub fn events_of<E: FromEventLog>(&self) -> Vec<E> {
+        get_logs()
+            .iter()
+            .filter_map(|log| RawEventLog::parse(log))
+            .filter_map(E::from_event_log)
+            .collect()
+    }
+}
+
+/// The block timestamp of a [`TestEnv`], for testing expiries and timelocks.
+/// See: [`TestEnv::clock`].
+pub struct Clock<'a> {
+    env: &'a mut TestEnv,
+}
+
+impl Clock<'_> {
+    /// The current block timestamp, in nanoseconds
+    pub fn now(&self) -> u64 {
+        self.env.context.context.block_timestamp
+    }
+
+    /// Sets the block timestamp, in nanoseconds
+    pub fn set(&mut self, timestamp_ns: u64) -> &mut Self {
+        self.env.set_block_timestamp(timestamp_ns);
+        self
+    }
+
+    /// Moves the block timestamp forward by `duration`
+    ///
+    /// # Panics
+    ///
+    /// If the timestamp overflows `u64` nanoseconds.
+    pub fn advance(&mut self, duration: Duration) -> &mut Self {
+        let timestamp_ns = u64::try_from(duration.as_nanos())
+            .ok()
+            .and_then(|duration_ns| self.now().checked_add(duration_ns))
+            .expect("Block timestamp overflow");
+        self.set(timestamp_ns)
+    }
+}
+
+/// An NEP-297 event log parsed from an `EVENT_JSON:` log line, with its data
+/// left as JSON.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RawEventLog {
+    /// Name of the event standard, e.g. "nep141"
+    pub standard: String,
+    /// Version of the standard, e.g. "1.0.0"
+    pub version: String,
+    /// Name of the particular event, e.g. "ft_transfer"
+    pub event: String,
+    /// Event metadata
+    pub data: serde_json::Value,
+}
+
+impl RawEventLog {
+    /// Parses an `EVENT_JSON:` log line. Returns `None` for other logs.
+    pub fn parse(log: &str) -> Option<Self> {
+        serde_json::from_str(log.strip_prefix("EVENT_JSON:")?).ok()
+    }
+}
+
+/// Events that can be read back from their event logs, for
+/// [`TestEnv::events_of`].
+///
+/// The events of the standards implemented by this crate implement this
+/// trait. Since the events of an enum are serialized without a tag (the
+/// event name is part of the log), implementations match on
+/// [`RawEventLog::event`] to choose the variant, e.g.:
+///
+/// ```
+/// use near_sdk_contract_tools::{event, testing::{FromEventLog, RawEventLog}};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Data {
+///     pub token_id: String,
+/// }
+///
+/// #[event(standard = "x-my-nft", version = "1.0.0")]
+/// pub enum MyEvent {
+///     Minted(Data),
+///     Burned(Data),
+/// }
+///
+/// impl FromEventLog for MyEvent {
+///     fn from_event_log(log: RawEventLog) -> Option<Self> {
+///         if log.standard != "x-my-nft" {
+///             return None;
+///         }
+///
+///         let data = serde_json::from_value(log.data).ok()?;
+///
+///         match log.event.as_str() {
+///             "minted" => Some(Self::Minted(data)),
+///             "burned" => Some(Self::Burned(data)),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait FromEventLog: Sized {
+    /// Reads the event from its log, or returns `None` if the log is not an
+    /// event of this type.
+    fn from_event_log(log: RawEventLog) -> Option<Self>;
+}
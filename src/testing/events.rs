@@ -0,0 +1,143 @@
+//! Replays recorded NEP-297 event logs through the event types, e.g. to check
+//! that an indexer using the types of this crate can read every event that
+//! a contract emits.
+//!
+//! ```
+//! use near_sdk_contract_tools::{
+//!     standard::nep141::{Nep141Controller, Nep141Event},
+//!     testing::{events, TestEnv},
+//! };
+//!
+//! struct Contract;
+//!
+//! impl Nep141Controller for Contract {}
+//!
+//! TestEnv::new();
+//! Contract.mint("alice".parse().unwrap(), 100, None);
+//!
+//! // Or e.g. the lines of a file
+//! let logs = near_sdk::test_utils::get_logs();
+//!
+//! let replayed = events::replay::<Nep141Event>(&logs).unwrap();
+//! assert!(matches!(replayed[..], [Nep141Event::FtMint(_)]));
+//! ```
+
+use thiserror::Error;
+
+use super::{FromEventLog, RawEventLog};
+use crate::standard::nep297::Event;
+
+/// A log that could not be replayed, with its position in the logs
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The log is not an `EVENT_JSON:` log
+    #[error("Log {index} is not an event: {log}")]
+    NotAnEvent {
+        /// Position of the log
+        index: usize,
+        /// The log
+        log: String,
+    },
+    /// The event is not of the replayed type, or its data does not
+    /// deserialize into it
+    #[error("Log {index} is not a known event: {log}")]
+    Unrecognized {
+        /// Position of the log
+        index: usize,
+        /// The log
+        log: String,
+    },
+    /// The event deserializes, but serializes back differently, e.g. a
+    /// field that is skipped when `None` on one side only
+    #[error("Log {index} does not round-trip: {log} became {reserialized}")]
+    Asymmetric {
+        /// Position of the log
+        index: usize,
+        /// The log
+        log: String,
+        /// The log emitted by the deserialized event
+        reserialized: String,
+    },
+}
+
+/// Reads every log as an event of type `E`, and checks that each event
+/// serializes back to the same JSON (ignoring formatting and key order).
+///
+/// The event types of this crate are serialized without a tag, since the
+/// event name is part of the log, so they are read with [`FromEventLog`]
+/// rather than `Deserialize`.
+///
+/// # Errors
+///
+/// The first log that is not an event of type `E`, or that does not
+/// round-trip. Logs that are not events at all are errors too: filter them
+/// out first if the logs are mixed.
+pub fn replay<E: Event + FromEventLog>(
+    logs: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<Vec<E>, ReplayError> {
+    logs.into_iter()
+        .enumerate()
+        .map(|(index, log)| {
+            let log = log.as_ref();
+
+            let raw = RawEventLog::parse(log).ok_or_else(|| ReplayError::NotAnEvent {
+                index,
+                log: log.to_string(),
+            })?;
+            let original = event_json(log);
+
+            let event = E::from_event_log(raw).ok_or_else(|| ReplayError::Unrecognized {
+                index,
+                log: log.to_string(),
+            })?;
+
+            let reserialized = event.to_event_string();
+            if event_json(&reserialized) != original {
+                return Err(ReplayError::Asymmetric {
+                    index,
+                    log: log.to_string(),
+                    reserialized,
+                });
+            }
+
+            Ok(event)
+        })
+        .collect()
+}
+
+fn event_json(log: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(log.strip_prefix("EVENT_JSON:")?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, ReplayError};
+    use crate::standard::nep141::Nep141Event;
+
+    #[test]
+    fn null_memo_does_not_round_trip() {
+        let log = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"alice","amount":"1","memo":null}]}"#;
+
+        assert!(matches!(
+            replay::<Nep141Event>([log]),
+            Err(ReplayError::Asymmetric { index: 0, .. }),
+        ));
+    }
+
+    #[test]
+    fn unrecognized() {
+        let logs = [
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"alice","amount":"1"}]}"#,
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[]}"#,
+        ];
+
+        assert!(matches!(
+            replay::<Nep141Event>(logs),
+            Err(ReplayError::Unrecognized { index: 1, .. }),
+        ));
+        assert!(matches!(
+            replay::<Nep141Event>(["hello"]),
+            Err(ReplayError::NotAnEvent { index: 0, .. }),
+        ));
+    }
+}
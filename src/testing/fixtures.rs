@@ -0,0 +1,79 @@
+//! Contracts in a known access-control state, written directly to storage
+//! (without guards or events), and assertions on that state, e.g.
+//!
+//! ```
+//! use near_sdk::{borsh::{self, BorshSerialize}, near_bindgen, AccountId, BorshStorageKey};
+//! use near_sdk_contract_tools::{testing::fixtures, Owner, Rbac};
+//!
+//! #[derive(BorshSerialize, BorshStorageKey)]
+//! enum Role {
+//!     Minter,
+//!     Burner,
+//! }
+//!
+//! #[derive(Owner, Rbac, Default)]
+//! #[rbac(roles = "Role")]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! let owner: AccountId = "owner".parse().unwrap();
+//! let alice: AccountId = "alice".parse().unwrap();
+//! let bob: AccountId = "bob".parse().unwrap();
+//!
+//! let mut contract = fixtures::owned_contract::<Contract>(&owner);
+//! fixtures::with_roles(
+//!     &mut contract,
+//!     &[(&alice, Role::Minter), (&bob, Role::Minter), (&bob, Role::Burner)],
+//! );
+//!
+//! fixtures::assert_is_owner::<Contract>(&owner);
+//! fixtures::assert_has_role::<Contract>(&bob, &Role::Burner);
+//! ```
+
+use near_sdk::AccountId;
+
+use crate::{owner::Owner, rbac::Rbac};
+
+/// A new contract owned by `owner`, as if [`Owner::init`] had been called,
+/// without emitting an event.
+pub fn owned_contract<T: Owner + Default>(owner: &AccountId) -> T {
+    let mut contract = T::default();
+    contract.set_initialized();
+    contract.update_owner_unchecked(Some(owner.clone()));
+    contract
+}
+
+/// Assigns each role to its account, without emitting events.
+pub fn with_roles<T: Rbac>(_contract: &mut T, roles: &[(&AccountId, T::Role)]) {
+    for (account_id, role) in roles {
+        T::with_members_of_mut(role, |set| set.insert((*account_id).clone()));
+    }
+}
+
+/// Asserts that `account_id` is the owner.
+///
+/// # Panics
+///
+/// If the owner is another account, or if there is no owner.
+#[track_caller]
+pub fn assert_is_owner<T: Owner>(account_id: &AccountId) {
+    let owner = T::read_owner();
+    assert_eq!(
+        owner.as_ref(),
+        Some(account_id),
+        "Owner is {owner:?}, expected `{account_id}`",
+    );
+}
+
+/// Asserts that `account_id` has `role`.
+///
+/// # Panics
+///
+/// If the account does not have the role.
+#[track_caller]
+pub fn assert_has_role<T: Rbac>(account_id: &AccountId, role: &T::Role) {
+    assert!(
+        T::has_role(account_id, role),
+        "`{account_id}` does not have the role",
+    );
+}
@@ -0,0 +1,70 @@
+//! Assertions on the state of a [`Nep141Controller`], e.g.
+//!
+//! ```
+//! use near_sdk::AccountId;
+//! use near_sdk_contract_tools::{standard::nep141::Nep141Controller, testing::ft};
+//!
+//! struct Contract;
+//!
+//! impl Nep141Controller for Contract {}
+//!
+//! let alice: AccountId = "alice".parse().unwrap();
+//! let bob: AccountId = "bob".parse().unwrap();
+//!
+//! Contract.mint(alice.clone(), 100, None);
+//! Contract.transfer(alice.clone(), bob.clone(), 40, None);
+//!
+//! ft::assert_balances::<Contract>(&[(&alice, 60), (&bob, 40)]);
+//! ft::assert_total_supply::<Contract>(100);
+//! ft::assert_supply_equals_sum_of_balances::<Contract>(&[&alice, &bob]);
+//! ```
+
+use near_sdk::AccountId;
+
+use crate::standard::nep141::Nep141Controller;
+
+/// Asserts the balance of each account.
+///
+/// # Panics
+///
+/// If any balance differs, naming the account.
+#[track_caller]
+pub fn assert_balances<C: Nep141Controller>(expected: &[(&AccountId, u128)]) {
+    for (account_id, expected) in expected {
+        let balance = C::balance_of(account_id);
+        assert_eq!(
+            balance, *expected,
+            "Balance of `{account_id}` is {balance}, expected {expected}",
+        );
+    }
+}
+
+/// Asserts the total supply.
+///
+/// # Panics
+///
+/// If the total supply differs.
+#[track_caller]
+pub fn assert_total_supply<C: Nep141Controller>(expected: u128) {
+    let total_supply = C::total_supply();
+    assert_eq!(
+        total_supply, expected,
+        "Total supply is {total_supply}, expected {expected}",
+    );
+}
+
+/// Asserts that the total supply is the sum of the balances of `accounts`,
+/// which must therefore include every account holding tokens.
+///
+/// # Panics
+///
+/// If the total supply differs from the sum, or if the sum overflows.
+#[track_caller]
+pub fn assert_supply_equals_sum_of_balances<C: Nep141Controller>(accounts: &[&AccountId]) {
+    let sum = accounts.iter().fold(0u128, |sum, account_id| {
+        sum.checked_add(C::balance_of(account_id))
+            .expect("Sum of balances overflows")
+    });
+
+    assert_total_supply::<C>(sum);
+}
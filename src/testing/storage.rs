@@ -0,0 +1,205 @@
+//! Snapshots of the storage of the mocked blockchain, e.g. to check which
+//! keys an operation writes:
+//!
+//! ```
+//! use near_sdk_contract_tools::testing::{storage, TestEnv};
+//!
+//! TestEnv::new();
+//!
+//! let before = storage::snapshot();
+//! near_sdk::env::storage_write(b"key", b"value");
+//! let after = storage::snapshot();
+//!
+//! let diff = storage::diff(&before, &after);
+//! assert_eq!(diff.added.get(&b"key".to_vec()), Some(&b"value".to_vec()));
+//! assert!(diff.removed.is_empty() && diff.changed.is_empty());
+//!
+//! // + 6b6579 |key| = 76616c7565 |value|
+//! println!("{diff}");
+//! ```
+
+use near_sdk::{
+    env, mock::with_mocked_blockchain, test_utils::VMContextBuilder, MockedBlockchain,
+    RuntimeFeesConfig, VMConfig,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// Reads every key and value in the storage of the mocked blockchain.
+///
+/// The mocked blockchain only gives up its storage when it is replaced, so
+/// it is restarted with the same storage and the current context (account
+/// IDs, block, balances, deposit, promise results, etc.). Like the setters of
+/// [`TestEnv`](super::TestEnv), this starts a new function call: the logs are
+/// cleared.
+pub fn snapshot() -> BTreeMap<Vec<u8>, Vec<u8>> {
+    let storage = with_mocked_blockchain(|b| b.take_storage());
+    let snapshot = storage
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    restart(storage);
+    snapshot
+}
+
+fn restart(storage: HashMap<Vec<u8>, Vec<u8>>) {
+    let mut context = VMContextBuilder::new();
+    context
+        .current_account_id(env::current_account_id())
+        .signer_account_id(env::signer_account_id())
+        .signer_account_pk(env::signer_account_pk())
+        .predecessor_account_id(env::predecessor_account_id())
+        .block_index(env::block_height())
+        .block_timestamp(env::block_timestamp())
+        .epoch_height(env::epoch_height())
+        // The attached deposit is added to the balance when a call starts
+        .account_balance(env::account_balance().saturating_sub(env::attached_deposit()))
+        .account_locked_balance(env::account_locked_balance())
+        .storage_usage(env::storage_usage())
+        .attached_deposit(env::attached_deposit())
+        .prepaid_gas(env::prepaid_gas())
+        .random_seed(env::random_seed_array());
+    let mut context = context.build();
+    context.input = env::input().unwrap_or_default();
+
+    let promise_results = (0..env::promise_results_count())
+        .map(env::promise_result)
+        .collect();
+
+    env::set_blockchain_interface(MockedBlockchain::new(
+        context,
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        promise_results,
+        storage,
+        Default::default(),
+        None,
+    ));
+}
+
+/// The differences between two storage snapshots. Displays as one line per
+/// key, with keys and values in hex and ASCII.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageDiff {
+    /// Keys that were written, with their values
+    pub added: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Keys that were removed, with their previous values
+    pub removed: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Keys whose values changed, with their previous and new values
+    pub changed: BTreeMap<Vec<u8>, (Vec<u8>, Vec<u8>)>,
+}
+
+impl StorageDiff {
+    /// Whether the snapshots are equal
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two storage snapshots, e.g. from before and after an operation.
+/// Keys written with the value they already had are not changes.
+pub fn diff(
+    before: &BTreeMap<Vec<u8>, Vec<u8>>,
+    after: &BTreeMap<Vec<u8>, Vec<u8>>,
+) -> StorageDiff {
+    let mut diff = StorageDiff::default();
+
+    for (key, value) in before {
+        match after.get(key) {
+            None => {
+                diff.removed.insert(key.clone(), value.clone());
+            }
+            Some(new_value) if new_value != value => {
+                diff.changed
+                    .insert(key.clone(), (value.clone(), new_value.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (key, value) in after {
+        if !before.contains_key(key) {
+            diff.added.insert(key.clone(), value.clone());
+        }
+    }
+
+    diff
+}
+
+struct HexAscii<'a>(&'a [u8]);
+
+impl fmt::Display for HexAscii<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        write!(f, " |")?;
+        for &byte in self.0 {
+            let c = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            write!(f, "{c}")?;
+        }
+        write!(f, "|")
+    }
+}
+
+impl fmt::Display for StorageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.added {
+            writeln!(f, "+ {} = {}", HexAscii(key), HexAscii(value))?;
+        }
+        for (key, value) in &self.removed {
+            writeln!(f, "- {} = {}", HexAscii(key), HexAscii(value))?;
+        }
+        for (key, (old, new)) in &self.changed {
+            writeln!(
+                f,
+                "~ {} = {} -> {}",
+                HexAscii(key),
+                HexAscii(old),
+                HexAscii(new),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::diff;
+
+    fn map(entries: &[(&str, &str)]) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let before = map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let after = map(&[("a", "1"), ("b", "\0"), ("d", "4")]);
+
+        let d = diff(&before, &after);
+
+        assert_eq!(d.added, map(&[("d", "4")]));
+        assert_eq!(d.removed, map(&[("c", "3")]));
+        assert_eq!(
+            d.changed,
+            [(b"b".to_vec(), (b"2".to_vec(), b"\x00".to_vec()))].into(),
+        );
+        assert_eq!(
+            d.to_string(),
+            "+ 64 |d| = 34 |4|\n- 63 |c| = 33 |3|\n~ 62 |b| = 32 |2| -> 00 |.|\n",
+        );
+
+        assert!(diff(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,410 @@
+//! Delayed execution of actions: an action is scheduled now, and may be
+//! executed once its delay has passed, unless it is cancelled in between.
+//!
+//! [`Timelock`] is generic over the same [`Action`] trait as
+//! [`ApprovalManager`](crate::approval::ApprovalManager), so the same action
+//! type can be put behind a multisig, a timelock, or both. Operations are
+//! identified by sequential IDs, which are never reused.
+//!
+//! Scheduling is not restricted, so the contract should guard the method
+//! that calls [`Timelock::schedule`] (e.g. with
+//! [`Owner::require_owner`](crate::owner::Owner::require_owner)). Executing
+//! an operation is allowed to anyone once it is ready. Who may cancel an
+//! operation is decided by [`TimelockHook::on_cancel`], which the contract
+//! implements.
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The timelock root storage slot is not used or modified. The default key is `~tl`.
+//! * (ERR) An operation is not executed before its ETA.
+//! * (ERR) An operation is executed at most once, and not after it is cancelled.
+//! * (ERR) The delay of an operation is at least [`Timelock::min_delay_ns`].
+//! * (ERR) [`TimelockHook::on_cancel`] is called before every cancellation.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U64,
+    store::UnorderedSet,
+    BorshStorageKey,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{approval::Action, slot::Slot, standard::nep297::Event, DefaultStorageKey};
+
+const NO_OPERATION_MESSAGE: &str = message!("TLK1", "No such operation");
+const NOT_READY_MESSAGE: &str = message!("TLK2", "Operation is not ready");
+const DELAY_TOO_SHORT_MESSAGE: &str = message!("TLK3", "Delay is shorter than the minimum");
+
+/// Events emitted by a [`Timelock`]
+#[event(
+    standard = "x-timelock",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum TimelockEvent {
+    /// Emitted when an operation is scheduled
+    Schedule {
+        /// ID of the operation
+        id: u32,
+        /// Block timestamp from which the operation may be executed
+        eta: U64,
+    },
+    /// Emitted when an operation is executed
+    Execute {
+        /// ID of the operation
+        id: u32,
+    },
+    /// Emitted when an operation is cancelled
+    Cancel {
+        /// ID of the operation
+        id: u32,
+    },
+}
+
+/// An action waiting for its delay to pass
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct Operation<A> {
+    /// The action to execute
+    pub action: A,
+    /// Block timestamp (in nanoseconds) from which the action may be executed
+    pub eta: U64,
+}
+
+impl<A> Operation<A> {
+    /// Whether the operation may be executed at the current block timestamp
+    pub fn is_ready(&self) -> bool {
+        env::block_timestamp() >= self.eta.0
+    }
+}
+
+/// Errors of [`Timelock`] operations
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum TimelockError {
+    /// No operation is queued with the given ID: it does not exist, or was
+    /// already executed or cancelled
+    #[error("{}", NO_OPERATION_MESSAGE)]
+    OperationNotFound(u32),
+    /// The ETA of the operation has not been reached
+    #[error("{}", NOT_READY_MESSAGE)]
+    NotReady(u32),
+    /// The delay is shorter than [`Timelock::min_delay_ns`]
+    #[error("{}", DELAY_TOO_SHORT_MESSAGE)]
+    DelayTooShort,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum TimelockStorageKey {
+    NextOperationId,
+    Operation(u32),
+    Queued,
+}
+
+/// Authorization of timelock cancellations
+pub trait TimelockHook<A> {
+    /// Called before an operation is cancelled. Panics if the predecessor may
+    /// not cancel it.
+    fn on_cancel(&self, id: u32, operation: &Operation<A>);
+}
+
+/// Queue of actions that may be executed once their delay has passed
+pub trait Timelock<A>: TimelockHook<A>
+where
+    A: Action<Self> + BorshSerialize + BorshDeserialize,
+{
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Timelock)
+    }
+
+    /// Because operations are deleted when they are executed or cancelled,
+    /// maintain a simple counter to guarantee unique IDs
+    fn slot_next_operation_id() -> Slot<u32> {
+        Self::root().field(TimelockStorageKey::NextOperationId)
+    }
+
+    /// Storage slot for a queued operation
+    fn slot_operation(id: u32) -> Slot<Operation<A>> {
+        Self::root().field(TimelockStorageKey::Operation(id))
+    }
+
+    /// Storage slot for the backing `UnorderedSet` of the IDs of the queued
+    /// operations
+    fn slot_queued() -> Slot<UnorderedSet<u32>> {
+        Self::root().field(TimelockStorageKey::Queued)
+    }
+
+    /// Deserializes the backing `UnorderedSet` of queued IDs, executes `f` on
+    /// it, and writes it back into storage, returning the return value of `f`
+    fn with_queued_mut<T>(f: impl FnOnce(&mut UnorderedSet<u32>) -> T) -> T {
+        let mut slot = Self::slot_queued();
+        let mut set = slot
+            .read()
+            .unwrap_or_else(|| UnorderedSet::new(slot.key.clone()));
+        let value = f(&mut set);
+        slot.write(&set);
+        value
+    }
+
+    /// Minimum delay (in nanoseconds) between scheduling an operation and
+    /// executing it. No minimum by default.
+    fn min_delay_ns() -> u64 {
+        0
+    }
+
+    /// Get a queued operation by ID
+    fn operation(id: u32) -> Option<Operation<A>> {
+        Self::slot_operation(id).read()
+    }
+
+    /// Number of queued operations
+    fn queued_operations_len() -> u32 {
+        Self::slot_queued().read().map_or(0, |set| set.len())
+    }
+
+    /// Queued operations, with their IDs, in no particular order. Paginated
+    /// by `offset` and `limit`.
+    fn queued_operations(offset: u32, limit: u32) -> Vec<(u32, Operation<A>)> {
+        Self::slot_queued().read().map_or_else(Vec::new, |set| {
+            set.iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .filter_map(|id| Some((*id, Self::operation(*id)?)))
+                .collect()
+        })
+    }
+
+    /// Queues `action` for execution after `delay_ns` nanoseconds. Emits a
+    /// [`TimelockEvent::Schedule`] event. Returns the ID of the operation.
+    fn schedule(&mut self, action: A, delay_ns: u64) -> Result<u32, TimelockError> {
+        if delay_ns < Self::min_delay_ns() {
+            return Err(TimelockError::DelayTooShort);
+        }
+
+        let id = Self::slot_next_operation_id().read().unwrap_or(0);
+        let eta = env::block_timestamp().saturating_add(delay_ns);
+
+        Self::slot_next_operation_id().write(&(id + 1));
+        Self::slot_operation(id).write(&Operation {
+            action,
+            eta: eta.into(),
+        });
+        Self::with_queued_mut(|set| set.insert(id));
+
+        TimelockEvent::Schedule {
+            id,
+            eta: eta.into(),
+        }
+        .emit();
+
+        Ok(id)
+    }
+
+    /// Executes a queued operation whose ETA has been reached, and removes
+    /// it from the queue. Emits a [`TimelockEvent::Execute`] event.
+    fn execute(&mut self, id: u32) -> Result<A::Output, TimelockError> {
+        let mut slot = Self::slot_operation(id);
+        let operation = slot.read().ok_or(TimelockError::OperationNotFound(id))?;
+
+        if !operation.is_ready() {
+            return Err(TimelockError::NotReady(id));
+        }
+
+        slot.remove();
+        Self::with_queued_mut(|set| set.remove(&id));
+
+        TimelockEvent::Execute { id }.emit();
+
+        Ok(operation.action.execute(self))
+    }
+
+    /// Removes a queued operation, after [`TimelockHook::on_cancel`]. An
+    /// operation may be cancelled until it is executed, even after its ETA.
+    /// Emits a [`TimelockEvent::Cancel`] event.
+    fn cancel(&mut self, id: u32) -> Result<(), TimelockError> {
+        let mut slot = Self::slot_operation(id);
+        let operation = slot.read().ok_or(TimelockError::OperationNotFound(id))?;
+
+        self.on_cancel(id, &operation);
+
+        slot.remove();
+        Self::with_queued_mut(|set| set.remove(&id));
+
+        TimelockEvent::Cancel { id }.emit();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        near_bindgen,
+    };
+
+    use super::{Operation, Timelock, TimelockError, TimelockHook};
+    use crate::{
+        approval::Action,
+        owner::Owner,
+        testing::{account, TestEnv},
+        Owner,
+    };
+
+    const DELAY: u64 = 100;
+
+    #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+    struct SetValue(u32);
+
+    impl Action<Contract> for SetValue {
+        type Output = u32;
+
+        fn execute(self, contract: &mut Contract) -> u32 {
+            contract.value = self.0;
+            self.0
+        }
+    }
+
+    #[derive(Owner)]
+    #[owner(crate = "crate")]
+    #[near_bindgen]
+    struct Contract {
+        value: u32,
+    }
+
+    impl TimelockHook<SetValue> for Contract {
+        fn on_cancel(&self, _id: u32, _operation: &Operation<SetValue>) {
+            Self::require_owner();
+        }
+    }
+
+    impl Timelock<SetValue> for Contract {
+        fn min_delay_ns() -> u64 {
+            DELAY
+        }
+    }
+
+    fn setup() -> (TestEnv, Contract, u32) {
+        let mut env = TestEnv::new();
+        let mut contract = Contract { value: 0 };
+        Owner::init(&mut contract, &account("owner"));
+
+        env.set_predecessor(&account("owner"))
+            .set_block_timestamp(1_000);
+        let id = contract.schedule(SetValue(7), DELAY).unwrap();
+
+        (env, contract, id)
+    }
+
+    #[test]
+    fn execute_at_eta() {
+        let (mut env, mut contract, id) = setup();
+
+        assert_eq!(
+            Contract::operation(id),
+            Some(Operation {
+                action: SetValue(7),
+                eta: (1_000 + DELAY).into(),
+            }),
+        );
+
+        env.set_block_timestamp(1_000 + DELAY - 1);
+        assert_eq!(contract.execute(id), Err(TimelockError::NotReady(id)));
+        assert_eq!(contract.value, 0);
+
+        env.set_block_timestamp(1_000 + DELAY);
+        assert_eq!(contract.execute(id), Ok(7));
+        assert_eq!(contract.value, 7);
+        assert_eq!(Contract::operation(id), None);
+    }
+
+    #[test]
+    fn execute_once() {
+        let (mut env, mut contract, id) = setup();
+
+        env.set_block_timestamp(1_000 + DELAY);
+        contract.execute(id).unwrap();
+
+        assert_eq!(
+            contract.execute(id),
+            Err(TimelockError::OperationNotFound(id)),
+        );
+    }
+
+    #[test]
+    fn delay_too_short() {
+        let (_env, mut contract, _) = setup();
+
+        assert_eq!(
+            contract.schedule(SetValue(8), DELAY - 1),
+            Err(TimelockError::DelayTooShort),
+        );
+    }
+
+    #[test]
+    fn cancel_after_eta() {
+        let (mut env, mut contract, id) = setup();
+
+        env.set_block_timestamp(1_000 + DELAY * 2);
+        contract.cancel(id).unwrap();
+
+        assert_eq!(
+            contract.execute(id),
+            Err(TimelockError::OperationNotFound(id)),
+        );
+        assert_eq!(contract.value, 0);
+        assert_eq!(Contract::queued_operations_len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner only")]
+    fn cancel_unauthorized() {
+        let (mut env, mut contract, id) = setup();
+
+        env.set_predecessor(&account("alice"));
+        contract.cancel(id).unwrap();
+    }
+
+    #[test]
+    fn queued_operations() {
+        let (_env, mut contract, first) = setup();
+        let second = contract.schedule(SetValue(8), DELAY * 2).unwrap();
+        let third = contract.schedule(SetValue(9), DELAY * 3).unwrap();
+        assert_eq!((first, second, third), (0, 1, 2));
+
+        contract.cancel(second).unwrap();
+
+        let mut queued = Contract::queued_operations(0, 10);
+        queued.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            queued,
+            vec![
+                (
+                    first,
+                    Operation {
+                        action: SetValue(7),
+                        eta: (1_000 + DELAY).into(),
+                    },
+                ),
+                (
+                    third,
+                    Operation {
+                        action: SetValue(9),
+                        eta: (1_000 + DELAY * 3).into(),
+                    },
+                ),
+            ],
+        );
+        assert_eq!(Contract::queued_operations(1, 10).len(), 1);
+        assert_eq!(Contract::queued_operations_len(), 2);
+    }
+}
@@ -12,13 +12,238 @@
 //!
 //! By default, after updating the contract logic the contract state is
 //! migrated. This behaviour can be changed by providing a
-//! custom [`PostUpgrade`].
+//! custom [`PostUpgrade`], or skipped entirely for upgrades that do not
+//! change the state schema (see [`serialized::deploy`]).
+//!
+//! With the [`crate::Upgrade`] macro, `no_migrate` skips the migration for
+//! every upgrade, so the new code does not need a migrate function:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Upgrade};
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "owner", no_migrate)]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! It cannot be combined with options that configure the migration:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Upgrade};
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "owner", no_migrate, migrate_method_name = "migrate")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! Alternatively, `allow_skip_migration` lets the caller skip the migration
+//! on a per-call basis with a `skip_migration` argument.
+//!
+//! # Entry point
+//!
+//! The name of the upgrade function generated by the [`crate::Upgrade`]
+//! macro can be changed with `function_name`, and it can be marked
+//! `#[private]` with `private`. With `raw_input`, the function reads the
+//! code directly from the function call input, without any serialization
+//! envelope:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Upgrade};
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(
+//!     hook = "owner",
+//!     serializer = "borsh",
+//!     function_name = "deploy_code",
+//!     raw_input
+//! )]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! This requires the `borsh` serializer:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Upgrade};
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "owner", serializer = "jsonbase64", raw_input)]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! # Authorization
+//!
+//! The `hook` option of the [`crate::Upgrade`] macro controls who may
+//! upgrade the contract. Checks can be composed:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen, BorshStorageKey,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Rbac, Upgrade};
+//!
+//! #[derive(BorshSerialize, BorshStorageKey)]
+//! enum Role {
+//!     Upgrader,
+//! }
+//!
+//! #[derive(Owner, Rbac, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[rbac(roles = "Role")]
+//! #[upgrade(hook = "owner_or_role(Role::Upgrader)")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! or delegated to an arbitrary function:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     env, near_bindgen, require,
+//! };
+//! use near_sdk_contract_tools::Upgrade;
+//!
+//! fn only_self(_contract: &Contract) {
+//!     require!(env::predecessor_account_id() == env::current_account_id());
+//! }
+//!
+//! #[derive(Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "expr(only_self)")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! Malformed hooks do not compile:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Rbac, Upgrade};
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "owner_or_role()")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen,
+//! };
+//! use near_sdk_contract_tools::Upgrade;
+//!
+//! #[derive(Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "expr(1 + 1)")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! Neither do roles of the wrong type:
+//!
+//! ```compile_fail
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     near_bindgen, BorshStorageKey,
+//! };
+//! use near_sdk_contract_tools::{owner::Owner, Owner, Rbac, Upgrade};
+//!
+//! #[derive(BorshSerialize, BorshStorageKey)]
+//! enum Role {
+//!     Upgrader,
+//! }
+//!
+//! #[derive(Owner, Rbac, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[rbac(roles = "Role")]
+//! #[upgrade(hook = "owner_or_role(0u8)")]
+//! #[near_bindgen]
+//! struct Contract {}
+//! ```
+//!
+//! The [`staged`] module stores the new code in contract storage before
+//! deploying it in a separate transaction, so that the code hash can be
+//! verified in between.
+//!
+//! The [`rollback`] module keeps track of the previously deployed code, so
+//! that it can be redeployed after a bad upgrade.
 //!
 //! The [`raw`] module is included mostly for legacy / compatibility reasons,
 //! and for the niche efficiency use-case, since it allows for the most
 //! efficient binary serialization (though only by a little). However, it is
 //! more difficult to use and has more sharp edges.
 //!
+//! The [`crate::Upgrade`] macro emits [`UpgradeStaged`], [`UpgradeDeployed`],
+//! and [`UpgradeUnstaged`] events. They are emitted before the deploy promise
+//! is created.
+//!
+//! # Completion callback
+//!
+//! Since [`UpgradeDeployed`] is emitted before the deployment, a failed
+//! migration would otherwise go unnoticed. With `on_complete`, the
+//! [`crate::Upgrade`] macro schedules a callback to `on_upgrade_complete`
+//! after the deployment and migration (see [`serialized::on_complete`]),
+//! which emits [`UpgradeCompleted`] or [`UpgradeFailed`] and calls
+//! [`serialized::UpgradeCompleteHook`]. With `on_complete = "default"`, the
+//! hook does nothing else; with `on_complete = "custom"`, the contract
+//! implements it:
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     env, near_bindgen, CryptoHash,
+//! };
+//! use near_sdk_contract_tools::{
+//!     owner::Owner, upgrade::serialized::UpgradeCompleteHook, Owner, Upgrade,
+//! };
+//!
+//! #[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+//! #[upgrade(hook = "owner", on_complete = "custom")]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl UpgradeCompleteHook for Contract {
+//!     fn on_upgrade_failed(&mut self, _code_hash: CryptoHash) {
+//!         env::log_str("Upgrade failed");
+//!     }
+//! }
+//! ```
+//!
+//! The callback is a separate receipt executed by whichever code is deployed
+//! at that time:
+//!
+//! - If the upgrade succeeded, the _new_ code executes the callback, so the
+//!   new code must also expose `on_upgrade_complete` (e.g. by deriving
+//!   [`crate::Upgrade`] with `on_complete`). Otherwise, the callback fails
+//!   with a `MethodNotFound` error. The upgrade itself is not affected, but no
+//!   [`UpgradeCompleted`] event is emitted.
+//! - If the migration failed, the deployment is reverted along with it, so
+//!   the _old_ code executes the callback and emits [`UpgradeFailed`].
+//!
+//! Staged code is already removed from storage when it is deployed, so the
+//! callback has nothing to clean up by default.
+//!
 //! # Safety
 //!
 //! If the contract state is migrated, the new contract logic must deserialize
@@ -26,7 +251,13 @@
 //! schema. If the new contract has a different storage schema from the old
 //! contract and does not migrate the state schema, the contract may become
 //! unusable.
-use near_sdk::Gas;
+use near_sdk::{
+    env, json_types::Base58CryptoHash, require, serde::Serialize, serde_json, AccountId, Gas,
+    GasWeight,
+};
+use near_sdk_contract_tools_macros::event;
+
+use crate::standard::nep297::ToEventLog;
 
 /// Default value for the name of the function that will be called after
 /// upgrade (usually a migrate function).
@@ -36,10 +267,18 @@ pub const DEFAULT_POST_UPGRADE_METHOD_ARGS: Vec<u8> = vec![];
 /// Guarantee the post-upgrade function receives at least this much gas by
 /// default.
 pub const DEFAULT_POST_UPGRADE_MINIMUM_GAS: Gas = Gas(15_000_000_000_000);
+/// Name of the function called on the contract after an upgrade completes.
+pub const UPGRADE_COMPLETE_METHOD_NAME: &str = "on_upgrade_complete";
+/// Gas attached to the upgrade completion callback.
+pub const DEFAULT_UPGRADE_COMPLETE_GAS: Gas = Gas(10_000_000_000_000);
+/// Default NEP-297 standard string of upgrade events.
+pub const DEFAULT_UPGRADE_EVENT_STANDARD: &str = "x-upgr";
 
 #[cfg(feature = "unstable")]
 pub mod raw;
+pub mod rollback;
 pub mod serialized;
+pub mod staged;
 
 /// Function call after upgrade descriptor
 #[derive(Debug, Clone)]
@@ -48,8 +287,12 @@ pub struct PostUpgrade {
     pub method: String,
     /// Serialized function input
     pub args: Vec<u8>,
-    /// Guarantee minimum gas
+    /// Reject the upgrade if less than this much gas remains for the
+    /// function call
     pub minimum_gas: Gas,
+    /// Attach exactly this much gas to the function call. By default, the
+    /// function call receives all remaining gas.
+    pub static_gas: Option<Gas>,
 }
 
 impl Default for PostUpgrade {
@@ -58,6 +301,122 @@ impl Default for PostUpgrade {
             method: DEFAULT_POST_UPGRADE_METHOD_NAME.to_string(),
             args: DEFAULT_POST_UPGRADE_METHOD_ARGS,
             minimum_gas: DEFAULT_POST_UPGRADE_MINIMUM_GAS,
+            static_gas: None,
         }
     }
 }
+
+impl PostUpgrade {
+    /// Static gas and gas weight to use for the function call
+    pub fn gas_allocation(&self) -> (Gas, GasWeight) {
+        match self.static_gas {
+            Some(static_gas) => (static_gas, GasWeight(0)),
+            None => (Gas(0), GasWeight(u64::MAX)),
+        }
+    }
+
+    /// Rejects if less than [`PostUpgrade::minimum_gas`] remains
+    pub fn require_minimum_gas(&self) {
+        let remaining_gas = env::prepaid_gas() - env::used_gas();
+
+        require!(
+            remaining_gas >= self.minimum_gas,
+            format_message!(
+                "UPG14",
+                "Insufficient gas for post-upgrade function call: {} required, {} remaining",
+                self.minimum_gas.0,
+                remaining_gas.0,
+            ),
+        );
+    }
+}
+
+/// Emitted when new code is staged for a two-phase upgrade
+#[event(
+    standard = "x-upgr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct UpgradeStaged {
+    /// SHA-256 hash of the staged code
+    pub code_hash: Base58CryptoHash,
+    /// Account that staged the code
+    pub by: AccountId,
+}
+
+/// Emitted when new code is deployed to the contract
+#[event(
+    standard = "x-upgr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct UpgradeDeployed {
+    /// SHA-256 hash of the deployed code
+    pub code_hash: Base58CryptoHash,
+    /// Account that deployed the code
+    pub by: AccountId,
+    /// Name of the function called after the deployment, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migrate: Option<String>,
+}
+
+/// Emitted when staged code is removed without being deployed
+#[event(
+    standard = "x-upgr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct UpgradeUnstaged {
+    /// SHA-256 hash of the removed code
+    pub code_hash: Base58CryptoHash,
+    /// Account that removed the code
+    pub by: AccountId,
+}
+
+/// Emitted by the upgrade completion callback after a successful upgrade
+#[event(
+    standard = "x-upgr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct UpgradeCompleted {
+    /// SHA-256 hash of the deployed code
+    pub code_hash: Base58CryptoHash,
+}
+
+/// Emitted by the upgrade completion callback when the deployment or the
+/// post-upgrade function call failed
+#[event(
+    standard = "x-upgr",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub struct UpgradeFailed {
+    /// SHA-256 hash of the code that failed to deploy
+    pub code_hash: Base58CryptoHash,
+}
+
+/// Emits an upgrade event with the given NEP-297 standard string, which
+/// replaces [`DEFAULT_UPGRADE_EVENT_STANDARD`].
+pub fn emit_upgrade_event<T: ToEventLog>(event: &T, standard: &'static str)
+where
+    T::Data: Serialize,
+{
+    let mut log = event.to_event_log();
+    log.standard = standard;
+
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        serde_json::to_string(&log).unwrap_or_else(|_| env::abort()),
+    ));
+}
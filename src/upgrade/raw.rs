@@ -28,6 +28,10 @@ use super::PostUpgrade;
 /// `near_sys::promise_batch_action_deploy_contract` (i.e. pointer to a valid
 /// WASM blob or a register descriptor).
 pub unsafe fn upgrade(post_upgrade: PostUpgrade) {
+    post_upgrade.require_minimum_gas();
+
+    let (static_gas, gas_weight) = post_upgrade.gas_allocation();
+
     // Create a promise batch
     let promise_id = sys::promise_batch_create(
         env::current_account_id().as_bytes().len() as u64,
@@ -48,8 +52,8 @@ pub unsafe fn upgrade(post_upgrade: PostUpgrade) {
         post_upgrade.args.len() as u64,
         post_upgrade.args.as_ptr() as u64,
         0,
-        post_upgrade.minimum_gas.0,
-        u64::MAX,
+        static_gas.0,
+        gas_weight.0,
     );
 
     sys::promise_return(promise_id);
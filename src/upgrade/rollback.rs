@@ -0,0 +1,183 @@
+//! Rollback support: remember the previously deployed code so that it can be
+//! redeployed after a bad upgrade.
+//!
+//! Every deployment performed through the [`crate::Upgrade`] macro records
+//! the hash of the deployed code. When the next deployment happens, that
+//! record becomes the "previous code". A contract cannot read its own code,
+//! so the code that was initially deployed to the account (not through an
+//! upgrade) is unknown: the first upgrade will not have a previous code
+//! hash.
+//!
+//! Optionally, the full code bytes can be retained as well, which allows
+//! [`UpgradeRollback::stage_rollback`] to stage the previous code for
+//! redeployment through the normal [`super::staged`] flow.
+//!
+//! # Storage cost
+//!
+//! Retaining code bytes is opt-in (see
+//! [`UpgradeRollback::max_retained_code_len`]) because it is expensive:
+//! up to two copies of the contract code are kept in storage at all times
+//! (the current code, and the previous code), and rolling back stages a
+//! third copy temporarily. At 1 NEAR per 100 KB, retaining a 300 KB contract
+//! locks up about 6 NEAR of the contract's own balance. Unlike staged code,
+//! this storage is not paid for by a deposit, so the contract account must
+//! hold enough balance to cover it, or the deployment will fail.
+
+use near_sdk::{env, CryptoHash};
+
+use crate::{
+    slot::{codec::RawCodec, Slot},
+    DefaultStorageKey,
+};
+
+use super::staged::StagedUpgrade;
+
+const NO_PREVIOUS_CODE_MESSAGE: &str = message!("UPG8", "Previous code was not retained");
+
+/// Storage and logic for tracking previously deployed code
+pub trait UpgradeRollback {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::UpgradeRollback)
+    }
+
+    /// Storage slot for the hash of the code deployed by the latest upgrade
+    fn slot_current_code_hash() -> Slot<CryptoHash> {
+        Self::root().field(b"h")
+    }
+
+    /// Storage slot for the code deployed by the latest upgrade, if retained
+    fn slot_current_code() -> Slot<Vec<u8>, RawCodec> {
+        Self::root().field(b"c").with_codec()
+    }
+
+    /// Storage slot for the hash of the code replaced by the latest upgrade
+    fn slot_previous_code_hash() -> Slot<CryptoHash> {
+        Self::root().field(b"p")
+    }
+
+    /// Storage slot for the code replaced by the latest upgrade, if retained
+    fn slot_previous_code() -> Slot<Vec<u8>, RawCodec> {
+        Self::root().field(b"q").with_codec()
+    }
+
+    /// Code up to this length (in bytes) is retained in storage so that it
+    /// can be redeployed. Defaults to 0: only code hashes are retained.
+    fn max_retained_code_len() -> usize {
+        0
+    }
+
+    /// SHA-256 hash of the code replaced by the latest upgrade, if known
+    fn previous_code_hash() -> Option<CryptoHash> {
+        Self::slot_previous_code_hash().read()
+    }
+
+    /// Whether the code replaced by the latest upgrade can be redeployed
+    fn is_previous_code_retained() -> bool {
+        Self::slot_previous_code().exists()
+    }
+
+    /// Records that `code` is about to be deployed: the currently deployed
+    /// code becomes the previous code.
+    fn record_deploy(code: &[u8]) {
+        Self::slot_previous_code_hash().set(Self::slot_current_code_hash().read().as_ref());
+
+        Self::slot_current_code().move_to(Self::slot_previous_code());
+
+        Self::slot_current_code_hash().write(&env::sha256_array(code));
+
+        if code.len() <= Self::max_retained_code_len() {
+            Self::slot_current_code().write_raw(code);
+        } else {
+            Self::slot_current_code().remove();
+        }
+    }
+
+    /// Stages the code replaced by the latest upgrade for redeployment. The
+    /// usual staging rules apply (see [`StagedUpgrade::stage_upgrade`]).
+    fn stage_rollback() -> CryptoHash
+    where
+        Self: StagedUpgrade,
+    {
+        let code = Self::slot_previous_code()
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_PREVIOUS_CODE_MESSAGE));
+
+        Self::stage_upgrade(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{env, test_utils::VMContextBuilder, testing_env, ONE_NEAR};
+
+    use super::UpgradeRollback;
+    use crate::upgrade::staged::StagedUpgrade;
+
+    struct HashOnly;
+
+    impl UpgradeRollback for HashOnly {}
+
+    struct Retain;
+
+    impl UpgradeRollback for Retain {
+        fn max_retained_code_len() -> usize {
+            4
+        }
+    }
+
+    impl StagedUpgrade for Retain {}
+
+    #[test]
+    fn hash_only() {
+        assert_eq!(HashOnly::previous_code_hash(), None);
+
+        HashOnly::record_deploy(&[1]);
+        assert_eq!(HashOnly::previous_code_hash(), None);
+
+        HashOnly::record_deploy(&[2]);
+        assert_eq!(
+            HashOnly::previous_code_hash(),
+            Some(env::sha256_array(&[1])),
+        );
+        assert!(!HashOnly::is_previous_code_retained());
+    }
+
+    #[test]
+    fn retain_up_to_limit() {
+        Retain::record_deploy(&[1, 1, 1, 1, 1]);
+        Retain::record_deploy(&[2, 2, 2, 2]);
+        assert!(!Retain::is_previous_code_retained());
+
+        Retain::record_deploy(&[3]);
+        assert!(Retain::is_previous_code_retained());
+        assert_eq!(
+            Retain::previous_code_hash(),
+            Some(env::sha256_array(&[2, 2, 2, 2])),
+        );
+    }
+
+    #[test]
+    fn stage_rollback() {
+        testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+
+        Retain::record_deploy(&[1]);
+        Retain::record_deploy(&[2]);
+
+        let code_hash = Retain::stage_rollback();
+
+        assert_eq!(code_hash, env::sha256_array(&[1]));
+        assert_eq!(Retain::staged_code_hash(), Some(code_hash));
+    }
+
+    #[test]
+    #[should_panic = "Previous code was not retained"]
+    fn stage_rollback_not_retained() {
+        testing_env!(VMContextBuilder::new().attached_deposit(ONE_NEAR).build());
+
+        Retain::record_deploy(&[1, 1, 1, 1, 1]);
+        Retain::record_deploy(&[2]);
+
+        Retain::stage_rollback();
+    }
+}
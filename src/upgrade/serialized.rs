@@ -1,9 +1,9 @@
 //! Contract upgrade functions that work as expected in conjunction with
 //! `#[near_bindgen]`.
 
-use near_sdk::{env, GasWeight, Promise};
+use near_sdk::{env, json_types::Base58CryptoHash, require, serde_json::json, CryptoHash, Promise};
 
-use super::PostUpgrade;
+use super::{PostUpgrade, DEFAULT_UPGRADE_COMPLETE_GAS, UPGRADE_COMPLETE_METHOD_NAME};
 
 /// Upgrade lifecycle hooks
 pub trait UpgradeHook {
@@ -13,21 +13,167 @@ pub trait UpgradeHook {
     fn on_upgrade(&self);
 }
 
-/// Creates a promise that upgrades the current contract with given code
+/// Hooks called by the upgrade completion callback (see
+/// [`on_complete`]). If you use the [`crate::Upgrade`] macro with
+/// `on_complete`, the callback is generated for you.
+pub trait UpgradeCompleteHook {
+    /// Called in the new code after the upgrade (including the post-upgrade
+    /// function call) succeeded
+    fn on_upgrade_complete(&mut self, _code_hash: CryptoHash) {}
+
+    /// Called in the old code after the upgrade failed
+    fn on_upgrade_failed(&mut self, _code_hash: CryptoHash) {}
+}
+
+/// Rejects if the SHA-256 hash of `code` does not match `expected_hash`
+pub fn require_code_hash(code: &[u8], expected_hash: CryptoHash) {
+    require_hash_match(env::sha256_array(code), expected_hash);
+}
+
+/// Rejects if `code_hash` does not match `expected_hash`
+pub fn require_hash_match(code_hash: CryptoHash, expected_hash: CryptoHash) {
+    require!(
+        code_hash == expected_hash,
+        format_message!(
+            "UPG15",
+            "Code hash mismatch: expected {}, got {}",
+            String::from(&Base58CryptoHash::from(expected_hash)),
+            String::from(&Base58CryptoHash::from(code_hash)),
+        ),
+    );
+}
+
+/// Creates a promise that upgrades the current contract with given code.
+/// Rejects if not enough gas remains for the post-upgrade function call.
 pub fn upgrade(code: Vec<u8>, post_upgrade: PostUpgrade) -> Promise {
+    post_upgrade.require_minimum_gas();
+
+    let (static_gas, gas_weight) = post_upgrade.gas_allocation();
+
     Promise::new(env::current_account_id())
         .deploy_contract(code)
         .function_call_weight(
             post_upgrade.method,
             post_upgrade.args,
             0,
-            post_upgrade.minimum_gas,
-            GasWeight(u64::MAX),
+            static_gas,
+            gas_weight,
         )
 }
 
+/// Creates a promise that replaces the code of the current contract without
+/// calling any function afterwards. Only suitable if the new code can read
+/// the existing state as-is.
+pub fn deploy(code: Vec<u8>) -> Promise {
+    Promise::new(env::current_account_id()).deploy_contract(code)
+}
+
+/// Schedules a call to `on_upgrade_complete` on the current contract after
+/// the upgrade `promise` resolves, with [`DEFAULT_UPGRADE_COMPLETE_GAS`].
+pub fn on_complete(promise: Promise, code_hash: CryptoHash) -> Promise {
+    promise.then(
+        Promise::new(env::current_account_id()).function_call(
+            UPGRADE_COMPLETE_METHOD_NAME.to_string(),
+            json!({ "code_hash": Base58CryptoHash::from(code_hash) })
+                .to_string()
+                .into_bytes(),
+            0,
+            DEFAULT_UPGRADE_COMPLETE_GAS,
+        ),
+    )
+}
+
 /// Creates a promise that upgrades the current contract with given code and
 /// common defaults for the subsequent post-upgrade invocation.
 pub fn upgrade_default(code: Vec<u8>) -> Promise {
     upgrade(code, PostUpgrade::default())
 }
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        mock::VmAction,
+        test_utils::{get_created_receipts, VMContextBuilder},
+        testing_env, Gas, GasWeight,
+    };
+
+    use super::{on_complete, upgrade};
+    use crate::upgrade::{PostUpgrade, DEFAULT_UPGRADE_COMPLETE_GAS};
+
+    fn post_upgrade_call_gas() -> Gas {
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+
+        match &receipts[0].actions[..] {
+            [VmAction::DeployContract { code }, VmAction::FunctionCall {
+                function_name, gas, ..
+            }] => {
+                assert_eq!(code, &[1, 2, 3]);
+                assert_eq!(function_name, "migrate");
+                *gas
+            }
+            actions => panic!("Unexpected actions: {actions:?}"),
+        }
+    }
+
+    #[test]
+    fn weighted_gas() {
+        let post_upgrade = PostUpgrade::default();
+        assert_eq!(post_upgrade.gas_allocation(), (Gas(0), GasWeight(u64::MAX)),);
+
+        upgrade(vec![1, 2, 3], post_upgrade);
+
+        assert_eq!(post_upgrade_call_gas(), Gas(0));
+    }
+
+    #[test]
+    fn static_gas() {
+        let post_upgrade = PostUpgrade {
+            static_gas: Some(Gas(20_000_000_000_000)),
+            ..Default::default()
+        };
+        assert_eq!(
+            post_upgrade.gas_allocation(),
+            (Gas(20_000_000_000_000), GasWeight(0)),
+        );
+
+        upgrade(vec![1, 2, 3], post_upgrade);
+
+        assert_eq!(post_upgrade_call_gas(), Gas(20_000_000_000_000));
+    }
+
+    #[test]
+    #[should_panic = "Insufficient gas for post-upgrade function call"]
+    fn insufficient_gas() {
+        testing_env!(VMContextBuilder::new()
+            .prepaid_gas(Gas(10_000_000_000_000))
+            .build());
+
+        upgrade(vec![1, 2, 3], PostUpgrade::default());
+    }
+
+    #[test]
+    fn completion_callback() {
+        on_complete(upgrade(vec![1, 2, 3], PostUpgrade::default()), [7; 32]);
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+
+        match &receipts[1].actions[..] {
+            [VmAction::FunctionCall {
+                function_name,
+                args,
+                gas,
+                ..
+            }] => {
+                assert_eq!(function_name, "on_upgrade_complete");
+                assert_eq!(
+                    String::from_utf8(args.clone()).unwrap(),
+                    r#"{"code_hash":"US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx"}"#,
+                );
+                assert_eq!(*gas, DEFAULT_UPGRADE_COMPLETE_GAS);
+            }
+            actions => panic!("Unexpected actions: {actions:?}"),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+//! Two-phase upgrades: the new code is first staged in storage, where its
+//! hash can be inspected, and then deployed in a separate transaction.
+//!
+//! A delay may be enforced between staging and deploying the code (see
+//! [`StagedUpgrade::stage_delay_ns`]), giving users a guaranteed window to
+//! review the new code. Staged code may be removed at any time.
+//!
+//! Staging code consumes storage, so the account staging the code must
+//! attach a deposit covering the storage cost. The deposit is refunded when
+//! the staged code is deployed or unstaged.
+//!
+//! Code that is too large to be sent in a single transaction can be uploaded
+//! in chunks with [`StagedUpgrade::stage_upgrade_chunk`], and then staged
+//! with [`StagedUpgrade::finalize_staged`]. Chunks may be uploaded in any
+//! order; uploading a chunk with the same index again replaces it. The
+//! storage deposit for the chunks is carried over to the staged code.
+//!
+//! Deployment of staged code can be put behind an
+//! [`ApprovalManager`](crate::approval::ApprovalManager) (e.g. a multisig)
+//! using [`UpgradeAction`]. Since the code lives in the staging slot, the
+//! request only needs to carry its hash.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::{Base58CryptoHash, Base64VecU8, U64},
+    require,
+    serde::{Deserialize, Serialize},
+    AccountId, Balance, CryptoHash, Promise,
+};
+
+use crate::{
+    slot::{codec::RawCodec, Slot},
+    DefaultStorageKey,
+};
+
+use super::{serialized, PostUpgrade};
+
+const NO_STAGED_CODE_MESSAGE: &str = message!("UPG1", "No code is staged");
+const EMPTY_CODE_MESSAGE: &str = message!("UPG2", "Cannot stage empty code");
+const STAGE_DELAY_MESSAGE: &str = message!("UPG3", "Staged code cannot be deployed yet");
+const EMPTY_CHUNK_MESSAGE: &str = message!("UPG4", "Cannot upload empty chunk");
+const NO_UPLOAD_MESSAGE: &str = message!("UPG5", "No chunked upload in progress");
+const UPLOAD_IN_PROGRESS_MESSAGE: &str =
+    message!("UPG6", "Another account's chunked upload is in progress");
+const ONLY_UPLOADER_MESSAGE: &str =
+    message!("UPG7", "Only the uploading account may finalize the upload");
+
+/// Account that paid for staging the code, and how much
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StagingDeposit {
+    /// Account to refund
+    pub account_id: AccountId,
+    /// Amount to refund
+    pub amount: Balance,
+}
+
+/// Chunks of code uploaded so far, and the deposit paid for them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkedUpload {
+    /// Account uploading the chunks
+    pub account_id: AccountId,
+    /// Deposit paid for the storage of the chunks
+    pub amount: Balance,
+    /// Indices of the uploaded chunks, in ascending order
+    pub indices: Vec<u32>,
+}
+
+/// Information about the currently staged code
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StagedUpgradeInfo {
+    /// SHA-256 hash of the staged code
+    pub code_hash: Base58CryptoHash,
+    /// Earliest block timestamp at which the code may be deployed
+    pub earliest_deploy_timestamp: U64,
+}
+
+/// Approvable action that deploys the staged code. The [`crate::Upgrade`]
+/// macro implements [`crate::approval::Action`] for this type when
+/// `hook = "multisig"` is specified.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeAction {
+    /// Hash of the code to deploy. Execution fails unless it matches the
+    /// hash of the staged code at that time.
+    pub code_hash: Base58CryptoHash,
+    /// Input for the post-upgrade function. Overrides the default
+    /// configured on the contract, if provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrate_args: Option<Base64VecU8>,
+}
+
+impl UpgradeAction {
+    /// Creates an action that deploys the staged code with the given hash
+    pub fn new(code_hash: CryptoHash) -> Self {
+        Self {
+            code_hash: code_hash.into(),
+            migrate_args: None,
+        }
+    }
+
+    /// Overrides the input for the post-upgrade function
+    pub fn with_migrate_args(mut self, migrate_args: Vec<u8>) -> Self {
+        self.migrate_args = Some(migrate_args.into());
+        self
+    }
+}
+
+/// Storage and logic for two-phase upgrades. Access control is the
+/// responsibility of the caller (the [`crate::Upgrade`] macro calls the
+/// configured [`super::serialized::UpgradeHook`]).
+pub trait StagedUpgrade {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::StagedUpgrade)
+    }
+
+    /// Storage slot for the staged code
+    fn slot_staged_code() -> Slot<Vec<u8>, RawCodec> {
+        Self::root().field(b"c").with_codec()
+    }
+
+    /// Storage slot for the hash of the staged code
+    fn slot_staged_code_hash() -> Slot<CryptoHash> {
+        Self::root().field(b"h")
+    }
+
+    /// Storage slot for the deposit paid to stage the code
+    fn slot_staging_deposit() -> Slot<StagingDeposit> {
+        Self::root().field(b"d")
+    }
+
+    /// Storage slot for the block timestamp at which the code was staged
+    fn slot_staged_at() -> Slot<u64> {
+        Self::root().field(b"t")
+    }
+
+    /// Storage slot for the progress of a chunked upload
+    fn slot_chunked_upload() -> Slot<ChunkedUpload> {
+        Self::root().field(b"u")
+    }
+
+    /// Storage slot for a chunk of a chunked upload
+    fn slot_chunk(index: u32) -> Slot<Vec<u8>, RawCodec> {
+        Self::root()
+            .ns(b"k")
+            .field(index.to_le_bytes().to_vec())
+            .with_codec()
+    }
+
+    /// Minimum time (in nanoseconds) between staging code and deploying it.
+    /// No delay by default.
+    fn stage_delay_ns() -> u64 {
+        0
+    }
+
+    /// SHA-256 hash of the staged code, if any code is staged
+    fn staged_code_hash() -> Option<CryptoHash> {
+        Self::slot_staged_code_hash().read()
+    }
+
+    /// Earliest block timestamp at which the staged code may be deployed, if
+    /// any code is staged
+    fn earliest_deploy_timestamp() -> Option<u64> {
+        Self::slot_staged_at()
+            .read()
+            .map(|staged_at| staged_at.saturating_add(Self::stage_delay_ns()))
+    }
+
+    /// Hash and earliest deploy timestamp of the staged code, if any code is
+    /// staged
+    fn staged_upgrade_info() -> Option<StagedUpgradeInfo> {
+        Some(StagedUpgradeInfo {
+            code_hash: Self::staged_code_hash()?.into(),
+            earliest_deploy_timestamp: Self::earliest_deploy_timestamp()?.into(),
+        })
+    }
+
+    /// Rejects if the stage delay has not yet passed
+    fn require_stage_delay_elapsed() {
+        let earliest = Self::earliest_deploy_timestamp()
+            .unwrap_or_else(|| env::panic_str(NO_STAGED_CODE_MESSAGE));
+
+        require!(env::block_timestamp() >= earliest, STAGE_DELAY_MESSAGE);
+    }
+
+    /// Stores the code in storage, replacing any previously staged code.
+    /// The attached deposit must cover the storage cost of the code; any
+    /// excess is refunded to the predecessor.
+    fn stage_upgrade(code: Vec<u8>) -> CryptoHash {
+        Self::stage_upgrade_with_deposit(code, env::attached_deposit())
+    }
+
+    /// Same as [`StagedUpgrade::stage_upgrade`], but the storage cost is
+    /// paid out of `available_deposit` instead of the attached deposit.
+    fn stage_upgrade_with_deposit(code: Vec<u8>, available_deposit: Balance) -> CryptoHash {
+        require!(!code.is_empty(), EMPTY_CODE_MESSAGE);
+
+        if Self::staged_code_hash().is_some() {
+            Self::unstage_upgrade();
+        }
+
+        let storage_usage_start = env::storage_usage();
+
+        let code_hash = env::sha256_array(&code);
+        Self::slot_staged_code().write(&code);
+        Self::slot_staged_code_hash().write(&code_hash);
+        Self::slot_staged_at().write(&env::block_timestamp());
+
+        let predecessor = env::predecessor_account_id();
+        let deposit = StagingDeposit {
+            account_id: predecessor.clone(),
+            amount: 0,
+        };
+        Self::slot_staging_deposit().write(&deposit);
+
+        let storage_cost =
+            (env::storage_usage() - storage_usage_start) as Balance * env::storage_byte_cost();
+
+        require!(
+            available_deposit >= storage_cost,
+            format_message!(
+                "UPG11",
+                "Insufficient deposit to stage code: {storage_cost} yoctoNEAR required"
+            ),
+        );
+
+        Self::slot_staging_deposit().write(&StagingDeposit {
+            amount: storage_cost,
+            ..deposit
+        });
+
+        let refund = available_deposit - storage_cost;
+        if refund > 0 {
+            Promise::new(predecessor).transfer(refund);
+        }
+
+        code_hash
+    }
+
+    /// Progress of the current chunked upload, if any
+    fn chunked_upload() -> Option<ChunkedUpload> {
+        Self::slot_chunked_upload().read()
+    }
+
+    /// Stores a chunk of code, replacing any chunk previously uploaded with
+    /// the same index. The attached deposit must cover any increase in
+    /// storage; any excess (and the deposit for storage freed by replacing
+    /// a larger chunk) is refunded to the predecessor. Only one account may
+    /// upload chunks at a time.
+    fn stage_upgrade_chunk(index: u32, bytes: Vec<u8>) {
+        require!(!bytes.is_empty(), EMPTY_CHUNK_MESSAGE);
+
+        let predecessor = env::predecessor_account_id();
+
+        let mut upload = Self::chunked_upload().unwrap_or_else(|| ChunkedUpload {
+            account_id: predecessor.clone(),
+            amount: 0,
+            indices: vec![],
+        });
+
+        require!(upload.account_id == predecessor, UPLOAD_IN_PROGRESS_MESSAGE);
+
+        let storage_usage_start = env::storage_usage();
+
+        Self::slot_chunk(index).write(&bytes);
+        if let Err(position) = upload.indices.binary_search(&index) {
+            upload.indices.insert(position, index);
+        }
+        Self::slot_chunked_upload().write(&upload);
+
+        let storage_usage_end = env::storage_usage();
+        let attached_deposit = env::attached_deposit();
+
+        let refund = if storage_usage_end >= storage_usage_start {
+            let storage_cost =
+                (storage_usage_end - storage_usage_start) as Balance * env::storage_byte_cost();
+
+            require!(
+                attached_deposit >= storage_cost,
+                format_message!(
+                    "UPG12",
+                    "Insufficient deposit to upload chunk: {storage_cost} yoctoNEAR required"
+                ),
+            );
+
+            upload.amount += storage_cost;
+            attached_deposit - storage_cost
+        } else {
+            let storage_freed = ((storage_usage_start - storage_usage_end) as Balance
+                * env::storage_byte_cost())
+            .min(upload.amount);
+
+            upload.amount -= storage_freed;
+            attached_deposit + storage_freed
+        };
+
+        Self::slot_chunked_upload().write(&upload);
+
+        if refund > 0 {
+            Promise::new(predecessor).transfer(refund);
+        }
+    }
+
+    /// Concatenates the uploaded chunks in order of their indices, which
+    /// must be exactly `0..total_chunks`, and stages the result, replacing
+    /// any previously staged code. Rejects unless the hash of the result
+    /// matches `expected_hash`. The deposit paid for the chunks (plus the
+    /// attached deposit) is used to pay for the staged code.
+    fn finalize_staged(total_chunks: u32, expected_hash: CryptoHash) -> CryptoHash {
+        let upload = Self::chunked_upload().unwrap_or_else(|| env::panic_str(NO_UPLOAD_MESSAGE));
+
+        require!(
+            upload.account_id == env::predecessor_account_id(),
+            ONLY_UPLOADER_MESSAGE,
+        );
+
+        require!(
+            upload.indices.iter().copied().eq(0..total_chunks),
+            format_message!(
+                "UPG13",
+                "Expected chunks 0 to {} to be uploaded, got {:?}",
+                total_chunks.saturating_sub(1),
+                upload.indices,
+            ),
+        );
+
+        let mut code = vec![];
+        for index in 0..total_chunks {
+            let mut chunk_slot = Self::slot_chunk(index);
+            code.extend(chunk_slot.read().unwrap_or_else(|| {
+                env::panic_str(message!("UPG9", "Inconsistent chunked upload state"))
+            }));
+            chunk_slot.remove();
+        }
+
+        Self::slot_chunked_upload().remove();
+
+        serialized::require_code_hash(&code, expected_hash);
+
+        Self::stage_upgrade_with_deposit(code, upload.amount + env::attached_deposit())
+    }
+
+    /// Removes all uploaded chunks and refunds the deposit paid for them
+    fn abort_staged_upload() {
+        let upload = Self::chunked_upload().unwrap_or_else(|| env::panic_str(NO_UPLOAD_MESSAGE));
+
+        for index in &upload.indices {
+            Self::slot_chunk(*index).remove();
+        }
+
+        Self::slot_chunked_upload().remove();
+
+        Self::refund(StagingDeposit {
+            account_id: upload.account_id,
+            amount: upload.amount,
+        });
+    }
+
+    /// Removes the staged code and refunds the staging deposit
+    fn unstage_upgrade() {
+        let deposit = Self::take_staged()
+            .map(|(_, deposit)| deposit)
+            .unwrap_or_else(|| env::panic_str(NO_STAGED_CODE_MESSAGE));
+
+        Self::refund(deposit);
+    }
+
+    /// Deploys the staged code, removes it from storage, and refunds the
+    /// staging deposit. Rejects if the stage delay has not yet passed. If
+    /// `expected_hash` is provided, rejects unless it matches the hash of
+    /// the staged code. If `post_upgrade` is `None`, no function is called
+    /// after the deployment.
+    fn deploy_staged(
+        post_upgrade: Option<PostUpgrade>,
+        expected_hash: Option<CryptoHash>,
+    ) -> Promise {
+        Self::require_stage_delay_elapsed();
+
+        if let Some(expected_hash) = expected_hash {
+            let code_hash =
+                Self::staged_code_hash().unwrap_or_else(|| env::panic_str(NO_STAGED_CODE_MESSAGE));
+            serialized::require_hash_match(code_hash, expected_hash);
+        }
+
+        let (code, deposit) =
+            Self::take_staged().unwrap_or_else(|| env::panic_str(NO_STAGED_CODE_MESSAGE));
+
+        Self::refund(deposit);
+
+        match post_upgrade {
+            Some(post_upgrade) => serialized::upgrade(code, post_upgrade),
+            None => serialized::deploy(code),
+        }
+    }
+
+    /// Removes the staged code and deposit record from storage, returning them
+    fn take_staged() -> Option<(Vec<u8>, StagingDeposit)> {
+        let code = Self::slot_staged_code().read()?;
+        let deposit = Self::slot_staging_deposit().read().unwrap_or_else(|| {
+            env::panic_str(message!("UPG10", "Inconsistent staged upgrade state"))
+        });
+
+        Self::slot_staged_code().remove();
+        Self::slot_staged_code_hash().remove();
+        Self::slot_staging_deposit().remove();
+        Self::slot_staged_at().remove();
+
+        Some((code, deposit))
+    }
+
+    /// Refunds a staging deposit
+    fn refund(deposit: StagingDeposit) {
+        if deposit.amount > 0 {
+            Promise::new(deposit.account_id).transfer(deposit.amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{env, test_utils::VMContextBuilder, testing_env, AccountId, ONE_NEAR};
+
+    use super::StagedUpgrade;
+    use crate::upgrade::PostUpgrade;
+
+    const DELAY: u64 = 1_000;
+
+    struct Contract;
+
+    impl StagedUpgrade for Contract {
+        fn stage_delay_ns() -> u64 {
+            DELAY
+        }
+    }
+
+    fn at(timestamp: u64) {
+        let alice: AccountId = "alice".parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice)
+            .attached_deposit(ONE_NEAR)
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    #[test]
+    fn stage_delay() {
+        at(5);
+        let code_hash = Contract::stage_upgrade(vec![1, 2, 3]);
+
+        let info = Contract::staged_upgrade_info().unwrap();
+        assert_eq!(info.code_hash, code_hash.into());
+        assert_eq!(info.earliest_deploy_timestamp.0, 5 + DELAY);
+
+        at(5 + DELAY);
+        Contract::deploy_staged(Some(PostUpgrade::default()), None);
+
+        assert_eq!(Contract::staged_upgrade_info(), None);
+    }
+
+    #[test]
+    #[should_panic = "Staged code cannot be deployed yet"]
+    fn deploy_before_delay() {
+        at(5);
+        Contract::stage_upgrade(vec![1, 2, 3]);
+
+        at(5 + DELAY - 1);
+        Contract::deploy_staged(Some(PostUpgrade::default()), None);
+    }
+
+    #[test]
+    fn unstage_during_delay() {
+        at(5);
+        Contract::stage_upgrade(vec![1, 2, 3]);
+
+        at(6);
+        Contract::unstage_upgrade();
+
+        assert_eq!(Contract::staged_code_hash(), None);
+        assert_eq!(Contract::earliest_deploy_timestamp(), None);
+    }
+
+    #[test]
+    #[should_panic = "Insufficient deposit to stage code"]
+    fn insufficient_deposit() {
+        testing_env!(VMContextBuilder::new().attached_deposit(0).build());
+
+        Contract::stage_upgrade(vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn chunked_upload_out_of_order() {
+        at(5);
+        Contract::stage_upgrade_chunk(2, vec![5, 6]);
+        Contract::stage_upgrade_chunk(0, vec![9, 9, 9, 9]);
+        Contract::stage_upgrade_chunk(1, vec![3, 4]);
+        Contract::stage_upgrade_chunk(0, vec![1, 2]);
+
+        assert_eq!(Contract::chunked_upload().unwrap().indices, vec![0, 1, 2]);
+
+        let expected_hash = env::sha256_array(&[1, 2, 3, 4, 5, 6]);
+        let code_hash = Contract::finalize_staged(3, expected_hash);
+
+        assert_eq!(code_hash, expected_hash);
+        assert_eq!(Contract::staged_code_hash(), Some(expected_hash));
+        assert_eq!(Contract::chunked_upload(), None);
+        assert_eq!(Contract::slot_chunk(0).read_raw(), None);
+    }
+
+    #[test]
+    #[should_panic = "Expected chunks 0 to 2 to be uploaded, got [0, 2]"]
+    fn chunked_upload_missing_chunk() {
+        at(5);
+        Contract::stage_upgrade_chunk(0, vec![1, 2]);
+        Contract::stage_upgrade_chunk(2, vec![5, 6]);
+
+        Contract::finalize_staged(3, env::sha256_array(&[1, 2, 5, 6]));
+    }
+
+    #[test]
+    fn abort_chunked_upload() {
+        at(5);
+        Contract::stage_upgrade_chunk(0, vec![1, 2]);
+        Contract::stage_upgrade_chunk(1, vec![3, 4]);
+
+        Contract::abort_staged_upload();
+
+        assert_eq!(Contract::chunked_upload(), None);
+        assert_eq!(Contract::slot_chunk(0).read_raw(), None);
+        assert_eq!(Contract::slot_chunk(1).read_raw(), None);
+    }
+}
@@ -2,6 +2,8 @@
 
 use near_sdk::{env, require, Promise};
 
+use crate::slot::Slot;
+
 /// Concatenate bytes to form a key. Useful for generating storage keys.
 ///
 /// # Examples
@@ -53,7 +55,7 @@ pub fn apply_storage_fee_and_refund(
 
     require!(
         attached_deposit >= total_required_deposit,
-        format!(
+        format_message!("STO3", 
             "Insufficient deposit: attached {attached_deposit} yoctoNEAR < required {total_required_deposit} yoctoNEAR ({storage_fee} storage + {additional_fees} additional)",
         )
     );
@@ -67,3 +69,59 @@ pub fn apply_storage_fee_and_refund(
         None
     }
 }
+
+// The helpers below are not generic and never inlined, so that a contract
+// contains one copy of their code, however many components and call sites
+// use them.
+
+/// Reads a `u128` from a slot. Returns `0` if the slot is empty.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk_contract_tools::{slot::Slot, utils::read_u128_or_zero};
+///
+/// let mut slot = Slot::<u128>::new(b"balance".to_vec());
+/// assert_eq!(read_u128_or_zero(&slot), 0);
+///
+/// slot.write(&7);
+/// assert_eq!(read_u128_or_zero(&slot), 7);
+/// ```
+#[inline(never)]
+pub fn read_u128_or_zero(slot: &Slot<u128>) -> u128 {
+    slot.read_raw().map_or(0, |bytes| {
+        <[u8; 16]>::try_from(bytes)
+            .map(u128::from_le_bytes)
+            .unwrap_or_else(|_| env::panic_str(message!("UTIL1", "Invalid u128 in storage")))
+    })
+}
+
+/// Adds `amount` to the `u128` in a slot (`0` if empty), and returns the new
+/// value.
+///
+/// # Panics
+///
+/// Panics with `overflow_message` on overflow.
+#[inline(never)]
+pub fn checked_add_u128(slot: &mut Slot<u128>, amount: u128, overflow_message: &str) -> u128 {
+    let value = read_u128_or_zero(slot)
+        .checked_add(amount)
+        .unwrap_or_else(|| env::panic_str(overflow_message));
+    slot.write_raw(&value.to_le_bytes());
+    value
+}
+
+/// Subtracts `amount` from the `u128` in a slot (`0` if empty), and returns
+/// the new value.
+///
+/// # Panics
+///
+/// Panics with `underflow_message` on underflow.
+#[inline(never)]
+pub fn checked_sub_u128(slot: &mut Slot<u128>, amount: u128, underflow_message: &str) -> u128 {
+    let value = read_u128_or_zero(slot)
+        .checked_sub(amount)
+        .unwrap_or_else(|| env::panic_str(underflow_message));
+    slot.write_raw(&value.to_le_bytes());
+    value
+}
@@ -0,0 +1,557 @@
+//! Linear vesting of NEP-141 tokens, with an optional cliff.
+//!
+//! Each beneficiary has at most one [`VestingSchedule`]. Nothing vests
+//! before the cliff; at the cliff, the amount that would have vested
+//! linearly since the start vests at once, and the rest vests linearly until
+//! the end of the schedule. Beneficiaries claim vested tokens with
+//! [`Vesting::claim_vested`].
+//!
+//! Claimed tokens are minted, or transferred from the contract account,
+//! depending on [`Vesting::funding`]. A schedule may be revoked, which stops
+//! the vesting: the tokens vested so far remain claimable, and the unvested
+//! tokens are paid (in the same way) to the treasury account.
+//!
+//! Access control is the responsibility of the caller: [`Vesting`] does not
+//! check who creates or revokes schedules.
+//!
+//! ```
+//! use near_sdk::{env, json_types::{U128, U64}, near_bindgen, AccountId};
+//! use near_sdk_contract_tools::{
+//!     owner::Owner,
+//!     vesting::{Vesting, VestingSchedule},
+//!     FungibleToken, Owner,
+//! };
+//!
+//! #[derive(Owner, FungibleToken)]
+//! #[fungible_token(name = "Token", symbol = "TKN", decimals = 18, no_hooks)]
+//! #[near_bindgen]
+//! struct Contract {}
+//!
+//! impl Vesting for Contract {}
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     pub fn create_schedule(
+//!         &mut self,
+//!         beneficiary_id: AccountId,
+//!         start: U64,
+//!         cliff: U64,
+//!         duration: U64,
+//!         total: U128,
+//!     ) {
+//!         Self::require_owner();
+//!         self.create_vesting_schedule(
+//!             beneficiary_id,
+//!             VestingSchedule::new(start.0, cliff.0, duration.0, total.0),
+//!         );
+//!     }
+//!
+//!     pub fn claim(&mut self) -> U128 {
+//!         self.claim_vested(&env::predecessor_account_id()).into()
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The vesting root storage slot is not used or modified. The default key is `~vs`.
+//! * (UB) With [`VestingFunding::Transfer`], the tokens of the schedules are
+//!   not otherwise moved out of the contract account.
+//! * (ERR) A beneficiary may have one schedule only, even after it is revoked.
+//! * (ERR) The cliff of a schedule is not after its end.
+//! * (ERR) A schedule may be revoked once.
+//! * (ERR) The treasury account is set before a schedule is revoked.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::{U128, U64},
+    require, AccountId, BorshStorageKey,
+};
+use near_sdk_contract_tools_macros::event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slot::Slot,
+    standard::{nep141::Nep141Controller, nep297::Event},
+    DefaultStorageKey,
+};
+
+const SCHEDULE_EXISTS_FAIL_MESSAGE: &str = message!("VST1", "Vesting schedule already exists");
+const INVALID_SCHEDULE_FAIL_MESSAGE: &str = message!("VST2", "Invalid vesting schedule");
+const NO_SCHEDULE_FAIL_MESSAGE: &str = message!("VST3", "No vesting schedule");
+const NOTHING_TO_CLAIM_FAIL_MESSAGE: &str = message!("VST4", "Nothing to claim");
+const REVOKED_FAIL_MESSAGE: &str = message!("VST5", "Vesting schedule already revoked");
+const NO_TREASURY_FAIL_MESSAGE: &str = message!("VST6", "No treasury account");
+
+/// How vested tokens are paid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VestingFunding {
+    /// Tokens are minted when they are paid out
+    Mint,
+    /// Tokens are transferred from the contract account, which must hold
+    /// the tokens of all schedules
+    Transfer,
+}
+
+/// A linear vesting schedule. Timestamps and durations are in nanoseconds.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+pub struct VestingSchedule {
+    /// Block timestamp at which vesting starts
+    pub start: U64,
+    /// Time after `start` before which nothing vests
+    pub cliff: U64,
+    /// Time after `start` at which everything has vested
+    pub duration: U64,
+    /// Amount vested over the whole schedule
+    pub total: U128,
+    /// Amount claimed so far
+    pub claimed: U128,
+    /// Block timestamp at which the schedule was revoked, if it was
+    pub revoked_at: Option<U64>,
+}
+
+impl VestingSchedule {
+    /// Creates a schedule vesting `total` over `duration` from `start`,
+    /// with a cliff after `cliff`
+    pub fn new(start: u64, cliff: u64, duration: u64, total: u128) -> Self {
+        Self {
+            start: start.into(),
+            cliff: cliff.into(),
+            duration: duration.into(),
+            total: total.into(),
+            claimed: U128(0),
+            revoked_at: None,
+        }
+    }
+
+    /// Whether the schedule vests anything, and its cliff is not after its
+    /// end
+    pub fn is_valid(&self) -> bool {
+        self.total.0 > 0 && self.duration.0 > 0 && self.cliff.0 <= self.duration.0
+    }
+
+    /// Amount vested at block timestamp `now`, including claimed tokens.
+    /// Nothing vests after the schedule is revoked.
+    pub fn vested_at(&self, now: u64) -> u128 {
+        let now = match self.revoked_at {
+            Some(revoked_at) => now.min(revoked_at.0),
+            None => now,
+        };
+        let elapsed = now.saturating_sub(self.start.0);
+
+        if elapsed < self.cliff.0 {
+            0
+        } else if elapsed >= self.duration.0 {
+            self.total.0
+        } else {
+            // total * elapsed / duration, without overflow: the remainder is
+            // less than `duration`, so its product with `elapsed` fits
+            let (elapsed, duration) = (elapsed as u128, self.duration.0 as u128);
+            self.total.0 / duration * elapsed + self.total.0 % duration * elapsed / duration
+        }
+    }
+
+    /// Amount that can be claimed at block timestamp `now`
+    pub fn claimable_at(&self, now: u64) -> u128 {
+        self.vested_at(now) - self.claimed.0
+    }
+}
+
+/// Events emitted when vesting schedules are created, claimed, and revoked
+#[event(
+    standard = "x-vesting",
+    version = "1.0.0",
+    crate = "crate",
+    macros = "near_sdk_contract_tools_macros"
+)]
+#[derive(Debug, Clone)]
+pub enum VestingEvent {
+    /// Emitted when a schedule is created
+    Create {
+        /// Account receiving the vested tokens
+        beneficiary_id: AccountId,
+        /// The schedule
+        schedule: VestingSchedule,
+    },
+    /// Emitted when vested tokens are claimed
+    Claim {
+        /// Account receiving the vested tokens
+        beneficiary_id: AccountId,
+        /// Amount claimed
+        amount: U128,
+    },
+    /// Emitted when a schedule is revoked
+    Revoke {
+        /// Account of the revoked schedule
+        beneficiary_id: AccountId,
+        /// Account receiving the unvested tokens
+        treasury_id: AccountId,
+        /// Amount of unvested tokens
+        amount: U128,
+    },
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey<'a> {
+    Treasury,
+    Schedule(&'a AccountId),
+}
+
+/// Vesting schedules of NEP-141 tokens
+pub trait Vesting: Nep141Controller {
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::Vesting)
+    }
+
+    /// Storage slot of the treasury account
+    fn slot_treasury() -> Slot<AccountId> {
+        <Self as Vesting>::root().field(StorageKey::Treasury)
+    }
+
+    /// Storage slot of the schedule of a beneficiary
+    fn slot_vesting_schedule(beneficiary_id: &AccountId) -> Slot<VestingSchedule> {
+        <Self as Vesting>::root().field(StorageKey::Schedule(beneficiary_id))
+    }
+
+    /// How vested tokens are paid out. Minted by default.
+    fn funding() -> VestingFunding {
+        VestingFunding::Mint
+    }
+
+    /// Account receiving the unvested tokens of revoked schedules, if set
+    fn treasury_id() -> Option<AccountId> {
+        Self::slot_treasury().read()
+    }
+
+    /// Sets the account receiving the unvested tokens of revoked schedules
+    fn set_treasury_id(&mut self, treasury_id: &AccountId) {
+        Self::slot_treasury().write(treasury_id);
+    }
+
+    /// Returns the schedule of `beneficiary_id`, if any
+    fn vesting_schedule(beneficiary_id: &AccountId) -> Option<VestingSchedule> {
+        Self::slot_vesting_schedule(beneficiary_id).read()
+    }
+
+    /// Amount that `beneficiary_id` can claim at block timestamp `now`
+    fn claimable(beneficiary_id: &AccountId, now: u64) -> u128 {
+        Self::vesting_schedule(beneficiary_id).map_or(0, |schedule| schedule.claimable_at(now))
+    }
+
+    /// Creates a schedule for `beneficiary_id`. Emits a
+    /// [`VestingEvent::Create`] event.
+    ///
+    /// # Panics
+    ///
+    /// If `beneficiary_id` already has a schedule, or if the schedule is not
+    /// valid (see [`VestingSchedule::is_valid`]).
+    fn create_vesting_schedule(&mut self, beneficiary_id: AccountId, schedule: VestingSchedule) {
+        let schedule = VestingSchedule {
+            claimed: U128(0),
+            revoked_at: None,
+            ..schedule
+        };
+        require!(schedule.is_valid(), INVALID_SCHEDULE_FAIL_MESSAGE);
+
+        let mut slot = Self::slot_vesting_schedule(&beneficiary_id);
+        require!(!slot.exists(), SCHEDULE_EXISTS_FAIL_MESSAGE);
+        slot.write(&schedule);
+
+        VestingEvent::Create {
+            beneficiary_id,
+            schedule,
+        }
+        .emit();
+    }
+
+    /// Pays out the tokens vested to `beneficiary_id` and not yet claimed.
+    /// Emits a [`VestingEvent::Claim`] event. Returns the amount claimed.
+    ///
+    /// # Panics
+    ///
+    /// If `beneficiary_id` has no schedule, or nothing to claim.
+    fn claim_vested(&mut self, beneficiary_id: &AccountId) -> u128 {
+        let mut slot = Self::slot_vesting_schedule(beneficiary_id);
+        let mut schedule = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_SCHEDULE_FAIL_MESSAGE));
+
+        let amount = schedule.claimable_at(env::block_timestamp());
+        require!(amount > 0, NOTHING_TO_CLAIM_FAIL_MESSAGE);
+
+        schedule.claimed = (schedule.claimed.0 + amount).into();
+        slot.write(&schedule);
+
+        pay_out(self, beneficiary_id.clone(), amount);
+
+        VestingEvent::Claim {
+            beneficiary_id: beneficiary_id.clone(),
+            amount: amount.into(),
+        }
+        .emit();
+
+        amount
+    }
+
+    /// Stops the vesting of the schedule of `beneficiary_id`, and pays out
+    /// the unvested tokens to the treasury account. The tokens vested so far
+    /// remain claimable. Emits a [`VestingEvent::Revoke`] event. Returns the
+    /// amount of unvested tokens.
+    ///
+    /// # Panics
+    ///
+    /// If `beneficiary_id` has no schedule, if it is already revoked, or if
+    /// the treasury account is not set.
+    fn revoke_vesting_schedule(&mut self, beneficiary_id: &AccountId) -> u128 {
+        let treasury_id =
+            Self::treasury_id().unwrap_or_else(|| env::panic_str(NO_TREASURY_FAIL_MESSAGE));
+
+        let mut slot = Self::slot_vesting_schedule(beneficiary_id);
+        let mut schedule = slot
+            .read()
+            .unwrap_or_else(|| env::panic_str(NO_SCHEDULE_FAIL_MESSAGE));
+        require!(schedule.revoked_at.is_none(), REVOKED_FAIL_MESSAGE);
+
+        let now = env::block_timestamp();
+        let amount = schedule.total.0 - schedule.vested_at(now);
+
+        schedule.revoked_at = Some(now.into());
+        slot.write(&schedule);
+
+        if amount > 0 {
+            pay_out(self, treasury_id.clone(), amount);
+        }
+
+        VestingEvent::Revoke {
+            beneficiary_id: beneficiary_id.clone(),
+            treasury_id,
+            amount: amount.into(),
+        }
+        .emit();
+
+        amount
+    }
+}
+
+/// Mints `amount` to `receiver_id`, or transfers it from the contract
+/// account, depending on [`Vesting::funding`]
+fn pay_out<C: Vesting + ?Sized>(contract: &mut C, receiver_id: AccountId, amount: u128) {
+    let memo = Some("vesting".to_string());
+
+    match C::funding() {
+        VestingFunding::Mint => contract.mint(receiver_id, amount, memo),
+        VestingFunding::Transfer => {
+            contract.transfer(env::current_account_id(), receiver_id, amount, memo)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::near_bindgen;
+
+    use super::{Vesting, VestingFunding, VestingSchedule};
+    use crate::{
+        standard::nep141::Nep141Controller,
+        testing::{account, TestEnv},
+        Nep141,
+    };
+
+    const START: u64 = 1_000;
+    const CLIFF: u64 = 250;
+    const DURATION: u64 = 1_000;
+    const TOTAL: u128 = 4_000;
+
+    #[derive(Nep141)]
+    #[nep141(crate = "crate", no_hooks)]
+    #[near_bindgen]
+    struct Contract {}
+
+    impl Vesting for Contract {}
+
+    #[derive(Nep141)]
+    #[nep141(crate = "crate", no_hooks)]
+    #[near_bindgen]
+    struct TransferContract {}
+
+    impl Vesting for TransferContract {
+        fn funding() -> VestingFunding {
+            VestingFunding::Transfer
+        }
+    }
+
+    fn setup() -> (TestEnv, Contract) {
+        let env = TestEnv::new();
+        let mut contract = Contract {};
+
+        contract.set_treasury_id(&account("treasury"));
+        contract.create_vesting_schedule(
+            account("alice"),
+            VestingSchedule::new(START, CLIFF, DURATION, TOTAL),
+        );
+
+        (env, contract)
+    }
+
+    #[test]
+    fn vested_at() {
+        let schedule = VestingSchedule::new(START, CLIFF, DURATION, TOTAL);
+
+        assert_eq!(schedule.vested_at(0), 0);
+        assert_eq!(schedule.vested_at(START + CLIFF - 1), 0);
+        assert_eq!(schedule.vested_at(START + CLIFF), 1_000);
+        assert_eq!(schedule.vested_at(START + 500), 2_000);
+        assert_eq!(schedule.vested_at(START + DURATION), TOTAL);
+        assert_eq!(schedule.vested_at(u64::MAX), TOTAL);
+    }
+
+    #[test]
+    fn vested_at_large_total() {
+        let schedule = VestingSchedule::new(0, 0, 3, u128::MAX);
+
+        assert_eq!(schedule.vested_at(1), u128::MAX / 3);
+        assert_eq!(schedule.vested_at(2), u128::MAX / 3 * 2);
+        assert_eq!(schedule.vested_at(3), u128::MAX);
+    }
+
+    #[test]
+    fn claim_at_cliff() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + CLIFF);
+        assert_eq!(Contract::claimable(&account("alice"), START + CLIFF), 1_000);
+        assert_eq!(contract.claim_vested(&account("alice")), 1_000);
+
+        assert_eq!(Contract::balance_of(&account("alice")), 1_000);
+        assert_eq!(Contract::total_supply(), 1_000);
+        assert_eq!(Contract::claimable(&account("alice"), START + CLIFF), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn claim_before_cliff() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + CLIFF - 1);
+        contract.claim_vested(&account("alice"));
+    }
+
+    #[test]
+    fn claim_after_vesting() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + 500);
+        assert_eq!(contract.claim_vested(&account("alice")), 2_000);
+
+        env.set_block_timestamp(START + DURATION * 2);
+        assert_eq!(contract.claim_vested(&account("alice")), 2_000);
+
+        assert_eq!(Contract::balance_of(&account("alice")), TOTAL);
+        assert_eq!(
+            Contract::vesting_schedule(&account("alice"))
+                .unwrap()
+                .claimed
+                .0,
+            TOTAL,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn claim_twice_after_vesting() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + DURATION);
+        contract.claim_vested(&account("alice"));
+        contract.claim_vested(&account("alice"));
+    }
+
+    #[test]
+    fn revoke() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + 500);
+        assert_eq!(contract.revoke_vesting_schedule(&account("alice")), 2_000);
+        assert_eq!(Contract::balance_of(&account("treasury")), 2_000);
+
+        // What vested before the revocation remains claimable
+        env.set_block_timestamp(START + DURATION);
+        assert_eq!(
+            Contract::claimable(&account("alice"), START + DURATION),
+            2_000
+        );
+        assert_eq!(contract.claim_vested(&account("alice")), 2_000);
+        assert_eq!(Contract::total_supply(), TOTAL);
+    }
+
+    #[test]
+    fn revoke_before_cliff() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START);
+        assert_eq!(contract.revoke_vesting_schedule(&account("alice")), TOTAL);
+        assert_eq!(Contract::claimable(&account("alice"), u64::MAX), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vesting schedule already revoked")]
+    fn revoke_twice() {
+        let (mut env, mut contract) = setup();
+
+        env.set_block_timestamp(START + 500);
+        contract.revoke_vesting_schedule(&account("alice"));
+        contract.revoke_vesting_schedule(&account("alice"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Vesting schedule already exists")]
+    fn create_twice() {
+        let (_env, mut contract) = setup();
+
+        contract.create_vesting_schedule(
+            account("alice"),
+            VestingSchedule::new(START, CLIFF, DURATION, TOTAL),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid vesting schedule")]
+    fn cliff_after_end() {
+        let (_env, mut contract) = setup();
+
+        contract.create_vesting_schedule(
+            account("bob"),
+            VestingSchedule::new(START, DURATION + 1, DURATION, TOTAL),
+        );
+    }
+
+    #[test]
+    fn transfer_funding() {
+        let mut env = TestEnv::new();
+        let mut contract = TransferContract {};
+        let contract_id = near_sdk::env::current_account_id();
+
+        contract.deposit_unchecked(&contract_id, TOTAL);
+        contract.set_treasury_id(&account("treasury"));
+        contract.create_vesting_schedule(
+            account("alice"),
+            VestingSchedule::new(START, CLIFF, DURATION, TOTAL),
+        );
+
+        env.set_block_timestamp(START + 500);
+        contract.claim_vested(&account("alice"));
+        contract.revoke_vesting_schedule(&account("alice"));
+
+        assert_eq!(TransferContract::balance_of(&account("alice")), 2_000);
+        assert_eq!(TransferContract::balance_of(&account("treasury")), 2_000);
+        assert_eq!(TransferContract::balance_of(&contract_id), 0);
+        assert_eq!(TransferContract::total_supply(), TOTAL);
+    }
+}
@@ -0,0 +1,353 @@
+//! Cross-contract calls with rollback.
+//!
+//! A common pattern is to update state optimistically, make a cross-contract
+//! call, and revert the update in a callback if the call failed.
+//! [`CallWithRollback`] schedules a standard callback on the current contract
+//! after the call, and [`XCall::resolve_call`] inspects the result of the
+//! call in that callback and runs the [`Rollback`] given to
+//! [`CallWithRollback::on_failure`].
+//!
+//! Closures cannot cross the callback boundary, so a rollback is a value: the
+//! state it needs is persisted in storage under a call ID until the callback
+//! runs. The contract exposes the callback as a private method named
+//! [`XCall::resolve_method_name`] (`xcall_resolve` by default), taking the
+//! call ID.
+//!
+//! ```
+//! use near_sdk::{
+//!     borsh::{self, BorshDeserialize, BorshSerialize},
+//!     json_types::U128,
+//!     near_bindgen, AccountId, Gas, Promise,
+//! };
+//! use near_sdk_contract_tools::xcall::{CallWithRollback, Rollback, XCall};
+//!
+//! #[derive(BorshSerialize, BorshDeserialize)]
+//! struct Unreserve {
+//!     amount: u128,
+//! }
+//!
+//! impl Rollback<Contract> for Unreserve {
+//!     type Output = ();
+//!
+//!     fn commit(self, _contract: &mut Contract, _value: Vec<u8>) {}
+//!
+//!     fn rollback(self, contract: &mut Contract) {
+//!         contract.reserved -= self.amount;
+//!     }
+//! }
+//!
+//! #[near_bindgen]
+//! struct Contract {
+//!     reserved: u128,
+//! }
+//!
+//! impl XCall for Contract {
+//!     type Rollback = Unreserve;
+//! }
+//!
+//! #[near_bindgen]
+//! impl Contract {
+//!     pub fn reserve(&mut self, oracle_id: AccountId, amount: U128) -> Promise {
+//!         self.reserved += amount.0;
+//!
+//!         let call = Promise::new(oracle_id).function_call(
+//!             "confirm".to_string(),
+//!             vec![],
+//!             0,
+//!             Gas(10_000_000_000_000),
+//!         );
+//!
+//!         CallWithRollback::new(call)
+//!             .on_failure(Unreserve { amount: amount.0 })
+//!             .schedule::<Self>()
+//!     }
+//!
+//!     #[private]
+//!     pub fn xcall_resolve(&mut self, call_id: u32) {
+//!         self.resolve_call(call_id)
+//!     }
+//! }
+//! ```
+//!
+//! The same [`Rollback`] trait resolves callbacks that receive their state
+//! as arguments instead, with [`resolve`]: the NEP-141 `ft_resolve_transfer`
+//! is [`FtTransferCallRollback`](crate::standard::nep141::FtTransferCallRollback).
+//!
+//! # Safety
+//! The default implementation assumes or enforces the following invariants.
+//! Violating assumed invariants may corrupt contract state and show unexpected
+//! behavior (UB). Enforced invariants throw an error (ERR) but contract
+//! state remains intact.
+//!
+//! * (UB) The cross-contract call root storage slot is not used or modified. The default key is `~xc`.
+//! * (UB) The callback method calls [`XCall::resolve_call`] and nothing
+//!   else that may fail before it. If the callback fails, the rollback is
+//!   not run, and its state stays in storage.
+//! * (ERR) [`XCall::resolve_call`] is only called by the contract itself.
+//! * (ERR) A call is resolved at most once.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, require,
+    serde_json::json,
+    BorshStorageKey, Gas, Promise, PromiseResult,
+};
+
+use crate::{slot::Slot, DefaultStorageKey};
+
+const CALLBACK_ONLY_FAIL_MESSAGE: &str = message!("XCL1", "Callback only");
+const CALL_NOT_FOUND_FAIL_MESSAGE: &str = message!("XCL2", "Call not found");
+
+/// Default name of the callback method
+pub const DEFAULT_RESOLVE_METHOD_NAME: &str = "xcall_resolve";
+/// Default gas for the callback, including the rollback
+pub const DEFAULT_RESOLVE_GAS: Gas = Gas(10_000_000_000_000);
+
+/// What to do once a cross-contract call completes. Consumed by the
+/// callback.
+pub trait Rollback<Cont: ?Sized> {
+    /// Return type of the callback
+    type Output;
+
+    /// The call succeeded and returned `value`
+    fn commit(self, contract: &mut Cont, value: Vec<u8>) -> Self::Output;
+
+    /// The call failed. Reverts the optimistic updates made before the call.
+    fn rollback(self, contract: &mut Cont) -> Self::Output;
+}
+
+/// Inspects the result of the call that the current callback follows (the
+/// first promise result), and runs [`Rollback::commit`] or
+/// [`Rollback::rollback`] accordingly. Aborts if the result is not ready.
+pub fn resolve<Cont: ?Sized, R: Rollback<Cont>>(contract: &mut Cont, rollback: R) -> R::Output {
+    match env::promise_result(0) {
+        PromiseResult::NotReady => env::abort(),
+        PromiseResult::Successful(value) => rollback.commit(contract, value),
+        PromiseResult::Failed => rollback.rollback(contract),
+    }
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    NextCallId,
+    Call(u32),
+}
+
+/// Cross-contract calls whose rollback state is persisted until their
+/// callback
+pub trait XCall {
+    /// State persisted for each call, run by the callback
+    type Rollback: Rollback<Self> + BorshSerialize + BorshDeserialize;
+
+    /// Storage root
+    fn root() -> Slot<()> {
+        Slot::root(DefaultStorageKey::XCall)
+    }
+
+    /// Storage slot of the next call ID
+    fn slot_next_call_id() -> Slot<u32> {
+        <Self as XCall>::root().field(StorageKey::NextCallId)
+    }
+
+    /// Storage slot of the rollback state of a pending call
+    fn slot_call(call_id: u32) -> Slot<Self::Rollback> {
+        <Self as XCall>::root().field(StorageKey::Call(call_id))
+    }
+
+    /// Name of the method that calls [`XCall::resolve_call`]
+    fn resolve_method_name() -> &'static str {
+        DEFAULT_RESOLVE_METHOD_NAME
+    }
+
+    /// Gas attached to the callback
+    fn resolve_gas() -> Gas {
+        DEFAULT_RESOLVE_GAS
+    }
+
+    /// Rollback state of a call whose callback has not run yet
+    fn pending_call(call_id: u32) -> Option<Self::Rollback> {
+        Self::slot_call(call_id).read()
+    }
+
+    /// Persists the rollback state of a new call, and returns its ID
+    fn register_call(rollback: &Self::Rollback) -> u32 {
+        let mut slot = Self::slot_next_call_id();
+        let call_id = slot.read().unwrap_or(0);
+        slot.write(&call_id.wrapping_add(1));
+        Self::slot_call(call_id).write(rollback);
+        call_id
+    }
+
+    /// Runs the rollback state of `call_id` against the result of the call.
+    /// Called by the callback method.
+    fn resolve_call(&mut self, call_id: u32) -> <Self::Rollback as Rollback<Self>>::Output
+    where
+        Self: Sized,
+    {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            CALLBACK_ONLY_FAIL_MESSAGE,
+        );
+
+        let rollback = Self::slot_call(call_id)
+            .take()
+            .unwrap_or_else(|| env::panic_str(CALL_NOT_FOUND_FAIL_MESSAGE));
+
+        resolve(self, rollback)
+    }
+}
+
+/// Builder of a cross-contract call followed by the [`XCall`] callback
+#[must_use]
+pub struct CallWithRollback<R> {
+    promise: Promise,
+    rollback: Option<R>,
+}
+
+impl<R> CallWithRollback<R> {
+    /// Wraps a cross-contract call
+    pub fn new(promise: Promise) -> Self {
+        Self {
+            promise,
+            rollback: None,
+        }
+    }
+
+    /// Sets the rollback state, run by the callback
+    pub fn on_failure(mut self, rollback: R) -> Self {
+        self.rollback = Some(rollback);
+        self
+    }
+
+    /// Persists the rollback state, and schedules the callback of `Cont`
+    /// after the call. Without a rollback state, returns the call as-is.
+    pub fn schedule<Cont: XCall<Rollback = R>>(self) -> Promise {
+        let rollback = match self.rollback {
+            Some(rollback) => rollback,
+            None => return self.promise,
+        };
+
+        let call_id = Cont::register_call(&rollback);
+
+        self.promise
+            .then(Promise::new(env::current_account_id()).function_call(
+                Cont::resolve_method_name().to_string(),
+                json!({ "call_id": call_id }).to_string().into_bytes(),
+                0,
+                Cont::resolve_gas(),
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, Promise, PromiseResult,
+    };
+
+    use super::{CallWithRollback, Rollback, XCall};
+    use crate::testing::TestEnv;
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+    struct Unreserve {
+        amount: u32,
+    }
+
+    impl Rollback<Contract> for Unreserve {
+        type Output = bool;
+
+        fn commit(self, contract: &mut Contract, value: Vec<u8>) -> bool {
+            contract.committed.push(value);
+            true
+        }
+
+        fn rollback(self, contract: &mut Contract) -> bool {
+            contract.reserved -= self.amount;
+            false
+        }
+    }
+
+    #[derive(Default)]
+    struct Contract {
+        reserved: u32,
+        committed: Vec<Vec<u8>>,
+    }
+
+    impl XCall for Contract {
+        type Rollback = Unreserve;
+    }
+
+    impl Contract {
+        fn reserve(&mut self, amount: u32) -> Promise {
+            self.reserved += amount;
+            CallWithRollback::new(Promise::new("oracle".parse().unwrap()))
+                .on_failure(Unreserve { amount })
+                .schedule::<Self>()
+        }
+    }
+
+    fn resolve_setup(result: PromiseResult) -> (TestEnv, Contract) {
+        let mut env = TestEnv::new();
+        let mut contract = Contract::default();
+        let _ = contract.reserve(10);
+        let _ = contract.reserve(20);
+
+        assert_eq!(Contract::pending_call(0), Some(Unreserve { amount: 10 }));
+        assert_eq!(Contract::pending_call(1), Some(Unreserve { amount: 20 }));
+
+        env.set_predecessor(&env::current_account_id())
+            .set_promise_results(vec![result]);
+
+        (env, contract)
+    }
+
+    #[test]
+    fn success() {
+        let (_env, mut contract) = resolve_setup(PromiseResult::Successful(b"ok".to_vec()));
+
+        assert!(contract.resolve_call(1));
+        assert_eq!(contract.reserved, 30);
+        assert_eq!(contract.committed, vec![b"ok".to_vec()]);
+        assert_eq!(Contract::pending_call(1), None);
+        assert_eq!(Contract::pending_call(0), Some(Unreserve { amount: 10 }));
+    }
+
+    #[test]
+    fn failure() {
+        let (_env, mut contract) = resolve_setup(PromiseResult::Failed);
+
+        assert!(!contract.resolve_call(1));
+        assert_eq!(contract.reserved, 10);
+        assert!(contract.committed.is_empty());
+        assert_eq!(Contract::pending_call(1), None);
+    }
+
+    #[test]
+    fn without_rollback() {
+        let _env = TestEnv::new();
+
+        let _ = CallWithRollback::<Unreserve>::new(Promise::new("oracle".parse().unwrap()))
+            .schedule::<Contract>();
+
+        assert_eq!(Contract::pending_call(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Call not found")]
+    fn resolve_twice() {
+        let (_env, mut contract) = resolve_setup(PromiseResult::Failed);
+
+        contract.resolve_call(0);
+        contract.resolve_call(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback only")]
+    fn resolve_external() {
+        let (mut env, mut contract) = resolve_setup(PromiseResult::Failed);
+
+        env.set_predecessor(&"mallory".parse().unwrap());
+        contract.resolve_call(0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Gas budgets: the gas burnt by operations in workspaces tests, compared
+//! to budgets checked into the repository, so that a change that makes an
+//! operation more expensive fails the tests.
+//!
+//! The budgets file has one `name = gas` line per operation, and `#` starts
+//! a comment. Run the tests with `UPDATE_GAS_BUDGETS=1` to write the
+//! measurements to the file instead of checking them, and review the diff.
+//!
+//! ```no_run
+//! use near_sdk_contract_tools_testing::{call, gas::GasReport, workspaces::Contract};
+//! use serde_json::json;
+//!
+//! async fn test(contract: &Contract) {
+//!     let mut report = GasReport::load("gas-budgets.toml", 5);
+//!
+//!     let result = call(contract.as_account(), contract.id(), "pause", json!({})).await;
+//!     report.record("pause", &result);
+//!
+//!     report.check();
+//! }
+//! ```
+
+use std::{collections::BTreeMap, env, fmt, fs, io, path::PathBuf};
+
+use workspaces::result::ExecutionSuccess;
+
+/// Set this environment variable to make [`GasReport::check`] write the
+/// measurements to the budgets file instead of checking them
+pub const UPDATE_ENV_VAR: &str = "UPDATE_GAS_BUDGETS";
+
+const TERA: f64 = 1_000_000_000_000.0;
+
+/// Total gas burnt by a transaction and every receipt it created, e.g.
+/// a cross-contract call and its callback
+pub fn gas_burnt(result: &ExecutionSuccess) -> u64 {
+    result.total_gas_burnt
+}
+
+/// Gas measurements and the budgets they are checked against. Displays as a
+/// table comparing them.
+#[derive(Clone, Debug)]
+pub struct GasReport {
+    path: PathBuf,
+    tolerance_percent: u64,
+    budgets: BTreeMap<String, u64>,
+    measured: BTreeMap<String, u64>,
+}
+
+impl GasReport {
+    /// Reads the budgets file at `path`, if it exists. Measurements may
+    /// exceed their budgets by up to `tolerance_percent`.
+    ///
+    /// # Panics
+    ///
+    /// If the file cannot be read or is malformed.
+    pub fn load(path: impl Into<PathBuf>, tolerance_percent: u64) -> Self {
+        let path = path.into();
+
+        let budgets = match fs::read_to_string(&path) {
+            Ok(contents) => parse_budgets(&contents)
+                .unwrap_or_else(|e| panic!("Malformed gas budgets in {}: {e}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => panic!("Failed to read gas budgets from {}: {e}", path.display()),
+        };
+
+        Self {
+            path,
+            tolerance_percent,
+            budgets,
+            measured: BTreeMap::new(),
+        }
+    }
+
+    /// Records the gas burnt by the operation `name`, see [`gas_burnt`].
+    /// Returns the gas burnt.
+    pub fn record(&mut self, name: &str, result: &ExecutionSuccess) -> u64 {
+        let gas = gas_burnt(result);
+        self.measured.insert(name.to_string(), gas);
+        gas
+    }
+
+    /// Operations that burnt more gas than their budget plus the tolerance
+    pub fn over_budget(&self) -> Vec<&str> {
+        self.measured
+            .iter()
+            .filter(|(name, &gas)| {
+                self.budgets.get(*name).map_or(false, |&budget| {
+                    u128::from(gas) * 100
+                        > u128::from(budget) * u128::from(100 + self.tolerance_percent)
+                })
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Prints the table, then writes the measurements to the budgets file if
+    /// [`UPDATE_ENV_VAR`] is set.
+    ///
+    /// # Panics
+    ///
+    /// If any operation is over budget (and the budgets are not being
+    /// updated), or if the budgets file cannot be written. Operations
+    /// without a budget are only reported.
+    pub fn check(&self) {
+        println!("{self}");
+
+        if env::var_os(UPDATE_ENV_VAR).is_some() {
+            let mut contents = format!(
+                "# Gas burnt by each operation. Regenerate with {UPDATE_ENV_VAR}=1 and review the diff.\n",
+            );
+            for (name, gas) in &self.measured {
+                contents.push_str(&format!("{name} = {gas}\n"));
+            }
+            fs::write(&self.path, contents).unwrap_or_else(|e| {
+                panic!(
+                    "Failed to write gas budgets to {}: {e}",
+                    self.path.display()
+                )
+            });
+            return;
+        }
+
+        let over_budget = self.over_budget();
+        assert!(
+            over_budget.is_empty(),
+            "Over the gas budget (+{}% tolerance): {}. If this is intended, run with {UPDATE_ENV_VAR}=1 to update {}",
+            self.tolerance_percent,
+            over_budget.join(", "),
+            self.path.display(),
+        );
+    }
+}
+
+fn parse_budgets(contents: &str) -> Result<BTreeMap<String, u64>, String> {
+    let mut budgets = BTreeMap::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, gas) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `name = gas`", i + 1))?;
+        let gas = gas
+            .trim()
+            .parse()
+            .map_err(|e| format!("line {}: {e}", i + 1))?;
+
+        budgets.insert(name.trim().to_string(), gas);
+    }
+
+    Ok(budgets)
+}
+
+impl fmt::Display for GasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .measured
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or_default()
+            .max("operation".len());
+
+        writeln!(
+            f,
+            "{:width$}  {:>12}  {:>12}  {:>8}",
+            "operation", "budget Tgas", "burnt Tgas", "change",
+        )?;
+
+        for (name, &gas) in &self.measured {
+            let (budget, change) = match self.budgets.get(name) {
+                Some(&budget) => (
+                    format!("{:.3}", budget as f64 / TERA),
+                    format!("{:+.1}%", (gas as f64 / budget as f64 - 1.0) * 100.0),
+                ),
+                None => ("-".to_string(), "new".to_string()),
+            };
+
+            writeln!(
+                f,
+                "{name:width$}  {budget:>12}  {:>12.3}  {change:>8}",
+                gas as f64 / TERA,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_budgets;
+
+    #[test]
+    fn parse() {
+        let budgets =
+            parse_budgets("# comment\n\nft_transfer = 100 # trailing\npause=5\n").unwrap();
+
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets["ft_transfer"], 100);
+        assert_eq!(budgets["pause"], 5);
+
+        assert!(parse_budgets("ft_transfer 100").is_err());
+        assert!(parse_budgets("ft_transfer = lots").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Helpers for [workspaces](https://docs.rs/workspaces) integration tests of
+//! contracts, covering the usual setup (dev accounts, deploy, call the
+//! initializer) and calls whose results are parsed from JSON.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! near-sdk-contract-tools-testing = "*"
+//! ```
+//!
+//! Every helper panics if the sandbox or the transaction fails, which fails
+//! the test with the error of the execution.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use near_sdk_contract_tools_testing::{call, deploy_and_init, make_accounts, view, workspaces};
+//! use serde_json::json;
+//!
+//! async fn test() {
+//!     let wasm = std::fs::read("target/wasm32-unknown-unknown/release/fungible_token.wasm").unwrap();
+//!
+//!     let worker = workspaces::sandbox().await.unwrap();
+//!     let contract = deploy_and_init(&worker, &wasm, "new", json!({})).await;
+//!     let accounts = make_accounts(&worker, 2).await;
+//!
+//!     call(&accounts[0], contract.id(), "mint", json!({ "amount": "100" })).await;
+//!
+//!     let balance: String = view(
+//!         &contract,
+//!         "ft_balance_of",
+//!         json!({ "account_id": accounts[0].id() }),
+//!     )
+//!     .await;
+//!
+//!     assert_eq!(balance, "100");
+//! }
+//! ```
+#![cfg(not(windows))]
+
+use serde::{de::DeserializeOwned, Serialize};
+use workspaces::{result::ExecutionSuccess, Account, AccountId, Contract, DevNetwork, Worker};
+
+pub use workspaces;
+
+pub mod gas;
+
+/// Creates `n` new dev accounts
+pub async fn make_accounts<N: DevNetwork + 'static>(worker: &Worker<N>, n: usize) -> Vec<Account> {
+    let mut accounts = Vec::with_capacity(n);
+    for _ in 0..n {
+        accounts.push(worker.dev_create_account().await.unwrap());
+    }
+    accounts
+}
+
+/// Deploys `wasm` to a new dev account and calls its initializer
+/// `init_method` with the JSON `args`
+pub async fn deploy_and_init<N: DevNetwork + 'static>(
+    worker: &Worker<N>,
+    wasm: &[u8],
+    init_method: &str,
+    args: impl Serialize,
+) -> Contract {
+    let contract = worker.dev_deploy(wasm).await.unwrap();
+    init(&contract, init_method, args).await;
+    contract
+}
+
+/// Deploys `wasm` to an existing `account` and calls its initializer
+/// `init_method` with the JSON `args`, e.g. when the contract must be its
+/// own predecessor
+pub async fn deploy_to_and_init(
+    account: &Account,
+    wasm: &[u8],
+    init_method: &str,
+    args: impl Serialize,
+) -> Contract {
+    let contract = account.deploy(wasm).await.unwrap().unwrap();
+    init(&contract, init_method, args).await;
+    contract
+}
+
+async fn init(contract: &Contract, init_method: &str, args: impl Serialize) {
+    contract
+        .call(init_method)
+        .args_json(args)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+/// Calls `method` on `contract_id` from `account` with the JSON `args` and
+/// the maximum gas
+///
+/// # Panics
+///
+/// If the execution fails.
+pub async fn call(
+    account: &Account,
+    contract_id: &AccountId,
+    method: &str,
+    args: impl Serialize,
+) -> ExecutionSuccess {
+    account
+        .call(contract_id, method)
+        .args_json(args)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Like [`call`], with `args` passed as-is, e.g. Borsh-serialized or raw
+/// input
+pub async fn call_raw(
+    account: &Account,
+    contract_id: &AccountId,
+    method: &str,
+    args: Vec<u8>,
+) -> ExecutionSuccess {
+    account
+        .call(contract_id, method)
+        .args(args)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Like [`call`], parsing the return value of the call from JSON
+pub async fn call_json<T: DeserializeOwned>(
+    account: &Account,
+    contract_id: &AccountId,
+    method: &str,
+    args: impl Serialize,
+) -> T {
+    call(account, contract_id, method, args)
+        .await
+        .json()
+        .unwrap()
+}
+
+/// Calls the view method `method` of `contract` with the JSON `args`, parsing
+/// the return value from JSON
+pub async fn view<T: DeserializeOwned>(
+    contract: &Contract,
+    method: &str,
+    args: impl Serialize,
+) -> T {
+    contract
+        .view(method, serde_json::to_vec(&args).unwrap())
+        .await
+        .unwrap()
+        .json()
+        .unwrap()
+}
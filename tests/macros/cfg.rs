@@ -0,0 +1,62 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId,
+};
+use near_sdk_contract_tools::{
+    assert_contract_externals,
+    owner::{Owner, OwnerExternal},
+    standard::nep141::Nep141Controller,
+    Nep141, Owner, Pause, Upgrade,
+};
+
+/// `all()` always holds, `any()` never does
+#[derive(Owner, Pause, Nep141, Upgrade, BorshSerialize, BorshDeserialize)]
+#[owner(cfg = "all()")]
+#[pause(cfg = "any()")]
+#[nep141(
+    no_hooks,
+    cfg(method = "ft_transfer_call", predicate = "any()"),
+    cfg(method = "ft_transfer", predicate = "test")
+)]
+#[upgrade(hook = "owner", cfg = "any()")]
+#[near_bindgen]
+struct Conditional {}
+
+#[test]
+fn externals() {
+    assert_contract_externals!(
+        Conditional: own_get_owner,
+        own_accept_owner,
+        ft_transfer,
+        ft_total_supply,
+        ft_balance_of,
+        ft_resolve_transfer
+    );
+}
+
+#[test]
+fn inherent_methods() {
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    let mut contract = Conditional {};
+    Owner::init(&mut contract, &alice);
+    contract.deposit_unchecked(&alice, 100);
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .attached_deposit(1)
+        .build());
+
+    contract.ft_transfer(bob.clone(), 40.into(), None);
+
+    assert_eq!(contract.ft_balance_of(alice).0, 60);
+    assert_eq!(contract.ft_balance_of(bob).0, 40);
+    assert_eq!(contract.ft_total_supply().0, 100);
+    assert_eq!(
+        contract.own_get_owner(),
+        Some(env::predecessor_account_id())
+    );
+}
@@ -0,0 +1,56 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen, AccountId, BorshStorageKey,
+};
+use near_sdk_contract_tools::{
+    assert_contract_externals, contract_tools,
+    owner::OwnerExternal,
+    pause::{Pause, PauseExternal},
+    standard::{
+        nep141::{Nep141, Nep141Controller},
+        nep148::Nep148,
+    },
+    testing::fixtures,
+};
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum Role {
+    Minter,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+#[contract_tools(
+    fungible_token(name = "My Token", symbol = "TKN", decimals = 18, no_hooks),
+    rbac(roles = "Role"),
+    pause(storage_key = b"paused"),
+    owner
+)]
+#[near_bindgen]
+struct Composed {}
+
+#[test]
+fn externals() {
+    assert_contract_externals!(
+        Composed: own_get_owner,
+        paus_is_paused,
+        ft_transfer,
+        ft_metadata
+    );
+}
+
+#[test]
+fn components() {
+    let owner: AccountId = "owner".parse().unwrap();
+    let mut contract = fixtures::owned_contract::<Composed>(&owner);
+
+    fixtures::with_roles(&mut contract, &[(&owner, Role::Minter)]);
+    contract.deposit_unchecked(&owner, 100);
+
+    assert_eq!(contract.own_get_owner(), Some(owner.clone()));
+    fixtures::assert_is_owner::<Composed>(&owner);
+    fixtures::assert_has_role::<Composed>(&owner, &Role::Minter);
+    assert_eq!(contract.ft_balance_of(owner).0, 100);
+    assert_eq!(contract.ft_metadata().symbol, "TKN");
+    assert_eq!(<Composed as Pause>::root().key, b"paused");
+    assert!(!contract.paus_is_paused());
+}
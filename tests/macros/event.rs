@@ -147,3 +147,70 @@ mod event_attribute_macro {
         assert_eq!(e.to_event_string(), f.to_event_string());
     }
 }
+
+mod rename_matches_runtime {
+    use near_sdk_contract_tools::{
+        rename::{apply, Strategy},
+        standard::nep297::ToEventLog,
+    };
+
+    macro_rules! strategy_event {
+        ($module:ident, $strategy:literal) => {
+            mod $module {
+                use near_sdk_contract_tools::Nep297;
+                use serde::Serialize;
+
+                #[derive(Nep297, Serialize)]
+                #[nep297(standard = "rename", version = "1", rename_all = $strategy)]
+                #[allow(clippy::upper_case_acronyms, non_camel_case_types)]
+                pub enum E {
+                    NftMint,
+                    HTTPRequest,
+                    Version2Update,
+                    ABC,
+                    A1B2,
+                    v2_update,
+                    _private_field,
+                }
+
+                pub const ALL: [(E, &str); 7] = [
+                    (E::NftMint, "NftMint"),
+                    (E::HTTPRequest, "HTTPRequest"),
+                    (E::Version2Update, "Version2Update"),
+                    (E::ABC, "ABC"),
+                    (E::A1B2, "A1B2"),
+                    (E::v2_update, "v2_update"),
+                    (E::_private_field, "_private_field"),
+                ];
+            }
+        };
+    }
+
+    strategy_event!(upper_camel_case, "UpperCamelCase");
+    strategy_event!(lower_camel_case, "lowerCamelCase");
+    strategy_event!(snake_case, "snake_case");
+    strategy_event!(kebab_case, "kebab-case");
+    strategy_event!(shouty_snake_case, "SHOUTY_SNAKE_CASE");
+    strategy_event!(title_case, "Title Case");
+    strategy_event!(shouty_kebab_case, "SHOUTY-KEBAB-CASE");
+
+    fn assert_matches<T: ToEventLog>(
+        strategy: Strategy,
+        events: impl IntoIterator<Item = (T, &'static str)>,
+    ) {
+        for (event, name) in events {
+            assert_eq!(event.to_event_log().event, apply(strategy, name));
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_matches(Strategy::UpperCamelCase, upper_camel_case::ALL);
+        assert_matches(Strategy::LowerCamelCase, lower_camel_case::ALL);
+        assert_matches(Strategy::SnakeCase, snake_case::ALL);
+        assert_matches(Strategy::KebabCase, kebab_case::ALL);
+        assert_matches(Strategy::ShoutySnakeCase, shouty_snake_case::ALL);
+        assert_matches(Strategy::TitleCase, title_case::ALL);
+        assert_matches(Strategy::ShoutyKebabCase, shouty_kebab_case::ALL);
+    }
+}
@@ -0,0 +1,155 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId,
+};
+use near_sdk_contract_tools::{
+    owner::{Owner, OwnerExternal, OwnerState},
+    pause::{Pause, PauseExternal, PauseState},
+    Owner, Pause,
+};
+
+#[derive(Owner, Pause, BorshSerialize, BorshDeserialize, Default)]
+#[near_bindgen]
+struct SlotContract {}
+
+#[derive(Owner, Pause, BorshSerialize, BorshDeserialize, Default)]
+#[owner(field = "owner_state")]
+#[pause(field = "pause_state")]
+#[near_bindgen]
+struct FieldContract {
+    owner_state: OwnerState,
+    pause_state: PauseState,
+}
+
+fn call_as(account_id: &AccountId) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(account_id.clone())
+        .attached_deposit(1)
+        .build());
+}
+
+/// Runs the same calls against either storage mode on one instance of the
+/// contract, without writing the root state in between. Returns the observed
+/// owners, proposed owners, and pause states.
+fn scenario<C>() -> Vec<(Option<AccountId>, Option<AccountId>, bool)>
+where
+    C: Owner + OwnerExternal + Pause + PauseExternal + BorshSerialize + Default,
+{
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob_acct".parse().unwrap();
+    let mut observed = vec![];
+    let mut contract = C::default();
+
+    macro_rules! step {
+        ($account:expr, $f:expr) => {
+            call_as(&$account);
+            $f(&mut contract);
+            observed.push((
+                contract.own_get_owner(),
+                contract.own_get_proposed_owner(),
+                contract.paus_is_paused(),
+            ));
+        };
+    }
+
+    step!(alice, |c: &mut C| Owner::init(c, &alice));
+    step!(alice, |c: &mut C| c.assert_owner());
+    step!(alice, |c: &mut C| c.pause());
+    step!(bob, |c: &mut C| assert_ne!(
+        c.current_owner(),
+        Some(bob.clone())
+    ));
+    step!(bob, |c: &mut C| assert!(c.paused()));
+    step!(alice, |c: &mut C| c.own_propose_owner(Some(bob.clone())));
+    step!(bob, |c: &mut C| c.own_accept_owner());
+    step!(bob, |c: &mut C| c.unpause());
+    step!(bob, |c: &mut C| assert!(!c.paused()));
+    step!(bob, |c: &mut C| c.own_renounce_owner());
+
+    observed
+}
+
+#[test]
+fn storage_modes_behave_identically() {
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob_acct".parse().unwrap();
+
+    let slot = scenario::<SlotContract>();
+    let field = scenario::<FieldContract>();
+
+    assert_eq!(slot, field);
+    assert_eq!(slot[2], (Some(alice.clone()), None, true));
+    assert_eq!(slot[5], (Some(alice), Some(bob.clone()), true));
+    assert_eq!(slot[7], (Some(bob), None, false));
+    assert_eq!(slot[9], (None, None, false));
+}
+
+#[test]
+fn field_mode_uses_no_slots() {
+    let alice: AccountId = "alice".parse().unwrap();
+    call_as(&alice);
+
+    let mut contract = FieldContract::default();
+    Owner::init(&mut contract, &alice);
+    contract.pause();
+
+    assert_eq!(contract.owner_state.owner, Some(alice));
+    assert!(contract.pause_state.is_paused);
+    assert!(!FieldContract::slot_is_initialized().exists());
+    assert!(!FieldContract::slot_paused().exists());
+}
+
+#[test]
+fn field_mode_init_then_propose() {
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob_acct".parse().unwrap();
+    call_as(&alice);
+
+    // Both in the same call, before the root state is ever written
+    let mut contract = FieldContract::default();
+    Owner::init(&mut contract, &alice);
+    contract.propose_owner(Some(bob.clone()));
+
+    assert_eq!(contract.own_get_proposed_owner(), Some(bob));
+
+    contract.renounce_owner();
+
+    assert_eq!(contract.own_get_owner(), None);
+    assert_eq!(contract.own_get_proposed_owner(), None);
+}
+
+#[test]
+fn field_mode_static_functions() {
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob_acct".parse().unwrap();
+    call_as(&alice);
+
+    let mut contract = FieldContract::default();
+    Owner::init(&mut contract, &alice);
+    contract.pause();
+    // The static functions read the root state, as written at the end of a call
+    env::state_write(&contract);
+
+    FieldContract::require_owner();
+    FieldContract::require_paused();
+
+    call_as(&bob);
+
+    assert_eq!(
+        FieldContract::check_owner(),
+        Err(near_sdk_contract_tools::owner::OwnerError::NotOwner),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Owner already initialized")]
+fn field_mode_init_once() {
+    let alice: AccountId = "alice".parse().unwrap();
+    call_as(&alice);
+
+    let mut contract = FieldContract::default();
+    Owner::init(&mut contract, &alice);
+    Owner::init(&mut contract, &alice);
+}
@@ -0,0 +1,160 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId, BorshStorageKey,
+};
+use near_sdk_contract_tools::{
+    migrate::{MigrateExternal, MigrateHook},
+    owner::{Owner, OwnerExternal},
+    pause::{Pause, PauseExternal},
+    rbac::Rbac,
+    standard::{
+        nep141::{Nep141, Nep141Controller},
+        nep148::Nep148,
+    },
+    FungibleToken, Migrate, Owner, Pause, Rbac, Upgrade,
+};
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum Role {
+    Minter,
+}
+
+trait Label {
+    fn label(&self) -> String;
+}
+
+impl Label for u32 {
+    fn label(&self) -> String {
+        format!("u32 {self}")
+    }
+}
+
+#[derive(Owner, Pause, Rbac, FungibleToken, BorshSerialize, BorshDeserialize)]
+#[rbac(roles = "Role")]
+#[fungible_token(name = "Generic", symbol = "GEN", decimals = 24, no_hooks)]
+struct GenericContract<T: BorshSerialize + BorshDeserialize, const N: usize>
+where
+    T: Label,
+{
+    pub value: T,
+}
+
+impl<T: BorshSerialize + BorshDeserialize, const N: usize> GenericContract<T, N>
+where
+    T: Label,
+{
+    pub fn new(value: T) -> Self {
+        let mut contract = Self { value };
+        Owner::init(&mut contract, &env::predecessor_account_id());
+        contract
+    }
+
+    pub fn mint(&mut self, account_id: AccountId, amount: u128) {
+        Self::require_role(&Role::Minter);
+        Self::require_unpaused();
+        self.deposit_unchecked(&account_id, amount);
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct OldGeneric<T> {
+    pub value: T,
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(from = "OldGeneric<T>")]
+struct MigratedGeneric<T: BorshSerialize + BorshDeserialize, const N: usize>
+where
+    T: Label,
+{
+    pub label: String,
+    pub value: T,
+}
+
+impl<T: BorshSerialize + BorshDeserialize, const N: usize> MigrateHook for MigratedGeneric<T, N>
+where
+    T: Label,
+{
+    fn on_migrate(old: OldGeneric<T>) -> Self {
+        Self {
+            label: format!("{} of {N}", old.value.label()),
+            value: old.value,
+        }
+    }
+}
+
+#[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+#[upgrade(
+    hook = "owner",
+    serializer = "borsh",
+    staged,
+    rollback,
+    on_complete = "default"
+)]
+struct UpgradableGeneric<T: BorshSerialize + BorshDeserialize, const N: usize>
+where
+    T: Label,
+{
+    pub value: T,
+}
+
+#[test]
+fn generic_contract() {
+    let alice: AccountId = "alice".parse().unwrap();
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .build());
+
+    let mut contract = GenericContract::<u32, 8>::new(7);
+
+    assert_eq!(contract.own_get_owner(), Some(alice.clone()));
+    assert_eq!(contract.capacity(), 8);
+    assert_eq!(contract.value.label(), "u32 7");
+
+    contract.add_role(alice.clone(), &Role::Minter);
+    contract.mint(alice.clone(), 100);
+
+    assert_eq!(contract.ft_balance_of(alice).0, 100);
+    assert_eq!(contract.ft_total_supply().0, 100);
+    assert_eq!(contract.ft_metadata().symbol, "GEN");
+
+    contract.pause();
+    assert!(contract.paus_is_paused());
+}
+
+#[test]
+fn generic_migrate() {
+    env::state_write(&OldGeneric { value: 5u32 });
+
+    let migrated = <MigratedGeneric<u32, 3> as MigrateExternal>::migrate();
+
+    assert_eq!(migrated.label, "u32 5 of 3");
+    assert_eq!(migrated.value, 5);
+}
+
+#[test]
+fn generic_upgrade() {
+    let alice: AccountId = "alice".parse().unwrap();
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .attached_deposit(near_sdk::ONE_NEAR)
+        .build());
+
+    let mut contract = UpgradableGeneric::<u32, 2> { value: 1 };
+    Owner::init(&mut contract, &alice);
+
+    let code_hash = contract.stage_upgrade(vec![1, 2, 3]);
+
+    assert_eq!(contract.staged_code_hash(), Some(code_hash));
+    assert_eq!(contract.previous_code_hash(), None);
+
+    contract.unstage_upgrade();
+
+    assert_eq!(contract.staged_code_hash(), None);
+}
@@ -1,9 +1,9 @@
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    env, near_bindgen,
+    env, near_bindgen, test_utils,
 };
 use near_sdk_contract_tools::{
-    migrate::{MigrateExternal, MigrateHook},
+    migrate::{MigrateError, MigrateExternal, MigrateHook, TryMigrateHook},
     Migrate,
 };
 
@@ -46,4 +46,742 @@ fn default_from() {
     let migrated = <MyContract as MigrateExternal>::migrate();
 
     assert_eq!(migrated.bar, 99);
+    assert_eq!(
+        test_utils::get_logs(),
+        vec![format!(
+            r#"EVENT_JSON:{{"standard":"x-migr","version":"1.0.0","event":"migrate","data":{{"from_version":"Old","to_version":"MyContract","by":"{}"}}}}"#,
+            env::predecessor_account_id(),
+        )],
+    );
+}
+
+#[test]
+#[should_panic = "Already migrated from Old to MyContract"]
+fn rerun_rejected() {
+    env::state_write(&Old::new(1));
+
+    let migrated = <MyContract as MigrateExternal>::migrate();
+    env::state_write(&migrated);
+
+    <MyContract as MigrateExternal>::migrate();
+}
+
+mod next {
+    use super::*;
+
+    /// Keeps the name of the contract type, so the default `to_version` is
+    /// the same as the previous migration's
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "super::MyContract", from_version = "MyContract")]
+    #[near_bindgen]
+    struct MyContract {
+        pub baz: u64,
+    }
+
+    impl MigrateHook for MyContract {
+        fn on_migrate(old: super::MyContract) -> Self {
+            Self { baz: old.bar + 1 }
+        }
+    }
+
+    #[test]
+    fn migrate_again_to_same_name() {
+        env::state_write(&Old::new(1));
+
+        let migrated = <super::MyContract as MigrateExternal>::migrate();
+        env::state_write(&migrated);
+
+        let migrated = <MyContract as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.baz, 2);
+    }
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(from = "Old", allow_rerun)]
+#[near_bindgen]
+struct MyRerunContract {
+    pub bar: u64,
+}
+
+impl MigrateHook for MyRerunContract {
+    fn on_migrate(old: Old) -> Self {
+        Self { bar: old.foo + 1 }
+    }
+}
+
+#[test]
+fn rerun_allowed() {
+    env::state_write(&Old::new(1));
+
+    let migrated = <MyRerunContract as MigrateExternal>::migrate();
+    env::state_write(&migrated);
+
+    let migrated = <MyRerunContract as MigrateExternal>::migrate();
+
+    assert_eq!(migrated.bar, 3);
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(from = "Old", from_version = "1.0.0", to_version = "2.0.0")]
+#[near_bindgen]
+struct MyVersionedContract {
+    pub bar: u64,
+}
+
+impl MigrateHook for MyVersionedContract {
+    fn on_migrate(old: Old) -> Self {
+        Self { bar: old.foo }
+    }
+}
+
+#[test]
+fn event_versions() {
+    env::state_write(&Old::new(1));
+
+    <MyVersionedContract as MigrateExternal>::migrate();
+
+    let logs = test_utils::get_logs();
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].contains(r#""from_version":"1.0.0","to_version":"2.0.0""#));
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(from = "Old", no_event)]
+#[near_bindgen]
+struct MySilentContract {
+    pub bar: u64,
+}
+
+impl MigrateHook for MySilentContract {
+    fn on_migrate(old: Old) -> Self {
+        Self { bar: old.foo }
+    }
+}
+
+#[test]
+fn no_event() {
+    env::state_write(&Old::new(1));
+
+    <MySilentContract as MigrateExternal>::migrate();
+
+    assert!(test_utils::get_logs().is_empty());
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(from = "Old")]
+#[near_bindgen]
+struct MyFallibleContract {
+    pub bar: u64,
+}
+
+impl TryMigrateHook for MyFallibleContract {
+    fn try_on_migrate(old: Old) -> Result<Self, MigrateError> {
+        if old.foo == 0 {
+            return Err(MigrateError::new("foo must be nonzero"));
+        }
+
+        Ok(Self { bar: old.foo })
+    }
+}
+
+#[test]
+fn fallible_success() {
+    env::state_write(&Old::new(7));
+
+    let migrated = <MyFallibleContract as MigrateExternal>::migrate();
+
+    assert_eq!(migrated.bar, 7);
+}
+
+#[test]
+#[should_panic = "foo must be nonzero"]
+fn fallible_failure() {
+    env::state_write(&Old::new(0));
+
+    <MyFallibleContract as MigrateExternal>::migrate();
+}
+
+mod chain {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+    };
+    use near_sdk_contract_tools::{
+        migrate::{MigrateChain, MigrateExternal, VersionedState},
+        MigrateChain,
+    };
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct ContractV1 {
+        pub foo: u32,
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct ContractV2 {
+        pub foo: u32,
+        pub name: String,
+    }
+
+    impl From<ContractV1> for ContractV2 {
+        fn from(old: ContractV1) -> Self {
+            Self {
+                foo: old.foo,
+                name: "v2".to_string(),
+            }
+        }
+    }
+
+    #[derive(MigrateChain, BorshSerialize, BorshDeserialize)]
+    #[migrate_chain(versions(ContractV1, ContractV2))]
+    #[near_bindgen]
+    pub struct ContractV3 {
+        pub bar: u64,
+        pub name: String,
+        pub flag: bool,
+    }
+
+    impl From<ContractV2> for ContractV3 {
+        fn from(old: ContractV2) -> Self {
+            Self {
+                bar: old.foo as u64,
+                name: old.name,
+                flag: true,
+            }
+        }
+    }
+
+    #[test]
+    fn v1_to_v3_detected() {
+        env::state_write(&ContractV1 { foo: 5 });
+
+        assert_eq!(ContractV3::stored_state_version(), None);
+        assert_eq!(ContractV3::read_versioned_state().version(), 0);
+
+        let migrated = <ContractV3 as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.bar, 5);
+        assert_eq!(migrated.name, "v2");
+        assert!(migrated.flag);
+        assert_eq!(ContractV3::stored_state_version(), Some(2));
+    }
+
+    #[test]
+    fn v2_to_v3_tagged() {
+        env::state_write(&ContractV2 {
+            foo: 9,
+            name: "tagged".to_string(),
+        });
+        ContractV3::slot_state_version().write(&1);
+
+        let migrated = <ContractV3 as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.bar, 9);
+        assert_eq!(migrated.name, "tagged");
+        assert_eq!(ContractV3::stored_state_version(), Some(2));
+    }
+
+    #[test]
+    fn latest_is_unchanged() {
+        env::state_write(&ContractV3 {
+            bar: 1,
+            name: "latest".to_string(),
+            flag: false,
+        });
+        ContractV3::write_state_version();
+
+        let migrated = <ContractV3 as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.bar, 1);
+        assert!(!migrated.flag);
+    }
+
+    #[test]
+    #[should_panic = "Failed to deserialize old state as version 0"]
+    fn wrong_tag() {
+        env::state_write(&ContractV2 {
+            foo: 9,
+            name: "tagged".to_string(),
+        });
+        ContractV3::slot_state_version().write(&0);
+
+        <ContractV3 as MigrateExternal>::migrate();
+    }
+}
+
+mod progressive {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        test_utils::VMContextBuilder,
+        testing_env,
+    };
+    use near_sdk_contract_tools::{
+        migrate::{MigrateExternal, MigrateHook, MigrationProgress, ProgressiveMigration},
+        Migrate,
+    };
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct Old {
+        pub values: Vec<u32>,
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "Old", progressive)]
+    #[near_bindgen]
+    pub struct Progressive {
+        pub old_values: Vec<u32>,
+        pub values: Vec<u64>,
+    }
+
+    impl MigrateHook for Progressive {
+        fn on_migrate(old: Old) -> Self {
+            Self::start_progressive_migration();
+
+            Self {
+                old_values: old.values,
+                values: vec![],
+            }
+        }
+    }
+
+    impl ProgressiveMigration for Progressive {
+        fn migrate_items(&mut self, cursor: u64, limit: u32) -> MigrationProgress {
+            let start = cursor as usize;
+            let end = (start + limit as usize).min(self.old_values.len());
+
+            self.values
+                .extend(self.old_values[start..end].iter().map(|v| *v as u64 * 2));
+
+            MigrationProgress {
+                done: end == self.old_values.len(),
+                items_processed: (end - start) as u32,
+            }
+        }
+    }
+
+    #[test]
+    fn migrate_in_steps() {
+        env::state_write(&Old {
+            values: (0..5).collect(),
+        });
+
+        let mut contract = <Progressive as MigrateExternal>::migrate();
+
+        assert!(!Progressive::is_migration_complete());
+
+        let step = |contract: &mut Progressive| {
+            let p = ProgressiveMigration::migrate_step(contract, 2);
+            (p.done, p.items_processed, Progressive::migration_cursor())
+        };
+
+        assert_eq!(step(&mut contract), (false, 2, 2));
+        assert_eq!(step(&mut contract), (false, 2, 4));
+        assert_eq!(step(&mut contract), (true, 1, 0));
+        assert!(Progressive::is_migration_complete());
+        assert_eq!(contract.values, vec![0, 2, 4, 6, 8]);
+
+        // Further steps do nothing
+        assert_eq!(step(&mut contract), (true, 0, 0));
+    }
+
+    #[test]
+    #[should_panic = "Migration in progress"]
+    fn blocked_until_complete() {
+        env::state_write(&Old { values: vec![1] });
+
+        <Progressive as MigrateExternal>::migrate();
+
+        Progressive::require_migration_complete();
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(
+        from = "Old",
+        progressive,
+        allow = "env::predecessor_account_id().as_str() == \"alice\""
+    )]
+    #[near_bindgen]
+    pub struct Guarded {
+        pub values: Vec<u32>,
+    }
+
+    impl MigrateHook for Guarded {
+        fn on_migrate(old: Old) -> Self {
+            Self::start_progressive_migration();
+
+            Self { values: old.values }
+        }
+    }
+
+    impl ProgressiveMigration for Guarded {
+        fn migrate_items(&mut self, _cursor: u64, _limit: u32) -> MigrationProgress {
+            MigrationProgress {
+                done: true,
+                items_processed: self.values.len() as u32,
+            }
+        }
+    }
+
+    fn guarded(predecessor: &str) -> Guarded {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(predecessor.parse().unwrap());
+        testing_env!(context.build());
+
+        env::state_write(&Old { values: vec![1] });
+
+        Guarded { values: vec![1] }
+    }
+
+    #[test]
+    fn step_allowed() {
+        let mut contract = guarded("alice");
+
+        assert!(contract.migrate_step(1).done);
+    }
+
+    #[test]
+    #[should_panic = "Migration not allowed"]
+    fn step_not_allowed() {
+        let mut contract = guarded("bob");
+
+        contract.migrate_step(1);
+    }
+}
+
+mod with_args {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        serde::{Deserialize, Serialize},
+    };
+    use near_sdk_contract_tools::Migrate;
+
+    use super::Old;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct MigrateArgs {
+        pub multiplier: u64,
+        pub label: String,
+    }
+
+    fn convert_with_struct(old: Old, args: MigrateArgs) -> StructArgs {
+        StructArgs {
+            bar: old.foo * args.multiplier,
+            label: args.label,
+        }
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(
+        from = "Old",
+        convert_with_args = "convert_with_struct",
+        args = "MigrateArgs"
+    )]
+    #[near_bindgen]
+    struct StructArgs {
+        pub bar: u64,
+        pub label: String,
+    }
+
+    fn convert_with_string(old: Old, args: String) -> StringArgs {
+        StringArgs {
+            bar: old.foo + args.parse::<u64>().unwrap(),
+        }
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "Old", convert_with_args = "convert_with_string")]
+    #[near_bindgen]
+    struct StringArgs {
+        pub bar: u64,
+    }
+
+    #[test]
+    fn struct_args() {
+        env::state_write(&Old::new(3));
+
+        let migrated = StructArgs::migrate(MigrateArgs {
+            multiplier: 4,
+            label: "scaled".to_string(),
+        });
+
+        assert_eq!(migrated.bar, 12);
+        assert_eq!(migrated.label, "scaled");
+    }
+
+    #[test]
+    fn struct_args_json() {
+        let args: MigrateArgs =
+            near_sdk::serde_json::from_str(r#"{"multiplier":2,"label":"json"}"#).unwrap();
+
+        env::state_write(&Old::new(5));
+
+        let migrated = StructArgs::migrate(args);
+
+        assert_eq!(migrated.bar, 10);
+        assert_eq!(migrated.label, "json");
+    }
+
+    #[test]
+    fn string_args() {
+        env::state_write(&Old::new(3));
+
+        let migrated = StringArgs::migrate("4".to_string());
+
+        assert_eq!(migrated.bar, 7);
+    }
+}
+
+mod deserialize_failure {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        serde::{Deserialize, Serialize},
+    };
+    use near_sdk_contract_tools::{
+        migrate::{MigrateExternal, MigrateHook},
+        Migrate,
+    };
+
+    use super::Old;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct JsonOld {
+        foo: u64,
+    }
+
+    /// Old state may have been written as JSON
+    fn decode_json(bytes: Vec<u8>) -> Old {
+        let old: JsonOld = near_sdk::serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| env::panic_str("Unrecognized state"));
+
+        Old::new(old.foo)
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "Old", on_deserialize_failure = "decode_json")]
+    #[near_bindgen]
+    struct Recovering {
+        pub bar: u64,
+    }
+
+    impl MigrateHook for Recovering {
+        fn on_migrate(old: Old) -> Self {
+            Self { bar: old.foo }
+        }
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "Old")]
+    #[near_bindgen]
+    struct Strict {
+        pub bar: u64,
+    }
+
+    impl MigrateHook for Strict {
+        fn on_migrate(old: Old) -> Self {
+            Self { bar: old.foo }
+        }
+    }
+
+    #[test]
+    fn hook_decodes_alternative() {
+        env::storage_write(b"STATE", br#"{"foo":42}"#);
+
+        let migrated = <Recovering as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.bar, 42);
+    }
+
+    #[test]
+    fn hook_not_called_for_valid_state() {
+        env::state_write(&Old::new(8));
+
+        let migrated = <Recovering as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.bar, 8);
+    }
+
+    #[test]
+    #[should_panic = "Unrecognized state"]
+    fn hook_rejects() {
+        env::storage_write(b"STATE", &[1, 2, 3]);
+
+        <Recovering as MigrateExternal>::migrate();
+    }
+
+    #[test]
+    #[should_panic = "macros::migrate::Old`"]
+    fn default_panic_names_type() {
+        env::storage_write(b"STATE", &[1, 2, 3]);
+
+        <Strict as MigrateExternal>::migrate();
+    }
+}
+
+mod dry_run {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        serde::Serialize,
+        test_utils,
+    };
+    use near_sdk_contract_tools::{
+        migrate::{MigrateController, MigrateDryRun, MigrateHook},
+        Migrate,
+    };
+
+    use super::Old;
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(from = "Old", dry_run)]
+    #[near_bindgen]
+    struct DryRun {
+        pub bar: u64,
+        pub doubled: u64,
+    }
+
+    impl MigrateHook for DryRun {
+        fn on_migrate(old: Old) -> Self {
+            Self {
+                bar: old.foo,
+                doubled: old.foo * 2,
+            }
+        }
+    }
+
+    #[derive(Serialize, Debug, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    struct Summary {
+        doubled: u64,
+    }
+
+    impl MigrateDryRun for DryRun {
+        type Summary = Summary;
+
+        fn summarize(new_schema: DryRun) -> Summary {
+            Summary {
+                doubled: new_schema.doubled,
+            }
+        }
+    }
+
+    #[test]
+    fn storage_untouched() {
+        env::state_write(&Old::new(21));
+        let before = env::storage_read(b"STATE").unwrap();
+
+        let summary = DryRun::migrate_dry_run();
+
+        assert_eq!(summary, Summary { doubled: 42 });
+        assert_eq!(env::storage_read(b"STATE").unwrap(), before);
+        assert_eq!(DryRun::slot_migrated_version().read(), None);
+        assert!(test_utils::get_logs().is_empty());
+    }
+}
+
+mod json_state {
+    use near_sdk::{
+        borsh::{self, BorshDeserialize, BorshSerialize},
+        env, near_bindgen,
+        serde::Deserialize,
+    };
+    use near_sdk_contract_tools::{
+        migrate::{MigrateExternal, MigrateHook},
+        Migrate,
+    };
+
+    #[derive(BorshDeserialize, Deserialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct LegacyJson {
+        count: u64,
+        owner: String,
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+    #[migrate(from = "LegacyJson", from_format = "json")]
+    #[near_bindgen]
+    struct FromJson {
+        pub count: u64,
+        pub owner: String,
+    }
+
+    impl MigrateHook for FromJson {
+        fn on_migrate(old: LegacyJson) -> Self {
+            Self {
+                count: old.count,
+                owner: old.owner,
+            }
+        }
+    }
+
+    fn fallback(_bytes: Vec<u8>) -> LegacyJson {
+        LegacyJson {
+            count: 0,
+            owner: "recovered".to_string(),
+        }
+    }
+
+    #[derive(Migrate, BorshSerialize, BorshDeserialize)]
+    #[migrate(
+        from = "LegacyJson",
+        from_format = "json",
+        on_deserialize_failure = "fallback"
+    )]
+    #[near_bindgen]
+    struct FromJsonWithFallback {
+        pub owner: String,
+    }
+
+    impl MigrateHook for FromJsonWithFallback {
+        fn on_migrate(old: LegacyJson) -> Self {
+            Self { owner: old.owner }
+        }
+    }
+
+    #[test]
+    fn json_to_borsh() {
+        env::storage_write(b"STATE", br#"{"count":5,"owner":"alice.near"}"#);
+
+        let migrated = <FromJson as MigrateExternal>::migrate();
+
+        // This is done automatically in real #[near_bindgen] WASM contracts
+        env::state_write(&migrated);
+
+        assert_eq!(
+            env::state_read::<FromJson>(),
+            Some(FromJson {
+                count: 5,
+                owner: "alice.near".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic = "Old state is not valid JSON"]
+    fn not_json() {
+        env::storage_write(b"STATE", &[1, 2, 3]);
+
+        <FromJson as MigrateExternal>::migrate();
+    }
+
+    #[test]
+    #[should_panic = "Old state does not match the schema of"]
+    fn wrong_shape() {
+        env::storage_write(b"STATE", br#"{"count":"five"}"#);
+
+        <FromJson as MigrateExternal>::migrate();
+    }
+
+    #[test]
+    fn fallback_hook() {
+        env::storage_write(b"STATE", &[1, 2, 3]);
+
+        let migrated = <FromJsonWithFallback as MigrateExternal>::migrate();
+
+        assert_eq!(migrated.owner, "recovered");
+    }
 }
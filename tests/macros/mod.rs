@@ -5,6 +5,7 @@ use near_sdk::{
     testing_env, AccountId, BorshStorageKey,
 };
 use near_sdk_contract_tools::{
+    assert_contract_externals,
     migrate::{MigrateExternal, MigrateHook},
     owner::Owner,
     pause::Pause,
@@ -13,11 +14,18 @@ use near_sdk_contract_tools::{
     Migrate, Owner, Pause, Rbac,
 };
 
+mod cfg;
+mod contract_tools;
 mod event;
+mod field;
+mod generic;
 mod migrate;
 mod owner;
 mod pause;
+mod prefix;
+mod skip;
 mod standard;
+mod storage_key;
 
 mod my_event {
     use near_sdk::AccountId;
@@ -174,6 +182,23 @@ impl MigrateIntegration {
     }
 }
 
+// `#[near_bindgen]` is expanded before the derive macros, so they cannot see it
+#[near_bindgen]
+#[derive(Owner, Pause, BorshSerialize, BorshDeserialize)]
+struct BindgenFirst {}
+
+#[test]
+fn externals() {
+    assert_contract_externals!(
+        Integration: own_get_owner,
+        paus_is_paused,
+        set_value,
+        get_value
+    );
+    assert_contract_externals!(MigrateIntegration: migrate, own_accept_owner, get_value);
+    assert_contract_externals!(BindgenFirst: own_get_owner, paus_is_paused);
+}
+
 #[test]
 fn integration() {
     let owner: AccountId = "owner".parse().unwrap();
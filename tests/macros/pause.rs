@@ -98,3 +98,25 @@ fn derive_pause_methods_fail_paused() {
 
     contract.only_when_unpaused(5);
 }
+
+#[derive(Pause)]
+#[pause(max_key_len = 2)]
+#[near_bindgen]
+struct ContractKeyBudget {}
+
+#[derive(Pause)]
+#[pause(storage_key = "StorageKey::Pause", max_key_len = 0)]
+#[near_bindgen]
+struct ContractKeyOverBudget {}
+
+#[test]
+fn max_key_len() {
+    assert_eq!(ContractKeyBudget::max_key_len(), 2);
+    assert!(!ContractKeyBudget::is_paused());
+}
+
+#[test]
+#[should_panic = "exceeds max_key_len = 0"]
+fn max_key_len_exceeded() {
+    ContractKeyOverBudget::is_paused();
+}
@@ -0,0 +1,107 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId,
+};
+use near_sdk_contract_tools::{
+    assert_contract_externals, migrate::MigrateHook, owner::Owner, pause::Pause, Migrate, Owner,
+    Pause, Upgrade,
+};
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct OldCouncil {
+    members: u32,
+}
+
+#[derive(Owner, Pause, Migrate, Upgrade, BorshSerialize, BorshDeserialize)]
+#[owner(method_prefix = "admin_")]
+#[pause(method_prefix = "admin_")]
+#[migrate(from = "OldCouncil", method_prefix = "state_")]
+#[upgrade(
+    hook = "owner",
+    method_prefix = "admin_",
+    migrate_method_name = "state_migrate"
+)]
+#[near_bindgen]
+struct Council {
+    members: u32,
+}
+
+impl MigrateHook for Council {
+    fn on_migrate(old: OldCouncil) -> Self {
+        Self {
+            members: old.members,
+        }
+    }
+}
+
+/// The contract's own methods, with the names that the components would
+/// otherwise generate
+#[near_bindgen]
+impl Council {
+    pub fn own_get_owner(&self) -> String {
+        "the council".to_string()
+    }
+
+    pub fn paus_is_paused(&self) -> bool {
+        self.members == 0
+    }
+
+    pub fn upgrade(&mut self) -> u32 {
+        self.members += 1;
+        self.members
+    }
+}
+
+#[derive(Owner, Pause, BorshSerialize, BorshDeserialize)]
+#[owner(method_prefix = "treasury_", storage_key = b"treasury_owner")]
+#[pause(method_prefix = "treasury_", storage_key = b"treasury_paused")]
+#[near_bindgen]
+struct Treasury {}
+
+#[test]
+fn externals() {
+    assert_contract_externals!(
+        Council: own_get_owner,
+        admin_own_get_owner,
+        admin_own_accept_owner,
+        paus_is_paused,
+        admin_paus_is_paused,
+        upgrade,
+        admin_upgrade,
+        state_migrate
+    );
+
+    assert_contract_externals!(
+        Treasury: treasury_own_get_owner,
+        treasury_own_propose_owner,
+        treasury_paus_is_paused
+    );
+}
+
+#[test]
+fn side_by_side() {
+    let alice: AccountId = "alice".parse().unwrap();
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .build());
+
+    let mut council = Council { members: 2 };
+    Owner::init(&mut council, &alice);
+
+    assert_eq!(council.own_get_owner(), "the council");
+    assert_eq!(council.admin_own_get_owner(), Some(alice.clone()));
+    assert_eq!(council.upgrade(), 3);
+
+    council.pause();
+    assert!(!council.paus_is_paused());
+    assert!(council.admin_paus_is_paused());
+
+    let mut treasury = Treasury {};
+    Owner::init(&mut treasury, &env::predecessor_account_id());
+
+    assert_eq!(treasury.treasury_own_get_owner(), Some(alice));
+    assert!(!treasury.treasury_paus_is_paused());
+}
@@ -0,0 +1,120 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId, Promise,
+};
+use near_sdk_contract_tools::{
+    assert_contract_externals,
+    owner::Owner,
+    pause::Pause,
+    standard::{
+        nep141::Nep141Controller,
+        nep148::{FungibleTokenMetadata, FT_METADATA_SPEC},
+    },
+    Nep141, Nep148, Owner, Pause,
+};
+
+/// Overrides one generated method of each component
+#[derive(Owner, Pause, Nep141, Nep148, BorshSerialize, BorshDeserialize)]
+#[owner(skip = "own_get_owner")]
+#[pause(skip = "paus_is_paused")]
+#[nep141(no_hooks, skip = "ft_transfer_call")]
+#[nep148(name = "Token", symbol = "TKN", decimals = 18, skip = "ft_metadata")]
+#[near_bindgen]
+struct Overrides {
+    pub frozen: bool,
+}
+
+#[near_bindgen]
+impl Overrides {
+    pub fn own_get_owner(&self) -> Option<AccountId> {
+        if self.frozen {
+            None
+        } else {
+            Self::slot_owner().read()
+        }
+    }
+
+    pub fn paus_is_paused(&self) -> bool {
+        self.frozen || Self::is_paused()
+    }
+
+    #[payable]
+    pub fn ft_transfer_call(
+        &mut self,
+        _receiver_id: AccountId,
+        _amount: U128,
+        _memo: Option<String>,
+        _msg: String,
+    ) -> Promise {
+        env::panic_str("ft_transfer_call is disabled");
+    }
+
+    pub fn ft_metadata(&self) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.into(),
+            name: if self.frozen { "Frozen Token" } else { "Token" }.into(),
+            symbol: "TKN".into(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 18,
+        }
+    }
+}
+
+#[test]
+fn externals() {
+    assert_contract_externals!(
+        Overrides: own_get_owner,
+        own_get_proposed_owner,
+        own_accept_owner,
+        paus_is_paused,
+        ft_transfer,
+        ft_transfer_call,
+        ft_balance_of,
+        ft_resolve_transfer,
+        ft_metadata
+    );
+}
+
+#[test]
+fn overridden_methods() {
+    let owner: AccountId = "owner".parse().unwrap();
+    let alice: AccountId = "alice".parse().unwrap();
+
+    let mut contract = Overrides { frozen: false };
+    Owner::init(&mut contract, &owner);
+    contract.deposit_unchecked(&owner, 100);
+
+    assert_eq!(contract.own_get_owner(), Some(owner.clone()));
+    assert!(!contract.paus_is_paused());
+    assert_eq!(contract.ft_metadata().name, "Token");
+
+    // Generated methods are unaffected
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(owner.clone())
+        .attached_deposit(1)
+        .build());
+    contract.ft_transfer(alice.clone(), U128(10), None);
+    assert_eq!(contract.ft_balance_of(alice), U128(10));
+    assert_eq!(contract.own_get_proposed_owner(), None);
+
+    contract.frozen = true;
+
+    assert_eq!(contract.own_get_owner(), None);
+    assert!(contract.paus_is_paused());
+    assert_eq!(contract.ft_metadata().name, "Frozen Token");
+}
+
+#[test]
+#[should_panic(expected = "ft_transfer_call is disabled")]
+fn overridden_ft_transfer_call() {
+    let mut contract = Overrides { frozen: false };
+
+    testing_env!(VMContextBuilder::new().attached_deposit(1).build());
+    contract.ft_transfer_call("alice".parse().unwrap(), U128(10), None, "".to_string());
+}
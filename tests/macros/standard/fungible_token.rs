@@ -1,8 +1,15 @@
 use near_sdk::{
-    json_types::Base64VecU8, near_bindgen, test_utils::VMContextBuilder, testing_env, AccountId,
+    borsh::{self, BorshSerialize},
+    json_types::Base64VecU8,
+    near_bindgen,
+    test_utils::VMContextBuilder,
+    testing_env, AccountId, BorshStorageKey,
 };
 use near_sdk_contract_tools::{
-    standard::nep141::{Nep141, Nep141Controller},
+    standard::{
+        nep141::{Nep141, Nep141Controller},
+        nep148::{Nep148, Nep148Controller},
+    },
     FungibleToken,
 };
 
@@ -69,3 +76,176 @@ fn metadata() {
         Some(Base64VecU8::from([97, 115, 100, 102].to_vec()))
     );
 }
+
+#[derive(FungibleToken)]
+#[fungible_token(
+    name = "My Hashed Fungible Token",
+    symbol = "MYHFT",
+    decimals = 18,
+    hashed_keys = 8,
+    no_hooks
+)]
+#[near_bindgen]
+struct MyHashedFungibleTokenContract {}
+
+#[test]
+fn hashed_keys() {
+    let mut ft = MyHashedFungibleTokenContract {};
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    assert_eq!(MyHashedFungibleTokenContract::hashed_key_len(), Some(8));
+    assert_ne!(
+        MyHashedFungibleTokenContract::slot_account(&alice).key,
+        MyFungibleTokenContract::slot_account(&alice).key,
+    );
+
+    ft.deposit_unchecked(&alice, 100);
+
+    let context = VMContextBuilder::new()
+        .predecessor_account_id(alice.clone())
+        .attached_deposit(1)
+        .build();
+
+    testing_env!(context);
+
+    ft.ft_transfer(bob.clone(), 30.into(), None);
+
+    assert_eq!(ft.ft_balance_of(alice).0, 70);
+    assert_eq!(ft.ft_balance_of(bob).0, 30);
+    assert_eq!(ft.ft_total_supply().0, 100);
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Token,
+    Core,
+    Metadata,
+}
+
+#[derive(FungibleToken)]
+#[fungible_token(
+    name = "My Shared Key Fungible Token",
+    symbol = "MYSFT",
+    decimals = 18,
+    storage_key = "StorageKey::Token",
+    no_hooks
+)]
+#[near_bindgen]
+struct MySharedKeyFungibleTokenContract {}
+
+#[derive(FungibleToken)]
+#[fungible_token(
+    name = "My Split Key Fungible Token",
+    symbol = "MYSKFT",
+    decimals = 18,
+    core_storage_key = "StorageKey::Core",
+    metadata_storage_key = "StorageKey::Metadata",
+    no_hooks
+)]
+#[near_bindgen]
+struct MySplitKeyFungibleTokenContract {}
+
+#[test]
+fn storage_keys() {
+    assert_eq!(
+        <MyFungibleTokenContract as Nep141Controller>::root().key,
+        b"~$141",
+    );
+    assert_eq!(
+        <MyFungibleTokenContract as Nep148Controller>::root().key,
+        b"~$148",
+    );
+
+    assert_eq!(
+        <MySharedKeyFungibleTokenContract as Nep141Controller>::root().key,
+        [0],
+    );
+    assert_eq!(
+        <MySharedKeyFungibleTokenContract as Nep148Controller>::root().key,
+        b"\0~$148",
+    );
+
+    assert_eq!(
+        <MySplitKeyFungibleTokenContract as Nep141Controller>::root().key,
+        [1],
+    );
+    assert_eq!(
+        <MySplitKeyFungibleTokenContract as Nep148Controller>::root().key,
+        [2],
+    );
+}
+
+/// The `ext_contract` interfaces used by the NEP-141 implementation are
+/// defined in the library, so a crate may define items with the same names
+/// next to its token contracts, and may define several token contracts.
+mod user_ext_contract {
+    use near_sdk::{ext_contract, json_types::U128, near_bindgen, AccountId, PromiseOrValue};
+    use near_sdk_contract_tools::{
+        standard::nep141::{self, Nep141, Nep141Controller},
+        FungibleToken,
+    };
+
+    // Only the generated `ext_nep141_receiver` module is used
+    #[allow(dead_code)]
+    #[ext_contract(ext_nep141_receiver)]
+    pub trait GameReceiver {
+        fn ft_on_transfer(
+            &mut self,
+            sender_id: AccountId,
+            amount: U128,
+            msg: String,
+        ) -> PromiseOrValue<U128>;
+
+        fn on_game_token(&mut self);
+    }
+
+    #[derive(FungibleToken)]
+    #[fungible_token(name = "Game Token", symbol = "GAME", decimals = 18, no_hooks)]
+    #[near_bindgen]
+    struct GameToken {}
+
+    #[derive(FungibleToken)]
+    #[fungible_token(
+        name = "Reward Token",
+        symbol = "RWD",
+        decimals = 6,
+        no_hooks,
+        storage_key = b"rwd"
+    )]
+    #[near_bindgen]
+    struct RewardToken {}
+
+    #[test]
+    fn two_token_contracts() {
+        let alice: AccountId = "alice".parse().unwrap();
+
+        GameToken {}.deposit_unchecked(&alice, 100);
+        RewardToken {}.deposit_unchecked(&alice, 7);
+
+        assert_eq!(GameToken {}.ft_balance_of(alice.clone()), U128(100));
+        assert_eq!(RewardToken {}.ft_balance_of(alice), U128(7));
+        assert_eq!(GameToken {}.ft_total_supply(), U128(100));
+        assert_eq!(RewardToken {}.ft_total_supply(), U128(7));
+    }
+
+    #[test]
+    fn ext_contract_paths() {
+        let receiver: AccountId = "receiver".parse().unwrap();
+
+        assert_eq!(GameToken {}.ft_total_supply(), U128(0));
+
+        let _ = ext_nep141_receiver::ext(receiver.clone()).on_game_token();
+        let _ = nep141::ext_nep141_receiver::ext(receiver.clone()).ft_on_transfer(
+            "sender".parse().unwrap(),
+            U128(1),
+            String::new(),
+        );
+        let _ = nep141::ext_nep141_resolver::ext(receiver).ft_resolve_transfer(
+            "sender".parse().unwrap(),
+            "receiver".parse().unwrap(),
+            U128(1),
+        );
+    }
+}
@@ -1,3 +1,5 @@
 pub mod fungible_token;
 pub mod nep141;
 pub mod nep148;
+pub mod nep245;
+pub mod nep330;
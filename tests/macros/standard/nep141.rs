@@ -3,11 +3,13 @@ use near_sdk::{
     collections::Vector,
     env,
     json_types::U128,
-    log, near_bindgen,
-    test_utils::VMContextBuilder,
-    testing_env, AccountId, PromiseOrValue,
+    log, near_bindgen, AccountId, PromiseOrValue,
+};
+use near_sdk_contract_tools::{
+    standard::nep141::*,
+    testing::{storage, TestEnv},
+    Nep141,
 };
-use near_sdk_contract_tools::{standard::nep141::*, Nep141};
 
 #[derive(Nep141, BorshDeserialize, BorshSerialize)]
 #[near_bindgen]
@@ -40,6 +42,11 @@ impl Nep141Hook<HookState> for FungibleToken {
     }
 }
 
+#[derive(Nep141)]
+#[nep141(no_hooks)]
+#[near_bindgen]
+struct PlainFungibleToken {}
+
 #[near_bindgen]
 struct FungibleTokenReceiver {
     pub log: Vector<(String, u128)>,
@@ -68,6 +75,7 @@ impl near_sdk_contract_tools::standard::nep141::Nep141Receiver for FungibleToken
 //  like that, and workspaces-rs doesn't work on macOS)
 #[test]
 fn nep141_transfer() {
+    let mut env = TestEnv::new();
     let mut ft = FungibleToken {
         transfers: Vector::new(b"t"),
         hooks: Vector::new(b"h"),
@@ -88,15 +96,20 @@ fn nep141_transfer() {
     assert_eq!(ft.ft_balance_of(bob.clone()).0, 20);
     assert_eq!(ft.ft_total_supply().0, 120);
 
-    let context = VMContextBuilder::new()
-        .predecessor_account_id(alice.clone())
-        .attached_deposit(1)
-        .build();
-
-    testing_env!(context);
+    env.set_predecessor(&alice).set_deposit(1);
 
     ft.ft_transfer(bob.clone(), 50.into(), None);
 
+    assert_eq!(
+        env.events_of::<Nep141Event>(),
+        vec![Nep141Event::FtTransfer(vec![event::FtTransferData {
+            old_owner_id: alice.clone(),
+            new_owner_id: bob.clone(),
+            amount: 50.into(),
+            memo: None,
+        }])],
+    );
+
     assert_eq!(
         ft.transfers.pop(),
         Some(Nep141Transfer {
@@ -116,3 +129,76 @@ fn nep141_transfer() {
     assert_eq!(ft.ft_balance_of(bob.clone()).0, 70);
     assert_eq!(ft.ft_total_supply().0, 120);
 }
+
+#[test]
+fn nep141_mint_burn_events() {
+    let env = TestEnv::new();
+    let mut ft = FungibleToken {
+        transfers: Vector::new(b"t"),
+        hooks: Vector::new(b"h"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+
+    ft.mint(alice.clone(), 100, Some("welcome".to_string()));
+    ft.burn(alice.clone(), 30, None);
+    log!("not an event");
+
+    assert_eq!(
+        env.events_of::<Nep141Event>(),
+        vec![
+            Nep141Event::FtMint(vec![event::FtMintData {
+                owner_id: alice.clone(),
+                amount: 100.into(),
+                memo: Some("welcome".to_string()),
+            }]),
+            Nep141Event::FtBurn(vec![event::FtBurnData {
+                owner_id: alice,
+                amount: 30.into(),
+                memo: None,
+            }]),
+        ],
+    );
+}
+
+#[test]
+fn nep141_transfer_storage_writes() {
+    let mut env = TestEnv::new();
+    let mut ft = PlainFungibleToken {};
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    ft.mint(alice.clone(), 100, None);
+
+    let before = storage::snapshot();
+
+    env.set_predecessor(&alice).set_deposit(1);
+    ft.ft_transfer(bob.clone(), 40.into(), None);
+
+    let diff = storage::diff(&before, &storage::snapshot());
+
+    // Only the balances of the sender and the receiver are written
+    assert_eq!(
+        diff.added,
+        [(
+            PlainFungibleToken::slot_account(&bob).key,
+            40u128.to_le_bytes().to_vec(),
+        )]
+        .into(),
+        "{diff}",
+    );
+    assert_eq!(
+        diff.changed,
+        [(
+            PlainFungibleToken::slot_account(&alice).key,
+            (
+                100u128.to_le_bytes().to_vec(),
+                60u128.to_le_bytes().to_vec(),
+            ),
+        )]
+        .into(),
+        "{diff}",
+    );
+    assert!(diff.removed.is_empty(), "{diff}");
+}
@@ -0,0 +1,208 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::Vector,
+    near_bindgen, AccountId,
+};
+use near_sdk_contract_tools::{standard::nep245::*, testing::TestEnv, Nep245};
+
+#[derive(Nep245, BorshDeserialize, BorshSerialize)]
+#[near_bindgen]
+struct MultiToken {
+    pub transfers: Vector<Nep245Transfer>,
+}
+
+impl Nep245Hook for MultiToken {
+    fn before_transfer(&mut self, transfer: &Nep245Transfer) {
+        self.transfers.push(transfer);
+    }
+}
+
+fn tokens(tokens: &[(&str, u128)]) -> Vec<(TokenId, u128)> {
+    tokens
+        .iter()
+        .map(|(token_id, amount)| (token_id.to_string(), *amount))
+        .collect()
+}
+
+#[test]
+fn nep245_transfer() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.deposit_unchecked(&alice, &tokens(&[("gold", 100), ("sword", 1)]));
+
+    env.set_predecessor(&alice).set_deposit(1);
+    mt.mt_transfer(bob.clone(), "gold".to_string(), 40.into(), None, None);
+
+    assert_eq!(
+        env.events_of::<Nep245Event>(),
+        vec![Nep245Event::MtTransfer(vec![event::MtTransferData {
+            authorized_id: None,
+            old_owner_id: alice.clone(),
+            new_owner_id: bob.clone(),
+            token_ids: vec!["gold".to_string()],
+            amounts: vec![40.into()],
+            memo: None,
+        }])],
+    );
+
+    assert_eq!(
+        mt.transfers.pop(),
+        Some(Nep245Transfer {
+            sender_id: alice.clone(),
+            receiver_id: bob.clone(),
+            tokens: tokens(&[("gold", 40)]),
+            memo: None,
+            msg: None,
+        }),
+    );
+
+    assert_eq!(mt.mt_balance_of(alice.clone(), "gold".to_string()).0, 60);
+    assert_eq!(mt.mt_balance_of(bob.clone(), "gold".to_string()).0, 40);
+    assert_eq!(mt.mt_total_supply("gold".to_string()).0, 100);
+}
+
+#[test]
+fn nep245_batch_transfer() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.mint(
+        alice.clone(),
+        tokens(&[("gold", 100), ("sword", 1)]),
+        Some("welcome".to_string()),
+    );
+
+    env.set_predecessor(&alice).set_deposit(1);
+    mt.mt_batch_transfer(
+        bob.clone(),
+        vec!["gold".to_string(), "sword".to_string()],
+        vec![30.into(), 1.into()],
+        None,
+        Some("loot".to_string()),
+    );
+
+    assert_eq!(
+        env.events_of::<Nep245Event>(),
+        vec![Nep245Event::MtTransfer(vec![event::MtTransferData {
+            authorized_id: None,
+            old_owner_id: alice.clone(),
+            new_owner_id: bob.clone(),
+            token_ids: vec!["gold".to_string(), "sword".to_string()],
+            amounts: vec![30.into(), 1.into()],
+            memo: Some("loot".to_string()),
+        }])],
+    );
+
+    let token_ids = vec![
+        "gold".to_string(),
+        "sword".to_string(),
+        "shield".to_string(),
+    ];
+    assert_eq!(
+        mt.mt_batch_balance_of(alice, token_ids.clone()),
+        vec![70.into(), 0.into(), 0.into()],
+    );
+    assert_eq!(
+        mt.mt_batch_balance_of(bob, token_ids),
+        vec![30.into(), 1.into(), 0.into()],
+    );
+    assert_eq!(mt.mt_total_supply("sword".to_string()).0, 1);
+}
+
+#[test]
+#[should_panic = "Token IDs and amounts differ in length"]
+fn nep245_batch_transfer_length_mismatch() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.deposit_unchecked(&alice, &tokens(&[("gold", 100)]));
+
+    env.set_predecessor(&alice).set_deposit(1);
+    mt.mt_batch_transfer(
+        bob,
+        vec!["gold".to_string()],
+        vec![1.into(), 2.into()],
+        None,
+        None,
+    );
+}
+
+#[test]
+#[should_panic = "Requires attached deposit of exactly 1 yoctoNEAR"]
+fn nep245_transfer_requires_one_yocto() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.deposit_unchecked(&alice, &tokens(&[("gold", 100)]));
+
+    env.set_predecessor(&alice);
+    mt.mt_transfer(bob, "gold".to_string(), 1.into(), None, None);
+}
+
+#[test]
+#[should_panic = "Approvals are not supported"]
+fn nep245_transfer_approval_unsupported() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.deposit_unchecked(&alice, &tokens(&[("gold", 100)]));
+
+    env.set_predecessor(&bob).set_deposit(1);
+    mt.mt_transfer(
+        bob.clone(),
+        "gold".to_string(),
+        1.into(),
+        Some((alice, 0)),
+        None,
+    );
+}
+
+#[test]
+#[should_panic = "Approvals are not supported"]
+fn nep245_batch_transfer_call_approvals_unsupported() {
+    let mut env = TestEnv::new();
+    let mut mt = MultiToken {
+        transfers: Vector::new(b"t"),
+    };
+
+    let alice: AccountId = "alice".parse().unwrap();
+    let bob: AccountId = "bob".parse().unwrap();
+
+    mt.deposit_unchecked(&alice, &tokens(&[("gold", 100)]));
+
+    env.set_predecessor(&alice).set_deposit(1);
+    mt.mt_batch_transfer_call(
+        bob,
+        vec!["gold".to_string()],
+        vec![1.into()],
+        Some(vec![None]),
+        None,
+        String::new(),
+    );
+}
@@ -0,0 +1,60 @@
+use near_sdk::{
+    near_bindgen,
+    serde_json::{self, json},
+};
+use near_sdk_contract_tools::{
+    standard::nep330::{Nep330, Standard},
+    ContractMetadata, FungibleToken, Nep245, Owner,
+};
+
+#[derive(Owner, FungibleToken, ContractMetadata)]
+#[fungible_token(name = "Test Fungible Token", symbol = "TFT", decimals = 18, no_hooks)]
+#[near_bindgen]
+struct FungibleTokenContract {}
+
+#[test]
+fn fungible_token_and_owner() {
+    let metadata = FungibleTokenContract {}.contract_source_metadata();
+
+    assert_eq!(
+        serde_json::to_value(metadata).unwrap(),
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "link": "https://github.com/NEARFoundation/near-sdk-contract-tools",
+            "standards": [
+                { "standard": "nep330", "version": "1.1.0" },
+                { "standard": "nep141", "version": "1.0.0" },
+                { "standard": "nep148", "version": "1.0.0" },
+            ],
+        }),
+    );
+}
+
+#[derive(Nep245, ContractMetadata)]
+#[nep245(no_hooks)]
+#[contract_metadata(
+    version = "abc123",
+    link = "https://example.com/source.tar.gz",
+    standard(standard = "x-custom", version = "0.1.0")
+)]
+#[near_bindgen]
+struct MultiTokenContract {}
+
+#[test]
+fn attributes() {
+    let metadata = MultiTokenContract {}.contract_source_metadata();
+
+    assert_eq!(metadata.version.as_deref(), Some("abc123"));
+    assert_eq!(
+        metadata.link.as_deref(),
+        Some("https://example.com/source.tar.gz"),
+    );
+    assert_eq!(
+        metadata.standards,
+        [
+            Standard::new("nep330", "1.1.0"),
+            Standard::new("nep245", "1.0.0"),
+            Standard::new("x-custom", "0.1.0"),
+        ],
+    );
+}
@@ -0,0 +1,62 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen, BorshStorageKey,
+};
+use near_sdk_contract_tools::{
+    owner::Owner, pause::Pause, standard::nep141::Nep141Controller, Nep141, Owner, Pause,
+};
+
+mod keys {
+    pub const OWNER: &[u8] = b"const_o";
+    pub const PAUSE: &[u8] = b"const_p";
+    pub const NEP141: &[u8] = b"const_ft";
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Owner,
+    Pause,
+    Nep141,
+}
+
+#[derive(Owner, Pause, Nep141, BorshSerialize, BorshDeserialize)]
+#[owner(storage_key = "crate::macros::storage_key::keys::OWNER")]
+#[pause(storage_key = "keys::PAUSE")]
+#[nep141(storage_key = "keys::NEP141", no_hooks)]
+#[near_bindgen]
+struct ConstKeys {}
+
+#[derive(Owner, Pause, Nep141, BorshSerialize, BorshDeserialize)]
+#[owner(storage_key = b"bytes_o")]
+#[pause(storage_key = b"bytes_p")]
+#[nep141(storage_key = b"bytes_ft", no_hooks)]
+#[near_bindgen]
+struct ByteKeys {}
+
+#[derive(Owner, Pause, Nep141, BorshSerialize, BorshDeserialize)]
+#[owner(storage_key = "StorageKey::Owner")]
+#[pause(storage_key = "StorageKey::Pause")]
+#[nep141(storage_key = "StorageKey::Nep141", no_hooks)]
+#[near_bindgen]
+struct EnumKeys {}
+
+#[test]
+fn const_keys() {
+    assert_eq!(<ConstKeys as Owner>::root().key, b"const_o");
+    assert_eq!(<ConstKeys as Pause>::root().key, b"const_p");
+    assert_eq!(<ConstKeys as Nep141Controller>::root().key, b"const_ft");
+}
+
+#[test]
+fn byte_keys() {
+    assert_eq!(<ByteKeys as Owner>::root().key, b"bytes_o");
+    assert_eq!(<ByteKeys as Pause>::root().key, b"bytes_p");
+    assert_eq!(<ByteKeys as Nep141Controller>::root().key, b"bytes_ft");
+}
+
+#[test]
+fn enum_keys() {
+    assert_eq!(<EnumKeys as Owner>::root().key, [0]);
+    assert_eq!(<EnumKeys as Pause>::root().key, [1]);
+    assert_eq!(<EnumKeys as Nep141Controller>::root().key, [2]);
+}
@@ -0,0 +1,22 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen, AccountId,
+};
+use near_sdk_contract_tools::{Nep141, Owner, Pause, Upgrade};
+
+// `any()` never holds
+#[derive(Owner, Pause, Nep141, Upgrade, BorshSerialize, BorshDeserialize)]
+#[pause(cfg = "any()")]
+#[nep141(no_hooks, cfg(method = "ft_transfer_call", predicate = "any()"))]
+#[upgrade(hook = "owner", cfg = "any()")]
+#[near_bindgen]
+struct Conditional {}
+
+fn main() {
+    let account_id: AccountId = "contract".parse().unwrap();
+
+    Conditional::ext(account_id.clone()).ft_transfer(account_id.clone(), 1.into(), None);
+    Conditional::ext(account_id.clone()).ft_transfer_call(account_id.clone(), 1.into(), None, "".into());
+    Conditional::ext(account_id.clone()).paus_is_paused();
+    Conditional::ext(account_id).upgrade(vec![].into(), None, None);
+}
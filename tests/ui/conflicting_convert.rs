@@ -0,0 +1,23 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk_contract_tools::Migrate;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Old {}
+
+fn convert(_old: Old) -> Contract {
+    Contract {}
+}
+
+fn convert_with_args(_old: Old, _args: String) -> Contract {
+    Contract {}
+}
+
+#[derive(Migrate, BorshSerialize, BorshDeserialize)]
+#[migrate(
+    from = "Old",
+    convert = "convert",
+    convert_with_args = "convert_with_args"
+)]
+struct Contract {}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk_contract_tools::contract_tools;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[contract_tools(owner, nep171)]
+#[near_sdk::near_bindgen]
+struct UnknownComponent {}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[contract_tools(owner, upgrade(serializer = "borsh", hook = "role(Role::Upgrader)"))]
+#[near_sdk::near_bindgen]
+struct MissingRbac {}
+
+fn main() {}
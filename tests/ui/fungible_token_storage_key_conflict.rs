@@ -0,0 +1,26 @@
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    BorshStorageKey,
+};
+use near_sdk_contract_tools::FungibleToken;
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Token,
+    Core,
+    Metadata,
+}
+
+#[derive(FungibleToken)]
+#[fungible_token(
+    name = "My Token",
+    symbol = "TKN",
+    decimals = 18,
+    storage_key = "StorageKey::Token",
+    core_storage_key = "StorageKey::Core",
+    metadata_storage_key = "StorageKey::Metadata",
+    no_hooks
+)]
+struct Contract {}
+
+fn main() {}
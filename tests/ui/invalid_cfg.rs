@@ -0,0 +1,17 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{Owner, Pause};
+
+#[derive(Owner, BorshSerialize, BorshDeserialize)]
+#[owner(cfg = "feature = testnet")]
+#[near_bindgen]
+struct OwnerContract {}
+
+#[derive(Pause, BorshSerialize, BorshDeserialize)]
+#[pause(cfg(method = "paus_pause", predicate = "test"))]
+#[near_bindgen]
+struct PauseContract {}
+
+fn main() {}
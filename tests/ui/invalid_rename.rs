@@ -0,0 +1,10 @@
+use near_sdk_contract_tools::Nep297;
+
+#[derive(Nep297)]
+#[nep297(standard = "x-ui", version = "1.0.0", rename_all = "camel")]
+enum Event {
+    First,
+    Second,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{assert_contract_externals, Pause};
+
+#[derive(Pause, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+struct Contract {}
+
+fn main() {
+    assert_contract_externals!(Contract: paus_is_paused, paus_pause);
+}
@@ -0,0 +1,7 @@
+use near_sdk_contract_tools::Rbac;
+
+#[derive(Rbac)]
+#[rbac(storage_key = "b\"r\".to_vec()")]
+struct Contract {}
+
+fn main() {}
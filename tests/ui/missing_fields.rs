@@ -0,0 +1,19 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{Nep148, Rbac, SimpleMultisig};
+
+#[derive(Rbac)]
+struct RbacContract {}
+
+#[derive(Nep148, BorshSerialize, BorshDeserialize)]
+#[nep148(name = "My Token", decimal = 24)]
+#[near_bindgen]
+struct Nep148Contract {}
+
+#[derive(SimpleMultisig)]
+#[simple_multisig(external)]
+struct MultisigContract {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk_contract_tools::Pause;
+
+#[derive(Pause, BorshSerialize, BorshDeserialize)]
+struct Contract {}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{Migrate, Owner, Rbac, Role, Upgrade};
+
+#[derive(Role)]
+enum Role {
+    Upgrader,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ContractV1 {}
+
+#[derive(Owner, Rbac, Upgrade, BorshSerialize, BorshDeserialize)]
+#[rbac(roles = "Role")]
+#[upgrade(hook = "owner_or_role(Role::Upgrder)")]
+#[near_bindgen]
+struct Contract {}
+
+// The check does not depend on the guard being compiled in
+#[derive(Rbac, Migrate, BorshSerialize, BorshDeserialize)]
+#[rbac(roles = "Role")]
+#[migrate(from = "ContractV1", allow = "role(Role::Migrator)", cfg = "any()")]
+#[near_bindgen]
+struct MigratedContract {}
+
+fn main() {}
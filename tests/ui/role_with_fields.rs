@@ -0,0 +1,9 @@
+use near_sdk_contract_tools::Role;
+
+#[derive(Role)]
+enum Role {
+    Admin,
+    Minter { limit: u32 },
+}
+
+fn main() {}
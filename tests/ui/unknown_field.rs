@@ -0,0 +1,7 @@
+use near_sdk_contract_tools::Nep148;
+
+#[derive(Nep148)]
+#[nep148(name = "My Token", symbol = "TKN", decimal = 18)]
+struct Contract {}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{FungibleToken, Owner};
+
+#[derive(Owner, BorshSerialize, BorshDeserialize)]
+#[owner(skip = "own_get_owner, own_transfer")]
+#[near_bindgen]
+struct OwnerContract {}
+
+#[derive(FungibleToken, BorshSerialize, BorshDeserialize)]
+#[fungible_token(
+    name = "My Token",
+    symbol = "TKN",
+    decimals = 18,
+    no_hooks,
+    skip = "ft_transfer_call, ft_mint"
+)]
+#[near_bindgen]
+struct TokenContract {}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+};
+use near_sdk_contract_tools::{assert_contract_externals, Owner, Upgrade};
+
+#[derive(Owner, Upgrade, BorshSerialize, BorshDeserialize)]
+#[upgrade(hook = "owner", serializer = "borsh", staged, stage_delay_ns = "1_000")]
+#[near_bindgen]
+struct Contract {}
+
+fn main() {
+    // Code may only be deployed once the delay has passed
+    assert_contract_externals!(Contract: stage_upgrade, deploy_staged, upgrade);
+}
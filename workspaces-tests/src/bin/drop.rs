@@ -0,0 +1,80 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::{U128, U64},
+    near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue, PublicKey,
+};
+use near_sdk_contract_tools::{
+    drop::{DropAsset, DropRecord, Linkdrop},
+    standard::nep141::Nep141Receiver,
+};
+
+/// Drops of NEAR and NEP-141 tokens, claimable with a key
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {}
+
+impl Linkdrop for Contract {}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    #[payable]
+    pub fn create_drop(
+        &mut self,
+        public_key: PublicKey,
+        asset: DropAsset,
+        amount: U128,
+        expires_at: Option<U64>,
+    ) -> Promise {
+        Linkdrop::create_drop(
+            self,
+            public_key,
+            asset,
+            amount.into(),
+            expires_at.map(Into::into),
+        )
+    }
+
+    #[private]
+    pub fn drop_resolve_add_key(&mut self, public_key: PublicKey) {
+        self.resolve_add_key(public_key);
+    }
+
+    pub fn claim(&mut self, account_id: AccountId) -> Promise {
+        Linkdrop::claim(self, account_id)
+    }
+
+    pub fn refund_drop(&mut self, public_key: PublicKey) -> Promise {
+        Linkdrop::refund_drop(self, public_key)
+    }
+
+    #[private]
+    pub fn drop_resolve_transfer(&mut self, public_key: PublicKey) {
+        self.resolve_transfer(public_key);
+    }
+
+    pub fn get_drop(&self, public_key: PublicKey) -> Option<DropRecord> {
+        Self::drop_record(&public_key)
+    }
+}
+
+#[near_bindgen]
+impl Nep141Receiver for Contract {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.fund_ft_drop(sender_id, amount, &msg)
+    }
+}
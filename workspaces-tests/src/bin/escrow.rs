@@ -0,0 +1,76 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue,
+};
+use near_sdk_contract_tools::{
+    escrow::{Escrow, EscrowHook, EscrowRecord, EscrowState},
+    owner::Owner,
+    standard::nep141::Nep141Receiver,
+    Owner,
+};
+
+/// Escrows of NEAR and NEP-141 tokens, settled by the owner
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, Owner)]
+#[near_bindgen]
+pub struct Contract {}
+
+impl EscrowHook for Contract {
+    fn on_settle(&self, _escrow: &EscrowRecord, _state: &EscrowState) {
+        Self::require_owner();
+    }
+}
+
+impl Escrow for Contract {}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        let mut contract = Self {};
+
+        Owner::init(&mut contract, &env::predecessor_account_id());
+
+        contract
+    }
+
+    #[payable]
+    pub fn lock(&mut self, id: String, beneficiary_id: AccountId) {
+        self.lock_attached_deposit(id, beneficiary_id);
+    }
+
+    pub fn release(&mut self, id: String) -> Promise {
+        Escrow::release(self, &id)
+    }
+
+    pub fn refund(&mut self, id: String) -> Promise {
+        Escrow::refund(self, &id)
+    }
+
+    #[private]
+    pub fn escrow_resolve_settle(&mut self, id: String) {
+        self.resolve_settle(&id)
+    }
+
+    pub fn get_escrow(&self, id: String) -> Option<EscrowRecord> {
+        self.escrow(&id)
+    }
+}
+
+#[near_bindgen]
+impl Nep141Receiver for Contract {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.lock_ft_transfer(sender_id, amount, &msg)
+    }
+}
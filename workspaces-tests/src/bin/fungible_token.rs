@@ -7,7 +7,7 @@ use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     env,
     json_types::U128,
-    near_bindgen, PanicOnDefault,
+    near_bindgen, AccountId, PanicOnDefault,
 };
 use near_sdk_contract_tools::{standard::nep141::*, FungibleToken};
 
@@ -26,4 +26,20 @@ impl Contract {
     pub fn mint(&mut self, amount: U128) {
         self.deposit_unchecked(&env::predecessor_account_id(), amount.into());
     }
+
+    pub fn mint_batch(&mut self, mints: Vec<(AccountId, U128)>) {
+        let mints = mints
+            .into_iter()
+            .map(|(account_id, amount)| (account_id, amount.into()))
+            .collect::<Vec<_>>();
+
+        self.deposit_batch_unchecked(&mints);
+    }
+
+    /// For gas comparison with `mint_batch`
+    pub fn mint_unbatched(&mut self, mints: Vec<(AccountId, U128)>) {
+        for (account_id, amount) in mints {
+            self.deposit_unchecked(&account_id, amount.into());
+        }
+    }
 }
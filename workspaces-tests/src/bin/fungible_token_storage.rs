@@ -0,0 +1,59 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, AccountId, Balance, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    slot::Slot, standard::nep141::*, storage_accounting::StorageAccounting, FungibleToken,
+};
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, FungibleToken)]
+#[fungible_token(name = "My Fungible Token", symbol = "MYFT", decimals = 18)]
+#[near_bindgen]
+pub struct Contract {}
+
+impl Nep141StorageBalance for Contract {
+    fn slot_storage_balance(account_id: &AccountId) -> Slot<Balance> {
+        Slot::root(b"s").field_ref(account_id)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn mint(&mut self, amount: U128) {
+        self.deposit_unchecked(&env::predecessor_account_id(), amount.into());
+    }
+
+    /// Returns the storage cost charged to the attached deposit
+    #[payable]
+    pub fn mint_paid(&mut self, amount: U128) -> U128 {
+        let accounting = StorageAccounting::attached_deposit();
+        self.deposit_unchecked(&env::predecessor_account_id(), amount.into());
+        accounting.settle().cost().into()
+    }
+
+    #[payable]
+    pub fn storage_deposit(&mut self) {
+        let mut slot = Self::slot_storage_balance(&env::predecessor_account_id());
+        let balance = slot.read().unwrap_or(0);
+        slot.write(&(balance + env::attached_deposit()));
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> U128 {
+        Self::slot_storage_balance(&account_id)
+            .read()
+            .unwrap_or(0)
+            .into()
+    }
+}
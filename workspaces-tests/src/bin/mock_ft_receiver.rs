@@ -0,0 +1,57 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, AccountId, Gas, PromiseOrValue,
+};
+use near_sdk_contract_tools::standard::nep141::Nep141Receiver;
+
+/// Gas left over by `"slow"`, for `ft_on_transfer` to return
+const SLOW_GAS_REMAINING: Gas = Gas(5_000_000_000_000);
+
+/// An `ft_on_transfer` receiver for testing `ft_transfer_call`, whose
+/// behavior is chosen by the `msg` of the transfer:
+///
+///  - `"use:N"` Returns `N` as unused, i.e. keeps `amount - N` tokens.
+///  - `"all"` Keeps all of the tokens.
+///  - `"panic"` Panics, so all of the tokens are refunded.
+///  - `"slow"` Burns most of the attached gas, then keeps all of the tokens.
+///
+/// Any other `msg` panics.
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {}
+
+#[near_bindgen]
+impl Nep141Receiver for Contract {
+    #[allow(unused_variables)]
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let unused = match msg.as_str() {
+            "all" => 0,
+            "panic" => env::panic_str("Receiver panicked"),
+            "slow" => {
+                let mut hash = env::sha256(&amount.0.to_le_bytes());
+                while env::prepaid_gas() - env::used_gas() > SLOW_GAS_REMAINING {
+                    hash = env::sha256(&hash);
+                }
+                0
+            }
+            _ => msg
+                .strip_prefix("use:")
+                .and_then(|unused| unused.parse().ok())
+                .unwrap_or_else(|| env::panic_str(&format!("Unknown msg: {msg}"))),
+        };
+
+        PromiseOrValue::Value(U128(unused))
+    }
+}
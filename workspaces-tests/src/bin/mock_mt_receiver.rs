@@ -0,0 +1,53 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, AccountId, PromiseOrValue,
+};
+use near_sdk_contract_tools::standard::nep245::{Nep245Receiver, TokenId};
+
+/// An `mt_on_transfer` receiver for testing `mt_transfer_call` and
+/// `mt_batch_transfer_call`, whose behavior is chosen by the `msg` of the
+/// transfer:
+///
+///  - `"use:N"` Returns `N` as unused for every token, i.e. keeps
+///     `amount - N` of each.
+///  - `"all"` Keeps all of the tokens.
+///  - `"panic"` Panics, so all of the tokens are refunded.
+///  - `"short"` Returns one amount fewer than the number of tokens, so all
+///     of the tokens are refunded.
+///
+/// Any other `msg` panics.
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {}
+
+#[near_bindgen]
+impl Nep245Receiver for Contract {
+    #[allow(unused_variables)]
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        let unused = match msg.as_str() {
+            "all" => 0,
+            "panic" => env::panic_str("Receiver panicked"),
+            "short" => return PromiseOrValue::Value(vec![U128(0); amounts.len() - 1]),
+            _ => msg
+                .strip_prefix("use:")
+                .and_then(|unused| unused.parse().ok())
+                .unwrap_or_else(|| env::panic_str(&format!("Unknown msg: {msg}"))),
+        };
+
+        PromiseOrValue::Value(vec![U128(unused); amounts.len()])
+    }
+}
@@ -0,0 +1,34 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, PanicOnDefault,
+};
+use near_sdk_contract_tools::{standard::nep245::*, Nep245};
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, Nep245)]
+#[nep245(no_hooks)]
+#[near_bindgen]
+pub struct Contract {}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn mint(&mut self, token_ids: Vec<TokenId>, amounts: Vec<U128>) {
+        let tokens = token_ids
+            .into_iter()
+            .zip(amounts.into_iter().map(u128::from))
+            .collect();
+
+        Nep245Controller::mint(self, env::predecessor_account_id(), tokens, None);
+    }
+}
@@ -52,15 +52,16 @@ impl Contract {
         receiver_id: AccountId,
         actions: Vec<native_transaction_action::PromiseAction>,
     ) -> u32 {
-        let request_id = self
-            .create_request(
-                native_transaction_action::NativeTransactionAction {
-                    receiver_id,
-                    actions,
-                },
-                ApprovalState::new(),
-            )
-            .unwrap();
+        let action = NativeTransactionAction {
+            receiver_id,
+            actions,
+        };
+
+        action
+            .validate()
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        let request_id = self.create_request(action, ApprovalState::new()).unwrap();
 
         near_sdk::log!(format!("Request ID: {request_id}"));
 
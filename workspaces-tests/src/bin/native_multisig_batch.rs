@@ -0,0 +1,68 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen, BorshStorageKey, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    approval::{
+        native_transaction_action::{NativeTransactionAction, NativeTransactionBatch},
+        simple_multisig::{ApprovalState, Configuration},
+        ApprovalManager,
+    },
+    rbac::Rbac,
+    Rbac, SimpleMultisig,
+};
+
+#[derive(Clone, Debug, BorshSerialize, BorshStorageKey)]
+pub enum Role {
+    Multisig,
+}
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, Rbac, SimpleMultisig)]
+#[simple_multisig(action = "NativeTransactionBatch", role = "Role::Multisig")]
+#[rbac(roles = "Role")]
+#[near_bindgen]
+pub struct Contract {}
+
+#[near_bindgen]
+impl Contract {
+    const APPROVAL_THRESHOLD: u8 = 2;
+    const VALIDITY_PERIOD: u64 = 1_000_000 * 1_000 * 60 * 60 * 24 * 7;
+
+    #[init]
+    pub fn new() -> Self {
+        <Self as ApprovalManager<_, _, _>>::init(Configuration::new(
+            Self::APPROVAL_THRESHOLD,
+            Self::VALIDITY_PERIOD,
+        ));
+
+        Self {}
+    }
+
+    pub fn obtain_multisig_permission(&mut self) {
+        self.add_role(env::predecessor_account_id(), &Role::Multisig);
+    }
+
+    pub fn request(&mut self, transactions: Vec<NativeTransactionAction>) -> u32 {
+        let action = NativeTransactionBatch { transactions };
+
+        action
+            .validate()
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        self.create_request(action, ApprovalState::new()).unwrap()
+    }
+
+    pub fn approve(&mut self, request_id: u32) {
+        self.approve_request(request_id).unwrap();
+    }
+
+    // Joint promises cannot be returned from a contract method
+    pub fn execute(&mut self, request_id: u32) {
+        self.execute_request(request_id).unwrap();
+    }
+}
@@ -11,9 +11,8 @@ use near_sdk::{
 };
 use near_sdk_contract_tools::{
     approval::{
-        self,
         simple_multisig::{AccountAuthorizer, ApprovalState, Configuration},
-        ApprovalManager,
+        ApprovalManager, Stateless, StatelessAction,
     },
     rbac::Rbac,
     slot::Slot,
@@ -32,10 +31,12 @@ enum MyAction {
     SayGoodbye,
 }
 
-impl approval::Action<Contract> for MyAction {
+// This action does not need access to the contract, so it can be adapted for
+// use with any contract via `Stateless`.
+impl StatelessAction for MyAction {
     type Output = &'static str;
 
-    fn execute(self, _contract: &mut Contract) -> Self::Output {
+    fn execute(self) -> Self::Output {
         match self {
             Self::SayHello => "hello",
             Self::SayGoodbye => "goodbye",
@@ -55,7 +56,7 @@ pub struct Contract {}
 
 // This single function implementation completely implements simple multisig on
 // the contract
-impl ApprovalManager<MyAction, ApprovalState, Configuration<Self>> for Contract {
+impl ApprovalManager<Stateless<MyAction>, ApprovalState, Configuration<Self>> for Contract {
     fn root() -> Slot<()> {
         Slot::new(StorageKey::SimpleMultisig)
     }
@@ -95,9 +96,14 @@ impl Contract {
 
     #[init]
     pub fn new() -> Self {
+        Self::new_with_validity_period(Self::VALIDITY_PERIOD)
+    }
+
+    #[init]
+    pub fn new_with_validity_period(validity_period_nanoseconds: u64) -> Self {
         <Self as ApprovalManager<_, _, _>>::init(Configuration::new(
             Self::APPROVAL_THRESHOLD,
-            Self::VALIDITY_PERIOD,
+            validity_period_nanoseconds,
         ));
 
         Self {}
@@ -114,7 +120,9 @@ impl Contract {
             _ => env::panic_str("action must be \"hello\" or \"goodbye\""),
         };
 
-        let request_id = self.create_request(action, ApprovalState::new()).unwrap();
+        let request_id = self
+            .create_request(Stateless(action), ApprovalState::new())
+            .unwrap();
 
         near_sdk::log!(format!("Request ID: {request_id}"));
 
@@ -125,6 +133,13 @@ impl Contract {
         self.approve_request(request_id).unwrap();
     }
 
+    pub fn approve_many(&mut self, request_ids: Vec<u32>) -> Vec<bool> {
+        self.approve_requests(request_ids)
+            .iter()
+            .map(Result::is_ok)
+            .collect()
+    }
+
     pub fn is_approved(&self, request_id: u32) -> bool {
         <Contract as ApprovalManager<_, _, _>>::is_approved_for_execution(request_id).is_ok()
     }
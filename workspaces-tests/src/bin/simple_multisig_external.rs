@@ -0,0 +1,76 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    serde::{Deserialize, Serialize},
+    BorshStorageKey, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    approval::{simple_multisig::Configuration, *},
+    rbac::Rbac,
+    Rbac, SimpleMultisig,
+};
+use std::string::ToString;
+use strum_macros::Display;
+
+#[derive(BorshSerialize, BorshStorageKey, Clone, Debug, Display)]
+pub enum Role {
+    Member,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CounterAction {
+    Increment,
+    Decrement,
+    Reset,
+}
+
+impl Action<Contract> for CounterAction {
+    type Output = u32;
+
+    fn execute(self, contract: &mut Contract) -> Self::Output {
+        match self {
+            CounterAction::Increment => {
+                contract.counter += 1;
+            }
+            CounterAction::Decrement => {
+                contract.counter -= 1;
+            }
+            CounterAction::Reset => {
+                contract.counter = 0;
+            }
+        }
+
+        contract.counter
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Rbac, SimpleMultisig)]
+#[simple_multisig(action = "CounterAction", role = "Role::Member", external)]
+#[rbac(roles = "Role")]
+#[near_bindgen]
+pub struct Contract {
+    pub counter: u32,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        <Self as ApprovalManager<_, _, _>>::init(Configuration::new(2, 0));
+
+        Self { counter: 0 }
+    }
+
+    pub fn obtain_multisig_permission(&mut self) {
+        self.add_role(env::predecessor_account_id(), &Role::Member);
+    }
+
+    pub fn get_counter(&self) -> u32 {
+        self.counter
+    }
+}
+
+pub fn main() {} // Ignore
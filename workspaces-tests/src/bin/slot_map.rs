@@ -0,0 +1,69 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedMap,
+    near_bindgen, PanicOnDefault,
+};
+use near_sdk_contract_tools::slot::{Slot, SlotMap};
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {
+    pub unordered_map: UnorderedMap<u32, u64>,
+}
+
+fn slot_map() -> SlotMap<u32, u64> {
+    SlotMap::new(Slot::root(b"s"))
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self {
+            unordered_map: UnorderedMap::new(b"u"),
+        }
+    }
+
+    pub fn insert_slot_map(&mut self, start: u32, count: u32) {
+        let mut map = slot_map();
+        for i in start..start + count {
+            map.insert(&i, &(i as u64));
+        }
+    }
+
+    pub fn insert_unordered_map(&mut self, start: u32, count: u32) {
+        for i in start..start + count {
+            self.unordered_map.insert(&i, &(i as u64));
+        }
+    }
+
+    pub fn remove_slot_map(&mut self, start: u32, count: u32) {
+        let mut map = slot_map();
+        for i in start..start + count {
+            map.remove(&i);
+        }
+    }
+
+    pub fn remove_unordered_map(&mut self, start: u32, count: u32) {
+        for i in start..start + count {
+            self.unordered_map.remove(&i);
+        }
+    }
+
+    pub fn slot_map_entries(&self, offset: u32, limit: u32) -> Vec<(u32, u64)> {
+        slot_map().iter(offset, limit).collect()
+    }
+
+    pub fn unordered_map_entries(&self, offset: u32, limit: u32) -> Vec<(u32, u64)> {
+        self.unordered_map
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
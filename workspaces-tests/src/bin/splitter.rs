@@ -0,0 +1,72 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U128,
+    near_bindgen, AccountId, PanicOnDefault, Promise, PromiseOrValue,
+};
+use near_sdk_contract_tools::{
+    splitter::{Payee, SplitAsset, Splitter},
+    standard::nep141::Nep141Receiver,
+};
+
+/// Splits NEAR and NEP-141 tokens between fixed payees
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {}
+
+impl Splitter for Contract {}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(payees: Vec<Payee>) -> Self {
+        let mut contract = Self {};
+
+        contract.init_payees(payees);
+
+        contract
+    }
+
+    #[payable]
+    pub fn deposit(&mut self) {
+        self.deposit_attached();
+    }
+
+    pub fn release(&mut self, asset: SplitAsset, payee_id: AccountId) -> Promise {
+        Splitter::release(self, asset, &payee_id)
+    }
+
+    #[private]
+    pub fn split_resolve_release(&mut self, asset: SplitAsset, payee_id: AccountId, amount: U128) {
+        self.resolve_release(asset, payee_id, amount)
+    }
+
+    pub fn get_releasable(&self, asset: SplitAsset, payee_id: AccountId) -> U128 {
+        Self::releasable(&asset, &payee_id).into()
+    }
+
+    pub fn get_released(&self, asset: SplitAsset, payee_id: AccountId) -> U128 {
+        Self::released(&asset, &payee_id).into()
+    }
+
+    pub fn get_total_received(&self, asset: SplitAsset) -> U128 {
+        Self::total_received(&asset).into()
+    }
+}
+
+#[near_bindgen]
+impl Nep141Receiver for Contract {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let _ = (sender_id, msg);
+        self.deposit_ft_transfer(amount)
+    }
+}
@@ -0,0 +1,50 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    migrate::{MigrateError, TryMigrateHook},
+    Migrate,
+};
+
+pub fn main() {} // Ignore
+
+#[derive(BorshDeserialize)]
+pub struct ContractOld {
+    pub foo: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Migrate)]
+#[migrate(from = "ContractOld")]
+#[near_bindgen]
+pub struct ContractNew {
+    pub bar: u64,
+}
+
+impl TryMigrateHook for ContractNew {
+    fn try_on_migrate(old_schema: ContractOld) -> Result<Self, MigrateError> {
+        if old_schema.foo == 0 {
+            return Err(MigrateError::new(
+                "foo must be incremented before migration",
+            ));
+        }
+
+        Ok(Self {
+            bar: old_schema.foo as u64,
+        })
+    }
+}
+
+#[near_bindgen]
+impl ContractNew {
+    #[init]
+    pub fn new() -> Self {
+        Self { bar: 0 }
+    }
+
+    pub fn get_bar(&self) -> u64 {
+        self.bar
+    }
+}
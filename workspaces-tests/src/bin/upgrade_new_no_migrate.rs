@@ -0,0 +1,26 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen, PanicOnDefault,
+};
+
+pub fn main() {} // Ignore
+
+/// Same state schema as `upgrade_old_no_migrate`, and no migrate function
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+#[near_bindgen]
+pub struct ContractNew {
+    pub foo: u32,
+}
+
+#[near_bindgen]
+impl ContractNew {
+    pub fn get_foo(&self) -> u32 {
+        self.foo
+    }
+
+    pub fn get_version(&self) -> u32 {
+        2
+    }
+}
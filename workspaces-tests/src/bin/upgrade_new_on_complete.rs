@@ -0,0 +1,61 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::Base58CryptoHash,
+    near_bindgen, CryptoHash, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    migrate::MigrateHook, owner::Owner, upgrade::serialized::UpgradeCompleteHook, Migrate, Owner,
+    Upgrade,
+};
+
+pub fn main() {} // Ignore
+
+#[derive(BorshDeserialize)]
+pub struct ContractOld {
+    pub foo: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Migrate, Owner, Upgrade)]
+#[migrate(from = "ContractOld")]
+#[upgrade(serializer = "borsh", hook = "owner", on_complete = "custom")]
+#[near_bindgen]
+pub struct ContractNew {
+    pub bar: u64,
+    pub completed_code_hash: Option<CryptoHash>,
+}
+
+impl MigrateHook for ContractNew {
+    fn on_migrate(old_schema: ContractOld) -> Self {
+        Self {
+            bar: old_schema.foo as u64,
+            completed_code_hash: None,
+        }
+    }
+}
+
+impl UpgradeCompleteHook for ContractNew {
+    fn on_upgrade_complete(&mut self, code_hash: CryptoHash) {
+        self.completed_code_hash = Some(code_hash);
+    }
+}
+
+#[near_bindgen]
+impl ContractNew {
+    #[init]
+    pub fn new() -> Self {
+        Self {
+            bar: 0,
+            completed_code_hash: None,
+        }
+    }
+
+    pub fn get_bar(&self) -> u64 {
+        self.bar
+    }
+
+    pub fn get_completed_code_hash(&self) -> Option<Base58CryptoHash> {
+        self.completed_code_hash.map(Into::into)
+    }
+}
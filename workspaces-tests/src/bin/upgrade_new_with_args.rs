@@ -0,0 +1,46 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    PanicOnDefault,
+};
+use near_sdk_contract_tools::Migrate;
+
+pub fn main() {} // Ignore
+
+#[derive(BorshDeserialize)]
+pub struct ContractOld {
+    pub foo: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrateArgs {
+    pub multiplier: u64,
+}
+
+fn convert(old: ContractOld, args: MigrateArgs) -> ContractNew {
+    ContractNew {
+        bar: old.foo as u64 * args.multiplier,
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Migrate)]
+#[migrate(
+    from = "ContractOld",
+    convert_with_args = "convert",
+    args = "MigrateArgs"
+)]
+#[near_bindgen]
+pub struct ContractNew {
+    pub bar: u64,
+}
+
+#[near_bindgen]
+impl ContractNew {
+    pub fn get_bar(&self) -> u64 {
+        self.bar
+    }
+}
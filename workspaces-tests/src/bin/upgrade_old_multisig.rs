@@ -10,9 +10,9 @@ use near_sdk::{
 };
 use near_sdk_contract_tools::{
     approval::{self, ApprovalManager},
+    contract_tools,
     owner::{Owner, OwnerExternal},
     rbac::Rbac,
-    Owner, Rbac, SimpleMultisig, Upgrade,
 };
 
 #[derive(BorshStorageKey, BorshSerialize, Debug, Clone)]
@@ -31,25 +31,18 @@ impl approval::Action<Contract> for ContractAction {
 
     fn execute(self, _contract: &mut Contract) -> Self::Output {
         match self {
-            ContractAction::Upgrade { code } => _contract.upgrade(code.into()),
+            ContractAction::Upgrade { code } => _contract.upgrade(code.into(), None, None),
         }
     }
 }
 
-#[derive(
-    BorshSerialize,
-    BorshDeserialize,
-    PanicOnDefault,
-    Owner,
-    Debug,
-    Clone,
-    Rbac,
-    Upgrade,
-    SimpleMultisig,
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Debug, Clone)]
+#[contract_tools(
+    owner,
+    rbac(roles = "Role"),
+    simple_multisig(role = "Role::Multisig", action = "ContractAction"),
+    upgrade(serializer = "borsh", hook = "owner")
 )]
-#[rbac(roles = "Role")]
-#[simple_multisig(role = "Role::Multisig", action = "ContractAction")]
-#[upgrade(serializer = "borsh", hook = "owner")]
 #[near_bindgen]
 pub struct Contract {
     pub foo: u32,
@@ -0,0 +1,49 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen, BorshStorageKey, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    owner::{Owner, OwnerExternal},
+    rbac::Rbac,
+    Owner, Rbac, Upgrade,
+};
+pub fn main() {}
+
+#[derive(BorshStorageKey, BorshSerialize, Debug, Clone)]
+pub enum Role {
+    Upgrader,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Owner, Rbac, Upgrade)]
+#[rbac(roles = "Role")]
+#[upgrade(serializer = "borsh", hook = "owner_or_role(Role::Upgrader)")]
+#[near_bindgen]
+pub struct ContractOld {
+    pub foo: u32,
+}
+
+#[near_bindgen]
+impl ContractOld {
+    #[init]
+    pub fn new() -> Self {
+        let mut contract = Self { foo: 0 };
+
+        Owner::init(&mut contract, &env::predecessor_account_id());
+        contract
+    }
+
+    pub fn grant_upgrader(&mut self, account_id: near_sdk::AccountId) {
+        Self::require_owner();
+        self.add_role(account_id, &Role::Upgrader);
+    }
+
+    pub fn increment_foo(&mut self) {
+        self.foo += 1;
+    }
+
+    pub fn get_foo(&self) -> u32 {
+        self.foo
+    }
+}
@@ -0,0 +1,56 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen, BorshStorageKey, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    approval::{self, ApprovalManager},
+    rbac::Rbac,
+    upgrade::staged::UpgradeAction,
+    Rbac, SimpleMultisig, Upgrade,
+};
+use strum_macros::Display;
+pub fn main() {}
+
+#[derive(BorshStorageKey, BorshSerialize, Debug, Clone, Display)]
+pub enum Role {
+    Multisig,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Rbac, Upgrade, SimpleMultisig)]
+#[rbac(roles = "Role")]
+#[simple_multisig(role = "Role::Multisig", action = "UpgradeAction", external)]
+#[upgrade(serializer = "borsh", hook = "multisig", staged)]
+#[near_bindgen]
+pub struct ContractOld {
+    pub foo: u32,
+}
+
+#[near_bindgen]
+impl ContractOld {
+    #[init]
+    pub fn new() -> Self {
+        <Self as ApprovalManager<_, _, _>>::init(approval::simple_multisig::Configuration::new(
+            2, 0,
+        ));
+
+        let mut contract = Self { foo: 0 };
+
+        contract.add_role(env::predecessor_account_id(), &Role::Multisig);
+
+        contract
+    }
+
+    pub fn obtain_multisig_permission(&mut self) {
+        self.add_role(env::predecessor_account_id(), &Role::Multisig);
+    }
+
+    pub fn increment_foo(&mut self) {
+        self.foo += 1;
+    }
+
+    pub fn get_foo(&self) -> u32 {
+        self.foo
+    }
+}
@@ -0,0 +1,69 @@
+#![allow(missing_docs)]
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+    store::{LookupMap, UnorderedMap},
+    PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    migrate::{MigrateHook, MigrationProgress, ProgressiveMigration},
+    Migrate,
+};
+
+pub fn main() {} // Ignore
+
+#[derive(BorshDeserialize)]
+pub struct ContractOld {
+    pub balances: UnorderedMap<u32, u32>,
+}
+
+/// Balances are re-keyed from `u32` to `String` and widened to `u64`
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Migrate)]
+#[migrate(from = "ContractOld", progressive)]
+#[near_bindgen]
+pub struct ContractNew {
+    pub old_balances: UnorderedMap<u32, u32>,
+    pub balances: LookupMap<String, u64>,
+}
+
+impl MigrateHook for ContractNew {
+    fn on_migrate(old_schema: ContractOld) -> Self {
+        Self::start_progressive_migration();
+
+        Self {
+            old_balances: old_schema.balances,
+            balances: LookupMap::new(b"v"),
+        }
+    }
+}
+
+impl ProgressiveMigration for ContractNew {
+    fn migrate_items(&mut self, cursor: u64, limit: u32) -> MigrationProgress {
+        let mut items_processed = 0;
+
+        for (key, value) in self
+            .old_balances
+            .iter()
+            .skip(cursor as usize)
+            .take(limit as usize)
+        {
+            self.balances.insert(key.to_string(), *value as u64);
+            items_processed += 1;
+        }
+
+        MigrationProgress {
+            done: cursor + items_processed as u64 >= self.old_balances.len() as u64,
+            items_processed,
+        }
+    }
+}
+
+#[near_bindgen]
+impl ContractNew {
+    pub fn get_balance(&self, key: String) -> Option<u64> {
+        Self::require_migration_complete();
+
+        self.balances.get(&key).copied()
+    }
+}
@@ -0,0 +1,55 @@
+#![allow(missing_docs)]
+
+use near_sdk_contract_tools::{
+    owner::{Owner, OwnerExternal},
+    upgrade::PostUpgrade,
+    Owner,
+};
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    store::UnorderedMap,
+    PanicOnDefault,
+};
+pub fn main() {}
+
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault, Owner)]
+#[near_bindgen]
+pub struct ContractOld {
+    pub balances: UnorderedMap<u32, u32>,
+}
+
+#[near_bindgen]
+impl ContractOld {
+    #[init]
+    pub fn new() -> Self {
+        let mut contract = Self {
+            balances: UnorderedMap::new(b"b"),
+        };
+
+        Owner::init(&mut contract, &env::predecessor_account_id());
+        contract
+    }
+
+    pub fn seed(&mut self, count: u32) {
+        for i in 0..count {
+            self.balances.insert(i, i * 10);
+        }
+    }
+
+    pub fn get_balance(&self, key: u32) -> Option<u32> {
+        self.balances.get(&key).copied()
+    }
+}
+
+#[no_mangle]
+pub fn upgrade() {
+    near_sdk::env::setup_panic_hook();
+
+    ContractOld::require_owner();
+
+    unsafe {
+        near_sdk_contract_tools::upgrade::raw::upgrade(PostUpgrade::default());
+    }
+}
@@ -0,0 +1,109 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, BorshStorageKey,
+};
+use near_sdk_contract_tools::{
+    owner::Owner, pause::Pause, rbac::Rbac, standard::nep141::*, FungibleToken, Owner, Pause, Rbac,
+};
+
+#[derive(BorshSerialize, BorshStorageKey)]
+pub enum Role {
+    Minter,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractV1 {}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractV2 {
+    pub transfers: u64,
+}
+
+/// The components keep their state in their own storage slots, so the
+/// token, owner, pause state, and roles are unaffected by migrating between
+/// versions.
+#[derive(BorshSerialize, BorshDeserialize, FungibleToken, Owner, Pause, Rbac)]
+#[fungible_token(name = "Versioned Token", symbol = "VER", decimals = 18)]
+#[rbac(roles = "Role")]
+#[near_bindgen]
+pub enum Contract {
+    V1(ContractV1),
+    V2(ContractV2),
+}
+
+impl Default for Contract {
+    fn default() -> Self {
+        env::panic_str("Contract is not initialized")
+    }
+}
+
+impl Nep141Hook for Contract {
+    fn before_transfer(&mut self, _transfer: &Nep141Transfer) {
+        Self::require_unpaused();
+    }
+
+    fn after_transfer(&mut self, _transfer: &Nep141Transfer, _state: ()) {
+        if let Self::V2(ContractV2 { transfers }) = self {
+            *transfers += 1;
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        let mut contract = Self::V1(ContractV1 {});
+
+        let predecessor = env::predecessor_account_id();
+        Owner::init(&mut contract, &predecessor);
+        contract.add_role(predecessor, &Role::Minter);
+
+        contract
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+        }
+    }
+
+    /// Number of transfers since migrating to V2
+    pub fn transfers(&self) -> u64 {
+        match self {
+            Self::V1(_) => 0,
+            Self::V2(ContractV2 { transfers }) => *transfers,
+        }
+    }
+
+    pub fn migrate_to_v2(&mut self) {
+        Self::require_owner();
+
+        if let Self::V1(ContractV1 {}) = self {
+            *self = Self::V2(ContractV2 { transfers: 0 });
+        }
+    }
+
+    pub fn mint(&mut self, amount: U128) {
+        Self::require_role(&Role::Minter);
+        self.deposit_unchecked(&env::predecessor_account_id(), amount.into());
+    }
+
+    pub fn pause(&mut self) {
+        Self::require_owner();
+        Pause::pause(self);
+    }
+
+    pub fn unpause(&mut self) {
+        Self::require_owner();
+        Pause::unpause(self);
+    }
+}
@@ -0,0 +1,68 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::{U128, U64},
+    near_bindgen, AccountId, PanicOnDefault,
+};
+use near_sdk_contract_tools::{
+    owner::Owner,
+    vesting::{Vesting, VestingSchedule},
+    FungibleToken, Owner,
+};
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, Owner, FungibleToken)]
+#[fungible_token(name = "Vested Token", symbol = "VEST", decimals = 18, no_hooks)]
+#[near_bindgen]
+pub struct Contract {}
+
+impl Vesting for Contract {}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(treasury_id: AccountId) -> Self {
+        let mut contract = Self {};
+
+        Owner::init(&mut contract, &env::predecessor_account_id());
+        contract.set_treasury_id(&treasury_id);
+
+        contract
+    }
+
+    pub fn create_schedule(
+        &mut self,
+        beneficiary_id: AccountId,
+        start: U64,
+        cliff: U64,
+        duration: U64,
+        total: U128,
+    ) {
+        Self::require_owner();
+        self.create_vesting_schedule(
+            beneficiary_id,
+            VestingSchedule::new(start.0, cliff.0, duration.0, total.0),
+        );
+    }
+
+    pub fn revoke(&mut self, beneficiary_id: AccountId) -> U128 {
+        Self::require_owner();
+        self.revoke_vesting_schedule(&beneficiary_id).into()
+    }
+
+    pub fn claim(&mut self) -> U128 {
+        self.claim_vested(&env::predecessor_account_id()).into()
+    }
+
+    pub fn get_schedule(&self, beneficiary_id: AccountId) -> Option<VestingSchedule> {
+        Self::vesting_schedule(&beneficiary_id)
+    }
+
+    pub fn get_claimable(&self, beneficiary_id: AccountId) -> U128 {
+        Self::claimable(&beneficiary_id, env::block_timestamp()).into()
+    }
+}
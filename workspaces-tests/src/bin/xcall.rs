@@ -0,0 +1,82 @@
+#![allow(missing_docs)]
+
+// Ignore
+pub fn main() {}
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen, AccountId, Gas, PanicOnDefault, Promise,
+};
+use near_sdk_contract_tools::{
+    standard::nep141::ext_nep141_receiver,
+    xcall::{CallWithRollback, Rollback, XCall},
+};
+
+const RECEIVER_GAS: Gas = Gas(10_000_000_000_000);
+
+/// Releases a reservation if the call fails
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Unreserve {
+    pub amount: u128,
+}
+
+impl Rollback<Contract> for Unreserve {
+    type Output = bool;
+
+    fn commit(self, _contract: &mut Contract, _value: Vec<u8>) -> bool {
+        true
+    }
+
+    fn rollback(self, contract: &mut Contract) -> bool {
+        contract.reserved -= self.amount;
+        false
+    }
+}
+
+/// Optimistically reserves an amount before calling `ft_on_transfer` on a
+/// receiver, and releases the reservation if the call fails
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize)]
+#[near_bindgen]
+pub struct Contract {
+    reserved: u128,
+}
+
+impl XCall for Contract {
+    type Rollback = Unreserve;
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self { reserved: 0 }
+    }
+
+    pub fn reserve(&mut self, receiver_id: AccountId, amount: U128, msg: String) -> Promise {
+        self.reserved += amount.0;
+
+        let call = ext_nep141_receiver::ext(receiver_id)
+            .with_static_gas(RECEIVER_GAS)
+            .ft_on_transfer(env::predecessor_account_id(), amount, msg);
+
+        CallWithRollback::new(call)
+            .on_failure(Unreserve { amount: amount.0 })
+            .schedule::<Self>()
+    }
+
+    /// Whether the call succeeded
+    #[private]
+    pub fn xcall_resolve(&mut self, call_id: u32) -> bool {
+        self.resolve_call(call_id)
+    }
+
+    pub fn get_reserved(&self) -> U128 {
+        self.reserved.into()
+    }
+
+    pub fn get_pending_call(&self, call_id: u32) -> Option<U128> {
+        Self::pending_call(call_id).map(|rollback| rollback.amount.into())
+    }
+}
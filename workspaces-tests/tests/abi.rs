@@ -0,0 +1,91 @@
+#![cfg(feature = "abi")]
+
+//! Checks the ABI generated for the external methods of the components. Run
+//! with `cargo test --package workspaces-tests --features abi --test abi`.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    near_bindgen,
+    serde_json::Value,
+    PanicOnDefault,
+};
+use near_sdk_contract_tools::FungibleToken;
+
+#[derive(PanicOnDefault, BorshSerialize, BorshDeserialize, FungibleToken)]
+#[fungible_token(name = "My Fungible Token", symbol = "MYFT", decimals = 18, no_hooks)]
+#[near_bindgen]
+pub struct Contract {}
+
+// `#[near_bindgen]` exports the ABI of each impl block as a JSON-encoded
+// chunk from a function named after the first method of the block.
+#[allow(improper_ctypes)]
+extern "C" {
+    fn __near_abi_ft_transfer() -> (*const u8, usize);
+    fn __near_abi_ft_resolve_transfer() -> (*const u8, usize);
+    fn __near_abi_ft_metadata() -> (*const u8, usize);
+}
+
+fn chunk(f: unsafe extern "C" fn() -> (*const u8, usize)) -> Value {
+    let (ptr, len) = unsafe { f() };
+    near_sdk::serde_json::from_slice(unsafe { std::slice::from_raw_parts(ptr, len) }).unwrap()
+}
+
+fn function<'a>(chunk: &'a Value, name: &str) -> &'a Value {
+    chunk["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == name)
+        .unwrap_or_else(|| panic!("`{name}` is missing from the ABI"))
+}
+
+fn params(function: &Value) -> Vec<&str> {
+    function["params"]["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn ft_transfer() {
+    let chunk = chunk(__near_abi_ft_transfer);
+    let ft_transfer = function(&chunk, "ft_transfer");
+
+    assert!(ft_transfer["doc"]
+        .as_str()
+        .unwrap()
+        .contains("Transfers `amount` tokens from the caller to `receiver_id`"));
+    assert_eq!(params(ft_transfer), ["receiver_id", "amount", "memo"]);
+    assert_eq!(ft_transfer["kind"], "call");
+
+    for name in ["ft_transfer_call", "ft_total_supply", "ft_balance_of"] {
+        assert!(
+            function(&chunk, name)["doc"].is_string(),
+            "`{name}` is undocumented"
+        );
+    }
+}
+
+#[test]
+fn ft_resolve_transfer() {
+    let chunk = chunk(__near_abi_ft_resolve_transfer);
+    let ft_resolve_transfer = function(&chunk, "ft_resolve_transfer");
+
+    assert!(ft_resolve_transfer["doc"].is_string());
+    assert_eq!(
+        params(ft_resolve_transfer),
+        ["sender_id", "receiver_id", "amount"],
+    );
+}
+
+#[test]
+fn ft_metadata() {
+    let chunk = chunk(__near_abi_ft_metadata);
+    let ft_metadata = function(&chunk, "ft_metadata");
+
+    assert!(ft_metadata["doc"].is_string());
+    assert_eq!(ft_metadata["kind"], "view");
+    assert!(ft_metadata["result"]["type_schema"].is_object());
+}
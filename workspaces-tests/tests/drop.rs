@@ -0,0 +1,282 @@
+#![cfg(not(windows))]
+
+use near_sdk::{
+    json_types::U128,
+    serde_json::{self, json},
+    ONE_NEAR,
+};
+use near_sdk_contract_tools::drop::{
+    DropAsset, DropKeyStatus, DropRecord, DEFAULT_CLAIM_ALLOWANCE,
+};
+use near_sdk_contract_tools_testing::{call, deploy_and_init, make_accounts, view};
+use workspaces::{
+    network::Sandbox,
+    result::ExecutionFinalResult,
+    types::{KeyType, SecretKey},
+    Account, AccountId, Contract, Worker,
+};
+
+const DROP_WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/drop.wasm");
+
+const FT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token.wasm");
+
+/// Well within the allowance of the key of a drop
+const CLAIM_GAS: u64 = 50_000_000_000_000;
+
+struct Setup {
+    pub worker: Worker<Sandbox>,
+    pub drop: Contract,
+    pub ft: Contract,
+    /// Funds the drops
+    pub alice: Account,
+    /// Claims the drops
+    pub bob: Account,
+}
+
+/// Setup for individual tests: alice holds 1000 tokens
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+    let mut accounts = make_accounts(&worker, 2).await;
+    let bob = accounts.pop().unwrap();
+    let alice = accounts.pop().unwrap();
+
+    let drop = deploy_and_init(&worker, DROP_WASM, "new", json!({})).await;
+
+    let ft = deploy_and_init(&worker, FT_WASM, "new", json!({})).await;
+    call(&alice, ft.id(), "mint", json!({ "amount": "1000" })).await;
+
+    Setup {
+        worker,
+        drop,
+        ft,
+        alice,
+        bob,
+    }
+}
+
+fn public_key_string(secret_key: &SecretKey) -> String {
+    serde_json::from_value(json!(secret_key.public_key())).unwrap()
+}
+
+impl Setup {
+    async fn create_drop(&self, secret_key: &SecretKey, asset: DropAsset, amount: u128) {
+        let deposit = match asset {
+            DropAsset::Near => amount + DEFAULT_CLAIM_ALLOWANCE,
+            DropAsset::FungibleToken { .. } => DEFAULT_CLAIM_ALLOWANCE,
+        };
+
+        self.alice
+            .call(self.drop.id(), "create_drop")
+            .args_json(json!({
+                "public_key": public_key_string(secret_key),
+                "asset": asset,
+                "amount": U128(amount),
+            }))
+            .deposit(deposit)
+            .max_gas()
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    /// Claims the drop of `secret_key` for bob, signing with the key. `None`
+    /// if the transaction is rejected, e.g. because the key does not exist.
+    async fn claim(&self, secret_key: &SecretKey) -> Option<ExecutionFinalResult> {
+        self.claim_for(secret_key, self.bob.id()).await
+    }
+
+    async fn claim_for(
+        &self,
+        secret_key: &SecretKey,
+        account_id: &AccountId,
+    ) -> Option<ExecutionFinalResult> {
+        Account::from_secret_key(self.drop.id().clone(), secret_key.clone(), &self.worker)
+            .call(self.drop.id(), "claim")
+            .args_json(json!({ "account_id": account_id }))
+            .gas(CLAIM_GAS)
+            .transact()
+            .await
+            .ok()
+    }
+
+    async fn get_drop(&self, secret_key: &SecretKey) -> Option<DropRecord> {
+        view(
+            &self.drop,
+            "get_drop",
+            json!({ "public_key": public_key_string(secret_key) }),
+        )
+        .await
+    }
+
+    async fn ft_balance(&self, account_id: &AccountId) -> u128 {
+        view::<U128>(
+            &self.ft,
+            "ft_balance_of",
+            json!({ "account_id": account_id }),
+        )
+        .await
+        .into()
+    }
+}
+
+#[tokio::test]
+async fn near_drop() {
+    let s = setup().await;
+    let secret_key = SecretKey::from_random(KeyType::ED25519);
+
+    s.create_drop(&secret_key, DropAsset::Near, ONE_NEAR).await;
+
+    let drop = s.get_drop(&secret_key).await.unwrap();
+    assert_eq!(drop.amount, U128(ONE_NEAR));
+    assert!(drop.funded);
+    assert_eq!(drop.key_status, DropKeyStatus::Added);
+    assert_eq!(&drop.funder_id.to_string(), s.alice.id().as_str());
+
+    let bob_before = s.bob.view_account().await.unwrap().balance;
+    s.claim(&secret_key).await.unwrap().into_result().unwrap();
+    let bob_after = s.bob.view_account().await.unwrap().balance;
+
+    assert_eq!(bob_after - bob_before, ONE_NEAR);
+    assert_eq!(s.get_drop(&secret_key).await, None);
+
+    // The key was deleted with the drop
+    assert!(s.claim(&secret_key).await.is_none());
+    assert_eq!(s.bob.view_account().await.unwrap().balance, bob_after);
+}
+
+#[tokio::test]
+async fn ft_drop() {
+    let s = setup().await;
+    let secret_key = SecretKey::from_random(KeyType::ED25519);
+    let asset = DropAsset::FungibleToken {
+        contract_id: s.ft.id().as_str().parse().unwrap(),
+    };
+
+    s.create_drop(&secret_key, asset, 100).await;
+
+    // Cannot be claimed before it is funded
+    let result = s.claim(&secret_key).await.unwrap();
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Drop is not funded"));
+
+    s.alice
+        .call(s.ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": s.drop.id(),
+            "amount": "100",
+            "msg": json!({ "public_key": public_key_string(&secret_key) }).to_string(),
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(s.ft_balance(s.alice.id()).await, 900);
+    assert!(s.get_drop(&secret_key).await.unwrap().funded);
+
+    s.claim(&secret_key).await.unwrap().into_result().unwrap();
+
+    assert_eq!(s.ft_balance(s.bob.id()).await, 100);
+    assert_eq!(s.ft_balance(s.drop.id()).await, 0);
+    assert_eq!(s.get_drop(&secret_key).await, None);
+}
+
+#[tokio::test]
+async fn existing_key_not_deleted() {
+    let s = setup().await;
+    // The full access key of the contract account
+    let secret_key = s.drop.as_account().secret_key().clone();
+
+    s.create_drop(&secret_key, DropAsset::Near, ONE_NEAR).await;
+
+    let drop = s.get_drop(&secret_key).await.unwrap();
+    assert_eq!(drop.key_status, DropKeyStatus::Failed);
+
+    let alice_before = s.alice.view_account().await.unwrap().balance;
+    s.bob
+        .call(s.drop.id(), "refund_drop")
+        .args_json(json!({ "public_key": public_key_string(&secret_key) }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+    let alice_after = s.alice.view_account().await.unwrap().balance;
+
+    assert_eq!(
+        alice_after - alice_before,
+        ONE_NEAR + DEFAULT_CLAIM_ALLOWANCE
+    );
+    assert_eq!(s.get_drop(&secret_key).await, None);
+
+    // The key is still usable
+    s.drop
+        .as_account()
+        .call(s.drop.id(), "get_drop")
+        .args_json(json!({ "public_key": public_key_string(&secret_key) }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn failed_claim_claimed_again() {
+    let s = setup().await;
+    let secret_key = SecretKey::from_random(KeyType::ED25519);
+    let missing: AccountId = format!("missing.{}", s.drop.id()).parse().unwrap();
+
+    s.create_drop(&secret_key, DropAsset::Near, ONE_NEAR).await;
+
+    // The account does not exist, so the transfer fails
+    s.claim_for(&secret_key, &missing).await.unwrap();
+    let drop = s.get_drop(&secret_key).await.unwrap();
+    assert_eq!(drop.transfer, None);
+    assert_eq!(drop.key_status, DropKeyStatus::Added);
+
+    // The key was kept with the drop
+    let bob_before = s.bob.view_account().await.unwrap().balance;
+    s.claim(&secret_key).await.unwrap().into_result().unwrap();
+    let bob_after = s.bob.view_account().await.unwrap().balance;
+
+    assert_eq!(bob_after - bob_before, ONE_NEAR);
+    assert_eq!(s.get_drop(&secret_key).await, None);
+}
+
+#[tokio::test]
+async fn unfunded_refunded_by_funder() {
+    let s = setup().await;
+    let secret_key = SecretKey::from_random(KeyType::ED25519);
+    let asset = DropAsset::FungibleToken {
+        contract_id: s.ft.id().as_str().parse().unwrap(),
+    };
+
+    s.create_drop(&secret_key, asset, 100).await;
+
+    // Only the funder may refund a drop that has not expired
+    let result = s
+        .bob
+        .call(s.drop.id(), "refund_drop")
+        .args_json(json!({ "public_key": public_key_string(&secret_key) }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Drop has not expired"));
+
+    s.alice
+        .call(s.drop.id(), "refund_drop")
+        .args_json(json!({ "public_key": public_key_string(&secret_key) }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(s.get_drop(&secret_key).await, None);
+    // The key was deleted with the drop
+    assert!(s.claim(&secret_key).await.is_none());
+}
@@ -0,0 +1,214 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json, ONE_NEAR};
+use near_sdk_contract_tools::escrow::{EscrowAsset, EscrowRecord, EscrowState};
+use near_sdk_contract_tools_testing::{call, deploy_and_init, make_accounts, view};
+use workspaces::{result::ExecutionFinalResult, Account, AccountId, Contract};
+
+const ESCROW_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/escrow.wasm");
+
+const FT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token.wasm");
+
+struct Setup {
+    pub escrow: Contract,
+    pub ft: Contract,
+    /// Owner of the escrow contract, i.e. the arbiter
+    pub arbiter: Account,
+    pub alice: Account,
+    pub bob: Account,
+}
+
+/// Setup for individual tests: alice holds 1000 tokens
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+    let mut accounts = make_accounts(&worker, 3).await;
+    let bob = accounts.pop().unwrap();
+    let alice = accounts.pop().unwrap();
+    let arbiter = accounts.pop().unwrap();
+
+    let escrow = worker.dev_deploy(ESCROW_WASM).await.unwrap();
+    arbiter
+        .call(escrow.id(), "new")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let ft = deploy_and_init(&worker, FT_WASM, "new", json!({})).await;
+    call(&alice, ft.id(), "mint", json!({ "amount": "1000" })).await;
+
+    Setup {
+        escrow,
+        ft,
+        arbiter,
+        alice,
+        bob,
+    }
+}
+
+async fn settle(s: &Setup, account: &Account, method: &str, id: &str) -> ExecutionFinalResult {
+    account
+        .call(s.escrow.id(), method)
+        .args_json(json!({ "id": id }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+}
+
+async fn get_escrow(s: &Setup, id: &str) -> Option<EscrowRecord> {
+    view(&s.escrow, "get_escrow", json!({ "id": id })).await
+}
+
+async fn ft_balance(s: &Setup, account_id: &AccountId) -> u128 {
+    view::<U128>(&s.ft, "ft_balance_of", json!({ "account_id": account_id }))
+        .await
+        .into()
+}
+
+#[tokio::test]
+async fn near_release() {
+    let s = setup().await;
+
+    s.alice
+        .call(s.escrow.id(), "lock")
+        .args_json(json!({ "id": "order-1", "beneficiary_id": s.bob.id() }))
+        .deposit(ONE_NEAR)
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let escrow = get_escrow(&s, "order-1").await.unwrap();
+    assert_eq!(escrow.asset, EscrowAsset::Near);
+    assert_eq!(escrow.amount, U128(ONE_NEAR));
+    assert_eq!(&escrow.depositor_id.to_string(), s.alice.id().as_str());
+
+    // Only the arbiter may settle
+    let result = settle(&s, &s.alice, "release", "order-1").await;
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    let bob_before = s.bob.view_account().await.unwrap().balance;
+    settle(&s, &s.arbiter, "release", "order-1")
+        .await
+        .into_result()
+        .unwrap();
+    let bob_after = s.bob.view_account().await.unwrap().balance;
+
+    assert_eq!(bob_after - bob_before, ONE_NEAR);
+    assert_eq!(
+        get_escrow(&s, "order-1").await.unwrap().state,
+        EscrowState::Released,
+    );
+
+    // Double release is rejected
+    let result = settle(&s, &s.arbiter, "release", "order-1").await;
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Escrow already settled"));
+    assert_eq!(s.bob.view_account().await.unwrap().balance, bob_after);
+}
+
+#[tokio::test]
+async fn failed_release_locked_again() {
+    let s = setup().await;
+    let missing: AccountId = format!("missing.{}", s.escrow.id()).parse().unwrap();
+
+    s.alice
+        .call(s.escrow.id(), "lock")
+        .args_json(json!({ "id": "order-3", "beneficiary_id": missing }))
+        .deposit(ONE_NEAR)
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The beneficiary does not exist, so the transfer fails
+    settle(&s, &s.arbiter, "release", "order-3").await;
+    assert_eq!(
+        get_escrow(&s, "order-3").await.unwrap().state,
+        EscrowState::Locked,
+    );
+
+    let alice_before = s.alice.view_account().await.unwrap().balance;
+    settle(&s, &s.arbiter, "refund", "order-3")
+        .await
+        .into_result()
+        .unwrap();
+    let alice_after = s.alice.view_account().await.unwrap().balance;
+
+    assert_eq!(alice_after - alice_before, ONE_NEAR);
+    assert_eq!(
+        get_escrow(&s, "order-3").await.unwrap().state,
+        EscrowState::Refunded,
+    );
+}
+
+#[tokio::test]
+async fn ft_refund() {
+    let s = setup().await;
+
+    s.alice
+        .call(s.ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": s.escrow.id(),
+            "amount": "100",
+            "msg": json!({ "id": "order-2", "beneficiary_id": s.bob.id() }).to_string(),
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(ft_balance(&s, s.alice.id()).await, 900);
+    assert_eq!(ft_balance(&s, s.escrow.id()).await, 100);
+
+    let escrow = get_escrow(&s, "order-2").await.unwrap();
+    assert_eq!(
+        escrow.asset,
+        EscrowAsset::FungibleToken {
+            contract_id: s.ft.id().as_str().parse().unwrap(),
+        },
+    );
+
+    settle(&s, &s.arbiter, "refund", "order-2")
+        .await
+        .into_result()
+        .unwrap();
+
+    assert_eq!(ft_balance(&s, s.alice.id()).await, 1000);
+    assert_eq!(ft_balance(&s, s.escrow.id()).await, 0);
+    assert_eq!(
+        get_escrow(&s, "order-2").await.unwrap().state,
+        EscrowState::Refunded,
+    );
+
+    // A refunded escrow cannot be released
+    let result = settle(&s, &s.arbiter, "release", "order-2").await;
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Escrow already settled"));
+    assert_eq!(ft_balance(&s, s.bob.id()).await, 0);
+}
+
+#[tokio::test]
+async fn ft_invalid_msg_refunds_transfer() {
+    let s = setup().await;
+
+    s.alice
+        .call(s.ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": s.escrow.id(),
+            "amount": "100",
+            "msg": "order-3",
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(ft_balance(&s, s.alice.id()).await, 1000);
+    assert_eq!(get_escrow(&s, "order-3").await, None);
+}
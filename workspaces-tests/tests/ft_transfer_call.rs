@@ -0,0 +1,132 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json};
+use workspaces::{result::ExecutionFinalResult, Account, AccountId, Contract};
+
+const FT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token.wasm");
+
+const RECEIVER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/mock_ft_receiver.wasm");
+
+async fn balance(contract: &Contract, account: &AccountId) -> u128 {
+    contract
+        .view(
+            "ft_balance_of",
+            json!({ "account_id": account })
+                .to_string()
+                .as_bytes()
+                .to_vec(),
+        )
+        .await
+        .unwrap()
+        .json::<U128>()
+        .map(|i| u128::from(i))
+        .unwrap()
+}
+
+struct Setup {
+    pub ft: Contract,
+    pub receiver: Contract,
+    pub alice: Account,
+}
+
+/// Setup for individual tests: alice holds 1000 tokens
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let ft = worker.dev_deploy(&FT_WASM.to_vec()).await.unwrap();
+    ft.call("new").transact().await.unwrap().unwrap();
+
+    let receiver = worker.dev_deploy(&RECEIVER_WASM.to_vec()).await.unwrap();
+
+    let alice = worker.dev_create_account().await.unwrap();
+    alice
+        .call(ft.id(), "mint")
+        .args_json(json!({ "amount": "1000" }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    Setup {
+        ft,
+        receiver,
+        alice,
+    }
+}
+
+async fn transfer_call(s: &Setup, amount: u128, msg: &str) -> ExecutionFinalResult {
+    s.alice
+        .call(s.ft.id(), "ft_transfer_call")
+        .deposit(1)
+        .max_gas()
+        .args_json(json!({
+            "receiver_id": s.receiver.id(),
+            "amount": U128(amount),
+            "msg": msg,
+        }))
+        .transact()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn transfer_call_refund() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "use:30").await;
+
+    // 30 unused tokens are refunded
+    assert_eq!(result.json::<U128>().unwrap(), U128(70));
+    assert_eq!(balance(&s.ft, s.alice.id()).await, 930);
+    assert_eq!(balance(&s.ft, s.receiver.id()).await, 70);
+}
+
+#[tokio::test]
+async fn transfer_call_unused_more_than_amount() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "use:500").await;
+
+    // At most `amount` is refunded
+    assert_eq!(result.json::<U128>().unwrap(), U128(0));
+    assert_eq!(balance(&s.ft, s.alice.id()).await, 1000);
+    assert_eq!(balance(&s.ft, s.receiver.id()).await, 0);
+}
+
+#[tokio::test]
+async fn transfer_call_full_use() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "all").await;
+
+    assert_eq!(result.json::<U128>().unwrap(), U128(100));
+    assert_eq!(balance(&s.ft, s.alice.id()).await, 900);
+    assert_eq!(balance(&s.ft, s.receiver.id()).await, 100);
+}
+
+#[tokio::test]
+async fn transfer_call_receiver_panics() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "panic").await;
+
+    // The transfer is resolved successfully, with a full refund
+    assert!(!result.receipt_failures().is_empty());
+    assert_eq!(result.json::<U128>().unwrap(), U128(0));
+    assert_eq!(balance(&s.ft, s.alice.id()).await, 1000);
+    assert_eq!(balance(&s.ft, s.receiver.id()).await, 0);
+}
+
+#[tokio::test]
+async fn transfer_call_slow_receiver() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "slow").await;
+
+    // The gas reserved for the resolution suffices
+    assert_eq!(result.json::<U128>().unwrap(), U128(100));
+    assert_eq!(balance(&s.ft, s.alice.id()).await, 900);
+    assert_eq!(balance(&s.ft, s.receiver.id()).await, 100);
+}
@@ -167,3 +167,48 @@ async fn transfer_no_deposit() {
         .unwrap()
         .unwrap();
 }
+
+#[tokio::test]
+async fn mint_batch_gas() {
+    let Setup { contract, accounts } = setup(6).await;
+    let alice = &accounts[0];
+
+    let mints = |accounts: &[Account]| {
+        accounts
+            .iter()
+            .map(|account| json!([account.id(), "100"]))
+            .collect::<Vec<_>>()
+    };
+
+    let unbatched = alice
+        .call(contract.id(), "mint_unbatched")
+        .args_json(json!({ "mints": mints(&accounts[..3]) }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let batched = alice
+        .call(contract.id(), "mint_batch")
+        .args_json(json!({ "mints": mints(&accounts[3..]) }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    for account in accounts.iter() {
+        assert_eq!(balance(&contract, account.id()).await, 100);
+    }
+
+    let total_supply = contract
+        .view("ft_total_supply", vec![])
+        .await
+        .unwrap()
+        .json::<U128>()
+        .unwrap();
+
+    assert_eq!(total_supply, U128(600));
+
+    // Total supply is only read and written once
+    assert!(batched.total_gas_burnt < unbatched.total_gas_burnt);
+}
@@ -0,0 +1,143 @@
+#![cfg(not(windows))]
+
+use near_sdk::{env::STORAGE_PRICE_PER_BYTE, json_types::U128, serde_json::json, ONE_NEAR};
+use workspaces::{Account, AccountId, Contract};
+
+const WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token_storage.wasm");
+
+struct Setup {
+    pub contract: Contract,
+    pub accounts: Vec<Account>,
+}
+
+async fn setup(num_accounts: usize) -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    let mut accounts = vec![];
+    for _ in 0..num_accounts {
+        accounts.push(worker.dev_create_account().await.unwrap());
+    }
+
+    Setup { contract, accounts }
+}
+
+async fn storage_usage(contract: &Contract) -> u128 {
+    contract.view_account().await.unwrap().storage_usage as u128
+}
+
+async fn storage_balance(contract: &Contract, account: &AccountId) -> u128 {
+    contract
+        .view(
+            "storage_balance_of",
+            json!({ "account_id": account }).to_string().into_bytes(),
+        )
+        .await
+        .unwrap()
+        .json::<U128>()
+        .unwrap()
+        .0
+}
+
+#[tokio::test]
+async fn transfer_debits_storage_balance() {
+    let Setup { contract, accounts } = setup(3).await;
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+
+    alice
+        .call(contract.id(), "mint")
+        .args_json(json!({ "amount": "100" }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    alice
+        .call(contract.id(), "storage_deposit")
+        .deposit(ONE_NEAR)
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let transfer = |receiver: &AccountId| {
+        alice
+            .call(contract.id(), "ft_transfer")
+            .args_json(json!({ "receiver_id": receiver, "amount": "10" }))
+            .deposit(1)
+            .transact()
+    };
+
+    // Creating bob's balance record is charged to alice
+    let usage_before = storage_usage(&contract).await;
+    transfer(bob.id()).await.unwrap().unwrap();
+    let added_bytes = storage_usage(&contract).await - usage_before;
+
+    assert!(added_bytes > 0);
+    assert_eq!(
+        storage_balance(&contract, alice.id()).await,
+        ONE_NEAR - added_bytes * STORAGE_PRICE_PER_BYTE,
+    );
+
+    // Updating an existing record is free
+    transfer(bob.id()).await.unwrap().unwrap();
+
+    assert_eq!(
+        storage_balance(&contract, alice.id()).await,
+        ONE_NEAR - added_bytes * STORAGE_PRICE_PER_BYTE,
+    );
+
+    // Bob has no storage balance to pay for charlie's record
+    let result = bob
+        .call(contract.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": charlie.id(), "amount": "5" }))
+        .deposit(1)
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(
+        format!("{:?}", result.into_result().unwrap_err()).contains("Insufficient storage balance")
+    );
+}
+
+#[tokio::test]
+async fn attached_deposit_refunded() {
+    let Setup { contract, accounts } = setup(1).await;
+    let alice = &accounts[0];
+
+    let usage_before = storage_usage(&contract).await;
+
+    let cost = alice
+        .call(contract.id(), "mint_paid")
+        .args_json(json!({ "amount": "100" }))
+        .deposit(ONE_NEAR)
+        .transact()
+        .await
+        .unwrap()
+        .json::<U128>()
+        .unwrap()
+        .0;
+
+    let added_bytes = storage_usage(&contract).await - usage_before;
+
+    assert!(added_bytes > 0);
+    assert_eq!(cost, added_bytes * STORAGE_PRICE_PER_BYTE);
+
+    let result = alice
+        .call(contract.id(), "mint_paid")
+        .args_json(json!({ "amount": "100" }))
+        .deposit(STORAGE_PRICE_PER_BYTE)
+        .transact()
+        .await
+        .unwrap();
+
+    // Alice's record already exists, so nothing is charged, and the whole
+    // deposit is refunded
+    assert_eq!(result.json::<U128>().unwrap().0, 0);
+}
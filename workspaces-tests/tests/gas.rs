@@ -0,0 +1,145 @@
+#![cfg(not(windows))]
+
+//! Guards against regressions in the gas burnt by core component
+//! operations. The budgets are in `gas-budgets.toml`; updating them is
+//! deliberate: `UPDATE_GAS_BUDGETS=1 cargo make test gas`.
+
+use near_sdk::{json_types::U128, serde_json::json};
+use near_sdk_contract_tools_testing::{
+    call, call_json, deploy_and_init, gas::GasReport, make_accounts,
+};
+use workspaces::{result::ExecutionSuccess, Account, AccountId};
+
+const FT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token.wasm");
+
+const RECEIVER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/mock_ft_receiver.wasm");
+
+const RBAC_WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/rbac.wasm");
+
+const MULTISIG_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/simple_multisig.wasm");
+
+const PAUSE_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/versioned.wasm");
+
+const BUDGETS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/gas-budgets.toml");
+
+const TOLERANCE_PERCENT: u64 = 5;
+
+async fn call_with_deposit(
+    account: &Account,
+    contract_id: &AccountId,
+    method: &str,
+    args: near_sdk::serde_json::Value,
+) -> ExecutionSuccess {
+    account
+        .call(contract_id, method)
+        .args_json(args)
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn gas_budgets() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let accounts = make_accounts(&worker, 2).await;
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let mut report = GasReport::load(BUDGETS, TOLERANCE_PERCENT);
+
+    // Fungible token
+    let ft = deploy_and_init(&worker, FT_WASM, "new", json!({})).await;
+    let receiver = worker.dev_deploy(RECEIVER_WASM).await.unwrap();
+
+    call(alice, ft.id(), "mint", json!({ "amount": "1000" })).await;
+
+    let result = call_with_deposit(
+        alice,
+        ft.id(),
+        "ft_transfer",
+        json!({ "receiver_id": bob.id(), "amount": U128(10) }),
+    )
+    .await;
+    report.record("ft_transfer", &result);
+
+    // Including `ft_on_transfer` and `ft_resolve_transfer`, with a refund
+    let result = call_with_deposit(
+        alice,
+        ft.id(),
+        "ft_transfer_call",
+        json!({ "receiver_id": receiver.id(), "amount": U128(10), "msg": "use:5" }),
+    )
+    .await;
+    report.record("ft_transfer_call", &result);
+
+    // Rbac
+    let rbac = deploy_and_init(&worker, RBAC_WASM, "new", json!({})).await;
+
+    let result = call(alice, rbac.id(), "acquire_role", json!({ "role": "a" })).await;
+    report.record("add_role", &result);
+
+    // Simple multisig, with an approval threshold of 2
+    let multisig = deploy_and_init(&worker, MULTISIG_WASM, "new", json!({})).await;
+
+    call(
+        alice,
+        multisig.id(),
+        "obtain_multisig_permission",
+        json!({}),
+    )
+    .await;
+    call(bob, multisig.id(), "obtain_multisig_permission", json!({})).await;
+
+    let request_id: u32 = call_json(
+        alice,
+        multisig.id(),
+        "request",
+        json!({ "action": "hello" }),
+    )
+    .await;
+
+    call(
+        alice,
+        multisig.id(),
+        "approve",
+        json!({ "request_id": request_id }),
+    )
+    .await;
+
+    let result = call(
+        bob,
+        multisig.id(),
+        "approve",
+        json!({ "request_id": request_id }),
+    )
+    .await;
+    report.record("approve_request", &result);
+
+    let result = call(
+        alice,
+        multisig.id(),
+        "execute",
+        json!({ "request_id": request_id }),
+    )
+    .await;
+    report.record("execute_request", &result);
+
+    // Pause, by the owner (the contract itself)
+    let pausable = deploy_and_init(&worker, PAUSE_WASM, "new", json!({})).await;
+    let owner = pausable.as_account();
+
+    let result = call(owner, pausable.id(), "pause", json!({})).await;
+    report.record("pause", &result);
+
+    let result = call(owner, pausable.id(), "unpause", json!({})).await;
+    report.record("unpause", &result);
+
+    report.check();
+}
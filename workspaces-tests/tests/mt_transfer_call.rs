@@ -0,0 +1,161 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json};
+use near_sdk_contract_tools_testing::{call, deploy_and_init, make_accounts, view};
+use workspaces::{result::ExecutionFinalResult, Account, AccountId, Contract};
+
+const MT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/multi_token.wasm");
+
+const RECEIVER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/mock_mt_receiver.wasm");
+
+struct Setup {
+    pub mt: Contract,
+    pub receiver: Contract,
+    pub alice: Account,
+}
+
+/// Setup for individual tests: alice holds 1000 gold and 1 sword
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let mt = deploy_and_init(&worker, MT_WASM, "new", json!({})).await;
+    let receiver = worker.dev_deploy(RECEIVER_WASM).await.unwrap();
+    let alice = make_accounts(&worker, 1).await.remove(0);
+
+    call(
+        &alice,
+        mt.id(),
+        "mint",
+        json!({ "token_ids": ["gold", "sword"], "amounts": ["1000", "1"] }),
+    )
+    .await;
+
+    Setup {
+        mt,
+        receiver,
+        alice,
+    }
+}
+
+/// Balances of gold and sword
+async fn balances(s: &Setup, account_id: &AccountId) -> Vec<u128> {
+    view::<Vec<U128>>(
+        &s.mt,
+        "mt_batch_balance_of",
+        json!({ "account_id": account_id, "token_ids": ["gold", "sword"] }),
+    )
+    .await
+    .into_iter()
+    .map(u128::from)
+    .collect()
+}
+
+async fn transfer_call(s: &Setup, amount: u128, msg: &str) -> ExecutionFinalResult {
+    s.alice
+        .call(s.mt.id(), "mt_transfer_call")
+        .deposit(1)
+        .max_gas()
+        .args_json(json!({
+            "receiver_id": s.receiver.id(),
+            "token_id": "gold",
+            "amount": U128(amount),
+            "msg": msg,
+        }))
+        .transact()
+        .await
+        .unwrap()
+}
+
+/// Transfers 100 gold and 1 sword
+async fn batch_transfer_call(s: &Setup, msg: &str) -> ExecutionFinalResult {
+    s.alice
+        .call(s.mt.id(), "mt_batch_transfer_call")
+        .deposit(1)
+        .max_gas()
+        .args_json(json!({
+            "receiver_id": s.receiver.id(),
+            "token_ids": ["gold", "sword"],
+            "amounts": ["100", "1"],
+            "msg": msg,
+        }))
+        .transact()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn transfer_call_refund() {
+    let s = setup().await;
+
+    let result = transfer_call(&s, 100, "use:30").await;
+
+    // 30 unused gold is refunded
+    assert_eq!(result.json::<Vec<U128>>().unwrap(), vec![U128(70)]);
+    assert_eq!(balances(&s, s.alice.id()).await, vec![930, 1]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![70, 0]);
+}
+
+#[tokio::test]
+async fn batch_transfer_call_refund() {
+    let s = setup().await;
+
+    let result = batch_transfer_call(&s, "use:1").await;
+
+    // 1 of each token is refunded
+    assert_eq!(result.json::<Vec<U128>>().unwrap(), vec![U128(99), U128(0)],);
+    assert_eq!(balances(&s, s.alice.id()).await, vec![901, 1]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![99, 0]);
+}
+
+#[tokio::test]
+async fn batch_transfer_call_unused_more_than_amount() {
+    let s = setup().await;
+
+    let result = batch_transfer_call(&s, "use:500").await;
+
+    // At most the amount of each token is refunded
+    assert_eq!(result.json::<Vec<U128>>().unwrap(), vec![U128(0), U128(0)]);
+    assert_eq!(balances(&s, s.alice.id()).await, vec![1000, 1]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![0, 0]);
+}
+
+#[tokio::test]
+async fn batch_transfer_call_full_use() {
+    let s = setup().await;
+
+    let result = batch_transfer_call(&s, "all").await;
+
+    assert_eq!(
+        result.json::<Vec<U128>>().unwrap(),
+        vec![U128(100), U128(1)],
+    );
+    assert_eq!(balances(&s, s.alice.id()).await, vec![900, 0]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![100, 1]);
+}
+
+#[tokio::test]
+async fn batch_transfer_call_receiver_panics() {
+    let s = setup().await;
+
+    let result = batch_transfer_call(&s, "panic").await;
+
+    // The transfer is resolved successfully, with a full refund
+    assert!(!result.receipt_failures().is_empty());
+    assert_eq!(result.json::<Vec<U128>>().unwrap(), vec![U128(0), U128(0)]);
+    assert_eq!(balances(&s, s.alice.id()).await, vec![1000, 1]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![0, 0]);
+}
+
+#[tokio::test]
+async fn batch_transfer_call_wrong_number_of_amounts() {
+    let s = setup().await;
+
+    let result = batch_transfer_call(&s, "short").await;
+
+    // Full refund
+    assert_eq!(result.json::<Vec<U128>>().unwrap(), vec![U128(0), U128(0)]);
+    assert_eq!(balances(&s, s.alice.id()).await, vec![1000, 1]);
+    assert_eq!(balances(&s, s.receiver.id()).await, vec![0, 0]);
+}
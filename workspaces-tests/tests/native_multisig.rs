@@ -1,8 +1,15 @@
 #![cfg(not(windows))]
 
-use near_sdk::{serde_json::json, Gas};
+use near_sdk::{
+    serde_json::{self, json},
+    Gas,
+};
 use near_sdk_contract_tools::approval::native_transaction_action::PromiseAction;
-use workspaces::{network::Sandbox, Account, Contract, Worker};
+use workspaces::{
+    network::Sandbox,
+    types::{KeyType, SecretKey},
+    Account, AccountId, Contract, Worker,
+};
 
 const WASM: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/native_multisig.wasm");
@@ -302,3 +309,201 @@ async fn external_xcc() {
 
     assert_eq!(calls_after, 1);
 }
+
+#[tokio::test]
+async fn rotate_key() {
+    let Setup {
+        worker,
+        contract,
+        accounts,
+    } = setup_roles(2).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let public_key_string = |public_key: workspaces::types::PublicKey| {
+        serde_json::from_value::<String>(json!(public_key)).unwrap()
+    };
+
+    let old_secret_key = contract.as_account().secret_key().clone();
+    let new_secret_key = SecretKey::from_random(KeyType::ED25519);
+
+    let actions = vec![
+        PromiseAction::AddFullAccessKey {
+            public_key: public_key_string(new_secret_key.public_key()),
+            nonce: None,
+        },
+        PromiseAction::DeleteKey {
+            public_key: public_key_string(old_secret_key.public_key()),
+        },
+    ];
+
+    let request_id = alice
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "receiver_id": contract.id(),
+            "actions": actions,
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    alice
+        .call(contract.id(), "approve")
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    bob.call(contract.id(), "approve")
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    alice
+        .call(contract.id(), "execute")
+        .max_gas()
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    // New key can sign transactions for the contract account
+    Account::from_secret_key(contract.id().clone(), new_secret_key, &worker)
+        .call(contract.id(), "obtain_multisig_permission")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Old key has been removed
+    assert!(
+        Account::from_secret_key(contract.id().clone(), old_secret_key, &worker)
+            .call(contract.id(), "obtain_multisig_permission")
+            .transact()
+            .await
+            .is_err()
+    );
+}
+
+async fn approve_and_execute(contract: &Contract, accounts: &[Account], request_id: u32) {
+    for account in accounts {
+        account
+            .call(contract.id(), "approve")
+            .args_json(json!({ "request_id": request_id }))
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    accounts[0]
+        .call(contract.id(), "execute")
+        .max_gas()
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn provision_sub_account() {
+    let Setup {
+        worker,
+        contract,
+        accounts,
+    } = setup_roles(2).await;
+
+    let alice = &accounts[0];
+
+    let sub_account_id: AccountId = format!("sub.{}", contract.id()).parse().unwrap();
+    // Smallest valid WASM module
+    let code = b"\0asm\x01\0\0\0".to_vec();
+
+    let request_id = alice
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "receiver_id": sub_account_id,
+            "actions": [
+                PromiseAction::CreateAccount,
+                PromiseAction::Transfer {
+                    amount: (near_sdk::ONE_NEAR * 5).into(),
+                },
+                PromiseAction::DeployContract { code: code.clone() },
+            ],
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    approve_and_execute(&contract, &accounts[..2], request_id).await;
+
+    let sub_account = worker.view_account(&sub_account_id).await.unwrap();
+    assert_eq!(sub_account.balance, near_sdk::ONE_NEAR * 5);
+    assert_eq!(worker.view_code(&sub_account_id).await.unwrap(), code);
+}
+
+#[tokio::test]
+#[should_panic = "not a direct sub-account"]
+async fn create_non_sub_account() {
+    let Setup {
+        contract, accounts, ..
+    } = setup_roles(1).await;
+
+    accounts[0]
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "receiver_id": "sub.other.test.near",
+            "actions": [PromiseAction::CreateAccount],
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn delete_account() {
+    let Setup {
+        worker,
+        contract,
+        accounts,
+    } = setup_roles(2).await;
+
+    let alice = &accounts[0];
+
+    // The protocol only allows an account to delete itself, so the
+    // (disposable) multisig account decommissions itself
+    let request_id = alice
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "receiver_id": contract.id(),
+            "actions": [PromiseAction::DeleteAccount {
+                beneficiary_id: alice.id().clone(),
+            }],
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    let balance_before = alice.view_account().await.unwrap().balance;
+
+    approve_and_execute(&contract, &accounts[..2], request_id).await;
+
+    assert!(worker.view_account(contract.id()).await.is_err());
+
+    // alice paid for gas, but received the remaining balance
+    let balance_after = alice.view_account().await.unwrap().balance;
+    assert!(balance_after > balance_before);
+}
@@ -0,0 +1,111 @@
+#![cfg(not(windows))]
+
+use near_sdk::serde_json::json;
+use near_sdk_contract_tools::approval::native_transaction_action::{
+    NativeTransactionAction, PromiseAction,
+};
+use workspaces::{Account, Contract};
+
+const WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/native_multisig_batch.wasm");
+
+struct Setup {
+    pub contract: Contract,
+    pub accounts: Vec<Account>,
+}
+
+/// Setup for individual tests
+async fn setup(num_accounts: usize) -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    // Initialize contract
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    // Initialize user accounts
+    let mut accounts = vec![];
+    for _ in 0..num_accounts {
+        let account = worker.dev_create_account().await.unwrap();
+        account
+            .call(contract.id(), "obtain_multisig_permission")
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+        accounts.push(account);
+    }
+
+    Setup { contract, accounts }
+}
+
+#[tokio::test]
+async fn two_receiver_payout() {
+    let Setup { contract, accounts } = setup(2).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let transfer = |account: &Account, amount: u128| NativeTransactionAction {
+        receiver_id: account.id().as_str().parse().unwrap(),
+        actions: vec![PromiseAction::Transfer {
+            amount: amount.into(),
+        }],
+    };
+
+    let request_id = alice
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "transactions": [
+                transfer(alice, near_sdk::ONE_NEAR * 10),
+                transfer(bob, near_sdk::ONE_NEAR * 5),
+            ],
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    for account in [alice, bob] {
+        account
+            .call(contract.id(), "approve")
+            .args_json(json!({ "request_id": request_id }))
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    let alice_before = alice.view_account().await.unwrap().balance;
+    let bob_before = bob.view_account().await.unwrap().balance;
+
+    // bob executes, so alice's balance changes only by the transfer
+    bob.call(contract.id(), "execute")
+        .max_gas()
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let alice_after = alice.view_account().await.unwrap().balance;
+    let bob_after = bob.view_account().await.unwrap().balance;
+
+    assert_eq!(alice_after - alice_before, near_sdk::ONE_NEAR * 10);
+    // bob paid for gas
+    assert!(bob_after - bob_before > near_sdk::ONE_NEAR * 4);
+}
+
+#[tokio::test]
+#[should_panic = "Batch is empty"]
+async fn empty_batch() {
+    let Setup { contract, accounts } = setup(1).await;
+
+    accounts[0]
+        .call(contract.id(), "request")
+        .args_json(json!({ "transactions": [] }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,97 @@
+#![cfg(not(windows))]
+
+use near_sdk::{serde_json::json, Gas};
+use near_sdk_contract_tools::approval::native_transaction_action::{
+    NativeTransactionAction, PromiseAction,
+};
+use workspaces::{Account, Contract};
+
+const WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/native_multisig_chain.wasm");
+
+struct Setup {
+    pub contract: Contract,
+    pub accounts: Vec<Account>,
+}
+
+/// Setup for individual tests
+async fn setup(num_accounts: usize) -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    // Initialize contract
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    // Initialize user accounts
+    let mut accounts = vec![];
+    for _ in 0..num_accounts {
+        let account = worker.dev_create_account().await.unwrap();
+        account
+            .call(contract.id(), "obtain_multisig_permission")
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+        accounts.push(account);
+    }
+
+    Setup { contract, accounts }
+}
+
+#[tokio::test]
+async fn two_stage_chain() {
+    let Setup { contract, accounts } = setup(2).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let log_stage = |message: &str| NativeTransactionAction {
+        receiver_id: contract.id().as_str().parse().unwrap(),
+        actions: vec![PromiseAction::FunctionCall {
+            function_name: "private_log".into(),
+            arguments: json!({ "message": message })
+                .to_string()
+                .as_bytes()
+                .to_vec(),
+            amount: 0.into(),
+            gas: (Gas::ONE_TERA.0 * 10).into(),
+        }],
+    };
+
+    let request_id = alice
+        .call(contract.id(), "request")
+        .args_json(json!({
+            "stages": [log_stage("first"), log_stage("second")],
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    for account in [alice, bob] {
+        account
+            .call(contract.id(), "approve")
+            .args_json(json!({ "request_id": request_id }))
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    let result = alice
+        .call(contract.id(), "execute")
+        .max_gas()
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(result.is_success());
+
+    let logs = result.logs();
+    let first = logs.iter().position(|log| *log == "first").unwrap();
+    let second = logs.iter().position(|log| *log == "second").unwrap();
+
+    assert!(first < second);
+}
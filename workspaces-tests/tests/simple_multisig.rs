@@ -143,3 +143,63 @@ async fn unauthorized_account() {
         .unwrap()
         .unwrap();
 }
+
+async fn request_hello(contract: &Contract, account: &Account) -> u32 {
+    account
+        .call(contract.id(), "request")
+        .args_json(json!({"action": "hello"}))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn batch_approval_with_expired_request() {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract
+        .call("new_with_validity_period")
+        .args_json(json!({ "validity_period_nanoseconds": 30_000_000_000u64 }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let alice = worker.dev_create_account().await.unwrap();
+
+    alice
+        .call(contract.id(), "obtain_multisig_permission")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let expired_request_id = request_hello(&contract, &alice).await;
+
+    // Advance well past the 30-second validity period
+    worker.fast_forward(100).await.unwrap();
+
+    let first_request_id = request_hello(&contract, &alice).await;
+    let second_request_id = request_hello(&contract, &alice).await;
+
+    let result = alice
+        .call(contract.id(), "approve_many")
+        .args_json(json!({
+            "request_ids": [first_request_id, expired_request_id, second_request_id],
+        }))
+        .transact()
+        .await
+        .unwrap();
+
+    let approval_events = result
+        .logs()
+        .iter()
+        .filter(|log| log.starts_with("EVENT_JSON:") && log.contains("\"approve\""))
+        .count();
+
+    assert_eq!(result.json::<Vec<bool>>().unwrap(), vec![true, false, true]);
+    assert_eq!(approval_events, 2);
+}
@@ -0,0 +1,139 @@
+#![cfg(not(windows))]
+
+use near_sdk::serde_json::{self, json};
+use workspaces::{Account, Contract};
+
+const WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/simple_multisig_external.wasm");
+
+struct Setup {
+    pub contract: Contract,
+    pub accounts: Vec<Account>,
+}
+
+/// Setup for individual tests
+async fn setup(num_accounts: usize) -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    // Initialize contract
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    // Initialize user accounts
+    let mut accounts = vec![];
+    for _ in 0..num_accounts {
+        let account = worker.dev_create_account().await.unwrap();
+        account
+            .call(contract.id(), "obtain_multisig_permission")
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+        accounts.push(account);
+    }
+
+    Setup { contract, accounts }
+}
+
+async fn is_approved(contract: &Contract, request_id: u32) -> bool {
+    contract
+        .view(
+            "multisig_is_approved",
+            json!({ "request_id": request_id })
+                .to_string()
+                .as_bytes()
+                .to_vec(),
+        )
+        .await
+        .unwrap()
+        .json::<bool>()
+        .unwrap()
+}
+
+async fn approve(contract: &Contract, account: &Account, request_id: u32) {
+    account
+        .call(contract.id(), "multisig_approve")
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn generated_methods() {
+    let Setup { contract, accounts } = setup(2).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let request_id = alice
+        .call(contract.id(), "multisig_request")
+        .args_json(json!({ "action": "Increment" }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    assert!(!is_approved(&contract, request_id).await);
+
+    approve(&contract, alice, request_id).await;
+
+    assert!(!is_approved(&contract, request_id).await);
+
+    approve(&contract, bob, request_id).await;
+
+    assert!(is_approved(&contract, request_id).await);
+
+    let summary = contract
+        .view(
+            "multisig_request_summary",
+            json!({ "request_id": request_id })
+                .to_string()
+                .as_bytes()
+                .to_vec(),
+        )
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .unwrap();
+
+    assert_eq!(
+        summary,
+        json!({
+            "approvers": [alice.id(), bob.id()],
+            "approvals_remaining": 0,
+            "is_approved_for_execution": true,
+        }),
+    );
+
+    let result = bob
+        .call(contract.id(), "multisig_execute")
+        .args_json(json!({ "request_id": request_id }))
+        .transact()
+        .await
+        .unwrap()
+        .json::<u32>()
+        .unwrap();
+
+    assert_eq!(result, 1);
+}
+
+#[tokio::test]
+#[should_panic = "Missing role"]
+async fn request_requires_role() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    let stranger = worker.dev_create_account().await.unwrap();
+
+    stranger
+        .call(contract.id(), "multisig_request")
+        .args_json(json!({ "action": "Reset" }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,68 @@
+#![cfg(not(windows))]
+
+use near_sdk::serde_json::json;
+use workspaces::Contract;
+
+const WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/slot_map.wasm");
+
+async fn setup() -> Contract {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+
+    contract
+}
+
+/// Gas burnt by calling `{method}_{collection}`
+async fn gas_burnt(contract: &Contract, method: &str, collection: &str, start: u32) -> u64 {
+    contract
+        .call(&format!("{method}_{collection}"))
+        .args_json(json!({ "start": start, "count": 20 }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap()
+        .total_gas_burnt
+}
+
+#[tokio::test]
+async fn gas_benchmark() {
+    let contract = setup().await;
+
+    for collection in ["slot_map", "unordered_map"] {
+        // First inserts create new entries, second inserts overwrite them
+        let insert_new = gas_burnt(&contract, "insert", collection, 0).await;
+        let insert_existing = gas_burnt(&contract, "insert", collection, 0).await;
+        let remove = gas_burnt(&contract, "remove", collection, 5).await;
+
+        println!(
+            "{collection}: insert (new) {insert_new}, insert (existing) {insert_existing}, remove {remove}",
+        );
+    }
+
+    // Both collections hold the same entries, swap-removed in the same order
+    let entries = |collection: &str| {
+        contract.view(
+            &format!("{collection}_entries"),
+            json!({ "offset": 0, "limit": 100 })
+                .to_string()
+                .into_bytes(),
+        )
+    };
+
+    let slot_map_entries = entries("slot_map")
+        .await
+        .unwrap()
+        .json::<Vec<(u32, u64)>>()
+        .unwrap();
+    let unordered_map_entries = entries("unordered_map")
+        .await
+        .unwrap()
+        .json::<Vec<(u32, u64)>>()
+        .unwrap();
+
+    assert_eq!(slot_map_entries.len(), 5);
+    assert_eq!(slot_map_entries, unordered_map_entries);
+}
@@ -0,0 +1,207 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json, ONE_NEAR};
+use near_sdk_contract_tools::splitter::SplitAsset;
+use near_sdk_contract_tools_testing::{call, deploy_and_init, make_accounts, view};
+use workspaces::{Account, AccountId, Contract};
+
+const SPLITTER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/splitter.wasm");
+
+const FT_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/fungible_token.wasm");
+
+struct Setup {
+    pub splitter: Contract,
+    pub ft: Contract,
+    /// Deposits and triggers releases
+    pub alice: Account,
+    /// Shares of 33.33%, 33.33%, and 33.34%
+    pub payees: Vec<Account>,
+}
+
+/// Setup for individual tests: alice holds 1000 tokens
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+    let mut accounts = make_accounts(&worker, 4).await;
+    let alice = accounts.remove(0);
+    let payees = accounts;
+
+    let splitter = deploy_and_init(
+        &worker,
+        SPLITTER_WASM,
+        "new",
+        json!({
+            "payees": [
+                { "account_id": payees[0].id(), "share": 3_333 },
+                { "account_id": payees[1].id(), "share": 3_333 },
+                { "account_id": payees[2].id(), "share": 3_334 },
+            ],
+        }),
+    )
+    .await;
+
+    let ft = deploy_and_init(&worker, FT_WASM, "new", json!({})).await;
+    call(&alice, ft.id(), "mint", json!({ "amount": "1000" })).await;
+
+    Setup {
+        splitter,
+        ft,
+        alice,
+        payees,
+    }
+}
+
+impl Setup {
+    fn token(&self) -> SplitAsset {
+        SplitAsset::FungibleToken {
+            contract_id: self.ft.id().as_str().parse().unwrap(),
+        }
+    }
+
+    async fn release(&self, asset: &SplitAsset, payee: &Account) {
+        self.alice
+            .call(self.splitter.id(), "release")
+            .args_json(json!({ "asset": asset, "payee_id": payee.id() }))
+            .max_gas()
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    async fn released(&self, asset: &SplitAsset) -> Vec<u128> {
+        let mut released = vec![];
+        for payee in &self.payees {
+            let amount: U128 = view(
+                &self.splitter,
+                "get_released",
+                json!({ "asset": asset, "payee_id": payee.id() }),
+            )
+            .await;
+            released.push(amount.0);
+        }
+        released
+    }
+
+    async fn ft_balance(&self, account_id: &AccountId) -> u128 {
+        view::<U128>(
+            &self.ft,
+            "ft_balance_of",
+            json!({ "account_id": account_id }),
+        )
+        .await
+        .into()
+    }
+
+    async fn ft_deposit(&self, amount: u128) {
+        self.alice
+            .call(self.ft.id(), "ft_transfer_call")
+            .args_json(json!({
+                "receiver_id": self.splitter.id(),
+                "amount": U128(amount),
+                "msg": "",
+            }))
+            .deposit(1)
+            .max_gas()
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn near_repeated_deposits() {
+    let s = setup().await;
+
+    let mut before = vec![];
+    for payee in &s.payees {
+        before.push(payee.view_account().await.unwrap().balance);
+    }
+
+    for _ in 0..2 {
+        s.alice
+            .call(s.splitter.id(), "deposit")
+            .deposit(ONE_NEAR)
+            .transact()
+            .await
+            .unwrap()
+            .unwrap();
+
+        s.release(&SplitAsset::Near, &s.payees[0]).await;
+    }
+
+    // The second release of the first payee covers the second deposit only
+    assert_eq!(
+        s.released(&SplitAsset::Near).await,
+        vec![ONE_NEAR / 10_000 * 3_333 * 2, 0, 0],
+    );
+
+    s.release(&SplitAsset::Near, &s.payees[1]).await;
+    s.release(&SplitAsset::Near, &s.payees[2]).await;
+
+    let released = s.released(&SplitAsset::Near).await;
+    assert_eq!(
+        released,
+        vec![
+            ONE_NEAR / 10_000 * 3_333 * 2,
+            ONE_NEAR / 10_000 * 3_333 * 2,
+            ONE_NEAR / 10_000 * 3_334 * 2,
+        ],
+    );
+    assert_eq!(released.iter().sum::<u128>(), ONE_NEAR * 2);
+
+    for (payee, before) in s.payees.iter().zip(before) {
+        let after = payee.view_account().await.unwrap().balance;
+        let released: U128 = view(
+            &s.splitter,
+            "get_released",
+            json!({ "asset": SplitAsset::Near, "payee_id": payee.id() }),
+        )
+        .await;
+        assert_eq!(after - before, released.0);
+    }
+
+    // Nothing left to release
+    let result = s
+        .alice
+        .call(s.splitter.id(), "release")
+        .args_json(json!({ "asset": SplitAsset::Near, "payee_id": s.payees[0].id() }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Nothing to release"));
+}
+
+#[tokio::test]
+async fn ft_dust_to_last_payee() {
+    let s = setup().await;
+    let token = s.token();
+
+    // 10 tokens: 3, 3, and 4 with the dust
+    s.ft_deposit(10).await;
+    for payee in &s.payees {
+        s.release(&token, payee).await;
+    }
+    assert_eq!(s.released(&token).await, vec![3, 3, 4]);
+
+    // 90 more: 33, 33, and 34 in total
+    s.ft_deposit(90).await;
+    s.release(&token, &s.payees[2]).await;
+    s.release(&token, &s.payees[0]).await;
+    assert_eq!(s.released(&token).await, vec![33, 3, 34]);
+
+    s.release(&token, &s.payees[1]).await;
+    assert_eq!(s.released(&token).await, vec![33, 33, 34]);
+
+    for (payee, expected) in s.payees.iter().zip([33, 33, 34]) {
+        assert_eq!(s.ft_balance(payee.id()).await, expected);
+    }
+    assert_eq!(s.ft_balance(s.splitter.id()).await, 0);
+    assert_eq!(s.ft_balance(s.alice.id()).await, 900);
+
+    let total: U128 = view(&s.splitter, "get_total_received", json!({ "asset": token })).await;
+    assert_eq!(total.0, 100);
+}
@@ -2,7 +2,15 @@
 
 use near_sdk::{
     borsh::{self, BorshSerialize},
+    env,
+    json_types::{Base58CryptoHash, Base64VecU8},
     serde::Serialize,
+    serde_json::json,
+    ONE_NEAR,
+};
+use near_sdk_contract_tools::{migrate::MigrationProgress, upgrade::staged::StagedUpgradeInfo};
+use near_sdk_contract_tools_testing::{
+    call, call_json, call_raw, deploy_to_and_init, make_accounts, view,
 };
 use workspaces::{Account, Contract};
 
@@ -15,9 +23,54 @@ const WASM_JSON: &[u8] =
 const WASM_RAW: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_raw.wasm");
 
+const WASM_NO_MIGRATE: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_no_migrate.wasm");
+
+const WASM_ON_COMPLETE: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_on_complete.wasm");
+
+const WASM_OWNER_OR_ROLE: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_owner_or_role.wasm");
+
+const WASM_RAW_INPUT: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_raw_input.wasm");
+
+const WASM_STAGED: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_staged.wasm");
+
+const WASM_STAGED_DELAY: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_staged_delay.wasm");
+
 const NEW_WASM: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new.wasm");
 
+const NEW_ALLOW_OWNER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new_allow_owner.wasm");
+
+const NEW_FALLIBLE_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new_fallible.wasm");
+
+const NEW_NO_MIGRATE_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new_no_migrate.wasm");
+
+const NEW_ON_COMPLETE_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new_on_complete.wasm");
+
+const NEW_WITH_ARGS_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new_with_args.wasm");
+
+const PROGRESSIVE_OLD_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_progressive_old.wasm");
+
+const PROGRESSIVE_NEW_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_progressive_new.wasm");
+
+const ROLLBACK_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_rollback.wasm");
+
+const ROLLBACK_BROKEN_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_rollback_broken.wasm");
+
 const BAD_WASM: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_bad.wasm");
 
@@ -27,12 +80,47 @@ const RANDOM_WASM: &[u8] =
 #[derive(BorshSerialize)]
 struct ArgsBorsh {
     pub code: Vec<u8>,
+    pub expected_hash: Option<Base58CryptoHash>,
+    pub migrate_args: Option<Base64VecU8>,
+}
+
+#[derive(BorshSerialize)]
+struct NoMigrateArgsBorsh {
+    pub code: Vec<u8>,
+    pub expected_hash: Option<Base58CryptoHash>,
+}
+
+#[derive(BorshSerialize)]
+struct StageArgsBorsh {
+    pub code: Vec<u8>,
+}
+
+#[derive(BorshSerialize)]
+struct ChunkArgsBorsh {
+    pub index: u32,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(BorshSerialize)]
+struct FinalizeArgsBorsh {
+    pub total_chunks: u32,
+    pub expected_hash: Base58CryptoHash,
+}
+
+#[derive(BorshSerialize)]
+struct DeployStagedArgsBorsh {
+    pub expected_hash: Option<Base58CryptoHash>,
+    pub migrate_args: Option<Base64VecU8>,
 }
 
 #[derive(Serialize)]
 #[serde(crate = "near_sdk::serde")]
 struct ArgsJson {
-    pub code: near_sdk::json_types::Base64VecU8,
+    pub code: Base64VecU8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<Base58CryptoHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migrate_args: Option<Base64VecU8>,
 }
 
 struct Setup {
@@ -45,15 +133,9 @@ async fn setup(num_accounts: usize, wasm: &[u8]) -> Setup {
     let worker = workspaces::sandbox().await.unwrap();
 
     // Initialize user accounts
-    let mut accounts = vec![];
-    for _ in 0..(num_accounts + 1) {
-        accounts.push(worker.dev_create_account().await.unwrap());
-    }
+    let accounts = make_accounts(&worker, num_accounts + 1).await;
 
-    let alice = &accounts[0].clone();
-
-    let contract = alice.deploy(&wasm.to_vec()).await.unwrap().unwrap();
-    contract.call("new").transact().await.unwrap().unwrap();
+    let contract = deploy_to_and_init(&accounts[0], wasm, "new", json!({})).await;
 
     Setup { contract, accounts }
 }
@@ -63,39 +145,20 @@ async fn perform_upgrade_test(wasm: &[u8], args: Vec<u8>) {
 
     let alice = &accounts[0];
 
-    alice
-        .call(contract.id(), "increment_foo")
-        .transact()
-        .await
-        .unwrap()
-        .unwrap();
+    call(alice, contract.id(), "increment_foo", json!({})).await;
 
-    let val = alice
-        .call(contract.id(), "get_foo")
-        .transact()
-        .await
-        .unwrap()
-        .json::<u32>()
-        .unwrap();
+    let val = call_json::<u32>(alice, contract.id(), "get_foo", json!({})).await;
 
     assert_eq!(val, 1);
 
-    alice
-        .call(contract.id(), "upgrade")
-        .max_gas()
-        .args(args)
-        .transact()
-        .await
-        .unwrap()
-        .unwrap();
+    let result = call_raw(alice, contract.id(), "upgrade", args).await;
 
-    let new_val = alice
-        .call(contract.id(), "get_bar")
-        .transact()
-        .await
-        .unwrap()
-        .json::<u64>()
-        .unwrap();
+    assert!(result.logs().contains(&format!(
+        r#"EVENT_JSON:{{"standard":"x-migr","version":"1.0.0","event":"migrate","data":{{"from_version":"ContractOld","to_version":"ContractNew","by":"{}"}}}}"#,
+        contract.id(),
+    ).as_str()));
+
+    let new_val = call_json::<u64>(alice, contract.id(), "get_bar", json!({})).await;
 
     assert_eq!(new_val, 1);
 }
@@ -106,6 +169,8 @@ async fn upgrade_borsh() {
         WASM_BORSH,
         ArgsBorsh {
             code: NEW_WASM.to_vec(),
+            expected_hash: None,
+            migrate_args: None,
         }
         .try_to_vec()
         .unwrap(),
@@ -119,6 +184,8 @@ async fn upgrade_jsonbase64() {
         WASM_JSON,
         near_sdk::serde_json::to_vec(&ArgsJson {
             code: NEW_WASM.to_vec().into(),
+            expected_hash: None,
+            migrate_args: None,
         })
         .unwrap(),
     )
@@ -143,13 +210,7 @@ async fn upgrade_failure_no_upgrade() {
 
     let alice = &accounts[0];
 
-    alice
-        .call(contract.id(), "upgrade")
-        .max_gas()
-        .transact()
-        .await
-        .unwrap()
-        .unwrap();
+    call(alice, contract.id(), "upgrade", json!({})).await;
 }
 
 #[tokio::test]
@@ -159,13 +220,7 @@ async fn upgrade_failure_random_wasm() {
 
     let alice = &accounts[0];
 
-    alice
-        .call(contract.id(), "upgrade")
-        .max_gas()
-        .transact()
-        .await
-        .unwrap()
-        .unwrap();
+    call(alice, contract.id(), "upgrade", json!({})).await;
 }
 
 async fn fail_owner(wasm: &[u8], args: Vec<u8>) {
@@ -173,13 +228,7 @@ async fn fail_owner(wasm: &[u8], args: Vec<u8>) {
 
     let bob = &accounts[1];
 
-    bob.call(contract.id(), "upgrade")
-        .max_gas()
-        .args(args)
-        .transact()
-        .await
-        .unwrap()
-        .unwrap();
+    call_raw(bob, contract.id(), "upgrade", args).await;
 }
 
 #[tokio::test]
@@ -189,6 +238,8 @@ async fn upgrade_failure_not_owner_borsh() {
         WASM_BORSH,
         ArgsBorsh {
             code: NEW_WASM.to_vec(),
+            expected_hash: None,
+            migrate_args: None,
         }
         .try_to_vec()
         .unwrap(),
@@ -203,6 +254,8 @@ async fn upgrade_failure_not_owner_jsonbase64() {
         WASM_JSON,
         near_sdk::serde_json::to_vec(&ArgsJson {
             code: NEW_WASM.to_vec().into(),
+            expected_hash: None,
+            migrate_args: None,
         })
         .unwrap(),
     )
@@ -214,3 +267,908 @@ async fn upgrade_failure_not_owner_jsonbase64() {
 async fn upgrade_failure_not_owner_raw() {
     fail_owner(WASM_RAW, NEW_WASM.to_vec()).await;
 }
+
+#[tokio::test]
+async fn upgrade_fallible_migration() {
+    let Setup { contract, accounts } = setup(1, WASM_RAW).await;
+
+    let alice = &accounts[0];
+
+    // Migration fails while foo is 0, and the upgrade is rolled back
+    let result = alice
+        .call(contract.id(), "upgrade")
+        .max_gas()
+        .args(NEW_FALLIBLE_WASM.to_vec())
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("foo must be incremented before migration"));
+
+    // Old code is still deployed
+    let val = call_json::<u32>(alice, contract.id(), "get_foo", json!({})).await;
+
+    assert_eq!(val, 0);
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    // Migration succeeds now that foo is 1
+    call_raw(alice, contract.id(), "upgrade", NEW_FALLIBLE_WASM.to_vec()).await;
+
+    let new_val = call_json::<u64>(alice, contract.id(), "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_progressive_migration() {
+    let Setup { contract, accounts } = setup(1, PROGRESSIVE_OLD_WASM).await;
+
+    let alice = &accounts[0];
+
+    call(alice, contract.id(), "seed", json!({ "count": 50 })).await;
+
+    call_raw(
+        alice,
+        contract.id(),
+        "upgrade",
+        PROGRESSIVE_NEW_WASM.to_vec(),
+    )
+    .await;
+
+    let migration_complete = || view::<bool>(&contract, "migration_complete", json!({}));
+
+    assert!(!migration_complete().await);
+
+    // Methods are blocked until the migration completes
+    let result = contract
+        .view(
+            "get_balance",
+            json!({ "key": "3" }).to_string().as_bytes().to_vec(),
+        )
+        .await;
+
+    assert!(format!("{:?}", result.unwrap_err()).contains("Migration in progress"));
+
+    // Only the contract itself may perform migration steps
+    let result = alice
+        .call(contract.id(), "migrate_step")
+        .args_json(json!({ "limit": 20 }))
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(result.is_failure());
+
+    let mut steps = vec![];
+    for _ in 0..3 {
+        let progress = contract
+            .call("migrate_step")
+            .args_json(json!({ "limit": 20 }))
+            .max_gas()
+            .transact()
+            .await
+            .unwrap()
+            .json::<MigrationProgress>()
+            .unwrap();
+
+        steps.push((progress.done, progress.items_processed));
+    }
+
+    assert_eq!(steps, vec![(false, 20), (false, 20), (true, 10)]);
+    assert!(migration_complete().await);
+
+    for key in [0u32, 19, 20, 49] {
+        let balance =
+            view::<Option<u64>>(&contract, "get_balance", json!({ "key": key.to_string() })).await;
+
+        assert_eq!(balance, Some(key as u64 * 10));
+    }
+}
+
+#[tokio::test]
+async fn migrate_allow_owner() {
+    let Setup { contract, accounts } = setup(2, WASM_RAW).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    // Deploy the new code without migrating
+    contract
+        .as_account()
+        .deploy(NEW_ALLOW_OWNER_WASM)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let result = bob
+        .call(contract.id(), "migrate")
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    contract
+        .call("migrate")
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn migrate_twice() {
+    let Setup { contract, accounts } = setup(1, WASM_RAW).await;
+
+    let alice = &accounts[0];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    call_raw(alice, contract.id(), "upgrade", NEW_WASM.to_vec()).await;
+
+    let result = contract.call("migrate").max_gas().transact().await.unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("Already migrated from ContractOld to ContractNew"));
+
+    // State is untouched
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_staged() {
+    let Setup { contract, accounts } = setup(2, WASM_STAGED).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    let args = StageArgsBorsh {
+        code: NEW_WASM.to_vec(),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Only the owner may stage code
+    let result = bob
+        .call(contract.id(), "stage_upgrade")
+        .args(args.clone())
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    // Deposit must cover storage
+    let result = alice
+        .call(contract.id(), "stage_upgrade")
+        .args(args.clone())
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("Insufficient deposit to stage code"));
+
+    let result = alice
+        .call(contract.id(), "stage_upgrade")
+        .args(args)
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let code_hash = result.json::<String>().unwrap();
+
+    assert!(result.logs().contains(
+        &format!(
+            r#"EVENT_JSON:{{"standard":"x-upgr","version":"1.0.0","event":"upgrade_staged","data":{{"code_hash":"{}","by":"{}"}}}}"#,
+            code_hash,
+            alice.id(),
+        )
+        .as_str()
+    ));
+
+    let staged_code_hash = view::<Option<String>>(&contract, "staged_code_hash", json!({})).await;
+
+    assert_eq!(staged_code_hash, Some(code_hash.clone()));
+
+    // Old code is still running
+    let val = view::<u32>(&contract, "get_foo", json!({})).await;
+
+    assert_eq!(val, 1);
+
+    // Deploying rejects a mismatched hash
+    let result = alice
+        .call(contract.id(), "deploy_staged")
+        .args(
+            DeployStagedArgsBorsh {
+                expected_hash: Some([0; 32].into()),
+                migrate_args: None,
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Code hash mismatch"));
+
+    let result = call_raw(
+        alice,
+        contract.id(),
+        "deploy_staged",
+        DeployStagedArgsBorsh {
+            expected_hash: Some(code_hash.parse().unwrap()),
+            migrate_args: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    assert!(result.logs().contains(
+        &format!(
+            r#"EVENT_JSON:{{"standard":"x-upgr","version":"1.0.0","event":"upgrade_deployed","data":{{"code_hash":"{}","by":"{}","migrate":"migrate"}}}}"#,
+            code_hash,
+            alice.id(),
+        )
+        .as_str()
+    ));
+
+    let deployed_code_hash = contract.view_account().await.unwrap().code_hash;
+
+    assert_eq!(deployed_code_hash.to_string(), code_hash);
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_wrong_hash() {
+    let Setup { contract, accounts } = setup(1, WASM_BORSH).await;
+
+    let alice = &accounts[0];
+
+    let result = alice
+        .call(contract.id(), "upgrade")
+        .args(
+            ArgsBorsh {
+                code: NEW_WASM.to_vec(),
+                expected_hash: Some([0; 32].into()),
+                migrate_args: None,
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Code hash mismatch"));
+
+    // Old code is still deployed
+    let val = view::<u32>(&contract, "get_foo", json!({})).await;
+
+    assert_eq!(val, 0);
+}
+
+#[tokio::test]
+async fn upgrade_expected_hash() {
+    let Setup { contract, accounts } = setup(1, WASM_STAGED).await;
+
+    let alice = &accounts[0];
+
+    // Use the staging area to compute the hash
+    let code_hash = alice
+        .call(contract.id(), "stage_upgrade")
+        .args(
+            StageArgsBorsh {
+                code: NEW_WASM.to_vec(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .json::<Base58CryptoHash>()
+        .unwrap();
+
+    let result = call(alice, contract.id(), "unstage_upgrade", json!({})).await;
+
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"upgrade_unstaged""#)));
+
+    let result = call_raw(
+        alice,
+        contract.id(),
+        "upgrade",
+        ArgsBorsh {
+            code: NEW_WASM.to_vec(),
+            expected_hash: Some(code_hash),
+            migrate_args: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    // The deployment event is logged by the upgrade call itself, ahead of the
+    // migration event logged by the deploy receipt
+    let logs = result.logs();
+    let deployed = logs
+        .iter()
+        .position(|log| log.contains(r#""event":"upgrade_deployed""#))
+        .unwrap();
+    let migrated = logs
+        .iter()
+        .position(|log| log.contains(r#""event":"migrate""#))
+        .unwrap();
+
+    assert!(deployed < migrated);
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 0);
+}
+
+#[tokio::test]
+async fn upgrade_staged_chunked() {
+    let Setup { contract, accounts } = setup(2, WASM_STAGED).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    let chunk_len = (NEW_WASM.len() + 2) / 3;
+    let chunks = NEW_WASM.chunks(chunk_len).collect::<Vec<_>>();
+    assert_eq!(chunks.len(), 3);
+
+    let upload_chunk = |account: &Account, index: u32, bytes: &[u8]| {
+        account
+            .call(contract.id(), "stage_upgrade_chunk")
+            .args(
+                ChunkArgsBorsh {
+                    index,
+                    bytes: bytes.to_vec(),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+            .deposit(10 * ONE_NEAR)
+            .max_gas()
+            .transact()
+    };
+
+    // Only the owner may upload chunks
+    let result = upload_chunk(bob, 0, chunks[0]).await.unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    // Out of order, with a bad chunk that is replaced later
+    upload_chunk(alice, 2, chunks[2]).await.unwrap().unwrap();
+    upload_chunk(alice, 0, &[0; 16]).await.unwrap().unwrap();
+    upload_chunk(alice, 1, chunks[1]).await.unwrap().unwrap();
+
+    let expected_hash: Base58CryptoHash = env::sha256_array(NEW_WASM).into();
+
+    let finalize = |total_chunks: u32| {
+        alice
+            .call(contract.id(), "finalize_staged")
+            .args(
+                FinalizeArgsBorsh {
+                    total_chunks,
+                    expected_hash,
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+            .max_gas()
+            .transact()
+    };
+
+    // The bad chunk does not match the hash
+    let result = finalize(3).await.unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Code hash mismatch"));
+
+    upload_chunk(alice, 0, chunks[0]).await.unwrap().unwrap();
+
+    // Every chunk must be present
+    let result = finalize(4).await.unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("Expected chunks 0 to 3 to be uploaded, got [0, 1, 2]"));
+
+    let code_hash = finalize(3)
+        .await
+        .unwrap()
+        .unwrap()
+        .json::<Base58CryptoHash>()
+        .unwrap();
+
+    assert_eq!(code_hash, expected_hash);
+
+    call_raw(
+        alice,
+        contract.id(),
+        "deploy_staged",
+        DeployStagedArgsBorsh {
+            expected_hash: Some(code_hash),
+            migrate_args: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 0);
+}
+
+#[tokio::test]
+async fn upgrade_abort_staged_upload() {
+    let Setup { contract, accounts } = setup(1, WASM_STAGED).await;
+
+    let alice = &accounts[0];
+
+    alice
+        .call(contract.id(), "stage_upgrade_chunk")
+        .args(
+            ChunkArgsBorsh {
+                index: 0,
+                bytes: NEW_WASM.to_vec(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let balance_before = alice.view_account().await.unwrap().balance;
+
+    call(alice, contract.id(), "abort_staged_upload", json!({})).await;
+
+    // Storage deposit is refunded
+    assert!(alice.view_account().await.unwrap().balance > balance_before);
+
+    // Nothing left to finalize
+    let result = alice
+        .call(contract.id(), "finalize_staged")
+        .args(
+            FinalizeArgsBorsh {
+                total_chunks: 1,
+                expected_hash: env::sha256_array(NEW_WASM).into(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("No chunked upload in progress"));
+}
+
+#[tokio::test]
+async fn upgrade_staged_delay() {
+    let Setup { contract, accounts } = setup(1, WASM_STAGED_DELAY).await;
+
+    let alice = &accounts[0];
+
+    let deploy_staged = || async {
+        alice
+            .call(contract.id(), "deploy_staged")
+            .args(
+                DeployStagedArgsBorsh {
+                    expected_hash: None,
+                    migrate_args: None,
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+            .max_gas()
+            .transact()
+            .await
+            .unwrap()
+    };
+
+    alice
+        .call(contract.id(), "stage_upgrade")
+        .args(
+            StageArgsBorsh {
+                code: NEW_WASM.to_vec(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        )
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let info = view::<Option<StagedUpgradeInfo>>(&contract, "staged_upgrade", json!({}))
+        .await
+        .unwrap();
+
+    // Too early
+    let result = deploy_staged().await;
+
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("Staged code cannot be deployed yet"));
+
+    // Keep trying until the delay passes
+    let mut attempts = 0;
+    loop {
+        let result = deploy_staged().await;
+        if result.is_success() {
+            break;
+        }
+
+        assert!(format!("{:?}", result.into_result().unwrap_err())
+            .contains("Staged code cannot be deployed yet"));
+
+        attempts += 1;
+        assert!(attempts < 50, "Stage delay did not elapse");
+    }
+
+    assert!(info.earliest_deploy_timestamp.0 > 0);
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 0);
+}
+
+#[tokio::test]
+#[should_panic = "MethodResolveError(MethodNotFound)"]
+async fn upgrade_staged_delay_no_direct_upgrade() {
+    let Setup { contract, accounts } = setup(1, WASM_STAGED_DELAY).await;
+
+    let alice = &accounts[0];
+
+    call_raw(
+        alice,
+        contract.id(),
+        "upgrade",
+        ArgsBorsh {
+            code: NEW_WASM.to_vec(),
+            expected_hash: None,
+            migrate_args: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn upgrade_rollback() {
+    let Setup { contract, accounts } = setup(1, ROLLBACK_WASM).await;
+
+    let alice = &accounts[0];
+
+    let previous_code_hash = || view::<Option<String>>(&contract, "previous_code_hash", json!({}));
+
+    let upgrade = |code: &[u8]| {
+        alice
+            .call(contract.id(), "upgrade")
+            .args(
+                ArgsBorsh {
+                    code: code.to_vec(),
+                    expected_hash: None,
+                    migrate_args: None,
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+            .max_gas()
+            .transact()
+    };
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    // The initially deployed code is unknown
+    assert_eq!(previous_code_hash().await, None);
+
+    // Redeploy the same code so that it is recorded
+    upgrade(ROLLBACK_WASM).await.unwrap().unwrap();
+    assert_eq!(previous_code_hash().await, None);
+
+    let good_code_hash = contract.view_account().await.unwrap().code_hash.to_string();
+
+    upgrade(ROLLBACK_BROKEN_WASM).await.unwrap().unwrap();
+    assert_eq!(previous_code_hash().await, Some(good_code_hash.clone()));
+
+    let result = contract.view("get_foo", vec![]).await;
+    assert!(format!("{:?}", result.unwrap_err()).contains("Broken"));
+
+    // Roll back through the staged flow
+    let result = alice
+        .call(contract.id(), "stage_rollback")
+        .deposit(10 * ONE_NEAR)
+        .max_gas()
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(result.json::<String>().unwrap(), good_code_hash);
+
+    call_raw(
+        alice,
+        contract.id(),
+        "deploy_staged",
+        DeployStagedArgsBorsh {
+            expected_hash: Some(good_code_hash.parse().unwrap()),
+            migrate_args: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    let code_hash = contract.view_account().await.unwrap().code_hash.to_string();
+
+    assert_eq!(code_hash, good_code_hash);
+
+    // State survived the broken deployment
+    let val = view::<u32>(&contract, "get_foo", json!({})).await;
+
+    assert_eq!(val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_migrate_args() {
+    let Setup { contract, accounts } = setup(1, WASM_BORSH).await;
+
+    let alice = &accounts[0];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    let migrate_args = near_sdk::serde_json::to_vec(&json!({
+        "args": {
+            "multiplier": 7,
+        },
+    }))
+    .unwrap();
+
+    call_raw(
+        alice,
+        contract.id(),
+        "upgrade",
+        ArgsBorsh {
+            code: NEW_WITH_ARGS_WASM.to_vec(),
+            expected_hash: None,
+            migrate_args: Some(migrate_args.into()),
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 7);
+}
+
+#[tokio::test]
+async fn upgrade_no_migrate() {
+    let Setup { contract, accounts } = setup(1, WASM_NO_MIGRATE).await;
+
+    let alice = &accounts[0];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    let result = call_raw(
+        alice,
+        contract.id(),
+        "upgrade",
+        NoMigrateArgsBorsh {
+            code: NEW_NO_MIGRATE_WASM.to_vec(),
+            expected_hash: None,
+        }
+        .try_to_vec()
+        .unwrap(),
+    )
+    .await;
+
+    // No migrate function is called
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"upgrade_deployed""#) && !log.contains(r#""migrate""#)));
+
+    let version = view::<u32>(&contract, "get_version", json!({})).await;
+
+    assert_eq!(version, 2);
+
+    let val = view::<u32>(&contract, "get_foo", json!({})).await;
+
+    assert_eq!(val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_owner_or_role() {
+    let Setup { contract, accounts } = setup(2, WASM_OWNER_OR_ROLE).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    let args = ArgsBorsh {
+        code: NEW_WASM.to_vec(),
+        expected_hash: None,
+        migrate_args: None,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Neither the owner nor an upgrader
+    let result = bob
+        .call(contract.id(), "upgrade")
+        .max_gas()
+        .args(args.clone())
+        .transact()
+        .await
+        .unwrap();
+
+    let error = format!("{:?}", result.into_result().unwrap_err());
+    assert!(error.contains("Unauthorized upgrade: Owner only; Unauthorized role"));
+
+    call(
+        alice,
+        contract.id(),
+        "grant_upgrader",
+        json!({ "account_id": bob.id() }),
+    )
+    .await;
+
+    call_raw(bob, contract.id(), "upgrade", args).await;
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_renamed_raw_input() {
+    let Setup { contract, accounts } = setup(2, WASM_RAW_INPUT).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+
+    call(alice, contract.id(), "increment_foo", json!({})).await;
+
+    // The default function name is not exposed
+    let result = alice
+        .call(contract.id(), "upgrade")
+        .max_gas()
+        .args(NEW_WASM.to_vec())
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(result.is_failure());
+
+    let result = bob
+        .call(contract.id(), "deploy_code")
+        .max_gas()
+        .args(NEW_WASM.to_vec())
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    // The input is the code itself
+    call_raw(alice, contract.id(), "deploy_code", NEW_WASM.to_vec()).await;
+
+    let new_val = view::<u64>(&contract, "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 1);
+}
+
+#[tokio::test]
+async fn upgrade_on_complete() {
+    let Setup { contract, accounts } = setup(1, WASM_ON_COMPLETE).await;
+
+    let alice = &accounts[0];
+
+    let upgrade = |code: &[u8]| {
+        alice
+            .call(contract.id(), "upgrade")
+            .args(
+                ArgsBorsh {
+                    code: code.to_vec(),
+                    expected_hash: None,
+                    migrate_args: None,
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+            .max_gas()
+            .transact()
+    };
+
+    // Migration fails while foo is 0, so the old code runs the callback
+    let result = upgrade(NEW_FALLIBLE_WASM).await.unwrap();
+
+    let fallible_code_hash = String::from(&Base58CryptoHash::from(env::sha256_array(
+        NEW_FALLIBLE_WASM,
+    )));
+
+    assert!(result.logs().contains(
+        &format!(
+            r#"EVENT_JSON:{{"standard":"x-upgr","version":"1.0.0","event":"upgrade_failed","data":{{"code_hash":"{fallible_code_hash}"}}}}"#,
+        )
+        .as_str()
+    ));
+
+    let val = view::<u32>(&contract, "get_foo", json!({})).await;
+
+    assert_eq!(val, 0);
+
+    // The new code runs the callback
+    let result = upgrade(NEW_ON_COMPLETE_WASM).await.unwrap().unwrap();
+
+    let code_hash: Base58CryptoHash = env::sha256_array(NEW_ON_COMPLETE_WASM).into();
+
+    assert!(result.logs().contains(
+        &format!(
+            r#"EVENT_JSON:{{"standard":"x-upgr","version":"1.0.0","event":"upgrade_completed","data":{{"code_hash":"{}"}}}}"#,
+            String::from(&code_hash),
+        )
+        .as_str()
+    ));
+
+    let completed_code_hash =
+        view::<Option<Base58CryptoHash>>(&contract, "get_completed_code_hash", json!({})).await;
+
+    assert_eq!(completed_code_hash, Some(code_hash));
+
+    // The callback can only be called by the contract itself
+    let result = alice
+        .call(contract.id(), "on_upgrade_complete")
+        .args_json(json!({ "code_hash": code_hash }))
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(result.is_failure());
+}
@@ -1,14 +1,28 @@
 #![cfg(not(windows))]
 
-use near_sdk::{json_types::Base64VecU8, serde_json::json};
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    json_types::Base64VecU8,
+    serde_json::json,
+    ONE_NEAR,
+};
+use near_sdk_contract_tools_testing::{call, call_json, deploy_to_and_init, make_accounts};
 use workspaces::{Account, Contract};
 
 const WASM: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_multisig.wasm");
 
+const STAGED_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_old_staged_multisig.wasm");
+
 const NEW_WASM: &[u8] =
     include_bytes!("../../target/wasm32-unknown-unknown/release/upgrade_new.wasm");
 
+#[derive(BorshSerialize)]
+struct StageArgsBorsh {
+    pub code: Vec<u8>,
+}
+
 struct Setup {
     pub contract: Contract,
     pub accounts: Vec<Account>,
@@ -19,15 +33,9 @@ async fn setup(num_accounts: usize, wasm: &[u8]) -> Setup {
     let worker = workspaces::testnet().await.unwrap();
 
     // Initialize user accounts
-    let mut accounts = vec![];
-    for _ in 0..(num_accounts + 1) {
-        accounts.push(worker.dev_create_account().await.unwrap());
-    }
-
-    let alice = &accounts[0].clone();
+    let accounts = make_accounts(&worker, num_accounts + 1).await;
 
-    let contract = alice.deploy(&wasm.to_vec()).await.unwrap().unwrap();
-    contract.call("new").transact().await.unwrap().unwrap();
+    let contract = deploy_to_and_init(&accounts[0], wasm, "new", json!({})).await;
 
     Setup { contract, accounts }
 }
@@ -40,52 +48,139 @@ async fn upgrade_multisig() {
 
     let code = Base64VecU8::from(Vec::from(NEW_WASM));
 
-    let request_id: u32 = alice
-        .call(contract.id(), "request")
-        .max_gas()
-        .args_json(json!({
+    let request_id: u32 = call_json(
+        alice,
+        contract.id(),
+        "request",
+        json!({
             "request": {
                 "Upgrade": {
                     "code": code,
                 },
             },
-        }))
-        .transact()
-        .await
-        .unwrap()
-        .unwrap()
-        .json()
-        .unwrap();
+        }),
+    )
+    .await;
+
+    call(
+        alice,
+        contract.id(),
+        "approve",
+        json!({
+            "request_id": request_id,
+        }),
+    )
+    .await;
+
+    call(
+        alice,
+        contract.id(),
+        "execute",
+        json!({
+            "request_id": request_id,
+        }),
+    )
+    .await;
+
+    let new_val = call_json::<u64>(alice, contract.id(), "get_bar", json!({})).await;
+
+    assert_eq!(new_val, 0);
+}
 
-    alice
-        .call(contract.id(), "approve")
+#[tokio::test]
+async fn upgrade_multisig_staged() {
+    let Setup { contract, accounts } = setup(2, STAGED_WASM).await;
+
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+
+    call(bob, contract.id(), "obtain_multisig_permission", json!({})).await;
+
+    let stage_args = StageArgsBorsh {
+        code: NEW_WASM.to_vec(),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Only multisig members may stage code
+    let result = charlie
+        .call(contract.id(), "stage_upgrade")
+        .args(stage_args.clone())
+        .deposit(10 * ONE_NEAR)
         .max_gas()
-        .args_json(json!({
-            "request_id": request_id,
-        }))
         .transact()
         .await
-        .unwrap()
         .unwrap();
 
-    alice
-        .call(contract.id(), "execute")
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Missing role"));
+
+    let code_hash: String = alice
+        .call(contract.id(), "stage_upgrade")
+        .args(stage_args)
+        .deposit(10 * ONE_NEAR)
         .max_gas()
-        .args_json(json!({
-            "request_id": request_id,
-        }))
         .transact()
         .await
         .unwrap()
+        .unwrap()
+        .json()
         .unwrap();
 
-    let new_val = alice
-        .call(contract.id(), "get_bar")
+    // Direct deployment is not possible
+    let result = alice
+        .call(contract.id(), "deploy_staged")
+        .max_gas()
         .transact()
         .await
-        .unwrap()
-        .json::<u64>()
         .unwrap();
 
+    assert!(result.is_failure());
+
+    let request_id: u32 = call_json(
+        alice,
+        contract.id(),
+        "multisig_request",
+        json!({
+            "action": {
+                "code_hash": code_hash,
+            },
+        }),
+    )
+    .await;
+
+    let approve = |account| {
+        call(
+            account,
+            contract.id(),
+            "multisig_approve",
+            json!({
+                "request_id": request_id,
+            }),
+        )
+    };
+
+    let execute = || {
+        alice
+            .call(contract.id(), "multisig_execute")
+            .max_gas()
+            .args_json(json!({
+                "request_id": request_id,
+            }))
+            .transact()
+    };
+
+    approve(alice).await;
+
+    // Not enough approvals yet
+    let result = execute().await.unwrap();
+    assert!(result.is_failure());
+
+    approve(bob).await;
+
+    execute().await.unwrap().unwrap();
+
+    let new_val = call_json::<u64>(alice, contract.id(), "get_bar", json!({})).await;
+
     assert_eq!(new_val, 0);
 }
@@ -0,0 +1,134 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json};
+use workspaces::{Account, AccountId, Contract};
+
+const WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/versioned.wasm");
+
+async fn view<T: near_sdk::serde::de::DeserializeOwned>(
+    contract: &Contract,
+    method: &str,
+    args: near_sdk::serde_json::Value,
+) -> T {
+    contract
+        .view(method, args.to_string().as_bytes().to_vec())
+        .await
+        .unwrap()
+        .json()
+        .unwrap()
+}
+
+async fn balance(contract: &Contract, account: &AccountId) -> u128 {
+    view::<U128>(contract, "ft_balance_of", json!({ "account_id": account }))
+        .await
+        .into()
+}
+
+async fn transfer(contract: &Contract, receiver: &Account, amount: u128) {
+    contract
+        .call("ft_transfer")
+        .deposit(1)
+        .args_json(json!({
+            "receiver_id": receiver.id(),
+            "amount": U128(amount),
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+struct Setup {
+    pub contract: Contract,
+    pub alice: Account,
+}
+
+/// The contract account is the owner and the minter, and starts with a
+/// balance of 1000 in V1.
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+
+    let contract = worker.dev_deploy(&WASM.to_vec()).await.unwrap();
+    contract.call("new").transact().await.unwrap().unwrap();
+    contract
+        .call("mint")
+        .args_json(json!({ "amount": "1000" }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let alice = worker.dev_create_account().await.unwrap();
+
+    Setup { contract, alice }
+}
+
+#[tokio::test]
+async fn components_survive_migration() {
+    let Setup { contract, alice } = setup().await;
+
+    assert_eq!(view::<u32>(&contract, "version", json!({})).await, 1);
+    transfer(&contract, &alice, 100).await;
+    assert_eq!(view::<u64>(&contract, "transfers", json!({})).await, 0);
+
+    contract
+        .call("migrate_to_v2")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(view::<u32>(&contract, "version", json!({})).await, 2);
+
+    // Balances, ownership, and roles are unchanged
+    assert_eq!(balance(&contract, contract.id()).await, 900);
+    assert_eq!(balance(&contract, alice.id()).await, 100);
+    assert_eq!(
+        view::<Option<AccountId>>(&contract, "own_get_owner", json!({})).await,
+        Some(contract.id().clone()),
+    );
+    contract
+        .call("mint")
+        .args_json(json!({ "amount": "10" }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(balance(&contract, contract.id()).await, 910);
+
+    // The hooks see the current version
+    transfer(&contract, &alice, 10).await;
+    assert_eq!(view::<u64>(&contract, "transfers", json!({})).await, 1);
+    assert_eq!(balance(&contract, alice.id()).await, 110);
+}
+
+#[tokio::test]
+#[should_panic(expected = "Disallowed while contract is paused")]
+async fn pause_survives_migration() {
+    let Setup { contract, alice } = setup().await;
+
+    contract.call("pause").transact().await.unwrap().unwrap();
+    contract
+        .call("migrate_to_v2")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(view::<bool>(&contract, "paus_is_paused", json!({})).await);
+
+    transfer(&contract, &alice, 10).await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "Owner only")]
+async fn migrate_owner_only() {
+    let Setup { contract, alice } = setup().await;
+
+    alice
+        .call(contract.id(), "migrate_to_v2")
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,199 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json};
+use near_sdk_contract_tools::vesting::VestingSchedule;
+use near_sdk_contract_tools_testing::{call_json, make_accounts, view};
+use workspaces::{network::Sandbox, Account, AccountId, Contract, Worker};
+
+const WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/vesting.wasm");
+
+const SECOND: u64 = 1_000_000_000;
+const CLIFF: u64 = 100 * SECOND;
+const DURATION: u64 = 1_000 * SECOND;
+const TOTAL: u128 = 1_000_000;
+
+struct Setup {
+    pub worker: Worker<Sandbox>,
+    pub contract: Contract,
+    /// Owner of the contract
+    pub owner: Account,
+    pub treasury: Account,
+    pub alice: Account,
+}
+
+/// Setup for individual tests
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+    let mut accounts = make_accounts(&worker, 3).await;
+    let alice = accounts.pop().unwrap();
+    let treasury = accounts.pop().unwrap();
+    let owner = accounts.pop().unwrap();
+
+    let contract = worker.dev_deploy(WASM).await.unwrap();
+    owner
+        .call(contract.id(), "new")
+        .args_json(json!({ "treasury_id": treasury.id() }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    Setup {
+        worker,
+        contract,
+        owner,
+        treasury,
+        alice,
+    }
+}
+
+async fn now(worker: &Worker<Sandbox>) -> u64 {
+    worker.view_block().await.unwrap().timestamp()
+}
+
+/// Produces blocks until the block timestamp reaches `timestamp`
+async fn fast_forward_to(worker: &Worker<Sandbox>, timestamp: u64) {
+    while now(worker).await < timestamp {
+        worker.fast_forward(100).await.unwrap();
+    }
+}
+
+async fn create_schedule(s: &Setup, beneficiary_id: &AccountId, start: u64) {
+    s.owner
+        .call(s.contract.id(), "create_schedule")
+        .args_json(json!({
+            "beneficiary_id": beneficiary_id,
+            "start": start.to_string(),
+            "cliff": CLIFF.to_string(),
+            "duration": DURATION.to_string(),
+            "total": TOTAL.to_string(),
+        }))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+async fn claim(s: &Setup, account: &Account) -> Result<u128, String> {
+    account
+        .call(s.contract.id(), "claim")
+        .transact()
+        .await
+        .unwrap()
+        .into_result()
+        .map(|result| result.json::<U128>().unwrap().0)
+        .map_err(|e| format!("{e:?}"))
+}
+
+async fn balance_of(s: &Setup, account_id: &AccountId) -> u128 {
+    view::<U128>(
+        &s.contract,
+        "ft_balance_of",
+        json!({ "account_id": account_id }),
+    )
+    .await
+    .0
+}
+
+#[tokio::test]
+async fn claim_across_timeline() {
+    let s = setup().await;
+    let start = now(&s.worker).await;
+    create_schedule(&s, s.alice.id(), start).await;
+
+    // Before the cliff
+    assert!(claim(&s, &s.alice)
+        .await
+        .unwrap_err()
+        .contains("Nothing to claim"));
+
+    // After the cliff, part of the tokens vested
+    fast_forward_to(&s.worker, start + CLIFF).await;
+    let first = claim(&s, &s.alice).await.unwrap();
+    assert!(first >= TOTAL * CLIFF as u128 / DURATION as u128);
+    assert!(first < TOTAL);
+    assert_eq!(balance_of(&s, s.alice.id()).await, first);
+
+    // After the end, the rest
+    fast_forward_to(&s.worker, start + DURATION).await;
+    let second = claim(&s, &s.alice).await.unwrap();
+    assert_eq!(first + second, TOTAL);
+    assert_eq!(balance_of(&s, s.alice.id()).await, TOTAL);
+
+    let schedule: VestingSchedule = view(
+        &s.contract,
+        "get_schedule",
+        json!({ "beneficiary_id": s.alice.id() }),
+    )
+    .await;
+    assert_eq!(schedule.claimed, U128(TOTAL));
+
+    assert!(claim(&s, &s.alice)
+        .await
+        .unwrap_err()
+        .contains("Nothing to claim"));
+}
+
+#[tokio::test]
+async fn revoke_mid_schedule() {
+    let s = setup().await;
+    let start = now(&s.worker).await;
+    create_schedule(&s, s.alice.id(), start).await;
+
+    fast_forward_to(&s.worker, start + CLIFF).await;
+    let first = claim(&s, &s.alice).await.unwrap();
+
+    // Only the owner may revoke
+    let result = s
+        .alice
+        .call(s.contract.id(), "revoke")
+        .args_json(json!({ "beneficiary_id": s.alice.id() }))
+        .transact()
+        .await
+        .unwrap();
+    assert!(format!("{:?}", result.into_result().unwrap_err()).contains("Owner only"));
+
+    let unvested = call_json::<U128>(
+        &s.owner,
+        s.contract.id(),
+        "revoke",
+        json!({ "beneficiary_id": s.alice.id() }),
+    )
+    .await
+    .0;
+    assert!(unvested > 0);
+    assert_eq!(balance_of(&s, s.treasury.id()).await, unvested);
+
+    // What vested before the revocation remains claimable, and no more
+    fast_forward_to(&s.worker, start + DURATION).await;
+    let vested = TOTAL - unvested;
+    let claimable = view::<U128>(
+        &s.contract,
+        "get_claimable",
+        json!({ "beneficiary_id": s.alice.id() }),
+    )
+    .await
+    .0;
+    assert_eq!(claimable, vested - first);
+
+    if claimable > 0 {
+        assert_eq!(claim(&s, &s.alice).await.unwrap(), claimable);
+    }
+    assert_eq!(balance_of(&s, s.alice.id()).await, vested);
+    assert_eq!(
+        view::<U128>(&s.contract, "ft_total_supply", json!({}))
+            .await
+            .0,
+        TOTAL,
+    );
+
+    let result = s
+        .owner
+        .call(s.contract.id(), "revoke")
+        .args_json(json!({ "beneficiary_id": s.alice.id() }))
+        .transact()
+        .await
+        .unwrap();
+    assert!(format!("{:?}", result.into_result().unwrap_err())
+        .contains("Vesting schedule already revoked"));
+}
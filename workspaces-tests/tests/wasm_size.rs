@@ -0,0 +1,102 @@
+#![cfg(not(windows))]
+
+//! Guards against regressions in the size of the compiled contracts, e.g. a
+//! derive macro pulling in code that a contract does not use. The recorded
+//! sizes are in `wasm-sizes.toml`; updating them is deliberate:
+//! `UPDATE_WASM_SIZES=1 cargo make test wasm_size`.
+
+use std::{collections::BTreeMap, env, fs};
+
+const SIZES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/wasm-sizes.toml");
+
+const UPDATE_ENV_VAR: &str = "UPDATE_WASM_SIZES";
+
+const TOLERANCE_PERCENT: usize = 2;
+
+macro_rules! wasm {
+    ($name:literal) => {
+        wasm!($name, "")
+    };
+    ($name:literal, $target_dir:literal) => {
+        include_bytes!(concat!(
+            "../../target/",
+            $target_dir,
+            "wasm32-unknown-unknown/release/",
+            $name,
+            ".wasm",
+        ))
+        .as_slice()
+    };
+}
+
+fn read_sizes() -> BTreeMap<String, usize> {
+    fs::read_to_string(SIZES)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split('#').next()?.split_once('='))
+        .map(|(name, size)| {
+            let size = size
+                .trim()
+                .parse()
+                .unwrap_or_else(|e| panic!("Malformed size of {name} in {SIZES}: {e}"));
+            (name.trim().to_string(), size)
+        })
+        .collect()
+}
+
+#[test]
+fn sizes() {
+    let measured = BTreeMap::from([
+        ("fungible_token", wasm!("fungible_token").len()),
+        (
+            "fungible_token_min_size",
+            wasm!("fungible_token", "min-size/").len(),
+        ),
+        ("rbac", wasm!("rbac").len()),
+        ("simple_multisig", wasm!("simple_multisig").len()),
+        ("counter_multisig", wasm!("counter_multisig").len()),
+        ("upgrade_old_staged", wasm!("upgrade_old_staged").len()),
+    ]);
+
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        let mut contents = format!(
+            "# Size in bytes of each compiled contract. Regenerate with {UPDATE_ENV_VAR}=1 and review the diff.\n",
+        );
+        for (name, size) in &measured {
+            contents.push_str(&format!("{name} = {size}\n"));
+        }
+        fs::write(SIZES, contents)
+            .unwrap_or_else(|e| panic!("Failed to write wasm sizes to {SIZES}: {e}"));
+        return;
+    }
+
+    let recorded = read_sizes();
+
+    for (name, size) in &measured {
+        let ceiling = recorded
+            .get(*name)
+            .map(|recorded| recorded * (100 + TOLERANCE_PERCENT) / 100)
+            .unwrap_or_else(|| {
+                panic!("No recorded size for {name}.wasm, run with {UPDATE_ENV_VAR}=1 to record it")
+            });
+
+        assert!(
+            *size <= ceiling,
+            "{name}.wasm is {size} bytes, exceeding the recorded size by more than {TOLERANCE_PERCENT}% ({ceiling} bytes). If this is intended, run with {UPDATE_ENV_VAR}=1 to update {SIZES}",
+        );
+    }
+}
+
+/// The `min-size` feature replaces the failure messages with their codes
+#[test]
+fn min_size_shrinks() {
+    let wasm = wasm!("fungible_token");
+    let min_size_wasm = wasm!("fungible_token", "min-size/");
+
+    assert!(
+        min_size_wasm.len() < wasm.len(),
+        "fungible_token.wasm is {} bytes with `min-size` and {} bytes without",
+        min_size_wasm.len(),
+        wasm.len(),
+    );
+}
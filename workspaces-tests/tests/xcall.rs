@@ -0,0 +1,98 @@
+#![cfg(not(windows))]
+
+use near_sdk::{json_types::U128, serde_json::json};
+use near_sdk_contract_tools_testing::{call_json, deploy_and_init, make_accounts, view};
+use workspaces::{Account, Contract};
+
+const XCALL_WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/xcall.wasm");
+
+const RECEIVER_WASM: &[u8] =
+    include_bytes!("../../target/wasm32-unknown-unknown/release/mock_ft_receiver.wasm");
+
+struct Setup {
+    pub xcall: Contract,
+    pub receiver: Contract,
+    pub alice: Account,
+}
+
+async fn setup() -> Setup {
+    let worker = workspaces::sandbox().await.unwrap();
+    let alice = make_accounts(&worker, 1).await.pop().unwrap();
+
+    let xcall = deploy_and_init(&worker, XCALL_WASM, "new", json!({})).await;
+    let receiver = worker.dev_deploy(RECEIVER_WASM).await.unwrap();
+
+    Setup {
+        xcall,
+        receiver,
+        alice,
+    }
+}
+
+impl Setup {
+    /// Whether the call succeeded
+    async fn reserve(&self, amount: u128, msg: &str) -> bool {
+        call_json(
+            &self.alice,
+            self.xcall.id(),
+            "reserve",
+            json!({
+                "receiver_id": self.receiver.id(),
+                "amount": U128(amount),
+                "msg": msg,
+            }),
+        )
+        .await
+    }
+
+    async fn reserved(&self) -> u128 {
+        view::<U128>(&self.xcall, "get_reserved", json!({})).await.0
+    }
+
+    async fn pending_call(&self, call_id: u32) -> Option<U128> {
+        view(
+            &self.xcall,
+            "get_pending_call",
+            json!({ "call_id": call_id }),
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+async fn success_commits() {
+    let s = setup().await;
+
+    assert!(s.reserve(100, "all").await);
+    assert_eq!(s.reserved().await, 100);
+    assert_eq!(s.pending_call(0).await, None);
+}
+
+#[tokio::test]
+async fn failure_rolls_back() {
+    let s = setup().await;
+
+    assert!(s.reserve(100, "all").await);
+    assert!(!s.reserve(40, "panic").await);
+    assert_eq!(s.reserved().await, 100);
+    assert_eq!(s.pending_call(1).await, None);
+
+    assert!(s.reserve(5, "use:0").await);
+    assert_eq!(s.reserved().await, 105);
+}
+
+#[tokio::test]
+async fn resolve_external() {
+    let s = setup().await;
+
+    let result = s
+        .alice
+        .call(s.xcall.id(), "xcall_resolve")
+        .args_json(json!({ "call_id": 0 }))
+        .max_gas()
+        .transact()
+        .await
+        .unwrap();
+
+    assert!(result.is_failure());
+}